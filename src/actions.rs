@@ -0,0 +1,216 @@
+use crate::models::InstanceInfo;
+use std::io::{self, BufRead, BufReader};
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex};
+
+/// Something the action menu (`a`) can do with an instance. New actions go
+/// here: the menu lists every variant the instance supports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InstanceAction {
+    Details,
+    ProbeHealth,
+    CopyAddress,
+    CopyHttpAddress,
+    OpenWebUi,
+    TogglePin,
+    ToggleMark,
+    /// Restart every instance of the instance's replicaset in turn
+    RollingRestart,
+}
+
+impl InstanceAction {
+    pub const ALL: [InstanceAction; 8] = [
+        InstanceAction::Details,
+        InstanceAction::ProbeHealth,
+        InstanceAction::CopyAddress,
+        InstanceAction::CopyHttpAddress,
+        InstanceAction::OpenWebUi,
+        InstanceAction::TogglePin,
+        InstanceAction::ToggleMark,
+        InstanceAction::RollingRestart,
+    ];
+
+    /// Key that runs the action directly while the menu is open
+    pub fn key(self) -> char {
+        match self {
+            InstanceAction::Details => 'd',
+            InstanceAction::ProbeHealth => 'h',
+            InstanceAction::CopyAddress => 'y',
+            InstanceAction::CopyHttpAddress => 'Y',
+            InstanceAction::OpenWebUi => 'o',
+            InstanceAction::TogglePin => '*',
+            InstanceAction::ToggleMark => 'm',
+            InstanceAction::RollingRestart => 'R',
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            InstanceAction::Details => "Details and metrics",
+            InstanceAction::ProbeHealth => "Probe health",
+            InstanceAction::CopyAddress => "Copy address",
+            InstanceAction::CopyHttpAddress => "Copy HTTP address",
+            InstanceAction::OpenWebUi => "Open web UI",
+            InstanceAction::TogglePin => "Pin / unpin",
+            InstanceAction::ToggleMark => "Mark / unmark",
+            InstanceAction::RollingRestart => "Rolling restart of the replicaset",
+        }
+    }
+
+    /// Whether the action makes sense for `instance`
+    pub fn available(self, instance: &InstanceInfo) -> bool {
+        match self {
+            InstanceAction::CopyAddress => !instance.binary_address.is_empty(),
+            InstanceAction::ProbeHealth
+            | InstanceAction::CopyHttpAddress
+            | InstanceAction::OpenWebUi => !instance.http_address.is_empty(),
+            InstanceAction::Details
+            | InstanceAction::TogglePin
+            | InstanceAction::ToggleMark
+            | InstanceAction::RollingRestart => true,
+        }
+    }
+
+    /// Whether the action changes the cluster. Only `--ops` sessions offer
+    /// these; the match lists every variant so new actions have to decide.
+    pub fn mutating(self) -> bool {
+        match self {
+            InstanceAction::Details
+            | InstanceAction::ProbeHealth
+            | InstanceAction::CopyAddress
+            | InstanceAction::CopyHttpAddress
+            | InstanceAction::OpenWebUi
+            | InstanceAction::TogglePin
+            | InstanceAction::ToggleMark => false,
+            InstanceAction::RollingRestart => true,
+        }
+    }
+
+    /// Actions available for `instance`, in menu order, leaving out the
+    /// mutating ones unless `allow_mutating`
+    pub fn for_instance(instance: &InstanceInfo, allow_mutating: bool) -> Vec<InstanceAction> {
+        Self::ALL
+            .into_iter()
+            .filter(|action| action.available(instance) && (allow_mutating || !action.mutating()))
+            .collect()
+    }
+}
+
+/// Web UI served on an instance's HTTP address
+pub fn web_ui_url(http_address: &str) -> String {
+    format!("http://{}/", http_address)
+}
+
+/// Open `url` in the desktop's default browser without waiting for it. The
+/// launcher's output is discarded so it can't draw over the TUI.
+pub fn open_in_browser(url: &str) -> io::Result<()> {
+    let mut command = if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else if cfg!(windows) {
+        let mut command = Command::new("cmd");
+        // The empty argument is the window title `start` expects first
+        command.args(["/C", "start", ""]);
+        command
+    } else {
+        Command::new("xdg-open")
+    };
+    let mut child = command
+        .arg(url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    // Reap the launcher once it exits
+    std::thread::spawn(move || child.wait());
+    Ok(())
+}
+
+/// Quote `value` as a single word for the shell `spawn_shell` runs
+pub fn shell_quote(value: &str) -> String {
+    if cfg!(windows) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        format!("'{}'", value.replace('\'', r"'\''"))
+    }
+}
+
+/// Run `command` with `sh` (`cmd` on Windows) without a terminal: its
+/// output would draw over the TUI. Errors can be read back from stderr.
+pub fn spawn_shell(command: &str) -> io::Result<Child> {
+    shell(command)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+}
+
+#[cfg(windows)]
+fn shell(command: &str) -> Command {
+    use std::os::windows::process::CommandExt;
+    let mut shell = Command::new("cmd");
+    // cmd parses its command line itself; Rust's quoting would garble it
+    shell.arg("/C").raw_arg(command);
+    shell
+}
+
+#[cfg(not(windows))]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("sh");
+    shell.args(["-c", command]);
+    shell
+}
+
+/// Last line a `spawn_shell` command wrote to stderr, read as it comes so
+/// a chatty command can't fill the pipe and block
+#[derive(Debug, Clone, Default)]
+pub struct StderrTail(Arc<Mutex<TailState>>);
+
+#[derive(Debug, Default)]
+struct TailState {
+    last_line: Option<String>,
+    closed: bool,
+}
+
+impl StderrTail {
+    /// Take over `child`'s stderr and drain it on a thread of its own
+    pub fn watch(child: &mut Child) -> Self {
+        let tail = Self::default();
+        let Some(stderr) = child.stderr.take() else {
+            tail.0.lock().unwrap().closed = true;
+            return tail;
+        };
+        let shared = Arc::clone(&tail.0);
+        std::thread::spawn(move || {
+            for line in BufReader::new(stderr).split(b'\n').map_while(Result::ok) {
+                let line = String::from_utf8_lossy(&line);
+                if !line.trim().is_empty() {
+                    shared.lock().unwrap().last_line = Some(line.trim().to_string());
+                }
+            }
+            shared.lock().unwrap().closed = true;
+        });
+        tail
+    }
+
+    /// The last non-empty line so far, usually the one saying what went
+    /// wrong
+    pub fn last_line(&self) -> Option<String> {
+        self.0.lock().unwrap().last_line.clone()
+    }
+
+    /// Whether stderr was read to the end
+    pub fn is_closed(&self) -> bool {
+        self.0.lock().unwrap().closed
+    }
+}
+
+/// Let a command from `spawn_shell` run on, draining its stderr so it
+/// can't block and reaping it once it exits
+pub fn reap_in_background(mut child: Child) {
+    std::thread::spawn(move || {
+        if let Some(mut stderr) = child.stderr.take() {
+            let _ = io::copy(&mut stderr, &mut io::sink());
+        }
+        child.wait()
+    });
+}
@@ -0,0 +1,304 @@
+//! Alert rules from the config, checked against every refresh. A rule
+//! fires once its condition has held for enough refreshes in a row, and
+//! clears as soon as it doesn't.
+
+use crate::actions::{reap_in_background, shell_quote, spawn_shell};
+use crate::health;
+use crate::models::{StateVariant, TierInfo};
+use serde::Deserialize;
+use std::collections::BTreeSet;
+
+/// What a rule measures on the cluster
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Metric {
+    /// Offline instances
+    Offline,
+    /// Instances whose current state differs from the target state
+    StateMismatch,
+    /// Highest capacity usage of a replicaset, in percent
+    Capacity,
+    /// Distinct Picodata versions running
+    Versions,
+    /// Health score, 0-100 (see `health`)
+    Health,
+}
+
+impl Metric {
+    /// Current value on `tiers`. Expelled instances don't count.
+    pub fn measure(self, tiers: &[TierInfo]) -> f64 {
+        let instances = || {
+            tiers
+                .iter()
+                .flat_map(|t| t.replicasets.iter())
+                .flat_map(|rs| rs.instances.iter())
+                .filter(|inst| inst.current_state != StateVariant::Expelled)
+        };
+        match self {
+            Metric::Offline => instances()
+                .filter(|inst| inst.current_state == StateVariant::Offline)
+                .count() as f64,
+            Metric::StateMismatch => instances()
+                .filter(|inst| inst.current_state != inst.target_state)
+                .count() as f64,
+            Metric::Capacity => tiers
+                .iter()
+                .flat_map(|t| t.replicasets.iter())
+                .map(|rs| rs.capacity_usage)
+                .fold(0.0, f64::max),
+            Metric::Versions => instances()
+                .map(|inst| inst.version.as_str())
+                .filter(|v| !v.is_empty())
+                .collect::<BTreeSet<_>>()
+                .len() as f64,
+            Metric::Health => f64::from(health::assess(tiers).score),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub enum Comparison {
+    #[default]
+    #[serde(rename = ">")]
+    Above,
+    #[serde(rename = ">=")]
+    AtLeast,
+    #[serde(rename = "<")]
+    Below,
+    #[serde(rename = "<=")]
+    AtMost,
+    #[serde(rename = "==")]
+    Equal,
+}
+
+impl Comparison {
+    pub fn holds(self, value: f64, threshold: f64) -> bool {
+        match self {
+            Comparison::Above => value > threshold,
+            Comparison::AtLeast => value >= threshold,
+            Comparison::Below => value < threshold,
+            Comparison::AtMost => value <= threshold,
+            Comparison::Equal => value == threshold,
+        }
+    }
+
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Comparison::Above => ">",
+            Comparison::AtLeast => ">=",
+            Comparison::Below => "<",
+            Comparison::AtMost => "<=",
+            Comparison::Equal => "==",
+        }
+    }
+}
+
+/// An alert rule from the config file
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct AlertRule {
+    pub name: String,
+    pub metric: Metric,
+    #[serde(default)]
+    pub op: Comparison,
+    pub threshold: f64,
+    /// Refreshes in a row the condition has to hold before the alert fires
+    #[serde(default = "one", rename = "for")]
+    pub refreshes: u32,
+    /// Shell command run when the alert fires, with `{name}`, `{value}`,
+    /// `{threshold}` and `{cluster}` placeholders
+    #[serde(default)]
+    pub command: Option<String>,
+}
+
+fn one() -> u32 {
+    1
+}
+
+impl AlertRule {
+    /// The condition as written, e.g. `offline > 0`
+    pub fn condition(&self) -> String {
+        format!(
+            "{} {} {}",
+            metric_name(self.metric),
+            self.op.symbol(),
+            self.threshold
+        )
+    }
+}
+
+fn metric_name(metric: Metric) -> &'static str {
+    match metric {
+        Metric::Offline => "offline",
+        Metric::StateMismatch => "state_mismatch",
+        Metric::Capacity => "capacity",
+        Metric::Versions => "versions",
+        Metric::Health => "health",
+    }
+}
+
+/// A rule that is firing
+#[derive(Debug, Clone, PartialEq)]
+pub struct Alert {
+    pub rule: String,
+    pub condition: String,
+    /// Latest measured value
+    pub value: f64,
+    /// Unix time the alert fired
+    pub since: u64,
+}
+
+/// The configured rules and how long each has been holding
+#[derive(Debug, Clone, Default)]
+pub struct AlertEngine {
+    rules: Vec<AlertRule>,
+    /// Refreshes in a row each rule's condition has held
+    streaks: Vec<u32>,
+    active: Vec<Option<Alert>>,
+}
+
+impl AlertEngine {
+    pub fn new(rules: Vec<AlertRule>) -> Self {
+        Self {
+            streaks: vec![0; rules.len()],
+            active: vec![None; rules.len()],
+            rules,
+        }
+    }
+
+    pub fn rules(&self) -> &[AlertRule] {
+        &self.rules
+    }
+
+    /// Firing alerts in rule order
+    pub fn active(&self) -> impl Iterator<Item = &Alert> {
+        self.active.iter().flatten()
+    }
+
+    /// Check every rule against a refresh. Returns the rules that started
+    /// firing with it, with their values.
+    pub fn evaluate(&mut self, tiers: &[TierInfo], now: u64) -> Vec<(&AlertRule, f64)> {
+        let mut fired = Vec::new();
+        for (index, rule) in self.rules.iter().enumerate() {
+            let value = rule.metric.measure(tiers);
+            if !rule.op.holds(value, rule.threshold) {
+                self.streaks[index] = 0;
+                self.active[index] = None;
+                continue;
+            }
+            self.streaks[index] = self.streaks[index].saturating_add(1);
+            match &mut self.active[index] {
+                Some(alert) => alert.value = value,
+                None if self.streaks[index] >= rule.refreshes.max(1) => {
+                    self.active[index] = Some(Alert {
+                        rule: rule.name.clone(),
+                        condition: rule.condition(),
+                        value,
+                        since: now,
+                    });
+                    fired.push((rule, value));
+                }
+                None => {}
+            }
+        }
+        fired
+    }
+}
+
+/// Run the rule's command for an alert that just fired, without waiting
+/// for it. It goes through the platform's shell, like the rolling restart
+/// steps (see `spawn_shell`).
+pub fn run_hook(rule: &AlertRule, value: f64, cluster: &str) -> std::io::Result<()> {
+    let Some(ref template) = rule.command else {
+        return Ok(());
+    };
+    let command = [
+        ("{name}", rule.name.as_str()),
+        ("{value}", &value.to_string()),
+        ("{threshold}", &rule.threshold.to_string()),
+        ("{cluster}", cluster),
+    ]
+    .iter()
+    .fold(template.clone(), |command, (placeholder, value)| {
+        command.replace(placeholder, &shell_quote(value))
+    });
+    reap_in_background(spawn_shell(&command)?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock;
+    use std::time::{Duration, Instant};
+
+    fn alert_rule(json: serde_json::Value) -> AlertRule {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn test_alerts_fire_after_consecutive_refreshes() {
+        let mut tiers: Vec<TierInfo> = serde_json::from_value(mock::tiers()).unwrap();
+        let mut engine = AlertEngine::new(vec![
+            alert_rule(
+                serde_json::json!({"name": "Down", "metric": "offline", "threshold": 0, "for": 2}),
+            ),
+            alert_rule(serde_json::json!({"name": "Skew", "metric": "versions", "threshold": 1})),
+        ]);
+
+        assert!(
+            engine.evaluate(&tiers, 100).is_empty(),
+            "one refresh isn't enough"
+        );
+        let fired: Vec<&str> = engine
+            .evaluate(&tiers, 110)
+            .iter()
+            .map(|(rule, _)| rule.name.as_str())
+            .collect();
+        assert_eq!(fired, ["Down"]);
+        assert!(engine.evaluate(&tiers, 120).is_empty(), "fires once");
+        let alert = engine.active().next().unwrap();
+        assert_eq!(alert.since, 110);
+        assert_eq!(alert.condition, "offline > 0");
+
+        // i3 comes back, i4 moves to a newer version
+        tiers[0].replicasets[1].instances[0].current_state = StateVariant::Online;
+        tiers[0].replicasets[1].instances[1].version = "25.7.0".to_string();
+        let fired = engine.evaluate(&tiers, 130);
+        assert_eq!(fired.len(), 1);
+        assert_eq!(fired[0].0.name, "Skew");
+        assert_eq!(fired[0].1, 2.0);
+        let active: Vec<&str> = engine.active().map(|a| a.rule.as_str()).collect();
+        assert_eq!(active, ["Skew"], "Down cleared");
+    }
+
+    #[test]
+    fn test_alert_metrics() {
+        let tiers: Vec<TierInfo> = serde_json::from_value(mock::tiers()).unwrap();
+        assert_eq!(Metric::Offline.measure(&tiers), 1.0);
+        assert_eq!(Metric::StateMismatch.measure(&tiers), 1.0);
+        assert_eq!(Metric::Versions.measure(&tiers), 1.0);
+        assert!(Metric::Health.measure(&tiers) < 100.0);
+    }
+
+    // The command is written for sh
+    #[test]
+    #[cfg(unix)]
+    fn test_alert_runs_its_command() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("alert.txt");
+        let rule = alert_rule(serde_json::json!({
+            "name": "Down",
+            "metric": "offline",
+            "threshold": 0,
+            "command": format!("echo {{name}} {{value}} {{cluster}} > '{}'", out.display()),
+        }));
+
+        run_hook(&rule, 1.0, "prod").unwrap();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !out.exists() && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        std::thread::sleep(Duration::from_millis(50));
+        assert_eq!(std::fs::read_to_string(&out).unwrap(), "Down 1 prod\n");
+    }
+}
@@ -0,0 +1,39 @@
+//! Plain-text feed of the selected row for screen readers (`--announce`).
+//! Each selection change is written as one line to a file or named pipe,
+//! which a screen reader or a `tail -f` in another terminal can speak.
+
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::mpsc::{channel, Sender};
+use std::thread;
+
+/// Start the writer thread for `path` and return the sender lines go to.
+///
+/// Opening a named pipe blocks until something reads it, so writing
+/// happens off the UI thread. When the reader goes away the pipe is
+/// reopened for the next line; lines sent in between are dropped.
+pub fn spawn(path: PathBuf) -> Sender<String> {
+    let (tx, rx) = channel::<String>();
+    thread::spawn(move || {
+        let mut out = None;
+        for line in rx {
+            if out.is_none() {
+                out = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&path)
+                    .ok();
+            }
+            if let Some(file) = &mut out {
+                if writeln!(file, "{}", line)
+                    .and_then(|_| file.flush())
+                    .is_err()
+                {
+                    out = None;
+                }
+            }
+        }
+    });
+    tx
+}
@@ -1,8 +1,27 @@
+use crate::chaos::{self, Chaos};
+use crate::client::{Client, Fetched, Timed};
+pub use crate::client::{ResponseMeta, ServerIdentity, INVALID_CREDENTIALS};
 use crate::models::*;
+use crate::paths;
+use crate::prepare::PreparedTiers;
+use crate::ratelimit::{self, RateLimiter};
+use crate::stream::{self, StreamEvent};
 use crate::tokens;
+use std::any::Any;
+use std::fmt;
+use std::ops::ControlFlow;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::mpsc::{Receiver, Sender};
+use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+
+/// Name of API worker threads, whose panics are caught and reported
+pub const WORKER_THREAD: &str = "api-worker";
+
+/// Most requests a fan-out to many instances has in flight at once
+pub const FAN_OUT_LIMIT: usize = 8;
 
 /// Requests that can be sent to the API worker
 #[derive(Debug)]
@@ -16,251 +35,694 @@ pub enum ApiRequest {
     SetToken {
         auth: String,
         refresh: String,
+        /// Save the session to disk too, as after a login with Remember me
+        remember: bool,
     },
+    /// Check the server is reachable before logging in, and identify the
+    /// cluster if it answers without a session
+    CheckServer,
     GetClusterInfo,
     GetTiers,
     GetHealthStatus {
         http_address: String,
     },
+    /// Health of every instance at `addresses` at once, answered by one
+    /// `HealthProbes` tagged with `id`. Replaces a fan-out in progress.
+    ProbeHealth {
+        id: u64,
+        addresses: Vec<String>,
+    },
+    /// Stop the fan-out in progress: requests not started yet are skipped
+    CancelFanOut,
+    /// Open a server-sent events stream at `path` (relative to the base URL)
+    StartStream {
+        path: String,
+    },
+    /// Other addresses of the cluster to switch to when the current one
+    /// stops answering; with `discover`, also every instance's address
+    /// from the tiers
+    SetFallbacks {
+        urls: Vec<String>,
+        discover: bool,
+    },
+    /// Tables of the cluster schema with their approximate sizes
+    GetTables,
+    /// Cluster configuration parameters
+    GetParameters,
     Shutdown,
 }
 
+impl ApiRequest {
+    /// Variant name, for logs; never includes credentials
+    pub fn name(&self) -> &'static str {
+        match self {
+            ApiRequest::GetConfig => "GetConfig",
+            ApiRequest::Login { .. } => "Login",
+            ApiRequest::SetToken { .. } => "SetToken",
+            ApiRequest::CheckServer => "CheckServer",
+            ApiRequest::GetClusterInfo => "GetClusterInfo",
+            ApiRequest::GetTiers => "GetTiers",
+            ApiRequest::GetHealthStatus { .. } => "GetHealthStatus",
+            ApiRequest::ProbeHealth { .. } => "ProbeHealth",
+            ApiRequest::CancelFanOut => "CancelFanOut",
+            ApiRequest::StartStream { .. } => "StartStream",
+            ApiRequest::SetFallbacks { .. } => "SetFallbacks",
+            ApiRequest::GetTables => "GetTables",
+            ApiRequest::GetParameters => "GetParameters",
+            ApiRequest::Shutdown => "Shutdown",
+        }
+    }
+
+    /// How to answer the request with `error` instead of data, for
+    /// requests that expect an answer
+    pub fn failure(&self) -> Option<Box<dyn FnOnce(String) -> ApiResponse + Send>> {
+        Some(match self {
+            ApiRequest::GetConfig => Box::new(|e| ApiResponse::Config(Err(e))),
+            ApiRequest::Login { .. } => Box::new(|e| ApiResponse::Login(Err(e))),
+            ApiRequest::CheckServer => Box::new(|e| ApiResponse::ServerCheck(Err(e))),
+            ApiRequest::GetClusterInfo => Box::new(|e| ApiResponse::ClusterInfo(Err(e))),
+            ApiRequest::GetTiers => Box::new(|e| ApiResponse::Tiers(Err(e))),
+            ApiRequest::GetHealthStatus { .. } => Box::new(|e| ApiResponse::HealthStatus(Err(e))),
+            ApiRequest::ProbeHealth { id, addresses } => {
+                let (id, count) = (*id, addresses.len());
+                Box::new(move |e| {
+                    ApiResponse::HealthProbes(HealthProbes {
+                        id,
+                        results: vec![Err(e); count],
+                        cancelled: false,
+                    })
+                })
+            }
+            ApiRequest::GetTables => Box::new(|e| ApiResponse::Tables(Err(e))),
+            ApiRequest::GetParameters => Box::new(|e| ApiResponse::Parameters(Err(e))),
+            ApiRequest::SetToken { .. }
+            | ApiRequest::StartStream { .. }
+            | ApiRequest::SetFallbacks { .. }
+            | ApiRequest::CancelFanOut
+            | ApiRequest::Shutdown => return None,
+        })
+    }
+
+    /// Whether the worker answers the request with a response of its own.
+    /// A stream's events come unasked.
+    pub fn expects_answer(&self) -> bool {
+        !matches!(
+            self,
+            ApiRequest::SetToken { .. }
+                | ApiRequest::StartStream { .. }
+                | ApiRequest::SetFallbacks { .. }
+                | ApiRequest::CancelFanOut
+                | ApiRequest::Shutdown
+        )
+    }
+}
+
+/// Answer to `GetTables` from the HTTP API worker
+pub const TABLES_NEED_IPROTO: &str =
+    "The HTTP API doesn't list tables; start picotui with --iproto to browse them";
+
+/// Answer to `GetParameters` from the HTTP API worker
+pub const PARAMETERS_NEED_IPROTO: &str =
+    "The HTTP API doesn't list configuration parameters; start picotui with --iproto to browse them";
+
 /// Responses from the API worker
 #[derive(Debug)]
 pub enum ApiResponse {
     Config(Result<UiConfig, String>),
     Login(Result<TokenResponse, String>),
+    ServerCheck(Result<ServerIdentity, String>),
     ClusterInfo(Result<ClusterInfo, String>),
     Tiers(Result<Vec<TierInfo>, String>),
+    /// Tiers with derived data computed off the UI thread (see `prepare`)
+    PreparedTiers(Box<PreparedTiers>),
     HealthStatus(Result<Box<HealthStatus>, String>),
+    HealthProbes(HealthProbes),
+    Tables(Result<Vec<TableInfo>, String>),
+    Parameters(Result<Vec<ConfigParameter>, String>),
+    /// The server answered 304: data is identical to the last response
+    NotModified(Endpoint),
+    Stream(StreamEvent),
+    /// The worker panicked handling a request and was restarted. The
+    /// request itself is answered with this as its error.
+    WorkerPanic(WorkerPanic),
+}
+
+/// Answer to `ProbeHealth`
+#[derive(Debug)]
+pub struct HealthProbes {
+    pub id: u64,
+    /// In the order of the addresses asked about
+    pub results: Vec<Result<HealthStatus, String>>,
+    /// Stopped by `CancelFanOut` or a newer `ProbeHealth`; instances not
+    /// probed by then have an error
+    pub cancelled: bool,
+}
+
+/// A panic caught in an API worker
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorkerPanic {
+    /// `ApiRequest::name` of the request being handled
+    pub request: &'static str,
+    /// The panic message
+    pub message: String,
+}
+
+impl fmt::Display for WorkerPanic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "API worker crashed handling {} ({}) and was restarted",
+            self.request, self.message
+        )
+    }
+}
+
+/// Polled endpoints that support conditional requests
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endpoint {
+    ClusterInfo,
+    Tiers,
+}
+
+impl Endpoint {
+    pub fn label(self) -> &'static str {
+        match self {
+            Endpoint::ClusterInfo => "cluster",
+            Endpoint::Tiers => "tiers",
+        }
+    }
+}
+
+/// A response together with its timing metadata
+#[derive(Debug)]
+pub struct ApiMessage {
+    pub response: ApiResponse,
+    pub meta: ResponseMeta,
 }
 
 /// Spawns a background thread that handles all HTTP requests
 pub fn spawn_api_worker(
     base_url: String,
     request_rx: Receiver<ApiRequest>,
-    response_tx: Sender<ApiResponse>,
+    response_tx: Sender<ApiMessage>,
     debug: bool,
 ) {
-    thread::spawn(move || {
-        let config = ureq::Agent::config_builder()
-            .timeout_connect(Some(Duration::from_secs(5)))
-            .timeout_recv_response(Some(Duration::from_secs(10)))
-            .build();
-        let client = config.new_agent();
-
-        let mut auth_token: Option<String> = None;
-        let base_url = base_url.trim_end_matches('/').to_string();
-
-        for request in request_rx {
-            match request {
-                ApiRequest::Shutdown => break,
-
-                ApiRequest::GetConfig => {
-                    let url = format!("{}/api/v1/config", base_url);
-                    log_debug(debug, &format!("GET {}", url));
-
-                    let result = client.get(&url).call();
-                    let response = match result {
-                        Ok(resp) => match resp.into_body().read_json::<UiConfig>() {
-                            Ok(config) => {
-                                log_debug(debug, "  OK: config received");
-                                Ok(config)
-                            }
-                            Err(e) => {
-                                log_debug(debug, &format!("  PARSE ERROR: {}", e));
-                                Err(format!("Failed to parse config: {}", e))
-                            }
-                        },
-                        Err(e) => {
-                            log_debug(debug, &format!("  ERROR: {}", e));
-                            Err(format!("Failed to get config: {}", e))
+    spawn_api_worker_via(base_url.clone(), base_url, request_rx, response_tx, debug);
+}
+
+/// Like `spawn_api_worker`, but requests go to `connect_url` (the local end
+/// of a tunnel) while sessions are still saved under `base_url`
+pub fn spawn_api_worker_via(
+    base_url: String,
+    connect_url: String,
+    request_rx: Receiver<ApiRequest>,
+    response_tx: Sender<ApiMessage>,
+    debug: bool,
+) {
+    let worker = HttpWorker::start(&connect_url, debug, None, &[], false);
+    let responses = response_tx.clone();
+    let restart = move |old: &HttpWorker| HttpWorker {
+        // The fan-out and the stream in progress still stop when they
+        // should, and requests keep counting against the same limit
+        limiter: old.limiter.clone(),
+        fan_out: old.fan_out.clone(),
+        event_stream: old.event_stream.clone(),
+        ..HttpWorker::start(
+            &connect_url,
+            debug,
+            old.client.token(),
+            old.client.fallbacks(),
+            old.discover,
+        )
+    };
+    spawn_worker(move || {
+        supervise(
+            request_rx,
+            responses,
+            debug,
+            worker,
+            restart,
+            |worker, request| {
+                let HttpWorker {
+                    client,
+                    chaos,
+                    limiter,
+                    discover: discovering,
+                    fan_out,
+                    event_stream,
+                } = worker;
+                let send = |response, meta| {
+                    let _ = response_tx.send(ApiMessage { response, meta });
+                };
+
+                match request {
+                    ApiRequest::Shutdown => {
+                        event_stream.store(true, Ordering::Relaxed);
+                        return ControlFlow::Break(());
+                    }
+
+                    ApiRequest::SetFallbacks { urls, discover } => {
+                        client.set_fallbacks(&urls);
+                        if discover {
+                            client.discover_peers();
+                            *discovering = true;
                         }
-                    };
-                    let _ = response_tx.send(ApiResponse::Config(response));
-                }
+                    }
 
-                ApiRequest::Login {
-                    username,
-                    password,
-                    remember_me,
-                } => {
-                    let url = format!("{}/api/v1/session", base_url);
-                    log_debug(
-                        debug,
-                        &format!("POST {} (user={}, remember={})", url, username, remember_me),
-                    );
-
-                    let req_body = LoginRequest { username, password };
-                    let result = client
-                        .post(&url)
-                        .header("Content-Type", "application/json")
-                        .send_json(&req_body);
-
-                    let response = match result {
-                        Ok(resp) => match resp.into_body().read_json::<TokenResponse>() {
-                            Ok(token_resp) => {
-                                log_debug(debug, "  OK: tokens received");
-                                auth_token = Some(token_resp.auth.clone());
-
-                                // Save tokens to disk only if remember_me is enabled
-                                if remember_me {
-                                    if let Err(e) = tokens::save_tokens(
-                                        &base_url,
-                                        &token_resp.auth,
-                                        &token_resp.refresh,
-                                    ) {
-                                        log_debug(
-                                            debug,
-                                            &format!("  WARN: failed to save tokens: {}", e),
-                                        );
-                                    } else {
-                                        log_debug(debug, "  OK: tokens saved to disk");
-                                    }
+                    ApiRequest::GetConfig => {
+                        let call =
+                            with_failover(client, chaos, limiter, |client| client.get_config());
+                        send(ApiResponse::Config(call.result), call.meta);
+                    }
+
+                    ApiRequest::Login {
+                        username,
+                        password,
+                        remember_me,
+                    } => {
+                        // The stream was opened with the session being replaced
+                        event_stream.store(true, Ordering::Relaxed);
+                        let call = with_failover(client, chaos, limiter, |client| {
+                            client.login(&username, &password)
+                        });
+                        if let Ok(ref token_resp) = call.result {
+                            // Save tokens to disk only if remember_me is enabled
+                            if remember_me {
+                                if let Err(e) = tokens::save_tokens(
+                                    &base_url,
+                                    &token_resp.auth,
+                                    &token_resp.refresh,
+                                ) {
+                                    log_debug(
+                                        debug,
+                                        &format!("  WARN: failed to save tokens: {}", e),
+                                    );
                                 } else {
-                                    log_debug(debug, "  OK: tokens not saved (remember_me=false)");
+                                    log_debug(debug, "  OK: tokens saved to disk");
                                 }
-
-                                Ok(token_resp)
-                            }
-                            Err(e) => {
-                                log_debug(debug, &format!("  PARSE ERROR: {}", e));
-                                Err(format!("Failed to parse tokens: {}", e))
-                            }
-                        },
-                        Err(ureq::Error::StatusCode(status)) => {
-                            let msg = if status == 401 {
-                                "Invalid username or password. Try again.".to_string()
                             } else {
-                                format!("Login failed: HTTP {}", status)
-                            };
-                            log_debug(debug, &format!("  ERROR: HTTP {}", status));
-                            Err(msg)
-                        }
-                        Err(e) => {
-                            log_debug(debug, &format!("  ERROR: {}", e));
-                            Err(format!("Login failed: {}", e))
+                                log_debug(debug, "  OK: tokens not saved (remember_me=false)");
+                            }
                         }
-                    };
-                    let _ = response_tx.send(ApiResponse::Login(response));
-                }
-
-                ApiRequest::SetToken { auth, refresh } => {
-                    log_debug(debug, "Setting token from saved session");
-                    auth_token = Some(auth.clone());
+                        send(ApiResponse::Login(call.result), call.meta);
+                    }
 
-                    // Also update saved tokens with potentially refreshed values
-                    if let Err(e) = tokens::save_tokens(&base_url, &auth, &refresh) {
-                        log_debug(
-                            debug,
-                            &format!("  WARN: failed to update saved tokens: {}", e),
-                        );
+                    ApiRequest::CheckServer => {
+                        let call =
+                            with_failover(client, chaos, limiter, |client| client.check_server());
+                        send(ApiResponse::ServerCheck(call.result), call.meta);
                     }
-                }
 
-                ApiRequest::GetClusterInfo => {
-                    let url = format!("{}/api/v1/cluster", base_url);
-                    log_debug(debug, &format!("GET {}", url));
+                    ApiRequest::SetToken {
+                        auth,
+                        refresh,
+                        remember,
+                    } => {
+                        log_debug(debug, "Setting token from saved session");
+                        event_stream.store(true, Ordering::Relaxed);
+                        client.set_token(&auth);
 
-                    let mut req = client.get(&url);
-                    if let Some(ref token) = auth_token {
-                        req = req.header("Authorization", &format!("Bearer {}", token));
+                        // Also update saved tokens with potentially refreshed values
+                        if remember {
+                            if let Err(e) = tokens::save_tokens(&base_url, &auth, &refresh) {
+                                log_debug(
+                                    debug,
+                                    &format!("  WARN: failed to update saved tokens: {}", e),
+                                );
+                            }
+                        }
                     }
 
-                    let result = req.call();
-                    let response = match result {
-                        Ok(resp) => match resp.into_body().read_json::<ClusterInfo>() {
-                            Ok(info) => {
-                                log_debug(debug, "  OK: cluster info received");
-                                Ok(info)
-                            }
-                            Err(e) => {
-                                log_debug(debug, &format!("  PARSE ERROR: {}", e));
-                                Err(format!("Failed to parse cluster info: {}", e))
+                    ApiRequest::GetClusterInfo => {
+                        let call = with_failover(client, chaos, limiter, |client| {
+                            client.get_cluster_info()
+                        });
+                        let response = match call.result {
+                            Ok(Fetched::Data(info)) => ApiResponse::ClusterInfo(Ok(info)),
+                            Ok(Fetched::NotModified) => {
+                                ApiResponse::NotModified(Endpoint::ClusterInfo)
                             }
-                        },
-                        Err(e) => {
-                            log_debug(debug, &format!("  ERROR: {}", e));
-                            Err(format!("Failed to get cluster info: {}", e))
-                        }
-                    };
-                    let _ = response_tx.send(ApiResponse::ClusterInfo(response));
-                }
+                            Err(e) => ApiResponse::ClusterInfo(Err(e)),
+                        };
+                        send(response, call.meta);
+                    }
 
-                ApiRequest::GetTiers => {
-                    let url = format!("{}/api/v1/tiers", base_url);
-                    log_debug(debug, &format!("GET {}", url));
+                    ApiRequest::GetTiers => {
+                        let call =
+                            with_failover(client, chaos, limiter, |client| client.get_tiers());
+                        let response = match call.result {
+                            Ok(Fetched::Data(tiers)) => ApiResponse::Tiers(Ok(tiers)),
+                            Ok(Fetched::NotModified) => ApiResponse::NotModified(Endpoint::Tiers),
+                            Err(e) => ApiResponse::Tiers(Err(e)),
+                        };
+                        send(response, call.meta);
+                    }
 
-                    let mut req = client.get(&url);
-                    if let Some(ref token) = auth_token {
-                        req = req.header("Authorization", &format!("Bearer {}", token));
+                    ApiRequest::StartStream { path } => {
+                        // At most one stream at a time
+                        event_stream.store(true, Ordering::Relaxed);
+                        *event_stream = Arc::default();
+                        stream::spawn_event_stream(
+                            format!("{}{}", client.base_url(), path),
+                            client.token().map(str::to_string),
+                            event_stream.clone(),
+                            stream::STREAM_IDLE_TIMEOUT,
+                            response_tx.clone(),
+                            debug,
+                        );
                     }
 
-                    let result = req.call();
-                    let response = match result {
-                        Ok(resp) => match resp.into_body().read_json::<Vec<TierInfo>>() {
-                            Ok(tiers) => {
-                                log_debug(debug, &format!("  OK: {} tiers received", tiers.len()));
-                                Ok(tiers)
-                            }
-                            Err(e) => {
-                                log_debug(debug, &format!("  PARSE ERROR: {}", e));
-                                Err(format!("Failed to parse tiers: {}", e))
-                            }
+                    // The HTTP API doesn't describe the schema or the settings
+                    ApiRequest::GetTables => send(
+                        ApiResponse::Tables(Err(TABLES_NEED_IPROTO.to_string())),
+                        ResponseMeta {
+                            latency: Duration::ZERO,
+                            reachable: true,
+                            bytes: 0,
                         },
-                        Err(e) => {
-                            log_debug(debug, &format!("  ERROR: {}", e));
-                            Err(format!("Failed to get tiers: {}", e))
-                        }
-                    };
-                    let _ = response_tx.send(ApiResponse::Tiers(response));
-                }
+                    ),
+                    ApiRequest::GetParameters => send(
+                        ApiResponse::Parameters(Err(PARAMETERS_NEED_IPROTO.to_string())),
+                        ResponseMeta {
+                            latency: Duration::ZERO,
+                            reachable: true,
+                            bytes: 0,
+                        },
+                    ),
 
-                ApiRequest::GetHealthStatus { http_address } => {
-                    // Health status is fetched directly from the instance's HTTP address
-                    let url = format!("http://{}/api/v1/health/status", http_address);
-                    log_debug(debug, &format!("GET {}", url));
+                    ApiRequest::GetHealthStatus { http_address } => {
+                        limiter.acquire();
+                        let call = chaos.apply(|| client.get_health_status(&http_address));
+                        send(
+                            ApiResponse::HealthStatus(call.result.map(Box::new)),
+                            call.meta,
+                        );
+                    }
 
-                    let mut req = client.get(&url);
-                    if let Some(ref token) = auth_token {
-                        req = req.header("Authorization", &format!("Bearer {}", token));
+                    ApiRequest::ProbeHealth { id, addresses } => {
+                        // A newer fan-out replaces the one in progress
+                        fan_out.store(true, Ordering::Relaxed);
+                        *fan_out = Arc::default();
+                        let probe = HealthProbe {
+                            id,
+                            addresses,
+                            cancel: fan_out.clone(),
+                            token: client.token().map(str::to_string),
+                            limiter: limiter.clone(),
+                            debug,
+                        };
+                        // Answered from its own thread, so this one can
+                        // take a cancellation meanwhile
+                        let response_tx = response_tx.clone();
+                        spawn_worker(move || probe.run(&response_tx));
                     }
 
-                    let result = req.call();
-                    let response = match result {
-                        Ok(resp) => match resp.into_body().read_json::<HealthStatus>() {
-                            Ok(status) => {
-                                log_debug(
-                                    debug,
-                                    &format!("  OK: health status {:?}", status.status),
-                                );
-                                Ok(Box::new(status))
-                            }
-                            Err(e) => {
-                                log_debug(debug, &format!("  PARSE ERROR: {}", e));
-                                Err(format!("Failed to parse health status: {}", e))
-                            }
-                        },
-                        Err(e) => {
-                            log_debug(debug, &format!("  ERROR: {}", e));
-                            Err(format!("Failed to get health status: {}", e))
-                        }
-                    };
-                    let _ = response_tx.send(ApiResponse::HealthStatus(response));
+                    ApiRequest::CancelFanOut => fan_out.store(true, Ordering::Relaxed),
                 }
+                ControlFlow::Continue(())
+            },
+        );
+    });
+}
+
+/// State of the HTTP API worker, started over after a panic
+struct HttpWorker {
+    client: Client,
+    chaos: Chaos,
+    /// Keeps requests under `--max-rate`
+    limiter: RateLimiter,
+    /// Fallbacks include every instance's address from the tiers
+    discover: bool,
+    /// Set to stop the fan-out in progress
+    fan_out: Arc<AtomicBool>,
+    /// Set to stop the event stream, if one is open
+    event_stream: Arc<AtomicBool>,
+}
+
+impl HttpWorker {
+    /// A worker for `connect_url` with the session and fallbacks it was
+    /// given so far
+    fn start(
+        connect_url: &str,
+        debug: bool,
+        token: Option<&str>,
+        fallbacks: &[String],
+        discover: bool,
+    ) -> Self {
+        let mut client = Client::new(connect_url).with_debug(debug);
+        // Servers without ETags resend the same tiers on every poll; don't
+        // parse, pass on and rebuild the UI for a body seen last time
+        client.skip_unchanged();
+        if let Some(token) = token {
+            client.set_token(token);
+        }
+        client.set_fallbacks(fallbacks);
+        if discover {
+            client.discover_peers();
+        }
+        Self {
+            client,
+            chaos: chaos::current(),
+            limiter: RateLimiter::new(ratelimit::max_rate()),
+            discover,
+            fan_out: Arc::default(),
+            event_stream: Arc::default(),
+        }
+    }
+}
+
+/// A `ProbeHealth` fan-out
+struct HealthProbe {
+    id: u64,
+    addresses: Vec<String>,
+    cancel: Arc<AtomicBool>,
+    token: Option<String>,
+    limiter: RateLimiter,
+    debug: bool,
+}
+
+impl HealthProbe {
+    /// Probe and answer, with the panic if one of the requests panicked
+    fn run(self, responses: &Sender<ApiMessage>) {
+        match panic::catch_unwind(AssertUnwindSafe(|| self.probe())) {
+            Ok(message) => {
+                let _ = responses.send(message);
+            }
+            Err(payload) => {
+                let request = ApiRequest::ProbeHealth {
+                    id: self.id,
+                    addresses: self.addresses,
+                };
+                let failure = request.failure();
+                report_panic(responses, self.debug, request.name(), failure, payload);
             }
         }
+    }
+
+    /// Every instance's health, from at most `FAN_OUT_LIMIT` requests at
+    /// a time, as one `HealthProbes`
+    fn probe(&self) -> ApiMessage {
+        let started = Instant::now();
+        let start = || {
+            // Health URLs are made of the instance address, not a base URL
+            let mut client = Client::new("").with_debug(self.debug);
+            if let Some(ref token) = self.token {
+                client.set_token(token);
+            }
+            (client, chaos::current())
+        };
+        let calls = fan_out(
+            &self.addresses,
+            FAN_OUT_LIMIT,
+            &self.cancel,
+            start,
+            |(client, chaos), address| {
+                self.limiter.acquire();
+                chaos.apply(|| client.get_health_status(address))
+            },
+        );
+        let mut meta = ResponseMeta {
+            latency: started.elapsed(),
+            reachable: calls.is_empty(),
+            bytes: 0,
+        };
+        let results = calls
+            .into_iter()
+            .map(|call| match call {
+                Some(call) => {
+                    meta.reachable |= call.meta.reachable;
+                    meta.bytes += call.meta.bytes;
+                    call.result
+                }
+                None => Err("Cancelled".to_string()),
+            })
+            .collect();
+        let response = ApiResponse::HealthProbes(HealthProbes {
+            id: self.id,
+            results,
+            cancelled: self.cancel.load(Ordering::Relaxed),
+        });
+        ApiMessage { response, meta }
+    }
+}
+
+/// Run `call` on every item from at most `limit` threads, each with its own
+/// state from `start`, and return the results in the order of `items`.
+/// Items not yet started once `cancel` is set are skipped and left `None`.
+/// The threads are named like the worker, so their panics stay quiet too.
+pub fn fan_out<T: Sync, S, R: Send>(
+    items: &[T],
+    limit: usize,
+    cancel: &AtomicBool,
+    start: impl Fn() -> S + Sync,
+    call: impl Fn(&mut S, &T) -> R + Sync,
+) -> Vec<Option<R>> {
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new((0..items.len()).map(|_| None).collect::<Vec<_>>());
+    thread::scope(|scope| {
+        for _ in 0..limit.min(items.len()) {
+            let run = || {
+                let mut state = start();
+                loop {
+                    let i = next.fetch_add(1, Ordering::Relaxed);
+                    if i >= items.len() || cancel.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    let result = call(&mut state, &items[i]);
+                    results.lock().unwrap_or_else(|e| e.into_inner())[i] = Some(result);
+                }
+            };
+            thread::Builder::new()
+                .name(WORKER_THREAD.to_string())
+                .spawn_scoped(scope, run)
+                .expect("failed to spawn API worker thread");
+        }
     });
+    results.into_inner().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Start an API worker thread, named so its panics stay off the screen
+/// (see `quiet_worker_panics`)
+pub(crate) fn spawn_worker(run: impl FnOnce() + Send + 'static) {
+    thread::Builder::new()
+        .name(WORKER_THREAD.to_string())
+        .spawn(run)
+        .expect("failed to spawn API worker thread");
+}
+
+/// Hand every request to `handle` until it breaks or the App goes away.
+/// A request that panics doesn't silently kill the worker: the panic is
+/// logged, reported as `WorkerPanic`, the request is answered with it as
+/// its error, and the worker goes on from a fresh state made by `restart`.
+pub(crate) fn supervise<W>(
+    requests: Receiver<ApiRequest>,
+    responses: Sender<ApiMessage>,
+    debug: bool,
+    mut worker: W,
+    restart: impl Fn(&W) -> W,
+    mut handle: impl FnMut(&mut W, ApiRequest) -> ControlFlow<()>,
+) {
+    for request in requests {
+        let name = request.name();
+        let failure = request.failure();
+        let payload = match panic::catch_unwind(AssertUnwindSafe(|| handle(&mut worker, request))) {
+            Ok(ControlFlow::Continue(())) => continue,
+            Ok(ControlFlow::Break(())) => break,
+            Err(payload) => payload,
+        };
+        worker = restart(&worker);
+        if !report_panic(&responses, debug, name, failure, payload) {
+            break;
+        }
+    }
+}
+
+/// Log a panic caught handling request `name`, report it and answer the
+/// request with it. False if the App is gone.
+fn report_panic(
+    responses: &Sender<ApiMessage>,
+    debug: bool,
+    name: &'static str,
+    failure: Option<Box<dyn FnOnce(String) -> ApiResponse + Send>>,
+    payload: Box<dyn Any + Send>,
+) -> bool {
+    let panic = WorkerPanic {
+        request: name,
+        message: panic_message(payload.as_ref()),
+    };
+    log_debug(
+        debug,
+        &format!(
+            "PANIC handling {}: {}, restarting worker",
+            name, panic.message
+        ),
+    );
+    let meta = ResponseMeta {
+        latency: Duration::ZERO,
+        reachable: true,
+        bytes: 0,
+    };
+    let mut answers = vec![ApiResponse::WorkerPanic(panic.clone())];
+    answers.extend(failure.map(|failed| failed(panic.to_string())));
+    answers
+        .into_iter()
+        .all(|response| responses.send(ApiMessage { response, meta }).is_ok())
+}
+
+/// Text of a panic payload, as `panic!` formats it
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
+    }
 }
 
-fn log_debug(debug: bool, message: &str) {
+/// Keep the default panic output, which would garble the screen, out of
+/// worker panics: `supervise` reports them. With `debug`, where they
+/// happened goes to the log.
+pub fn quiet_worker_panics(debug: bool) {
+    let default = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        if thread::current().name() != Some(WORKER_THREAD) {
+            default(info);
+        } else if let Some(location) = info.location() {
+            log_debug(debug, &format!("PANIC at {}", location));
+        }
+    }));
+}
+
+/// Make a call, moving on through the fallback addresses while the
+/// current one doesn't answer at all. Every attempt waits its turn under
+/// the rate limit.
+fn with_failover<T>(
+    client: &mut Client,
+    chaos: &mut Chaos,
+    limiter: &RateLimiter,
+    call: impl Fn(&mut Client) -> Timed<T>,
+) -> Timed<T> {
+    limiter.acquire();
+    let mut timed = chaos.apply(|| call(client));
+    for _ in 0..client.fallbacks().len() {
+        if timed.meta.reachable || !client.fail_over() {
+            break;
+        }
+        limiter.acquire();
+        timed = chaos.apply(|| call(client));
+    }
+    timed
+}
+
+pub(crate) fn log_debug(debug: bool, message: &str) {
     if debug {
         use std::fs::OpenOptions;
         use std::io::Write;
         if let Ok(mut file) = OpenOptions::new()
             .create(true)
             .append(true)
-            .open("picotui.log")
+            .open(paths::log_file())
         {
             let elapsed = std::time::SystemTime::now()
                 .duration_since(std::time::UNIX_EPOCH)
@@ -278,3 +740,156 @@ fn log_debug(debug: bool, message: &str) {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc::channel;
+
+    /// A worker counting the requests it handled, that panics on `GetTiers`
+    /// and `SetFallbacks`
+    fn run(requests: Vec<ApiRequest>) -> Vec<ApiResponse> {
+        let (request_tx, request_rx) = channel();
+        let (response_tx, response_rx) = channel();
+        for request in requests {
+            request_tx.send(request).unwrap();
+        }
+        drop(request_tx);
+        let answers = response_tx.clone();
+        supervise(
+            request_rx,
+            response_tx,
+            false,
+            0,
+            |_| 100,
+            |handled, request| {
+                *handled += 1;
+                match request {
+                    ApiRequest::GetTiers | ApiRequest::SetFallbacks { .. } => {
+                        panic!("index out of bounds")
+                    }
+                    ApiRequest::Shutdown => return ControlFlow::Break(()),
+                    _ => {}
+                }
+                let _ = answers.send(ApiMessage {
+                    response: ApiResponse::Config(Ok(UiConfig {
+                        is_auth_enabled: *handled > 100,
+                    })),
+                    meta: ResponseMeta {
+                        latency: Duration::ZERO,
+                        reachable: true,
+                        bytes: 0,
+                    },
+                });
+                ControlFlow::Continue(())
+            },
+        );
+        response_rx
+            .try_iter()
+            .map(|message| message.response)
+            .collect()
+    }
+
+    #[test]
+    fn test_panic_is_reported_and_the_worker_restarted() {
+        let responses = run(vec![
+            ApiRequest::GetConfig,
+            ApiRequest::GetTiers,
+            ApiRequest::GetConfig,
+        ]);
+        let panic = WorkerPanic {
+            request: "GetTiers",
+            message: "index out of bounds".to_string(),
+        };
+        match &responses[..] {
+            [ApiResponse::Config(Ok(before)), ApiResponse::WorkerPanic(reported), ApiResponse::Tiers(Err(error)), ApiResponse::Config(Ok(after))] =>
+            {
+                assert!(!before.is_auth_enabled);
+                assert_eq!(reported, &panic);
+                assert_eq!(error, &panic.to_string());
+                assert!(after.is_auth_enabled, "state is the restarted one");
+            }
+            other => panic!("unexpected responses {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_panic_without_an_answer_is_only_reported() {
+        let fallbacks = ApiRequest::SetFallbacks {
+            urls: Vec::new(),
+            discover: true,
+        };
+        let responses = run(vec![fallbacks, ApiRequest::Shutdown]);
+        assert!(matches!(responses[..], [ApiResponse::WorkerPanic(_)]));
+
+        // Login names the request, never the credentials
+        let login = ApiRequest::Login {
+            username: "admin".to_string(),
+            password: "secret".to_string(),
+            remember_me: false,
+        };
+        assert_eq!(login.name(), "Login");
+        assert!(ApiRequest::Shutdown.failure().is_none());
+    }
+
+    #[test]
+    fn test_fan_out_keeps_order_and_bounds_concurrency() {
+        let running = AtomicUsize::new(0);
+        let peak = AtomicUsize::new(0);
+        let items: Vec<u64> = (0..40).collect();
+        let results = fan_out(
+            &items,
+            4,
+            &AtomicBool::new(false),
+            || (),
+            |_, &n| {
+                let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                thread::sleep(Duration::from_millis(40 - n));
+                running.fetch_sub(1, Ordering::SeqCst);
+                n * 2
+            },
+        );
+        let expected: Vec<_> = items.iter().map(|n| Some(n * 2)).collect();
+        assert_eq!(results, expected);
+        assert!(peak.load(Ordering::SeqCst) <= 4);
+        assert!(fan_out(&[] as &[u64], 4, &AtomicBool::new(false), || (), |_, n| *n).is_empty());
+
+        let names = fan_out(
+            &items,
+            4,
+            &AtomicBool::new(false),
+            || (),
+            |_, _| thread::current().name().map(str::to_string),
+        );
+        assert!(names
+            .iter()
+            .all(|name| name.as_ref().and_then(Option::as_deref) == Some(WORKER_THREAD)));
+    }
+
+    #[test]
+    fn test_fan_out_stops_starting_items_once_cancelled() {
+        let cancel = AtomicBool::new(false);
+        let items: Vec<u64> = (0..10).collect();
+        let results = fan_out(
+            &items,
+            1,
+            &cancel,
+            || (),
+            |_, &n| {
+                if n == 2 {
+                    cancel.store(true, Ordering::Relaxed);
+                }
+                n
+            },
+        );
+        assert_eq!(results[..3], [Some(0), Some(1), Some(2)]);
+        assert!(results[3..].iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn test_panic_message_of_formatted_panics() {
+        let payload = panic::catch_unwind(|| panic!("bad index {}", 7)).unwrap_err();
+        assert_eq!(panic_message(payload.as_ref()), "bad index 7");
+    }
+}
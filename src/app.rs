@@ -1,12 +1,100 @@
-use crate::api::{ApiRequest, ApiResponse};
+use crate::actions::{self, InstanceAction};
+use crate::alerts::{self, AlertEngine};
+use crate::api::{
+    ApiRequest, ApiResponse, Endpoint, ResponseMeta, ServerIdentity, INVALID_CREDENTIALS,
+};
+use crate::audit;
+use crate::bookmarks::{self, Bookmarks};
+use crate::bugreport;
+use crate::compat::{self, Capability, Compatibility, PicodataVersion};
+use crate::config::{RestartCommands, SavedFilter};
+use crate::confirm::{ConfirmDialog, Severity};
+use crate::diagnostics::FrameStats;
+use crate::doctor::TerminalEnv;
+use crate::errorlog::ErrorLog;
+use crate::filter::{self, FilterExpr};
+use crate::health::ClusterHealth;
+use crate::heatmap::{self, Coloring};
+use crate::i18n::{tr, trf};
+use crate::keymacros::{Key, KeyMacros};
 use crate::models::*;
+use crate::parameters;
+use crate::pins;
+use crate::prepare::{self, BucketCounts, PreparedTiers};
+use crate::rolling::{self, Outcome, RollingRestart};
+use crate::selection;
+use crate::shared::Shared;
+use crate::snapshot::{self, Snapshot, SnapshotDiff};
+use crate::source::{DataSource, SpawnSource};
+use crate::stream::StreamEvent;
+use crate::timings::SessionTimings;
+use crate::toasts::{ToastKind, Toasts};
 use crate::tokens;
+use crate::ui::format::{self, NumberFormat, TimeFormat};
+use crate::ui::screenshot::ScreenshotFormat;
+use crate::ui::theme::{self, Theme};
+use crate::usernames;
+use ratatui::text::Line;
 use ratatui::widgets::ListState;
-use std::collections::HashSet;
-use std::sync::mpsc::{Receiver, Sender};
+use serde::Deserialize;
+use std::cell::RefCell;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Number of filters kept in the history
+const FILTER_HISTORY_LIMIT: usize = 50;
+
+/// Clusters with at least this many instances re-filter only after typing pauses
+const FILTER_DEBOUNCE_THRESHOLD: usize = 2000;
+/// Pause after the last filter keystroke before re-filtering a large cluster
+const FILTER_DEBOUNCE: Duration = Duration::from_millis(100);
+
+// Failed logins allowed before further attempts are delayed
+const LOGIN_THROTTLE_AFTER: u32 = 3;
+// Delay after the first throttled failure; doubles with each further one
+const LOGIN_THROTTLE_BASE: Duration = Duration::from_secs(2);
+const LOGIN_THROTTLE_MAX: Duration = Duration::from_secs(30);
+
+// How long a press of the reveal key shows the password on terminals that
+// don't report key releases; auto-repeat keeps extending it while held
+const REVEAL_HOLD: Duration = Duration::from_millis(600);
+
+// How long the detail popup marks itself as updated after a refresh changed it
+const DETAIL_FLASH: Duration = Duration::from_millis(1500);
+
+// How long the screen stays inverted for `--bell flash`
+const SCREEN_FLASH: Duration = Duration::from_millis(150);
+
+// Instances named in one state change toast; more are only counted
+const MAX_TOASTED_NAMES: usize = 3;
+
+/// Consecutive transport failures before the connection is reported as down
+const CONNECTION_DOWN_THRESHOLD: u32 = 3;
+
+// Refreshes that found the server unreachable before the refresh interval
+// is stretched; it doubles with each further one
+const REFRESH_BACKOFF_AFTER: u32 = 2;
+const REFRESH_BACKOFF_MAX: Duration = Duration::from_secs(120);
+
+// How long the first connection may take before the connecting screen is
+// shown, so a quick start doesn't flash it
+pub const INIT_GRACE: Duration = Duration::from_millis(300);
+
+// Keys typed before the first screen that are kept to run once it's shown
+const MAX_QUEUED_KEYS: usize = 32;
+
+// A worker that stopped is replaced automatically at most this often; one
+// that keeps dying is left for `R` to restart
+const WORKER_RESPAWN_COOLDOWN: Duration = Duration::from_secs(30);
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum InputMode {
+    /// Waiting for the server to say whether it needs a login
+    Initializing,
     Normal,
     Login,
 }
@@ -18,7 +106,18 @@ pub enum LoginFocus {
     RememberMe,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// Result of the server check shown on the login screen
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum ServerCheck {
+    #[default]
+    NotChecked,
+    Checking,
+    Reachable(ServerIdentity),
+    Unreachable(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum ViewMode {
     #[default]
     Tiers,
@@ -27,6 +126,9 @@ pub enum ViewMode {
 }
 
 impl ViewMode {
+    /// In the order of their number keys
+    pub const ALL: [ViewMode; 3] = [ViewMode::Tiers, ViewMode::Replicasets, ViewMode::Instances];
+
     pub fn cycle_next(self) -> Self {
         match self {
             ViewMode::Tiers => ViewMode::Replicasets,
@@ -44,6 +146,103 @@ impl ViewMode {
     }
 }
 
+impl FromStr for ViewMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "tiers" => Ok(ViewMode::Tiers),
+            "replicasets" => Ok(ViewMode::Replicasets),
+            "instances" => Ok(ViewMode::Instances),
+            _ => Err(anyhow::anyhow!(
+                "Unknown view '{}' (expected tiers, replicasets or instances)",
+                s
+            )),
+        }
+    }
+}
+
+/// One row of the view switcher (`v`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ViewSummary {
+    pub mode: ViewMode,
+    /// Rows the view has
+    pub count: usize,
+    /// Rows left by the filter and scope, when they hide any
+    pub shown: Option<usize>,
+    /// Rows with an instance that isn't Online or is changing state
+    pub problems: usize,
+}
+
+/// How a refresh bringing new problems gets attention (`--bell`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bell {
+    /// Ring the terminal bell
+    Ring,
+    /// Invert the screen for a moment
+    Flash,
+    Both,
+}
+
+impl Bell {
+    fn rings(self) -> bool {
+        matches!(self, Bell::Ring | Bell::Both)
+    }
+
+    fn flashes(self) -> bool {
+        matches!(self, Bell::Flash | Bell::Both)
+    }
+}
+
+impl FromStr for Bell {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "bell" => Ok(Bell::Ring),
+            "flash" => Ok(Bell::Flash),
+            "both" => Ok(Bell::Both),
+            _ => Err(anyhow::anyhow!(
+                "Unknown bell '{}' (expected bell, flash or both)",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConnectionStatus {
+    #[default]
+    Connecting,
+    Connected,
+    Reconnecting,
+    Down,
+}
+
+impl ConnectionStatus {
+    pub fn label(self) -> &'static str {
+        match self {
+            ConnectionStatus::Connecting => "connecting",
+            ConnectionStatus::Connected => "connected",
+            ConnectionStatus::Reconnecting => "reconnecting",
+            ConnectionStatus::Down => "down",
+        }
+    }
+}
+
+/// State of the optional server-sent events stream
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StreamState {
+    /// Not connected; will try after the next successful poll
+    #[default]
+    Idle,
+    Connecting,
+    /// Updates are pushed by the server, polling is paused
+    Live,
+    /// Server has no stream endpoint, polling only
+    Unavailable,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum SortField {
     #[default]
@@ -52,17 +251,154 @@ pub enum SortField {
 }
 
 impl SortField {
-    pub fn cycle_next(self) -> Self {
+    /// In the order the sort picker lists them
+    pub const ALL: [SortField; 2] = [SortField::Name, SortField::FailureDomain];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SortField::Name => "Name",
+            SortField::FailureDomain => "Domain",
+        }
+    }
+
+    /// Key that picks the field in the sort picker
+    pub fn key(self) -> char {
+        match self {
+            SortField::Name => 'n',
+            SortField::FailureDomain => 'd',
+        }
+    }
+}
+
+/// Where one request of a refresh stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestProgress {
+    Pending,
+    Done,
+    Failed,
+}
+
+/// What the next letter does after `m` or `'`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BookmarkPrompt {
+    Set,
+    Jump,
+}
+
+/// Operation waiting in the confirmation dialog
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OpsAction {
+    StartRollingRestart,
+}
+
+/// Health of every instance of a tier or replicaset, probed at once
+#[derive(Debug, Clone)]
+pub struct GroupHealth {
+    /// Tags the answer; a newer probe makes older answers stale
+    pub id: u64,
+    /// e.g. "replicaset default_1"
+    pub title: String,
+    /// Instance names and HTTP addresses
+    pub instances: Vec<(String, String)>,
+    /// In the order of `instances`; `None` while probing
+    pub results: Option<Vec<Result<HealthStatus, String>>>,
+}
+
+/// What an operation would do, shown instead of running it
+/// (`--ops-dry-run`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DryRun {
+    pub title: String,
+    pub lines: Vec<String>,
+}
+
+/// Part of the topology the Instances view is limited to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InstanceScope {
+    Tier(String),
+    Replicaset { tier: String, replicaset: String },
+}
+
+impl InstanceScope {
+    pub fn label(&self) -> String {
+        match self {
+            InstanceScope::Tier(tier) => format!("tier {}", tier),
+            InstanceScope::Replicaset { replicaset, .. } => format!("replicaset {}", replicaset),
+        }
+    }
+
+    fn contains(&self, tier: &str, replicaset: &str) -> bool {
         match self {
-            SortField::Name => SortField::FailureDomain,
-            SortField::FailureDomain => SortField::Name,
+            InstanceScope::Tier(t) => t == tier,
+            InstanceScope::Replicaset {
+                tier: t,
+                replicaset: r,
+            } => t == tier && r == replicaset,
         }
     }
+}
+
+/// A column of the Instances view
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InstanceColumn {
+    Name,
+    State,
+    Tier,
+    Replicaset,
+    Lag,
+    Address,
+    PgAddress,
+    HttpAddress,
+    FailureDomain,
+    Version,
+    Uptime,
+    Pid,
+    RaftId,
+}
+
+impl InstanceColumn {
+    pub const ALL: [InstanceColumn; 13] = [
+        InstanceColumn::Name,
+        InstanceColumn::State,
+        InstanceColumn::Tier,
+        InstanceColumn::Replicaset,
+        InstanceColumn::Lag,
+        InstanceColumn::Address,
+        InstanceColumn::PgAddress,
+        InstanceColumn::HttpAddress,
+        InstanceColumn::FailureDomain,
+        InstanceColumn::Version,
+        InstanceColumn::Uptime,
+        InstanceColumn::Pid,
+        InstanceColumn::RaftId,
+    ];
+
+    /// Columns shown when the config file doesn't choose any
+    pub const DEFAULT: [InstanceColumn; 6] = [
+        InstanceColumn::Name,
+        InstanceColumn::State,
+        InstanceColumn::Replicaset,
+        InstanceColumn::Lag,
+        InstanceColumn::Address,
+        InstanceColumn::FailureDomain,
+    ];
 
     pub fn label(self) -> &'static str {
         match self {
-            SortField::Name => "Name",
-            SortField::FailureDomain => "Domain",
+            InstanceColumn::Name => "Name",
+            InstanceColumn::State => "State",
+            InstanceColumn::Tier => "Tier",
+            InstanceColumn::Replicaset => "Replicaset",
+            InstanceColumn::Lag => "Replication lag",
+            InstanceColumn::Address => "Binary address",
+            InstanceColumn::PgAddress => "PG address",
+            InstanceColumn::HttpAddress => "HTTP address",
+            InstanceColumn::FailureDomain => "Failure domain",
+            InstanceColumn::Version => "Version",
+            InstanceColumn::Uptime => "Uptime",
+            InstanceColumn::Pid => "PID",
+            InstanceColumn::RaftId => "Raft ID",
         }
     }
 }
@@ -90,44 +426,231 @@ impl SortOrder {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum TreeItem {
     Tier(usize),
     Replicaset(usize, usize),
     Instance(usize, usize, usize),
 }
 
+/// Instances view ordering together with the settings it was computed for
+struct InstanceCache {
+    filter: String,
+    fuzzy: bool,
+    sort_field: SortField,
+    sort_order: SortOrder,
+    pinned: BTreeSet<String>,
+    scope: Option<InstanceScope>,
+    indices: Vec<(usize, usize, usize)>,
+}
+
+/// Everything besides the row itself that a Tiers view line depends on
+#[derive(Debug, Clone, PartialEq)]
+pub struct TreeLineInputs {
+    generation: u64,
+    search: String,
+    pinned: BTreeSet<String>,
+    marked: BTreeSet<String>,
+    theme: Theme,
+    number_format: NumberFormat,
+    server_version: Option<PicodataVersion>,
+}
+
+/// Styled Tiers view rows, reused across frames while they were rendered
+/// from the same inputs. Rows are keyed by item and expanded state; the
+/// selection highlight is applied on top, so moving it keeps the cache.
+#[derive(Debug, Default)]
+pub struct TreeLineCache {
+    inputs: Option<TreeLineInputs>,
+    lines: HashMap<(TreeItem, bool), Line<'static>>,
+}
+
+impl TreeLineCache {
+    /// Drop every cached line unless they were rendered from `inputs`
+    pub fn validate(&mut self, inputs: TreeLineInputs) {
+        if self.inputs.as_ref() != Some(&inputs) {
+            self.lines.clear();
+            self.inputs = Some(inputs);
+        }
+    }
+
+    pub fn get_or_insert_with(
+        &mut self,
+        item: TreeItem,
+        expanded: bool,
+        render: impl FnOnce() -> Line<'static>,
+    ) -> &Line<'static> {
+        self.lines.entry((item, expanded)).or_insert_with(render)
+    }
+
+    pub fn get(&self, item: TreeItem, expanded: bool) -> Option<&Line<'static>> {
+        self.lines.get(&(item, expanded))
+    }
+
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+}
+
+/// A row the search can land on: a tree node (possibly under a collapsed
+/// parent) in the Tiers view, or a list row in the flat views
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SearchTarget {
+    Node(TreeItem),
+    Row(usize),
+}
+
+/// Identity of a tree node by name, stable across data refreshes
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum NodeKey {
+    Tier(String),
+    Replicaset(String, String),
+    Instance(String, String, String),
+}
+
+impl NodeKey {
+    /// Names from the tier down, as stored in bookmarks
+    fn path(&self) -> Vec<String> {
+        match self {
+            NodeKey::Tier(tier) => vec![tier.clone()],
+            NodeKey::Replicaset(tier, rs) => vec![tier.clone(), rs.clone()],
+            NodeKey::Instance(tier, rs, inst) => vec![tier.clone(), rs.clone(), inst.clone()],
+        }
+    }
+
+    fn from_path(path: &[String]) -> Option<NodeKey> {
+        match path {
+            [tier] => Some(NodeKey::Tier(tier.clone())),
+            [tier, rs] => Some(NodeKey::Replicaset(tier.clone(), rs.clone())),
+            [tier, rs, inst] => Some(NodeKey::Instance(tier.clone(), rs.clone(), inst.clone())),
+            _ => None,
+        }
+    }
+
+    fn parent(&self) -> Option<NodeKey> {
+        match self {
+            NodeKey::Tier(_) => None,
+            NodeKey::Replicaset(tier, _) => Some(NodeKey::Tier(tier.clone())),
+            NodeKey::Instance(tier, rs, _) => Some(NodeKey::Replicaset(tier.clone(), rs.clone())),
+        }
+    }
+}
+
 pub struct App {
     pub running: bool,
 
     // Connection info
     pub base_url: String,
 
-    // Channels for API communication
-    pub request_tx: Sender<ApiRequest>,
-    pub response_rx: Receiver<ApiResponse>,
+    // Where requests go and answers come from
+    pub source: Box<dyn DataSource>,
+
+    // Connection health
+    pub connection_status: ConnectionStatus,
+    pub last_latency: Option<Duration>,
+    pub connection_failures: u32,
+    // Consecutive refreshes that ended without reaching the server
+    pub unreachable_refreshes: u32,
+
+    // Streaming updates (None disables streaming)
+    pub stream_path: Option<String>,
+    pub stream_state: StreamState,
+
+    // Set when state changed and the UI needs to be redrawn
+    pub dirty: bool,
 
     // Loading state
     pub loading: bool,
-    pub pending_init: bool,
+    // Requests of the current refresh; `loading` stays set until none is pending
+    pub refresh_progress: Vec<(Endpoint, RequestProgress)>,
+    pub refresh_started_at: Option<Instant>,
 
     // Input mode
     pub input_mode: InputMode,
+    // Keys typed while initializing, run once the cluster is shown
+    pub queued_keys: Vec<Key>,
+    // Whether the URL can be changed from the connecting screen: not when
+    // the cluster is reached through a tunnel or over iproto
+    pub url_editable: bool,
+    // URL being typed on the connecting screen
+    pub url_prompt: Option<String>,
+    // URL to start a new API worker for before the next frame
+    pub pending_connect: Option<String>,
+    // Starts a replacement for `source` when its worker stops
+    pub respawn_source: Option<SpawnSource>,
+    // The worker stopped answering for good
+    pub worker_stopped: bool,
+    last_respawn: Option<Instant>,
+    // Tokens the worker was given, handed again to a replacement
+    session: Option<Session>,
 
     // Auth
     pub auth_enabled: bool,
     pub has_saved_token: bool,
+    pub current_user: Option<String>,
     pub login_username: String,
     pub login_password: String,
     pub login_focus: LoginFocus,
     pub login_remember_me: bool,
     pub login_show_password: bool,
+    /// Reveal key is held down (terminals that report key releases)
+    pub login_reveal_held: bool,
+    login_reveal_until: Option<Instant>,
     pub login_error: Option<String>,
+    /// Validation error shown inline on the username field
+    pub login_username_error: Option<String>,
+    /// Set after submitting an empty password; a second submit goes through
+    pub login_password_warning: Option<String>,
+    /// Reported by terminals that send keyboard state with key events
+    pub login_caps_lock: bool,
+    /// Consecutive rejected logins
+    pub login_failures: u32,
+    login_retry_at: Option<Instant>,
+    // The username of each successful login is saved here per cluster URL
+    pub usernames_path: Option<PathBuf>,
+    // Audit trail of user actions (None disables it)
+    pub audit_log_path: Option<PathBuf>,
+    // Read into bug-report bundles (`B`); the debug log only with --debug
+    pub config_path: Option<PathBuf>,
+    pub debug_log: Option<PathBuf>,
+    pub server_check: ServerCheck,
 
     // Data
     pub cluster_info: Option<ClusterInfo>,
-    pub tiers: Vec<TierInfo>,
+    /// Server version reported by the cluster endpoint, if parseable
+    pub server_version: Option<PicodataVersion>,
+    /// Shared with the snapshots saved from it instead of copied into them
+    pub tiers: Shared<Vec<TierInfo>>,
+    /// Tiers whose buckets are moving between replicasets
+    pub rebalancing_tiers: HashSet<String>,
+    // Bucket counts of the current snapshot, to detect rebalancing
+    bucket_counts: BucketCounts,
+    /// Health score of the latest tiers snapshot
+    pub cluster_health: Option<ClusterHealth>,
+    // Alert rules from the config and the alerts firing
+    pub alerts: AlertEngine,
+    pub show_alerts: bool,
+    /// Unix time of the last confirmed tiers snapshot
+    pub last_updated: Option<u64>,
     pub last_error: Option<String>,
+    // Every error of the session, including the ones never shown because
+    // an earlier one still was (`!`)
+    pub error_log: ErrorLog,
+    pub show_error_log: bool,
+
+    // Transient informational message shown in the status bar
+    pub status_message: Option<String>,
+
+    // Display preferences for sizes, counts and timestamps
+    pub number_format: NumberFormat,
+    pub time_format: TimeFormat,
+
+    // Screen dump requested for the next rendered frame
+    pub pending_screenshot: Option<ScreenshotFormat>,
 
     // Tree state
     pub expanded_tiers: HashSet<usize>,
@@ -135,204 +658,1096 @@ pub struct App {
     pub tree_items: Vec<TreeItem>,
     pub selected_index: usize,
 
-    // Detail popup
+    // Hide everything that changes the cluster (`--read-only`)
+    pub read_only: bool,
+    // Offer actions that change the cluster (`--ops`)
+    pub ops: bool,
+    // Show what operations would do instead of doing it (`--ops-dry-run`)
+    pub ops_dry_run: bool,
+    pub dry_run: Option<DryRun>,
+    // Commands of the rolling restart assistant, from the config
+    pub restart_commands: RestartCommands,
+    // Rolling restart popup, kept after the restart ends until closed
+    pub rolling_restart: Option<RollingRestart>,
+    // Confirmation asked before an operation runs, above everything else
+    pub confirm: Option<ConfirmDialog<OpsAction>>,
+
+    // Screen-reader friendly rendering (`--accessible`)
+    pub accessible: bool,
+    // Selection changes written as text for screen readers (`--announce`)
+    pub announce_tx: Option<Sender<String>>,
+    last_announced: Option<String>,
+
+    // Keep terminal output to real changes (`--slow-link`)
+    pub slow_link: bool,
+
+    // Ring or flash when a refresh brings a new Offline instance or alert
+    pub bell: Option<Bell>,
+    // Bell to ring with the next frame
+    pub pending_bell: bool,
+    flash_until: Option<Instant>,
+
+    // Notifications in the top right corner
+    pub toasts: Toasts,
+    // Error already shown as a toast, so each refresh failing the same way
+    // doesn't bring it back
+    toasted_error: Option<String>,
+
+    // `.` repeat and recorded key sequences
+    pub macros: KeyMacros,
+
+    // Detail popup, bound to the instance it was opened for so refreshes
+    // update it in place even if sorting moves the selection
     pub show_detail: bool,
+    detail_instance: Option<String>,
+    detail_updated_at: Option<Instant>,
+
+    // Instance actions menu, bound to the instance it was opened for
+    pub show_action_menu: bool,
+    pub action_menu_index: usize,
+    action_menu_instance: Option<String>,
+
+    // Leader distribution popup
+    pub show_leaders: bool,
+
+    // Memory statistics popup
+    pub show_memory_stats: bool,
+
+    // Instances by failure domain, with names next to the markers or not
+    pub show_topology: bool,
+    pub topology_names: bool,
+
+    // Instance heatmap, its cursor bound to an instance by name
+    pub show_heatmap: bool,
+    pub heatmap_coloring: Coloring,
+    heatmap_instance: Option<String>,
+
+    // Request latency, errors and traffic per endpoint this session
+    pub timings: SessionTimings,
+    pub show_timings: bool,
+    pub frame_stats: FrameStats,
+    pub show_diagnostics: bool,
+
+    // Snapshots: saved under `snapshot_dir` (disabled when None), named
+    // through a status bar prompt and compared with the live cluster
+    pub snapshot_dir: Option<PathBuf>,
+    pub snapshot_prompt_active: bool,
+    pub snapshot_name: String,
+    pub snapshot_names: Vec<String>,
+    pub show_snapshot_picker: bool,
+    pub snapshot_picker_index: usize,
+    pub snapshot_diff: Option<(String, SnapshotDiff)>,
+    // Tiers of the compared snapshot, and the generation of the live tiers
+    // the diff was taken against
+    compared_snapshot: Option<(Arc<Vec<TierInfo>>, u64)>,
+
+    // Color theme. `T` switches between the built-in theme and the skins in
+    // `skins_dir`, re-reading them so edits apply without a restart.
+    pub theme: Theme,
+    pub skins_dir: Option<PathBuf>,
+
+    // Status bar template from the config; the built-in layout when None
+    pub status_bar_format: Option<String>,
+    // When the main loop will next refresh automatically, if it will
+    pub next_refresh: Option<Instant>,
 
     // Health status popup
     pub show_health: bool,
     pub health_status: Option<HealthStatus>,
     pub health_loading: bool,
     pub health_error: Option<String>,
+    // The popup lists every instance of a tier or replicaset instead
+    pub group_health: Option<GroupHealth>,
+    last_probe_id: u64,
+
+    // Tables popup
+    pub show_tables: bool,
+    pub tables: Option<Vec<TableInfo>>,
+    pub tables_loading: bool,
+    pub tables_error: Option<String>,
+    pub tables_scroll: usize,
+
+    // Configuration parameters popup
+    pub show_parameters: bool,
+    pub parameters: Option<Vec<ConfigParameter>>,
+    pub parameters_loading: bool,
+    pub parameters_error: Option<String>,
+    pub parameters_scroll: usize,
+    pub parameters_search: String,
+    /// The search line has the keyboard
+    pub parameters_search_active: bool,
 
     // View mode
     pub view_mode: ViewMode,
+    // Expand nodes with Offline, Expelled or transitioning instances on refresh
+    pub expand_problems: bool,
 
     // Sorting (instances view)
     pub sort_field: SortField,
     pub sort_order: SortOrder,
+    pub show_sort_picker: bool,
+    pub sort_picker_index: usize,
 
     // Filtering (instances view)
     pub filter_text: String,
+    // Siblings scope set with z/Z, applied on top of the filter
+    pub scope: Option<InstanceScope>,
+    // Set while filter edits on a large cluster wait for typing to pause;
+    // the Instances view keeps showing the previous result until then
+    filter_settle_at: Option<Instant>,
     pub filter_active: bool,
+    /// Subsequence matching ranked by score instead of plain substrings
+    pub fuzzy_filter: bool,
+    // Recently applied filters, oldest first; browsed with ↑/↓ while typing
+    pub filter_history: Vec<String>,
+    filter_history_pos: Option<usize>,
+    filter_draft: String,
+
+    // Named filters from the config file
+    pub saved_filters: Vec<SavedFilter>,
+    pub show_filter_picker: bool,
+    pub filter_picker_index: usize,
+
+    // Instances view columns, in display order
+    pub instance_columns: Vec<InstanceColumn>,
+    pub show_column_picker: bool,
+    pub column_picker_index: usize,
+
+    // View switcher (`v`)
+    pub show_view_switcher: bool,
+    pub view_switcher_index: usize,
+
+    // Search (all views): jumps between matching rows without hiding others
+    pub search_text: String,
+    pub search_active: bool,
+    search_origin: Option<NodeKey>,
+
+    // Pinned instance names, sorted to the top of the Instances view.
+    // Saved to `pins_path` (per cluster URL) when set.
+    pub pinned: BTreeSet<String>,
+    pub pins_path: Option<PathBuf>,
+
+    // Tree positions bookmarked with m + letter, saved to `bookmarks_path`
+    // (per cluster URL) when set. The prompt waits for the letter.
+    pub bookmarks: Bookmarks,
+    pub bookmarks_path: Option<PathBuf>,
+    pub bookmark_prompt: Option<BookmarkPrompt>,
+
+    // Instances marked with Space for bulk actions
+    pub marked: BTreeSet<String>,
+    // Text to hand to the terminal clipboard after the next frame
+    pub pending_clipboard: Option<String>,
+    // URL to open in the browser after the next frame
+    pub pending_open_url: Option<String>,
 
     // List state for scrolling
     pub list_state: ListState,
+
+    // Sorted/filtered Instances view, invalidated on data, filter or sort change
+    instance_cache: RefCell<Option<InstanceCache>>,
+
+    // Bumped whenever the tree is rebuilt from new data
+    data_generation: u64,
+    pub tree_line_cache: TreeLineCache,
 }
 
 impl App {
-    pub fn new(
-        base_url: String,
-        request_tx: Sender<ApiRequest>,
-        response_rx: Receiver<ApiResponse>,
-    ) -> Self {
-        // Check for saved token
-        let saved_token = tokens::load_tokens(&base_url);
-        let has_saved_token = saved_token.is_some();
-
-        // If we have a saved token, send it to the API worker
-        if let Some(token_entry) = saved_token {
-            let _ = request_tx.send(ApiRequest::SetToken {
-                auth: token_entry.auth,
-                refresh: token_entry.refresh,
-            });
-        }
+    pub fn new(base_url: String, source: impl DataSource + 'static) -> Self {
+        let session = restore_session(&base_url, &source);
+        let has_saved_token = session.is_some();
+        let current_user = session
+            .as_ref()
+            .and_then(|session| tokens::username_from_jwt(&session.auth));
 
         Self {
             running: true,
             base_url,
-            request_tx,
-            response_rx,
+            source: Box::new(source),
+            connection_status: ConnectionStatus::default(),
+            last_latency: None,
+            connection_failures: 0,
+            unreachable_refreshes: 0,
+            stream_path: None,
+            stream_state: StreamState::default(),
+            dirty: true,
             loading: false,
-            pending_init: true,
+            refresh_progress: Vec::new(),
+            refresh_started_at: None,
             input_mode: InputMode::Normal,
+            queued_keys: Vec::new(),
+            url_editable: false,
+            url_prompt: None,
+            pending_connect: None,
+            respawn_source: None,
+            worker_stopped: false,
+            last_respawn: None,
+            session,
             auth_enabled: false,
             has_saved_token,
+            current_user,
             login_username: String::new(),
             login_password: String::new(),
             login_focus: LoginFocus::Username,
             login_remember_me: true,
             login_show_password: false,
+            login_reveal_held: false,
+            login_reveal_until: None,
             login_error: None,
+            login_username_error: None,
+            login_password_warning: None,
+            login_caps_lock: false,
+            login_failures: 0,
+            login_retry_at: None,
+            usernames_path: None,
+            audit_log_path: None,
+            config_path: None,
+            debug_log: None,
+            server_check: ServerCheck::NotChecked,
             cluster_info: None,
-            tiers: Vec::new(),
+            server_version: None,
+            rebalancing_tiers: HashSet::new(),
+            bucket_counts: BucketCounts::new(),
+            cluster_health: None,
+            alerts: AlertEngine::default(),
+            show_alerts: false,
+            last_updated: None,
+            tiers: Shared::default(),
             last_error: None,
+            error_log: ErrorLog::default(),
+            show_error_log: false,
+            status_message: None,
+            number_format: NumberFormat::default(),
+            time_format: TimeFormat::default(),
+            pending_screenshot: None,
             expanded_tiers: HashSet::new(),
             expanded_replicasets: HashSet::new(),
             tree_items: Vec::new(),
             selected_index: 0,
+            read_only: false,
+            ops: false,
+            ops_dry_run: false,
+            dry_run: None,
+            restart_commands: RestartCommands::default(),
+            rolling_restart: None,
+            confirm: None,
+            accessible: false,
+            announce_tx: None,
+            last_announced: None,
+            slow_link: false,
+            bell: None,
+            pending_bell: false,
+            flash_until: None,
+            toasts: Toasts::default(),
+            toasted_error: None,
+            macros: KeyMacros::default(),
             show_detail: false,
+            detail_instance: None,
+            detail_updated_at: None,
+            show_action_menu: false,
+            action_menu_index: 0,
+            action_menu_instance: None,
+            show_leaders: false,
+            show_memory_stats: false,
+            show_topology: false,
+            topology_names: false,
+            show_heatmap: false,
+            heatmap_coloring: Coloring::default(),
+            heatmap_instance: None,
+            timings: SessionTimings::default(),
+            show_timings: false,
+            frame_stats: FrameStats::default(),
+            show_diagnostics: false,
+            snapshot_dir: None,
+            theme: Theme::default(),
+            skins_dir: None,
+            status_bar_format: None,
+            next_refresh: None,
+            snapshot_prompt_active: false,
+            snapshot_name: String::new(),
+            snapshot_names: Vec::new(),
+            show_snapshot_picker: false,
+            snapshot_picker_index: 0,
+            snapshot_diff: None,
+            compared_snapshot: None,
             show_health: false,
             health_status: None,
             health_loading: false,
+            group_health: None,
+            last_probe_id: 0,
             health_error: None,
+            show_tables: false,
+            tables: None,
+            tables_loading: false,
+            tables_error: None,
+            tables_scroll: 0,
+            show_parameters: false,
+            parameters: None,
+            parameters_loading: false,
+            parameters_error: None,
+            parameters_scroll: 0,
+            parameters_search: String::new(),
+            parameters_search_active: false,
             view_mode: ViewMode::default(),
+            expand_problems: false,
             sort_field: SortField::default(),
             sort_order: SortOrder::default(),
+            show_sort_picker: false,
+            sort_picker_index: 0,
             filter_text: String::new(),
+            scope: None,
+            filter_settle_at: None,
             filter_active: false,
+            fuzzy_filter: false,
+            filter_history: Vec::new(),
+            filter_history_pos: None,
+            filter_draft: String::new(),
+            saved_filters: Vec::new(),
+            show_filter_picker: false,
+            filter_picker_index: 0,
+            instance_columns: InstanceColumn::DEFAULT.to_vec(),
+            show_column_picker: false,
+            column_picker_index: 0,
+            show_view_switcher: false,
+            view_switcher_index: 0,
+            search_text: String::new(),
+            search_active: false,
+            search_origin: None,
+            pinned: BTreeSet::new(),
+            pins_path: None,
+            bookmarks: Bookmarks::new(),
+            bookmarks_path: None,
+            bookmark_prompt: None,
+            marked: BTreeSet::new(),
+            pending_clipboard: None,
+            pending_open_url: None,
             list_state: ListState::default().with_selected(Some(0)),
+            instance_cache: RefCell::new(None),
+            data_generation: 0,
+            tree_line_cache: TreeLineCache::default(),
         }
     }
 
+    /// Queue a request, counting it as in flight until it is answered
+    fn send(&mut self, request: ApiRequest) {
+        if request.expects_answer() {
+            self.frame_stats.in_flight += 1;
+        }
+        self.source.send(request);
+    }
+
     /// Start initialization by requesting config
     pub fn start_init(&mut self) {
         self.loading = true;
-        self.pending_init = true;
-        let _ = self.request_tx.send(ApiRequest::GetConfig);
+        self.refresh_progress.clear();
+        self.refresh_started_at = Some(Instant::now());
+        self.input_mode = InputMode::Initializing;
+        self.send(ApiRequest::GetConfig);
+    }
+
+    /// Whether the connecting screen is due: once the grace period is over
+    /// or the first attempt failed
+    pub fn init_screen_due(&self) -> bool {
+        !self.loading
+            || !self.animate()
+            || self
+                .refresh_started_at
+                .is_none_or(|at| at.elapsed() >= INIT_GRACE)
+    }
+
+    /// Keep a key typed while initializing for when the cluster is shown
+    pub fn queue_key(&mut self, key: Key) {
+        if self.queued_keys.len() < MAX_QUEUED_KEYS {
+            self.queued_keys.push(key);
+        }
+    }
+
+    /// Open the URL prompt of the connecting screen
+    pub fn start_url_edit(&mut self) {
+        if self.url_editable {
+            self.url_prompt = Some(self.base_url.clone());
+            self.status_message = None;
+        }
+    }
+
+    /// Connect to the URL typed at the prompt once the main loop has
+    /// started a worker for it
+    pub fn submit_url_edit(&mut self) {
+        let Some(url) = self.url_prompt.take() else {
+            return;
+        };
+        let url = url.trim().trim_end_matches('/').to_string();
+        if !(url.starts_with("http://") || url.starts_with("https://")) {
+            self.url_prompt = Some(url);
+            self.status_message =
+                Some(tr("The URL must start with http:// or https://").to_string());
+            return;
+        }
+        self.status_message = None;
+        self.pending_connect = Some(url);
+    }
+
+    /// Switch to another cluster URL served by the sources `spawn` starts,
+    /// with the saved session, username, bookmarks and pins of that URL,
+    /// and start over
+    pub fn connect(&mut self, base_url: String, spawn: SpawnSource) {
+        self.source.send(ApiRequest::Shutdown);
+        let source = spawn();
+        self.session = restore_session(&base_url, &source);
+        self.source = source;
+        self.respawn_source = Some(spawn);
+        self.base_url = base_url;
+        self.has_saved_token = self.session.is_some();
+        self.current_user = self
+            .session
+            .as_ref()
+            .and_then(|session| tokens::username_from_jwt(&session.auth));
+        self.connection_status = ConnectionStatus::default();
+        self.connection_failures = 0;
+        self.unreachable_refreshes = 0;
+        self.worker_stopped = false;
+        // Whatever the old worker still owed is lost with it
+        self.frame_stats.in_flight = 0;
+        self.last_error = None;
+        self.queued_keys.clear();
+        if let Some(username) = self
+            .usernames_path
+            .as_deref()
+            .and_then(|path| usernames::load_username(path, &self.base_url))
+        {
+            self.login_username = username;
+            self.login_focus = LoginFocus::Password;
+        }
+        if let Some(ref path) = self.bookmarks_path {
+            self.bookmarks = bookmarks::load_bookmarks(path, &self.base_url);
+        }
+        if let Some(ref path) = self.pins_path {
+            self.pinned = pins::load_pins(path, &self.base_url);
+        }
+        self.start_init();
+    }
+
+    /// Replace the data source with a fresh one from `respawn_source`, e.g.
+    /// after its worker stopped, handing it the current session and picking
+    /// up where the old one left off. False if there's no way to start one.
+    pub fn reconnect(&mut self) -> bool {
+        let Some(spawn) = self.respawn_source.as_ref() else {
+            return false;
+        };
+        self.source.send(ApiRequest::Shutdown);
+        self.source = spawn();
+        self.last_respawn = Some(Instant::now());
+        self.worker_stopped = false;
+        self.frame_stats.in_flight = 0;
+        self.connection_failures = 0;
+        self.connection_status = ConnectionStatus::Reconnecting;
+        // The old stream delivered to the old worker's channel
+        if self.stream_state != StreamState::Unavailable {
+            self.stream_state = StreamState::Idle;
+        }
+        if let Some(ref session) = self.session {
+            self.source.send(ApiRequest::SetToken {
+                auth: session.auth.clone(),
+                refresh: session.refresh.clone(),
+                remember: session.saved,
+            });
+        }
+        self.loading = false;
+        match self.input_mode {
+            InputMode::Initializing => self.start_init(),
+            InputMode::Normal => self.request_refresh(),
+            InputMode::Login => {}
+        }
+        true
     }
 
     /// Request a data refresh (non-blocking)
     pub fn request_refresh(&mut self) {
+        self.request_endpoints(&[Endpoint::ClusterInfo, Endpoint::Tiers]);
+    }
+
+    /// Re-request the tiers alone, keeping the cluster summary. The API
+    /// can't narrow the tiers to one, so this only saves the cluster request
+    /// while watching the tree converge.
+    pub fn request_tiers_refresh(&mut self) {
+        self.request_endpoints(&[Endpoint::Tiers]);
+    }
+
+    fn request_endpoints(&mut self, endpoints: &[Endpoint]) {
         self.loading = true;
         self.last_error = None;
-        let _ = self.request_tx.send(ApiRequest::GetClusterInfo);
-        let _ = self.request_tx.send(ApiRequest::GetTiers);
+        self.refresh_progress = endpoints
+            .iter()
+            .map(|&endpoint| (endpoint, RequestProgress::Pending))
+            .collect();
+        self.refresh_started_at = Some(Instant::now());
+        for endpoint in endpoints {
+            self.send(match endpoint {
+                Endpoint::ClusterInfo => ApiRequest::GetClusterInfo,
+                Endpoint::Tiers => ApiRequest::GetTiers,
+            });
+        }
+    }
+
+    /// Record the outcome of one refresh request; loading ends with the last one
+    fn finish_request(&mut self, endpoint: Endpoint, ok: bool) {
+        for (e, progress) in &mut self.refresh_progress {
+            if *e == endpoint {
+                *progress = if ok {
+                    RequestProgress::Done
+                } else {
+                    RequestProgress::Failed
+                };
+            }
+        }
+        self.loading = self
+            .refresh_progress
+            .iter()
+            .any(|(_, progress)| *progress == RequestProgress::Pending);
+        if !self.loading && self.connection_status != ConnectionStatus::Connected {
+            self.unreachable_refreshes += 1;
+        }
+    }
+
+    /// Automatic refresh interval: `base`, stretched while refreshes keep
+    /// failing to reach the server
+    pub fn refresh_interval(&self, base: Duration) -> Duration {
+        let interval = match self
+            .unreachable_refreshes
+            .checked_sub(REFRESH_BACKOFF_AFTER)
+        {
+            Some(extra) => base
+                .saturating_mul(2 << extra.min(8))
+                .min(REFRESH_BACKOFF_MAX.max(base)),
+            None => base,
+        };
+        // Steps of a rolling restart are noticed on refresh
+        if self
+            .rolling_restart
+            .as_ref()
+            .is_some_and(RollingRestart::is_running)
+        {
+            interval.min(rolling::REFRESH_INTERVAL)
+        } else {
+            interval
+        }
+    }
+
+    pub fn is_backing_off(&self) -> bool {
+        self.unreachable_refreshes >= REFRESH_BACKOFF_AFTER
+    }
+
+    /// The refresh was abandoned, e.g. because the session expired
+    fn cancel_refresh(&mut self) {
+        self.refresh_progress.clear();
+        self.loading = false;
     }
 
     /// Request login (non-blocking)
     pub fn request_login(&mut self) {
         self.loading = true;
+        self.refresh_progress.clear();
+        self.refresh_started_at = Some(Instant::now());
         self.login_error = None;
-        let _ = self.request_tx.send(ApiRequest::Login {
+        // The worker drops the stream opened with the old session; a new
+        // one starts after the next successful poll
+        if self.stream_state != StreamState::Unavailable {
+            self.stream_state = StreamState::Idle;
+        }
+        self.send(ApiRequest::Login {
             username: self.login_username.clone(),
             password: self.login_password.clone(),
             remember_me: self.login_remember_me,
         });
     }
 
-    /// Request health status for the selected instance
-    pub fn request_health_status(&mut self) {
-        // Extract http_address first to avoid borrow issues
-        let http_address = self.get_selected_instance().map(|i| i.http_address.clone());
-
-        if let Some(addr) = http_address {
-            if addr.is_empty() {
-                self.health_error = Some("Instance has no HTTP address".to_string());
-                self.show_health = true;
-                return;
-            }
-            self.health_loading = true;
-            self.health_status = None;
-            self.health_error = None;
-            self.show_health = true;
-            let _ = self
-                .request_tx
-                .send(ApiRequest::GetHealthStatus { http_address: addr });
+    /// Validate the login form and submit it if it's complete
+    pub fn submit_login(&mut self) {
+        if self.login_username.trim().is_empty() {
+            self.login_username_error = Some("required".to_string());
+            self.login_focus = LoginFocus::Username;
+            return;
+        }
+        self.login_username_error = None;
+        if self.login_retry_in().is_some() {
+            return;
+        }
+        if self.login_password.is_empty() && self.login_password_warning.is_none() {
+            self.login_password_warning = Some("empty, Enter again to submit".to_string());
+            self.login_focus = LoginFocus::Password;
+            return;
         }
+        self.login_password_warning = None;
+        self.request_login();
     }
 
-    /// Logout, clear saved tokens, and exit
-    pub fn logout(&mut self) {
-        // Delete tokens directly (don't rely on worker thread)
-        let _ = tokens::delete_tokens(&self.base_url);
-        self.running = false;
+    /// Whether the password is shown in clear text: toggled on, or the
+    /// reveal key is being held
+    pub fn password_revealed(&self) -> bool {
+        self.login_show_password || self.login_reveal_held || self.login_reveal_until.is_some()
     }
 
-    /// Process any pending API responses (non-blocking)
-    pub fn process_responses(&mut self) {
-        use std::sync::mpsc::TryRecvError;
+    /// Show the password briefly; called for every press and auto-repeat
+    /// of the reveal key on terminals that don't report its release
+    pub fn hold_password_reveal(&mut self) {
+        self.login_reveal_until = Some(Instant::now() + REVEAL_HOLD);
+    }
 
-        loop {
-            match self.response_rx.try_recv() {
-                Ok(response) => self.handle_response(response),
-                Err(TryRecvError::Empty) => break,
-                Err(TryRecvError::Disconnected) => {
-                    self.last_error = Some("API worker disconnected".to_string());
-                    break;
+    /// Hide the password again once the reveal key is no longer repeating.
+    /// Returns true if the view needs redrawing.
+    pub fn settle_password_reveal(&mut self) -> bool {
+        if self
+            .login_reveal_until
+            .is_some_and(|at| Instant::now() >= at)
+        {
+            self.login_reveal_until = None;
+            return true;
+        }
+        false
+    }
+
+    /// Insert pasted text into the text input that has focus, if any.
+    /// Control characters are dropped so a trailing newline can't submit.
+    pub fn paste(&mut self, text: &str) {
+        let text: String = text.chars().filter(|c| !c.is_control()).collect();
+        match self.input_mode {
+            InputMode::Initializing => {
+                if let Some(url) = self.url_prompt.as_mut() {
+                    url.push_str(&text);
+                }
+            }
+            InputMode::Login => match self.login_focus {
+                LoginFocus::Username => {
+                    self.login_username.push_str(&text);
+                    self.login_username_error = None;
+                }
+                LoginFocus::Password => {
+                    self.login_password.push_str(&text);
+                    self.login_password_warning = None;
+                }
+                LoginFocus::RememberMe => {}
+            },
+            InputMode::Normal => {
+                if self.filter_active {
+                    self.filter_text.push_str(&text);
+                    self.filter_edited();
+                } else if self.snapshot_prompt_active {
+                    self.snapshot_name.push_str(&text);
+                } else if self.search_active {
+                    self.search_text.push_str(&text);
+                    self.update_search();
+                } else if let Some(dialog) = self.confirm.as_mut() {
+                    dialog.push_str(&text);
+                } else if self.parameters_search_active {
+                    let search = format!("{}{}", self.parameters_search, text);
+                    self.set_parameters_search(search);
                 }
             }
         }
     }
 
-    fn handle_response(&mut self, response: ApiResponse) {
-        match response {
-            ApiResponse::Config(result) => {
-                self.loading = false;
-                match result {
-                    Ok(config) => {
-                        self.auth_enabled = config.is_auth_enabled;
+    /// Time left before another login may be submitted after repeated failures
+    pub fn login_retry_in(&self) -> Option<Duration> {
+        self.login_retry_at
+            .map(|at| at.saturating_duration_since(Instant::now()))
+            .filter(|left| !left.is_zero())
+    }
+
+    /// Check the server is reachable and find out which cluster it is
+    pub fn check_server(&mut self) {
+        self.server_check = ServerCheck::Checking;
+        self.send(ApiRequest::CheckServer);
+    }
+
+    /// Request health status for the selected instance
+    pub fn request_health_status(&mut self) {
+        // Extract http_address first to avoid borrow issues
+        let http_address = self.get_selected_instance().map(|i| i.http_address.clone());
+
+        if let Some(addr) = http_address {
+            self.request_health_for(addr);
+        }
+    }
+
+    fn request_health_for(&mut self, http_address: String) {
+        self.cancel_group_health();
+        if http_address.is_empty() {
+            self.health_error = Some("Instance has no HTTP address".to_string());
+            self.show_health = true;
+            return;
+        }
+        self.health_loading = true;
+        self.health_status = None;
+        self.health_error = None;
+        self.show_health = true;
+        self.send(ApiRequest::GetHealthStatus { http_address });
+    }
+
+    /// Probe the health of every instance of the selected tier or
+    /// replicaset at once. False if the selection is neither.
+    pub fn request_group_health(&mut self) -> bool {
+        let Some(item) = self
+            .selected_node_key()
+            .and_then(|key| self.find_tree_item(&key))
+        else {
+            return false;
+        };
+        let (title, replicasets) = match item {
+            TreeItem::Tier(t) => {
+                let tier = &self.tiers[t];
+                (format!("tier {}", tier.name), &tier.replicasets[..])
+            }
+            TreeItem::Replicaset(t, r) => {
+                let rs = &self.tiers[t].replicasets[r];
+                (format!("replicaset {}", rs.name), std::slice::from_ref(rs))
+            }
+            TreeItem::Instance(..) => return false,
+        };
+        let instances = replicasets
+            .iter()
+            .flat_map(|rs| &rs.instances)
+            .filter(|inst| !inst.http_address.is_empty())
+            .map(|inst| (inst.name.clone(), inst.http_address.clone()))
+            .collect();
+        self.probe_group_health(title, instances);
+        true
+    }
+
+    /// Probe the instances of the group in the popup again
+    pub fn refresh_group_health(&mut self) {
+        if let Some(group) = self.group_health.take() {
+            self.probe_group_health(group.title, group.instances);
+        }
+    }
+
+    fn probe_group_health(&mut self, title: String, instances: Vec<(String, String)>) {
+        self.cancel_group_health();
+        self.show_health = true;
+        self.health_status = None;
+        if instances.is_empty() {
+            self.health_error = Some(format!("No instance of {} has an HTTP address", title));
+            return;
+        }
+        self.health_error = None;
+        self.health_loading = true;
+        self.last_probe_id += 1;
+        self.send(ApiRequest::ProbeHealth {
+            id: self.last_probe_id,
+            addresses: instances.iter().map(|(_, addr)| addr.clone()).collect(),
+        });
+        self.group_health = Some(GroupHealth {
+            id: self.last_probe_id,
+            title,
+            instances,
+            results: None,
+        });
+    }
+
+    /// Forget the group in the health popup, stopping the probes still to
+    /// be made
+    pub fn cancel_group_health(&mut self) {
+        if let Some(group) = self.group_health.take() {
+            if group.results.is_none() {
+                self.send(ApiRequest::CancelFanOut);
+                self.health_loading = false;
+            }
+        }
+    }
+
+    /// Open the tables popup and (re)load the table list
+    pub fn request_tables(&mut self) {
+        self.show_tables = true;
+        self.tables_loading = true;
+        self.tables_error = None;
+        self.send(ApiRequest::GetTables);
+    }
+
+    /// Open the configuration parameters popup and (re)load the parameters
+    pub fn request_parameters(&mut self) {
+        self.show_parameters = true;
+        self.parameters_loading = true;
+        self.parameters_error = None;
+        self.send(ApiRequest::GetParameters);
+    }
+
+    /// Parameters matching the search, in display order
+    pub fn visible_parameters(&self) -> Vec<&ConfigParameter> {
+        self.parameters
+            .as_deref()
+            .map(|params| parameters::matching(params, &self.parameters_search))
+            .unwrap_or_default()
+    }
+
+    /// Narrow the parameters to a new search, from the top
+    pub fn set_parameters_search(&mut self, search: String) {
+        self.parameters_search = search;
+        self.parameters_scroll = 0;
+    }
+
+    /// Record a user action in the audit log
+    pub fn audit(&mut self, action: &str, detail: &str) {
+        let Some(ref path) = self.audit_log_path else {
+            return;
+        };
+        if let Err(e) = audit::append(
+            path,
+            &self.base_url,
+            self.current_user.as_deref(),
+            action,
+            detail,
+        ) {
+            self.status_message = Some(format!("{:#}", e));
+        }
+    }
+
+    /// Logout, clear saved tokens, and exit
+    pub fn logout(&mut self) {
+        self.audit("logout", "");
+        // Delete tokens directly (don't rely on worker thread)
+        let _ = tokens::delete_tokens(&self.base_url);
+        self.running = false;
+    }
+
+    /// Process any pending API responses (non-blocking)
+    pub fn process_responses(&mut self) {
+        use std::sync::mpsc::TryRecvError;
+
+        let mut handled = 0;
+        loop {
+            match self.source.try_recv() {
+                Ok(message) => {
+                    handled += 1;
+                    // Unasked: not an answer to a request in flight
+                    if !matches!(
+                        message.response,
+                        ApiResponse::Stream(_) | ApiResponse::WorkerPanic(_)
+                    ) {
+                        self.frame_stats.in_flight = self.frame_stats.in_flight.saturating_sub(1);
+                    }
+                    let before = (self.last_error.clone(), self.connection_status);
+                    let unchanged = matches!(message.response, ApiResponse::NotModified(_));
+                    self.timings.record(&message.response, &message.meta);
+                    self.record_meta(message.meta);
+                    self.handle_response(message.response);
+                    self.toast_new_error();
+                    // Nothing on screen changes for unchanged data when the
+                    // header doesn't show latency
+                    let quiet = self.slow_link
+                        && unchanged
+                        && before == (self.last_error.clone(), self.connection_status);
+                    if !quiet {
+                        self.dirty = true;
+                    }
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    // Replace a worker that died, unless the last
+                    // replacement died just as quickly
+                    let respawn_due = self
+                        .last_respawn
+                        .is_none_or(|at| at.elapsed() >= WORKER_RESPAWN_COOLDOWN);
+                    if respawn_due && self.reconnect() {
+                        self.set_error("API worker disconnected, restarted it".to_string());
+                        self.toast(ToastKind::Info, tr("API worker restarted"));
+                        self.dirty = true;
+                        break;
+                    }
+                    self.dirty = self.connection_status != ConnectionStatus::Down;
+                    self.worker_stopped = true;
+                    // Found again on every pass; logged once
+                    let error = if self.respawn_source.is_some() {
+                        "API worker disconnected, R to restart it"
+                    } else {
+                        "API worker disconnected"
+                    };
+                    if self.last_error.as_deref() != Some(error) {
+                        self.set_error(error.to_string());
+                    }
+                    self.connection_status = ConnectionStatus::Down;
+                    break;
+                }
+            }
+        }
+        self.frame_stats.record_responses(handled);
+    }
+
+    /// Inputs the cached Tiers view lines must have been rendered from
+    pub fn tree_line_inputs(&self) -> TreeLineInputs {
+        TreeLineInputs {
+            generation: self.data_generation,
+            search: self.search_text.clone(),
+            pinned: self.pinned.clone(),
+            marked: self.marked.clone(),
+            theme: self.theme.clone(),
+            number_format: self.number_format,
+            server_version: self.server_version,
+        }
+    }
+
+    /// Whether the row is shown expanded (instances never are)
+    pub fn is_expanded(&self, item: TreeItem) -> bool {
+        match item {
+            TreeItem::Tier(t) => self.expanded_tiers.contains(&t),
+            TreeItem::Replicaset(t, r) => self.expanded_replicasets.contains(&(t, r)),
+            TreeItem::Instance(..) => false,
+        }
+    }
+
+    /// Whether the connected server provides an optional feature.
+    /// Assumed available until the server version is known.
+    pub fn supports(&self, capability: Capability) -> bool {
+        self.server_version
+            .is_none_or(|version| version >= capability.since())
+    }
+
+    /// Warning shown when the server API is outside the range picotui understands
+    pub fn compatibility_warning(&self) -> Option<String> {
+        self.server_version.and_then(compat::warning)
+    }
+
+    /// Whether the server pushes updates, making periodic polling unnecessary
+    pub fn is_streaming(&self) -> bool {
+        self.stream_state == StreamState::Live
+    }
+
+    /// Open the event stream if enabled and not already connected or known
+    /// to be unavailable
+    fn maybe_start_stream(&mut self) {
+        if self.stream_state != StreamState::Idle {
+            return;
+        }
+        if let Some(path) = self.stream_path.clone() {
+            self.stream_state = StreamState::Connecting;
+            self.send(ApiRequest::StartStream { path });
+        }
+    }
+
+    /// Whether API responses are expected soon
+    pub fn is_busy(&self) -> bool {
+        self.loading
+            || self.health_loading
+            || self.tables_loading
+            || self.parameters_loading
+            || self.filter_settle_at.is_some()
+            || self.server_check == ServerCheck::Checking
+            || self.login_reveal_until.is_some()
+            || self.flash_until.is_some()
+            || !self.toasts.is_empty()
+    }
+
+    /// Update connection health from response timing metadata
+    fn record_meta(&mut self, meta: ResponseMeta) {
+        // Pushed stream updates carry no round-trip time
+        if !meta.latency.is_zero() {
+            self.last_latency = Some(meta.latency);
+        }
+        if meta.reachable {
+            self.connection_failures = 0;
+            self.unreachable_refreshes = 0;
+            self.connection_status = ConnectionStatus::Connected;
+        } else {
+            self.connection_failures += 1;
+            self.connection_status = if self.connection_failures >= CONNECTION_DOWN_THRESHOLD {
+                ConnectionStatus::Down
+            } else {
+                ConnectionStatus::Reconnecting
+            };
+        }
+    }
+
+    fn handle_response(&mut self, response: ApiResponse) {
+        match response {
+            ApiResponse::Config(result) => {
+                self.loading = false;
+                match result {
+                    Ok(config) => {
+                        self.auth_enabled = config.is_auth_enabled;
                         if self.auth_enabled {
                             if self.has_saved_token {
                                 // Try using saved token - fetch data directly
                                 // If it fails with 401, we'll show login
+                                self.input_mode = InputMode::Normal;
                                 self.request_refresh();
-                                self.pending_init = false;
                             } else {
+                                // Keys meant for the cluster view aren't
+                                // for the login form
+                                self.queued_keys.clear();
                                 self.input_mode = InputMode::Login;
-                                self.pending_init = false;
+                                self.check_server();
                             }
                         } else {
                             // No auth needed, request data
+                            self.input_mode = InputMode::Normal;
                             self.request_refresh();
-                            self.pending_init = false;
                         }
                     }
-                    Err(e) => {
-                        self.last_error = Some(format!("Failed to connect: {}", e));
-                        self.pending_init = false;
-                    }
+                    // Stay on the connecting screen to retry or edit the URL
+                    Err(e) => self.set_error(format!("Failed to connect: {}", e)),
                 }
             }
 
             ApiResponse::Login(result) => {
                 self.loading = false;
                 match result {
-                    Ok(_) => {
+                    Ok(tokens) => {
+                        self.session = Some(Session {
+                            auth: tokens.auth,
+                            refresh: tokens.refresh,
+                            saved: self.login_remember_me,
+                        });
                         self.input_mode = InputMode::Normal;
+                        self.current_user = Some(self.login_username.clone());
+                        self.audit("login", "ok");
                         self.login_password.clear();
+                        self.login_failures = 0;
+                        self.login_retry_at = None;
+                        if let Some(ref path) = self.usernames_path {
+                            if let Err(e) =
+                                usernames::save_username(path, &self.base_url, &self.login_username)
+                            {
+                                self.status_message =
+                                    Some(format!("Could not save username: {}", e));
+                            }
+                        }
                         self.request_refresh();
                     }
                     Err(e) => {
+                        self.audit(
+                            "login",
+                            &format!("failed as {}: {}", self.login_username, e),
+                        );
+                        // Back off after repeated wrong credentials rather
+                        // than hammering the auth endpoint
+                        if e == INVALID_CREDENTIALS {
+                            self.login_failures += 1;
+                            if let Some(extra) =
+                                self.login_failures.checked_sub(LOGIN_THROTTLE_AFTER)
+                            {
+                                let delay = LOGIN_THROTTLE_BASE
+                                    .saturating_mul(1 << extra.min(8))
+                                    .min(LOGIN_THROTTLE_MAX);
+                                self.login_retry_at = Some(Instant::now() + delay);
+                            }
+                        }
                         self.login_error = Some(e);
                     }
                 }
             }
 
+            ApiResponse::ServerCheck(result) => {
+                self.server_check = match result {
+                    Ok(identity) => ServerCheck::Reachable(identity),
+                    Err(e) => ServerCheck::Unreachable(e),
+                };
+            }
+
             ApiResponse::ClusterInfo(result) => {
                 match result {
                     Ok(info) => {
+                        self.server_version =
+                            PicodataVersion::parse(&info.current_instance_version);
                         self.cluster_info = Some(info);
                         self.last_error = None;
+                        self.finish_request(Endpoint::ClusterInfo, true);
                     }
                     Err(e) => {
                         // Check if this is an auth error (401) with saved token
@@ -341,7 +1756,8 @@ impl App {
                         {
                             // Saved token is invalid, need to re-login
                             self.has_saved_token = false;
-                            self.loading = false;
+                            self.session = None;
+                            self.cancel_refresh();
                             self.input_mode = InputMode::Login;
                             self.login_error =
                                 Some("Session expired, please login again".to_string());
@@ -349,18 +1765,27 @@ impl App {
                             let _ = tokens::delete_tokens(&self.base_url);
                             return;
                         }
-                        self.last_error = Some(format!("Cluster: {}", e));
+                        self.set_error(format!("Cluster: {}", e));
+                        // Error will be shown in status bar
+                        self.finish_request(Endpoint::ClusterInfo, false);
                     }
                 }
-                // Mark loading complete - error will be shown in status bar
-                self.loading = false;
+            }
+
+            ApiResponse::PreparedTiers(prepared) => {
+                self.last_updated = Some(format::unix_now());
+                self.set_prepared_tiers(*prepared);
+                self.maybe_start_stream();
+                self.finish_request(Endpoint::Tiers, true);
             }
 
             ApiResponse::Tiers(result) => {
                 match result {
                     Ok(tiers) => {
-                        self.tiers = tiers;
-                        self.rebuild_tree();
+                        self.last_updated = Some(format::unix_now());
+                        self.set_tiers(tiers);
+                        self.maybe_start_stream();
+                        self.finish_request(Endpoint::Tiers, true);
                     }
                     Err(e) => {
                         // Check if this is an auth error (401)
@@ -369,7 +1794,8 @@ impl App {
                         {
                             // Saved token is invalid, need to re-login
                             self.has_saved_token = false;
-                            self.loading = false;
+                            self.session = None;
+                            self.cancel_refresh();
                             self.input_mode = InputMode::Login;
                             self.login_error =
                                 Some("Session expired, please login again".to_string());
@@ -377,13 +1803,87 @@ impl App {
                             let _ = tokens::delete_tokens(&self.base_url);
                             return;
                         }
+                        let error = match self.compatibility_warning() {
+                            // Parse failures against an unknown API are expected;
+                            // point at the likely cause instead
+                            Some(warning) => format!("Tiers: {} ({})", e, warning),
+                            None => format!("Tiers: {}", e),
+                        };
+                        // The status bar keeps the cluster error, which usually
+                        // explains this one; the log gets both
                         if self.last_error.is_none() {
-                            self.last_error = Some(format!("Tiers: {}", e));
+                            self.set_error(error);
+                        } else {
+                            self.error_log.record(&error, format::unix_now());
                         }
+                        // Error will be shown in status bar
+                        self.finish_request(Endpoint::Tiers, false);
                     }
                 }
-                // Mark loading complete - error will be shown in status bar
-                self.loading = false;
+            }
+
+            ApiResponse::NotModified(endpoint) => {
+                // Data is unchanged, nothing to re-parse or rebuild
+                match endpoint {
+                    Endpoint::ClusterInfo => self.last_error = None,
+                    Endpoint::Tiers => {
+                        self.last_updated = Some(format::unix_now());
+                        // Still a refresh for rules that must hold for several
+                        if self.evaluate_alerts() {
+                            self.get_attention();
+                        }
+                    }
+                }
+                self.finish_request(endpoint, true);
+            }
+
+            ApiResponse::Stream(event) => {
+                self.stream_state = match event {
+                    StreamEvent::Connected => StreamState::Live,
+                    StreamEvent::Unavailable(_) => StreamState::Unavailable,
+                    // Polling resumes; reconnect after the next successful poll
+                    StreamEvent::Disconnected(_) => StreamState::Idle,
+                };
+            }
+
+            ApiResponse::Tables(result) => {
+                self.tables_loading = false;
+                match result {
+                    Ok(tables) => {
+                        self.tables_scroll = self.tables_scroll.min(tables.len().saturating_sub(1));
+                        self.tables = Some(tables);
+                    }
+                    Err(e) => self.tables_error = Some(e),
+                }
+            }
+
+            ApiResponse::Parameters(result) => {
+                self.parameters_loading = false;
+                match result {
+                    Ok(params) => {
+                        self.parameters = Some(params);
+                        self.parameters_scroll = self
+                            .parameters_scroll
+                            .min(self.visible_parameters().len().saturating_sub(1));
+                    }
+                    Err(e) => self.parameters_error = Some(e),
+                }
+            }
+
+            // The request is answered separately, with this as its error
+            ApiResponse::WorkerPanic(panic) => self.set_error(panic.to_string()),
+
+            ApiResponse::HealthProbes(probes) => {
+                // Answers of probes cancelled or replaced since are stale
+                let Some(group) = self
+                    .group_health
+                    .as_mut()
+                    .filter(|group| group.id == probes.id && !probes.cancelled)
+                else {
+                    return;
+                };
+                group.results = Some(probes.results);
+                self.health_loading = false;
             }
 
             ApiResponse::HealthStatus(result) => {
@@ -401,309 +1901,2143 @@ impl App {
         }
     }
 
-    pub fn rebuild_tree(&mut self) {
-        self.tree_items.clear();
+    /// Replace cluster data, keeping expansion state and the selected node
+    /// attached to the same logical tier/replicaset/instance (by name)
+    pub fn set_tiers(&mut self, tiers: Vec<TierInfo>) {
+        let prepared = prepare::prepare(&self.bucket_counts, tiers);
+        self.set_prepared_tiers(prepared);
+    }
+
+    /// Same as `set_tiers` for data already prepared off the UI thread
+    pub fn set_prepared_tiers(&mut self, prepared: PreparedTiers) {
+        let selected = self.selected_node_key();
+        let detail_before = self.show_detail.then(|| self.detail_instance().cloned());
+        // The first snapshot is not news
+        let offline_before = (!self.tiers.is_empty()).then(|| offline_instances(&self.tiers));
+        let shape_before = self.tree_shape();
+        let expanded_tiers: HashSet<String> = self
+            .expanded_tiers
+            .iter()
+            .filter_map(|&t| self.tiers.get(t).map(|tier| tier.name.clone()))
+            .collect();
+        let expanded_replicasets: HashSet<(String, String)> = self
+            .expanded_replicasets
+            .iter()
+            .filter_map(|&(t, r)| {
+                let tier = self.tiers.get(t)?;
+                let rs = tier.replicasets.get(r)?;
+                Some((tier.name.clone(), rs.name.clone()))
+            })
+            .collect();
 
+        self.rebalancing_tiers = prepared.rebalancing;
+        self.cluster_health = Some(prepared.health);
+        self.bucket_counts = prepared.bucket_counts;
+        self.tiers.replace(prepared.tiers);
+        self.refresh_snapshot_diff();
+        let alerts_fired = self.evaluate_alerts();
+        let offline_now = offline_instances(&self.tiers);
+        let mut newly_offline = false;
+        if let Some(before) = offline_before {
+            newly_offline = self.toast_state_changes(&before, &offline_now);
+        }
+        if newly_offline || alerts_fired {
+            self.get_attention();
+        }
+
+        self.expanded_tiers.clear();
+        self.expanded_replicasets.clear();
         for (tier_idx, tier) in self.tiers.iter().enumerate() {
-            self.tree_items.push(TreeItem::Tier(tier_idx));
+            if expanded_tiers.contains(&tier.name) {
+                self.expanded_tiers.insert(tier_idx);
+            }
+            for (rs_idx, rs) in tier.replicasets.iter().enumerate() {
+                if expanded_replicasets.contains(&(tier.name.clone(), rs.name.clone())) {
+                    self.expanded_replicasets.insert((tier_idx, rs_idx));
+                }
+            }
+        }
+        if self.expand_problems {
+            self.expand_problem_nodes();
+        }
 
-            if self.expanded_tiers.contains(&tier_idx) {
-                for (rs_idx, replicaset) in tier.replicasets.iter().enumerate() {
-                    self.tree_items.push(TreeItem::Replicaset(tier_idx, rs_idx));
+        // The rows are indices, so the same shape means the same rows
+        if self.tree_shape() == shape_before {
+            self.rows_changed();
+        } else {
+            self.rebuild_tree();
+        }
 
-                    if self.expanded_replicasets.contains(&(tier_idx, rs_idx)) {
-                        for inst_idx in 0..replicaset.instances.len() {
-                            self.tree_items
-                                .push(TreeItem::Instance(tier_idx, rs_idx, inst_idx));
-                        }
-                    }
+        if let Some(key) = selected {
+            self.select_node_key(&key);
+        }
+
+        if let Some(Some(before)) = detail_before {
+            match self.detail_instance() {
+                Some(after) if *after != before => self.detail_updated_at = Some(Instant::now()),
+                Some(_) => {}
+                None => {
+                    self.show_detail = false;
+                    self.status_message = Some(trf("{} left the cluster", &[&before.name]));
                 }
             }
         }
+    }
 
-        // Clamp selection
-        if !self.tree_items.is_empty() && self.selected_index >= self.tree_items.len() {
-            self.selected_index = self.tree_items.len() - 1;
+    /// Check the alert rules against the new tiers, running the commands of
+    /// the ones that start firing. True if any did.
+    fn evaluate_alerts(&mut self) -> bool {
+        let cluster = self
+            .cluster_info
+            .as_ref()
+            .map(|c| c.cluster_name.clone())
+            .unwrap_or_default();
+        let fired = self.alerts.evaluate(&self.tiers, format::unix_now());
+        for &(rule, value) in &fired {
+            let message = match alerts::run_hook(rule, value, &cluster) {
+                Ok(()) => trf("Alert: {}", &[&rule.name]),
+                Err(e) => trf("Alert: {} (command failed: {})", &[&rule.name, &e]),
+            };
+            self.toasts.push(ToastKind::Error, message, Instant::now());
         }
-        self.list_state.select(Some(self.selected_index));
+        !fired.is_empty()
     }
 
-    /// Reset selection to first item and sync list state
-    pub fn reset_selection(&mut self) {
-        self.selected_index = 0;
-        self.list_state.select(Some(0));
+    /// Toast the instances that went Offline or came back Online since the
+    /// last refresh. True if any went Offline.
+    fn toast_state_changes(&mut self, before: &HashSet<String>, now: &HashSet<String>) -> bool {
+        let mut went_offline: Vec<&str> = now
+            .iter()
+            .filter(|name| !before.contains(*name))
+            .map(String::as_str)
+            .collect();
+        // Instances that left the cluster are not back
+        let online = online_instances(&self.tiers);
+        let mut back_online: Vec<&str> = before
+            .iter()
+            .filter(|name| online.contains(*name))
+            .map(String::as_str)
+            .collect();
+        went_offline.sort_unstable();
+        back_online.sort_unstable();
+
+        let toasts = [
+            (
+                ToastKind::Success,
+                back_online,
+                "{} back Online",
+                "{} instances back Online",
+            ),
+            (
+                ToastKind::Error,
+                went_offline,
+                "{} went Offline",
+                "{} instances went Offline",
+            ),
+        ];
+        let mut any_offline = false;
+        for (kind, names, some, many) in toasts {
+            if names.is_empty() {
+                continue;
+            }
+            any_offline |= kind == ToastKind::Error;
+            let message = match names.as_slice() {
+                names if names.len() <= MAX_TOASTED_NAMES => trf(some, &[&names.join(", ")]),
+                names => trf(many, &[&names.len()]),
+            };
+            self.toast(kind, message);
+        }
+        any_offline
     }
 
-    pub fn select_next(&mut self) {
-        let count = self.get_item_count();
-        if count > 0 {
-            self.selected_index = (self.selected_index + 1) % count;
-            self.list_state.select(Some(self.selected_index));
+    /// Show a notification in the top right corner for a few seconds
+    pub fn toast(&mut self, kind: ToastKind, message: impl Into<String>) {
+        self.toasts.push(kind, message, Instant::now());
+    }
+
+    /// Show an error in the status bar and add it to the error log
+    fn set_error(&mut self, error: String) {
+        self.error_log.record(&error, format::unix_now());
+        self.last_error = Some(error);
+    }
+
+    /// Toast an error the status bar just started showing. The same error
+    /// is toasted again only after a refresh has succeeded in between.
+    fn toast_new_error(&mut self) {
+        match &self.last_error {
+            Some(error) if self.toasted_error.as_ref() != Some(error) => {
+                let error = error.clone();
+                self.toast(ToastKind::Error, error.clone());
+                self.toasted_error = Some(error);
+            }
+            None if !self.loading => self.toasted_error = None,
+            _ => {}
         }
     }
 
-    pub fn select_previous(&mut self) {
-        let count = self.get_item_count();
-        if count > 0 {
-            self.selected_index = if self.selected_index == 0 {
-                count - 1
+    /// Drop the toasts that have been shown long enough. True if the screen
+    /// needs redrawing.
+    pub fn settle_toasts(&mut self) -> bool {
+        self.toasts.expire(Instant::now())
+    }
+
+    /// Ring and/or flash as `--bell` asks. Where flashes are off (see
+    /// `animate`) the bell rings instead.
+    fn get_attention(&mut self) {
+        let Some(bell) = self.bell else {
+            return;
+        };
+        if bell.flashes() && self.animate() {
+            self.flash_until = Some(Instant::now() + SCREEN_FLASH);
+        }
+        self.pending_bell |= bell.rings() || !self.animate();
+    }
+
+    /// Whether the screen is inverted for `--bell flash`
+    pub fn is_flashing(&self) -> bool {
+        self.flash_until.is_some()
+    }
+
+    /// End the flash once it has been shown long enough. True if the
+    /// screen needs redrawing.
+    pub fn settle_flash(&mut self) -> bool {
+        if self.flash_until.is_some_and(|at| Instant::now() >= at) {
+            self.flash_until = None;
+            return true;
+        }
+        false
+    }
+
+    /// Turn automatic expansion of unhealthy nodes on or off
+    pub fn toggle_expand_problems(&mut self) {
+        self.expand_problems = !self.expand_problems;
+        if self.expand_problems {
+            let selected = self.selected_node_key();
+            self.expand_problem_nodes();
+            self.rebuild_tree();
+            if let Some(key) = selected {
+                self.select_node_key(&key);
+            }
+        }
+        self.status_message = Some(
+            tr(if self.expand_problems {
+                "Expand problems: on"
             } else {
-                self.selected_index - 1
-            };
-            self.list_state.select(Some(self.selected_index));
+                "Expand problems: off"
+            })
+            .to_string(),
+        );
+    }
+
+    /// Expand every tier and replicaset holding an instance that is not
+    /// Online or is moving to another state. Nothing is collapsed.
+    fn expand_problem_nodes(&mut self) {
+        for (tier_idx, tier) in self.tiers.iter().enumerate() {
+            for (rs_idx, rs) in tier.replicasets.iter().enumerate() {
+                if rs.instances.iter().any(is_troubled) {
+                    self.expanded_tiers.insert(tier_idx);
+                    self.expanded_replicasets.insert((tier_idx, rs_idx));
+                }
+            }
         }
     }
 
-    /// Jump to first item (gg in Vim)
-    pub fn select_first(&mut self) {
-        self.selected_index = 0;
-        self.list_state.select(Some(0));
+    /// Whether buckets of the named tier are being rebalanced
+    pub fn is_rebalancing(&self, tier_name: &str) -> bool {
+        self.rebalancing_tiers.contains(tier_name)
+    }
+
+    /// Logical identity of the currently selected row
+    fn selected_node_key(&self) -> Option<NodeKey> {
+        match self.view_mode {
+            ViewMode::Tiers => {
+                let item = self.tree_items.get(self.selected_index)?;
+                self.node_key(item)
+            }
+            ViewMode::Replicasets => {
+                let (tier, rs) = self
+                    .tiers
+                    .iter()
+                    .flat_map(|t| t.replicasets.iter().map(move |rs| (t, rs)))
+                    .nth(self.selected_index)?;
+                Some(NodeKey::Replicaset(tier.name.clone(), rs.name.clone()))
+            }
+            ViewMode::Instances => {
+                let (tier, rs, inst) = *self.get_sorted_instances().get(self.selected_index)?;
+                Some(NodeKey::Instance(
+                    tier.to_string(),
+                    rs.to_string(),
+                    inst.name.clone(),
+                ))
+            }
+        }
+    }
+
+    /// Tree node for `key`, whether or not its parents are expanded
+    fn find_tree_item(&self, key: &NodeKey) -> Option<TreeItem> {
+        let tier_idx = |name: &str| self.tiers.iter().position(|t| t.name == name);
+        let rs_idx = |t: usize, name: &str| {
+            self.tiers[t]
+                .replicasets
+                .iter()
+                .position(|rs| rs.name == name)
+        };
+        Some(match key {
+            NodeKey::Tier(tier) => TreeItem::Tier(tier_idx(tier)?),
+            NodeKey::Replicaset(tier, rs) => {
+                let t = tier_idx(tier)?;
+                TreeItem::Replicaset(t, rs_idx(t, rs)?)
+            }
+            NodeKey::Instance(tier, rs, inst) => {
+                let t = tier_idx(tier)?;
+                let r = rs_idx(t, rs)?;
+                let i = self.tiers[t].replicasets[r]
+                    .instances
+                    .iter()
+                    .position(|i| &i.name == inst)?;
+                TreeItem::Instance(t, r, i)
+            }
+        })
+    }
+
+    /// Bookmark the selected row under `letter`
+    pub fn set_bookmark(&mut self, letter: char) {
+        let Some(key) = self.selected_node_key() else {
+            return;
+        };
+        let path = key.path();
+        let name = path.last().cloned().unwrap_or_default();
+        self.bookmarks.insert(letter, path);
+        if let Some(ref path) = self.bookmarks_path {
+            if let Err(e) = bookmarks::save_bookmarks(path, &self.base_url, &self.bookmarks) {
+                self.status_message = Some(trf("Could not save bookmarks: {}", &[&e]));
+                return;
+            }
+        }
+        self.status_message = Some(trf("Bookmark {}: {}", &[&letter, &name]));
+    }
+
+    /// Select the node bookmarked under `letter` in the Tiers view,
+    /// expanding its parents
+    pub fn jump_to_bookmark(&mut self, letter: char) {
+        let Some(key) = self
+            .bookmarks
+            .get(&letter)
+            .and_then(|path| NodeKey::from_path(path))
+        else {
+            self.status_message = Some(trf("No bookmark {}", &[&letter]));
+            return;
+        };
+        let name = key.path().pop().unwrap_or_default();
+        let Some(item) = self.find_tree_item(&key) else {
+            self.status_message = Some(trf("Bookmark {}: {} is gone", &[&letter, &name]));
+            return;
+        };
+        self.view_mode = ViewMode::Tiers;
+        self.reveal(SearchTarget::Node(item));
+        self.status_message = Some(trf("Bookmark {}: {}", &[&letter, &name]));
+    }
+
+    /// Open the heatmap with the cursor on the selected instance
+    pub fn open_heatmap(&mut self) {
+        self.heatmap_instance = self.get_selected_instance().map(|inst| inst.name.clone());
+        self.show_heatmap = true;
+    }
+
+    /// Index into `cells` of the heatmap cursor, the first cell when its
+    /// instance is gone
+    pub fn heatmap_cursor(&self, cells: &[heatmap::Cell]) -> usize {
+        self.heatmap_instance
+            .as_ref()
+            .and_then(|name| {
+                cells
+                    .iter()
+                    .position(|&cell| self.heatmap_name(cell) == Some(name))
+            })
+            .unwrap_or(0)
+    }
+
+    /// Move the heatmap cursor `delta` instances along
+    pub fn move_heatmap_cursor(&mut self, delta: isize) {
+        let cells = heatmap::cells(&self.tiers);
+        let index = heatmap::step(&cells, self.heatmap_cursor(&cells), delta);
+        self.set_heatmap_cursor(&cells, index);
+    }
+
+    /// Move the heatmap cursor to the next or previous replicaset
+    pub fn move_heatmap_replicaset(&mut self, forward: bool) {
+        let cells = heatmap::cells(&self.tiers);
+        let index = heatmap::next_replicaset(&cells, self.heatmap_cursor(&cells), forward);
+        self.set_heatmap_cursor(&cells, index);
+    }
+
+    fn set_heatmap_cursor(&mut self, cells: &[heatmap::Cell], index: usize) {
+        if let Some(&cell) = cells.get(index) {
+            self.heatmap_instance = self.heatmap_name(cell).cloned();
+        }
+    }
+
+    fn heatmap_name(&self, (t, r, i): heatmap::Cell) -> Option<&String> {
+        Some(
+            &self
+                .tiers
+                .get(t)?
+                .replicasets
+                .get(r)?
+                .instances
+                .get(i)?
+                .name,
+        )
+    }
+
+    /// Close the heatmap and select its cursor's instance in the Tiers view
+    pub fn jump_to_heatmap_cell(&mut self) {
+        let cells = heatmap::cells(&self.tiers);
+        let Some(&(t, r, i)) = cells.get(self.heatmap_cursor(&cells)) else {
+            return;
+        };
+        self.show_heatmap = false;
+        self.view_mode = ViewMode::Tiers;
+        self.reveal(SearchTarget::Node(TreeItem::Instance(t, r, i)));
+        self.status_message = self
+            .heatmap_name((t, r, i))
+            .map(|name| format!("Heatmap: {}", name));
+    }
+
+    fn node_key(&self, item: &TreeItem) -> Option<NodeKey> {
+        Some(match *item {
+            TreeItem::Tier(t) => NodeKey::Tier(self.tiers.get(t)?.name.clone()),
+            TreeItem::Replicaset(t, r) => {
+                let tier = self.tiers.get(t)?;
+                NodeKey::Replicaset(tier.name.clone(), tier.replicasets.get(r)?.name.clone())
+            }
+            TreeItem::Instance(t, r, i) => {
+                let tier = self.tiers.get(t)?;
+                let rs = tier.replicasets.get(r)?;
+                NodeKey::Instance(
+                    tier.name.clone(),
+                    rs.name.clone(),
+                    rs.instances.get(i)?.name.clone(),
+                )
+            }
+        })
+    }
+
+    /// Move the selection to the row for `key`, falling back to its closest
+    /// ancestor that is still visible. Leaves the selection alone otherwise.
+    fn select_node_key(&mut self, key: &NodeKey) {
+        if self.selected_node_key().as_ref() == Some(key) {
+            return;
+        }
+        let mut candidate = Some(key.clone());
+        while let Some(key) = candidate {
+            let position = match self.view_mode {
+                ViewMode::Tiers => self
+                    .tree_items
+                    .iter()
+                    .position(|item| self.node_key(item).as_ref() == Some(&key)),
+                ViewMode::Replicasets => self
+                    .tiers
+                    .iter()
+                    .flat_map(|t| t.replicasets.iter().map(move |rs| (t, rs)))
+                    .position(|(t, rs)| {
+                        key == NodeKey::Replicaset(t.name.clone(), rs.name.clone())
+                    }),
+                ViewMode::Instances => {
+                    self.get_sorted_instances()
+                        .iter()
+                        .position(|(tier, rs, inst)| {
+                            key == NodeKey::Instance(
+                                tier.to_string(),
+                                rs.to_string(),
+                                inst.name.clone(),
+                            )
+                        })
+                }
+            };
+            if let Some(index) = position {
+                self.selected_index = index;
+                self.list_state.select(Some(index));
+                return;
+            }
+            candidate = key.parent();
+        }
+    }
+
+    pub fn rebuild_tree(&mut self) {
+        self.tree_items.clear();
+        self.rows_changed();
+
+        for (tier_idx, tier) in self.tiers.iter().enumerate() {
+            self.tree_items.push(TreeItem::Tier(tier_idx));
+
+            if self.expanded_tiers.contains(&tier_idx) {
+                for (rs_idx, replicaset) in tier.replicasets.iter().enumerate() {
+                    self.tree_items.push(TreeItem::Replicaset(tier_idx, rs_idx));
+
+                    if self.expanded_replicasets.contains(&(tier_idx, rs_idx)) {
+                        for inst_idx in 0..replicaset.instances.len() {
+                            self.tree_items
+                                .push(TreeItem::Instance(tier_idx, rs_idx, inst_idx));
+                        }
+                    }
+                }
+            }
+        }
+
+        // Clamp selection
+        if !self.tree_items.is_empty() && self.selected_index >= self.tree_items.len() {
+            self.selected_index = self.tree_items.len() - 1;
+        }
+        self.list_state.select(Some(self.selected_index));
+    }
+
+    /// The data behind the rows changed: drop what was derived from it
+    fn rows_changed(&mut self) {
+        self.instance_cache.replace(None);
+        self.data_generation += 1;
+    }
+
+    /// What `tree_items` is built from, without the instances: for each
+    /// tier its replicaset count if expanded, each followed by its instance
+    /// count if expanded. Equal shapes give equal `tree_items`.
+    fn tree_shape(&self) -> Vec<usize> {
+        let mut shape = Vec::with_capacity(self.tiers.len());
+        for (tier_idx, tier) in self.tiers.iter().enumerate() {
+            if !self.expanded_tiers.contains(&tier_idx) {
+                shape.push(0);
+                continue;
+            }
+            shape.push(tier.replicasets.len());
+            shape.extend(tier.replicasets.iter().enumerate().map(|(rs_idx, rs)| {
+                if self.expanded_replicasets.contains(&(tier_idx, rs_idx)) {
+                    rs.instances.len()
+                } else {
+                    0
+                }
+            }));
+        }
+        shape
+    }
+
+    /// The filter text was edited. Large clusters are re-filtered once
+    /// typing pauses (see `settle_filter`), smaller ones right away.
+    pub fn filter_edited(&mut self) {
+        let instances: usize = self.tiers.iter().map(|t| t.instance_count).sum();
+        if instances >= FILTER_DEBOUNCE_THRESHOLD {
+            self.filter_settle_at = Some(Instant::now() + FILTER_DEBOUNCE);
+        }
+        self.reset_selection();
+    }
+
+    /// Apply a debounced filter edit once typing has paused.
+    /// Returns true if the view needs redrawing.
+    pub fn settle_filter(&mut self) -> bool {
+        if self.filter_settle_at.is_some_and(|at| Instant::now() >= at) {
+            self.filter_settle_at = None;
+            self.reset_selection();
+            return true;
+        }
+        false
+    }
+
+    /// Reset selection to first item and sync list state
+    pub fn reset_selection(&mut self) {
+        self.selected_index = 0;
+        self.list_state.select(Some(0));
+    }
+
+    pub fn select_next(&mut self) {
+        let count = self.get_item_count();
+        if count > 0 {
+            self.selected_index = (self.selected_index + 1) % count;
+            self.list_state.select(Some(self.selected_index));
+        }
+    }
+
+    pub fn select_previous(&mut self) {
+        let count = self.get_item_count();
+        if count > 0 {
+            self.selected_index = if self.selected_index == 0 {
+                count - 1
+            } else {
+                self.selected_index - 1
+            };
+            self.list_state.select(Some(self.selected_index));
+        }
+    }
+
+    /// Jump to first item (gg in Vim)
+    pub fn select_first(&mut self) {
+        self.selected_index = 0;
+        self.list_state.select(Some(0));
+    }
+
+    /// Jump to last item (G in Vim)
+    pub fn select_last(&mut self) {
+        let count = self.get_item_count();
+        if count > 0 {
+            self.selected_index = count - 1;
+            self.list_state.select(Some(self.selected_index));
+        }
+    }
+
+    /// Move half page down (Ctrl+D in Vim)
+    pub fn select_half_page_down(&mut self, visible_height: usize) {
+        let count = self.get_item_count();
+        if count > 0 {
+            let half_page = visible_height / 2;
+            self.selected_index = (self.selected_index + half_page).min(count - 1);
+            self.list_state.select(Some(self.selected_index));
+        }
+    }
+
+    /// Move half page up (Ctrl+U in Vim)
+    pub fn select_half_page_up(&mut self, visible_height: usize) {
+        let half_page = visible_height / 2;
+        self.selected_index = self.selected_index.saturating_sub(half_page);
+        self.list_state.select(Some(self.selected_index));
+    }
+
+    /// Move full page down (Ctrl+F in Vim)
+    pub fn select_page_down(&mut self, visible_height: usize) {
+        let count = self.get_item_count();
+        if count > 0 {
+            self.selected_index = (self.selected_index + visible_height).min(count - 1);
+            self.list_state.select(Some(self.selected_index));
+        }
+    }
+
+    /// Move full page up (Ctrl+B in Vim)
+    pub fn select_page_up(&mut self, visible_height: usize) {
+        self.selected_index = self.selected_index.saturating_sub(visible_height);
+        self.list_state.select(Some(self.selected_index));
+    }
+
+    pub fn expand_selected(&mut self) {
+        match self.view_mode {
+            ViewMode::Tiers => {
+                if let Some(item) = self.tree_items.get(self.selected_index).cloned() {
+                    match item {
+                        TreeItem::Tier(tier_idx) => {
+                            if self.expanded_tiers.insert(tier_idx) {
+                                self.insert_children(self.selected_index, &item);
+                            }
+                        }
+                        TreeItem::Replicaset(tier_idx, rs_idx) => {
+                            if self.expanded_replicasets.insert((tier_idx, rs_idx)) {
+                                self.insert_children(self.selected_index, &item);
+                            }
+                        }
+                        TreeItem::Instance(_, _, _) => self.open_detail(),
+                    }
+                }
+            }
+            ViewMode::Replicasets => {
+                // Could expand to show instances, but for now do nothing
+            }
+            ViewMode::Instances => self.open_detail(),
+        }
+    }
+
+    pub fn collapse_selected(&mut self) {
+        if self.view_mode != ViewMode::Tiers {
+            return;
+        }
+
+        if let Some(item) = self.tree_items.get(self.selected_index).cloned() {
+            match item {
+                TreeItem::Tier(tier_idx) => {
+                    self.expanded_tiers.remove(&tier_idx);
+                    // Also collapse all replicasets in this tier
+                    self.expanded_replicasets.retain(|(t, _)| *t != tier_idx);
+                    self.remove_children(self.selected_index);
+                }
+                TreeItem::Replicaset(tier_idx, rs_idx) => {
+                    self.expanded_replicasets.remove(&(tier_idx, rs_idx));
+                    self.remove_children(self.selected_index);
+                }
+                TreeItem::Instance(tier_idx, rs_idx, _) => {
+                    // Collapse parent replicaset and move selection onto it
+                    self.expanded_replicasets.remove(&(tier_idx, rs_idx));
+                    if let Some(parent) = self.tree_items[..self.selected_index]
+                        .iter()
+                        .rposition(|i| matches!(i, TreeItem::Replicaset(t, r) if *t == tier_idx && *r == rs_idx))
+                    {
+                        self.remove_children(parent);
+                        self.selected_index = parent;
+                        self.list_state.select(Some(parent));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Rows shown under an expanded node, honoring nested expansion
+    fn child_rows(&self, item: &TreeItem) -> Vec<TreeItem> {
+        let mut rows = Vec::new();
+        match *item {
+            TreeItem::Tier(tier_idx) => {
+                if let Some(tier) = self.tiers.get(tier_idx) {
+                    for rs_idx in 0..tier.replicasets.len() {
+                        let rs_item = TreeItem::Replicaset(tier_idx, rs_idx);
+                        if self.expanded_replicasets.contains(&(tier_idx, rs_idx)) {
+                            let nested = self.child_rows(&rs_item);
+                            rows.push(rs_item);
+                            rows.extend(nested);
+                        } else {
+                            rows.push(rs_item);
+                        }
+                    }
+                }
+            }
+            TreeItem::Replicaset(tier_idx, rs_idx) => {
+                if let Some(rs) = self
+                    .tiers
+                    .get(tier_idx)
+                    .and_then(|t| t.replicasets.get(rs_idx))
+                {
+                    rows.extend(
+                        (0..rs.instances.len())
+                            .map(|inst_idx| TreeItem::Instance(tier_idx, rs_idx, inst_idx)),
+                    );
+                }
+            }
+            TreeItem::Instance(_, _, _) => {}
+        }
+        rows
+    }
+
+    /// Splice the children of the node at `row` into the tree
+    fn insert_children(&mut self, row: usize, item: &TreeItem) {
+        let children = self.child_rows(item);
+        self.tree_items.splice(row + 1..row + 1, children);
+    }
+
+    /// Remove all descendants of the node at `row` from the tree
+    fn remove_children(&mut self, row: usize) {
+        let depth = |item: &TreeItem| match item {
+            TreeItem::Tier(_) => 0,
+            TreeItem::Replicaset(_, _) => 1,
+            TreeItem::Instance(_, _, _) => 2,
+        };
+        let parent_depth = depth(&self.tree_items[row]);
+        let end = self.tree_items[row + 1..]
+            .iter()
+            .position(|i| depth(i) <= parent_depth)
+            .map_or(self.tree_items.len(), |p| row + 1 + p);
+        self.tree_items.drain(row + 1..end);
+    }
+
+    pub fn toggle_detail(&mut self) {
+        // Only show detail if we can get an instance
+        match self.view_mode {
+            ViewMode::Tiers => {
+                // Only toggle if an instance is selected
+                if let Some(TreeItem::Instance(_, _, _)) = self.tree_items.get(self.selected_index)
+                {
+                    self.toggle_detail_popup();
+                }
+            }
+            ViewMode::Replicasets => {
+                // Can't show instance detail in replicasets view
+            }
+            ViewMode::Instances => self.toggle_detail_popup(),
+        }
+    }
+
+    fn toggle_detail_popup(&mut self) {
+        if self.show_detail {
+            self.show_detail = false;
+        } else {
+            self.open_detail();
+        }
+    }
+
+    /// Show the detail popup for the selected instance
+    fn open_detail(&mut self) {
+        self.detail_instance = self.get_selected_instance().map(|inst| inst.name.clone());
+        self.detail_updated_at = None;
+        self.show_detail = true;
+    }
+
+    /// Instance shown in the detail popup: the one it was opened for, or the
+    /// selected one when it was opened directly
+    pub fn detail_instance(&self) -> Option<&InstanceInfo> {
+        match &self.detail_instance {
+            Some(name) => self.find_instance(name),
+            None => self.get_selected_instance(),
+        }
+    }
+
+    fn find_instance(&self, name: &str) -> Option<&InstanceInfo> {
+        self.tiers
+            .iter()
+            .flat_map(|t| t.replicasets.iter())
+            .flat_map(|rs| rs.instances.iter())
+            .find(|inst| inst.name == name)
+    }
+
+    /// Open the actions menu for the selected instance
+    pub fn open_action_menu(&mut self) {
+        let Some(name) = self.get_selected_instance().map(|inst| inst.name.clone()) else {
+            return;
+        };
+        self.action_menu_instance = Some(name);
+        self.action_menu_index = 0;
+        self.show_action_menu = true;
+    }
+
+    /// Instance the actions menu was opened for, while it's still in the cluster
+    pub fn action_menu_instance(&self) -> Option<&InstanceInfo> {
+        self.action_menu_instance
+            .as_deref()
+            .and_then(|name| self.find_instance(name))
+    }
+
+    /// Actions the menu offers for its instance
+    pub fn action_menu_rows(&self) -> Vec<InstanceAction> {
+        self.action_menu_instance()
+            .map(|instance| InstanceAction::for_instance(instance, self.can_change_cluster()))
+            .unwrap_or_default()
+    }
+
+    /// Close the menu and run `action` on its instance
+    pub fn run_action(&mut self, action: InstanceAction) {
+        self.show_action_menu = false;
+        let Some(instance) = self.action_menu_instance().cloned() else {
+            self.status_message = Some(tr("Instance left the cluster").to_string());
+            return;
+        };
+        if !action.available(&instance) || (action.mutating() && !self.can_change_cluster()) {
+            return;
+        }
+        self.audit(
+            "action",
+            &format!("{} on {}", action.label(), instance.name),
+        );
+        match action {
+            InstanceAction::Details => {
+                self.detail_instance = Some(instance.name);
+                self.detail_updated_at = None;
+                self.show_detail = true;
+            }
+            InstanceAction::ProbeHealth => self.request_health_for(instance.http_address),
+            InstanceAction::CopyAddress => {
+                self.status_message = Some(trf("Copied {}", &[&instance.binary_address]));
+                self.pending_clipboard = Some(instance.binary_address);
+            }
+            InstanceAction::CopyHttpAddress => {
+                self.status_message = Some(trf("Copied {}", &[&instance.http_address]));
+                self.pending_clipboard = Some(instance.http_address);
+            }
+            InstanceAction::OpenWebUi => {
+                let url = actions::web_ui_url(&instance.http_address);
+                self.status_message = Some(trf("Opening {}", &[&url]));
+                self.pending_open_url = Some(url);
+            }
+            InstanceAction::TogglePin => self.toggle_pin(instance.name),
+            InstanceAction::ToggleMark => {
+                if !self.marked.remove(&instance.name) {
+                    self.marked.insert(instance.name);
+                }
+            }
+            InstanceAction::RollingRestart => self.plan_rolling_restart(&instance.name),
+        }
+    }
+
+    /// Copy the dry run's commands to the clipboard
+    pub fn copy_dry_run(&mut self) {
+        if let Some(ref dry_run) = self.dry_run {
+            self.pending_clipboard = Some(dry_run.lines.join("\n"));
+            self.status_message = Some(tr("Copied the commands").to_string());
+        }
+    }
+
+    /// Whether actions that change the cluster are offered. Not against a
+    /// server whose API picotui doesn't understand.
+    pub fn can_change_cluster(&self) -> bool {
+        self.ops
+            && !self.read_only
+            && self
+                .server_version
+                .is_none_or(|version| compat::check(version) == Compatibility::Supported)
+    }
+
+    /// Show the rolling restart plan for the replicaset of `instance`
+    fn plan_rolling_restart(&mut self, instance: &str) {
+        let Some(replicaset) = self
+            .tiers
+            .iter()
+            .flat_map(|t| t.replicasets.iter())
+            .find(|rs| rs.instances.iter().any(|inst| inst.name == instance))
+            .map(|rs| rs.name.clone())
+        else {
+            return;
+        };
+        match RollingRestart::plan(&self.tiers, &replicaset, self.restart_commands.clone()) {
+            Ok(plan) => self.rolling_restart = Some(plan),
+            Err(e) => self.status_message = Some(e),
+        }
+    }
+
+    /// Ask to confirm the planned rolling restart by its replicaset name,
+    /// or show its commands in a dry run
+    pub fn ask_to_start_rolling_restart(&mut self) {
+        let Some(restart) = self.rolling_restart.as_ref() else {
+            return;
+        };
+        if restart.outcome != Outcome::Planned {
+            return;
+        }
+        if self.ops_dry_run {
+            let detail = format!("rolling restart {}", restart.replicaset);
+            self.dry_run = Some(DryRun {
+                title: format!("Rolling restart: {}", restart.replicaset),
+                lines: restart.script(&self.tiers),
+            });
+            self.audit("dry-run", &detail);
+            return;
+        }
+        self.confirm = Some(ConfirmDialog::new(
+            "Rolling restart",
+            format!(
+                "Every instance of {} will be restarted, one at a time.",
+                restart.replicaset
+            ),
+            Severity::Danger,
+            &restart.replicaset,
+            self.cluster_info.as_ref().map(|c| c.cluster_name.as_str()),
+            OpsAction::StartRollingRestart,
+        ));
+    }
+
+    /// Run the operation of the confirmation dialog if its name is typed
+    pub fn confirm_operation(&mut self) {
+        if !self
+            .confirm
+            .as_ref()
+            .is_some_and(ConfirmDialog::is_confirmed)
+        {
+            return;
+        }
+        let Some(dialog) = self.confirm.take() else {
+            return;
+        };
+        match dialog.action {
+            OpsAction::StartRollingRestart => self.start_rolling_restart(),
+        }
+    }
+
+    /// Start the planned rolling restart
+    fn start_rolling_restart(&mut self) {
+        let Some(restart) = self.rolling_restart.as_mut() else {
+            return;
+        };
+        if restart.outcome != Outcome::Planned {
+            return;
+        }
+        restart.begin(&self.tiers, Instant::now());
+        let detail = format!("start {}", restart.replicaset);
+        self.audit("rolling-restart", &detail);
+        self.audit_rolling_commands();
+    }
+
+    /// Stop the rolling restart before its next step
+    pub fn abort_rolling_restart(&mut self) {
+        let Some(restart) = self.rolling_restart.as_mut() else {
+            return;
+        };
+        if !restart.is_running() {
+            return;
+        }
+        restart.abort();
+        let detail = format!(
+            "abort {} at {}",
+            restart.replicaset, restart.instances[restart.current]
+        );
+        self.audit("rolling-restart", &detail);
+    }
+
+    /// Move the rolling restart along with the latest tiers. True if it
+    /// changed.
+    pub fn advance_rolling_restart(&mut self) -> bool {
+        let Some(restart) = self.rolling_restart.as_mut() else {
+            return false;
+        };
+        if !restart.advance(&self.tiers, Instant::now()) {
+            return false;
+        }
+        let detail = match &restart.outcome {
+            Outcome::Done => Some(format!("done {}", restart.replicaset)),
+            Outcome::Failed(reason) => Some(format!("failed {}: {}", restart.replicaset, reason)),
+            _ => None,
+        };
+        self.audit_rolling_commands();
+        if let Some(detail) = detail {
+            self.audit("rolling-restart", &detail);
+        }
+        true
+    }
+
+    /// Record the commands the rolling restart just ran
+    fn audit_rolling_commands(&mut self) {
+        let Some(restart) = self.rolling_restart.as_mut() else {
+            return;
+        };
+        for command in restart.take_started_commands() {
+            self.audit("rolling-restart", &format!("run {}", command));
+        }
+    }
+
+    /// Whether a refresh changed the detail popup's instance moments ago
+    pub fn detail_recently_updated(&self) -> bool {
+        self.animate()
+            && self
+                .detail_updated_at
+                .is_some_and(|at| at.elapsed() < DETAIL_FLASH)
+    }
+
+    /// Whether to draw spinners and short-lived flashes. They only make
+    /// screen readers chatter and cost output on a slow link.
+    pub fn animate(&self) -> bool {
+        !self.accessible && !self.slow_link
+    }
+
+    /// The selected row as a sentence, for screen readers
+    pub fn selection_text(&self) -> Option<String> {
+        let key = self.selected_node_key()?;
+        Some(match self.find_tree_item(&key)? {
+            TreeItem::Tier(t) => {
+                let tier = &self.tiers[t];
+                format!(
+                    "tier {}, {} replicasets, {} instances, memory {:.1}%",
+                    tier.name, tier.replicaset_count, tier.instance_count, tier.capacity_usage
+                )
+            }
+            TreeItem::Replicaset(t, r) => {
+                let tier = &self.tiers[t];
+                let rs = &tier.replicasets[r];
+                format!(
+                    "replicaset {} in tier {}, {}, {} instances, memory {:.1}%",
+                    rs.name, tier.name, rs.state, rs.instance_count, rs.capacity_usage
+                )
+            }
+            TreeItem::Instance(t, r, i) => {
+                let tier = &self.tiers[t];
+                let rs = &tier.replicasets[r];
+                let inst = &rs.instances[i];
+                let mut text = format!("instance {}, {}", inst.name, inst.current_state);
+                if inst.target_state != inst.current_state {
+                    text.push_str(&format!(" going {}", inst.target_state));
+                }
+                if inst.is_leader {
+                    text.push_str(", leader");
+                }
+                if inst.is_raft_leader {
+                    text.push_str(", raft leader");
+                }
+                text.push_str(&format!(", replicaset {}, tier {}", rs.name, tier.name));
+                text
+            }
+        })
+    }
+
+    /// Send the selected row to `--announce` if it changed since last time
+    pub fn announce_selection(&mut self) {
+        let Some(tx) = &self.announce_tx else {
+            return;
+        };
+        let text = self.selection_text();
+        if text != self.last_announced {
+            if let Some(ref line) = text {
+                let _ = tx.send(line.clone());
+            }
+            self.last_announced = text;
+        }
+    }
+
+    pub fn get_selected_instance(&self) -> Option<&InstanceInfo> {
+        match self.view_mode {
+            ViewMode::Tiers => {
+                if let Some(TreeItem::Instance(tier_idx, rs_idx, inst_idx)) =
+                    self.tree_items.get(self.selected_index)
+                {
+                    self.tiers
+                        .get(*tier_idx)
+                        .and_then(|t| t.replicasets.get(*rs_idx))
+                        .and_then(|r| r.instances.get(*inst_idx))
+                } else {
+                    None
+                }
+            }
+            ViewMode::Replicasets => None, // Can't select instance in replicasets view
+            ViewMode::Instances => {
+                // Get sorted instances and select by index
+                let instances = self.get_sorted_instances();
+                instances.get(self.selected_index).map(|(_, _, inst)| *inst)
+            }
+        }
+    }
+
+    /// Get sorted and filtered instances for Instances view
+    pub fn get_sorted_instances(&self) -> Vec<(&str, &str, &InstanceInfo)> {
+        self.with_sorted_instance_indices(|indices| {
+            indices
+                .iter()
+                .map(|&(tier_idx, rs_idx, inst_idx)| {
+                    let tier = &self.tiers[tier_idx];
+                    let rs = &tier.replicasets[rs_idx];
+                    (
+                        tier.name.as_str(),
+                        rs.name.as_str(),
+                        &rs.instances[inst_idx],
+                    )
+                })
+                .collect()
+        })
+    }
+
+    /// Number of instances shown in the Instances view (after filtering)
+    pub fn sorted_instance_count(&self) -> usize {
+        self.with_sorted_instance_indices(|indices| indices.len())
+    }
+
+    /// Run `f` on the cached (tier, replicaset, instance) indices for the
+    /// Instances view, recomputing them if the filter, sort settings or pins changed
+    fn with_sorted_instance_indices<T>(&self, f: impl FnOnce(&[(usize, usize, usize)]) -> T) -> T {
+        let mut cache = self.instance_cache.borrow_mut();
+        let same_settings = |c: &InstanceCache| {
+            c.fuzzy == self.fuzzy_filter
+                && c.sort_field == self.sort_field
+                && c.sort_order == self.sort_order
+                && c.pinned == self.pinned
+                && c.scope == self.scope
+        };
+        let fresh = cache.as_ref().is_some_and(|c| {
+            same_settings(c) && (c.filter == self.filter_text || self.filter_settle_at.is_some())
+        });
+        if !fresh {
+            // Typing more of the same filter only removes rows, so start
+            // from the previous result instead of every instance
+            let previous = cache
+                .take()
+                .filter(|c| same_settings(c) && filter::refines(&self.filter_text, &c.filter));
+            *cache = Some(InstanceCache {
+                filter: self.filter_text.clone(),
+                fuzzy: self.fuzzy_filter,
+                sort_field: self.sort_field,
+                sort_order: self.sort_order,
+                pinned: self.pinned.clone(),
+                scope: self.scope.clone(),
+                indices: self.compute_sorted_instance_indices(previous.map(|c| c.indices)),
+            });
+        }
+        f(cache
+            .as_ref()
+            .map(|c| c.indices.as_slice())
+            .unwrap_or_default())
+    }
+
+    /// Filter and sort the instances, or only `candidates` when a previous
+    /// result is known to contain every match
+    fn compute_sorted_instance_indices(
+        &self,
+        candidates: Option<Vec<(usize, usize, usize)>>,
+    ) -> Vec<(usize, usize, usize)> {
+        let expr = FilterExpr::parse(&self.filter_text);
+
+        let candidates = candidates.unwrap_or_else(|| {
+            self.tiers
+                .iter()
+                .enumerate()
+                .flat_map(|(tier_idx, tier)| {
+                    tier.replicasets
+                        .iter()
+                        .enumerate()
+                        .flat_map(move |(rs_idx, rs)| {
+                            (0..rs.instances.len())
+                                .map(move |inst_idx| (tier_idx, rs_idx, inst_idx))
+                        })
+                })
+                .collect()
+        });
+        let mut instances: Vec<(usize, usize, usize)> = candidates
+            .into_iter()
+            .filter(|&(tier_idx, rs_idx, _)| {
+                self.scope.as_ref().is_none_or(|scope| {
+                    let tier = &self.tiers[tier_idx];
+                    scope.contains(&tier.name, &tier.replicasets[rs_idx].name)
+                })
+            })
+            .filter(|&idx| expr.is_empty() || self.filter_score(&expr, idx).is_some())
+            .collect();
+
+        let instance = |&(tier_idx, rs_idx, inst_idx): &(usize, usize, usize)| {
+            &self.tiers[tier_idx].replicasets[rs_idx].instances[inst_idx]
+        };
+
+        // Sort based on current sort settings
+        match self.sort_field {
+            SortField::Name => {
+                instances.sort_by(|a, b| {
+                    let cmp = instance(a).name.cmp(&instance(b).name);
+                    if self.sort_order == SortOrder::Desc {
+                        cmp.reverse()
+                    } else {
+                        cmp
+                    }
+                });
+            }
+            SortField::FailureDomain => {
+                // Format each domain once rather than on every comparison
+                let mut keyed: Vec<_> = instances
+                    .into_iter()
+                    .map(|idx| {
+                        (
+                            Self::format_failure_domain(&instance(&idx).failure_domain),
+                            idx,
+                        )
+                    })
+                    .collect();
+                keyed.sort_by(|(domain_a, a), (domain_b, b)| {
+                    let cmp = domain_a.cmp(domain_b);
+                    // If domains are equal, sort by name
+                    let cmp = if cmp == std::cmp::Ordering::Equal {
+                        instance(a).name.cmp(&instance(b).name)
+                    } else {
+                        cmp
+                    };
+                    if self.sort_order == SortOrder::Desc {
+                        cmp.reverse()
+                    } else {
+                        cmp
+                    }
+                });
+                instances = keyed.into_iter().map(|(_, idx)| idx).collect();
+            }
+        }
+
+        // Best fuzzy matches first; the stable sort keeps the chosen order for ties
+        if self.fuzzy_filter && !expr.is_empty() {
+            let mut scored: Vec<_> = instances
+                .into_iter()
+                .map(|idx| (self.filter_score(&expr, idx).unwrap_or(0), idx))
+                .collect();
+            scored.sort_by_key(|&(score, _)| std::cmp::Reverse(score));
+            instances = scored.into_iter().map(|(_, idx)| idx).collect();
+        }
+
+        // Pinned instances stay on top, in the order chosen above
+        if !self.pinned.is_empty() {
+            instances.sort_by_key(|idx| !self.pinned.contains(&instance(idx).name));
+        }
+
+        instances
+    }
+
+    /// Score of an instance against the filter expression. Terms match the
+    /// instance name, tier, replicaset, address, state or failure domain.
+    fn filter_score(
+        &self,
+        expr: &FilterExpr,
+        (tier_idx, rs_idx, inst_idx): (usize, usize, usize),
+    ) -> Option<i64> {
+        let tier = &self.tiers[tier_idx];
+        let rs = &tier.replicasets[rs_idx];
+        let inst = &rs.instances[inst_idx];
+        let state = inst.current_state.to_string();
+        let fields: Vec<&str> = [
+            inst.name.as_str(),
+            tier.name.as_str(),
+            rs.name.as_str(),
+            inst.binary_address.as_str(),
+            state.as_str(),
+        ]
+        .into_iter()
+        .chain(inst.failure_domain.values().map(String::as_str))
+        .collect();
+        expr.score(&fields, self.fuzzy_filter)
+    }
+
+    /// Rows of the column picker: shown columns in display order, then the
+    /// hidden ones
+    pub fn column_picker_rows(&self) -> Vec<(InstanceColumn, bool)> {
+        let hidden = InstanceColumn::ALL
+            .into_iter()
+            .filter(|c| !self.instance_columns.contains(c));
+        self.instance_columns
+            .iter()
+            .map(|&c| (c, true))
+            .chain(hidden.map(|c| (c, false)))
+            .collect()
+    }
+
+    pub fn open_column_picker(&mut self) {
+        self.column_picker_index = 0;
+        self.show_column_picker = true;
+    }
+
+    /// Show or hide the column under the picker cursor. Shown columns are
+    /// appended at the end; the name column can't be hidden.
+    pub fn toggle_picker_column(&mut self) {
+        let Some(&(column, shown)) = self.column_picker_rows().get(self.column_picker_index) else {
+            return;
+        };
+        if shown {
+            if column == InstanceColumn::Name {
+                return;
+            }
+            self.instance_columns.retain(|&c| c != column);
+        } else {
+            self.instance_columns.push(column);
+        }
+        // Keep the cursor on the same column
+        if let Some(index) = self
+            .column_picker_rows()
+            .iter()
+            .position(|&(c, _)| c == column)
+        {
+            self.column_picker_index = index;
+        }
+    }
+
+    /// Move the shown column under the picker cursor one place left or right
+    pub fn move_picker_column(&mut self, earlier: bool) {
+        let index = self.column_picker_index;
+        if index >= self.instance_columns.len() {
+            return;
+        }
+        let target = if earlier {
+            index.checked_sub(1)
+        } else {
+            Some(index + 1).filter(|&i| i < self.instance_columns.len())
+        };
+        if let Some(target) = target {
+            self.instance_columns.swap(index, target);
+            self.column_picker_index = target;
+        }
+    }
+
+    /// Show another view, dropping the filter and search of this one
+    pub fn switch_view(&mut self, mode: ViewMode) {
+        // Probes of the old view's tier or replicaset aren't wanted anymore
+        self.cancel_group_health();
+        self.view_mode = mode;
+        self.filter_text.clear();
+        self.filter_active = false;
+        self.clear_search();
+        self.reset_selection();
+    }
+
+    /// Open the view switcher with the cursor on the current view
+    pub fn open_view_switcher(&mut self) {
+        self.view_switcher_index = ViewMode::ALL
+            .iter()
+            .position(|&m| m == self.view_mode)
+            .unwrap_or(0);
+        self.show_view_switcher = true;
+    }
+
+    /// Switch to `mode` from the view switcher. Picking the current view
+    /// keeps its filter.
+    pub fn pick_view(&mut self, mode: ViewMode) {
+        self.show_view_switcher = false;
+        if mode != self.view_mode {
+            self.switch_view(mode);
+        }
+    }
+
+    /// Row counts and problems of every view, for the view switcher
+    pub fn view_summaries(&self) -> Vec<ViewSummary> {
+        let replicasets = || self.tiers.iter().flat_map(|t| t.replicasets.iter());
+        let instances = || replicasets().flat_map(|rs| rs.instances.iter());
+        ViewMode::ALL
+            .into_iter()
+            .map(|mode| match mode {
+                ViewMode::Tiers => ViewSummary {
+                    mode,
+                    count: self.tiers.len(),
+                    shown: None,
+                    problems: self
+                        .tiers
+                        .iter()
+                        .filter(|t| {
+                            t.replicasets
+                                .iter()
+                                .any(|rs| rs.instances.iter().any(is_troubled))
+                        })
+                        .count(),
+                },
+                ViewMode::Replicasets => ViewSummary {
+                    mode,
+                    count: replicasets().count(),
+                    shown: None,
+                    problems: replicasets()
+                        .filter(|rs| rs.instances.iter().any(is_troubled))
+                        .count(),
+                },
+                ViewMode::Instances => {
+                    let count = instances().count();
+                    let filtered = !self.filter_text.is_empty() || self.scope.is_some();
+                    ViewSummary {
+                        mode,
+                        count,
+                        shown: filtered
+                            .then(|| self.sorted_instance_count())
+                            .filter(|&shown| shown != count),
+                        problems: instances().filter(|inst| is_troubled(inst)).count(),
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// Open the sort picker with the cursor on the current sort field
+    pub fn open_sort_picker(&mut self) {
+        self.sort_picker_index = SortField::ALL
+            .iter()
+            .position(|&f| f == self.sort_field)
+            .unwrap_or(0);
+        self.show_sort_picker = true;
+    }
+
+    /// Sort by `field` and close the sort picker
+    pub fn pick_sort_field(&mut self, field: SortField) {
+        self.show_sort_picker = false;
+        if field != self.sort_field {
+            self.sort_field = field;
+            self.reset_selection();
+        }
+    }
+
+    pub fn toggle_sort_order(&mut self) {
+        self.sort_order = self.sort_order.toggle();
+        self.reset_selection();
+    }
+
+    /// Ask for a name to save the current tiers under
+    pub fn start_snapshot_prompt(&mut self) {
+        if self.snapshot_dir.is_none() {
+            self.status_message = Some(tr("Snapshots are not available").to_string());
+        } else if self.tiers.is_empty() {
+            self.status_message = Some(tr("Nothing to snapshot yet").to_string());
+        } else {
+            self.snapshot_name.clear();
+            self.snapshot_prompt_active = true;
+        }
+    }
+
+    pub fn cancel_snapshot_prompt(&mut self) {
+        self.snapshot_prompt_active = false;
+        self.snapshot_name.clear();
+    }
+
+    /// Save the current tiers; an empty name falls back to the current time
+    pub fn save_snapshot(&mut self) {
+        self.snapshot_prompt_active = false;
+        let Some(ref dir) = self.snapshot_dir else {
+            return;
+        };
+
+        let taken_at = format::unix_now();
+        let name = match self.snapshot_name.trim() {
+            "" => format!("snapshot-{}", taken_at),
+            name => name.to_string(),
+        };
+        let snapshot = Snapshot {
+            name,
+            taken_at,
+            url: Some(self.base_url.clone()),
+            tiers: self.tiers.handle(),
+        };
+        match snapshot::save(dir, &snapshot) {
+            Ok(_) => {
+                self.toast(
+                    ToastKind::Success,
+                    trf("Saved snapshot \"{}\"", &[&snapshot.name]),
+                );
+                self.audit("snapshot", &snapshot.name);
+            }
+            Err(e) => self.toast(
+                ToastKind::Error,
+                trf("Failed to save snapshot: {}", &[&format!("{:#}", e)]),
+            ),
+        }
+        self.snapshot_name.clear();
+    }
+
+    pub fn open_snapshot_picker(&mut self) {
+        self.snapshot_names = self
+            .snapshot_dir
+            .as_deref()
+            .map(|dir| snapshot::list_for(dir, &self.base_url))
+            .unwrap_or_default();
+        if self.snapshot_names.is_empty() {
+            self.status_message =
+                Some(tr("No saved snapshots of this cluster (w to save one)").to_string());
+            return;
+        }
+        self.snapshot_picker_index = 0;
+        self.show_snapshot_picker = true;
+    }
+
+    /// Compare a saved snapshot with the live cluster
+    pub fn compare_snapshot(&mut self, index: usize) {
+        let (Some(dir), Some(name)) = (&self.snapshot_dir, self.snapshot_names.get(index)) else {
+            return;
+        };
+        self.show_snapshot_picker = false;
+        match snapshot::load(dir, name) {
+            Ok(saved) => {
+                let diff = snapshot::diff(&saved.tiers, &self.tiers);
+                self.snapshot_diff = Some((saved.name, diff));
+                self.compared_snapshot = Some((saved.tiers, self.tiers.generation()));
+            }
+            Err(e) => self.status_message = Some(format!("{:#}", e)),
+        }
+    }
+
+    /// Compare the open snapshot diff again once the live tiers moved on
+    fn refresh_snapshot_diff(&mut self) {
+        let (Some((_, diff)), Some((saved, generation))) =
+            (&mut self.snapshot_diff, &mut self.compared_snapshot)
+        else {
+            return;
+        };
+        if *generation != self.tiers.generation() {
+            *diff = snapshot::diff(saved, &self.tiers);
+            *generation = self.tiers.generation();
+        }
+    }
+
+    /// Re-read the skins directory. Broken skins are reported in the
+    /// status bar and left out.
+    fn reload_themes(&mut self) -> Vec<Theme> {
+        let Some(ref dir) = self.skins_dir else {
+            return vec![Theme::default()];
+        };
+        let (themes, errors) = theme::load_themes(dir);
+        if let Some(first) = errors.first() {
+            self.status_message = Some(trf("Skipped skin {}", &[first]));
+        }
+        themes
+    }
+
+    /// Switch to the theme called `name`, returning false if there is none
+    pub fn select_theme(&mut self, name: &str) -> bool {
+        match self.reload_themes().into_iter().find(|t| t.name == name) {
+            Some(theme) => {
+                self.theme = Theme {
+                    depth: self.theme.depth,
+                    ..theme
+                };
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Switch to the theme after the current one, wrapping around
+    pub fn cycle_theme(&mut self) {
+        let themes = self.reload_themes();
+        let next = themes
+            .iter()
+            .position(|t| t.name == self.theme.name)
+            .map_or(0, |i| (i + 1) % themes.len());
+        self.theme = Theme {
+            depth: self.theme.depth,
+            ..themes[next].clone()
+        };
+        if self.status_message.is_none() {
+            self.status_message = Some(trf("Theme: {}", &[&self.theme.name]));
+        }
+    }
+
+    pub fn is_pinned(&self, name: &str) -> bool {
+        self.pinned.contains(name)
+    }
+
+    /// Pin or unpin the selected instance. The selection follows the
+    /// instance when pinning moves it in the Instances view.
+    pub fn toggle_pin_selected(&mut self) {
+        if let Some(name) = self.get_selected_instance().map(|inst| inst.name.clone()) {
+            self.toggle_pin(name);
+        }
+    }
+
+    fn toggle_pin(&mut self, name: String) {
+        if !self.pinned.remove(&name) {
+            self.pinned.insert(name.clone());
+        }
+
+        if self.view_mode == ViewMode::Instances {
+            self.select_instance_row(&name);
+        }
+
+        if let Some(ref path) = self.pins_path {
+            if let Err(e) = pins::save_pins(path, &self.base_url, &self.pinned) {
+                self.status_message = Some(trf("Could not save pins: {}", &[&e]));
+                return;
+            }
+        }
+        self.status_message = Some(if self.pinned.contains(&name) {
+            trf("Pinned {}", &[&name])
+        } else {
+            trf("Unpinned {}", &[&name])
+        });
+    }
+
+    /// Select the Instances view row of `name`, if it's shown
+    fn select_instance_row(&mut self, name: &str) {
+        if let Some(index) = self
+            .get_sorted_instances()
+            .iter()
+            .position(|(_, _, inst)| inst.name == name)
+        {
+            self.selected_index = index;
+            self.list_state.select(Some(index));
+        }
+    }
+
+    /// Position of the selected instance, or of the selected replicaset in
+    /// the Tiers view, as (tier, replicaset, instance) indices
+    fn selected_location(&self) -> Option<(usize, usize, Option<usize>)> {
+        if let Some(&TreeItem::Replicaset(t, r)) = self
+            .tree_items
+            .get(self.selected_index)
+            .filter(|_| self.view_mode == ViewMode::Tiers)
+        {
+            return Some((t, r, None));
+        }
+        let name = &self.get_selected_instance()?.name;
+        self.tiers.iter().enumerate().find_map(|(t, tier)| {
+            tier.replicasets.iter().enumerate().find_map(|(r, rs)| {
+                rs.instances
+                    .iter()
+                    .position(|inst| &inst.name == name)
+                    .map(|i| (t, r, Some(i)))
+            })
+        })
+    }
+
+    /// Select the leader of the selected instance's replicaset. From the
+    /// leader itself, go on to the Raft leader of the cluster.
+    pub fn jump_to_leader(&mut self) {
+        let Some((t, r, current)) = self.selected_location() else {
+            return;
+        };
+        let rs = &self.tiers[t].replicasets[r];
+        let target = match rs.instances.iter().position(|inst| inst.is_leader) {
+            Some(leader) if Some(leader) != current => Some(("Leader", (t, r, leader))),
+            Some(_) => self.tiers.iter().enumerate().find_map(|(t, tier)| {
+                tier.replicasets.iter().enumerate().find_map(|(r, rs)| {
+                    rs.instances
+                        .iter()
+                        .position(|inst| inst.is_raft_leader)
+                        .map(|i| ("Raft leader", (t, r, i)))
+                })
+            }),
+            None => {
+                self.status_message = Some(trf("Replicaset {} has no leader", &[&rs.name]));
+                return;
+            }
+        };
+        let Some((role, (t, r, i))) = target else {
+            self.status_message = Some(tr("No Raft leader reported").to_string());
+            return;
+        };
+
+        let name = self.tiers[t].replicasets[r].instances[i].name.clone();
+        match self.view_mode {
+            ViewMode::Tiers => self.reveal(SearchTarget::Node(TreeItem::Instance(t, r, i))),
+            ViewMode::Instances => {
+                if !self
+                    .get_sorted_instances()
+                    .iter()
+                    .any(|(_, _, inst)| inst.name == name)
+                {
+                    self.status_message = Some(trf("{} {} is filtered out", &[&tr(role), &name]));
+                    return;
+                }
+                self.select_instance_row(&name);
+            }
+            ViewMode::Replicasets => return,
+        }
+        self.status_message = Some(format!("{}: {}", tr(role), name));
+    }
+
+    /// Limit the Instances view to the selected instance's replicaset, or
+    /// with `whole_tier` its tier, keeping the instance selected
+    pub fn scope_to_selected(&mut self, whole_tier: bool) {
+        if self.view_mode != ViewMode::Instances {
+            return;
+        }
+        let Some((tier, replicaset, name)) = self
+            .get_sorted_instances()
+            .get(self.selected_index)
+            .map(|(tier, rs, inst)| (tier.to_string(), rs.to_string(), inst.name.clone()))
+        else {
+            return;
+        };
+        self.scope = Some(if whole_tier {
+            InstanceScope::Tier(tier)
+        } else {
+            InstanceScope::Replicaset { tier, replicaset }
+        });
+        self.select_instance_row(&name);
+    }
+
+    /// Back to the whole cluster, staying on the selected instance
+    pub fn clear_scope(&mut self) {
+        let name = self.get_selected_instance().map(|inst| inst.name.clone());
+        self.scope = None;
+        match name {
+            Some(name) => self.select_instance_row(&name),
+            None => self.reset_selection(),
+        }
+    }
+
+    pub fn is_marked(&self, name: &str) -> bool {
+        self.marked.contains(name)
+    }
+
+    /// Mark or unmark the selected instance and move on to the next row
+    pub fn toggle_mark_selected(&mut self) {
+        let Some(name) = self.get_selected_instance().map(|inst| inst.name.clone()) else {
+            return;
+        };
+        if !self.marked.remove(&name) {
+            self.marked.insert(name);
+        }
+        if self.selected_index + 1 < self.get_item_count() {
+            self.select_next();
+        }
+    }
+
+    pub fn clear_marks(&mut self) {
+        self.marked.clear();
+    }
+
+    /// Marked instances that are still in the cluster, in topology order
+    pub fn marked_instances(&self) -> Vec<(&str, &str, &InstanceInfo)> {
+        self.tiers
+            .iter()
+            .flat_map(|tier| {
+                tier.replicasets.iter().flat_map(move |rs| {
+                    rs.instances
+                        .iter()
+                        .filter(|inst| self.marked.contains(&inst.name))
+                        .map(move |inst| (tier.name.as_str(), rs.name.as_str(), inst))
+                })
+            })
+            .collect()
+    }
+
+    /// Write the marked instances to a JSON file in the current directory
+    pub fn export_marked(&mut self) {
+        let instances = self.marked_instances();
+        if instances.is_empty() {
+            self.status_message = Some(tr("No instances marked (Space to mark)").to_string());
+            return;
+        }
+        match selection::save(&instances) {
+            Ok(path) => self.toast(
+                ToastKind::Success,
+                trf(
+                    "Exported {} instances to {}",
+                    &[&instances.len(), &path.display()],
+                ),
+            ),
+            Err(e) => self.toast(
+                ToastKind::Error,
+                trf("Failed to export instances: {}", &[&e]),
+            ),
+        }
+    }
+
+    /// Write a bug-report bundle to the current directory
+    pub fn save_bug_report(&mut self) {
+        let files = bugreport::collect(self, &TerminalEnv::from_env());
+        match bugreport::save(&files) {
+            Ok(path) => {
+                self.toast(
+                    ToastKind::Success,
+                    trf("Saved bug report to {}", &[&path.display()]),
+                );
+                self.audit("export", &path.display().to_string());
+            }
+            Err(e) => self.toast(
+                ToastKind::Error,
+                trf("Failed to save bug report: {}", &[&e]),
+            ),
+        }
+    }
+
+    /// Copy the names of the marked instances, one per line
+    pub fn copy_marked_names(&mut self) {
+        let names: Vec<String> = self
+            .marked_instances()
+            .iter()
+            .map(|(_, _, inst)| inst.name.clone())
+            .collect();
+        if names.is_empty() {
+            self.status_message = Some(tr("No instances marked (Space to mark)").to_string());
+            return;
+        }
+        self.status_message = Some(trf("Copied {} names to clipboard", &[&names.len()]));
+        self.pending_clipboard = Some(names.join("\n"));
+    }
+
+    fn format_failure_domain(domain: &std::collections::BTreeMap<String, String>) -> String {
+        domain
+            .iter()
+            .map(|(k, v)| format!("{}:{}", k, v))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Get the total number of items in the current view
+    pub fn get_item_count(&self) -> usize {
+        match self.view_mode {
+            ViewMode::Tiers => self.tree_items.len(),
+            ViewMode::Replicasets => self.tiers.iter().map(|t| t.replicasets.len()).sum(),
+            ViewMode::Instances => self.sorted_instance_count(),
+        }
+    }
+
+    /// Remember the current filter when it is applied
+    pub fn record_filter(&mut self) {
+        self.filter_history_pos = None;
+        if self.filter_text.is_empty() {
+            return;
+        }
+        self.filter_history.retain(|f| *f != self.filter_text);
+        self.filter_history.push(self.filter_text.clone());
+        if self.filter_history.len() > FILTER_HISTORY_LIMIT {
+            self.filter_history.remove(0);
+        }
+    }
+
+    /// Replace the filter with the previous (older) history entry
+    pub fn filter_history_prev(&mut self) {
+        let pos = match self.filter_history_pos {
+            Some(0) => return,
+            Some(pos) => pos - 1,
+            None if self.filter_history.is_empty() => return,
+            None => {
+                self.filter_draft = self.filter_text.clone();
+                self.filter_history.len() - 1
+            }
+        };
+        self.filter_history_pos = Some(pos);
+        self.filter_text = self.filter_history[pos].clone();
+        self.reset_selection();
+    }
+
+    /// Replace the filter with the next (newer) history entry, returning
+    /// to the text being typed past the newest one
+    pub fn filter_history_next(&mut self) {
+        let Some(pos) = self.filter_history_pos else {
+            return;
+        };
+        if pos + 1 < self.filter_history.len() {
+            self.filter_history_pos = Some(pos + 1);
+            self.filter_text = self.filter_history[pos + 1].clone();
+        } else {
+            self.filter_history_pos = None;
+            self.filter_text = std::mem::take(&mut self.filter_draft);
+        }
+        self.reset_selection();
+    }
+
+    /// Open the saved filter picker; says where to define filters if there are none
+    pub fn open_filter_picker(&mut self) {
+        if self.saved_filters.is_empty() {
+            self.status_message = Some(tr("No saved filters in config.json").to_string());
+            return;
+        }
+        self.show_filter_picker = true;
+        self.filter_picker_index = 0;
+    }
+
+    /// Apply a saved filter by its position in the picker
+    pub fn apply_saved_filter(&mut self, index: usize) {
+        let Some(saved) = self.saved_filters.get(index) else {
+            return;
+        };
+        self.fuzzy_filter = saved.fuzzy;
+        self.show_filter_picker = false;
+        self.set_filter(saved.filter.clone());
+    }
+
+    /// Replace the Instances view filter with `filter`
+    pub fn set_filter(&mut self, filter: String) {
+        self.filter_text = filter;
+        self.filter_active = false;
+        self.record_filter();
+        self.reset_selection();
     }
 
-    /// Jump to last item (G in Vim)
-    pub fn select_last(&mut self) {
-        let count = self.get_item_count();
-        if count > 0 {
-            self.selected_index = count - 1;
-            self.list_state.select(Some(self.selected_index));
-        }
+    /// Open the search prompt, remembering where the selection started
+    pub fn start_search(&mut self) {
+        self.search_active = true;
+        self.search_text.clear();
+        self.search_origin = self.selected_node_key();
     }
 
-    /// Move half page down (Ctrl+D in Vim)
-    pub fn select_half_page_down(&mut self, visible_height: usize) {
-        let count = self.get_item_count();
-        if count > 0 {
-            let half_page = visible_height / 2;
-            self.selected_index = (self.selected_index + half_page).min(count - 1);
-            self.list_state.select(Some(self.selected_index));
+    /// Re-run the search from the starting row as the pattern is typed
+    pub fn update_search(&mut self) {
+        if let Some(origin) = self.search_origin.clone() {
+            self.select_node_key(&origin);
+        }
+        if !self.search_text.is_empty() {
+            self.search_step(true, true);
         }
     }
 
-    /// Move half page up (Ctrl+U in Vim)
-    pub fn select_half_page_up(&mut self, visible_height: usize) {
-        let half_page = visible_height / 2;
-        self.selected_index = self.selected_index.saturating_sub(half_page);
-        self.list_state.select(Some(self.selected_index));
+    /// Close the prompt and keep the pattern for n/N
+    pub fn confirm_search(&mut self) {
+        self.search_active = false;
+        self.search_origin = None;
+        if !self.search_text.is_empty() && !self.row_matches_search(self.selected_index) {
+            self.status_message = Some(trf("Pattern not found: {}", &[&self.search_text]));
+        }
     }
 
-    /// Move full page down (Ctrl+F in Vim)
-    pub fn select_page_down(&mut self, visible_height: usize) {
-        let count = self.get_item_count();
-        if count > 0 {
-            self.selected_index = (self.selected_index + visible_height).min(count - 1);
-            self.list_state.select(Some(self.selected_index));
+    /// Close the prompt and return to where the search started
+    pub fn cancel_search(&mut self) {
+        if let Some(origin) = self.search_origin.take() {
+            self.select_node_key(&origin);
         }
+        self.search_active = false;
+        self.search_text.clear();
     }
 
-    /// Move full page up (Ctrl+B in Vim)
-    pub fn select_page_up(&mut self, visible_height: usize) {
-        self.selected_index = self.selected_index.saturating_sub(visible_height);
-        self.list_state.select(Some(self.selected_index));
+    /// Jump to the next (or previous) match, wrapping around
+    pub fn search_next(&mut self, forward: bool) {
+        if self.search_text.is_empty() {
+            return;
+        }
+        if !self.search_step(forward, false) {
+            self.status_message = Some(trf("Pattern not found: {}", &[&self.search_text]));
+        }
     }
 
-    pub fn expand_selected(&mut self) {
+    /// Rows in display order. In the Tiers view nodes under collapsed
+    /// parents are included, so a match can be revealed by expanding them.
+    fn search_targets(&self) -> Vec<SearchTarget> {
         match self.view_mode {
             ViewMode::Tiers => {
-                if let Some(item) = self.tree_items.get(self.selected_index) {
-                    match item {
-                        TreeItem::Tier(tier_idx) => {
-                            self.expanded_tiers.insert(*tier_idx);
-                            self.rebuild_tree();
-                        }
-                        TreeItem::Replicaset(tier_idx, rs_idx) => {
-                            self.expanded_replicasets.insert((*tier_idx, *rs_idx));
-                            self.rebuild_tree();
-                        }
-                        TreeItem::Instance(_, _, _) => {
-                            self.show_detail = true;
+                let mut targets = Vec::new();
+                for (t, tier) in self.tiers.iter().enumerate() {
+                    targets.push(SearchTarget::Node(TreeItem::Tier(t)));
+                    for (r, rs) in tier.replicasets.iter().enumerate() {
+                        targets.push(SearchTarget::Node(TreeItem::Replicaset(t, r)));
+                        for i in 0..rs.instances.len() {
+                            targets.push(SearchTarget::Node(TreeItem::Instance(t, r, i)));
                         }
                     }
                 }
+                targets
             }
-            ViewMode::Replicasets => {
-                // Could expand to show instances, but for now do nothing
-            }
-            ViewMode::Instances => {
-                self.show_detail = true;
+            ViewMode::Replicasets | ViewMode::Instances => {
+                (0..self.get_item_count()).map(SearchTarget::Row).collect()
             }
         }
     }
 
-    pub fn collapse_selected(&mut self) {
-        if self.view_mode != ViewMode::Tiers {
-            return;
-        }
-
-        if let Some(item) = self.tree_items.get(self.selected_index) {
-            match item {
-                TreeItem::Tier(tier_idx) => {
-                    self.expanded_tiers.remove(tier_idx);
-                    // Also collapse all replicasets in this tier
-                    self.expanded_replicasets.retain(|(t, _)| *t != *tier_idx);
-                    self.rebuild_tree();
-                }
-                TreeItem::Replicaset(tier_idx, rs_idx) => {
-                    self.expanded_replicasets.remove(&(*tier_idx, *rs_idx));
-                    self.rebuild_tree();
-                }
-                TreeItem::Instance(tier_idx, rs_idx, _) => {
-                    // Collapse parent replicaset
-                    self.expanded_replicasets.remove(&(*tier_idx, *rs_idx));
-                    self.rebuild_tree();
-                }
+    /// Match of the search expression against a target's names
+    fn target_matches(&self, target: SearchTarget, expr: &FilterExpr) -> bool {
+        let contains = |s: &str| expr.matches(&[s]);
+        match target {
+            SearchTarget::Node(TreeItem::Tier(t)) => contains(&self.tiers[t].name),
+            SearchTarget::Node(TreeItem::Replicaset(t, r)) => {
+                contains(&self.tiers[t].replicasets[r].name)
+            }
+            SearchTarget::Node(TreeItem::Instance(t, r, i)) => {
+                contains(&self.tiers[t].replicasets[r].instances[i].name)
             }
+            SearchTarget::Row(row) => match self.view_mode {
+                ViewMode::Replicasets => {
+                    self.replicaset_at(row).is_some_and(|rs| contains(&rs.name))
+                }
+                _ => self
+                    .with_sorted_instance_indices(|indices| indices.get(row).copied())
+                    .is_some_and(|(t, r, i)| {
+                        let rs = &self.tiers[t].replicasets[r];
+                        let inst = &rs.instances[i];
+                        expr.matches(&[&inst.name, &rs.name, &inst.binary_address])
+                    }),
+            },
         }
     }
 
-    pub fn toggle_detail(&mut self) {
-        // Only show detail if we can get an instance
-        match self.view_mode {
-            ViewMode::Tiers => {
-                // Only toggle if an instance is selected
-                if let Some(TreeItem::Instance(_, _, _)) = self.tree_items.get(self.selected_index)
-                {
-                    self.show_detail = !self.show_detail;
-                }
-            }
-            ViewMode::Replicasets => {
-                // Can't show instance detail in replicasets view
-            }
-            ViewMode::Instances => {
-                self.show_detail = !self.show_detail;
+    /// Replicaset on `row` of the Replicasets view, found tier by tier
+    /// rather than replicaset by replicaset
+    fn replicaset_at(&self, mut row: usize) -> Option<&ReplicasetInfo> {
+        for tier in self.tiers.iter() {
+            match tier.replicasets.get(row) {
+                Some(rs) => return Some(rs),
+                None => row -= tier.replicasets.len(),
             }
         }
+        None
     }
 
-    pub fn get_selected_instance(&self) -> Option<&InstanceInfo> {
-        match self.view_mode {
-            ViewMode::Tiers => {
-                if let Some(TreeItem::Instance(tier_idx, rs_idx, inst_idx)) =
-                    self.tree_items.get(self.selected_index)
-                {
-                    self.tiers
-                        .get(*tier_idx)
-                        .and_then(|t| t.replicasets.get(*rs_idx))
-                        .and_then(|r| r.instances.get(*inst_idx))
-                } else {
-                    None
-                }
-            }
-            ViewMode::Replicasets => None, // Can't select instance in replicasets view
-            ViewMode::Instances => {
-                // Get sorted instances and select by index
-                let instances = self.get_sorted_instances();
-                instances.get(self.selected_index).map(|(_, _, inst)| *inst)
-            }
-        }
+    fn row_matches_search(&self, index: usize) -> bool {
+        let expr = FilterExpr::parse(&self.search_text);
+        let target = match self.view_mode {
+            ViewMode::Tiers => match self.tree_items.get(index) {
+                Some(&item) => SearchTarget::Node(item),
+                None => return false,
+            },
+            _ => SearchTarget::Row(index),
+        };
+        self.target_matches(target, &expr)
     }
 
-    /// Get sorted and filtered instances for Instances view
-    pub fn get_sorted_instances(&self) -> Vec<(&str, &str, &InstanceInfo)> {
-        let filter_lower = self.filter_text.to_lowercase();
+    /// Move the selection to the next matching target. Returns false when
+    /// nothing matches.
+    fn search_step(&mut self, forward: bool, include_current: bool) -> bool {
+        let expr = FilterExpr::parse(&self.search_text);
+        let targets = self.search_targets();
+        let n = targets.len();
+        if n == 0 {
+            return false;
+        }
 
-        let mut instances: Vec<(&str, &str, &InstanceInfo)> = self
-            .tiers
-            .iter()
-            .flat_map(|tier| {
-                tier.replicasets.iter().flat_map(move |rs| {
-                    rs.instances
-                        .iter()
-                        .map(move |inst| (tier.name.as_str(), rs.name.as_str(), inst))
-                })
-            })
-            .filter(|(tier_name, rs_name, inst)| {
-                if filter_lower.is_empty() {
-                    return true;
-                }
-                // Match against instance name, tier, replicaset, address, or failure domain
-                inst.name.to_lowercase().contains(&filter_lower)
-                    || tier_name.to_lowercase().contains(&filter_lower)
-                    || rs_name.to_lowercase().contains(&filter_lower)
-                    || inst.binary_address.to_lowercase().contains(&filter_lower)
-                    || inst
-                        .failure_domain
-                        .values()
-                        .any(|v| v.to_lowercase().contains(&filter_lower))
-            })
-            .collect();
+        let current = match self.view_mode {
+            ViewMode::Tiers => self
+                .tree_items
+                .get(self.selected_index)
+                .and_then(|&item| targets.iter().position(|t| *t == SearchTarget::Node(item)))
+                .unwrap_or(0),
+            _ => self.selected_index.min(n - 1),
+        };
 
-        // Sort based on current sort settings
-        match self.sort_field {
-            SortField::Name => {
-                instances.sort_by(|a, b| {
-                    let cmp = a.2.name.cmp(&b.2.name);
-                    if self.sort_order == SortOrder::Desc {
-                        cmp.reverse()
-                    } else {
-                        cmp
-                    }
-                });
+        let first = if include_current { 0 } else { 1 };
+        let found = (first..n + first).map(|step| {
+            let step = step % n;
+            if forward {
+                (current + step) % n
+            } else {
+                (current + n - step) % n
             }
-            SortField::FailureDomain => {
-                instances.sort_by(|a, b| {
-                    let domain_a = Self::format_failure_domain(&a.2.failure_domain);
-                    let domain_b = Self::format_failure_domain(&b.2.failure_domain);
-                    let cmp = domain_a.cmp(&domain_b);
-                    // If domains are equal, sort by name
-                    let cmp = if cmp == std::cmp::Ordering::Equal {
-                        a.2.name.cmp(&b.2.name)
-                    } else {
-                        cmp
-                    };
-                    if self.sort_order == SortOrder::Desc {
-                        cmp.reverse()
-                    } else {
-                        cmp
-                    }
-                });
+        });
+        for idx in found {
+            if self.target_matches(targets[idx], &expr) {
+                self.reveal(targets[idx]);
+                return true;
             }
         }
-
-        instances
+        false
     }
 
-    fn format_failure_domain(domain: &std::collections::HashMap<String, String>) -> String {
-        if domain.is_empty() {
-            return String::new();
-        }
-        let mut pairs: Vec<_> = domain.iter().collect();
-        pairs.sort_by(|a, b| a.0.cmp(b.0));
-        pairs
-            .iter()
-            .map(|(k, v)| format!("{}:{}", k, v))
-            .collect::<Vec<_>>()
-            .join(", ")
+    /// Select a search target, expanding its parents in the Tiers view
+    fn reveal(&mut self, target: SearchTarget) {
+        let index = match target {
+            SearchTarget::Row(row) => row,
+            SearchTarget::Node(item) => {
+                let needs_rebuild = match item {
+                    TreeItem::Tier(_) => false,
+                    TreeItem::Replicaset(t, _) => self.expanded_tiers.insert(t),
+                    TreeItem::Instance(t, r, _) => {
+                        // Both must run; don't short-circuit
+                        let tier = self.expanded_tiers.insert(t);
+                        self.expanded_replicasets.insert((t, r)) | tier
+                    }
+                };
+                if needs_rebuild {
+                    self.rebuild_tree();
+                }
+                match self.tree_items.iter().position(|i| *i == item) {
+                    Some(index) => index,
+                    None => return,
+                }
+            }
+        };
+        self.selected_index = index;
+        self.list_state.select(Some(index));
     }
 
-    /// Get the total number of items in the current view
-    pub fn get_item_count(&self) -> usize {
-        match self.view_mode {
-            ViewMode::Tiers => self.tree_items.len(),
-            ViewMode::Replicasets => self.tiers.iter().map(|t| t.replicasets.len()).sum(),
-            ViewMode::Instances => self
-                .tiers
-                .iter()
-                .flat_map(|t| t.replicasets.iter())
-                .map(|r| r.instances.len())
-                .sum(),
-        }
+    /// Forget the search pattern (and its highlighting)
+    pub fn clear_search(&mut self) {
+        self.search_text.clear();
+        self.search_active = false;
+        self.search_origin = None;
     }
 
     pub fn shutdown(&self) {
-        let _ = self.request_tx.send(ApiRequest::Shutdown);
+        self.source.send(ApiRequest::Shutdown);
     }
 }
 
+/// Tokens of the logged-in session
+#[derive(Debug, Clone)]
+struct Session {
+    auth: String,
+    refresh: String,
+    /// Saved to disk, with Remember me or from an earlier run
+    saved: bool,
+}
+
+/// Hand the session saved for `base_url`, if any, to `source`
+fn restore_session(base_url: &str, source: &impl DataSource) -> Option<Session> {
+    let entry = tokens::load_tokens(base_url)?;
+    source.send(ApiRequest::SetToken {
+        auth: entry.auth.clone(),
+        refresh: entry.refresh.clone(),
+        remember: true,
+    });
+    Some(Session {
+        auth: entry.auth,
+        refresh: entry.refresh,
+        saved: true,
+    })
+}
+
+/// Not Online, or on its way to another state
+fn is_troubled(inst: &InstanceInfo) -> bool {
+    inst.current_state != StateVariant::Online || inst.target_state != inst.current_state
+}
+
+fn online_instances(tiers: &[TierInfo]) -> HashSet<String> {
+    tiers
+        .iter()
+        .flat_map(|t| t.replicasets.iter())
+        .flat_map(|rs| rs.instances.iter())
+        .filter(|inst| inst.current_state == StateVariant::Online)
+        .map(|inst| inst.name.clone())
+        .collect()
+}
+
+/// Names of the Offline instances
+fn offline_instances(tiers: &[TierInfo]) -> HashSet<String> {
+    tiers
+        .iter()
+        .flat_map(|t| t.replicasets.iter())
+        .flat_map(|rs| rs.instances.iter())
+        .filter(|inst| inst.current_state == StateVariant::Offline)
+        .map(|inst| inst.name.clone())
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::api::ApiMessage;
+    use crate::source::HttpSource;
     use std::sync::mpsc::channel;
 
     /// Create a test app with saved token state
     fn test_app_with_saved_token() -> App {
         let (req_tx, _req_rx) = channel();
         let (_res_tx, res_rx) = channel();
-        let mut app = App::new("http://test:8080".to_string(), req_tx, res_rx);
+        let mut app = App::new(
+            "http://test:8080".to_string(),
+            HttpSource::new(req_tx, res_rx),
+        );
         app.has_saved_token = true;
         app.loading = true;
         app.auth_enabled = true;
@@ -711,6 +4045,50 @@ mod tests {
         app
     }
 
+    #[test]
+    fn test_login_warns_on_empty_password_and_throttles_failures() {
+        let (req_tx, req_rx) = channel();
+        let (_res_tx, res_rx) = channel();
+        let mut app = App::new(
+            "http://test:8080".to_string(),
+            HttpSource::new(req_tx, res_rx),
+        );
+        app.input_mode = InputMode::Login;
+        app.login_username = "admin".to_string();
+
+        // The first submit with an empty password only warns
+        app.submit_login();
+        assert!(app.login_password_warning.is_some());
+        assert!(req_rx.try_recv().is_err());
+        app.submit_login();
+        assert!(matches!(req_rx.try_recv(), Ok(ApiRequest::Login { .. })));
+
+        for attempt in 1..=LOGIN_THROTTLE_AFTER {
+            assert!(app.login_retry_in().is_none(), "attempt {}", attempt);
+            app.handle_response(ApiResponse::Login(Err(INVALID_CREDENTIALS.to_string())));
+        }
+        assert_eq!(app.login_failures, LOGIN_THROTTLE_AFTER);
+        let wait = app.login_retry_in().expect("throttled after repeated 401s");
+        assert!(wait <= LOGIN_THROTTLE_BASE);
+
+        app.login_password = "secret".to_string();
+        app.submit_login();
+        assert!(req_rx.try_recv().is_err(), "no request while throttled");
+
+        // Other failures don't count towards the limit
+        app.handle_response(ApiResponse::Login(
+            Err("Login failed: HTTP 500".to_string()),
+        ));
+        assert_eq!(app.login_failures, LOGIN_THROTTLE_AFTER);
+
+        app.login_retry_at = None;
+        app.handle_response(ApiResponse::Login(Ok(crate::models::TokenResponse {
+            auth: "a".to_string(),
+            refresh: "r".to_string(),
+        })));
+        assert_eq!(app.login_failures, 0);
+    }
+
     #[test]
     fn test_401_error_on_cluster_info_allows_relogin() {
         let mut app = test_app_with_saved_token();
@@ -762,6 +4140,177 @@ mod tests {
         assert!(app.login_error.is_some(), "should have login error message");
     }
 
+    #[test]
+    fn test_connection_status_tracks_failures() {
+        let mut app = test_app_with_saved_token();
+        let ok = ResponseMeta {
+            latency: Duration::from_millis(12),
+            reachable: true,
+            bytes: 0,
+        };
+        let failed = ResponseMeta {
+            latency: Duration::from_millis(5000),
+            reachable: false,
+            bytes: 0,
+        };
+
+        assert_eq!(app.connection_status, ConnectionStatus::Connecting);
+
+        app.record_meta(ok);
+        assert_eq!(app.connection_status, ConnectionStatus::Connected);
+        assert_eq!(app.last_latency, Some(Duration::from_millis(12)));
+
+        app.record_meta(failed);
+        assert_eq!(app.connection_status, ConnectionStatus::Reconnecting);
+
+        for _ in 1..CONNECTION_DOWN_THRESHOLD {
+            app.record_meta(failed);
+        }
+        assert_eq!(app.connection_status, ConnectionStatus::Down);
+
+        app.record_meta(ok);
+        assert_eq!(app.connection_status, ConnectionStatus::Connected);
+        assert_eq!(app.connection_failures, 0);
+    }
+
+    #[test]
+    fn test_refresh_backs_off_while_unreachable() {
+        let mut app = test_app_with_saved_token();
+        let base = Duration::from_secs(5);
+        let failed = ResponseMeta {
+            latency: Duration::from_millis(5000),
+            reachable: false,
+            bytes: 0,
+        };
+        let fail_refresh = |app: &mut App| {
+            app.request_refresh();
+            app.record_meta(failed);
+            app.handle_response(ApiResponse::ClusterInfo(Err("refused".to_string())));
+            app.record_meta(failed);
+            app.handle_response(ApiResponse::Tiers(Err("refused".to_string())));
+        };
+
+        fail_refresh(&mut app);
+        assert!(!app.is_backing_off(), "a single failure isn't enough");
+        assert_eq!(app.refresh_interval(base), base);
+
+        fail_refresh(&mut app);
+        assert!(app.is_backing_off());
+        assert_eq!(app.refresh_interval(base), Duration::from_secs(10));
+        fail_refresh(&mut app);
+        assert_eq!(app.refresh_interval(base), Duration::from_secs(20));
+        for _ in 0..10 {
+            fail_refresh(&mut app);
+        }
+        assert_eq!(app.refresh_interval(base), REFRESH_BACKOFF_MAX);
+
+        // The first answer from the server restores the normal interval
+        app.record_meta(ResponseMeta {
+            latency: Duration::from_millis(12),
+            reachable: true,
+            bytes: 0,
+        });
+        assert!(!app.is_backing_off());
+        assert_eq!(app.refresh_interval(base), base);
+    }
+
+    #[test]
+    fn test_responses_mark_ui_dirty() {
+        let (req_tx, _req_rx) = channel();
+        let (res_tx, res_rx) = channel();
+        let mut app = App::new(
+            "http://test:8080".to_string(),
+            HttpSource::new(req_tx, res_rx),
+        );
+        app.dirty = false;
+
+        app.process_responses();
+        assert!(!app.dirty, "no responses, nothing to redraw");
+
+        res_tx
+            .send(ApiMessage {
+                response: ApiResponse::HealthStatus(Err("boom".to_string())),
+                meta: ResponseMeta {
+                    latency: Duration::from_millis(1),
+                    reachable: true,
+                    bytes: 0,
+                },
+            })
+            .unwrap();
+        app.process_responses();
+        assert!(app.dirty, "a handled response should trigger a redraw");
+    }
+
+    #[test]
+    fn test_loading_lasts_until_every_refresh_request_finishes() {
+        let (req_tx, _req_rx) = channel();
+        let (_res_tx, res_rx) = channel();
+        let mut app = App::new(
+            "http://test:8080".to_string(),
+            HttpSource::new(req_tx, res_rx),
+        );
+
+        app.request_refresh();
+        app.handle_response(ApiResponse::ClusterInfo(Err("HTTP 500".to_string())));
+        assert!(app.loading, "tiers are still in flight");
+        assert_eq!(
+            app.refresh_progress,
+            vec![
+                (Endpoint::ClusterInfo, RequestProgress::Failed),
+                (Endpoint::Tiers, RequestProgress::Pending),
+            ]
+        );
+
+        app.handle_response(ApiResponse::NotModified(Endpoint::Tiers));
+        assert!(!app.loading);
+        assert_eq!(
+            app.refresh_progress[1],
+            (Endpoint::Tiers, RequestProgress::Done)
+        );
+    }
+
+    #[test]
+    fn test_stream_lifecycle_falls_back_to_polling() {
+        let (req_tx, req_rx) = channel();
+        let (_res_tx, res_rx) = channel();
+        let mut app = App::new(
+            "http://test:8080".to_string(),
+            HttpSource::new(req_tx, res_rx),
+        );
+        app.stream_path = Some("/api/v1/events".to_string());
+
+        app.handle_response(ApiResponse::Tiers(Ok(Vec::new())));
+        assert_eq!(app.stream_state, StreamState::Connecting);
+        assert!(req_rx
+            .try_iter()
+            .any(|r| matches!(r, ApiRequest::StartStream { .. })));
+
+        app.handle_response(ApiResponse::Stream(StreamEvent::Connected));
+        assert!(
+            app.is_streaming(),
+            "polling pauses while the stream is live"
+        );
+
+        app.handle_response(ApiResponse::Stream(StreamEvent::Disconnected(
+            "eof".to_string(),
+        )));
+        assert!(!app.is_streaming(), "polling resumes when the stream drops");
+
+        // Next successful poll reconnects
+        app.handle_response(ApiResponse::Tiers(Ok(Vec::new())));
+        assert_eq!(app.stream_state, StreamState::Connecting);
+
+        app.handle_response(ApiResponse::Stream(StreamEvent::Unavailable(
+            "HTTP 404".to_string(),
+        )));
+        app.handle_response(ApiResponse::Tiers(Ok(Vec::new())));
+        assert_eq!(
+            app.stream_state,
+            StreamState::Unavailable,
+            "no retries once the server has no stream endpoint"
+        );
+    }
+
     #[test]
     fn test_non_401_error_does_not_trigger_relogin() {
         let mut app = test_app_with_saved_token();
@@ -781,4 +4330,63 @@ mod tests {
         assert!(app.login_error.is_none(), "should not have login error");
         assert!(app.last_error.is_some(), "should have last_error set");
     }
+
+    #[test]
+    fn test_server_version_gates_capabilities() {
+        let mut app = test_app_with_saved_token();
+        assert!(
+            app.supports(Capability::ReplicasetState),
+            "assume support until the version is known"
+        );
+
+        let info: ClusterInfo = serde_json::from_value(serde_json::json!({
+            "capacityUsage": 0.0,
+            "clusterName": "test",
+            "clusterVersion": "1.0.0",
+            "currentInstaceVersion": "25.6.0",
+            "replicasetsCount": 0,
+            "instancesCurrentStateOffline": 0,
+            "instancesCurrentStateOnline": 0,
+            "memory": {"usable": 0, "used": 0},
+            "plugins": []
+        }))
+        .unwrap();
+        app.handle_response(ApiResponse::ClusterInfo(Ok(info)));
+
+        assert_eq!(app.server_version, Some(PicodataVersion::new(25, 6, 0)));
+        assert!(!app.supports(Capability::ReplicasetState));
+        assert!(!app.supports(Capability::RaftRoles));
+        assert!(app.compatibility_warning().is_none());
+    }
+
+    #[test]
+    fn test_ops_need_a_supported_server() {
+        let mut app = test_app_with_saved_token();
+        app.ops = true;
+        assert!(
+            app.can_change_cluster(),
+            "allowed until the version is known"
+        );
+
+        app.server_version = Some(PicodataVersion::new(26, 2, 0));
+        assert!(app.can_change_cluster());
+        app.server_version = Some(PicodataVersion::new(27, 1, 0));
+        assert!(!app.can_change_cluster());
+        app.server_version = Some(PicodataVersion::new(24, 1, 0));
+        assert!(!app.can_change_cluster());
+    }
+
+    #[test]
+    fn test_parse_error_mentions_newer_server() {
+        let mut app = test_app_with_saved_token();
+        app.server_version = Some(PicodataVersion::new(27, 1, 0));
+
+        app.handle_response(ApiResponse::Tiers(Err(
+            "Failed to parse tiers: missing field `name`".to_string(),
+        )));
+
+        let error = app.last_error.unwrap();
+        assert!(error.contains("missing field"));
+        assert!(error.contains("newer than picotui understands"));
+    }
 }
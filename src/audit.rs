@@ -0,0 +1,80 @@
+//! Local audit trail of what the user did: logins, logouts, manual
+//! refreshes, exports and instance actions. Unlike the `--debug` log it is
+//! always written and kept across runs, one JSON object per line.
+
+use crate::paths;
+use crate::private_files;
+use anyhow::{Context, Result};
+use chrono::Utc;
+use serde::Serialize;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+#[derive(Serialize)]
+struct AuditEntry<'a> {
+    time: String,
+    url: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    user: Option<&'a str>,
+    action: &'a str,
+    #[serde(skip_serializing_if = "str::is_empty")]
+    detail: &'a str,
+}
+
+/// Default location of the audit log, next to the saved sessions
+pub fn audit_log_path() -> Option<PathBuf> {
+    paths::state_dir().map(|dir| dir.join("audit.log"))
+}
+
+/// Append one entry for the cluster at `url` to the log at `path`
+pub fn append(
+    path: &Path,
+    url: &str,
+    user: Option<&str>,
+    action: &str,
+    detail: &str,
+) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        private_files::create_private_dir(parent)?;
+    }
+    let entry = AuditEntry {
+        time: Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+        url: url.trim_end_matches('/'),
+        user,
+        action,
+        detail,
+    };
+    let mut line = serde_json::to_string(&entry)?;
+    line.push('\n');
+    private_files::append_private_file(path)
+        .and_then(|mut file| file.write_all(line.as_bytes()))
+        .with_context(|| format!("Could not write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entries_are_appended_as_json_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audit.log");
+
+        append(&path, "http://a:8080/", None, "start", "").unwrap();
+        append(&path, "http://a:8080", Some("admin"), "login", "ok").unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<serde_json::Value> = contents
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["url"], "http://a:8080");
+        assert_eq!(lines[0]["action"], "start");
+        assert!(lines[0].get("user").is_none());
+        assert!(lines[0].get("detail").is_none());
+        assert_eq!(lines[1]["user"], "admin");
+        assert_eq!(lines[1]["detail"], "ok");
+        assert!(lines[1]["time"].as_str().unwrap().ends_with('Z'));
+    }
+}
@@ -0,0 +1,71 @@
+use anyhow::{Context, Result};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+
+/// Tree positions by letter: the names from the tier down to the bookmarked
+/// node, so `["default", "r1"]` is replicaset r1 of tier default
+pub type Bookmarks = BTreeMap<char, Vec<String>>;
+
+/// Bookmarks for each cluster, keyed by normalized URL
+type BookmarksFile = HashMap<String, Bookmarks>;
+
+/// Default location of the bookmarks file
+pub fn bookmarks_file_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|p| p.join("picotui/bookmarks.json"))
+}
+
+fn read_bookmarks_file(path: &Path) -> BookmarksFile {
+    File::open(path)
+        .ok()
+        .and_then(|file| serde_json::from_reader(file).ok())
+        .unwrap_or_default()
+}
+
+/// Load the bookmarks set for the cluster at `url`
+pub fn load_bookmarks(path: &Path, url: &str) -> Bookmarks {
+    read_bookmarks_file(path)
+        .remove(url.trim_end_matches('/'))
+        .unwrap_or_default()
+}
+
+/// Save the bookmarks for the cluster at `url`, keeping other clusters' ones
+pub fn save_bookmarks(path: &Path, url: &str, bookmarks: &Bookmarks) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut all = read_bookmarks_file(path);
+    let normalized_url = url.trim_end_matches('/').to_string();
+    if bookmarks.is_empty() {
+        all.remove(&normalized_url);
+    } else {
+        all.insert(normalized_url, bookmarks.clone());
+    }
+
+    let file = File::create(path).with_context(|| format!("Could not write {}", path.display()))?;
+    serde_json::to_writer_pretty(file, &all)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bookmarks_are_kept_per_cluster() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bookmarks.json");
+        assert!(load_bookmarks(&path, "http://a:8080").is_empty());
+
+        let a = Bookmarks::from([
+            ('a', vec!["default".to_string(), "r1".to_string()]),
+            ('b', vec!["storage".to_string()]),
+        ]);
+        save_bookmarks(&path, "http://a:8080/", &a).unwrap();
+        save_bookmarks(&path, "http://b:8080", &Bookmarks::new()).unwrap();
+
+        assert_eq!(load_bookmarks(&path, "http://a:8080"), a);
+        assert!(load_bookmarks(&path, "http://b:8080").is_empty());
+    }
+}
@@ -0,0 +1,177 @@
+//! Injected latency and failures in the API worker (`--inject-latency`,
+//! `--inject-errors`, debug builds only), to watch spinners, backoff and
+//! stale markers on a perfectly healthy network.
+
+use crate::client::{ResponseMeta, Timed};
+use anyhow::{anyhow, Result};
+use std::sync::OnceLock;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Error of a request failed on purpose
+pub const INJECTED_FAILURE: &str = "Injected failure (--inject-errors)";
+
+static CHAOS: OnceLock<Chaos> = OnceLock::new();
+
+#[derive(Debug, Clone, Default)]
+pub struct Chaos {
+    /// Added before every request
+    pub latency: Duration,
+    /// Share of requests failed as if the server didn't answer, 0 to 1
+    pub error_rate: f64,
+    /// xorshift state for failures
+    rng: u64,
+}
+
+/// Make every API worker started from now on misbehave. Only the first
+/// call has an effect.
+pub fn set(latency: Duration, error_rate: f64) {
+    let _ = CHAOS.set(Chaos::new(latency, error_rate, 0));
+}
+
+/// What API workers should inject: nothing unless `set` was called
+pub fn current() -> Chaos {
+    let seed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos() as u64;
+    match CHAOS.get() {
+        Some(chaos) => Chaos::new(chaos.latency, chaos.error_rate, seed),
+        None => Chaos::default(),
+    }
+}
+
+impl Chaos {
+    /// Failures drawn from `seed`
+    pub fn new(latency: Duration, error_rate: f64, seed: u64) -> Self {
+        Self {
+            latency,
+            error_rate,
+            rng: seed | 1,
+        }
+    }
+
+    /// Make `call` late, or fail it without making it
+    pub fn apply<T>(&mut self, call: impl FnOnce() -> Timed<T>) -> Timed<T> {
+        if !self.latency.is_zero() {
+            thread::sleep(self.latency);
+        }
+        let mut timed = if self.chance(self.error_rate) {
+            Timed {
+                result: Err(INJECTED_FAILURE.to_string()),
+                meta: ResponseMeta {
+                    latency: Duration::ZERO,
+                    reachable: false,
+                    bytes: 0,
+                },
+            }
+        } else {
+            call()
+        };
+        timed.meta.latency += self.latency;
+        timed
+    }
+
+    /// True with probability `p`
+    fn chance(&mut self, p: f64) -> bool {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+        p > 0.0 && (self.rng % 1_000_000) as f64 / 1_000_000.0 < p
+    }
+}
+
+/// `--inject-latency`: `500ms`, `2s`, or plain milliseconds
+pub fn parse_latency(s: &str) -> Result<Duration> {
+    let s = s.trim();
+    let (number, scale) = if let Some(ms) = s.strip_suffix("ms") {
+        (ms, 1)
+    } else if let Some(secs) = s.strip_suffix('s') {
+        (secs, 1000)
+    } else {
+        (s, 1)
+    };
+    number
+        .trim()
+        .parse::<u64>()
+        .map(|n| Duration::from_millis(n * scale))
+        .map_err(|_| anyhow!("Invalid latency '{}' (expected e.g. 500ms or 2s)", s))
+}
+
+/// `--inject-errors`: a percentage such as `10%`, 0 to 100
+pub fn parse_error_rate(s: &str) -> Result<f64> {
+    let s = s.trim();
+    s.strip_suffix('%')
+        .unwrap_or(s)
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|percent| (0.0..=100.0).contains(percent))
+        .map(|percent| percent / 100.0)
+        .ok_or_else(|| anyhow!("Invalid error rate '{}' (expected 0% to 100%)", s))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ok() -> Timed<()> {
+        Timed {
+            result: Ok(()),
+            meta: ResponseMeta {
+                latency: Duration::from_millis(3),
+                reachable: true,
+                bytes: 10,
+            },
+        }
+    }
+
+    #[test]
+    fn test_parse_latency() {
+        assert_eq!(parse_latency("500ms").unwrap(), Duration::from_millis(500));
+        assert_eq!(parse_latency("2s").unwrap(), Duration::from_secs(2));
+        assert_eq!(parse_latency("250").unwrap(), Duration::from_millis(250));
+        assert!(parse_latency("fast").is_err());
+        assert!(parse_latency("-5ms").is_err());
+    }
+
+    #[test]
+    fn test_parse_error_rate() {
+        assert_eq!(parse_error_rate("10%").unwrap(), 0.1);
+        assert_eq!(parse_error_rate("100").unwrap(), 1.0);
+        assert!(parse_error_rate("150%").is_err());
+        assert!(parse_error_rate("often").is_err());
+    }
+
+    #[test]
+    fn test_no_chaos_passes_calls_through() {
+        let timed = Chaos::default().apply(ok);
+        assert!(timed.result.is_ok());
+        assert_eq!(timed.meta.latency, Duration::from_millis(3));
+    }
+
+    #[test]
+    fn test_injected_failures_look_unreachable() {
+        let mut chaos = Chaos::new(Duration::ZERO, 1.0, 42);
+        let timed = chaos.apply(|| -> Timed<()> { panic!("the call must not be made") });
+        assert_eq!(timed.result.unwrap_err(), INJECTED_FAILURE);
+        assert!(!timed.meta.reachable);
+    }
+
+    #[test]
+    fn test_error_rate_fails_a_share_of_calls() {
+        let mut chaos = Chaos::new(Duration::ZERO, 0.25, 7);
+        let failed = (0..4000)
+            .filter(|_| chaos.apply(ok).result.is_err())
+            .count();
+        assert!((800..1200).contains(&failed), "{} of 4000 failed", failed);
+    }
+
+    #[test]
+    fn test_latency_is_added_and_reported() {
+        let mut chaos = Chaos::new(Duration::from_millis(20), 0.0, 1);
+        let timed = chaos.apply(ok);
+        assert!(timed.result.is_ok());
+        assert_eq!(timed.meta.latency, Duration::from_millis(23));
+    }
+}
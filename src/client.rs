@@ -0,0 +1,518 @@
+//! Blocking client for the Picodata HTTP API.
+//!
+//! This is the data layer the TUI's API worker is built on, usable on its
+//! own by tools that need cluster data without a terminal:
+//!
+//! ```no_run
+//! use picotui::client::{Client, Fetched};
+//!
+//! let mut client = Client::new("http://localhost:8080");
+//! if client.get_config().result?.is_auth_enabled {
+//!     client.login("admin", "secret").result?;
+//! }
+//! if let Fetched::Data(tiers) = client.get_tiers().result? {
+//!     println!("{} tiers", tiers.len());
+//! }
+//! # Ok::<(), String>(())
+//! ```
+//!
+//! Calls return the parsed data together with timing metadata. The client
+//! keeps the session token and the ETags of polled endpoints, so repeated
+//! `get_cluster_info`/`get_tiers` calls can come back `NotModified`.
+//! Other addresses of the same cluster can be given (or discovered from
+//! the tiers) to switch to with `fail_over` when one stops answering.
+
+use crate::api::log_debug;
+use crate::models::*;
+use crate::schema;
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Login error for rejected credentials (HTTP 401)
+pub const INVALID_CREDENTIALS: &str = "Invalid username or password. Try again.";
+
+/// What a reachable server revealed about itself before login. Both fields
+/// are None when the cluster endpoint requires a session.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ServerIdentity {
+    pub cluster_name: Option<String>,
+    pub version: Option<String>,
+}
+
+/// Timing metadata attached to every call
+#[derive(Debug, Clone, Copy)]
+pub struct ResponseMeta {
+    /// Time spent waiting for the HTTP round trip
+    pub latency: Duration,
+    /// Whether the server answered at all (any HTTP status counts)
+    pub reachable: bool,
+    /// Size of the response body that was read
+    pub bytes: u64,
+}
+
+impl ResponseMeta {
+    fn from_result<T>(started: Instant, result: &Result<T, ureq::Error>) -> Self {
+        let reachable = matches!(result, Ok(_) | Err(ureq::Error::StatusCode(_)));
+        Self {
+            latency: started.elapsed(),
+            reachable,
+            bytes: 0,
+        }
+    }
+}
+
+/// Result of a call with its timing metadata. Errors are messages ready to
+/// show to the user.
+#[derive(Debug)]
+pub struct Timed<T> {
+    pub result: Result<T, String>,
+    pub meta: ResponseMeta,
+}
+
+/// Data from a polled endpoint
+#[derive(Debug)]
+pub enum Fetched<T> {
+    Data(T),
+    /// Identical to the previous response: the server answered 304, or the
+    /// body was the same with `skip_unchanged` on
+    NotModified,
+}
+
+/// Blocking client for one cluster
+pub struct Client {
+    agent: ureq::Agent,
+    base_url: String,
+    auth_token: Option<String>,
+    /// Last ETag seen per URL, sent back as If-None-Match
+    etags: HashMap<String, String>,
+    /// Fingerprint of the last body parsed per URL, once `skip_unchanged`
+    /// is called
+    last_bodies: Option<HashMap<String, Fingerprint>>,
+    /// Other base URLs of the same cluster, in the order to try them
+    fallbacks: Vec<String>,
+    /// Add the instances of each tiers response to `fallbacks`
+    discover: bool,
+    debug: bool,
+}
+
+impl Client {
+    pub fn new(base_url: &str) -> Self {
+        let config = ureq::Agent::config_builder()
+            .timeout_connect(Some(Duration::from_secs(5)))
+            .timeout_recv_response(Some(Duration::from_secs(10)))
+            .build();
+        Self {
+            agent: config.new_agent(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            auth_token: None,
+            etags: HashMap::new(),
+            last_bodies: None,
+            fallbacks: Vec::new(),
+            discover: false,
+            debug: false,
+        }
+    }
+
+    /// Log requests and responses to the debug log
+    pub fn with_debug(mut self, debug: bool) -> Self {
+        self.debug = debug;
+        self
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Session token sent with requests, from `login` or `set_token`
+    pub fn token(&self) -> Option<&str> {
+        self.auth_token.as_deref()
+    }
+
+    /// Use a session token obtained earlier, e.g. a saved one
+    pub fn set_token(&mut self, auth: &str) {
+        self.auth_token = Some(auth.to_string());
+        self.forget_responses();
+    }
+
+    /// From now on report a full response whose body is identical to the
+    /// previous one for the same URL as `NotModified`, for servers that
+    /// don't send ETags
+    pub fn skip_unchanged(&mut self) {
+        self.last_bodies.get_or_insert_with(HashMap::new);
+    }
+
+    /// Other base URLs of the same cluster to switch to with `fail_over`
+    pub fn set_fallbacks(&mut self, urls: &[String]) {
+        for url in urls {
+            self.add_fallback(url.trim_end_matches('/'));
+        }
+    }
+
+    /// From now on add the HTTP address of every instance in a tiers
+    /// response to the fallbacks, so one seed address is enough
+    pub fn discover_peers(&mut self) {
+        self.discover = true;
+    }
+
+    pub fn fallbacks(&self) -> &[String] {
+        &self.fallbacks
+    }
+
+    /// Switch to the next fallback, which moves the current URL to the end
+    /// of the list. False when there is nothing to switch to.
+    pub fn fail_over(&mut self) -> bool {
+        if self.fallbacks.is_empty() {
+            return false;
+        }
+        let next = self.fallbacks.remove(0);
+        let previous = std::mem::replace(&mut self.base_url, next);
+        self.fallbacks.push(previous);
+        log_debug(self.debug, &format!("Failing over to {}", self.base_url));
+        true
+    }
+
+    fn add_fallback(&mut self, url: &str) {
+        if url != self.base_url && !self.fallbacks.iter().any(|known| known == url) {
+            self.fallbacks.push(url.to_string());
+        }
+    }
+
+    /// Learn the instances' addresses, with the scheme of the current URL
+    fn learn_peers(&mut self, tiers: &[TierInfo]) {
+        let scheme = self
+            .base_url
+            .split_once("://")
+            .map_or("http", |(scheme, _)| scheme)
+            .to_string();
+        let before = self.fallbacks.len();
+        for instance in tiers
+            .iter()
+            .flat_map(|tier| tier.replicasets.iter())
+            .flat_map(|replicaset| replicaset.instances.iter())
+            .filter(|instance| !instance.http_address.is_empty())
+        {
+            self.add_fallback(&format!("{}://{}", scheme, instance.http_address));
+        }
+        if self.fallbacks.len() > before {
+            log_debug(
+                self.debug,
+                &format!(
+                    "  discovered {} new address(es)",
+                    self.fallbacks.len() - before
+                ),
+            );
+        }
+    }
+
+    /// Responses seen under another session say nothing about this one
+    fn forget_responses(&mut self) {
+        self.etags.clear();
+        if let Some(bodies) = &mut self.last_bodies {
+            bodies.clear();
+        }
+    }
+
+    fn get(&self, url: &str) -> ureq::RequestBuilder<ureq::typestate::WithoutBody> {
+        let mut req = self.agent.get(url);
+        if let Some(ref token) = self.auth_token {
+            req = req.header("Authorization", &format!("Bearer {}", token));
+        }
+        req
+    }
+
+    pub fn get_config(&mut self) -> Timed<UiConfig> {
+        let url = format!("{}/api/v1/config", self.base_url);
+        log_debug(self.debug, &format!("GET {}", url));
+
+        let started = Instant::now();
+        let result = self.agent.get(&url).call();
+        let mut meta = ResponseMeta::from_result(started, &result);
+        let result = match result {
+            Ok(resp) => match read_json::<UiConfig>(resp, &mut meta) {
+                Ok(config) => {
+                    log_debug(self.debug, "  OK: config received");
+                    Ok(config)
+                }
+                Err(e) => {
+                    log_debug(self.debug, &format!("  PARSE ERROR: {}", e));
+                    Err(format!("Failed to parse config: {}", e))
+                }
+            },
+            Err(e) => {
+                log_debug(self.debug, &format!("  ERROR: {}", e));
+                Err(format!("Failed to get config: {}", e))
+            }
+        };
+        Timed { result, meta }
+    }
+
+    /// Log in and use the new session for the following requests
+    pub fn login(&mut self, username: &str, password: &str) -> Timed<TokenResponse> {
+        let url = format!("{}/api/v1/session", self.base_url);
+        log_debug(self.debug, &format!("POST {} (user={})", url, username));
+
+        let req_body = LoginRequest {
+            username: username.to_string(),
+            password: password.to_string(),
+        };
+        let started = Instant::now();
+        let result = self
+            .agent
+            .post(&url)
+            .header("Content-Type", "application/json")
+            .send_json(&req_body);
+        let mut meta = ResponseMeta::from_result(started, &result);
+
+        let result = match result {
+            Ok(resp) => match read_json::<TokenResponse>(resp, &mut meta) {
+                Ok(token_resp) => {
+                    log_debug(self.debug, "  OK: tokens received");
+                    self.set_token(&token_resp.auth);
+                    Ok(token_resp)
+                }
+                Err(e) => {
+                    log_debug(self.debug, &format!("  PARSE ERROR: {}", e));
+                    Err(format!("Failed to parse tokens: {}", e))
+                }
+            },
+            Err(ureq::Error::StatusCode(status)) => {
+                log_debug(self.debug, &format!("  ERROR: HTTP {}", status));
+                if status == 401 {
+                    Err(INVALID_CREDENTIALS.to_string())
+                } else {
+                    Err(format!("Login failed: HTTP {}", status))
+                }
+            }
+            Err(e) => {
+                log_debug(self.debug, &format!("  ERROR: {}", e));
+                Err(format!("Login failed: {}", e))
+            }
+        };
+        Timed { result, meta }
+    }
+
+    /// Check the server is reachable, and identify the cluster if it
+    /// answers without a session
+    pub fn check_server(&mut self) -> Timed<ServerIdentity> {
+        let url = format!("{}/api/v1/config", self.base_url);
+        log_debug(self.debug, &format!("GET {} (server check)", url));
+
+        let started = Instant::now();
+        let result = self.agent.get(&url).call();
+        let meta = ResponseMeta::from_result(started, &result);
+        let result = match result {
+            Ok(_) => {
+                let url = format!("{}/api/v1/cluster", self.base_url);
+                // Identity is a bonus; the server is reachable either way
+                let info = self
+                    .get(&url)
+                    .call()
+                    .ok()
+                    .and_then(|resp| resp.into_body().read_json::<ClusterInfo>().ok());
+                log_debug(
+                    self.debug,
+                    &format!("  OK: reachable, identified={}", info.is_some()),
+                );
+                Ok(ServerIdentity {
+                    cluster_name: info.as_ref().map(|i| i.cluster_name.clone()),
+                    version: info.map(|i| i.cluster_version),
+                })
+            }
+            Err(e) => {
+                log_debug(self.debug, &format!("  ERROR: {}", e));
+                Err(e.to_string())
+            }
+        };
+        Timed { result, meta }
+    }
+
+    pub fn get_cluster_info(&mut self) -> Timed<Fetched<ClusterInfo>> {
+        let url = format!("{}/api/v1/cluster", self.base_url);
+        self.get_polled(&url, "cluster info", |_| {
+            "cluster info received".to_string()
+        })
+    }
+
+    pub fn get_tiers(&mut self) -> Timed<Fetched<Vec<TierInfo>>> {
+        let url = format!("{}/api/v1/tiers", self.base_url);
+        let call = self.get_polled(&url, "tiers", |tiers: &Vec<TierInfo>| {
+            format!("{} tiers received", tiers.len())
+        });
+        if let (true, Ok(Fetched::Data(tiers))) = (self.discover, &call.result) {
+            self.learn_peers(tiers);
+        }
+        call
+    }
+
+    /// GET a polled endpoint with a conditional request. `what` names the
+    /// data in error messages.
+    fn get_polled<T: DeserializeOwned>(
+        &mut self,
+        url: &str,
+        what: &str,
+        received: impl Fn(&T) -> String,
+    ) -> Timed<Fetched<T>> {
+        log_debug(self.debug, &format!("GET {}", url));
+
+        let mut req = self.get(url);
+        if let Some(etag) = self.etags.get(url) {
+            req = req.header("If-None-Match", etag);
+        }
+
+        let started = Instant::now();
+        let result = req.call();
+        let mut meta = ResponseMeta::from_result(started, &result);
+        let result = match result {
+            Ok(resp) if resp.status() == 304 => {
+                log_debug(self.debug, "  OK: not modified");
+                Ok(Fetched::NotModified)
+            }
+            Ok(resp) => {
+                remember_etag(&mut self.etags, url, &resp);
+                match read_json_changed::<T>(resp, &mut meta, self.last_bodies.as_mut(), url) {
+                    Ok(None) => {
+                        log_debug(self.debug, "  OK: unchanged");
+                        Ok(Fetched::NotModified)
+                    }
+                    Ok(Some(data)) => {
+                        log_debug(self.debug, &format!("  OK: {}", received(&data)));
+                        Ok(Fetched::Data(data))
+                    }
+                    Err(e) => {
+                        self.etags.remove(url);
+                        log_debug(self.debug, &format!("  PARSE ERROR: {}", e));
+                        Err(format!("Failed to parse {}: {}", what, e))
+                    }
+                }
+            }
+            Err(e) => {
+                log_debug(self.debug, &format!("  ERROR: {}", e));
+                Err(format!("Failed to get {}: {}", what, e))
+            }
+        };
+        Timed { result, meta }
+    }
+
+    /// Health of one instance, fetched from its own HTTP address
+    pub fn get_health_status(&mut self, http_address: &str) -> Timed<HealthStatus> {
+        let url = format!("http://{}/api/v1/health/status", http_address);
+        log_debug(self.debug, &format!("GET {}", url));
+
+        let started = Instant::now();
+        let result = self.get(&url).call();
+        let mut meta = ResponseMeta::from_result(started, &result);
+        let result = match result {
+            Ok(resp) => match read_json::<HealthStatus>(resp, &mut meta) {
+                Ok(status) => {
+                    log_debug(
+                        self.debug,
+                        &format!("  OK: health status {:?}", status.status),
+                    );
+                    Ok(status)
+                }
+                Err(e) => {
+                    log_debug(self.debug, &format!("  PARSE ERROR: {}", e));
+                    Err(format!("Failed to parse health status: {}", e))
+                }
+            },
+            Err(e) => {
+                log_debug(self.debug, &format!("  ERROR: {}", e));
+                Err(format!("Failed to get health status: {}", e))
+            }
+        };
+        Timed { result, meta }
+    }
+}
+
+/// Read a JSON body, counting its size into `meta`
+fn read_json<T: DeserializeOwned>(
+    resp: ureq::http::Response<ureq::Body>,
+    meta: &mut ResponseMeta,
+) -> Result<T, ureq::Error> {
+    let body = resp.into_body().read_to_vec()?;
+    meta.bytes = body.len() as u64;
+    schema::from_slice(&body).map_err(ureq::Error::Json)
+}
+
+/// Like `read_json`, but `None` when `bodies` is given and the body is
+/// the same as the last one parsed for `url`
+fn read_json_changed<T: DeserializeOwned>(
+    resp: ureq::http::Response<ureq::Body>,
+    meta: &mut ResponseMeta,
+    bodies: Option<&mut HashMap<String, Fingerprint>>,
+    url: &str,
+) -> Result<Option<T>, ureq::Error> {
+    let body = resp.into_body().read_to_vec()?;
+    meta.bytes = body.len() as u64;
+    let Some(bodies) = bodies else {
+        return schema::from_slice(&body)
+            .map(Some)
+            .map_err(ureq::Error::Json);
+    };
+
+    let fingerprint = Fingerprint::of(&body);
+    if bodies.get(url) == Some(&fingerprint) {
+        return Ok(None);
+    }
+    match schema::from_slice(&body) {
+        Ok(value) => {
+            bodies.insert(url.to_string(), fingerprint);
+            Ok(Some(value))
+        }
+        Err(e) => {
+            bodies.remove(url);
+            Err(ureq::Error::Json(e))
+        }
+    }
+}
+
+/// Length and hash of a response body, kept instead of the body to tell
+/// an unchanged response without holding on to a copy of it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fingerprint {
+    len: usize,
+    hash: u64,
+}
+
+impl Fingerprint {
+    /// Four independent lanes of a word each, several times faster than
+    /// SipHash on a large body. Each step is a bijection of its word, so
+    /// bodies of the same length differing in a single word never collide.
+    pub fn of(body: &[u8]) -> Self {
+        fn mix(hash: u64, word: u64) -> u64 {
+            (hash.rotate_left(5) ^ word).wrapping_mul(0x517c_c1b7_2722_0a95)
+        }
+        let mut lanes = [0u64, 1, 2, 3];
+        let mut blocks = body.chunks_exact(32);
+        for block in &mut blocks {
+            for (lane, word) in lanes.iter_mut().zip(block.chunks_exact(8)) {
+                *lane = mix(*lane, u64::from_le_bytes(word.try_into().unwrap()));
+            }
+        }
+        let mut hash = lanes.into_iter().fold(0, mix);
+        for &byte in blocks.remainder() {
+            hash = mix(hash, u64::from(byte));
+        }
+        Self {
+            len: body.len(),
+            hash,
+        }
+    }
+}
+
+/// Store the response's ETag (if any) for the next conditional request
+fn remember_etag<B>(
+    etags: &mut HashMap<String, String>,
+    url: &str,
+    resp: &ureq::http::Response<B>,
+) {
+    match resp.headers().get("ETag").and_then(|v| v.to_str().ok()) {
+        Some(etag) => {
+            etags.insert(url.to_string(), etag.to_string());
+        }
+        None => {
+            etags.remove(url);
+        }
+    }
+}
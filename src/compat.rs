@@ -0,0 +1,132 @@
+use std::fmt;
+
+/// Oldest Picodata release picotui is known to work with
+pub const OLDEST_SUPPORTED: PicodataVersion = PicodataVersion::new(25, 1, 0);
+
+/// Newest Picodata release picotui understands; newer servers may change the API
+pub const NEWEST_KNOWN: PicodataVersion = PicodataVersion::new(26, 2, u32::MAX);
+
+/// Picodata release version (major.minor.patch)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct PicodataVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl PicodataVersion {
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    /// Parse a version string such as "26.2.0" or "25.6.1-12-gabcdef".
+    /// Missing minor/patch components default to 0.
+    pub fn parse(s: &str) -> Option<Self> {
+        let core = s.trim().split(['-', '+', ' ']).next()?;
+        let mut parts = core.split('.');
+        let major = parts.next()?.parse().ok()?;
+        let minor = parts.next().map_or(Some(0), |p| p.parse().ok())?;
+        let patch = parts.next().map_or(Some(0), |p| p.parse().ok())?;
+        Some(Self::new(major, minor, patch))
+    }
+}
+
+impl fmt::Display for PicodataVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// Optional features that depend on the server version
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Capability {
+    /// Raft voter/leader flags on instances
+    RaftRoles,
+    /// Replicaset state from _pico_replicaset
+    ReplicasetState,
+}
+
+impl Capability {
+    /// First Picodata release providing the capability
+    pub fn since(self) -> PicodataVersion {
+        match self {
+            Capability::RaftRoles | Capability::ReplicasetState => PicodataVersion::new(26, 2, 0),
+        }
+    }
+}
+
+/// How a server version relates to what picotui understands
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compatibility {
+    Supported,
+    TooOld,
+    TooNew,
+}
+
+pub fn check(version: PicodataVersion) -> Compatibility {
+    if version < OLDEST_SUPPORTED {
+        Compatibility::TooOld
+    } else if version > NEWEST_KNOWN {
+        Compatibility::TooNew
+    } else {
+        Compatibility::Supported
+    }
+}
+
+/// Human-readable warning for an unsupported server, if any
+pub fn warning(version: PicodataVersion) -> Option<String> {
+    match check(version) {
+        Compatibility::Supported => None,
+        Compatibility::TooOld => Some(format!(
+            "Picodata {} is older than supported ({}+); some data may be missing",
+            version, OLDEST_SUPPORTED
+        )),
+        Compatibility::TooNew => Some(format!(
+            "Picodata {} is newer than picotui understands (up to {}.{}); consider upgrading picotui",
+            version, NEWEST_KNOWN.major, NEWEST_KNOWN.minor
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_versions() {
+        assert_eq!(
+            PicodataVersion::parse("26.2.0"),
+            Some(PicodataVersion::new(26, 2, 0))
+        );
+        assert_eq!(
+            PicodataVersion::parse("25.6.1-12-gabcdef"),
+            Some(PicodataVersion::new(25, 6, 1))
+        );
+        assert_eq!(
+            PicodataVersion::parse("26"),
+            Some(PicodataVersion::new(26, 0, 0))
+        );
+        assert_eq!(PicodataVersion::parse("unknown"), None);
+    }
+
+    #[test]
+    fn test_compatibility_range() {
+        assert_eq!(check(PicodataVersion::new(24, 1, 0)), Compatibility::TooOld);
+        assert_eq!(
+            check(PicodataVersion::new(25, 6, 0)),
+            Compatibility::Supported
+        );
+        assert_eq!(
+            check(PicodataVersion::new(26, 2, 7)),
+            Compatibility::Supported
+        );
+        assert_eq!(check(PicodataVersion::new(26, 3, 0)), Compatibility::TooNew);
+        assert!(warning(PicodataVersion::new(27, 1, 0))
+            .unwrap()
+            .contains("newer"));
+    }
+}
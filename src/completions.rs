@@ -0,0 +1,531 @@
+//! Shell completions (`picotui completions bash|zsh|fish`), generated from
+//! the `--help` text so every option added there is completed as well.
+
+use anyhow::anyhow;
+use std::fmt::Write;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl FromStr for Shell {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "bash" => Ok(Shell::Bash),
+            "zsh" => Ok(Shell::Zsh),
+            "fish" => Ok(Shell::Fish),
+            _ => Err(anyhow!(
+                "Unknown shell '{}' (expected bash, zsh or fish)",
+                s
+            )),
+        }
+    }
+}
+
+/// Values of the options that take one of a fixed set
+const CHOICES: &[(&str, &[&str])] = &[
+    ("view", &["tiers", "replicasets", "instances"]),
+    ("units", &["binary", "si"]),
+    ("time", &["relative", "local", "utc"]),
+    ("lang", &["en", "ru"]),
+    ("color", &["auto", "always", "never", "16"]),
+    ("bell", &["bell", "flash", "both"]),
+    ("wait-until", &["all-online", "converged", "all-ready"]),
+    ("iproto-auth", &["md5", "chap-sha1", "ldap"]),
+];
+
+/// An option as `--help` lists it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CliOption {
+    pub short: Option<char>,
+    /// Without the leading dashes
+    pub long: String,
+    /// Placeholder of the value, e.g. `URL`; `None` for flags
+    pub value: Option<String>,
+    /// First line of the description
+    pub help: String,
+}
+
+/// A command as `--help` lists it, with the words that may follow it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CliCommand {
+    pub name: String,
+    pub subcommands: Vec<String>,
+    pub help: String,
+}
+
+/// What the options can be completed with besides the fixed choices
+#[derive(Debug, Clone, Default)]
+pub struct Dynamic {
+    /// Saved filters from the config file, offered for `--filter`
+    pub filters: Vec<String>,
+}
+
+/// Options under OPTIONS and COMMANDS (subcommand options are indented
+/// further) in the help text
+pub fn options(help: &str) -> Vec<CliOption> {
+    let lines: Vec<&str> = help.lines().collect();
+    let mut options = Vec::new();
+    for (idx, line) in lines.iter().enumerate() {
+        let spec_line = line.trim_start();
+        if !spec_line.starts_with('-') {
+            continue;
+        }
+        let (spec, text) = match spec_line.split_once("  ") {
+            Some((spec, text)) => (spec, text.trim()),
+            // The description didn't fit and starts on the next line
+            None => (spec_line, lines.get(idx + 1).map_or("", |l| l.trim())),
+        };
+        let (short, long) = match spec.split_once(", ") {
+            Some((short, long)) => (short.strip_prefix('-').and_then(|s| s.chars().next()), long),
+            None => (None, spec),
+        };
+        let Some(long) = long.strip_prefix("--") else {
+            continue;
+        };
+        let (long, value) = match long.split_once(' ') {
+            Some((long, value)) => (long, Some(value.replace(['<', '>'], ""))),
+            None => (long, None),
+        };
+        // Commands may share an option, e.g. --listen
+        if options.iter().any(|option: &CliOption| option.long == long) {
+            continue;
+        }
+        options.push(CliOption {
+            short,
+            long: long.to_string(),
+            value,
+            help: text.to_string(),
+        });
+    }
+    options
+}
+
+/// Commands under COMMANDS in the help text; `tokens list` and `tokens
+/// clear` make one command with two subcommands
+pub fn commands(help: &str) -> Vec<CliCommand> {
+    let mut commands: Vec<CliCommand> = Vec::new();
+    let section = help
+        .split_once("COMMANDS:")
+        .map_or("", |(_, rest)| rest.split("\n\n").next().unwrap_or(""));
+    let lines: Vec<&str> = section.lines().collect();
+    for (idx, line) in lines.iter().enumerate() {
+        // Commands are indented by four spaces, their descriptions and
+        // options further
+        let Some(entry) = line.strip_prefix("    ") else {
+            continue;
+        };
+        if entry.starts_with(' ') || entry.starts_with('-') {
+            continue;
+        }
+        let (spec, text) = match entry.split_once("  ") {
+            Some((spec, text)) => (spec, text.trim()),
+            None => (entry, lines.get(idx + 1).map_or("", |l| l.trim())),
+        };
+        let mut words = spec.split_whitespace();
+        let Some(name) = words.next() else {
+            continue;
+        };
+        let subcommand = words
+            .next()
+            .filter(|w| w.chars().all(|c| c.is_ascii_lowercase() || c == '|'));
+        let command = match commands.iter_mut().find(|c| c.name == name) {
+            Some(command) => command,
+            None => {
+                commands.push(CliCommand {
+                    name: name.to_string(),
+                    subcommands: Vec::new(),
+                    help: text.to_string(),
+                });
+                commands.last_mut().unwrap()
+            }
+        };
+        if let Some(subcommand) = subcommand {
+            command
+                .subcommands
+                .extend(subcommand.split('|').map(str::to_string));
+        }
+    }
+    commands
+}
+
+/// Fixed values of an option, if it takes one of a set
+fn choices(option: &CliOption) -> Option<&'static [&'static str]> {
+    CHOICES
+        .iter()
+        .find(|(long, _)| *long == option.long)
+        .map(|(_, values)| *values)
+}
+
+fn takes_path(option: &CliOption) -> bool {
+    option.value.as_deref() == Some("PATH")
+}
+
+/// The completion script for `shell`
+pub fn generate(shell: Shell, help: &str, dynamic: &Dynamic) -> String {
+    let options = options(help);
+    let commands = commands(help);
+    match shell {
+        Shell::Bash => bash(&options, &commands, dynamic),
+        Shell::Zsh => zsh(&options, &commands, dynamic),
+        Shell::Fish => fish(&options, &commands, dynamic),
+    }
+}
+
+/// `s` in single quotes for a POSIX shell
+fn quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+fn bash(options: &[CliOption], commands: &[CliCommand], dynamic: &Dynamic) -> String {
+    let mut out = String::new();
+    let flags: Vec<String> = options
+        .iter()
+        .flat_map(|o| {
+            let short = o.short.map(|c| format!("-{}", c));
+            short.into_iter().chain([format!("--{}", o.long)])
+        })
+        .collect();
+    let names = |o: &CliOption| match o.short {
+        Some(c) => format!("-{}|--{}", c, o.long),
+        None => format!("--{}", o.long),
+    };
+
+    out.push_str("_picotui() {\n");
+    out.push_str("    local cur=\"${COMP_WORDS[COMP_CWORD]}\"\n");
+    out.push_str("    local prev=\"${COMP_WORDS[COMP_CWORD-1]}\"\n");
+    out.push_str("    local IFS=$'\\n'\n");
+    out.push_str("    case \"$prev\" in\n");
+    for option in options.iter().filter(|o| o.value.is_some()) {
+        let action = if let Some(values) = choices(option) {
+            format!(
+                "COMPREPLY=($(compgen -W {} -- \"$cur\"))",
+                quote(&values.join("\n"))
+            )
+        } else if option.long == "filter" && !dynamic.filters.is_empty() {
+            // Filters have spaces and quotes that must reach the command
+            // line escaped
+            format!(
+                "COMPREPLY=($(compgen -W {} -- \"$cur\"))\n            \
+                 local i\n            \
+                 for i in \"${{!COMPREPLY[@]}}\"; do COMPREPLY[$i]=$(printf '%q' \"${{COMPREPLY[$i]}}\"); done",
+                compgen_words(&dynamic.filters)
+            )
+        } else if takes_path(option) {
+            "COMPREPLY=($(compgen -f -- \"$cur\"))".to_string()
+        } else {
+            // Anything goes; don't offer the options as values
+            "COMPREPLY=()".to_string()
+        };
+        let _ = writeln!(
+            out,
+            "        {})\n            {}\n            return ;;",
+            names(option),
+            action
+        );
+    }
+    out.push_str("    esac\n");
+    out.push_str("    if [[ $COMP_CWORD -eq 2 ]]; then\n");
+    out.push_str("        case \"${COMP_WORDS[1]}\" in\n");
+    for command in commands.iter().filter(|c| !c.subcommands.is_empty()) {
+        let _ = writeln!(
+            out,
+            "            {})\n                COMPREPLY=($(compgen -W {} -- \"$cur\"))\n                return ;;",
+            command.name,
+            quote(&command.subcommands.join("\n"))
+        );
+    }
+    out.push_str("        esac\n");
+    out.push_str("    fi\n");
+    out.push_str("    if [[ $COMP_CWORD -eq 1 && $cur != -* ]]; then\n");
+    let names: Vec<&str> = commands.iter().map(|c| c.name.as_str()).collect();
+    let _ = writeln!(
+        out,
+        "        COMPREPLY=($(compgen -W {} -- \"$cur\"))\n        return",
+        quote(&names.join("\n"))
+    );
+    out.push_str("    fi\n");
+    let _ = writeln!(
+        out,
+        "    COMPREPLY=($(compgen -W {} -- \"$cur\"))",
+        quote(&flags.join("\n"))
+    );
+    out.push_str("}\n");
+    out.push_str("complete -F _picotui picotui\n");
+    out
+}
+
+/// Newline separated words for `compgen -W`, which expands them once more
+fn compgen_words(words: &[String]) -> String {
+    let escaped: Vec<String> = words
+        .iter()
+        .map(|w| {
+            w.chars()
+                .flat_map(|c| match c {
+                    '\\' | '\'' | '"' | '$' | '`' => vec!['\\', c],
+                    c => vec![c],
+                })
+                .collect()
+        })
+        .collect();
+    quote(&escaped.join("\n"))
+}
+
+/// `s` escaped for the description in brackets of a zsh option spec
+fn zsh_description(s: &str) -> String {
+    s.replace('\'', r"'\''")
+        .replace('[', r"\[")
+        .replace(']', r"\]")
+        .replace(':', r"\:")
+}
+
+fn zsh(options: &[CliOption], commands: &[CliCommand], dynamic: &Dynamic) -> String {
+    let mut out = String::new();
+    out.push_str("#compdef picotui\n\n");
+    out.push_str("_picotui() {\n");
+    out.push_str("    local state\n");
+    out.push_str("    _arguments -s \\\n");
+    for option in options {
+        let names = match option.short {
+            Some(c) => format!(
+                "'(-{c} --{long})'{{-{c},--{long}}}'",
+                c = c,
+                long = option.long
+            ),
+            None => format!("'--{}", option.long),
+        };
+        let value = match &option.value {
+            None => String::new(),
+            Some(placeholder) => {
+                let action = if let Some(values) = choices(option) {
+                    format!("({})", values.join(" "))
+                } else if option.long == "filter" && !dynamic.filters.is_empty() {
+                    let filters: Vec<String> = dynamic
+                        .filters
+                        .iter()
+                        .map(|f| f.replace('\'', r"'\''").replace(' ', r"\ "))
+                        .collect();
+                    format!("({})", filters.join(" "))
+                } else if takes_path(option) {
+                    "_files".to_string()
+                } else {
+                    String::new()
+                };
+                format!(":{}:{}", zsh_description(placeholder), action)
+            }
+        };
+        let _ = writeln!(
+            out,
+            "        {}[{}]{}' \\",
+            names,
+            zsh_description(&option.help),
+            value
+        );
+    }
+    let described: Vec<String> = commands
+        .iter()
+        .map(|c| {
+            format!(
+                "{}\\:{}",
+                c.name,
+                zsh_description(&c.help).replace(' ', "\\ ")
+            )
+        })
+        .collect();
+    let _ = writeln!(out, "        '1:command:(({}))' \\", described.join(" "));
+    out.push_str("        '2:argument:->argument'\n");
+    out.push_str("    case $state in\n");
+    out.push_str("        argument)\n");
+    out.push_str("            case $words[2] in\n");
+    for command in commands.iter().filter(|c| !c.subcommands.is_empty()) {
+        let _ = writeln!(
+            out,
+            "                {}) _values {} {} ;;",
+            command.name,
+            quote(&command.name),
+            command
+                .subcommands
+                .iter()
+                .map(|s| quote(s))
+                .collect::<Vec<_>>()
+                .join(" ")
+        );
+    }
+    out.push_str("            esac\n");
+    out.push_str("            ;;\n");
+    out.push_str("    esac\n");
+    out.push_str("}\n\n");
+    out.push_str("_picotui \"$@\"\n");
+    out
+}
+
+fn fish(options: &[CliOption], commands: &[CliCommand], dynamic: &Dynamic) -> String {
+    let mut out = String::new();
+    out.push_str("complete -c picotui -f\n");
+    for command in commands {
+        let _ = writeln!(
+            out,
+            "complete -c picotui -n __fish_use_subcommand -a {} -d {}",
+            command.name,
+            quote(&command.help)
+        );
+        if !command.subcommands.is_empty() {
+            let _ = writeln!(
+                out,
+                "complete -c picotui -n '__fish_seen_subcommand_from {}' -a {}",
+                command.name,
+                quote(&command.subcommands.join(" "))
+            );
+        }
+    }
+    for option in options {
+        let mut line = String::from("complete -c picotui");
+        if let Some(c) = option.short {
+            let _ = write!(line, " -s {}", c);
+        }
+        let _ = write!(line, " -l {}", option.long);
+        if option.value.is_some() {
+            if let Some(values) = choices(option) {
+                let _ = write!(line, " -x -a {}", quote(&values.join(" ")));
+            } else if option.long == "filter" && !dynamic.filters.is_empty() {
+                let filters: Vec<String> = dynamic.filters.iter().map(|f| quote(f)).collect();
+                let _ = write!(line, " -x -a {}", quote(&filters.join(" ")));
+            } else if takes_path(option) {
+                line.push_str(" -r -F");
+            } else {
+                line.push_str(" -x");
+            }
+        }
+        let _ = write!(line, " -d {}", quote(&option.help));
+        out.push_str(&line);
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HELP: &str = "USAGE:
+    tool [OPTIONS]
+
+COMMANDS:
+    exporter              Serve metrics
+        --listen <ADDR>   Address to listen on
+    tokens list           List saved sessions
+    tokens clear [URL]    Forget a session
+    completions bash|zsh
+                          Print completions
+
+OPTIONS:
+    -u, --url <URL>       Cluster URL: the first one
+                          continued here
+    --view <VIEW>         View to start in
+    --iproto-auth <METHOD>
+                          Auth method
+    --config <PATH>       Config file
+    --fuzzy               Fuzzy matching
+    -h, --help            Print help";
+
+    #[test]
+    fn test_options_from_help() {
+        let options = options(HELP);
+        let longs: Vec<&str> = options.iter().map(|o| o.long.as_str()).collect();
+        assert_eq!(
+            longs,
+            [
+                "listen",
+                "url",
+                "view",
+                "iproto-auth",
+                "config",
+                "fuzzy",
+                "help"
+            ]
+        );
+        assert_eq!(
+            options[1],
+            CliOption {
+                short: Some('u'),
+                long: "url".to_string(),
+                value: Some("URL".to_string()),
+                help: "Cluster URL: the first one".to_string(),
+            }
+        );
+        assert_eq!(
+            options[3].help, "Auth method",
+            "description on the next line"
+        );
+        assert_eq!(options[5].value, None);
+    }
+
+    #[test]
+    fn test_commands_from_help() {
+        let commands = commands(HELP);
+        assert_eq!(commands.len(), 3);
+        assert_eq!(commands[0].name, "exporter");
+        assert!(commands[0].subcommands.is_empty());
+        assert_eq!(commands[1].name, "tokens");
+        assert_eq!(commands[1].subcommands, ["list", "clear"]);
+        assert_eq!(commands[2].subcommands, ["bash", "zsh"]);
+        assert_eq!(commands[2].help, "Print completions");
+    }
+
+    #[test]
+    fn test_choices_are_accepted() {
+        use crate::app::ViewMode;
+        use crate::i18n::Lang;
+        use crate::ui::format::{ByteUnits, TimeFormat};
+        use crate::ui::theme::ColorMode;
+        use crate::watch::WaitCondition;
+
+        for (long, values) in CHOICES {
+            for value in *values {
+                let parsed = match *long {
+                    "view" => value.parse::<ViewMode>().map(drop),
+                    "units" => value.parse::<ByteUnits>().map(drop),
+                    "time" => value.parse::<TimeFormat>().map(drop),
+                    "lang" => value.parse::<Lang>().map(drop),
+                    "color" => value.parse::<ColorMode>().map(drop),
+                    "bell" => value.parse::<crate::app::Bell>().map(drop),
+                    "wait-until" => value.parse::<WaitCondition>().map(drop),
+                    #[cfg(feature = "iproto")]
+                    "iproto-auth" => value.parse::<crate::iproto::AuthMethod>().map(drop),
+                    _ => Ok(()),
+                };
+                assert!(parsed.is_ok(), "--{} {}", long, value);
+            }
+        }
+    }
+
+    #[test]
+    fn test_scripts_cover_options() {
+        let dynamic = Dynamic {
+            filters: vec!["dc2 offline".to_string()],
+        };
+        let bash = generate(Shell::Bash, HELP, &dynamic);
+        assert!(bash.contains("-u|--url)"));
+        assert!(bash.contains("'tiers\nreplicasets\ninstances'"));
+        assert!(bash.contains("compgen -f"), "paths complete files");
+        assert!(bash.contains("'list\nclear'"));
+        assert!(bash.ends_with("complete -F _picotui picotui\n"));
+
+        let zsh = generate(Shell::Zsh, HELP, &dynamic);
+        assert!(zsh.starts_with("#compdef picotui"));
+        assert!(zsh.contains("'(-u --url)'{-u,--url}'[Cluster URL\\: the first one]:URL:' \\"));
+        assert!(zsh.contains("'--view[View to start in]:VIEW:(tiers replicasets instances)' \\"));
+        assert!(zsh.contains("'--config[Config file]:PATH:_files' \\"));
+
+        let fish = generate(Shell::Fish, HELP, &dynamic);
+        assert!(fish.contains("complete -c picotui -s u -l url -x -d 'Cluster URL: the first one'"));
+        assert!(fish.contains("complete -c picotui -l fuzzy -d 'Fuzzy matching'"));
+        assert!(fish.contains("-n '__fish_seen_subcommand_from tokens' -a 'list clear'"));
+    }
+}
@@ -0,0 +1,180 @@
+use crate::alerts::AlertRule;
+use crate::app::{InstanceColumn, ViewMode};
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::fs::File;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+/// User preferences read from `config.json` in the picotui config directory
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Named filters offered by the saved filter picker
+    pub saved_filters: Vec<SavedFilter>,
+    /// Instances view columns in display order; the built-in set when absent
+    pub instance_columns: Option<Vec<InstanceColumn>>,
+    /// View to start in; Instances when only `filter` is set
+    pub view: Option<ViewMode>,
+    /// Instances view filter to start with
+    pub filter: Option<String>,
+    /// Theme to start with: "default" or the name of a skin
+    pub theme: Option<String>,
+    /// Status bar template with `{keys}`, `{cluster}`, `{refresh}`, `{user}`,
+    /// `{url}`, `{marked}` and `{status}` placeholders
+    pub status_bar: Option<String>,
+    /// Days a saved session is kept before it's pruned, 0 to keep forever
+    pub token_max_age_days: Option<u64>,
+    /// Requests per second sent to the cluster at most, 0 for no limit
+    pub max_request_rate: Option<f64>,
+    /// Expand tree nodes containing unhealthy instances on every refresh
+    pub expand_problems: bool,
+    /// Commands the rolling restart assistant runs (`--ops`)
+    pub rolling_restart: RestartCommands,
+    /// Alert rules checked on every refresh
+    pub alerts: Vec<AlertRule>,
+}
+
+/// Shell commands for the steps of a rolling restart, with `{tier}`,
+/// `{replicaset}`, `{instance}`, `{address}` and `{host}` placeholders.
+/// A step without a command is left to the operator.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct RestartCommands {
+    /// Moves the replicaset leadership off `{instance}`
+    pub switch_leader: Option<String>,
+    /// Restarts `{instance}`
+    pub restart: Option<String>,
+}
+
+/// A named Instances view filter
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct SavedFilter {
+    pub name: String,
+    pub filter: String,
+    /// Apply the filter with fuzzy matching
+    #[serde(default)]
+    pub fuzzy: bool,
+}
+
+/// Default location of the config file
+pub fn config_file_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|p| p.join("picotui/config.json"))
+}
+
+/// Load the config from `path`. A missing file yields the defaults;
+/// a malformed one is an error so typos don't go unnoticed.
+pub fn load_config(path: &Path) -> Result<Config> {
+    match File::open(path) {
+        Ok(file) => serde_json::from_reader(file)
+            .with_context(|| format!("Invalid config file {}", path.display())),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(Config::default()),
+        Err(e) => Err(e).with_context(|| format!("Could not read {}", path.display())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_saved_filters() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.json");
+        std::fs::write(
+            &path,
+            r#"{"saved_filters": [
+                {"name": "DC2", "filter": "dc2"},
+                {"name": "Storage", "filter": "stor", "fuzzy": true}
+            ]}"#,
+        )
+        .unwrap();
+
+        let config = load_config(&path).unwrap();
+        assert_eq!(config.saved_filters.len(), 2);
+        assert_eq!(config.saved_filters[0].filter, "dc2");
+        assert!(!config.saved_filters[0].fuzzy);
+        assert!(config.saved_filters[1].fuzzy);
+        assert!(config.instance_columns.is_none());
+    }
+
+    #[test]
+    fn test_load_instance_columns() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.json");
+        std::fs::write(
+            &path,
+            r#"{"instance_columns": ["name", "state", "version", "uptime"]}"#,
+        )
+        .unwrap();
+
+        let config = load_config(&path).unwrap();
+        assert_eq!(
+            config.instance_columns,
+            Some(vec![
+                InstanceColumn::Name,
+                InstanceColumn::State,
+                InstanceColumn::Version,
+                InstanceColumn::Uptime,
+            ])
+        );
+
+        std::fs::write(&path, r#"{"instance_columns": ["nmae"]}"#).unwrap();
+        assert!(load_config(&path).is_err(), "unknown columns are reported");
+    }
+
+    #[test]
+    fn test_load_startup_view() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.json");
+        std::fs::write(&path, r#"{"view": "instances", "filter": "offline"}"#).unwrap();
+
+        let config = load_config(&path).unwrap();
+        assert_eq!(config.view, Some(ViewMode::Instances));
+        assert_eq!(config.filter.as_deref(), Some("offline"));
+
+        std::fs::write(&path, r#"{"view": "instance"}"#).unwrap();
+        assert!(load_config(&path).is_err(), "unknown views are reported");
+    }
+
+    #[test]
+    fn test_load_alert_rules() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.json");
+        std::fs::write(
+            &path,
+            r#"{"alerts": [
+                {"name": "Down", "metric": "offline", "threshold": 0, "for": 2},
+                {"name": "Sick", "metric": "health", "op": "<", "threshold": 70,
+                 "command": "notify-send {name}"}
+            ]}"#,
+        )
+        .unwrap();
+
+        let config = load_config(&path).unwrap();
+        assert_eq!(config.alerts.len(), 2);
+        assert_eq!(config.alerts[0].condition(), "offline > 0");
+        assert_eq!(config.alerts[0].refreshes, 2);
+        assert_eq!(config.alerts[1].condition(), "health < 70");
+        assert_eq!(config.alerts[1].refreshes, 1);
+        assert!(config.alerts[1].command.is_some());
+
+        std::fs::write(
+            &path,
+            r#"{"alerts": [{"name": "x", "metric": "offlne", "threshold": 0}]}"#,
+        )
+        .unwrap();
+        assert!(load_config(&path).is_err(), "unknown metrics are reported");
+    }
+
+    #[test]
+    fn test_missing_config_is_default_and_malformed_is_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.json");
+        assert!(load_config(&path).unwrap().saved_filters.is_empty());
+
+        std::fs::write(&path, "{ not json").unwrap();
+        let err = load_config(&path).unwrap_err();
+        assert!(err.to_string().contains("Invalid config file"));
+    }
+}
@@ -0,0 +1,114 @@
+//! Confirmation asked before anything that changes the cluster. The more
+//! damage an operation can do, the more the user has to type to go ahead,
+//! so a stray Enter can't start it.
+
+/// How much confirmation an operation needs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// Reversible or harmless if repeated: Enter is enough
+    Caution,
+    /// Affects one replicaset or instance: type its name
+    Danger,
+    /// Affects the whole cluster: type the cluster name
+    Critical,
+}
+
+impl Severity {
+    pub fn label(self) -> &'static str {
+        match self {
+            Severity::Caution => "Caution",
+            Severity::Danger => "Danger",
+            Severity::Critical => "Critical",
+        }
+    }
+}
+
+/// A pending operation waiting for the user's go-ahead. `A` says what to
+/// run once confirmed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfirmDialog<A> {
+    pub title: String,
+    /// What is about to happen
+    pub message: String,
+    pub severity: Severity,
+    /// Text to type, empty for `Caution`
+    pub expected: String,
+    /// What the user has typed so far
+    pub input: String,
+    pub action: A,
+}
+
+impl<A> ConfirmDialog<A> {
+    /// Ask before running `action` on `target` (an instance or replicaset
+    /// name). `Critical` operations ask for `cluster_name` instead, or
+    /// `target` while the cluster name isn't known.
+    pub fn new(
+        title: impl Into<String>,
+        message: impl Into<String>,
+        severity: Severity,
+        target: &str,
+        cluster_name: Option<&str>,
+        action: A,
+    ) -> Self {
+        let expected = match severity {
+            Severity::Caution => String::new(),
+            Severity::Danger => target.to_string(),
+            Severity::Critical => cluster_name
+                .filter(|name| !name.is_empty())
+                .unwrap_or(target)
+                .to_string(),
+        };
+        Self {
+            title: title.into(),
+            message: message.into(),
+            severity,
+            expected,
+            input: String::new(),
+            action,
+        }
+    }
+
+    /// Whether the operation may run: the name is typed exactly
+    pub fn is_confirmed(&self) -> bool {
+        self.input == self.expected
+    }
+
+    pub fn needs_typing(&self) -> bool {
+        !self.expected.is_empty()
+    }
+
+    pub fn push_str(&mut self, text: &str) {
+        if self.needs_typing() {
+            self.input.push_str(text);
+        }
+    }
+
+    pub fn pop(&mut self) {
+        self.input.pop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_severity_decides_what_to_type() {
+        let caution = ConfirmDialog::new("t", "m", Severity::Caution, "r1", Some("prod"), ());
+        assert!(caution.is_confirmed(), "Enter is enough");
+
+        let mut danger = ConfirmDialog::new("t", "m", Severity::Danger, "r1", Some("prod"), ());
+        assert!(!danger.is_confirmed());
+        danger.push_str("r");
+        assert!(!danger.is_confirmed());
+        danger.push_str("1");
+        assert!(danger.is_confirmed());
+        danger.push_str("0");
+        assert!(!danger.is_confirmed(), "the exact name");
+
+        let critical = ConfirmDialog::new("t", "m", Severity::Critical, "r1", Some("prod"), ());
+        assert_eq!(critical.expected, "prod");
+        let unnamed = ConfirmDialog::new("t", "m", Severity::Critical, "r1", Some(""), ());
+        assert_eq!(unnamed.expected, "r1", "falls back to the target");
+    }
+}
@@ -0,0 +1,57 @@
+//! Frame diagnostics (`F12`): how long frames take to draw and how much
+//! work each one had, to tell a slow terminal from a flood of responses on
+//! a big cluster before optimizing either.
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// Draw times kept for the average and maximum
+const FRAME_WINDOW: usize = 100;
+
+#[derive(Debug, Clone, Default)]
+pub struct FrameStats {
+    /// Frames drawn this session
+    pub frames: u64,
+    /// List rows drawn in the last frame, and rows the view has in total
+    pub rows: (usize, usize),
+    /// Responses handled since the frame before the last one
+    pub responses: usize,
+    /// Most responses handled in one pass of the main loop
+    pub peak_responses: usize,
+    /// Requests sent to the worker that haven't been answered yet
+    pub in_flight: usize,
+    /// Draw times of the latest frames, oldest first
+    recent: VecDeque<Duration>,
+    /// Responses handled since the last frame
+    pending_responses: usize,
+}
+
+impl FrameStats {
+    /// Count the responses one pass of the main loop handled
+    pub fn record_responses(&mut self, count: usize) {
+        self.pending_responses += count;
+        self.peak_responses = self.peak_responses.max(count);
+    }
+
+    pub fn record_frame(&mut self, took: Duration) {
+        self.frames += 1;
+        self.responses = std::mem::take(&mut self.pending_responses);
+        if self.recent.len() == FRAME_WINDOW {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(took);
+    }
+
+    pub fn last(&self) -> Option<Duration> {
+        self.recent.back().copied()
+    }
+
+    pub fn avg(&self) -> Option<Duration> {
+        let total: Duration = self.recent.iter().sum();
+        (!self.recent.is_empty()).then(|| total / self.recent.len() as u32)
+    }
+
+    pub fn max(&self) -> Option<Duration> {
+        self.recent.iter().max().copied()
+    }
+}
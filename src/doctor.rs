@@ -0,0 +1,522 @@
+//! `picotui doctor`: check what usually stands between picotui and a
+//! cluster (the address, TLS, the saved session, the server version) and
+//! what the terminal can show, and say what to do about each problem.
+
+use crate::compat::{self, Compatibility, PicodataVersion};
+use crate::models::{ClusterInfo, UiConfig};
+use crate::schema;
+use crate::tokens;
+use crate::ui::theme::{ColorDepth, ColorMode};
+use std::fmt::{self, Write as _};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Ok,
+    Warn,
+    Fail,
+    /// Not checked, because an earlier check failed or it doesn't apply
+    Skip,
+}
+
+impl Status {
+    fn label(self) -> &'static str {
+        match self {
+            Status::Ok => "ok",
+            Status::Warn => "warn",
+            Status::Fail => "FAIL",
+            Status::Skip => "--",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Check {
+    pub name: &'static str,
+    pub status: Status,
+    pub detail: String,
+    /// What to do about a warning or failure
+    pub hint: Option<String>,
+}
+
+impl Check {
+    fn new(name: &'static str, status: Status, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            status,
+            detail: detail.into(),
+            hint: None,
+        }
+    }
+
+    fn hint(mut self, hint: impl Into<String>) -> Self {
+        self.hint = Some(hint.into());
+        self
+    }
+}
+
+/// What the terminal announces about itself through the environment
+#[derive(Debug, Clone, Default)]
+pub struct TerminalEnv {
+    pub term: Option<String>,
+    pub colorterm: Option<String>,
+    pub no_color: bool,
+    /// First of LC_ALL, LC_CTYPE and LANG that is set
+    pub locale: Option<(&'static str, String)>,
+}
+
+impl TerminalEnv {
+    pub fn from_env() -> Self {
+        let var = |name| std::env::var(name).ok().filter(|value| !value.is_empty());
+        Self {
+            term: var("TERM"),
+            colorterm: var("COLORTERM"),
+            no_color: var("NO_COLOR").is_some(),
+            locale: ["LC_ALL", "LC_CTYPE", "LANG"]
+                .into_iter()
+                .find_map(|name| var(name).map(|value| (name, value))),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Report {
+    pub url: String,
+    pub checks: Vec<Check>,
+}
+
+impl Report {
+    pub fn failed(&self) -> bool {
+        self.checks.iter().any(|check| check.status == Status::Fail)
+    }
+
+    fn count(&self, status: Status) -> usize {
+        self.checks
+            .iter()
+            .filter(|check| check.status == status)
+            .count()
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Checking {}", self.url)?;
+        writeln!(f)?;
+        let width = self.checks.iter().map(|c| c.name.len()).max().unwrap_or(0);
+        for check in &self.checks {
+            writeln!(
+                f,
+                "  {:<4}  {:<width$}  {}",
+                check.status.label(),
+                check.name,
+                check.detail
+            )?;
+            if let Some(hint) = &check.hint {
+                writeln!(f, "  {:<4}  {:<width$}  → {}", "", "", hint)?;
+            }
+        }
+        writeln!(f)?;
+        let (warnings, failures) = (self.count(Status::Warn), self.count(Status::Fail));
+        let mut summary = String::new();
+        match failures {
+            0 => summary.push_str("No problems"),
+            1 => summary.push_str("1 problem"),
+            n => write!(summary, "{} problems", n)?,
+        }
+        match warnings {
+            0 => {}
+            1 => summary.push_str(", 1 warning"),
+            n => write!(summary, ", {} warnings", n)?,
+        }
+        writeln!(f, "{}", summary)
+    }
+}
+
+/// Check the cluster at `url` with the saved session token `auth`, and the
+/// terminal described by `terminal`
+pub fn run(url: &str, auth: Option<&str>, terminal: &TerminalEnv) -> Report {
+    let url = url.trim_end_matches('/');
+    let agent = ureq::Agent::config_builder()
+        .timeout_connect(Some(CONNECT_TIMEOUT))
+        .timeout_recv_response(Some(Duration::from_secs(10)))
+        .http_status_as_error(false)
+        .build()
+        .new_agent();
+    let mut checks = cluster_checks(&agent, url, auth);
+    checks.push(color_check(terminal));
+    checks.push(unicode_check(terminal));
+    Report {
+        url: url.to_string(),
+        checks,
+    }
+}
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+fn cluster_checks(agent: &ureq::Agent, url: &str, auth: Option<&str>) -> Vec<Check> {
+    let https = url.starts_with("https://");
+    let skipped = |name| Check::new(name, Status::Skip, "needs a connection");
+
+    let started = Instant::now();
+    let config = match get(agent, &format!("{}/api/v1/config", url), None) {
+        Ok((200, body)) => serde_json::from_slice::<UiConfig>(&body).ok(),
+        Ok((status, _)) => {
+            return vec![
+                Check::new(
+                    "Connection",
+                    Status::Fail,
+                    format!("the server answers HTTP {} to /api/v1/config", status),
+                )
+                .hint("This isn't the Picodata HTTP API; check the port in --url"),
+                tls_ok(https),
+                skipped("Session"),
+                skipped("Version"),
+            ];
+        }
+        Err(e) if https && is_tls_error(&e) => {
+            return vec![
+                Check::new("Connection", Status::Ok, "the server accepts connections"),
+                tls_failure(&e),
+                skipped("Session"),
+                skipped("Version"),
+            ];
+        }
+        Err(e) => {
+            let (detail, hint) = connection_failure(&e);
+            return vec![
+                Check::new("Connection", Status::Fail, detail).hint(hint),
+                skipped("TLS"),
+                skipped("Session"),
+                skipped("Version"),
+            ];
+        }
+    };
+    let latency = started.elapsed();
+    let Some(config) = config else {
+        return vec![
+            Check::new(
+                "Connection",
+                Status::Fail,
+                "the server answers, but not with the Picodata API",
+            )
+            .hint("Check that --url points to the HTTP address of a Picodata instance"),
+            tls_ok(https),
+            skipped("Session"),
+            skipped("Version"),
+        ];
+    };
+    let mut checks = vec![
+        Check::new(
+            "Connection",
+            Status::Ok,
+            format!("answers in {} ms", latency.as_millis()),
+        ),
+        tls_ok(https),
+    ];
+
+    let user = auth.and_then(tokens::username_from_jwt);
+    let session = if config.is_auth_enabled { auth } else { None };
+    let info = match get(agent, &format!("{}/api/v1/cluster", url), session) {
+        Ok((200, body)) => schema::from_slice::<ClusterInfo>(&body).map_err(|e| e.to_string()),
+        Ok((status, _)) => Err(format!("HTTP {}", status)),
+        Err(e) => Err(e.to_string()),
+    };
+    let session_check = match (config.is_auth_enabled, auth, &info) {
+        (false, _, _) => Check::new("Session", Status::Ok, "the cluster doesn't require a login"),
+        (true, None, _) => Check::new("Session", Status::Warn, "no saved session for this URL")
+            .hint("Log in once in the UI; the session is saved for the next start"),
+        (true, Some(_), Ok(_)) => Check::new(
+            "Session",
+            Status::Ok,
+            format!(
+                "the saved session of {} is accepted",
+                user.as_deref().unwrap_or("the user")
+            ),
+        ),
+        (true, Some(_), Err(e)) => Check::new(
+            "Session",
+            Status::Warn,
+            format!(
+                "the saved session of {} is rejected ({})",
+                user.as_deref().unwrap_or("the user"),
+                e
+            ),
+        )
+        .hint(format!(
+            "Log in again, or forget it with `picotui tokens clear {}`",
+            url
+        )),
+    };
+    checks.push(session_check);
+
+    checks.push(match info {
+        Ok(info) => version_check(&info.current_instance_version),
+        Err(_) if config.is_auth_enabled => {
+            Check::new("Version", Status::Skip, "needs a working session")
+        }
+        Err(e) => Check::new(
+            "Version",
+            Status::Fail,
+            format!("cluster information unavailable: {}", e),
+        )
+        .hint("Run with --debug and look at picotui.log for the response"),
+    });
+    checks
+}
+
+/// Status and body of a GET
+fn get(agent: &ureq::Agent, url: &str, auth: Option<&str>) -> Result<(u16, Vec<u8>), ureq::Error> {
+    let mut req = agent.get(url);
+    if let Some(token) = auth {
+        req = req.header("Authorization", &format!("Bearer {}", token));
+    }
+    let mut resp = req.call()?;
+    let status = resp.status().as_u16();
+    let body = resp.body_mut().read_to_vec()?;
+    Ok((status, body))
+}
+
+/// rustls reports handshake failures as invalid data on the stream
+fn is_tls_error(e: &ureq::Error) -> bool {
+    match e {
+        ureq::Error::Tls(_) | ureq::Error::Pem(_) | ureq::Error::Rustls(_) => true,
+        ureq::Error::Io(io) => io.kind() == std::io::ErrorKind::InvalidData,
+        _ => false,
+    }
+}
+
+fn tls_ok(https: bool) -> Check {
+    if https {
+        Check::new("TLS", Status::Ok, "the certificate is valid for this host")
+    } else {
+        Check::new("TLS", Status::Skip, "plain HTTP, traffic is not encrypted")
+    }
+}
+
+fn tls_failure(e: &ureq::Error) -> Check {
+    let message = match e {
+        ureq::Error::Io(io) => io.to_string(),
+        e => e.to_string(),
+    };
+    let lower = message.to_lowercase();
+    let hint = if ["unknownissuer", "unknown issuer", "causedasendentity"]
+        .iter()
+        .any(|kind| lower.contains(kind))
+    {
+        "The certificate is self-signed or from a private CA; only public web CAs are trusted"
+    } else if lower.contains("expired") {
+        "The server certificate has expired and must be renewed"
+    } else if lower.contains("not valid for name") || lower.contains("notvalidforname") {
+        "The certificate was issued for another name; use that host name in --url"
+    } else if lower.contains("content type") || lower.contains("corrupt") {
+        "The server doesn't seem to speak TLS; try http:// in --url"
+    } else {
+        "Check the server certificate, or try http:// in --url"
+    };
+    Check::new("TLS", Status::Fail, message).hint(hint)
+}
+
+/// What went wrong reaching the server, and what to do about it
+fn connection_failure(e: &ureq::Error) -> (String, &'static str) {
+    match e {
+        ureq::Error::HostNotFound => (
+            "the host name doesn't resolve".to_string(),
+            "Check the host in --url",
+        ),
+        ureq::Error::ConnectionFailed => (
+            "the connection is refused".to_string(),
+            "Check the port in --url, and that the instance serves the HTTP API (http_listen)",
+        ),
+        ureq::Error::Io(io) if io.kind() == std::io::ErrorKind::ConnectionRefused => (
+            "the connection is refused".to_string(),
+            "Check the port in --url, and that the instance serves the HTTP API (http_listen)",
+        ),
+        // The system resolver's failure comes as an I/O error
+        ureq::Error::Io(io) if io.to_string().contains("lookup address") => (
+            "the host name doesn't resolve".to_string(),
+            "Check the host in --url",
+        ),
+        ureq::Error::Timeout(_) => (
+            format!("no answer in {} s", CONNECT_TIMEOUT.as_secs()),
+            "A firewall may be dropping the traffic; --ssh or --k8s-service can go around it",
+        ),
+        ureq::Error::BadUri(_) => (e.to_string(), "Check the URL format, e.g. http://host:8080"),
+        e => (e.to_string(), "Check --url"),
+    }
+}
+
+fn version_check(version: &str) -> Check {
+    let Some(parsed) = PicodataVersion::parse(version) else {
+        return Check::new(
+            "Version",
+            Status::Warn,
+            format!("unknown Picodata version \"{}\"", version),
+        )
+        .hint("Some columns may be empty if the API differs");
+    };
+    match compat::check(parsed) {
+        Compatibility::Supported => Check::new(
+            "Version",
+            Status::Ok,
+            format!("Picodata {} is supported", parsed),
+        ),
+        Compatibility::TooOld => Check::new(
+            "Version",
+            Status::Warn,
+            format!(
+                "Picodata {} is older than {}",
+                parsed,
+                compat::OLDEST_SUPPORTED
+            ),
+        )
+        .hint("Some data may be missing; upgrade the cluster for the full view"),
+        Compatibility::TooNew => Check::new(
+            "Version",
+            Status::Warn,
+            format!(
+                "Picodata {} is newer than picotui knows (up to {}.{})",
+                parsed,
+                compat::NEWEST_KNOWN.major,
+                compat::NEWEST_KNOWN.minor
+            ),
+        )
+        .hint("Upgrade picotui"),
+    }
+}
+
+fn color_check(terminal: &TerminalEnv) -> Check {
+    let term = terminal.term.as_deref();
+    match ColorMode::Auto.depth(terminal.no_color, term) {
+        ColorDepth::Monochrome if terminal.no_color => {
+            Check::new("Colors", Status::Warn, "none, NO_COLOR is set")
+                .hint("Unset NO_COLOR, or pass --color always")
+        }
+        ColorDepth::Monochrome => Check::new(
+            "Colors",
+            Status::Warn,
+            format!("none, TERM={}", term.unwrap_or_default()),
+        )
+        .hint("Use a terminal emulator, or pass --color always if it has colors"),
+        ColorDepth::Ansi16 => Check::new(
+            "Colors",
+            Status::Warn,
+            format!("16 colors, TERM={}", term.unwrap_or_default()),
+        )
+        .hint("Theme colors are approximated; a terminal emulator shows them as meant"),
+        ColorDepth::Full => match terminal.colorterm.as_deref() {
+            Some(colorterm @ ("truecolor" | "24bit")) => Check::new(
+                "Colors",
+                Status::Ok,
+                format!("24-bit, COLORTERM={}", colorterm),
+            ),
+            _ => Check::new(
+                "Colors",
+                Status::Warn,
+                "the terminal doesn't announce 24-bit color (COLORTERM)",
+            )
+            .hint(
+                "Export COLORTERM=truecolor if it has them; otherwise pass --color 16 \
+                 if theme colors look wrong",
+            ),
+        },
+    }
+}
+
+const GARBLED: &str = "Borders and symbols may come out garbled";
+
+fn unicode_check(terminal: &TerminalEnv) -> Check {
+    match &terminal.locale {
+        Some((name, value)) => {
+            let lower = value.to_lowercase();
+            if lower.contains("utf-8") || lower.contains("utf8") {
+                Check::new("Unicode", Status::Ok, format!("{}={}", name, value))
+            } else {
+                Check::new(
+                    "Unicode",
+                    Status::Warn,
+                    format!("{}={} is not a UTF-8 locale", name, value),
+                )
+                .hint(format!(
+                    "{}; set a UTF-8 locale or pass --accessible",
+                    GARBLED
+                ))
+            }
+        }
+        // The Windows console doesn't go by the locale variables
+        None if cfg!(windows) => Check::new("Unicode", Status::Ok, "Windows console"),
+        None => Check::new("Unicode", Status::Warn, "no locale is set").hint(format!(
+            "{}; set LANG to a UTF-8 locale, e.g. C.UTF-8",
+            GARBLED
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env(term: &str, colorterm: Option<&str>, lang: Option<&str>) -> TerminalEnv {
+        TerminalEnv {
+            term: Some(term.to_string()),
+            colorterm: colorterm.map(str::to_string),
+            no_color: false,
+            locale: lang.map(|lang| ("LANG", lang.to_string())),
+        }
+    }
+
+    #[test]
+    fn test_terminal_checks() {
+        let good = env("xterm-256color", Some("truecolor"), Some("en_US.UTF-8"));
+        assert_eq!(color_check(&good).status, Status::Ok);
+        assert_eq!(unicode_check(&good).status, Status::Ok);
+
+        let console = env("linux", None, Some("C"));
+        let colors = color_check(&console);
+        assert_eq!(colors.status, Status::Warn);
+        assert_eq!(colors.detail, "16 colors, TERM=linux");
+        let unicode = unicode_check(&console);
+        assert_eq!(unicode.status, Status::Warn);
+        assert!(unicode.hint.unwrap().contains("--accessible"));
+
+        let plain = env("xterm-256color", None, Some("C.utf8"));
+        assert_eq!(color_check(&plain).status, Status::Warn);
+        assert_eq!(unicode_check(&plain).status, Status::Ok);
+
+        let no_color = TerminalEnv {
+            no_color: true,
+            ..good
+        };
+        assert_eq!(color_check(&no_color).detail, "none, NO_COLOR is set");
+    }
+
+    #[test]
+    fn test_version_check() {
+        assert_eq!(version_check("25.6.0").status, Status::Ok);
+        assert_eq!(version_check("24.1.0").status, Status::Warn);
+        assert!(version_check("27.1.0")
+            .hint
+            .unwrap()
+            .contains("Upgrade picotui"));
+        assert_eq!(version_check("dev").status, Status::Warn);
+    }
+
+    #[test]
+    fn test_report_summary() {
+        let report = Report {
+            url: "http://localhost:8080".to_string(),
+            checks: vec![
+                Check::new("Connection", Status::Fail, "the connection is refused")
+                    .hint("Check the port in --url"),
+                Check::new("TLS", Status::Skip, "needs a connection"),
+                Check::new("Unicode", Status::Warn, "no locale is set"),
+            ],
+        };
+        assert!(report.failed());
+        let text = report.to_string();
+        assert!(text.starts_with("Checking http://localhost:8080\n"));
+        assert!(text.contains("  FAIL  Connection  the connection is refused\n"));
+        assert!(text.contains("                    → Check the port in --url\n"));
+        assert!(text.contains("  --    TLS         needs a connection\n"));
+        assert!(text.ends_with("1 problem, 1 warning\n"));
+    }
+}
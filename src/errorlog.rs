@@ -0,0 +1,62 @@
+//! Errors seen this session (`!`), so one that the next refresh replaced
+//! or cleared can still be read.
+
+use std::collections::VecDeque;
+
+/// Errors kept; the oldest goes first
+const ERROR_LOG_LIMIT: usize = 50;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoggedError {
+    pub message: String,
+    /// Unix time of the first of the repeats in a row
+    pub first_at: u64,
+    /// Unix time of the latest repeat
+    pub last_at: u64,
+    /// Times the error came up in a row
+    pub count: u32,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ErrorLog {
+    /// Oldest first
+    entries: VecDeque<LoggedError>,
+}
+
+impl ErrorLog {
+    /// Add an error. The same error as the latest one is counted instead,
+    /// so a cluster that stays unreachable doesn't push everything out.
+    pub fn record(&mut self, message: &str, now: u64) {
+        if let Some(last) = self.entries.back_mut().filter(|e| e.message == message) {
+            last.last_at = now;
+            last.count += 1;
+            return;
+        }
+        if self.entries.len() == ERROR_LOG_LIMIT {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(LoggedError {
+            message: message.to_string(),
+            first_at: now,
+            last_at: now,
+            count: 1,
+        });
+    }
+
+    /// Newest first
+    pub fn iter(&self) -> impl Iterator<Item = &LoggedError> {
+        self.entries.iter().rev()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
@@ -0,0 +1,193 @@
+use crate::api::{ApiRequest, ApiResponse};
+use crate::health;
+use crate::models::{StateVariant, TierInfo};
+use crate::snapshot::{self, SnapshotDiff};
+use crate::ui::format::unix_now;
+use crate::watch;
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use std::io::Write;
+use std::time::Duration;
+
+/// How long to wait for the cluster on startup and for each poll
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// One line of `--events-stdout` output
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    InstanceAdded {
+        instance: String,
+    },
+    InstanceRemoved {
+        instance: String,
+    },
+    StateChanged {
+        instance: String,
+        from: StateVariant,
+        to: StateVariant,
+    },
+    MemoryChanged {
+        tier: String,
+        before: u64,
+        after: u64,
+    },
+    /// Emitted after every successful poll, following that poll's changes
+    Refresh {
+        instances: usize,
+        online: usize,
+        offline: usize,
+        health: u8,
+        /// Number of change events emitted for this poll
+        changes: usize,
+    },
+    Error {
+        message: String,
+    },
+}
+
+/// Change events for a diff, in the order the diff lists them
+pub fn change_events(diff: &SnapshotDiff) -> Vec<Event> {
+    let added = diff.added.iter().map(|name| Event::InstanceAdded {
+        instance: name.clone(),
+    });
+    let removed = diff.removed.iter().map(|name| Event::InstanceRemoved {
+        instance: name.clone(),
+    });
+    let states = diff.state_changes.iter().map(|c| Event::StateChanged {
+        instance: c.instance.clone(),
+        from: c.from,
+        to: c.to,
+    });
+    let memory = diff.memory.iter().map(|c| Event::MemoryChanged {
+        tier: c.tier.clone(),
+        before: c.before,
+        after: c.after,
+    });
+    added.chain(removed).chain(states).chain(memory).collect()
+}
+
+/// Summary of a poll that produced `changes` change events
+pub fn refresh_event(tiers: &[TierInfo], changes: usize) -> Event {
+    let instances: Vec<_> = tiers
+        .iter()
+        .flat_map(|t| t.replicasets.iter())
+        .flat_map(|rs| rs.instances.iter())
+        .collect();
+    let count = |state| {
+        instances
+            .iter()
+            .filter(|inst| inst.current_state == state)
+            .count()
+    };
+    Event::Refresh {
+        instances: instances.len(),
+        online: count(StateVariant::Online),
+        offline: count(StateVariant::Offline),
+        health: health::assess(tiers).score,
+        changes,
+    }
+}
+
+/// Poll the cluster every `interval` and write one JSON object per line to
+/// stdout for every change. The first poll only emits a refresh summary.
+/// Runs until stdout is closed or the saved session expires. Requests go
+/// to `connect_url`, see `watch::start_headless`.
+pub fn stream_events(
+    base_url: &str,
+    connect_url: &str,
+    interval: Duration,
+    debug: bool,
+) -> Result<()> {
+    let (request_tx, response_rx) =
+        watch::start_headless(base_url, connect_url, REQUEST_TIMEOUT, debug)?
+            .ok_or_else(|| anyhow!("No response from {}", base_url))?;
+
+    let mut stdout = std::io::stdout().lock();
+    let mut previous: Option<Vec<TierInfo>> = None;
+    loop {
+        // A reply that came after its timeout would pass for this poll's
+        while response_rx.try_recv().is_ok() {}
+        let _ = request_tx.send(ApiRequest::GetTiers);
+        let events = match response_rx
+            .recv_timeout(REQUEST_TIMEOUT)
+            .map(|message| message.response)
+        {
+            Ok(ApiResponse::Tiers(Ok(tiers))) => {
+                let mut events = match &previous {
+                    Some(old) => change_events(&snapshot::diff(old, &tiers)),
+                    None => Vec::new(),
+                };
+                events.push(refresh_event(&tiers, events.len()));
+                previous = Some(tiers);
+                events
+            }
+            // Unchanged since the last poll
+            Ok(ApiResponse::NotModified(_)) => match &previous {
+                Some(tiers) => vec![refresh_event(tiers, 0)],
+                None => Vec::new(),
+            },
+            Ok(ApiResponse::Tiers(Err(e))) => {
+                if e.contains("401") {
+                    let _ = request_tx.send(ApiRequest::Shutdown);
+                    return Err(anyhow!("Saved session is no longer valid: {}", e));
+                }
+                vec![Event::Error { message: e }]
+            }
+            Ok(_) => Vec::new(),
+            Err(_) => vec![Event::Error {
+                message: "Timed out waiting for the cluster".to_string(),
+            }],
+        };
+
+        for event in events {
+            let mut line = serde_json::to_value(&event)?;
+            line["time"] = unix_now().into();
+            // A closed pipe means the consumer is gone
+            if writeln!(stdout, "{}", line).is_err() {
+                let _ = request_tx.send(ApiRequest::Shutdown);
+                return Ok(());
+            }
+        }
+        let _ = stdout.flush();
+        std::thread::sleep(interval);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock;
+
+    #[test]
+    fn test_change_events_from_refresh() {
+        let old: Vec<TierInfo> = serde_json::from_value(mock::tiers()).unwrap();
+        let mut new = old.clone();
+        new[0].replicasets[1].instances[0].current_state = StateVariant::Online;
+        new[1].replicasets[0].instances.pop();
+
+        let events = change_events(&snapshot::diff(&old, &new));
+        let lines: Vec<String> = events
+            .iter()
+            .map(|e| serde_json::to_string(e).unwrap())
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                r#"{"event":"instance_removed","instance":"s1-i2"}"#,
+                r#"{"event":"state_changed","instance":"i3","from":"Offline","to":"Online"}"#,
+            ]
+        );
+
+        match refresh_event(&new, events.len()) {
+            Event::Refresh {
+                instances,
+                online,
+                offline,
+                changes,
+                ..
+            } => assert_eq!((instances, online, offline, changes), (5, 5, 0, 2)),
+            other => panic!("unexpected event {:?}", other),
+        }
+    }
+}
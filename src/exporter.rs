@@ -0,0 +1,361 @@
+use crate::api::{ApiRequest, ApiResponse};
+use crate::health;
+use crate::models::{ClusterInfo, InstanceInfo, StateVariant, TierInfo};
+use crate::watch;
+use anyhow::{anyhow, Context, Result};
+use std::fmt::Write as _;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Default address for `picotui exporter`
+pub const DEFAULT_LISTEN: &str = "0.0.0.0:9123";
+
+/// How long to wait for the cluster on startup and for each poll
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Latest data polled from the cluster
+#[derive(Debug, Clone, Default)]
+pub struct Scrape {
+    /// Whether the last poll succeeded
+    pub up: bool,
+    pub cluster: Option<ClusterInfo>,
+    /// Shared with the scrapes being rendered, so a poll never waits on one
+    pub tiers: Arc<Vec<TierInfo>>,
+}
+
+/// Poll the cluster every `interval` and serve the latest data as
+/// Prometheus metrics on `listen` until the process is killed or the saved
+/// session expires. Requests go to `connect_url`, see
+/// `watch::start_headless`.
+pub fn run(
+    base_url: &str,
+    connect_url: &str,
+    listen: &str,
+    interval: Duration,
+    debug: bool,
+) -> Result<()> {
+    let listener =
+        TcpListener::bind(listen).with_context(|| format!("Could not listen on {}", listen))?;
+    let (request_tx, response_rx) =
+        watch::start_headless(base_url, connect_url, REQUEST_TIMEOUT, debug)?
+            .ok_or_else(|| anyhow!("No response from {}", base_url))?;
+    eprintln!(
+        "Serving metrics for {} on http://{}/metrics",
+        base_url, listen
+    );
+
+    let scrape = Arc::new(Mutex::new(Scrape::default()));
+    let served = Arc::clone(&scrape);
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    if let Err(e) = respond(stream, &served) {
+                        eprintln!("Scrape failed: {}", e);
+                    }
+                }
+                Err(e) => eprintln!("Connection failed: {}", e),
+            }
+        }
+    });
+
+    loop {
+        // The worker answers in order, so each request gets the next
+        // response once a reply that came too late is out of the way
+        let mut up = true;
+        for request in [ApiRequest::GetClusterInfo, ApiRequest::GetTiers] {
+            while response_rx.try_recv().is_ok() {}
+            let _ = request_tx.send(request);
+            let response = response_rx
+                .recv_timeout(REQUEST_TIMEOUT)
+                .map(|message| message.response);
+            let mut scrape = scrape.lock().unwrap();
+            match response {
+                Ok(ApiResponse::ClusterInfo(Ok(info))) => scrape.cluster = Some(info),
+                Ok(ApiResponse::Tiers(Ok(tiers))) => scrape.tiers = Arc::new(tiers),
+                // Data unchanged since the last poll
+                Ok(ApiResponse::NotModified(_)) => {}
+                Ok(ApiResponse::ClusterInfo(Err(e))) | Ok(ApiResponse::Tiers(Err(e))) => {
+                    if e.contains("401") {
+                        let _ = request_tx.send(ApiRequest::Shutdown);
+                        return Err(anyhow!("Saved session is no longer valid: {}", e));
+                    }
+                    eprintln!("Poll failed: {}", e);
+                    up = false;
+                }
+                Ok(_) => up = false,
+                Err(_) => {
+                    eprintln!("Poll failed: timed out waiting for the cluster");
+                    up = false;
+                }
+            }
+        }
+        scrape.lock().unwrap().up = up;
+        std::thread::sleep(interval);
+    }
+}
+
+/// Answer a single HTTP request: metrics on `/metrics`, 404 elsewhere
+fn respond(mut stream: TcpStream, scrape: &Mutex<Scrape>) -> Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut reader = BufReader::new(&stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Skip the headers, the request has no body we care about
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+
+    let path = request_line.split_whitespace().nth(1).unwrap_or("");
+    let (status, body) = if path == "/metrics" {
+        let scrape = scrape.lock().unwrap().clone();
+        ("200 OK", render_metrics(&scrape))
+    } else {
+        (
+            "404 Not Found",
+            "Metrics are served on /metrics\n".to_string(),
+        )
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )?;
+    Ok(())
+}
+
+/// Render the scrape in the Prometheus text exposition format
+pub fn render_metrics(scrape: &Scrape) -> String {
+    let mut out = String::new();
+    let mut gauge = |name: &str, help: &str, samples: Vec<(String, f64)>| {
+        let _ = writeln!(out, "# HELP {} {}", name, help);
+        let _ = writeln!(out, "# TYPE {} gauge", name);
+        for (labels, value) in samples {
+            let _ = writeln!(out, "{}{} {}", name, labels, value);
+        }
+    };
+
+    gauge(
+        "picotui_up",
+        "Whether the last poll of the cluster succeeded",
+        vec![(String::new(), f64::from(u8::from(scrape.up)))],
+    );
+    // Data from before the cluster stopped answering would pass for current
+    if !scrape.up {
+        return out;
+    }
+
+    if let Some(info) = &scrape.cluster {
+        gauge(
+            "picodata_cluster_info",
+            "Cluster name and version",
+            vec![(
+                labels(&[
+                    ("cluster", &info.cluster_name),
+                    ("version", &info.cluster_version),
+                ]),
+                1.0,
+            )],
+        );
+        gauge(
+            "picodata_cluster_instances",
+            "Instances by current state",
+            vec![
+                (
+                    labels(&[("state", "online")]),
+                    info.instances_current_state_online as f64,
+                ),
+                (
+                    labels(&[("state", "offline")]),
+                    info.instances_current_state_offline as f64,
+                ),
+            ],
+        );
+        gauge(
+            "picodata_cluster_memory_used_bytes",
+            "Memory used across the cluster",
+            vec![(String::new(), info.memory.used as f64)],
+        );
+        gauge(
+            "picodata_cluster_memory_usable_bytes",
+            "Memory usable across the cluster",
+            vec![(String::new(), info.memory.usable as f64)],
+        );
+    }
+
+    if scrape.tiers.is_empty() {
+        return out;
+    }
+
+    gauge(
+        "picotui_cluster_health_score",
+        "Cluster health score (0-100) as shown in the TUI header",
+        vec![(
+            String::new(),
+            f64::from(health::assess(&scrape.tiers).score),
+        )],
+    );
+
+    let per_tier = |value: fn(&TierInfo) -> f64| -> Vec<(String, f64)> {
+        scrape
+            .tiers
+            .iter()
+            .map(|t| (labels(&[("tier", &t.name)]), value(t)))
+            .collect()
+    };
+    gauge(
+        "picodata_tier_memory_used_bytes",
+        "Memory used by the tier",
+        per_tier(|t| t.memory.used as f64),
+    );
+    gauge(
+        "picodata_tier_memory_usable_bytes",
+        "Memory usable by the tier",
+        per_tier(|t| t.memory.usable as f64),
+    );
+    gauge(
+        "picodata_tier_capacity_usage_percent",
+        "Used share of the tier's usable memory",
+        per_tier(|t| t.capacity_usage),
+    );
+    gauge(
+        "picodata_tier_replicasets",
+        "Replicasets in the tier",
+        per_tier(|t| t.replicaset_count as f64),
+    );
+    gauge(
+        "picodata_tier_buckets",
+        "Buckets in the tier",
+        per_tier(|t| t.bucket_count as f64),
+    );
+
+    let instances: Vec<_> = scrape
+        .tiers
+        .iter()
+        .flat_map(|t| t.replicasets.iter().map(move |rs| (t, rs)))
+        .flat_map(|(t, rs)| rs.instances.iter().map(move |inst| (t, rs, inst)))
+        .collect();
+    let per_instance = |value: &dyn Fn(&InstanceInfo) -> Option<f64>| {
+        instances
+            .iter()
+            .filter_map(|(t, rs, inst)| {
+                let labels = labels(&[
+                    ("tier", &t.name),
+                    ("replicaset", &rs.name),
+                    ("instance", &inst.name),
+                ]);
+                value(inst).map(|v| (labels, v))
+            })
+            .collect()
+    };
+    let flag = |b: bool| Some(f64::from(u8::from(b)));
+    gauge(
+        "picodata_instance_online",
+        "Whether the instance's current state is Online",
+        per_instance(&|inst| flag(inst.current_state == StateVariant::Online)),
+    );
+    gauge(
+        "picodata_instance_converged",
+        "Whether the instance's current state matches its target state",
+        per_instance(&|inst| flag(inst.current_state == inst.target_state)),
+    );
+    gauge(
+        "picodata_instance_leader",
+        "Whether the instance is its replicaset's leader",
+        per_instance(&|inst| flag(inst.is_leader)),
+    );
+    gauge(
+        "picodata_instance_replication_lag_seconds",
+        "Replication lag behind the replicaset leader (newer Picodata versions)",
+        per_instance(&|inst| inst.replication_lag),
+    );
+    gauge(
+        "picodata_instance_uptime_seconds",
+        "Seconds since the instance process started (newer Picodata versions)",
+        per_instance(&|inst| inst.uptime_seconds.map(|s| s as f64)),
+    );
+
+    out
+}
+
+/// Format a label set, escaping values as the exposition format requires
+fn labels(pairs: &[(&str, &str)]) -> String {
+    let pairs: Vec<String> = pairs
+        .iter()
+        .map(|(key, value)| {
+            let value = value
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n");
+            format!("{}=\"{}\"", key, value)
+        })
+        .collect();
+    format!("{{{}}}", pairs.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock;
+
+    #[test]
+    fn test_label_values_are_escaped() {
+        assert_eq!(
+            labels(&[("tier", "a\"b"), ("instance", "c\\d")]),
+            r#"{tier="a\"b",instance="c\\d"}"#
+        );
+    }
+
+    #[test]
+    fn test_metrics_without_data() {
+        let metrics = render_metrics(&Scrape::default());
+        assert!(metrics.contains("# TYPE picotui_up gauge\npicotui_up 0\n"));
+        assert!(!metrics.contains("picodata_"));
+    }
+
+    #[test]
+    fn test_exporter_metrics() {
+        let scrape = Scrape {
+            up: true,
+            cluster: None,
+            tiers: serde_json::from_value(mock::tiers()).unwrap(),
+        };
+        let metrics = render_metrics(&scrape);
+
+        assert!(metrics.contains("picotui_up 1\n"));
+        assert!(metrics.contains("picotui_cluster_health_score 90\n"));
+        assert!(
+            metrics.contains("picodata_tier_memory_usable_bytes{tier=\"storage\"} 2147483648\n")
+        );
+        assert!(metrics.contains(
+            "picodata_instance_online{tier=\"default\",replicaset=\"r2\",instance=\"i3\"} 0\n"
+        ));
+        assert!(metrics.contains(
+            "picodata_instance_leader{tier=\"default\",replicaset=\"r1\",instance=\"i1\"} 1\n"
+        ));
+    }
+
+    #[test]
+    fn test_exporter_drops_stale_series_while_down() {
+        let mut scrape = Scrape {
+            up: true,
+            cluster: Some(serde_json::from_value(mock::cluster_info()).unwrap()),
+            tiers: serde_json::from_value(mock::tiers()).unwrap(),
+        };
+        let metrics = render_metrics(&scrape);
+        assert!(metrics.contains("picodata_cluster_info{"));
+        assert!(metrics.contains("picodata_tier_buckets{tier=\"default\"}"));
+
+        // The last poll failed: the data held is from before
+        scrape.up = false;
+        let metrics = render_metrics(&scrape);
+        assert!(metrics.contains("picotui_up 0\n"));
+        assert!(!metrics.contains("picodata_"));
+        assert!(!metrics.contains("picotui_cluster_health_score"));
+    }
+}
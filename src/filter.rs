@@ -0,0 +1,151 @@
+use crate::fuzzy::fuzzy_match;
+
+/// Parsed filter/search expression.
+///
+/// Space-separated terms must all match (AND), `|` separates alternatives
+/// (OR, lower precedence than AND) and `!term` excludes rows where the term
+/// matches. Terms are matched case-insensitively against every field of a row:
+/// `dc2 !offline | storage` selects rows containing "dc2" but not "offline",
+/// plus every row containing "storage".
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FilterExpr {
+    alternatives: Vec<Vec<Term>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Term {
+    /// Lowercased term text
+    text: String,
+    negated: bool,
+}
+
+impl FilterExpr {
+    pub fn parse(input: &str) -> Self {
+        let alternatives = input
+            .split('|')
+            .map(|alternative| {
+                alternative
+                    .split_whitespace()
+                    .filter_map(|word| {
+                        let (negated, text) = match word.strip_prefix('!') {
+                            Some(rest) => (true, rest),
+                            None => (false, word),
+                        };
+                        // A lone "!" is still being typed; ignore it
+                        (!text.is_empty()).then(|| Term {
+                            text: text.to_lowercase(),
+                            negated,
+                        })
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .filter(|terms| !terms.is_empty())
+            .collect();
+        Self { alternatives }
+    }
+
+    /// An empty expression matches everything
+    pub fn is_empty(&self) -> bool {
+        self.alternatives.is_empty()
+    }
+
+    pub fn matches(&self, fields: &[&str]) -> bool {
+        self.score(fields, false).is_some()
+    }
+
+    /// Match score of a row, `None` if it doesn't match. With `fuzzy`,
+    /// positive terms match as subsequences and the score ranks how well;
+    /// otherwise every match scores 0. Exclusions always use substrings.
+    pub fn score(&self, fields: &[&str], fuzzy: bool) -> Option<i64> {
+        if self.is_empty() {
+            return Some(0);
+        }
+
+        let lowered: Vec<String> = fields.iter().map(|f| f.to_lowercase()).collect();
+        let contains = |term: &str| lowered.iter().any(|f| f.contains(term));
+
+        self.alternatives
+            .iter()
+            .filter_map(|terms| {
+                terms.iter().try_fold(0i64, |total, term| {
+                    if term.negated {
+                        return (!contains(&term.text)).then_some(total);
+                    }
+                    if fuzzy {
+                        fields
+                            .iter()
+                            .filter_map(|f| fuzzy_match(&term.text, f))
+                            .map(|m| m.score)
+                            .max()
+                            .map(|score| total + score)
+                    } else {
+                        contains(&term.text).then_some(total)
+                    }
+                })
+            })
+            .max()
+    }
+
+    /// Terms worth highlighting (everything that isn't an exclusion)
+    pub fn positive_terms(&self) -> Vec<&str> {
+        self.alternatives
+            .iter()
+            .flatten()
+            .filter(|t| !t.negated)
+            .map(|t| t.text.as_str())
+            .collect()
+    }
+}
+
+/// Whether every row matching `new` is known to match `old` as well, so
+/// `new` can be applied to the rows `old` left. Holds when `new` only
+/// appends to `old` and neither uses `|` or `!`, which could widen the result.
+pub fn refines(new: &str, old: &str) -> bool {
+    let plain = |s: &str| !s.contains(['|', '!']);
+    new.starts_with(old) && plain(new) && plain(old)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_refines() {
+        assert!(refines("dc", ""));
+        assert!(refines("dc2", "dc"));
+        assert!(refines("dc2 r1", "dc2"));
+        assert!(!refines("dc", "dc2"));
+        assert!(!refines("dc2 | r1", "dc2"));
+        assert!(!refines("!ab", "!a"));
+    }
+
+    const ROW: &[&str] = &["storage-1-instance-2", "dc2", "Offline"];
+
+    #[test]
+    fn test_and_terms() {
+        assert!(FilterExpr::parse("dc2 offline").matches(ROW));
+        assert!(!FilterExpr::parse("dc2 online").matches(ROW));
+        assert!(FilterExpr::parse("").matches(ROW));
+        assert!(FilterExpr::parse("  ").is_empty());
+    }
+
+    #[test]
+    fn test_negation_and_alternatives() {
+        assert!(!FilterExpr::parse("dc2 !offline").matches(ROW));
+        assert!(FilterExpr::parse("!dc1").matches(ROW));
+        assert!(FilterExpr::parse("dc1 | storage").matches(ROW));
+        assert!(!FilterExpr::parse("dc1|router").matches(ROW));
+        assert!(
+            FilterExpr::parse("dc2 !").matches(ROW),
+            "dangling ! is ignored"
+        );
+    }
+
+    #[test]
+    fn test_fuzzy_scores_positive_terms() {
+        let expr = FilterExpr::parse("st1i2 !online");
+        assert!(expr.score(ROW, true).is_some());
+        assert!(expr.score(ROW, false).is_none());
+        assert_eq!(expr.positive_terms(), vec!["st1i2"]);
+    }
+}
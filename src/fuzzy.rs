@@ -0,0 +1,126 @@
+/// Score for each matched character
+const MATCH: i64 = 16;
+/// Bonus when a match directly follows the previous one
+const CONSECUTIVE: i64 = 8;
+/// Bonus when a match starts a word ("storage-1" → 's', '1')
+const BOUNDARY: i64 = 8;
+
+/// Result of a successful fuzzy match
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FuzzyMatch {
+    pub score: i64,
+    /// Byte offsets of the matched characters in the text
+    pub positions: Vec<usize>,
+}
+
+/// fzf-style subsequence match: every pattern character must appear in
+/// `text` in order (case-insensitive). Among all alignments the one with the
+/// best score wins; consecutive runs and word starts score higher, gaps
+/// between matched characters cost one point each.
+pub fn fuzzy_match(pattern: &str, text: &str) -> Option<FuzzyMatch> {
+    let pattern: Vec<char> = pattern.chars().flat_map(char::to_lowercase).collect();
+    if pattern.is_empty() {
+        return Some(FuzzyMatch {
+            score: 0,
+            positions: Vec::new(),
+        });
+    }
+
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let (n, m) = (chars.len(), pattern.len());
+    if m > n {
+        return None;
+    }
+
+    let eq = |j: usize, p: char| chars[j].1.to_lowercase().eq(std::iter::once(p));
+    let bonus = |j: usize| {
+        if j == 0 || !chars[j - 1].1.is_alphanumeric() {
+            BOUNDARY
+        } else {
+            0
+        }
+    };
+
+    // score[i][j]: best score with pattern[..=i] matched and pattern[i] at text char j
+    const NONE: i64 = i64::MIN / 4;
+    let mut score = vec![vec![NONE; n]; m];
+    let mut from = vec![vec![0usize; n]; m];
+
+    for (j, first) in score[0].iter_mut().enumerate() {
+        if eq(j, pattern[0]) {
+            *first = MATCH + bonus(j);
+        }
+    }
+
+    for i in 1..m {
+        // Best non-adjacent predecessor so far, as score[i-1][k] + k, so
+        // the gap penalty (j - k - 1) can be applied in O(1)
+        let mut best = NONE;
+        let mut best_k = 0;
+        for j in 0..n {
+            if j >= 2 && score[i - 1][j - 2] > NONE {
+                let k = j - 2;
+                let value = score[i - 1][k] + k as i64;
+                if value > best {
+                    best = value;
+                    best_k = k;
+                }
+            }
+            if !eq(j, pattern[i]) {
+                continue;
+            }
+
+            let mut current = NONE;
+            if j >= 1 && score[i - 1][j - 1] > NONE {
+                current = score[i - 1][j - 1] + CONSECUTIVE;
+                from[i][j] = j - 1;
+            }
+            if best > NONE && best - (j as i64 - 1) > current {
+                current = best - (j as i64 - 1);
+                from[i][j] = best_k;
+            }
+            if current > NONE {
+                score[i][j] = current + MATCH + bonus(j);
+            }
+        }
+    }
+
+    let (mut j, &best) = score[m - 1]
+        .iter()
+        .enumerate()
+        .filter(|(_, &s)| s > NONE)
+        .max_by_key(|(j, &s)| (s, std::cmp::Reverse(*j)))?;
+
+    let mut positions = vec![0; m];
+    for i in (0..m).rev() {
+        positions[i] = chars[j].0;
+        j = from[i][j];
+    }
+
+    Some(FuzzyMatch {
+        score: best,
+        positions,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subsequence_across_word_boundaries() {
+        let m = fuzzy_match("st1i2", "storage-1-instance-2").unwrap();
+        assert_eq!(m.positions, vec![0, 1, 8, 10, 19]);
+        assert!(fuzzy_match("st1i3", "storage-1-instance-2").is_none());
+        assert!(fuzzy_match("ST", "storage").is_some(), "case-insensitive");
+    }
+
+    #[test]
+    fn test_ranking_prefers_tight_and_word_start_matches() {
+        let tight = fuzzy_match("i2", "i2").unwrap().score;
+        let boundary = fuzzy_match("i2", "storage-1-instance-2").unwrap().score;
+        let scattered = fuzzy_match("i2", "xixxxxxxx2").unwrap().score;
+        assert!(tight > boundary);
+        assert!(boundary > scattered);
+    }
+}
@@ -0,0 +1,115 @@
+use crate::models::{StateVariant, TierInfo};
+use std::collections::BTreeSet;
+
+/// Replicasets above this capacity usage (percent) count as a problem
+pub const CAPACITY_THRESHOLD: f64 = 85.0;
+
+// Maximum penalty of each kind of problem; together they add up to 100
+const OFFLINE_WEIGHT: f64 = 40.0;
+const CAPACITY_WEIGHT: f64 = 25.0;
+const MISMATCH_WEIGHT: f64 = 20.0;
+const VERSION_SKEW_WEIGHT: f64 = 15.0;
+
+/// Overall cluster health on a 0-100 scale, derived from the tiers snapshot
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClusterHealth {
+    pub score: u8,
+    /// Contributing problems, biggest penalty first
+    pub problems: Vec<Problem>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Problem {
+    /// Points taken off the score
+    pub penalty: f64,
+    pub description: String,
+}
+
+/// Score the cluster. Expelled instances are left out: they are gone on
+/// purpose. Each kind of problem costs up to its weight, in proportion to
+/// the share of instances (or replicasets) affected; version skew costs
+/// its full weight as soon as instances run more than one version.
+pub fn assess(tiers: &[TierInfo]) -> ClusterHealth {
+    let instances: Vec<_> = tiers
+        .iter()
+        .flat_map(|t| t.replicasets.iter())
+        .flat_map(|rs| rs.instances.iter())
+        .filter(|inst| inst.current_state != StateVariant::Expelled)
+        .collect();
+    let replicasets: Vec<_> = tiers.iter().flat_map(|t| t.replicasets.iter()).collect();
+
+    let mut problems = Vec::new();
+    let mut share = |affected: usize, total: usize, weight: f64, description: String| {
+        if affected > 0 && total > 0 {
+            problems.push(Problem {
+                penalty: weight * affected as f64 / total as f64,
+                description,
+            });
+        }
+    };
+
+    let offline = instances
+        .iter()
+        .filter(|inst| inst.current_state == StateVariant::Offline)
+        .count();
+    share(
+        offline,
+        instances.len(),
+        OFFLINE_WEIGHT,
+        plural(offline, "instance offline", "instances offline"),
+    );
+
+    let mismatched = instances
+        .iter()
+        .filter(|inst| inst.current_state != inst.target_state)
+        .count();
+    share(
+        mismatched,
+        instances.len(),
+        MISMATCH_WEIGHT,
+        plural(
+            mismatched,
+            "instance not at target state",
+            "instances not at target state",
+        ),
+    );
+
+    let full = replicasets
+        .iter()
+        .filter(|rs| rs.capacity_usage > CAPACITY_THRESHOLD)
+        .count();
+    share(
+        full,
+        replicasets.len(),
+        CAPACITY_WEIGHT,
+        format!(
+            "{} over {:.0}% capacity",
+            plural(full, "replicaset", "replicasets"),
+            CAPACITY_THRESHOLD
+        ),
+    );
+
+    let versions: BTreeSet<&str> = instances
+        .iter()
+        .map(|inst| inst.version.as_str())
+        .filter(|v| !v.is_empty())
+        .collect();
+    if versions.len() > 1 {
+        problems.push(Problem {
+            penalty: VERSION_SKEW_WEIGHT,
+            description: format!("{} versions running", versions.len()),
+        });
+    }
+
+    problems.sort_by(|a, b| b.penalty.total_cmp(&a.penalty));
+    let penalty: f64 = problems.iter().map(|p| p.penalty).sum();
+
+    ClusterHealth {
+        score: (100.0 - penalty).round().clamp(0.0, 100.0) as u8,
+        problems,
+    }
+}
+
+fn plural(count: usize, one: &str, many: &str) -> String {
+    format!("{} {}", count, if count == 1 { one } else { many })
+}
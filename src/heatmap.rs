@@ -0,0 +1,84 @@
+//! Heatmap of the cluster: one cell per instance, grouped by tier and
+//! replicaset, so hundreds of instances fit on one screen and hotspots
+//! stand out.
+
+use crate::models::TierInfo;
+
+/// What the color of a cell shows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Coloring {
+    /// Memory usage of the instance's replicaset
+    #[default]
+    Capacity,
+    /// Current state, and whether it is moving to another one
+    State,
+}
+
+impl Coloring {
+    pub fn toggle(self) -> Self {
+        match self {
+            Coloring::Capacity => Coloring::State,
+            Coloring::State => Coloring::Capacity,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Coloring::Capacity => "capacity",
+            Coloring::State => "state",
+        }
+    }
+}
+
+/// Tier, replicaset and instance indexes of a cell
+pub type Cell = (usize, usize, usize);
+
+/// Every instance in display order: tier by tier, replicaset by replicaset
+pub fn cells(tiers: &[TierInfo]) -> Vec<Cell> {
+    tiers
+        .iter()
+        .enumerate()
+        .flat_map(|(t, tier)| {
+            tier.replicasets
+                .iter()
+                .enumerate()
+                .flat_map(move |(r, rs)| (0..rs.instances.len()).map(move |i| (t, r, i)))
+        })
+        .collect()
+}
+
+/// Index `delta` cells away from `from`, stopping at either end
+pub fn step(cells: &[Cell], from: usize, delta: isize) -> usize {
+    from.saturating_add_signed(delta)
+        .min(cells.len().saturating_sub(1))
+}
+
+/// Index of the first cell of the next replicaset, or of the previous one
+/// (the current one when not at its start). Stays put at either end.
+pub fn next_replicaset(cells: &[Cell], from: usize, forward: bool) -> usize {
+    let Some(&(t, r, _)) = cells.get(from) else {
+        return from;
+    };
+    let same = |cell: &Cell| (cell.0, cell.1) == (t, r);
+    if forward {
+        cells[from..]
+            .iter()
+            .position(|cell| !same(cell))
+            .map_or(from, |offset| from + offset)
+    } else {
+        let start = cells[..from]
+            .iter()
+            .rposition(|cell| !same(cell))
+            .map_or(0, |i| i + 1);
+        if start < from {
+            return start;
+        }
+        let Some(&(pt, pr, _)) = start.checked_sub(1).and_then(|i| cells.get(i)) else {
+            return from;
+        };
+        cells[..start]
+            .iter()
+            .rposition(|cell| (cell.0, cell.1) != (pt, pr))
+            .map_or(0, |i| i + 1)
+    }
+}
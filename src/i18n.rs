@@ -0,0 +1,537 @@
+//! Translations of UI strings.
+//!
+//! The English text is the message key: `tr("Refresh")` returns the
+//! translation for the language chosen with `--lang`, or the English text
+//! when the catalog has none, so a missing entry never hides a message.
+//! Placeholders are written `{}` and filled in order by `trf`.
+
+use anyhow::{anyhow, Result};
+use std::fmt::Display;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+/// Language of the UI
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Lang {
+    #[default]
+    En,
+    Ru,
+}
+
+impl FromStr for Lang {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "en" => Ok(Lang::En),
+            "ru" => Ok(Lang::Ru),
+            _ => Err(anyhow!("Unknown language '{}' (expected en or ru)", s)),
+        }
+    }
+}
+
+/// Set by `--lang`
+static LANG: OnceLock<Lang> = OnceLock::new();
+
+/// Show the UI in `lang`. Only the first call has an effect.
+pub fn set_lang(lang: Lang) {
+    let _ = LANG.set(lang);
+}
+
+pub fn lang() -> Lang {
+    LANG.get().copied().unwrap_or_default()
+}
+
+/// `message` in the UI language
+pub fn tr(message: &str) -> &str {
+    translate(lang(), message)
+}
+
+/// `message` in the UI language with its `{}` placeholders replaced by `args`
+pub fn trf(message: &str, args: &[&dyn Display]) -> String {
+    fill(tr(message), args)
+}
+
+fn translate(lang: Lang, message: &str) -> &str {
+    let catalog = match lang {
+        Lang::En => return message,
+        Lang::Ru => RU,
+    };
+    catalog
+        .iter()
+        .find(|(en, _)| *en == message)
+        .map_or(message, |(_, translated)| translated)
+}
+
+fn fill(template: &str, args: &[&dyn Display]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut args = args.iter();
+    let mut rest = template;
+    while let Some(pos) = rest.find("{}") {
+        out.push_str(&rest[..pos]);
+        if let Some(arg) = args.next() {
+            out.push_str(&arg.to_string());
+        }
+        rest = &rest[pos + 2..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Russian catalog
+const RU: &[(&str, &str)] = &[
+    // Header and status bar
+    ("Picodata Cluster Monitor", "монитор кластера Picodata"),
+    ("Updated {}", "Обновлено {}"),
+    ("Cluster Info", "Кластер"),
+    ("Loading cluster info...", "Загрузка данных кластера..."),
+    ("connecting", "подключение"),
+    ("connected", "подключено"),
+    ("reconnecting", "переподключение"),
+    ("down", "нет связи"),
+    ("READ-ONLY", "ТОЛЬКО ЧТЕНИЕ"),
+    ("Loading...", "Загрузка..."),
+    ("Error: {}", "Ошибка: {}"),
+    (
+        " (backing off, next retry in {}s)",
+        " (пауза, следующая попытка через {} с)",
+    ),
+    ("user: {}", "пользователь: {}"),
+    ("{} marked", "отмечено: {}"),
+    ("recording @{}", "запись @{}"),
+    // Key hints
+    ("Navigate", "Навигация"),
+    ("Collapse/Expand", "Свернуть/Развернуть"),
+    ("Details", "Подробности"),
+    ("Actions", "Действия"),
+    ("Export", "Экспорт"),
+    ("Copy names", "Копировать имена"),
+    ("View", "Вид"),
+    ("Sort", "Сортировка"),
+    ("Order", "Порядок"),
+    ("Filter", "Фильтр"),
+    ("Saved", "Сохранённые"),
+    ("Columns", "Колонки"),
+    ("Whole cluster", "Весь кластер"),
+    ("Siblings", "Соседи"),
+    ("Search", "Поиск"),
+    ("Next/Prev", "След./Пред."),
+    ("Refresh", "Обновить"),
+    ("Logout", "Выйти"),
+    ("Quit", "Выход"),
+    ("Apply", "Применить"),
+    ("Clear", "Очистить"),
+    ("History", "История"),
+    ("Save", "Сохранить"),
+    ("Cancel", "Отмена"),
+    ("Confirm", "Подтвердить"),
+    ("Save (empty for timestamp)", "Сохранить (пустое — метка времени)"),
+    ("Fuzzy: on", "Нечёткий: вкл."),
+    ("Fuzzy: off", "Нечёткий: выкл."),
+    ("Filter: ", "Фильтр: "),
+    ("Search: ", "Поиск: "),
+    ("Snapshot name: ", "Имя снимка: "),
+    // Cluster header
+    ("Cluster: ", "Кластер: "),
+    ("Version: ", "Версия: "),
+    ("Picodata: ", "Picodata: "),
+    ("Replicasets: ", "Replicasets: "),
+    ("Instances: ", "Инстансы: "),
+    ("Plugins: ", "Плагины: "),
+    ("none", "нет"),
+    ("Memory: {} / {} ({}%)", "Память: {} / {} ({}%)"),
+    // Login
+    ("Picodata Login", "Вход в Picodata"),
+    (
+        "Enter your credentials to connect to ",
+        "Введите учётные данные для подключения к ",
+    ),
+    ("Username", "Имя пользователя"),
+    ("Password", "Пароль"),
+    ("Password (visible)", "Пароль (виден)"),
+    ("{} chars", "символов: {}"),
+    ("caps lock on", "включён Caps Lock"),
+    ("required", "обязательно"),
+    (
+        "empty, Enter again to submit",
+        "пусто, нажмите Enter ещё раз для отправки",
+    ),
+    ("Remember me", "Запомнить меня"),
+    ("(save login session)", "(сохранить сессию)"),
+    (
+        "{} failed attempts, retry in {}s",
+        "неудачных попыток: {}, повтор через {} с",
+    ),
+    ("Failed attempts: {}", "Неудачных попыток: {}"),
+    ("navigate", "перемещение"),
+    ("toggle", "переключить"),
+    ("show/hide", "показать/скрыть"),
+    ("hold to peek", "удерживать для просмотра"),
+    ("test", "проверить"),
+    ("login", "войти"),
+    ("quit", "выход"),
+    ("Press ^T to test the connection", "Нажмите ^T, чтобы проверить соединение"),
+    ("Checking server...", "Проверка сервера..."),
+    ("✓ Cluster ", "✓ Кластер "),
+    ("✓ Server reachable", "✓ Сервер доступен"),
+    (
+        " (cluster shown after login)",
+        " (кластер будет показан после входа)",
+    ),
+    ("✗ Server unreachable: {}", "✗ Сервер недоступен: {}"),
+    (
+        "Invalid username or password. Try again.",
+        "Неверное имя пользователя или пароль. Попробуйте ещё раз.",
+    ),
+    ("Instances", "Инстансы"),
+    ("Replicasets", "Replicasets"),
+    ("Tiers / Replicasets / Instances", "Tiers / Replicasets / Инстансы"),
+    ("Instance", "Инстанс"),
+    (
+        "No tiers found. Press 'r' to refresh.",
+        "Tiers не найдены. Нажмите 'r', чтобы обновить.",
+    ),
+    (
+        "No replicasets found. Press 'r' to refresh.",
+        "Replicasets не найдены. Нажмите 'r', чтобы обновить.",
+    ),
+    (
+        "No instances found. Press 'r' to refresh.",
+        "Инстансы не найдены. Нажмите 'r', чтобы обновить.",
+    ),
+    (
+        "No instances in {}. Press Esc to return.",
+        "В {} нет инстансов. Нажмите Esc, чтобы вернуться.",
+    ),
+    (
+        "No instances match filter \"{}\". Press Esc to clear.",
+        "Нет инстансов под фильтр \"{}\". Нажмите Esc, чтобы сбросить.",
+    ),
+    // Rows and popup fields
+    ("Tier", "Tier"),
+    ("Lag", "Отставание"),
+    ("Ver", "Верс."),
+    ("Up", "Работает"),
+    ("Inst:", "Инст.:"),
+    ("Buckets:", "Бакеты:"),
+    ("Vote:", "Голос:"),
+    ("Mem:", "Память:"),
+    ("Name:", "Имя:"),
+    ("Current State:", "Текущее состояние:"),
+    ("Target State:", "Целевое состояние:"),
+    ("Is Leader:", "Лидер:"),
+    ("Is Voter:", "Голосующий:"),
+    ("Is Raft Leader:", "Лидер Raft:"),
+    ("Version:", "Версия:"),
+    ("Uptime:", "Время работы:"),
+    ("Status:", "Статус:"),
+    ("Tier:", "Tier:"),
+    ("Replicaset:", "Replicaset:"),
+    ("State:", "Состояние:"),
+    ("Term:", "Терм:"),
+    ("Leader:", "Лидер:"),
+    ("Applied:", "Применено:"),
+    ("Persisted:", "Сохранено:"),
+    ("Limbo Owner:", "Владелец limbo:"),
+    ("Active:", "Активно:"),
+    ("Resharding:", "Решардинг:"),
+    ("Garbage:", "Мусор:"),
+    ("updated", "обновлено"),
+    ("high", "высокая"),
+    ("critical", "критическая"),
+    // Popups
+    ("Instance Details", "Сведения об инстансе"),
+    ("Health Status", "Состояние здоровья"),
+    ("Leader Distribution", "Распределение лидеров"),
+    ("Memory Statistics", "Статистика памяти"),
+    ("Request Timings", "Время запросов"),
+    ("Saved Filters", "Сохранённые фильтры"),
+    ("Compare Snapshot", "Сравнение со снимком"),
+    ("By tier", "По tiers"),
+    ("By failure domain", "По failure domains"),
+    ("By host", "По хостам"),
+    ("Changes since {}", "Изменения с {}"),
+    ("No changes", "Изменений нет"),
+    ("loading...", "загрузка..."),
+    ("error", "ошибка"),
+    ("Loading health status...", "Загрузка состояния..."),
+    ("Probing {} instances...", "Опрос инстансов: {}..."),
+    ("Esc to close", "Esc — закрыть"),
+    ("Press Esc or Enter to close", "Esc или Enter — закрыть"),
+    ("Press Esc to close, r to refresh", "Esc — закрыть, r — обновить"),
+    ("Enter or 1-9 to apply, Esc to close", "Enter или 1-9 — применить, Esc — закрыть"),
+    (
+        "Enter to compare with the live cluster, Esc to close",
+        "Enter — сравнить с текущим кластером, Esc — закрыть",
+    ),
+    (
+        "Space show/hide, J/K move, Esc to close",
+        "Пробел — показать/скрыть, J/K — переместить, Esc — закрыть",
+    ),
+    ("Enter or key to run, Esc to close", "Enter или клавиша — выполнить, Esc — закрыть"),
+    (
+        "Failure domain figures split each replicaset's memory evenly among its instances. Esc to close",
+        "Для failure domains память replicaset делится поровну между его инстансами. Esc — закрыть",
+    ),
+    (
+        "Figures cover this session; 304 responses count as requests with no body. Esc to close",
+        "Данные за текущую сессию; ответы 304 считаются запросами без тела. Esc — закрыть",
+    ),
+    ("No requests yet", "Запросов ещё не было"),
+    ("Endpoint", "Запрос"),
+    ("Requests", "Запросов"),
+    ("Errors", "Ошибок"),
+    ("Min", "Мин."),
+    ("Avg", "Сред."),
+    ("Received", "Получено"),
+    ("Choose a cluster", "Выберите кластер"),
+    ("connect", "подключиться"),
+    ("checking...", "проверка..."),
+    ("ready", "доступен"),
+    ("login required", "нужен вход"),
+    ("Connecting to ", "Подключение к "),
+    ("Could not connect to ", "Не удалось подключиться к "),
+    ("Retrying in {}s", "Повтор через {} с"),
+    (
+        "1 key will be applied once connected",
+        "Нажатая клавиша сработает после подключения",
+    ),
+    (
+        "{} keys will be applied once connected",
+        "Нажатых клавиш: {}, они сработают после подключения",
+    ),
+    ("retry", "повторить"),
+    ("edit URL", "изменить URL"),
+    ("cancel", "отмена"),
+    ("Tables", "Таблицы"),
+    ("Table", "Таблица"),
+    ("Distribution", "Распределение"),
+    ("Engine", "Движок"),
+    ("Rows", "Строк"),
+    ("Size", "Размер"),
+    ("global", "глобальная"),
+    ("sharded", "шардированная"),
+    ("Loading tables...", "Загрузка таблиц..."),
+    (
+        "Sizes are estimated from the connected instance; - when it holds no share. j/k to scroll, r to reload, Esc to close",
+        "Размеры оценены по подключённому инстансу; - если на нём нет части данных. j/k — прокрутка, r — обновить, Esc — закрыть",
+    ),
+    (
+        "The HTTP API doesn't list tables; start picotui with --iproto to browse them",
+        "HTTP API не отдаёт список таблиц; чтобы просмотреть их, запустите picotui с --iproto",
+    ),
+    ("Configuration", "Конфигурация"),
+    ("Parameter", "Параметр"),
+    ("Value", "Значение"),
+    ("Default", "По умолчанию"),
+    ("No matching parameters", "Нет подходящих параметров"),
+    ("Loading parameters...", "Загрузка параметров..."),
+    (
+        "Highlighted values differ from the default. / to search, j/k to scroll, r to reload, Esc to close",
+        "Выделены значения, отличные от значений по умолчанию. / — поиск, j/k — прокрутка, r — обновить, Esc — закрыть",
+    ),
+    (
+        "The HTTP API doesn't list configuration parameters; start picotui with --iproto to browse them",
+        "HTTP API не отдаёт параметры конфигурации; чтобы просмотреть их, запустите picotui с --iproto",
+    ),
+    ("OPS", "ОПЕРАЦИИ"),
+    ("Rolling restart", "Поочерёдный перезапуск"),
+    (
+        "Instances are restarted one at a time, followers first:",
+        "Инстансы перезапускаются по одному, сначала реплики:",
+    ),
+    ("Leader switch", "Смена лидера"),
+    ("Restart", "Перезапуск"),
+    ("by hand", "вручную"),
+    ("switching leader away", "смена лидера"),
+    ("taking offline", "остановка"),
+    ("waiting for Online", "ожидание Online"),
+    (
+        "move the replicaset leader to another instance",
+        "переведите лидерство репликасета на другой инстанс",
+    ),
+    ("restart the instance", "перезапустите инстанс"),
+    ("All instances restarted", "Все инстансы перезапущены"),
+    ("Stopped", "Остановлено"),
+    (
+        "Aborted; the remaining instances were not touched",
+        "Прервано; остальные инстансы не затронуты",
+    ),
+    ("Enter to start, Esc to cancel", "Enter — начать, Esc — отмена"),
+    ("x to abort before the next step", "x — прервать перед следующим шагом"),
+    ("Caution", "Внимание"),
+    ("Danger", "Опасно"),
+    ("Critical", "Критично"),
+    ("Type", "Введите"),
+    ("to confirm:", "для подтверждения:"),
+    ("Enter to confirm, Esc to cancel", "Enter — подтвердить, Esc — отмена"),
+    ("Esc to cancel", "Esc — отмена"),
+    ("Dry run", "Пробный запуск"),
+    ("OPS DRY-RUN", "ОПЕРАЦИИ: ПРОБНЫЙ ЗАПУСК"),
+    ("Enter for a dry run, Esc to cancel", "Enter — пробный запуск, Esc — отмена"),
+    ("Alerts", "Оповещения"),
+    ("1 alert", "оповещений: 1"),
+    ("{} alerts", "оповещений: {}"),
+    ("now", "сейчас"),
+    ("since {}", "с {}"),
+    (
+        "No alert rules configured; add them under \"alerts\" in the config file",
+        "Правила оповещений не заданы; добавьте их в \"alerts\" файла конфигурации",
+    ),
+    (
+        "All clear: {} rules checked on every refresh",
+        "Всё в порядке: правил, проверяемых при каждом обновлении: {}",
+    ),
+    ("Heatmap", "Тепловая карта"),
+    ("capacity", "заполненность"),
+    ("state", "состояние"),
+    ("No instances", "Нет инстансов"),
+    ("replicaset", "репликасет"),
+    ("tier", "tier"),
+    ("leader", "лидер"),
+    ("changing state", "меняет состояние"),
+    (
+        "←→ instance, ↑↓ replicaset, c coloring, Enter to go to the instance, Esc to close",
+        "←→ — инстанс, ↑↓ — репликасет, c — раскраска, Enter — перейти к инстансу, Esc — закрыть",
+    ),
+    ("Topology", "Топология"),
+    ("instance", "инстанс"),
+    ("not set", "не задан"),
+    ("No failure domains", "Failure domains не заданы"),
+    ("{}/{} online", "в сети {}/{}"),
+    ("n to show names, Esc to close", "n — показать имена, Esc — закрыть"),
+    (
+        "Nothing was run. y to copy the commands, Esc to close",
+        "Ничего не запущено. y — скопировать команды, Esc — закрыть",
+    ),
+    ("Diagnostics", "Диагностика"),
+    ("Frame", "Кадр"),
+    ("Frames", "Кадров"),
+    ("Responses", "Ответов"),
+    ("In flight", "Ожидают"),
+    ("{} ms, avg {}, max {}", "{} мс, сред. {}, макс. {}"),
+    ("{} of {}", "{} из {}"),
+    ("{} last frame, {} at most per tick", "{} за кадр, до {} за цикл"),
+    ("F12 to hide", "F12 — скрыть"),
+    ("Error Log", "Журнал ошибок"),
+    ("No errors this session", "За сессию ошибок не было"),
+    ("  ×{} since {}", "  ×{} с {}"),
+    (
+        "Newest first. c to clear, Esc to close",
+        "Сначала новые. c — очистить, Esc — закрыть",
+    ),
+    (" (! lists {} errors)", " (! — все ошибки: {})"),
+    ("Sort by", "Сортировать по"),
+    ("ascending", "по возрастанию"),
+    ("descending", "по убыванию"),
+    (
+        "Enter or key to sort, ←→ order, Esc to close",
+        "Enter или клавиша — сортировать, ←→ — порядок, Esc — закрыть",
+    ),
+    ("Views", "Режимы"),
+    ("filtered", "с фильтром"),
+    ("{} with problems", "проблемных: {}"),
+    (
+        "Number or Enter to switch, Esc to close",
+        "Цифра или Enter — переключить, Esc — закрыть",
+    ),
+    // Status messages and notifications
+    (
+        "The URL must start with http:// or https://",
+        "URL должен начинаться с http:// или https://",
+    ),
+    ("API worker restarted", "Обработчик API перезапущен"),
+    ("{} left the cluster", "{} покинул кластер"),
+    ("Instance left the cluster", "Инстанс покинул кластер"),
+    ("Alert: {}", "Оповещение: {}"),
+    (
+        "Alert: {} (command failed: {})",
+        "Оповещение: {} (ошибка команды: {})",
+    ),
+    ("{} back Online", "{}: снова Online"),
+    ("{} instances back Online", "Инстансов снова Online: {}"),
+    ("{} went Offline", "{}: перешёл в Offline"),
+    ("{} instances went Offline", "Инстансов перешло в Offline: {}"),
+    ("Expand problems: on", "Раскрывать проблемные: вкл."),
+    ("Expand problems: off", "Раскрывать проблемные: выкл."),
+    ("Bookmark: press a letter", "Закладка: нажмите букву"),
+    ("Jump to bookmark: press a letter", "Переход к закладке: нажмите букву"),
+    ("Bookmark {}: {}", "Закладка {}: {}"),
+    ("No bookmark {}", "Закладки {} нет"),
+    ("Bookmark {}: {} is gone", "Закладка {}: {} больше нет"),
+    ("Could not save bookmarks: {}", "Не удалось сохранить закладки: {}"),
+    ("Copied {}", "Скопировано: {}"),
+    ("Copied the commands", "Команды скопированы"),
+    ("Opening {}", "Открывается {}"),
+    ("Could not open {}: {}", "Не удалось открыть {}: {}"),
+    ("Snapshots are not available", "Снимки недоступны"),
+    ("Nothing to snapshot yet", "Пока нечего сохранять в снимок"),
+    ("Saved snapshot \"{}\"", "Снимок \"{}\" сохранён"),
+    ("Failed to save snapshot: {}", "Не удалось сохранить снимок: {}"),
+    (
+        "No saved snapshots of this cluster (w to save one)",
+        "Сохранённых снимков этого кластера нет (w — сохранить)",
+    ),
+    ("Skipped skin {}", "Пропущена тема {}"),
+    ("Theme: {}", "Тема: {}"),
+    ("Unknown theme \"{}\"", "Неизвестная тема \"{}\""),
+    ("Pinned {}", "Закреплён {}"),
+    ("Unpinned {}", "Откреплён {}"),
+    ("Could not save pins: {}", "Не удалось сохранить закрепления: {}"),
+    ("Leader", "Лидер"),
+    ("Raft leader", "Лидер Raft"),
+    ("Replicaset {} has no leader", "У репликасета {} нет лидера"),
+    ("No Raft leader reported", "Лидер Raft неизвестен"),
+    ("{} {} is filtered out", "{} {} скрыт фильтром"),
+    (
+        "No instances marked (Space to mark)",
+        "Нет отмеченных инстансов (Пробел — отметить)",
+    ),
+    ("Copied {} names to clipboard", "Скопировано имён в буфер обмена: {}"),
+    ("Exported {} instances to {}", "Инстансов экспортировано: {}, файл {}"),
+    ("Failed to export instances: {}", "Не удалось экспортировать инстансы: {}"),
+    ("Saved bug report to {}", "Отчёт об ошибке сохранён в {}"),
+    ("Failed to save bug report: {}", "Не удалось сохранить отчёт об ошибке: {}"),
+    ("Saved screen to {}", "Экран сохранён в {}"),
+    ("Failed to save screen: {}", "Не удалось сохранить экран: {}"),
+    ("No saved filters in config.json", "В config.json нет сохранённых фильтров"),
+    ("Pattern not found: {}", "Не найдено: {}"),
+    ("Record macro: press a letter", "Запись макроса: нажмите букву"),
+    ("Replay macro: press a letter", "Воспроизведение макроса: нажмите букву"),
+    ("Recording @{}, Q to stop", "Запись @{}, Q — остановить"),
+    ("Recorded @{}", "Записан @{}"),
+    ("Register {} is empty", "Регистр {} пуст"),
+    (
+        "Macro stopped at the confirmation",
+        "Макрос остановлен на подтверждении",
+    ),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_translate_falls_back_to_english() {
+        assert_eq!(translate(Lang::En, "Refresh"), "Refresh");
+        assert_eq!(translate(Lang::Ru, "Refresh"), "Обновить");
+        assert_eq!(
+            translate(Lang::Ru, "Not in the catalog"),
+            "Not in the catalog"
+        );
+        assert_eq!(fill(translate(Lang::Ru, "{} marked"), &[&3]), "отмечено: 3");
+        assert!("de".parse::<Lang>().is_err());
+    }
+
+    #[test]
+    fn test_catalog_keeps_placeholders() {
+        for (en, ru) in RU {
+            assert_eq!(en.matches("{}").count(), ru.matches("{}").count(), "{}", en);
+        }
+        let mut keys: Vec<&str> = RU.iter().map(|(en, _)| *en).collect();
+        keys.sort_unstable();
+        let total = keys.len();
+        keys.dedup();
+        assert_eq!(keys.len(), total, "duplicate catalog entries");
+    }
+}
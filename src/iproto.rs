@@ -0,0 +1,1132 @@
+//! Cluster data over the binary protocol (iproto), for clusters that run
+//! without the HTTP API (`--iproto`).
+//!
+//! The worker logs in to one instance with the user's credentials and
+//! reads the topology from the system tables with `pico.sql`. Memory
+//! usage, HTTP addresses and instance health are only known to the HTTP
+//! API, so they stay empty.
+
+use crate::api::{
+    self, log_debug, ApiMessage, ApiRequest, ApiResponse, HealthProbes, ResponseMeta,
+    ServerIdentity, INVALID_CREDENTIALS,
+};
+use crate::models::*;
+use crate::prepare;
+use crate::source::DataSource;
+use crate::stream::StreamEvent;
+use anyhow::{anyhow, bail, Context, Result};
+use base64::Engine;
+use md5::Md5;
+use rmpv::Value;
+use sha1::{Digest, Sha1};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::io::{Cursor, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::ops::ControlFlow;
+use std::str::FromStr;
+use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
+use std::time::{Duration, Instant};
+
+/// Error of health requests, which only the HTTP API answers
+const HEALTH_NEEDS_HTTP: &str = "Instance health is only available through the HTTP API";
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const IO_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Largest response accepted, and so the most a length prefix can make the
+/// client allocate. The system tables of a big cluster are a few
+/// megabytes; a length beyond this is a broken or hostile peer.
+const MAX_RESPONSE_LEN: u64 = 16 * 1024 * 1024;
+
+// Request types and body keys of the protocol
+const IPROTO_AUTH: u64 = 0x07;
+const IPROTO_CALL: u64 = 0x0a;
+const IPROTO_EVAL: u64 = 0x29;
+const KEY_REQUEST_TYPE: u64 = 0x00;
+const KEY_SYNC: u64 = 0x01;
+const KEY_TUPLE: u64 = 0x21;
+const KEY_FUNCTION_NAME: u64 = 0x22;
+const KEY_USER_NAME: u64 = 0x23;
+const KEY_EXPR: u64 = 0x27;
+const KEY_DATA: u64 = 0x30;
+const KEY_ERROR_24: u64 = 0x31;
+
+/// Error codes for an unknown user or a wrong password
+const CREDENTIAL_ERRORS: [u64; 3] = [45, 47, 229];
+
+/// How the password is proven to the server, as set for the user with
+/// `CREATE USER ... USING <method>`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AuthMethod {
+    #[default]
+    Md5,
+    ChapSha1,
+    /// The password is sent as is and checked against an LDAP server
+    Ldap,
+}
+
+impl AuthMethod {
+    fn label(self) -> &'static str {
+        match self {
+            AuthMethod::Md5 => "md5",
+            AuthMethod::ChapSha1 => "chap-sha1",
+            AuthMethod::Ldap => "ldap",
+        }
+    }
+
+    /// Proof of the password for the greeting's `salt`
+    fn scramble(self, user: &str, password: &str, salt: &[u8]) -> Vec<u8> {
+        match self {
+            AuthMethod::Md5 => md5_scramble(user, password, salt).into_bytes(),
+            AuthMethod::ChapSha1 => chap_sha1_scramble(password, salt),
+            AuthMethod::Ldap => password.as_bytes().to_vec(),
+        }
+    }
+}
+
+impl FromStr for AuthMethod {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "md5" => Ok(AuthMethod::Md5),
+            "chap-sha1" => Ok(AuthMethod::ChapSha1),
+            "ldap" => Ok(AuthMethod::Ldap),
+            _ => Err(anyhow!(
+                "Unknown auth method '{}' (expected md5, chap-sha1 or ldap)",
+                s
+            )),
+        }
+    }
+}
+
+/// `md5` followed by md5(md5(password + user) + salt), in hex
+fn md5_scramble(user: &str, password: &str, salt: &[u8]) -> String {
+    let inner = hex(&Md5::digest(format!("{}{}", password, user)));
+    let mut outer = Md5::new();
+    outer.update(inner.as_bytes());
+    outer.update(&salt[..4.min(salt.len())]);
+    format!("md5{}", hex(&outer.finalize()))
+}
+
+/// sha1(password) xor sha1(salt + sha1(sha1(password)))
+fn chap_sha1_scramble(password: &str, salt: &[u8]) -> Vec<u8> {
+    let hash1 = Sha1::digest(password.as_bytes());
+    let hash2 = Sha1::digest(hash1);
+    let mut hasher = Sha1::new();
+    hasher.update(&salt[..20.min(salt.len())]);
+    hasher.update(hash2);
+    let hash3 = hasher.finalize();
+    hash1.iter().zip(hash3.iter()).map(|(a, b)| a ^ b).collect()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Error reported by the server, as opposed to a network failure
+#[derive(Debug)]
+struct ServerError {
+    code: u64,
+    message: String,
+}
+
+impl fmt::Display for ServerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for ServerError {}
+
+/// One session with an instance
+struct Connection {
+    stream: TcpStream,
+    salt: Vec<u8>,
+    sync: u64,
+    /// Response bytes read since the last `take_bytes`
+    bytes: u64,
+}
+
+impl Connection {
+    /// Connect and read the greeting
+    fn open(address: &str) -> Result<Self> {
+        let addr = address
+            .to_socket_addrs()
+            .with_context(|| format!("Invalid address {}", address))?
+            .next()
+            .ok_or_else(|| anyhow!("Invalid address {}", address))?;
+        let mut stream = TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT)?;
+        stream.set_read_timeout(Some(IO_TIMEOUT))?;
+        stream.set_write_timeout(Some(IO_TIMEOUT))?;
+
+        // Two 64-byte lines: server version, then the base64 salt
+        let mut greeting = [0u8; 128];
+        stream.read_exact(&mut greeting)?;
+        if !greeting.starts_with(b"Tarantool") {
+            bail!("{} does not speak the binary protocol", address);
+        }
+        let salt = String::from_utf8_lossy(&greeting[64..]).trim().to_string();
+        let salt = base64::engine::general_purpose::STANDARD
+            .decode(salt)
+            .context("Invalid greeting")?;
+
+        Ok(Self {
+            stream,
+            salt,
+            sync: 0,
+            bytes: 0,
+        })
+    }
+
+    fn authenticate(&mut self, method: AuthMethod, user: &str, password: &str) -> Result<()> {
+        let scramble = method.scramble(user, password, &self.salt);
+        let mut body = Vec::new();
+        rmp::encode::write_map_len(&mut body, 2)?;
+        rmp::encode::write_uint(&mut body, KEY_USER_NAME)?;
+        rmp::encode::write_str(&mut body, user)?;
+        rmp::encode::write_uint(&mut body, KEY_TUPLE)?;
+        rmp::encode::write_array_len(&mut body, 2)?;
+        rmp::encode::write_str(&mut body, method.label())?;
+        // Scrambles are raw bytes, but the protocol wants them as a string
+        rmp::encode::write_str_len(&mut body, scramble.len() as u32)?;
+        body.extend_from_slice(&scramble);
+        self.request(IPROTO_AUTH, &body)?;
+        Ok(())
+    }
+
+    /// Call a stored function with string arguments and return its results
+    fn call(&mut self, function: &str, args: &[&str]) -> Result<Vec<Value>> {
+        let mut body = Vec::new();
+        rmp::encode::write_map_len(&mut body, 2)?;
+        rmp::encode::write_uint(&mut body, KEY_FUNCTION_NAME)?;
+        rmp::encode::write_str(&mut body, function)?;
+        rmp::encode::write_uint(&mut body, KEY_TUPLE)?;
+        rmp::encode::write_array_len(&mut body, args.len() as u32)?;
+        for arg in args {
+            rmp::encode::write_str(&mut body, arg)?;
+        }
+        match self.request(IPROTO_CALL, &body)? {
+            Some(Value::Array(values)) => Ok(values),
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    /// Run a Lua chunk on the instance and return what it returns
+    fn eval(&mut self, expr: &str) -> Result<Vec<Value>> {
+        let mut body = Vec::new();
+        rmp::encode::write_map_len(&mut body, 2)?;
+        rmp::encode::write_uint(&mut body, KEY_EXPR)?;
+        rmp::encode::write_str(&mut body, expr)?;
+        rmp::encode::write_uint(&mut body, KEY_TUPLE)?;
+        rmp::encode::write_array_len(&mut body, 0)?;
+        match self.request(IPROTO_EVAL, &body)? {
+            Some(Value::Array(values)) => Ok(values),
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    /// Send one request and wait for its response; the response's data,
+    /// if it has any
+    fn request(&mut self, request_type: u64, body: &[u8]) -> Result<Option<Value>> {
+        self.sync += 1;
+        let mut header = Vec::new();
+        rmp::encode::write_map_len(&mut header, 2)?;
+        rmp::encode::write_uint(&mut header, KEY_REQUEST_TYPE)?;
+        rmp::encode::write_uint(&mut header, request_type)?;
+        rmp::encode::write_uint(&mut header, KEY_SYNC)?;
+        rmp::encode::write_uint(&mut header, self.sync)?;
+
+        let mut packet = Vec::with_capacity(5 + header.len() + body.len());
+        rmp::encode::write_u32(&mut packet, (header.len() + body.len()) as u32)?;
+        packet.extend_from_slice(&header);
+        packet.extend_from_slice(body);
+        self.stream.write_all(&packet)?;
+
+        let len: u64 = rmp::decode::read_int(&mut self.stream)
+            .map_err(|e| anyhow!("Invalid response: {}", e))?;
+        if len > MAX_RESPONSE_LEN {
+            bail!(
+                "Response of {} bytes is over the {} MiB limit",
+                len,
+                MAX_RESPONSE_LEN / 1024 / 1024
+            );
+        }
+        let mut response = vec![0u8; len as usize];
+        self.stream.read_exact(&mut response)?;
+        self.bytes += len;
+
+        let mut cursor = Cursor::new(response.as_slice());
+        let header = rmpv::decode::read_value(&mut cursor)?;
+        let body = if (cursor.position() as usize) < response.len() {
+            rmpv::decode::read_value(&mut cursor)?
+        } else {
+            Value::Nil
+        };
+
+        let status = field(&header, KEY_REQUEST_TYPE)
+            .and_then(Value::as_u64)
+            .unwrap_or(0);
+        if status != 0 {
+            let message = field(&body, KEY_ERROR_24)
+                .and_then(Value::as_str)
+                .unwrap_or("unknown error")
+                .to_string();
+            return Err(ServerError {
+                code: status & 0x7fff,
+                message,
+            }
+            .into());
+        }
+        Ok(field(&body, KEY_DATA).cloned())
+    }
+
+    fn take_bytes(&mut self) -> u64 {
+        std::mem::take(&mut self.bytes)
+    }
+}
+
+/// Value under an integer key of a protocol map
+fn field(map: &Value, key: u64) -> Option<&Value> {
+    map.as_map()?
+        .iter()
+        .find(|(k, _)| k.as_u64() == Some(key))
+        .map(|(_, v)| v)
+}
+
+/// Value under a string key of a map returned by a function
+fn entry<'a>(map: &'a Value, key: &str) -> Option<&'a Value> {
+    map.as_map()?
+        .iter()
+        .find(|(k, _)| k.as_str() == Some(key))
+        .map(|(_, v)| v)
+}
+
+/// One row of a `pico.sql` result by column name
+type Record = HashMap<String, Value>;
+
+/// Run a query with `pico.sql` and return its rows
+fn select(conn: &mut Connection, query: &str) -> Result<Vec<Record>> {
+    let result = conn.call("pico.sql", &[query])?;
+    let result = result.first().ok_or_else(|| anyhow!("Empty result"))?;
+    let columns: Vec<String> = entry(result, "metadata")
+        .and_then(Value::as_array)
+        .map(|metadata| {
+            metadata
+                .iter()
+                .map(|column| text(entry(column, "name")))
+                .collect()
+        })
+        .unwrap_or_default();
+    let rows = entry(result, "rows")
+        .and_then(Value::as_array)
+        .cloned()
+        .unwrap_or_default();
+    Ok(rows
+        .into_iter()
+        .filter_map(|row| match row {
+            Value::Array(values) => Some(columns.iter().cloned().zip(values).collect()),
+            _ => None,
+        })
+        .collect())
+}
+
+fn text(value: Option<&Value>) -> String {
+    match value {
+        Some(Value::String(s)) => s.as_str().unwrap_or_default().to_string(),
+        Some(Value::Nil) | None => String::new(),
+        Some(other) => other.to_string(),
+    }
+}
+
+/// Instance state stored as `[variant, incarnation]`, a map with a
+/// `variant` key, or a bare name
+fn state(value: Option<&Value>) -> StateVariant {
+    let variant = match value {
+        Some(Value::Array(parts)) => text(parts.first()),
+        Some(map @ Value::Map(_)) => text(entry(map, "variant")),
+        other => text(other),
+    };
+    match variant.as_str() {
+        "Online" => StateVariant::Online,
+        "Expelled" => StateVariant::Expelled,
+        _ => StateVariant::Offline,
+    }
+}
+
+/// Cluster data gathered in one pass over the system tables
+struct Topology {
+    cluster_name: String,
+    version: String,
+    tiers: Vec<TierInfo>,
+    plugins: Vec<String>,
+}
+
+impl Topology {
+    fn fetch(conn: &mut Connection) -> Result<Self> {
+        let instances = select(conn, "SELECT * FROM _pico_instance")?;
+        let replicasets = select(conn, "SELECT * FROM _pico_replicaset")?;
+        let tiers = select(conn, "SELECT * FROM _pico_tier")?;
+        // Older versions keep addresses elsewhere or don't have these
+        let addresses = select(conn, "SELECT * FROM _pico_peer_address").unwrap_or_default();
+        let plugins = select(conn, "SELECT * FROM _pico_plugin")
+            .unwrap_or_default()
+            .iter()
+            .filter(|plugin| plugin.get("enabled").and_then(Value::as_bool) == Some(true))
+            .map(|plugin| text(plugin.get("name")))
+            .collect();
+        let raft_leader = conn
+            .call("pico.raft_status", &[])
+            .ok()
+            .and_then(|status| status.first().and_then(|s| entry(s, "leader_id")?.as_u64()));
+        let (cluster_name, self_name) = match conn.call("pico.instance_info", &[]) {
+            Ok(info) => {
+                let info = info.first().cloned().unwrap_or(Value::Nil);
+                (
+                    text(entry(&info, "cluster_name")),
+                    text(entry(&info, "name")),
+                )
+            }
+            Err(_) => (String::new(), String::new()),
+        };
+        let version = instances
+            .iter()
+            .find(|inst| text(inst.get("name")) == self_name)
+            .map(|inst| text(inst.get("picodata_version")))
+            .unwrap_or_default();
+
+        Ok(Self {
+            cluster_name,
+            version,
+            tiers: build_tiers(&tiers, &replicasets, &instances, &addresses, raft_leader),
+            plugins,
+        })
+    }
+
+    fn cluster_info(&self) -> ClusterInfo {
+        let instances = || {
+            self.tiers
+                .iter()
+                .flat_map(|t| t.replicasets.iter())
+                .flat_map(|r| r.instances.iter())
+        };
+        let count = |state| {
+            instances()
+                .filter(|inst| inst.current_state == state)
+                .count()
+        };
+        ClusterInfo {
+            capacity_usage: 0.0,
+            cluster_name: self.cluster_name.clone(),
+            cluster_version: self.version.clone(),
+            current_instance_version: self.version.clone(),
+            replicasets_count: self.tiers.iter().map(|t| t.replicasets.len()).sum(),
+            instances_current_state_offline: count(StateVariant::Offline),
+            instances_current_state_online: count(StateVariant::Online),
+            memory: MemoryInfo { usable: 0, used: 0 },
+            plugins: self.plugins.clone(),
+        }
+    }
+}
+
+/// Size and row count of every space on the connected instance, by name
+const LOCAL_SIZES: &str = "
+local sizes = {}
+for _, def in box.space._space:pairs() do
+    local space = box.space[def.name]
+    if space ~= nil then
+        local ok, len = pcall(space.len, space)
+        sizes[def.name] = {space:bsize(), ok and len or box.NULL}
+    end
+end
+return sizes
+";
+
+/// Tables from `_pico_table`, with sizes estimated from the connected
+/// instance's share of the data
+fn fetch_tables(conn: &mut Connection) -> Result<Vec<TableInfo>> {
+    let tables = select(conn, "SELECT * FROM _pico_table")?;
+    let replicasets = select(conn, "SELECT * FROM _pico_replicaset")?;
+    let local_tier = conn
+        .call("pico.instance_info", &[])
+        .ok()
+        .and_then(|info| info.first().map(|info| text(entry(info, "tier"))))
+        .unwrap_or_default();
+    // Needs the right to execute Lua; without it only the sizes are missing
+    let sizes = conn
+        .eval(LOCAL_SIZES)
+        .ok()
+        .and_then(|values| values.into_iter().next())
+        .unwrap_or(Value::Nil);
+    Ok(build_tables(&tables, &replicasets, &local_tier, &sizes))
+}
+
+/// Scale local sizes up to the cluster: a global table is the same
+/// everywhere, a sharded one is assumed spread evenly over its tier's
+/// replicasets. Sharded tables of other tiers can't be measured from here.
+/// Largest first.
+fn build_tables(
+    tables: &[Record],
+    replicasets: &[Record],
+    local_tier: &str,
+    sizes: &Value,
+) -> Vec<TableInfo> {
+    let mut result: Vec<TableInfo> = tables
+        .iter()
+        .map(|table| {
+            let name = text(table.get("name"));
+            let distribution = distribution(table.get("distribution"));
+            let factor = match &distribution {
+                TableDistribution::Global => Some(1),
+                TableDistribution::Sharded { tier } if tier == local_tier => Some(
+                    replicasets
+                        .iter()
+                        .filter(|rs| text(rs.get("tier")) == *tier)
+                        .count() as u64,
+                ),
+                TableDistribution::Sharded { .. } => None,
+            };
+            let local = entry(sizes, &name).and_then(Value::as_array);
+            let measure = |index: usize| {
+                let value = local?.get(index)?.as_u64()?;
+                Some(value * factor?)
+            };
+            TableInfo {
+                engine: text(table.get("engine")),
+                rows: measure(1),
+                size: measure(0),
+                name,
+                distribution,
+            }
+        })
+        .collect();
+    result.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.name.cmp(&b.name)));
+    result
+}
+
+/// Parameters from `_pico_db_config`, or `_pico_property` on versions
+/// before it, by name
+fn fetch_parameters(conn: &mut Connection) -> Result<Vec<ConfigParameter>> {
+    let rows = match select(conn, "SELECT * FROM _pico_db_config") {
+        Ok(rows) => rows,
+        Err(_) => select(conn, "SELECT * FROM _pico_property")?,
+    };
+    Ok(build_parameters(&rows))
+}
+
+fn build_parameters(rows: &[Record]) -> Vec<ConfigParameter> {
+    let mut result: Vec<ConfigParameter> = rows
+        .iter()
+        .map(|row| ConfigParameter {
+            name: text(row.get("key")),
+            scope: text(row.get("scope")),
+            value: text(row.get("value")),
+        })
+        .collect();
+    result.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| a.scope.cmp(&b.scope)));
+    result
+}
+
+/// `Global`, or a `Sharded*` variant holding the tier as a `tier` field or
+/// as the last element
+fn distribution(value: Option<&Value>) -> TableDistribution {
+    let (variant, fields) = match value {
+        Some(Value::Map(entries)) => match entries.first() {
+            Some((variant, fields)) => (text(Some(variant)), Some(fields)),
+            None => (String::new(), None),
+        },
+        other => (text(other), None),
+    };
+    if !variant.starts_with("Sharded") {
+        return TableDistribution::Global;
+    }
+    let tier = match fields {
+        Some(map @ Value::Map(_)) => text(entry(map, "tier")),
+        Some(Value::Array(parts)) => text(parts.last()),
+        _ => String::new(),
+    };
+    TableDistribution::Sharded { tier }
+}
+
+/// Group instance rows into replicasets and tiers, sorted by name
+fn build_tiers(
+    tiers: &[Record],
+    replicasets: &[Record],
+    instances: &[Record],
+    addresses: &[Record],
+    raft_leader: Option<u64>,
+) -> Vec<TierInfo> {
+    // raft_id -> (binary address, pg address)
+    let mut peers: HashMap<u64, (String, String)> = HashMap::new();
+    for peer in addresses {
+        let Some(raft_id) = peer.get("raft_id").and_then(Value::as_u64) else {
+            continue;
+        };
+        let slot = peers.entry(raft_id).or_default();
+        match text(peer.get("connection_type")).as_str() {
+            "pgproto" => slot.1 = text(peer.get("address")),
+            _ => slot.0 = text(peer.get("address")),
+        }
+    }
+
+    let mut by_replicaset: BTreeMap<String, Vec<InstanceInfo>> = BTreeMap::new();
+    let masters: HashMap<String, String> = replicasets
+        .iter()
+        .map(|rs| (text(rs.get("name")), text(rs.get("current_master_name"))))
+        .collect();
+    for inst in instances {
+        let name = text(inst.get("name"));
+        let replicaset = text(inst.get("replicaset_name"));
+        let raft_id = inst.get("raft_id").and_then(Value::as_u64);
+        let (binary_address, pg_address) = raft_id
+            .and_then(|id| peers.get(&id).cloned())
+            .unwrap_or_default();
+        let failure_domain = inst
+            .get("failure_domain")
+            .and_then(Value::as_map)
+            .map(|domain| {
+                domain
+                    .iter()
+                    .map(|(k, v)| (text(Some(k)), text(Some(v))))
+                    .collect()
+            })
+            .unwrap_or_default();
+        by_replicaset
+            .entry(replicaset.clone())
+            .or_default()
+            .push(InstanceInfo {
+                http_address: String::new(),
+                version: text(inst.get("picodata_version")),
+                failure_domain,
+                is_leader: masters.get(&replicaset) == Some(&name),
+                is_voter: false,
+                is_raft_leader: raft_id.is_some() && raft_id == raft_leader,
+                current_state: state(inst.get("current_state")),
+                target_state: state(inst.get("target_state")),
+                name,
+                binary_address,
+                pg_address,
+                uptime_seconds: None,
+                pid: None,
+                raft_id,
+                replication_lag: None,
+            });
+    }
+
+    let mut result: Vec<TierInfo> = tiers
+        .iter()
+        .map(|tier| {
+            let name = text(tier.get("name"));
+            let mut tier_replicasets: Vec<ReplicasetInfo> = replicasets
+                .iter()
+                .filter(|rs| text(rs.get("tier")) == name)
+                .map(|rs| {
+                    let rs_name = text(rs.get("name"));
+                    let mut members = by_replicaset.remove(&rs_name).unwrap_or_default();
+                    members.sort_by(|a, b| a.name.cmp(&b.name));
+                    let leader_state = members
+                        .iter()
+                        .find(|inst| inst.is_leader)
+                        .map_or(StateVariant::Offline, |inst| inst.current_state);
+                    ReplicasetInfo {
+                        version: String::new(),
+                        state: leader_state,
+                        replicaset_state: match text(rs.get("state")).as_str() {
+                            "not-ready" => ReplicasetState::NotReady,
+                            _ => ReplicasetState::Ready,
+                        },
+                        instance_count: members.len(),
+                        bucket_count: None,
+                        uuid: text(rs.get("uuid")),
+                        instances: members,
+                        capacity_usage: 0.0,
+                        memory: MemoryInfo { usable: 0, used: 0 },
+                        name: rs_name,
+                    }
+                })
+                .collect();
+            tier_replicasets.sort_by(|a, b| a.name.cmp(&b.name));
+            TierInfo {
+                replicaset_count: tier_replicasets.len(),
+                rf: tier
+                    .get("replication_factor")
+                    .and_then(Value::as_u64)
+                    .unwrap_or(0) as u8,
+                bucket_count: tier
+                    .get("bucket_count")
+                    .and_then(Value::as_u64)
+                    .unwrap_or(0),
+                instance_count: tier_replicasets.iter().map(|rs| rs.instances.len()).sum(),
+                can_vote: tier
+                    .get("can_vote")
+                    .and_then(Value::as_bool)
+                    .unwrap_or(true),
+                name,
+                services: Vec::new(),
+                memory: MemoryInfo { usable: 0, used: 0 },
+                capacity_usage: 0.0,
+                replicasets: tier_replicasets,
+            }
+        })
+        .collect();
+    result.sort_by(|a, b| a.name.cmp(&b.name));
+    result
+}
+
+/// The binary protocol worker thread, reached over channels
+pub struct IprotoSource {
+    requests: Sender<ApiRequest>,
+    responses: Receiver<ApiMessage>,
+}
+
+impl IprotoSource {
+    /// Start the worker for the instance at `address` (host:port). Its tiers
+    /// are prepared for the UI on the way, as for `HttpSource`.
+    pub fn spawn(address: &str, method: AuthMethod, debug: bool) -> Self {
+        let (request_tx, request_rx) = channel();
+        let (raw_response_tx, raw_response_rx) = channel();
+        let (response_tx, response_rx) = channel();
+        let worker = Worker {
+            address: address.to_string(),
+            method,
+            debug,
+            credentials: None,
+            conn: None,
+            topology: None,
+        };
+        api::spawn_worker(move || worker.run(request_rx, raw_response_tx));
+        prepare::spawn_preparer(raw_response_rx, response_tx);
+        Self {
+            requests: request_tx,
+            responses: response_rx,
+        }
+    }
+}
+
+impl DataSource for IprotoSource {
+    fn send(&self, request: ApiRequest) {
+        let _ = self.requests.send(request);
+    }
+
+    fn try_recv(&self) -> Result<ApiMessage, TryRecvError> {
+        self.responses.try_recv()
+    }
+}
+
+struct Worker {
+    address: String,
+    method: AuthMethod,
+    debug: bool,
+    credentials: Option<(String, String)>,
+    conn: Option<Connection>,
+    /// Read for a `GetClusterInfo`, kept for the `GetTiers` a refresh
+    /// sends right after it so the system tables are read once
+    topology: Option<Topology>,
+}
+
+impl Worker {
+    fn run(self, requests: Receiver<ApiRequest>, responses: Sender<ApiMessage>) {
+        let debug = self.debug;
+        // Started over logged in as before, reconnecting on the next request
+        let restart = |old: &Worker| Worker {
+            address: old.address.clone(),
+            method: old.method,
+            debug: old.debug,
+            credentials: old.credentials.clone(),
+            conn: None,
+            topology: None,
+        };
+        let answers = responses.clone();
+        api::supervise(
+            requests,
+            answers,
+            debug,
+            self,
+            restart,
+            |worker, request| worker.handle(request, &responses),
+        );
+    }
+
+    fn handle(&mut self, request: ApiRequest, responses: &Sender<ApiMessage>) -> ControlFlow<()> {
+        let started = Instant::now();
+        // Only good for the request right after the one that read it
+        let topology = self.topology.take();
+        let (response, reachable) = match request {
+            ApiRequest::Shutdown => return ControlFlow::Break(()),
+            // Sessions, ETags and HTTP addresses are HTTP notions
+            ApiRequest::SetToken { .. }
+            | ApiRequest::SetFallbacks { .. }
+            | ApiRequest::CancelFanOut => return ControlFlow::Continue(()),
+
+            // A password is always needed: guest can't read the system tables
+            ApiRequest::GetConfig => match Connection::open(&self.address) {
+                Ok(_) => (
+                    ApiResponse::Config(Ok(UiConfig {
+                        is_auth_enabled: true,
+                    })),
+                    true,
+                ),
+                Err(e) => (
+                    ApiResponse::Config(Err(format!("Failed to get config: {}", e))),
+                    false,
+                ),
+            },
+
+            ApiRequest::CheckServer => match Connection::open(&self.address) {
+                Ok(_) => (
+                    ApiResponse::ServerCheck(Ok(ServerIdentity::default())),
+                    true,
+                ),
+                Err(e) => (ApiResponse::ServerCheck(Err(e.to_string())), false),
+            },
+
+            // Saving sessions is left to the HTTP API: this would mean the password
+            ApiRequest::Login {
+                username, password, ..
+            } => {
+                log_debug(
+                    self.debug,
+                    &format!("IPROTO AUTH {} as {}", self.address, username),
+                );
+                self.conn = None;
+                let result = self.connect(&username, &password);
+                let reachable = reachable(&result);
+                let result = match result {
+                    Ok(conn) => {
+                        self.conn = Some(conn);
+                        self.credentials = Some((username, password));
+                        Ok(TokenResponse {
+                            auth: String::new(),
+                            refresh: String::new(),
+                        })
+                    }
+                    Err(e) => match e.downcast_ref::<ServerError>() {
+                        Some(err) if CREDENTIAL_ERRORS.contains(&err.code) => {
+                            Err(INVALID_CREDENTIALS.to_string())
+                        }
+                        _ => Err(format!("Login failed: {}", e)),
+                    },
+                };
+                (ApiResponse::Login(result), reachable)
+            }
+
+            ApiRequest::GetClusterInfo => {
+                let result = self.fetch();
+                let reachable = reachable(&result);
+                let result = result
+                    .map(|topology| {
+                        let info = topology.cluster_info();
+                        self.topology = Some(topology);
+                        info
+                    })
+                    .map_err(|e| format!("Failed to get cluster info: {}", e));
+                (ApiResponse::ClusterInfo(result), reachable)
+            }
+
+            ApiRequest::GetTiers => {
+                let result = topology.map_or_else(|| self.fetch(), Ok);
+                let reachable = reachable(&result);
+                let result = result
+                    .map(|topology| topology.tiers)
+                    .map_err(|e| format!("Failed to get tiers: {}", e));
+                (ApiResponse::Tiers(result), reachable)
+            }
+
+            ApiRequest::GetTables => {
+                let result = self.with_connection("tables", fetch_tables);
+                let reachable = reachable(&result);
+                let result = result.map_err(|e| format!("Failed to get tables: {}", e));
+                (ApiResponse::Tables(result), reachable)
+            }
+
+            ApiRequest::GetParameters => {
+                let result = self.with_connection("parameters", fetch_parameters);
+                let reachable = reachable(&result);
+                let result = result.map_err(|e| format!("Failed to get parameters: {}", e));
+                (ApiResponse::Parameters(result), reachable)
+            }
+
+            ApiRequest::GetHealthStatus { .. } => (
+                ApiResponse::HealthStatus(Err(HEALTH_NEEDS_HTTP.to_string())),
+                true,
+            ),
+
+            ApiRequest::ProbeHealth { id, addresses } => (
+                ApiResponse::HealthProbes(HealthProbes {
+                    id,
+                    results: vec![Err(HEALTH_NEEDS_HTTP.to_string()); addresses.len()],
+                    cancelled: false,
+                }),
+                true,
+            ),
+
+            ApiRequest::StartStream { .. } => (
+                ApiResponse::Stream(StreamEvent::Unavailable(
+                    "not available over iproto".to_string(),
+                )),
+                true,
+            ),
+        };
+        let bytes = self.conn.as_mut().map_or(0, Connection::take_bytes);
+        let meta = ResponseMeta {
+            latency: started.elapsed(),
+            reachable,
+            bytes,
+        };
+        match responses.send(ApiMessage { response, meta }) {
+            Ok(()) => ControlFlow::Continue(()),
+            Err(_) => ControlFlow::Break(()),
+        }
+    }
+
+    fn connect(&self, username: &str, password: &str) -> Result<Connection> {
+        let mut conn = Connection::open(&self.address)?;
+        conn.authenticate(self.method, username, password)?;
+        Ok(conn)
+    }
+
+    /// Read the topology
+    fn fetch(&mut self) -> Result<Topology> {
+        self.with_connection("topology", Topology::fetch)
+    }
+
+    /// Run `query` on the session, reconnecting once if the connection broke
+    fn with_connection<T>(
+        &mut self,
+        what: &str,
+        query: impl Fn(&mut Connection) -> Result<T>,
+    ) -> Result<T> {
+        log_debug(
+            self.debug,
+            &format!("IPROTO {} from {}", what, self.address),
+        );
+        if let Some(conn) = &mut self.conn {
+            match query(conn) {
+                Ok(result) => return Ok(result),
+                Err(e) if e.downcast_ref::<ServerError>().is_some() => return Err(e),
+                Err(e) => {
+                    log_debug(self.debug, &format!("  ERROR: {}, reconnecting", e));
+                    self.conn = None;
+                }
+            }
+        }
+        let Some((username, password)) = self.credentials.clone() else {
+            bail!("not logged in");
+        };
+        let mut conn = self.connect(&username, &password)?;
+        let result = query(&mut conn);
+        self.conn = Some(conn);
+        result
+    }
+}
+
+/// Whether the server answered, even if with an error
+fn reachable<T>(result: &Result<T>) -> bool {
+    match result {
+        Ok(_) => true,
+        Err(e) => e.downcast_ref::<ServerError>().is_some(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(fields: &[(&str, Value)]) -> Record {
+        fields
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_oversized_response_is_refused() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        std::thread::spawn(move || {
+            let (mut socket, _) = listener.accept().unwrap();
+            let mut greeting = [b' '; 128];
+            greeting[..9].copy_from_slice(b"Tarantool");
+            greeting[64..68].copy_from_slice(b"AAAA");
+            socket.write_all(&greeting).unwrap();
+            let mut request = [0u8; 64];
+            let _ = socket.read(&mut request);
+            // A length prefix of a terabyte
+            let mut len = Vec::new();
+            rmp::encode::write_u64(&mut len, 1 << 40).unwrap();
+            socket.write_all(&len).unwrap();
+            std::thread::sleep(Duration::from_secs(1));
+        });
+
+        let mut conn = Connection::open(&address).unwrap();
+        let err = conn.eval("return 1").unwrap_err().to_string();
+        assert!(err.contains("over the 16 MiB limit"), "{}", err);
+    }
+
+    #[test]
+    fn test_scrambles() {
+        let salt: Vec<u8> = (0..32).collect();
+        // Reference values computed with Python's hashlib
+        assert_eq!(
+            md5_scramble("admin", "secret", &salt),
+            "md52b151f107a311bcd7804096b93c45f35"
+        );
+        assert_eq!(
+            hex(&chap_sha1_scramble("secret", &salt)),
+            "21b3ff405f32cbe4aafff291396046ea29fa3a4d"
+        );
+        assert_eq!(
+            "chap-sha1".parse::<AuthMethod>().unwrap(),
+            AuthMethod::ChapSha1
+        );
+        assert!("plain".parse::<AuthMethod>().is_err());
+    }
+
+    #[test]
+    fn test_build_tiers_from_system_tables() {
+        let online = Value::Array(vec!["Online".into(), 1.into()]);
+        let offline = Value::Array(vec!["Offline".into(), 0.into()]);
+        let tiers = [record(&[
+            ("name", "default".into()),
+            ("replication_factor", 2.into()),
+            ("can_vote", true.into()),
+        ])];
+        let replicasets = [record(&[
+            ("name", "r1".into()),
+            ("tier", "default".into()),
+            ("current_master_name", "i1".into()),
+            ("state", "ready".into()),
+        ])];
+        let instance = |name: &str, raft_id: u64, state: &Value| {
+            record(&[
+                ("name", name.into()),
+                ("replicaset_name", "r1".into()),
+                ("raft_id", raft_id.into()),
+                ("current_state", state.clone()),
+                ("target_state", online.clone()),
+                (
+                    "failure_domain",
+                    Value::Map(vec![("dc".into(), "dc1".into())]),
+                ),
+                ("picodata_version", "25.3.0".into()),
+            ])
+        };
+        let instances = [instance("i2", 2, &offline), instance("i1", 1, &online)];
+        let addresses = [
+            record(&[
+                ("raft_id", 1.into()),
+                ("address", "10.0.0.1:3301".into()),
+                ("connection_type", "iproto".into()),
+            ]),
+            record(&[
+                ("raft_id", 1.into()),
+                ("address", "10.0.0.1:5432".into()),
+                ("connection_type", "pgproto".into()),
+            ]),
+        ];
+
+        let tiers = build_tiers(&tiers, &replicasets, &instances, &addresses, Some(2));
+        assert_eq!(tiers.len(), 1);
+        assert_eq!(tiers[0].rf, 2);
+        assert_eq!(tiers[0].instance_count, 2);
+        let rs = &tiers[0].replicasets[0];
+        assert_eq!(rs.state, StateVariant::Online, "state of the master");
+        let names: Vec<&str> = rs.instances.iter().map(|i| i.name.as_str()).collect();
+        assert_eq!(names, ["i1", "i2"]);
+        let i1 = &rs.instances[0];
+        assert!(i1.is_leader);
+        assert_eq!(i1.binary_address, "10.0.0.1:3301");
+        assert_eq!(i1.pg_address, "10.0.0.1:5432");
+        assert_eq!(i1.failure_domain["dc"], "dc1");
+        let i2 = &rs.instances[1];
+        assert_eq!(i2.current_state, StateVariant::Offline);
+        assert!(i2.is_raft_leader);
+    }
+
+    #[test]
+    fn test_build_tables_estimates_cluster_sizes() {
+        let sharded = |tier: &str| {
+            Value::Map(vec![(
+                "ShardedImplicitly".into(),
+                Value::Array(vec![
+                    Value::Array(vec!["id".into()]),
+                    "murmur3".into(),
+                    tier.into(),
+                ]),
+            )])
+        };
+        let table = |name: &str, distribution: Value| {
+            record(&[
+                ("name", name.into()),
+                ("distribution", distribution),
+                ("engine", "memtx".into()),
+            ])
+        };
+        let tables = [
+            table("orders", sharded("default")),
+            table("archive", sharded("cold")),
+            table("regions", Value::Map(vec![("Global".into(), Value::Nil)])),
+        ];
+        let replicaset = |tier: &str| record(&[("tier", tier.into())]);
+        let replicasets = [
+            replicaset("default"),
+            replicaset("default"),
+            replicaset("default"),
+            replicaset("cold"),
+        ];
+        let sizes = Value::Map(vec![
+            ("orders".into(), Value::Array(vec![1000.into(), 10.into()])),
+            ("regions".into(), Value::Array(vec![500.into(), 5.into()])),
+        ]);
+
+        let tables = build_tables(&tables, &replicasets, "default", &sizes);
+        let names: Vec<&str> = tables.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, ["orders", "regions", "archive"], "largest first");
+        assert_eq!(tables[0].size, Some(3000), "local share times replicasets");
+        assert_eq!(tables[0].rows, Some(30));
+        assert_eq!(
+            tables[1].size,
+            Some(500),
+            "global tables are not multiplied"
+        );
+        assert_eq!(tables[1].distribution, TableDistribution::Global);
+        assert_eq!(tables[2].size, None, "no share on this instance's tier");
+        assert_eq!(
+            tables[2].distribution,
+            TableDistribution::Sharded {
+                tier: "cold".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_build_parameters_sorted_by_name_and_tier() {
+        let row = |key: &str, scope: &str, value: Value| {
+            record(&[
+                ("key", key.into()),
+                ("scope", scope.into()),
+                ("value", value),
+            ])
+        };
+        let params = build_parameters(&[
+            row("sql_vdbe_opcode_max", "storage", 90000.into()),
+            row("auth_password_enforce_digits", "", true.into()),
+            row("sql_vdbe_opcode_max", "", 45000.into()),
+        ]);
+        let keys: Vec<(&str, &str)> = params
+            .iter()
+            .map(|p| (p.name.as_str(), p.scope.as_str()))
+            .collect();
+        assert_eq!(
+            keys,
+            [
+                ("auth_password_enforce_digits", ""),
+                ("sql_vdbe_opcode_max", ""),
+                ("sql_vdbe_opcode_max", "storage"),
+            ]
+        );
+        assert_eq!(params[0].value, "true");
+        assert_eq!(params[2].value, "90000");
+    }
+}
@@ -0,0 +1,87 @@
+use crossterm::event::{KeyCode, KeyModifiers};
+use std::collections::HashMap;
+
+/// A key press as the normal-mode handlers see it
+pub type Key = (KeyCode, KeyModifiers);
+
+/// What the next letter does after `Q` or `@`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegisterPrompt {
+    Record,
+    Replay,
+}
+
+/// Structural action `.` repeats
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RepeatAction {
+    /// A single key such as expand or change sort
+    Key(Key),
+    /// Set the Instances view filter, whatever it was before
+    SetFilter(String),
+}
+
+/// `.` to repeat the last structural action and `Q<letter>` ... `Q` /
+/// `@<letter>` to record and replay key sequences. Keys are stored as
+/// pressed and replayed through the same handlers.
+#[derive(Debug, Default)]
+pub struct KeyMacros {
+    /// Last structural action, repeated by `.`
+    pub last_action: Option<RepeatAction>,
+    /// Register being recorded into and the keys so far
+    recording: Option<(char, Vec<Key>)>,
+    registers: HashMap<char, Vec<Key>>,
+    pub register_prompt: Option<RegisterPrompt>,
+}
+
+impl KeyMacros {
+    /// Register being recorded into
+    pub fn recording(&self) -> Option<char> {
+        self.recording.as_ref().map(|(register, _)| *register)
+    }
+
+    pub fn start_recording(&mut self, register: char) {
+        self.recording = Some((register, Vec::new()));
+    }
+
+    /// Stop recording and keep the keys; returns the register they went to
+    pub fn stop_recording(&mut self) -> Option<char> {
+        let (register, keys) = self.recording.take()?;
+        self.registers.insert(register, keys);
+        Some(register)
+    }
+
+    /// Keys recorded into `register`
+    pub fn register(&self, register: char) -> Option<Vec<Key>> {
+        self.registers.get(&register).cloned()
+    }
+
+    /// Note a key about to be handled, for the register being recorded
+    pub fn record(&mut self, key: Key) {
+        if let Some((_, keys)) = &mut self.recording {
+            keys.push(key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(c: char) -> Key {
+        (KeyCode::Char(c), KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn test_registers() {
+        let mut macros = KeyMacros::default();
+        macros.record(key('j'));
+        assert!(macros.register('a').is_none(), "not recording");
+        macros.start_recording('a');
+        macros.record(key('j'));
+        macros.record(key('l'));
+        assert_eq!(macros.recording(), Some('a'));
+        assert_eq!(macros.stop_recording(), Some('a'));
+        assert_eq!(macros.register('a'), Some(vec![key('j'), key('l')]));
+        assert_eq!(macros.stop_recording(), None);
+    }
+}
@@ -0,0 +1,118 @@
+use crate::models::{StateVariant, TierInfo};
+use std::collections::BTreeMap;
+
+/// Replicaset leaders held by one host or failure domain
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LeaderCount {
+    pub key: String,
+    pub leaders: usize,
+    /// Non-expelled instances in the group, for comparison
+    pub instances: usize,
+}
+
+/// How replicaset leadership is spread across the cluster
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LeaderDistribution {
+    /// Grouped by the host part of the binary address
+    pub by_host: Vec<LeaderCount>,
+    /// Grouped by failure domain `key:value` pairs
+    pub by_domain: Vec<LeaderCount>,
+}
+
+/// Count replicaset (vshard) leaders per host and per failure domain,
+/// busiest groups first
+pub fn leader_distribution(tiers: &[TierInfo]) -> LeaderDistribution {
+    let mut by_host: BTreeMap<String, (usize, usize)> = BTreeMap::new();
+    let mut by_domain: BTreeMap<String, (usize, usize)> = BTreeMap::new();
+
+    let instances = tiers
+        .iter()
+        .flat_map(|t| t.replicasets.iter())
+        .flat_map(|rs| rs.instances.iter())
+        .filter(|inst| inst.current_state != StateVariant::Expelled);
+    for inst in instances {
+        let leader = usize::from(inst.is_leader);
+
+        let entry = by_host
+            .entry(host(&inst.binary_address).to_string())
+            .or_default();
+        entry.0 += leader;
+        entry.1 += 1;
+
+        for (key, value) in &inst.failure_domain {
+            let entry = by_domain.entry(format!("{}:{}", key, value)).or_default();
+            entry.0 += leader;
+            entry.1 += 1;
+        }
+    }
+
+    LeaderDistribution {
+        by_host: ranked(by_host),
+        by_domain: ranked(by_domain),
+    }
+}
+
+fn ranked(groups: BTreeMap<String, (usize, usize)>) -> Vec<LeaderCount> {
+    let mut counts: Vec<_> = groups
+        .into_iter()
+        .map(|(key, (leaders, instances))| LeaderCount {
+            key,
+            leaders,
+            instances,
+        })
+        .collect();
+    // Stable sort keeps groups with equal counts in name order
+    counts.sort_by_key(|c| std::cmp::Reverse(c.leaders));
+    counts
+}
+
+/// Host part of a `host:port` address (IPv6 addresses keep their brackets)
+pub(crate) fn host(address: &str) -> &str {
+    match address.rsplit_once(':') {
+        Some((host, port)) if port.chars().all(|c| c.is_ascii_digit()) => host,
+        _ => address,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock;
+
+    #[test]
+    fn test_host_strips_port() {
+        assert_eq!(host("10.0.0.1:3301"), "10.0.0.1");
+        assert_eq!(host("[::1]:3301"), "[::1]");
+        assert_eq!(host("node-1"), "node-1");
+    }
+
+    #[test]
+    fn test_leader_distribution() {
+        let mut tiers = mock::tiers();
+        // Pile both default-tier leaders onto one host
+        tiers[0]["replicasets"][1]["instances"][0]["binaryAddress"] = "10.0.0.1:3302".into();
+        let tiers: Vec<TierInfo> = serde_json::from_value(tiers).unwrap();
+
+        let distribution = leader_distribution(&tiers);
+        let top = &distribution.by_host[0];
+        assert_eq!(
+            (top.key.as_str(), top.leaders, top.instances),
+            ("10.0.0.1", 2, 2)
+        );
+
+        let domains: Vec<(&str, usize)> = distribution
+            .by_domain
+            .iter()
+            .map(|c| (c.key.as_str(), c.leaders))
+            .collect();
+        assert_eq!(
+            domains,
+            vec![
+                ("datacenter:dc1", 2),
+                ("rack:r1", 2),
+                ("datacenter:dc2", 1),
+                ("rack:r2", 0),
+            ]
+        );
+    }
+}
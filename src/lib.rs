@@ -1,5 +1,54 @@
+pub mod actions;
+pub mod alerts;
+pub mod announce;
 pub mod api;
 pub mod app;
+pub mod audit;
+pub mod bookmarks;
+pub mod bugreport;
+pub mod chaos;
+pub mod client;
+pub mod compat;
+pub mod completions;
+pub mod config;
+pub mod confirm;
+pub mod diagnostics;
+pub mod doctor;
+pub mod errorlog;
+pub mod events;
+pub mod exporter;
+pub mod filter;
+pub mod fuzzy;
+pub mod health;
+pub mod heatmap;
+pub mod i18n;
+#[cfg(feature = "iproto")]
+pub mod iproto;
+pub mod keymacros;
+pub mod leaders;
+pub mod mock;
 pub mod models;
+pub mod parameters;
+pub mod paths;
+pub mod picker;
+pub mod pins;
+pub mod prepare;
+pub mod private_files;
+pub mod ratelimit;
+pub mod rolling;
+pub mod schema;
+pub mod selection;
+pub mod shared;
+pub mod snapshot;
+pub mod source;
+pub mod stats;
+pub mod stream;
+pub mod testing;
+pub mod timings;
+pub mod toasts;
 pub mod tokens;
+pub mod topology;
+pub mod tunnel;
 pub mod ui;
+pub mod usernames;
+pub mod watch;
@@ -1,40 +1,330 @@
 use anyhow::{anyhow, Result};
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyModifiers},
+    event::{
+        self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+        Event, KeyCode, KeyEventKind, KeyEventState, KeyModifiers, KeyboardEnhancementFlags,
+        PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+    },
     execute,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+    terminal::{
+        disable_raw_mode, enable_raw_mode, supports_keyboard_enhancement, EnterAlternateScreen,
+        LeaveAlternateScreen,
+    },
 };
+use picotui::actions;
+use picotui::alerts::AlertEngine;
+use picotui::announce;
 use picotui::api;
-use picotui::app::{App, InputMode, LoginFocus, ViewMode};
+use picotui::app::{App, Bell, BookmarkPrompt, InputMode, LoginFocus, SortField, ViewMode};
+use picotui::audit;
+use picotui::bookmarks;
+#[cfg(debug_assertions)]
+use picotui::chaos;
+use picotui::completions::{self, Shell};
+use picotui::config;
+use picotui::doctor;
+use picotui::events;
+use picotui::exporter;
+use picotui::i18n::{self, Lang};
+#[cfg(feature = "iproto")]
+use picotui::iproto::{AuthMethod, IprotoSource};
+use picotui::keymacros::{Key, RegisterPrompt, RepeatAction};
+use picotui::mock::{self, MockOptions};
+use picotui::paths;
+use picotui::picker::ClusterPicker;
+use picotui::pins;
+use picotui::ratelimit;
+use picotui::rolling::Outcome;
+use picotui::schema;
+use picotui::selection;
+use picotui::snapshot;
+use picotui::source::{DataSource, HttpSource, SpawnSource};
+use picotui::stream;
+use picotui::toasts::ToastKind;
+use picotui::tokens;
+use picotui::tunnel::{K8sService, Tunnel};
 use picotui::ui;
+use picotui::ui::format::{unix_now, ByteUnits, NumberFormat, TimeFormat};
+use picotui::ui::screenshot::{self, ScreenshotFormat};
+use picotui::ui::theme::{self, ColorDepth, ColorMode};
+use picotui::usernames;
+use picotui::watch::{self, WaitCondition};
 use ratatui::{backend::CrosstermBackend, Terminal};
 use std::io;
-use std::sync::mpsc::channel;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 struct Args {
     url: String,
+    /// Other URLs of the cluster after the first one in `--url`
+    fallback_urls: Vec<String>,
+    discover: bool,
+    /// No `--url` given: offer the saved clusters to choose from
+    pick_cluster: bool,
     refresh: u64,
+    /// `--max-rate`, over the config file's `max_request_rate`
+    max_rate: Option<f64>,
     debug: bool,
+    /// Reject API responses with fields picotui doesn't know
+    strict: bool,
+    wait_until: Option<WaitCondition>,
+    wait_timeout: u64,
+    stream_path: Option<String>,
+    number_format: NumberFormat,
+    time_format: TimeFormat,
+    lang: Lang,
+    fuzzy: bool,
+    expand_problems: bool,
+    read_only: bool,
+    ops: bool,
+    ops_dry_run: bool,
+    accessible: bool,
+    slow_link: bool,
+    bell: Option<Bell>,
+    color: ColorDepth,
+    view: Option<ViewMode>,
+    filter: Option<String>,
+    announce_path: Option<PathBuf>,
+    /// SSH destination to forward the cluster URL through
+    ssh: Option<String>,
+    /// Kubernetes service to port-forward to
+    k8s_service: Option<K8sService>,
+    /// Binary protocol address to read the cluster from instead of the HTTP API
+    #[cfg(feature = "iproto")]
+    iproto: Option<String>,
+    #[cfg(feature = "iproto")]
+    iproto_auth: AuthMethod,
+    config_path: Option<PathBuf>,
+    data_dir: Option<PathBuf>,
+    diff: Option<(String, String)>,
+    events_stdout: bool,
+    /// Listen address when running as `picotui exporter`
+    exporter_listen: Option<String>,
+    tokens_command: Option<TokensCommand>,
+    /// Shell to print completions for instead of starting
+    completions: Option<Shell>,
+    /// Check the connection and the terminal instead of starting
+    doctor: bool,
+    /// Listen address and behavior when running as `picotui mock-server`
+    mock_server: Option<(String, MockOptions)>,
+    /// Latency and share of failures to inject into API requests
+    #[cfg(debug_assertions)]
+    inject: (Duration, f64),
+    terminal_mode: TerminalMode,
 }
 
-fn parse_args() -> Result<Args> {
-    let mut args = pico_args::Arguments::from_env();
+/// `picotui tokens ...`: manage saved sessions
+enum TokensCommand {
+    List,
+    /// Forget the session for one URL, or all of them
+    Clear(Option<String>),
+}
 
-    if args.contains(["-h", "--help"]) {
-        println!(
-            "picotui - Terminal UI for Picodata cluster management
+/// How the TUI takes over the terminal
+#[derive(Clone, Copy)]
+struct TerminalMode {
+    /// Draw on a separate screen instead of inline in the scrollback
+    alt_screen: bool,
+    mouse_capture: bool,
+    /// Whether key releases are reported: always on Windows, elsewhere
+    /// when the terminal supports the kitty keyboard protocol
+    release_events: bool,
+}
+
+impl TerminalMode {
+    fn enter(self, out: &mut impl io::Write) -> io::Result<()> {
+        enable_raw_mode()?;
+        if self.alt_screen {
+            execute!(out, EnterAlternateScreen)?;
+        }
+        if self.mouse_capture {
+            execute!(out, EnableMouseCapture)?;
+        }
+        execute!(out, EnableBracketedPaste)?;
+        if self.release_events && !cfg!(windows) {
+            execute!(
+                out,
+                PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::REPORT_EVENT_TYPES)
+            )?;
+        }
+        Ok(())
+    }
+
+    fn leave(self, out: &mut impl io::Write) -> io::Result<()> {
+        disable_raw_mode()?;
+        if self.release_events && !cfg!(windows) {
+            execute!(out, PopKeyboardEnhancementFlags)?;
+        }
+        execute!(out, DisableBracketedPaste)?;
+        if self.mouse_capture {
+            execute!(out, DisableMouseCapture)?;
+        }
+        if self.alt_screen {
+            execute!(out, LeaveAlternateScreen)?;
+        }
+        Ok(())
+    }
+
+    /// Hand the terminal back to the shell and stop until resumed with
+    /// SIGCONT, then take it over again and repaint from scratch
+    #[cfg(unix)]
+    fn suspend(self, terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> Result<()> {
+        self.leave(terminal.backend_mut())?;
+        terminal.show_cursor()?;
+        signal_hook::low_level::emulate_default_handler(signal_hook::consts::SIGTSTP)?;
+        self.enter(terminal.backend_mut())?;
+        terminal.clear()?;
+        Ok(())
+    }
+
+    #[cfg(not(unix))]
+    fn suspend(self, _terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Set when the process receives SIGTSTP from outside (`kill -TSTP`); the
+/// main loop suspends cleanly instead of stopping with the terminal in raw mode.
+/// Ctrl+Z itself arrives as a key press because raw mode disables signals.
+#[cfg(unix)]
+fn watch_suspend_signal() -> Result<Arc<AtomicBool>> {
+    let flag = Arc::new(AtomicBool::new(false));
+    signal_hook::flag::register(signal_hook::consts::SIGTSTP, Arc::clone(&flag))?;
+    Ok(flag)
+}
+
+#[cfg(not(unix))]
+fn watch_suspend_signal() -> Result<Arc<AtomicBool>> {
+    Ok(Arc::new(AtomicBool::new(false)))
+}
+
+const HELP: &str = "picotui - Terminal UI for Picodata cluster management
 
 USAGE:
     picotui [OPTIONS]
+    picotui exporter [--listen <ADDR>] [OPTIONS]
+    picotui tokens list|clear [URL]
+    picotui completions bash|zsh|fish
+    picotui doctor [OPTIONS]
+    picotui mock-server [--listen <ADDR>] [--topology <TxRxI>]
+
+COMMANDS:
+    exporter              Run headless and serve cluster metrics in Prometheus
+                          format; polls every --refresh seconds
+        --listen <ADDR>   Address to serve /metrics on [default: 0.0.0.0:9123]
+    tokens list           List saved sessions
+    tokens clear [URL]    Forget the saved session for URL, or all of them
+    completions bash|zsh|fish
+                          Print shell completions, with the saved filters of
+                          the config file for --filter
+    doctor                Check the connection and the terminal, with fixes
+                          for what fails: address, TLS, saved session,
+                          server version, colors and Unicode
+    mock-server           Serve a stand-in cluster for UI work and demos: the
+                          test fixture cluster unless --topology is given
+        --listen <ADDR>   Address to serve on [default: 127.0.0.1:8080]
+        --topology <TxRxI>
+                          Generate TIERSxREPLICASETSxINSTANCES, e.g. 2x10x3
+        --auth <USER:PASSWORD>
+                          Require a login with these credentials
+        --offline <N>     Instances to start Offline [default: 0]
+        --churn <P>       Chance of each instance going Offline or back
+                          Online on every tiers request, 0 to 1 [default: 0]
+        --error-rate <P>  Share of requests failed with HTTP 500 [default: 0]
+        --latency <MS>    Delay every answer by MS milliseconds [default: 0]
 
 OPTIONS:
-    -u, --url <URL>       Picodata HTTP API URL [default: http://localhost:8080]
+    -u, --url <URL>       Picodata HTTP API URL [default: http://localhost:8080];
+                          several comma-separated URLs of one cluster are
+                          tried in turn when one stops answering
+    --discover            Also fail over to every instance's HTTP address
+                          learned from the cluster, so one seed URL is enough
     -r, --refresh <SECS>  Auto-refresh interval in seconds, 0 to disable [default: 5]
-    -d, --debug           Enable debug mode (log API responses to picotui.log)
+    --max-rate <N>        Send at most N requests per second to the cluster,
+                          0 for no limit [default: 20]
+    -d, --debug           Enable debug mode (log API responses to picotui.log
+                          in the state directory)
+    --strict              Fail on API responses with unknown fields instead
+                          of ignoring them, to catch API changes
+    --stream <PATH>       Server-sent events endpoint for live updates
+                          [default: /api/v1/events]
+    --no-stream           Disable streaming, always poll
+    --wait-until <COND>   Run headless until COND holds, then exit
+                          (all-online, converged, all-ready)
+    --wait-timeout <SECS> Give up waiting after SECS seconds [default: 300]
+    --events-stdout       Run headless, print cluster changes to stdout as
+                          JSON Lines
+    --units <UNITS>       Memory units: binary (GiB) or si (GB) [default: binary]
+    --precision <N>       Decimal places for memory sizes [default: 1]
+    --thousands-sep       Group digits in large numbers (3,000)
+    --time <FORMAT>       Timestamps: relative, local or utc [default: relative]
+    --lang <LANG>         UI language: en or ru [default: en]
+    --view <VIEW>         View to start in: tiers, replicasets or instances
+                          [default: tiers, instances with --filter]
+    --filter <EXPR>       Start with this Instances view filter, e.g. offline
+    --fuzzy               Start with fuzzy filter matching (toggle with Tab)
+    --expand-problems     Expand tiers and replicasets with unhealthy instances
+                          on every refresh (toggle with E)
+    --read-only           Hide actions that change the cluster and mark the
+                          session read-only in the header
+    --ops                 Offer actions that change the cluster, such as the
+                          rolling restart assistant
+    --ops-dry-run         Like --ops, but show the commands an operation would
+                          run instead of running them
+    --accessible          Screen-reader friendly display: ASCII borders, words
+                          next to color-coded levels, cursor on the selection
+    --slow-link           For very slow terminals and links: redraw only when
+                          something changed
+    --color <WHEN>        Colors: auto, always, never or 16 (basic ANSI colors
+                          only); auto honors NO_COLOR and TERM [default: auto]
+    --bell <KIND>         Get attention when a refresh brings a new Offline
+                          instance or alert: bell, flash or both
+    --announce <PATH>     Write each selection change as a line of text to
+                          PATH (a file or named pipe) for screen readers
+    --ssh <DEST>          Reach the cluster URL through an SSH port forward
+                          via DEST (user@bastion)
+    --k8s-service <SVC>   Reach the cluster through kubectl port-forward to
+                          service SVC ([NAMESPACE/]NAME[:PORT], port 8080 by
+                          default), restarted when it drops
+    --k8s-context <CTX>   kubectl context for --k8s-service [default: current]
+    --iproto <ADDR>       Read the cluster over the binary protocol from the
+                          instance at ADDR (host:port), for clusters without
+                          the HTTP API
+    --iproto-auth <METHOD>
+                          Auth method of the --iproto user: md5, chap-sha1
+                          or ldap [default: md5]
+    --config <PATH>       Config file with saved filters and columns
+                          [default: ~/.config/picotui/config.json]
+    --diff <OLD>,<NEW>    Print the changes between two saved snapshots and exit
+    --data-dir <PATH>     Keep sessions, logs, snapshots and cache here instead
+                          of the XDG state/data/cache directories
+    --no-alt-screen       Draw inline instead of on the alternate screen; the
+                          last frame stays in the scrollback
+    --no-mouse            Don't capture the mouse, keep terminal text selection
     -h, --help            Print help
-    -V, --version         Print version"
-        );
+    -V, --version         Print version";
+
+/// Options of debug builds only
+#[cfg(debug_assertions)]
+const DEBUG_HELP: &str = "
+DEBUG BUILD OPTIONS:
+    --inject-latency <TIME>
+                          Delay every API request by TIME, e.g. 500ms or 2s
+    --inject-errors <RATE>
+                          Fail this share of API requests as if the server
+                          didn't answer, e.g. 10%";
+
+fn parse_args() -> Result<Args> {
+    parse_args_from(pico_args::Arguments::from_env())
+}
+
+fn parse_args_from(mut args: pico_args::Arguments) -> Result<Args> {
+    if args.contains(["-h", "--help"]) {
+        println!("{}", HELP);
+        #[cfg(debug_assertions)]
+        println!("{}", DEBUG_HELP);
         std::process::exit(0);
     }
 
@@ -43,13 +333,198 @@ OPTIONS:
         std::process::exit(0);
     }
 
-    let url: String = args
-        .opt_value_from_str(["-u", "--url"])?
-        .unwrap_or_else(|| "http://localhost:8080".to_string());
+    let mut exporter_listen = None;
+    let mut tokens_command = None;
+    let mut completions = None;
+    let mut doctor = false;
+    let mut mock_server = None;
+    match args.subcommand()?.as_deref() {
+        Some("exporter") => {
+            exporter_listen = Some(
+                args.opt_value_from_str("--listen")?
+                    .unwrap_or_else(|| exporter::DEFAULT_LISTEN.to_string()),
+            )
+        }
+        Some("tokens") => {
+            tokens_command = Some(match args.subcommand()?.as_deref() {
+                Some("list") => TokensCommand::List,
+                Some("clear") => TokensCommand::Clear(None),
+                _ => return Err(anyhow!("Usage: picotui tokens list|clear [URL]")),
+            })
+        }
+        Some("completions") => {
+            let shell = args.subcommand()?;
+            completions = Some(
+                shell
+                    .as_deref()
+                    .ok_or_else(|| anyhow!("Usage: picotui completions bash|zsh|fish"))?
+                    .parse()?,
+            )
+        }
+        Some("doctor") => doctor = true,
+        Some("mock-server") => mock_server = Some(parse_mock_options(&mut args)?),
+        Some(other) => return Err(anyhow!("Unknown command '{}'", other)),
+        None => {}
+    }
+
+    let url: Option<String> = args.opt_value_from_str(["-u", "--url"])?;
+    let url_given = url.is_some();
+    let url = url.unwrap_or_else(|| "http://localhost:8080".to_string());
+    // The first URL keys saved sessions; the rest are fallbacks
+    let mut urls = url.split(',').map(str::trim).filter(|url| !url.is_empty());
+    let url = urls
+        .next()
+        .ok_or_else(|| anyhow!("--url needs at least one URL"))?
+        .to_string();
+    let fallback_urls: Vec<String> = urls.map(str::to_string).collect();
+    let discover = args.contains("--discover");
 
     let refresh: u64 = args.opt_value_from_str(["-r", "--refresh"])?.unwrap_or(5);
+    let max_rate = args.opt_value_from_fn("--max-rate", ratelimit::parse_rate)?;
 
     let debug = args.contains(["-d", "--debug"]);
+    let strict = args.contains("--strict");
+    #[cfg(debug_assertions)]
+    let inject = (
+        args.opt_value_from_fn("--inject-latency", chaos::parse_latency)?
+            .unwrap_or_default(),
+        args.opt_value_from_fn("--inject-errors", chaos::parse_error_rate)?
+            .unwrap_or(0.0),
+    );
+
+    let stream: Option<String> = args.opt_value_from_str("--stream")?;
+    #[cfg(feature = "iproto")]
+    let iproto: Option<String> = args.opt_value_from_str("--iproto")?;
+    #[cfg(feature = "iproto")]
+    let iproto_auth: AuthMethod = args
+        .opt_value_from_str("--iproto-auth")?
+        .unwrap_or_default();
+    #[cfg(not(feature = "iproto"))]
+    let iproto: Option<String> = None;
+
+    let ssh: Option<String> = args.opt_value_from_str("--ssh")?;
+    let mut k8s_service: Option<K8sService> = args.opt_value_from_str("--k8s-service")?;
+    let k8s_context: Option<String> = args.opt_value_from_str("--k8s-context")?;
+    match &mut k8s_service {
+        Some(service) => service.context = k8s_context,
+        None if k8s_context.is_some() => {
+            return Err(anyhow!("--k8s-context needs --k8s-service"));
+        }
+        None => {}
+    }
+    // The service stands in for the URL, e.g. to key saved sessions
+    let url = match &k8s_service {
+        Some(service) => service.to_string(),
+        None => url,
+    };
+    let forward = match (&ssh, &k8s_service) {
+        (Some(_), Some(_)) => return Err(anyhow!("--ssh and --k8s-service can't be combined")),
+        (Some(_), None) => Some("--ssh"),
+        (None, Some(_)) => Some("--k8s-service"),
+        (None, None) => None,
+    };
+    if ssh.is_some() && url.starts_with("https://") {
+        return Err(anyhow!(
+            "--ssh reaches the cluster at 127.0.0.1, where its certificate can't match; \
+             use an http:// URL"
+        ));
+    }
+    let failover = !fallback_urls.is_empty() || discover;
+    if let (Some(forward), true) = (forward, failover) {
+        return Err(anyhow!(
+            "{} reaches a single address; fallback URLs and --discover can't be used with it",
+            forward
+        ));
+    }
+    if let (Some(forward), true) = (forward, doctor) {
+        return Err(anyhow!(
+            "picotui doctor checks --url directly and can't be used with {}",
+            forward
+        ));
+    }
+    if doctor && iproto.is_some() {
+        return Err(anyhow!("picotui doctor checks the HTTP API, not --iproto"));
+    }
+    if let (Some(forward), Some(_)) = (forward, &iproto) {
+        return Err(anyhow!(
+            "{} forwards the HTTP API and can't be used with --iproto",
+            forward
+        ));
+    }
+
+    // Events are only streamed over HTTP
+    let stream_path = if args.contains("--no-stream") || iproto.is_some() {
+        None
+    } else {
+        Some(stream.unwrap_or_else(|| stream::DEFAULT_STREAM_PATH.to_string()))
+    };
+
+    let wait_until: Option<WaitCondition> = args.opt_value_from_str("--wait-until")?;
+    let wait_timeout: u64 = args.opt_value_from_str("--wait-timeout")?.unwrap_or(300);
+
+    let events_stdout = args.contains("--events-stdout");
+
+    let units: ByteUnits = args.opt_value_from_str("--units")?.unwrap_or_default();
+    let precision: usize = args.opt_value_from_str("--precision")?.unwrap_or(1);
+    let thousands_separator = args.contains("--thousands-sep");
+    let number_format = NumberFormat {
+        units,
+        precision,
+        thousands_separator,
+    };
+
+    let time_format: TimeFormat = args.opt_value_from_str("--time")?.unwrap_or_default();
+    let lang: Lang = args.opt_value_from_str("--lang")?.unwrap_or_default();
+    let fuzzy = args.contains("--fuzzy");
+    let expand_problems = args.contains("--expand-problems");
+    let read_only = args.contains("--read-only");
+    let ops_dry_run = args.contains("--ops-dry-run");
+    let ops = args.contains("--ops") || ops_dry_run;
+    if ops && read_only {
+        return Err(anyhow!(
+            "--ops and --ops-dry-run can't be combined with --read-only"
+        ));
+    }
+    let accessible = args.contains("--accessible");
+    let slow_link = args.contains("--slow-link");
+    let bell: Option<Bell> = args.opt_value_from_str("--bell")?;
+    let color: ColorMode = args.opt_value_from_str("--color")?.unwrap_or_default();
+    let color = color.depth(
+        std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty()),
+        std::env::var("TERM").ok().as_deref(),
+    );
+    let view: Option<ViewMode> = args.opt_value_from_str("--view")?;
+    let filter: Option<String> = args.opt_value_from_str("--filter")?;
+    let announce_path: Option<PathBuf> = args.opt_value_from_str("--announce")?;
+    let config_path: Option<PathBuf> = args
+        .opt_value_from_str("--config")?
+        .or_else(config::config_file_path);
+
+    let data_dir: Option<PathBuf> = args.opt_value_from_str("--data-dir")?;
+
+    let diff: Option<String> = args.opt_value_from_str("--diff")?;
+    let diff = match diff {
+        Some(pair) => match pair.split_once(',') {
+            Some((old, new)) => Some((old.to_string(), new.to_string())),
+            None => return Err(anyhow!("--diff expects two snapshot names: OLD,NEW")),
+        },
+        None => None,
+    };
+
+    let terminal_mode = TerminalMode {
+        alt_screen: !args.contains("--no-alt-screen"),
+        mouse_capture: !args.contains("--no-mouse"),
+        release_events: false,
+    };
+
+    // Free arguments go last, after every option has been taken out
+    if let Some(TokensCommand::Clear(url)) = &mut tokens_command {
+        *url = args.opt_free_from_str()?;
+    }
+
+    // Headless modes look up saved sessions by the URL they connect to
+    let headless = exporter_listen.is_some() || events_stdout || wait_until.is_some();
+    let pick_cluster = !url_given && forward.is_none() && iproto.is_none() && !headless;
 
     let remaining = args.finish();
     if !remaining.is_empty() {
@@ -58,52 +533,389 @@ OPTIONS:
 
     Ok(Args {
         url,
+        fallback_urls,
+        discover,
+        pick_cluster,
         refresh,
+        max_rate,
         debug,
+        strict,
+        wait_until,
+        wait_timeout,
+        stream_path,
+        number_format,
+        time_format,
+        lang,
+        fuzzy,
+        expand_problems,
+        read_only,
+        ops,
+        ops_dry_run,
+        accessible,
+        slow_link,
+        bell,
+        color,
+        view,
+        filter,
+        announce_path,
+        ssh,
+        k8s_service,
+        #[cfg(feature = "iproto")]
+        iproto,
+        #[cfg(feature = "iproto")]
+        iproto_auth,
+        config_path,
+        data_dir,
+        diff,
+        events_stdout,
+        exporter_listen,
+        tokens_command,
+        completions,
+        doctor,
+        mock_server,
+        #[cfg(debug_assertions)]
+        inject,
+        terminal_mode,
     })
 }
 
+/// Options of `picotui mock-server`
+fn parse_mock_options(args: &mut pico_args::Arguments) -> Result<(String, MockOptions)> {
+    let listen = args
+        .opt_value_from_str("--listen")?
+        .unwrap_or_else(|| mock::DEFAULT_LISTEN.to_string());
+    let auth = match args.opt_value_from_str::<_, String>("--auth")? {
+        Some(credentials) => match credentials.split_once(':') {
+            Some((user, password)) => Some((user.to_string(), password.to_string())),
+            None => return Err(anyhow!("--auth expects USER:PASSWORD")),
+        },
+        None => None,
+    };
+    let mut fraction = |name: &'static str| -> Result<f64> {
+        let value: f64 = args.opt_value_from_str(name)?.unwrap_or(0.0);
+        if !(0.0..=1.0).contains(&value) {
+            return Err(anyhow!("{} must be between 0 and 1", name));
+        }
+        Ok(value)
+    };
+    let churn = fraction("--churn")?;
+    let error_rate = fraction("--error-rate")?;
+    let options = MockOptions {
+        topology: args.opt_value_from_str("--topology")?,
+        auth,
+        offline: args.opt_value_from_str("--offline")?.unwrap_or(0),
+        churn,
+        error_rate,
+        latency: Duration::from_millis(args.opt_value_from_str("--latency")?.unwrap_or(0)),
+    };
+    Ok((listen, options))
+}
+
+/// Where the App gets cluster data from: the binary protocol with
+/// `--iproto`, the HTTP API otherwise. The URL part keys saved sessions,
+/// pins and bookmarks; the other starts a source, again whenever the
+/// previous one's worker stopped.
+fn data_source(args: &Args, tunnel: Option<&Tunnel>) -> (String, SpawnSource) {
+    let debug = args.debug;
+    #[cfg(feature = "iproto")]
+    if let Some(address) = args.iproto.clone() {
+        let auth = args.iproto_auth;
+        let base_url = format!("iproto://{}", address);
+        let spawn: SpawnSource =
+            Box::new(move || Box::new(IprotoSource::spawn(&address, auth, debug)));
+        return (base_url, spawn);
+    }
+    let url = args.url.clone();
+    let connect_url = tunnel.map_or_else(|| url.clone(), |t| t.local_url().to_string());
+    let fallbacks = (!args.fallback_urls.is_empty() || args.discover)
+        .then(|| (args.fallback_urls.clone(), args.discover));
+    let spawn: SpawnSource = Box::new(move || {
+        let source = HttpSource::spawn_via(&url, &connect_url, debug);
+        if let Some((urls, discover)) = fallbacks.clone() {
+            source.send(api::ApiRequest::SetFallbacks { urls, discover });
+        }
+        Box::new(source)
+    });
+    (args.url.clone(), spawn)
+}
+
+/// View and Instances view filter to start with. A filter only narrows the
+/// Instances view, so that's where it starts unless a view is given too;
+/// the config's filter is dropped when the command line picks another view.
+fn startup_view(args: &Args, config: &config::Config) -> Result<(ViewMode, Option<String>)> {
+    let (view, filter) = match (args.view, &args.filter) {
+        (view, Some(filter)) => (view.unwrap_or(ViewMode::Instances), Some(filter)),
+        (Some(view), None) => (
+            view,
+            config
+                .filter
+                .as_ref()
+                .filter(|_| view == ViewMode::Instances),
+        ),
+        (None, None) => (
+            config.view.unwrap_or(if config.filter.is_some() {
+                ViewMode::Instances
+            } else {
+                ViewMode::Tiers
+            }),
+            config.filter.as_ref(),
+        ),
+    };
+    if filter.is_some() && view != ViewMode::Instances {
+        return Err(anyhow!("A filter only applies to the Instances view"));
+    }
+    Ok((view, filter.cloned()))
+}
+
 fn main() -> Result<()> {
-    let args = parse_args()?;
+    let mut args = parse_args()?;
+    i18n::set_lang(args.lang);
+    #[cfg(debug_assertions)]
+    chaos::set(args.inject.0, args.inject.1);
+
+    if let Some(dir) = args.data_dir.clone() {
+        paths::set_data_dir(dir);
+    }
 
     // Clear debug log file if debug mode
     if args.debug {
-        let _ = std::fs::write("picotui.log", "");
+        let log = paths::log_file();
+        if let Some(parent) = log.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        let _ = std::fs::write(log, "");
+    }
+    // Caught and shown in the status bar instead of over the screen
+    api::quiet_worker_panics(args.debug);
+
+    let config = match args.config_path {
+        Some(ref path) => config::load_config(path)?,
+        None => config::Config::default(),
+    };
+    if let Some(days) = config.token_max_age_days {
+        tokens::set_max_age_days(days);
+    }
+    if let Some(rate) = args.max_rate.or(config.max_request_rate) {
+        ratelimit::set_max_rate(rate);
+    }
+    schema::set_strict(args.strict);
+    if let Some(shell) = args.completions {
+        let dynamic = completions::Dynamic {
+            filters: config.saved_filters.into_iter().map(|f| f.filter).collect(),
+        };
+        print!("{}", completions::generate(shell, HELP, &dynamic));
+        return Ok(());
     }
 
-    // Create channels for API communication
-    let (request_tx, request_rx) = channel();
-    let (response_tx, response_rx) = channel();
+    let (start_view, start_filter) = startup_view(&args, &config)?;
 
-    // Spawn API worker thread
-    api::spawn_api_worker(args.url.clone(), request_rx, response_tx, args.debug);
+    // Saved session management, no cluster connection needed
+    if let Some(command) = args.tokens_command {
+        return run_tokens_command(command);
+    }
+
+    // Stand-in cluster, runs until killed
+    if let Some((listen, options)) = args.mock_server {
+        return mock::run(&listen, options);
+    }
+
+    if args.doctor {
+        let auth = tokens::load_tokens(&args.url).map(|entry| entry.auth);
+        let report = doctor::run(&args.url, auth.as_deref(), &doctor::TerminalEnv::from_env());
+        print!("{}", report);
+        std::process::exit(if report.failed() { 1 } else { 0 });
+    }
+
+    // Offline snapshot comparison, no cluster connection needed
+    if let Some((old, new)) = args.diff {
+        let dir = snapshot::snapshot_dir()
+            .ok_or_else(|| anyhow!("Could not determine data directory"))?;
+        let old = snapshot::load(&dir, &old)?;
+        let new = snapshot::load(&dir, &new)?;
+        if let Some(warning) = snapshot::source_mismatch(&old, &new) {
+            eprintln!("Warning: {}", warning);
+        }
+        print!("{}", snapshot::diff(&old.tiers, &new.tiers));
+        return Ok(());
+    }
+
+    // Open the tunnel while ssh can still prompt on the terminal. It is
+    // closed when dropped, so the headless modes drop it before exiting.
+    let tunnel = match (&args.ssh, &args.k8s_service) {
+        (Some(destination), _) => Some(Tunnel::ssh(destination, &args.url, args.debug)?),
+        (None, Some(service)) => Some(Tunnel::kubectl(service, args.debug)?),
+        (None, None) => None,
+    };
+    let connect_url = tunnel
+        .as_ref()
+        .map_or(args.url.as_str(), Tunnel::local_url)
+        .to_string();
+
+    // Headless Prometheus exporter
+    if let Some(listen) = args.exporter_listen {
+        let interval = Duration::from_secs(args.refresh.max(1));
+        if let Err(e) = exporter::run(&args.url, &connect_url, &listen, interval, args.debug) {
+            eprintln!("Error: {}", e);
+            drop(tunnel);
+            std::process::exit(2);
+        }
+        return Ok(());
+    }
+
+    // Headless change feed
+    if args.events_stdout {
+        let interval = Duration::from_secs(args.refresh.max(1));
+        if let Err(e) = events::stream_events(&args.url, &connect_url, interval, args.debug) {
+            eprintln!("Error: {}", e);
+            drop(tunnel);
+            std::process::exit(2);
+        }
+        return Ok(());
+    }
+
+    // Headless watch mode: poll until the condition holds, no terminal setup
+    if let Some(condition) = args.wait_until {
+        let interval = Duration::from_secs(args.refresh.max(1));
+        let timeout = Duration::from_secs(args.wait_timeout);
+        let code = match watch::wait_until(
+            &args.url,
+            &connect_url,
+            condition,
+            interval,
+            timeout,
+            args.debug,
+        ) {
+            Ok(outcome) => outcome.exit_code(),
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                2
+            }
+        };
+        drop(tunnel);
+        std::process::exit(code);
+    }
 
     // Setup terminal
-    enable_raw_mode()?;
+    let mode = TerminalMode {
+        release_events: cfg!(windows) || supports_keyboard_enhancement().unwrap_or(false),
+        ..args.terminal_mode
+    };
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    mode.enter(&mut stdout)?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
+    if !mode.alt_screen {
+        // Start from a blank screen rather than drawing over the shell output
+        terminal.clear()?;
+    }
+
+    // Several saved clusters and no --url: ask where to connect
+    let picker = if args.pick_cluster {
+        ClusterPicker::from_saved_sessions()
+    } else {
+        None
+    };
+    if let Some(mut picker) = picker {
+        match run_picker(&mut terminal, &mut picker, args.color)? {
+            Some(url) => args.url = url,
+            None => {
+                mode.leave(terminal.backend_mut())?;
+                terminal.show_cursor()?;
+                return Ok(());
+            }
+        }
+    }
+
+    // Spawn API worker thread
+    let (base_url, spawn) = data_source(&args, tunnel.as_ref());
 
     // Create app with channels
-    let mut app = App::new(args.url.clone(), request_tx, response_rx);
+    let mut app = App::new(base_url, spawn());
+    app.respawn_source = Some(spawn);
+    // Only a plain HTTP connection can be pointed elsewhere
+    app.url_editable = tunnel.is_none() && app.base_url == args.url;
+    app.stream_path = args.stream_path.clone();
+    app.number_format = args.number_format;
+    app.time_format = args.time_format;
+    app.fuzzy_filter = args.fuzzy;
+    app.expand_problems = args.expand_problems || config.expand_problems;
+    app.read_only = args.read_only;
+    app.ops = args.ops;
+    app.ops_dry_run = args.ops_dry_run;
+    app.restart_commands = config.rolling_restart;
+    app.alerts = AlertEngine::new(config.alerts);
+    app.accessible = args.accessible;
+    app.slow_link = args.slow_link;
+    app.bell = args.bell;
+    app.theme.depth = args.color;
+    app.announce_tx = args.announce_path.clone().map(announce::spawn);
+    app.saved_filters = config.saved_filters;
+    app.view_mode = start_view;
+    if let Some(filter) = start_filter {
+        app.set_filter(filter);
+    }
+    if let Some(columns) = config.instance_columns.filter(|c| !c.is_empty()) {
+        app.instance_columns = columns;
+    }
+    app.snapshot_dir = snapshot::snapshot_dir();
+    app.skins_dir = theme::skins_dir();
+    app.status_bar_format = config.status_bar;
+    if let Some(name) = config.theme {
+        if !app.select_theme(&name) {
+            app.status_message = Some(i18n::trf("Unknown theme \"{}\"", &[&name]));
+        }
+    }
+    app.usernames_path = usernames::usernames_file_path();
+    if let Some(username) = app
+        .usernames_path
+        .as_deref()
+        .and_then(|path| usernames::load_username(path, &app.base_url))
+    {
+        // Only the password is left to type
+        app.login_username = username;
+        app.login_focus = LoginFocus::Password;
+    }
+    app.bookmarks_path = bookmarks::bookmarks_file_path();
+    if let Some(ref path) = app.bookmarks_path {
+        app.bookmarks = bookmarks::load_bookmarks(path, &app.base_url);
+    }
+    app.pins_path = pins::pins_file_path();
+    if let Some(ref path) = app.pins_path {
+        app.pinned = pins::load_pins(path, &app.base_url);
+    }
+
+    app.audit_log_path = audit::audit_log_path();
+    app.config_path = args.config_path.clone();
+    app.debug_log = args.debug.then(paths::log_file);
+    let session = if app.read_only {
+        "read-only"
+    } else if app.ops_dry_run {
+        "ops-dry-run"
+    } else if app.ops {
+        "ops"
+    } else {
+        ""
+    };
+    app.audit("start", session);
 
     // Start initialization (non-blocking)
     app.start_init();
 
     // Run main loop
-    let result = run_app(&mut terminal, &mut app, args.refresh);
+    let result = run_app(&mut terminal, &mut app, args.refresh, mode, args.debug);
+    app.audit("quit", "");
 
     // Shutdown API worker
     app.shutdown();
 
     // Restore terminal
-    disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
+    mode.leave(terminal.backend_mut())?;
+    if !mode.alt_screen {
+        // Leave the last frame intact and put the prompt below it
+        let bottom = terminal.size()?.height.saturating_sub(1);
+        terminal.set_cursor_position((0, bottom))?;
+        println!();
+    }
     terminal.show_cursor()?;
 
     if let Err(e) = result {
@@ -113,10 +925,93 @@ fn main() -> Result<()> {
     Ok(())
 }
 
+fn run_tokens_command(command: TokensCommand) -> Result<()> {
+    match command {
+        TokensCommand::List => {
+            let sessions = tokens::list_tokens();
+            if sessions.is_empty() {
+                println!("No saved sessions");
+            }
+            let now = unix_now();
+            for (url, entry) in sessions {
+                let user = tokens::username_from_jwt(&entry.auth).unwrap_or_else(|| "-".into());
+                println!(
+                    "{}  {}  saved {}",
+                    url,
+                    user,
+                    TimeFormat::Relative.timestamp(entry.saved_at, now)
+                );
+            }
+        }
+        TokensCommand::Clear(Some(url)) => {
+            if tokens::delete_tokens(&url)? {
+                println!("Forgot the session for {}", url.trim_end_matches('/'));
+            } else {
+                println!("No saved session for {}", url.trim_end_matches('/'));
+            }
+        }
+        TokensCommand::Clear(None) => {
+            let count = tokens::clear_tokens()?;
+            println!("Forgot {} saved session(s)", count);
+        }
+    }
+    Ok(())
+}
+
+/// Ctrl held without Alt. Windows reports AltGr as Ctrl+Alt, and characters
+/// typed with it (`@`, `{`, `\\` on many layouts) must stay text.
+fn ctrl(modifiers: KeyModifiers) -> bool {
+    modifiers.contains(KeyModifiers::CONTROL) && !modifiers.contains(KeyModifiers::ALT)
+}
+
+// Poll interval while waiting for API responses
+const BUSY_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+// Redraw at least this often even when nothing changed
+const MAX_REDRAW_INTERVAL: Duration = Duration::from_secs(1);
+
+// Redraw this often while requests are in flight so the spinner turns
+const SPINNER_REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Show the cluster picker until a cluster is chosen (its URL is returned)
+/// or the user quits
+fn run_picker(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    picker: &mut ClusterPicker,
+    color: ColorDepth,
+) -> Result<Option<String>> {
+    loop {
+        picker.poll();
+        terminal.draw(|frame| {
+            ui::draw_picker(frame, picker);
+            color.apply(frame.buffer_mut());
+        })?;
+        if !event::poll(BUSY_POLL_INTERVAL)? {
+            continue;
+        }
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match key.code {
+            KeyCode::Up | KeyCode::Char('k') => picker.select_previous(),
+            KeyCode::Down | KeyCode::Char('j') => picker.select_next(),
+            KeyCode::Enter => return Ok(picker.selected_url().map(str::to_string)),
+            KeyCode::Char('c') if ctrl(key.modifiers) => return Ok(None),
+            KeyCode::Esc | KeyCode::Char('q') => return Ok(None),
+            _ => {}
+        }
+    }
+}
+
 fn run_app(
     terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
     app: &mut App,
     refresh_secs: u64,
+    mode: TerminalMode,
+    debug: bool,
 ) -> Result<()> {
     let tick_rate = if refresh_secs > 0 {
         Duration::from_secs(refresh_secs)
@@ -124,37 +1019,185 @@ fn run_app(
         Duration::from_secs(3600) // Effectively disabled
     };
     let mut last_tick = Instant::now();
+    let mut last_draw = Instant::now();
+    let suspend_requested = watch_suspend_signal()?;
 
     while app.running {
+        if suspend_requested.swap(false, Ordering::Relaxed) {
+            mode.suspend(terminal)?;
+            app.dirty = true;
+        }
+
         // Process any pending API responses (non-blocking)
         app.process_responses();
+        // Keys typed while connecting run once the first data is in
+        if app.input_mode == InputMode::Normal && !app.loading && !app.queued_keys.is_empty() {
+            for (code, modifiers) in std::mem::take(&mut app.queued_keys) {
+                handle_normal_mode_key(app, code, modifiers);
+            }
+            app.dirty = true;
+        }
+        if let Some(url) = app.pending_connect.take() {
+            let connect_url = url.clone();
+            app.connect(
+                url,
+                Box::new(move || Box::new(HttpSource::spawn(&connect_url, debug))),
+            );
+            last_tick = Instant::now();
+            app.dirty = true;
+        }
+        if app.settle_filter()
+            || app.settle_password_reveal()
+            || app.settle_flash()
+            || app.settle_toasts()
+            || app.advance_rolling_restart()
+        {
+            app.dirty = true;
+        }
+
+        // Stretched while the cluster is unreachable
+        let refresh_interval = app.refresh_interval(tick_rate);
+        app.next_refresh = (refresh_secs > 0).then(|| last_tick + refresh_interval);
+
+        // Draw UI only when state changed or the periodic redraw is due
+        let redraw_interval = if app.loading && app.animate() {
+            SPINNER_REDRAW_INTERVAL
+        } else {
+            MAX_REDRAW_INTERVAL
+        };
+        // (never on a timer with --slow-link, where every byte counts)
+        let redraw_due = !app.slow_link && last_draw.elapsed() >= redraw_interval;
+        if app.dirty || redraw_due {
+            let draw_started = Instant::now();
+            let completed = terminal.draw(|f| ui::draw(f, &mut *app))?;
+            app.frame_stats.record_frame(draw_started.elapsed());
+            app.dirty = false;
+            last_draw = Instant::now();
+            app.announce_selection();
+
+            // Dump the frame we just rendered if requested
+            if let Some(format) = app.pending_screenshot.take() {
+                match screenshot::save(completed.buffer, format) {
+                    Ok(path) => {
+                        app.toast(
+                            ToastKind::Success,
+                            i18n::trf("Saved screen to {}", &[&path.display()]),
+                        );
+                        app.audit("export", &path.display().to_string());
+                    }
+                    Err(e) => app.toast(
+                        ToastKind::Error,
+                        i18n::trf("Failed to save screen: {}", &[&e]),
+                    ),
+                }
+                app.dirty = true;
+            }
+
+            if let Some(text) = app.pending_clipboard.take() {
+                use std::io::Write;
+                let out = terminal.backend_mut();
+                write!(out, "{}", selection::osc52_copy(&text))?;
+                out.flush()?;
+            }
+
+            if std::mem::take(&mut app.pending_bell) {
+                use std::io::Write;
+                let out = terminal.backend_mut();
+                write!(out, "\x07")?;
+                out.flush()?;
+            }
 
-        // Draw UI
-        terminal.draw(|f| ui::draw(f, &mut *app))?;
+            if let Some(url) = app.pending_open_url.take() {
+                if let Err(e) = actions::open_in_browser(&url) {
+                    app.status_message = Some(i18n::trf("Could not open {}: {}", &[&url, &e]));
+                    app.dirty = true;
+                }
+            }
+        }
 
-        // Poll for keyboard input with short timeout for responsiveness
-        let timeout = Duration::from_millis(50);
+        // Poll quickly while responses are expected, otherwise sleep until
+        // the next tick or forced redraw, whichever comes first
+        let timeout = if app.is_busy() {
+            BUSY_POLL_INTERVAL
+        } else {
+            let until_tick = refresh_interval.saturating_sub(last_tick.elapsed());
+            let until_redraw = if app.slow_link {
+                until_tick
+            } else {
+                MAX_REDRAW_INTERVAL.saturating_sub(last_draw.elapsed())
+            };
+            until_tick.min(until_redraw).max(BUSY_POLL_INTERVAL)
+        };
 
         if crossterm::event::poll(timeout)? {
-            if let Event::Key(key) = event::read()? {
-                match app.input_mode {
-                    InputMode::Login => handle_login_input(app, key.code, key.modifiers),
-                    InputMode::Normal => {
-                        if app.show_health {
-                            handle_health_input(app, key.code);
-                        } else if app.show_detail {
-                            handle_detail_input(app, key.code);
-                        } else {
-                            handle_normal_input(app, key.code, key.modifiers);
+            match event::read()? {
+                // Only the password reveal key cares about releases
+                Event::Key(key) if key.kind == KeyEventKind::Release => {
+                    let reveal_released = key.code == KeyCode::Char('r');
+                    app.dirty |= reveal_released && app.login_reveal_held;
+                    app.login_reveal_held &= !reveal_released;
+                }
+                Event::Key(key) if key.code == KeyCode::F(12) => {
+                    app.show_diagnostics = !app.show_diagnostics;
+                    app.dirty = true;
+                }
+                Event::Key(key) if key.code == KeyCode::Char('z') && ctrl(key.modifiers) => {
+                    mode.suspend(terminal)?;
+                    app.dirty = true;
+                }
+                Event::Key(key) => {
+                    app.dirty = true;
+                    match app.input_mode {
+                        InputMode::Initializing => {
+                            handle_init_key(app, key.code, key.modifiers);
+                        }
+                        InputMode::Login => {
+                            app.login_caps_lock = key.state.contains(KeyEventState::CAPS_LOCK);
+                            if key.code == KeyCode::Char('r') && ctrl(key.modifiers) {
+                                // Reveal while held: until released where the
+                                // terminal reports it, else while auto-repeating
+                                if mode.release_events {
+                                    app.login_reveal_held = true;
+                                } else {
+                                    app.hold_password_reveal();
+                                }
+                            } else {
+                                handle_login_input(app, key.code, key.modifiers);
+                            }
+                        }
+                        InputMode::Normal => {
+                            handle_normal_mode_key(app, key.code, key.modifiers);
                         }
                     }
                 }
+                Event::Paste(text) => {
+                    app.paste(&text);
+                    app.dirty = true;
+                }
+                Event::Resize(_, _) => app.dirty = true,
+                _ => {}
             }
         }
 
-        // Auto-refresh
-        if last_tick.elapsed() >= tick_rate && app.input_mode == InputMode::Normal && !app.loading {
+        // Retry a failed first connection on the same schedule
+        if last_tick.elapsed() >= refresh_interval
+            && app.input_mode == InputMode::Initializing
+            && !app.loading
+            && app.url_prompt.is_none()
+        {
+            app.start_init();
+            app.dirty = true;
+            last_tick = Instant::now();
+        }
+
+        // Auto-refresh (paused while the server streams updates)
+        if last_tick.elapsed() >= refresh_interval
+            && app.input_mode == InputMode::Normal
+            && !app.loading
+            && !app.is_streaming()
+        {
             app.request_refresh();
+            app.dirty = !app.slow_link;
             last_tick = Instant::now();
         }
     }
@@ -162,15 +1205,223 @@ fn run_app(
     Ok(())
 }
 
+/// Macro keys (`.`, `Q`, `@`) at the top level, everything else goes to
+/// the open popup or the main screen
+fn handle_normal_mode_key(app: &mut App, code: KeyCode, modifiers: KeyModifiers) {
+    // Register letter after Q or @; anything else cancels
+    if let Some(prompt) = app.macros.register_prompt.take() {
+        app.status_message = None;
+        if let KeyCode::Char(register @ 'a'..='z') = code {
+            match prompt {
+                RegisterPrompt::Record => {
+                    app.macros.start_recording(register);
+                    app.status_message = Some(i18n::trf("Recording @{}, Q to stop", &[&register]));
+                }
+                RegisterPrompt::Replay => match app.macros.register(register) {
+                    Some(keys) => replay_keys(app, &keys),
+                    None => {
+                        app.status_message = Some(i18n::trf("Register {} is empty", &[&register]))
+                    }
+                },
+            }
+        }
+        return;
+    }
+
+    if at_top_level(app) && !ctrl(modifiers) {
+        match code {
+            KeyCode::Char('.') => {
+                match app.macros.last_action.clone() {
+                    Some(RepeatAction::Key(key)) => dispatch_normal_key(app, key),
+                    Some(RepeatAction::SetFilter(filter))
+                        if app.view_mode == ViewMode::Instances =>
+                    {
+                        app.set_filter(filter);
+                    }
+                    _ => {}
+                }
+                return;
+            }
+            KeyCode::Char('Q') => {
+                app.status_message = Some(match app.macros.stop_recording() {
+                    Some(register) => i18n::trf("Recorded @{}", &[&register]),
+                    None => {
+                        app.macros.register_prompt = Some(RegisterPrompt::Record);
+                        i18n::tr("Record macro: press a letter").to_string()
+                    }
+                });
+                return;
+            }
+            KeyCode::Char('@') => {
+                app.macros.register_prompt = Some(RegisterPrompt::Replay);
+                app.status_message = Some(i18n::tr("Replay macro: press a letter").to_string());
+                return;
+            }
+            _ => {}
+        }
+    }
+
+    dispatch_normal_key(app, (code, modifiers));
+}
+
+/// Replay a register, stopping at a confirmation dialog: what it confirms
+/// has to be typed by the user, not by the macro
+fn replay_keys(app: &mut App, keys: &[Key]) {
+    for &key in keys {
+        if app.confirm.is_some() {
+            app.status_message = Some(i18n::tr("Macro stopped at the confirmation").to_string());
+            return;
+        }
+        dispatch_normal_key(app, key);
+    }
+}
+
+/// No popup or prompt has the keyboard
+fn at_top_level(app: &App) -> bool {
+    !(app.show_health
+        || app.show_action_menu
+        || app.show_detail
+        || app.show_leaders
+        || app.show_memory_stats
+        || app.show_heatmap
+        || app.show_topology
+        || app.show_timings
+        || app.show_error_log
+        || app.show_tables
+        || app.show_alerts
+        || app.show_parameters
+        || app.rolling_restart.is_some()
+        || app.confirm.is_some()
+        || app.dry_run.is_some()
+        || app.snapshot_diff.is_some()
+        || app.show_snapshot_picker
+        || app.show_filter_picker
+        || app.show_column_picker
+        || app.show_sort_picker
+        || app.show_view_switcher
+        || app.filter_active
+        || app.snapshot_prompt_active
+        || app.search_active
+        || app.bookmark_prompt.is_some())
+}
+
+/// Keys that change what the views show, repeated by `.`
+fn is_structural(code: KeyCode) -> bool {
+    matches!(
+        code,
+        KeyCode::Right | KeyCode::Left | KeyCode::Char('l' | 'h' | 'S' | 'z' | 'Z' | ' ' | '*')
+    )
+}
+
+/// Handle one key, noting it for macros and `.`. Keys typed into a
+/// confirmation dialog are left out of macros.
+fn dispatch_normal_key(app: &mut App, key: Key) {
+    let (code, modifiers) = key;
+    if app.confirm.is_none() {
+        app.macros.record(key);
+    }
+
+    let was_filtering = app.filter_active;
+    if at_top_level(app) && !ctrl(modifiers) && is_structural(code) {
+        app.macros.last_action = Some(RepeatAction::Key(key));
+    }
+
+    if app.confirm.is_some() {
+        handle_confirm_input(app, code);
+    } else if app.dry_run.is_some() {
+        handle_dry_run_input(app, code);
+    } else if app.show_health {
+        handle_health_input(app, code);
+    } else if app.show_action_menu {
+        handle_action_menu_input(app, code);
+    } else if app.show_detail {
+        handle_detail_input(app, code);
+    } else if app.show_leaders {
+        handle_leaders_input(app, code);
+    } else if app.show_memory_stats {
+        handle_memory_stats_input(app, code);
+    } else if app.show_heatmap {
+        handle_heatmap_input(app, code);
+    } else if app.show_topology {
+        handle_topology_input(app, code);
+    } else if app.show_timings {
+        handle_timings_input(app, code);
+    } else if app.show_error_log {
+        handle_error_log_input(app, code);
+    } else if app.show_tables {
+        handle_tables_input(app, code);
+    } else if app.show_alerts {
+        handle_alerts_input(app, code);
+    } else if app.show_parameters {
+        handle_parameters_input(app, code);
+    } else if app.rolling_restart.is_some() {
+        handle_rolling_restart_input(app, code);
+    } else if app.snapshot_diff.is_some() {
+        handle_snapshot_diff_input(app, code);
+    } else if app.show_snapshot_picker {
+        handle_snapshot_picker_input(app, code);
+    } else if app.show_filter_picker {
+        handle_filter_picker_input(app, code);
+    } else if app.show_column_picker {
+        handle_column_picker_input(app, code);
+    } else if app.show_sort_picker {
+        handle_sort_picker_input(app, code);
+    } else if app.show_view_switcher {
+        handle_view_switcher_input(app, code);
+    } else {
+        handle_normal_input(app, code, modifiers);
+    }
+
+    // An applied filter repeats as setting the same filter again
+    if was_filtering && !app.filter_active && code == KeyCode::Enter {
+        app.macros.last_action = Some(RepeatAction::SetFilter(app.filter_text.clone()));
+    }
+}
+
+/// Keys on the connecting screen. Keys meant for the cluster view are
+/// kept and run once it's shown.
+fn handle_init_key(app: &mut App, code: KeyCode, modifiers: KeyModifiers) {
+    if let Some(url) = app.url_prompt.as_mut() {
+        match code {
+            KeyCode::Enter => app.submit_url_edit(),
+            KeyCode::Esc => {
+                app.url_prompt = None;
+                app.status_message = None;
+            }
+            KeyCode::Backspace => {
+                url.pop();
+            }
+            KeyCode::Char('c') if ctrl(modifiers) => app.running = false,
+            KeyCode::Char(c) if !ctrl(modifiers) => url.push(c),
+            _ => {}
+        }
+        return;
+    }
+    match code {
+        KeyCode::Char('c') if ctrl(modifiers) => app.running = false,
+        KeyCode::Esc | KeyCode::Char('q') => app.running = false,
+        KeyCode::Char('r') | KeyCode::Enter if !app.loading => app.start_init(),
+        KeyCode::Char('e') if app.url_editable => app.start_url_edit(),
+        _ => app.queue_key((code, modifiers)),
+    }
+}
+
 fn handle_login_input(app: &mut App, key: KeyCode, modifiers: KeyModifiers) {
     match key {
         KeyCode::Esc | KeyCode::Char('q') => {
             app.running = false;
         }
-        KeyCode::Char('s') if modifiers.contains(KeyModifiers::CONTROL) => {
+        KeyCode::Char('s') if ctrl(modifiers) => {
             // Toggle show/hide password
             app.login_show_password = !app.login_show_password;
         }
+        KeyCode::F(2) => {
+            // Same toggle for consoles that take Ctrl+S for flow control
+            app.login_show_password = !app.login_show_password;
+        }
+        KeyCode::Char('t') if ctrl(modifiers) => {
+            app.check_server();
+        }
         KeyCode::Tab | KeyCode::Down => {
             // Cycle through: Username -> Password -> RememberMe -> Username
             app.login_focus = match app.login_focus {
@@ -187,11 +1438,9 @@ fn handle_login_input(app: &mut App, key: KeyCode, modifiers: KeyModifiers) {
                 LoginFocus::RememberMe => LoginFocus::Password,
             };
         }
-        KeyCode::Enter => {
+        KeyCode::Enter if !app.loading => {
             // Always submit login on Enter, regardless of focused field
-            if !app.login_username.is_empty() && !app.loading {
-                app.request_login();
-            }
+            app.submit_login();
         }
         KeyCode::Char(' ') if app.login_focus == LoginFocus::RememberMe => {
             // Space toggles checkbox
@@ -200,18 +1449,22 @@ fn handle_login_input(app: &mut App, key: KeyCode, modifiers: KeyModifiers) {
         KeyCode::Backspace => match app.login_focus {
             LoginFocus::Username => {
                 app.login_username.pop();
+                app.login_username_error = None;
             }
             LoginFocus::Password => {
                 app.login_password.pop();
+                app.login_password_warning = None;
             }
             LoginFocus::RememberMe => {}
         },
         KeyCode::Char(c) => match app.login_focus {
             LoginFocus::Username => {
                 app.login_username.push(c);
+                app.login_username_error = None;
             }
             LoginFocus::Password => {
                 app.login_password.push(c);
+                app.login_password_warning = None;
             }
             LoginFocus::RememberMe => {}
         },
@@ -219,6 +1472,33 @@ fn handle_login_input(app: &mut App, key: KeyCode, modifiers: KeyModifiers) {
     }
 }
 
+fn handle_action_menu_input(app: &mut App, key: KeyCode) {
+    let rows = app.action_menu_rows();
+    let count = rows.len().max(1);
+    match key {
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('a') => {
+            app.show_action_menu = false;
+        }
+        KeyCode::Up | KeyCode::Char('k') => {
+            app.action_menu_index = (app.action_menu_index + count - 1) % count;
+        }
+        KeyCode::Down | KeyCode::Char('j') => {
+            app.action_menu_index = (app.action_menu_index + 1) % count;
+        }
+        KeyCode::Enter => match rows.get(app.action_menu_index) {
+            Some(&action) => app.run_action(action),
+            None => app.show_action_menu = false,
+        },
+        KeyCode::Char(c) => {
+            // Each action's own key runs it directly
+            if let Some(&action) = rows.iter().find(|action| action.key() == c) {
+                app.run_action(action);
+            }
+        }
+        _ => {}
+    }
+}
+
 fn handle_detail_input(app: &mut App, key: KeyCode) {
     match key {
         KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') => {
@@ -228,19 +1508,312 @@ fn handle_detail_input(app: &mut App, key: KeyCode) {
     }
 }
 
-// Default visible height for page navigation (will be overridden by actual terminal size)
-const DEFAULT_PAGE_HEIGHT: usize = 20;
-
-fn handle_health_input(app: &mut App, key: KeyCode) {
+fn handle_leaders_input(app: &mut App, key: KeyCode) {
+    match key {
+        KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') | KeyCode::Char('L') => {
+            app.show_leaders = false;
+        }
+        _ => {}
+    }
+}
+
+fn handle_memory_stats_input(app: &mut App, key: KeyCode) {
+    match key {
+        KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') | KeyCode::Char('M') => {
+            app.show_memory_stats = false;
+        }
+        _ => {}
+    }
+}
+
+fn handle_timings_input(app: &mut App, key: KeyCode) {
+    match key {
+        KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') | KeyCode::Char('R') => {
+            app.show_timings = false;
+        }
+        _ => {}
+    }
+}
+
+fn handle_error_log_input(app: &mut App, key: KeyCode) {
+    match key {
+        KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') | KeyCode::Char('!') => {
+            app.show_error_log = false;
+        }
+        KeyCode::Char('c') => app.error_log.clear(),
+        _ => {}
+    }
+}
+
+fn handle_tables_input(app: &mut App, key: KeyCode) {
+    let count = app.tables.as_ref().map_or(0, Vec::len);
+    match key {
+        KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') | KeyCode::Char('t') => {
+            app.show_tables = false;
+        }
+        KeyCode::Up | KeyCode::Char('k') => {
+            app.tables_scroll = app.tables_scroll.saturating_sub(1);
+        }
+        KeyCode::Down | KeyCode::Char('j') if app.tables_scroll + 1 < count => {
+            app.tables_scroll += 1;
+        }
+        KeyCode::Char('r') if !app.tables_loading => {
+            app.request_tables();
+        }
+        _ => {}
+    }
+}
+
+fn handle_heatmap_input(app: &mut App, key: KeyCode) {
+    match key {
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('U') => app.show_heatmap = false,
+        KeyCode::Enter => app.jump_to_heatmap_cell(),
+        KeyCode::Left | KeyCode::Char('h') => app.move_heatmap_cursor(-1),
+        KeyCode::Right | KeyCode::Char('l') => app.move_heatmap_cursor(1),
+        KeyCode::Down | KeyCode::Char('j') => app.move_heatmap_replicaset(true),
+        KeyCode::Up | KeyCode::Char('k') => app.move_heatmap_replicaset(false),
+        KeyCode::Char('c') => app.heatmap_coloring = app.heatmap_coloring.toggle(),
+        _ => {}
+    }
+}
+
+fn handle_topology_input(app: &mut App, key: KeyCode) {
+    match key {
+        KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') | KeyCode::Char('O') => {
+            app.show_topology = false;
+        }
+        KeyCode::Char('n') => app.topology_names = !app.topology_names,
+        _ => {}
+    }
+}
+
+fn handle_alerts_input(app: &mut App, key: KeyCode) {
+    if let KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') | KeyCode::Char('A') = key {
+        app.show_alerts = false;
+    }
+}
+
+fn handle_parameters_input(app: &mut App, key: KeyCode) {
+    if app.parameters_search_active {
+        match key {
+            KeyCode::Enter => app.parameters_search_active = false,
+            KeyCode::Esc => {
+                app.parameters_search_active = false;
+                app.set_parameters_search(String::new());
+            }
+            KeyCode::Backspace => {
+                let mut search = app.parameters_search.clone();
+                search.pop();
+                app.set_parameters_search(search);
+            }
+            KeyCode::Char(c) => {
+                let search = format!("{}{}", app.parameters_search, c);
+                app.set_parameters_search(search);
+            }
+            _ => {}
+        }
+        return;
+    }
+    let count = app.visible_parameters().len();
+    match key {
+        KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') | KeyCode::Char('C') => {
+            app.show_parameters = false;
+        }
+        KeyCode::Char('/') => {
+            app.parameters_search_active = true;
+        }
+        KeyCode::Up | KeyCode::Char('k') => {
+            app.parameters_scroll = app.parameters_scroll.saturating_sub(1);
+        }
+        KeyCode::Down | KeyCode::Char('j') if app.parameters_scroll + 1 < count => {
+            app.parameters_scroll += 1;
+        }
+        KeyCode::Char('r') if !app.parameters_loading => {
+            app.request_parameters();
+        }
+        _ => {}
+    }
+}
+
+fn handle_confirm_input(app: &mut App, key: KeyCode) {
+    let Some(dialog) = app.confirm.as_mut() else {
+        return;
+    };
+    match key {
+        KeyCode::Esc => app.confirm = None,
+        KeyCode::Enter => app.confirm_operation(),
+        KeyCode::Backspace => dialog.pop(),
+        KeyCode::Char(c) => dialog.push_str(&c.to_string()),
+        _ => {}
+    }
+}
+
+fn handle_dry_run_input(app: &mut App, key: KeyCode) {
+    match key {
+        KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') => app.dry_run = None,
+        KeyCode::Char('y') => app.copy_dry_run(),
+        _ => {}
+    }
+}
+
+fn handle_rolling_restart_input(app: &mut App, key: KeyCode) {
+    let Some(outcome) = app.rolling_restart.as_ref().map(|r| r.outcome.clone()) else {
+        return;
+    };
+    match (outcome, key) {
+        (Outcome::Planned, KeyCode::Enter) => app.ask_to_start_rolling_restart(),
+        (Outcome::Running, KeyCode::Char('x')) => app.abort_rolling_restart(),
+        // Only closed once nothing is going on
+        (Outcome::Running, _) => {}
+        (_, KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q')) => app.rolling_restart = None,
+        _ => {}
+    }
+}
+
+fn handle_snapshot_diff_input(app: &mut App, key: KeyCode) {
+    match key {
+        KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') => {
+            app.snapshot_diff = None;
+        }
+        _ => {}
+    }
+}
+
+fn handle_snapshot_picker_input(app: &mut App, key: KeyCode) {
+    let count = app.snapshot_names.len();
+    match key {
+        KeyCode::Esc | KeyCode::Char('q') => {
+            app.show_snapshot_picker = false;
+        }
+        KeyCode::Up | KeyCode::Char('k') => {
+            app.snapshot_picker_index = (app.snapshot_picker_index + count - 1) % count;
+        }
+        KeyCode::Down | KeyCode::Char('j') => {
+            app.snapshot_picker_index = (app.snapshot_picker_index + 1) % count;
+        }
+        KeyCode::Enter => {
+            app.compare_snapshot(app.snapshot_picker_index);
+        }
+        _ => {}
+    }
+}
+
+fn handle_column_picker_input(app: &mut App, key: KeyCode) {
+    let count = app.column_picker_rows().len();
+    match key {
+        KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') => {
+            app.show_column_picker = false;
+        }
+        KeyCode::Up | KeyCode::Char('k') => {
+            app.column_picker_index = (app.column_picker_index + count - 1) % count;
+        }
+        KeyCode::Down | KeyCode::Char('j') => {
+            app.column_picker_index = (app.column_picker_index + 1) % count;
+        }
+        KeyCode::Char(' ') => {
+            app.toggle_picker_column();
+        }
+        KeyCode::Char('K') => {
+            app.move_picker_column(true);
+        }
+        KeyCode::Char('J') => {
+            app.move_picker_column(false);
+        }
+        _ => {}
+    }
+}
+
+fn handle_sort_picker_input(app: &mut App, key: KeyCode) {
+    let count = SortField::ALL.len();
+    match key {
+        KeyCode::Esc | KeyCode::Char('q') => {
+            app.show_sort_picker = false;
+        }
+        KeyCode::Up | KeyCode::Char('k') => {
+            app.sort_picker_index = (app.sort_picker_index + count - 1) % count;
+        }
+        KeyCode::Down | KeyCode::Char('j') => {
+            app.sort_picker_index = (app.sort_picker_index + 1) % count;
+        }
+        KeyCode::Enter => {
+            app.pick_sort_field(SortField::ALL[app.sort_picker_index]);
+        }
+        KeyCode::Left | KeyCode::Right | KeyCode::Tab | KeyCode::Char('S') => {
+            app.toggle_sort_order();
+        }
+        KeyCode::Char(c) => {
+            if let Some(&field) = SortField::ALL.iter().find(|f| f.key() == c) {
+                app.pick_sort_field(field);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn handle_view_switcher_input(app: &mut App, key: KeyCode) {
+    let count = ViewMode::ALL.len();
+    match key {
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('v') => {
+            app.show_view_switcher = false;
+        }
+        KeyCode::Up | KeyCode::Char('k') => {
+            app.view_switcher_index = (app.view_switcher_index + count - 1) % count;
+        }
+        KeyCode::Down | KeyCode::Char('j') | KeyCode::Char('g') | KeyCode::Tab => {
+            app.view_switcher_index = (app.view_switcher_index + 1) % count;
+        }
+        KeyCode::Enter => {
+            app.pick_view(ViewMode::ALL[app.view_switcher_index]);
+        }
+        KeyCode::Char(c @ '1'..='9') => {
+            let index = c as usize - '1' as usize;
+            if let Some(&mode) = ViewMode::ALL.get(index) {
+                app.pick_view(mode);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn handle_filter_picker_input(app: &mut App, key: KeyCode) {
+    let count = app.saved_filters.len();
+    match key {
+        KeyCode::Esc | KeyCode::Char('q') => {
+            app.show_filter_picker = false;
+        }
+        KeyCode::Up | KeyCode::Char('k') => {
+            app.filter_picker_index = (app.filter_picker_index + count - 1) % count;
+        }
+        KeyCode::Down | KeyCode::Char('j') => {
+            app.filter_picker_index = (app.filter_picker_index + 1) % count;
+        }
+        KeyCode::Enter => {
+            app.apply_saved_filter(app.filter_picker_index);
+        }
+        KeyCode::Char(c @ '1'..='9') => {
+            // Quick select
+            app.apply_saved_filter(c as usize - '1' as usize);
+        }
+        _ => {}
+    }
+}
+
+// Default visible height for page navigation (will be overridden by actual terminal size)
+const DEFAULT_PAGE_HEIGHT: usize = 20;
+
+fn handle_health_input(app: &mut App, key: KeyCode) {
     match key {
         KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') => {
             app.show_health = false;
             app.health_status = None;
             app.health_error = None;
+            app.cancel_group_health();
         }
-        KeyCode::Char('r') => {
+        KeyCode::Char('r') if !app.health_loading => {
             // Refresh health status
-            if !app.health_loading {
+            if app.group_health.is_some() {
+                app.refresh_group_health();
+            } else {
                 app.show_health = false; // Close temporarily
                 app.request_health_status();
             }
@@ -262,25 +1835,78 @@ fn handle_normal_input(app: &mut App, key: KeyCode, modifiers: KeyModifiers) {
             KeyCode::Enter => {
                 // Exit filter mode but keep filter
                 app.filter_active = false;
+                app.record_filter();
+            }
+            KeyCode::Up => app.filter_history_prev(),
+            KeyCode::Down => app.filter_history_next(),
+            KeyCode::Tab => {
+                // Toggle fuzzy matching
+                app.fuzzy_filter = !app.fuzzy_filter;
+                app.reset_selection();
             }
             KeyCode::Backspace => {
                 app.filter_text.pop();
-                app.reset_selection();
+                app.filter_edited();
             }
             KeyCode::Char(c) => {
                 app.filter_text.push(c);
-                app.reset_selection();
+                app.filter_edited();
+            }
+            _ => {}
+        }
+        return;
+    }
+
+    // Snapshot name prompt
+    if app.snapshot_prompt_active {
+        match key {
+            KeyCode::Esc => app.cancel_snapshot_prompt(),
+            KeyCode::Enter => app.save_snapshot(),
+            KeyCode::Backspace => {
+                app.snapshot_name.pop();
+            }
+            KeyCode::Char(c) => app.snapshot_name.push(c),
+            _ => {}
+        }
+        return;
+    }
+
+    // Handle search input mode
+    if app.search_active {
+        match key {
+            KeyCode::Esc => app.cancel_search(),
+            KeyCode::Enter => app.confirm_search(),
+            KeyCode::Backspace => {
+                app.search_text.pop();
+                app.update_search();
+            }
+            KeyCode::Char(c) => {
+                app.search_text.push(c);
+                app.update_search();
             }
             _ => {}
         }
         return;
     }
 
+    app.status_message = None;
+
+    // Second key of m<letter> / '<letter>; anything else cancels
+    if let Some(prompt) = app.bookmark_prompt.take() {
+        if let KeyCode::Char(letter @ ('a'..='z' | 'A'..='Z')) = key {
+            match prompt {
+                BookmarkPrompt::Set => app.set_bookmark(letter),
+                BookmarkPrompt::Jump => app.jump_to_bookmark(letter),
+            }
+        }
+        return;
+    }
+
     match key {
         KeyCode::Char('q') => {
             app.running = false;
         }
-        KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => {
+        KeyCode::Char('c') if ctrl(modifiers) => {
             app.running = false;
         }
         // Basic navigation
@@ -305,19 +1931,19 @@ fn handle_normal_input(app: &mut App, key: KeyCode, modifiers: KeyModifiers) {
             // Go to last item
             app.select_last();
         }
-        KeyCode::Char('d') if modifiers.contains(KeyModifiers::CONTROL) => {
+        KeyCode::Char('d') if ctrl(modifiers) => {
             // Half page down (Ctrl+D)
             app.select_half_page_down(DEFAULT_PAGE_HEIGHT);
         }
-        KeyCode::Char('u') if modifiers.contains(KeyModifiers::CONTROL) => {
+        KeyCode::Char('u') if ctrl(modifiers) => {
             // Half page up (Ctrl+U)
             app.select_half_page_up(DEFAULT_PAGE_HEIGHT);
         }
-        KeyCode::Char('f') if modifiers.contains(KeyModifiers::CONTROL) => {
+        KeyCode::Char('f') if ctrl(modifiers) => {
             // Full page down (Ctrl+F)
             app.select_page_down(DEFAULT_PAGE_HEIGHT);
         }
-        KeyCode::Char('b') if modifiers.contains(KeyModifiers::CONTROL) => {
+        KeyCode::Char('b') if ctrl(modifiers) => {
             // Full page up (Ctrl+B)
             app.select_page_up(DEFAULT_PAGE_HEIGHT);
         }
@@ -331,71 +1957,297 @@ fn handle_normal_input(app: &mut App, key: KeyCode, modifiers: KeyModifiers) {
         KeyCode::Enter => {
             app.toggle_detail();
         }
-        KeyCode::Char('r') => {
-            if !app.loading {
-                app.request_refresh();
-            }
+        KeyCode::Char('r') if ctrl(modifiers) && !app.loading => {
+            // Tiers only, without the cluster summary
+            app.audit("refresh", "tiers");
+            app.request_tiers_refresh();
         }
-        KeyCode::Char('X') => {
+        KeyCode::Char('r') if !app.loading => {
+            app.audit("refresh", "");
+            app.request_refresh();
+        }
+        KeyCode::Char('X') if app.auth_enabled => {
             // Logout (capital X to avoid accidental logout)
-            if app.auth_enabled {
-                app.logout();
-            }
+            app.logout();
         }
         // View modes
         KeyCode::Char('g') => {
             // Cycle view mode and clear filter
-            app.view_mode = app.view_mode.cycle_next();
-            app.filter_text.clear();
-            app.filter_active = false;
-            app.reset_selection();
+            app.switch_view(app.view_mode.cycle_next());
+        }
+        KeyCode::Char('v') => {
+            // Every view with its row count and problems
+            app.open_view_switcher();
         }
         KeyCode::Char('1') => {
-            app.view_mode = ViewMode::Tiers;
-            app.filter_text.clear();
-            app.filter_active = false;
-            app.reset_selection();
+            app.switch_view(ViewMode::Tiers);
         }
         KeyCode::Char('2') => {
-            app.view_mode = ViewMode::Replicasets;
-            app.filter_text.clear();
-            app.filter_active = false;
-            app.reset_selection();
+            app.switch_view(ViewMode::Replicasets);
         }
         KeyCode::Char('3') => {
-            app.view_mode = ViewMode::Instances;
-            app.filter_text.clear();
-            app.filter_active = false;
-            app.reset_selection();
+            app.switch_view(ViewMode::Instances);
         }
         // Sorting
-        KeyCode::Char('s') => {
-            // Cycle sort field (only in instances view)
-            if app.view_mode == ViewMode::Instances {
-                app.sort_field = app.sort_field.cycle_next();
-                app.reset_selection();
-            }
+        KeyCode::Char('s') if app.view_mode == ViewMode::Instances => {
+            // Pick the sort field and order (only in instances view)
+            app.open_sort_picker();
         }
-        KeyCode::Char('S') => {
+        KeyCode::Char('S') if app.view_mode == ViewMode::Instances => {
             // Toggle sort order (only in instances view)
-            if app.view_mode == ViewMode::Instances {
-                app.sort_order = app.sort_order.toggle();
-                app.reset_selection();
-            }
+            app.toggle_sort_order();
         }
         // Filtering
-        KeyCode::Char('/') => {
+        KeyCode::Char('z') if app.view_mode == ViewMode::Instances => {
+            // Only the selected instance's replicaset
+            app.scope_to_selected(false);
+        }
+        KeyCode::Char('Z') if app.view_mode == ViewMode::Instances => {
+            // Only the selected instance's tier
+            app.scope_to_selected(true);
+        }
+        KeyCode::Char('/') if app.view_mode == ViewMode::Instances => {
             // Start filter mode (only in instances view)
-            if app.view_mode == ViewMode::Instances {
-                app.filter_active = true;
+            app.filter_active = true;
+        }
+        // Search (all views)
+        KeyCode::Char('?') => {
+            app.start_search();
+        }
+        KeyCode::Char('n') => {
+            app.search_next(true);
+        }
+        KeyCode::Char('N') => {
+            app.search_next(false);
+        }
+        KeyCode::Esc => {
+            if !app.search_text.is_empty() {
+                app.clear_search();
+            } else if app.scope.is_some() {
+                app.clear_scope();
+            } else {
+                app.clear_marks();
             }
         }
-        KeyCode::Char('H') => {
+        // Multi-select and bulk actions
+        KeyCode::Char(' ') => {
+            app.toggle_mark_selected();
+        }
+        KeyCode::Char('e') => {
+            app.export_marked();
+        }
+        KeyCode::Char('y') => {
+            app.copy_marked_names();
+        }
+        KeyCode::Char('F') if app.view_mode == ViewMode::Instances => {
+            // Pick a saved filter (only in instances view)
+            app.open_filter_picker();
+        }
+        KeyCode::Char('c') if app.view_mode == ViewMode::Instances => {
+            // Choose columns (only in instances view)
+            app.open_column_picker();
+        }
+        KeyCode::Char('*') => {
+            // Pin/unpin the selected instance
+            app.toggle_pin_selected();
+        }
+        // Screen dump
+        KeyCode::Char('p') => {
+            app.pending_screenshot = Some(ScreenshotFormat::Text);
+        }
+        KeyCode::Char('P') => {
+            app.pending_screenshot = Some(ScreenshotFormat::Ansi);
+        }
+        KeyCode::Char('T') => {
+            // Next theme, re-reading the skins directory
+            app.cycle_theme();
+        }
+        // Snapshots
+        KeyCode::Char('w') => {
+            app.start_snapshot_prompt();
+        }
+        KeyCode::Char('D') => {
+            // Compare a saved snapshot with the live cluster
+            app.open_snapshot_picker();
+        }
+        KeyCode::Char('M') => {
+            // Memory per tier and failure domain
+            app.show_memory_stats = true;
+        }
+        KeyCode::Char('U') => {
+            // One colored cell per instance
+            app.open_heatmap();
+        }
+        KeyCode::Char('O') => {
+            // Instances laid out by failure domain
+            app.show_topology = true;
+        }
+        KeyCode::Char('L') => {
+            // Leader distribution per host and failure domain
+            app.show_leaders = true;
+        }
+        KeyCode::Char('R') if app.worker_stopped => {
+            // Start a new API worker in place of the one that stopped
+            app.reconnect();
+        }
+        KeyCode::Char('R') => {
+            // Request latency and traffic per endpoint
+            app.show_timings = true;
+        }
+        KeyCode::Char('!') => {
+            // Errors of this session, including ones already replaced
+            app.show_error_log = true;
+        }
+        KeyCode::Char('B') => {
+            // Redacted zip of logs, config and recent data for an issue
+            app.save_bug_report();
+        }
+        KeyCode::Char('t') => {
+            // Tables of the cluster schema
+            app.request_tables();
+        }
+        KeyCode::Char('A') => {
+            app.show_alerts = true;
+        }
+        KeyCode::Char('C') => {
+            // Cluster configuration parameters
+            app.request_parameters();
+        }
+        KeyCode::Char('a') => {
+            // Actions for the selected instance
+            app.open_action_menu();
+        }
+        // Bookmarks
+        KeyCode::Char('m') => {
+            app.bookmark_prompt = Some(BookmarkPrompt::Set);
+            app.status_message = Some(i18n::tr("Bookmark: press a letter").to_string());
+        }
+        KeyCode::Char('\'') => {
+            app.bookmark_prompt = Some(BookmarkPrompt::Jump);
+            app.status_message = Some(i18n::tr("Jump to bookmark: press a letter").to_string());
+        }
+        KeyCode::Char('E') => {
+            // Keep unhealthy nodes expanded in the Tiers view
+            app.toggle_expand_problems();
+        }
+        KeyCode::Char('^') => {
+            // Leader of the selected replicaset, then the Raft leader
+            app.jump_to_leader();
+        }
+        KeyCode::Char('H') if app.get_selected_instance().is_some() => {
             // Show health status for selected instance
-            if app.get_selected_instance().is_some() {
-                app.request_health_status();
-            }
+            app.request_health_status();
+        }
+        KeyCode::Char('H') => {
+            // Health of every instance of the selected tier or replicaset
+            app.request_group_health();
         }
         _ => {}
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use picotui::testing::AppBuilder;
+
+    fn key(code: KeyCode) -> Key {
+        (code, KeyModifiers::NONE)
+    }
+
+    /// Action menu, rolling restart of r1, start it, type "r1", confirm
+    const RESTART_KEYS: [KeyCode; 6] = [
+        KeyCode::Char('a'),
+        KeyCode::Char('R'),
+        KeyCode::Enter,
+        KeyCode::Char('r'),
+        KeyCode::Char('1'),
+        KeyCode::Enter,
+    ];
+
+    fn parse(args: &[&str]) -> Result<Args> {
+        parse_args_from(pico_args::Arguments::from_vec(
+            args.iter().map(Into::into).collect(),
+        ))
+    }
+
+    #[test]
+    fn test_ssh_works_with_the_headless_modes() {
+        for headless in [
+            &["--events-stdout"][..],
+            &["--wait-until", "all-online"],
+            &["exporter"],
+        ] {
+            // A subcommand comes first
+            let mut args = headless.to_vec();
+            args.extend(["--ssh", "user@bastion", "--url", "http://10.0.0.1:8080"]);
+            let parsed = parse(&args).unwrap();
+            assert_eq!(
+                parsed.ssh.as_deref(),
+                Some("user@bastion"),
+                "{:?}",
+                headless
+            );
+        }
+    }
+
+    #[test]
+    fn test_k8s_service_works_with_the_headless_modes() {
+        for headless in [
+            &["--events-stdout"][..],
+            &["--wait-until", "converged"],
+            &["exporter"],
+        ] {
+            let mut args = headless.to_vec();
+            args.extend(["--k8s-service", "picodata/api"]);
+            let parsed = parse(&args).unwrap();
+            assert!(parsed.k8s_service.is_some(), "{:?}", headless);
+        }
+    }
+
+    fn ops_app() -> App {
+        let mut app = AppBuilder::new().view(ViewMode::Instances).build();
+        app.ops = true;
+        app
+    }
+
+    #[test]
+    fn test_macros_skip_keys_typed_into_confirmation() {
+        let mut app = ops_app();
+        app.macros.start_recording('a');
+        for code in RESTART_KEYS {
+            dispatch_normal_key(&mut app, key(code));
+        }
+        assert!(app.rolling_restart.as_ref().unwrap().is_running());
+        app.macros.stop_recording();
+        assert_eq!(
+            app.macros.register('a').unwrap(),
+            RESTART_KEYS[..3]
+                .iter()
+                .map(|&code| key(code))
+                .collect::<Vec<_>>(),
+            "up to the key opening the dialog"
+        );
+    }
+
+    #[test]
+    fn test_macro_replay_stops_at_confirmation() {
+        let mut app = ops_app();
+        // As if the name had been recorded too
+        app.macros.start_recording('a');
+        for code in RESTART_KEYS {
+            app.macros.record(key(code));
+        }
+        app.macros.stop_recording();
+
+        handle_normal_mode_key(&mut app, KeyCode::Char('@'), KeyModifiers::NONE);
+        handle_normal_mode_key(&mut app, KeyCode::Char('a'), KeyModifiers::NONE);
+        let dialog = app.confirm.as_ref().expect("the dialog is open");
+        assert!(dialog.input.is_empty());
+        assert_eq!(
+            app.rolling_restart.as_ref().unwrap().outcome,
+            Outcome::Planned,
+            "nothing started"
+        );
+    }
+}
@@ -0,0 +1,664 @@
+//! `picotui mock-server`: a stand-in for a Picodata cluster serving the
+//! endpoints picotui uses, for UI work, demos and load tests without a live
+//! cluster. It serves the cluster the tests use, or a generated one of any
+//! size, and can fail requests, answer slowly and take instances down.
+
+use anyhow::{anyhow, Context, Result};
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Default address for `picotui mock-server`, where picotui connects by default
+pub const DEFAULT_LISTEN: &str = "127.0.0.1:8080";
+
+/// Session token handed out on login
+const TOKEN: &str = "mock-auth-token";
+
+/// Size of a generated cluster
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Topology {
+    pub tiers: usize,
+    /// Replicasets in each tier
+    pub replicasets: usize,
+    /// Instances in each replicaset
+    pub instances: usize,
+}
+
+impl Topology {
+    pub fn instance_count(&self) -> usize {
+        self.tiers * self.replicasets * self.instances
+    }
+}
+
+/// `TIERSxREPLICASETSxINSTANCES`, e.g. `2x10x3`
+impl FromStr for Topology {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let sizes: Vec<usize> = s
+            .split('x')
+            .map(|n| n.trim().parse().ok().filter(|&n| n > 0))
+            .collect::<Option<_>>()
+            .ok_or_else(|| anyhow!("Invalid topology '{}' (expected e.g. 2x10x3)", s))?;
+        match sizes[..] {
+            [tiers, replicasets, instances] => Ok(Self {
+                tiers,
+                replicasets,
+                instances,
+            }),
+            _ => Err(anyhow!(
+                "Invalid topology '{}' (expected TIERSxREPLICASETSxINSTANCES)",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MockOptions {
+    /// Generated cluster; the test fixture cluster when None
+    pub topology: Option<Topology>,
+    /// Username and password to require, if any
+    pub auth: Option<(String, String)>,
+    /// Instances taken Offline at the start
+    pub offline: usize,
+    /// Chance of each instance changing between Online and Offline on every
+    /// tiers request, 0 to 1
+    pub churn: f64,
+    /// Share of requests answered with HTTP 500, 0 to 1
+    pub error_rate: f64,
+    /// Added to every answer
+    pub latency: Duration,
+}
+
+// Fixtures shared with the tests
+
+/// `/api/v1/cluster` of the fixture cluster
+pub fn cluster_info() -> Value {
+    json!({
+        "capacityUsage": 30.5,
+        "clusterName": "test-cluster",
+        "clusterVersion": "1.0.0",
+        "currentInstaceVersion": "25.6.0",
+        "replicasetsCount": 2,
+        "instancesCurrentStateOffline": 1,
+        "instancesCurrentStateOnline": 5,
+        "memory": {
+            "usable": 4294967296_u64,
+            "used": 1288490188_u64
+        },
+        "plugins": ["plugin1"]
+    })
+}
+
+/// `/api/v1/tiers` of the fixture cluster: two tiers, three replicasets and
+/// six instances, one of them Offline
+pub fn tiers() -> Value {
+    json!([
+        {
+            "name": "default",
+            "replicasetCount": 2,
+            "rf": 3,
+            "bucketCount": 3000,
+            "instanceCount": 4,
+            "can_vote": true,
+            "services": [],
+            "memory": {
+                "usable": 2147483648_u64,
+                "used": 644245094_u64
+            },
+            "capacityUsage": 30.0,
+            "replicasets": [
+                {
+                    "name": "r1",
+                    "version": "1",
+                    "state": "Online",
+                    "instanceCount": 2,
+                    "uuid": "uuid-r1",
+                    "capacityUsage": 30.0,
+                    "memory": {
+                        "usable": 1073741824_u64,
+                        "used": 322122547_u64
+                    },
+                    "instances": [
+                        {
+                            "name": "i1",
+                            "httpAddress": "10.0.0.1:8080",
+                            "version": "25.6.0",
+                            "failureDomain": {"datacenter": "dc1", "rack": "r1"},
+                            "isLeader": true,
+                            "currentState": "Online",
+                            "targetState": "Online",
+                            "binaryAddress": "10.0.0.1:3301",
+                            "pgAddress": "10.0.0.1:5432"
+                        },
+                        {
+                            "name": "i2",
+                            "httpAddress": "10.0.0.2:8080",
+                            "version": "25.6.0",
+                            "failureDomain": {"datacenter": "dc1", "rack": "r2"},
+                            "isLeader": false,
+                            "currentState": "Online",
+                            "targetState": "Online",
+                            "binaryAddress": "10.0.0.2:3301",
+                            "pgAddress": "10.0.0.2:5432"
+                        }
+                    ]
+                },
+                {
+                    "name": "r2",
+                    "version": "1",
+                    "state": "Online",
+                    "instanceCount": 2,
+                    "uuid": "uuid-r2",
+                    "capacityUsage": 30.0,
+                    "memory": {
+                        "usable": 1073741824_u64,
+                        "used": 322122547_u64
+                    },
+                    "instances": [
+                        {
+                            "name": "i3",
+                            "httpAddress": "10.0.0.3:8080",
+                            "version": "25.6.0",
+                            "failureDomain": {"datacenter": "dc2", "rack": "r1"},
+                            "isLeader": true,
+                            "currentState": "Offline",
+                            "targetState": "Online",
+                            "binaryAddress": "10.0.0.3:3301",
+                            "pgAddress": "10.0.0.3:5432"
+                        },
+                        {
+                            "name": "i4",
+                            "httpAddress": "10.0.0.4:8080",
+                            "version": "25.6.0",
+                            "failureDomain": {"datacenter": "dc2", "rack": "r2"},
+                            "isLeader": false,
+                            "currentState": "Online",
+                            "targetState": "Online",
+                            "binaryAddress": "10.0.0.4:3301",
+                            "pgAddress": "10.0.0.4:5432"
+                        }
+                    ]
+                }
+            ]
+        },
+        {
+            "name": "storage",
+            "replicasetCount": 1,
+            "rf": 2,
+            "bucketCount": 0,
+            "instanceCount": 2,
+            "can_vote": false,
+            "services": ["storage"],
+            "memory": {
+                "usable": 2147483648_u64,
+                "used": 644245094_u64
+            },
+            "capacityUsage": 30.0,
+            "replicasets": [
+                {
+                    "name": "s1",
+                    "version": "1",
+                    "state": "Online",
+                    "instanceCount": 2,
+                    "uuid": "uuid-s1",
+                    "capacityUsage": 30.0,
+                    "memory": {
+                        "usable": 2147483648_u64,
+                        "used": 644245094_u64
+                    },
+                    "instances": [
+                        {
+                            "name": "s1-i1",
+                            "httpAddress": "10.0.1.1:8080",
+                            "version": "25.6.0",
+                            "failureDomain": {"datacenter": "dc1"},
+                            "isLeader": true,
+                            "currentState": "Online",
+                            "targetState": "Online",
+                            "binaryAddress": "10.0.1.1:3301",
+                            "pgAddress": ""
+                        },
+                        {
+                            "name": "s1-i2",
+                            "httpAddress": "10.0.1.2:8080",
+                            "version": "25.6.0",
+                            "failureDomain": {"datacenter": "dc2"},
+                            "isLeader": false,
+                            "currentState": "Online",
+                            "targetState": "Online",
+                            "binaryAddress": "10.0.1.2:3301",
+                            "pgAddress": ""
+                        }
+                    ]
+                }
+            ]
+        }
+    ])
+}
+
+/// `/api/v1/config` with auth disabled
+pub fn config_no_auth() -> Value {
+    json!({
+        "isAuthEnabled": false
+    })
+}
+
+/// `/api/v1/config` with auth enabled
+pub fn config_with_auth() -> Value {
+    json!({
+        "isAuthEnabled": true
+    })
+}
+
+/// `/api/v1/session` answer to a successful login
+pub fn login_success() -> Value {
+    json!({
+        "auth": "test-auth-token-12345",
+        "refresh": "test-refresh-token-67890"
+    })
+}
+
+/// Tiers of a generated cluster, with every instance's HTTP address set to
+/// `http_address` so `--discover` finds no addresses that don't answer
+pub fn generate(topology: Topology, http_address: &str) -> Value {
+    let fixture = tiers();
+    let template = &fixture[0]["replicasets"][0]["instances"][0];
+    let mut number = 0;
+    let tiers: Vec<Value> = (0..topology.tiers)
+        .map(|t| {
+            let tier = if t == 0 {
+                "default".to_string()
+            } else {
+                format!("tier{}", t + 1)
+            };
+            let replicasets: Vec<Value> = (0..topology.replicasets)
+                .map(|r| {
+                    let name = format!("{}_{}", tier, r + 1);
+                    let instances: Vec<Value> = (0..topology.instances)
+                        .map(|i| {
+                            number += 1;
+                            let mut instance = template.clone();
+                            instance["name"] = json!(format!("{}_{}", name, i + 1));
+                            instance["httpAddress"] = json!(http_address);
+                            instance["binaryAddress"] =
+                                json!(format!("127.0.0.1:{}", 3300 + number));
+                            instance["pgAddress"] = json!(format!("127.0.0.1:{}", 5431 + number));
+                            instance["isLeader"] = json!(i == 0);
+                            instance["failureDomain"] = json!({
+                                "datacenter": format!("dc{}", i % 3 + 1),
+                                "rack": format!("r{}", r % 4 + 1),
+                            });
+                            instance
+                        })
+                        .collect();
+                    let usable = GIB * topology.instances as u64;
+                    // Spread usage over 10-90% so the gauges differ
+                    let used = usable / 100 * (10 + (t * 37 + r * 13) as u64 % 81);
+                    json!({
+                        "name": name,
+                        "version": "1",
+                        "state": "Online",
+                        "instanceCount": topology.instances,
+                        "uuid": format!("uuid-{}", name),
+                        "capacityUsage": percent(used, usable),
+                        "memory": {"usable": usable, "used": used},
+                        "instances": instances,
+                    })
+                })
+                .collect();
+            let usable: u64 = replicasets.iter().map(|r| memory(r, "usable")).sum();
+            let used: u64 = replicasets.iter().map(|r| memory(r, "used")).sum();
+            json!({
+                "name": tier,
+                "replicasetCount": topology.replicasets,
+                "rf": topology.instances,
+                "bucketCount": if t == 0 { 3000 } else { 0 },
+                "instanceCount": topology.replicasets * topology.instances,
+                "can_vote": t == 0,
+                "services": [],
+                "memory": {"usable": usable, "used": used},
+                "capacityUsage": percent(used, usable),
+                "replicasets": replicasets,
+            })
+        })
+        .collect();
+    Value::Array(tiers)
+}
+
+const GIB: u64 = 1 << 30;
+
+fn memory(node: &Value, field: &str) -> u64 {
+    node["memory"][field].as_u64().unwrap_or(0)
+}
+
+fn percent(used: u64, usable: u64) -> f64 {
+    (used as f64 * 1000.0 / usable.max(1) as f64).round() / 10.0
+}
+
+fn instances_mut(tiers: &mut Value) -> impl Iterator<Item = &mut Value> {
+    tiers
+        .as_array_mut()
+        .into_iter()
+        .flatten()
+        .flat_map(|tier| tier["replicasets"].as_array_mut().into_iter().flatten())
+        .flat_map(|rs| rs["instances"].as_array_mut().into_iter().flatten())
+}
+
+/// The cluster the mock server answers for
+pub struct MockCluster {
+    options: MockOptions,
+    tiers: Value,
+    /// xorshift state for failures and churn
+    rng: u64,
+}
+
+impl MockCluster {
+    /// A cluster answering for `http_address`, with failures drawn from `seed`
+    pub fn new(options: MockOptions, http_address: &str, seed: u64) -> Self {
+        let tiers = match options.topology {
+            Some(topology) => generate(topology, http_address),
+            None => tiers(),
+        };
+        let mut cluster = Self {
+            options,
+            tiers,
+            rng: seed | 1,
+        };
+        let mut online: Vec<usize> = (0..cluster.instance_count())
+            .filter(|&i| cluster.state(i) == "Online")
+            .collect();
+        for _ in 0..cluster.options.offline.min(online.len()) {
+            let pick = online.remove(cluster.next_random() as usize % online.len());
+            cluster.set_state(pick, "Offline");
+        }
+        cluster
+    }
+
+    pub fn instance_count(&self) -> usize {
+        let mut tiers = self.tiers.clone();
+        instances_mut(&mut tiers).count()
+    }
+
+    fn state(&mut self, index: usize) -> String {
+        instances_mut(&mut self.tiers)
+            .nth(index)
+            .and_then(|inst| inst["currentState"].as_str().map(str::to_string))
+            .unwrap_or_default()
+    }
+
+    fn set_state(&mut self, index: usize, state: &str) {
+        if let Some(instance) = instances_mut(&mut self.tiers).nth(index) {
+            instance["currentState"] = json!(state);
+        }
+    }
+
+    fn next_random(&mut self) -> u64 {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+        self.rng
+    }
+
+    /// True with probability `p`
+    fn chance(&mut self, p: f64) -> bool {
+        p > 0.0 && (self.next_random() % 1_000_000) as f64 / 1_000_000.0 < p
+    }
+
+    /// Status and JSON body of the answer to a request
+    pub fn handle(
+        &mut self,
+        method: &str,
+        path: &str,
+        authorization: Option<&str>,
+        body: &[u8],
+    ) -> (u16, Value) {
+        if self.chance(self.options.error_rate) {
+            return error(500, "Internal", "failure injected by the mock server");
+        }
+        let authorized = self.options.auth.is_none()
+            || authorization.and_then(|value| value.strip_prefix("Bearer ")) == Some(TOKEN);
+        match (method, path) {
+            ("GET", "/api/v1/config") => (
+                200,
+                if self.options.auth.is_some() {
+                    config_with_auth()
+                } else {
+                    config_no_auth()
+                },
+            ),
+            ("POST", "/api/v1/session") => {
+                let request: Value = serde_json::from_slice(body).unwrap_or_default();
+                let accepted = self.options.auth.as_ref().is_none_or(|(user, password)| {
+                    request["username"] == user.as_str() && request["password"] == password.as_str()
+                });
+                if accepted {
+                    let mut tokens = login_success();
+                    tokens["auth"] = json!(TOKEN);
+                    (200, tokens)
+                } else {
+                    error(401, "Unauthorized", "wrong username or password")
+                }
+            }
+            ("GET", "/api/v1/cluster" | "/api/v1/tiers") if !authorized => {
+                error(401, "Unauthorized", "session required")
+            }
+            ("GET", "/api/v1/cluster") => (200, self.cluster_info()),
+            ("GET", "/api/v1/tiers") => {
+                self.churn();
+                (200, self.tiers.clone())
+            }
+            _ => error(
+                404,
+                "NotFound",
+                &format!("{} is not served by the mock", path),
+            ),
+        }
+    }
+
+    /// Flip instances between Online and Offline
+    fn churn(&mut self) {
+        for index in 0..self.instance_count() {
+            if self.chance(self.options.churn) {
+                let state = match self.state(index).as_str() {
+                    "Online" => "Offline",
+                    _ => "Online",
+                };
+                self.set_state(index, state);
+            }
+        }
+    }
+
+    /// The fixture cluster summary with the counts of the current tiers
+    fn cluster_info(&mut self) -> Value {
+        let mut info = cluster_info();
+        let (mut online, mut offline) = (0, 0);
+        for instance in instances_mut(&mut self.tiers) {
+            match instance["currentState"].as_str() {
+                Some("Online") => online += 1,
+                _ => offline += 1,
+            }
+        }
+        let replicasets: usize = self
+            .tiers
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|tier| tier["replicasets"].as_array().map_or(0, Vec::len))
+            .sum();
+        info["instancesCurrentStateOnline"] = json!(online);
+        info["instancesCurrentStateOffline"] = json!(offline);
+        info["replicasetsCount"] = json!(replicasets);
+        let tiers = self.tiers.as_array().into_iter().flatten();
+        let (usable, used) = tiers.fold((0, 0), |(usable, used), tier| {
+            (usable + memory(tier, "usable"), used + memory(tier, "used"))
+        });
+        info["memory"] = json!({"usable": usable, "used": used});
+        info["capacityUsage"] = json!(percent(used, usable));
+        info["clusterName"] = json!("mock-cluster");
+        info
+    }
+}
+
+/// An answer with an error body shaped like Picodata's
+fn error(status: u16, kind: &str, message: &str) -> (u16, Value) {
+    (status, json!({"error": kind, "errorMessage": message}))
+}
+
+/// Serve a mock cluster on `listen` until the process is killed
+pub fn run(listen: &str, options: MockOptions) -> Result<()> {
+    let listener =
+        TcpListener::bind(listen).with_context(|| format!("Could not listen on {}", listen))?;
+    let address = listener.local_addr()?.to_string();
+    let auth = options.auth.clone();
+    let seed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos() as u64;
+    let cluster = MockCluster::new(options, &address, seed);
+    eprintln!(
+        "Serving a mock cluster of {} instances on http://{}",
+        cluster.instance_count(),
+        address
+    );
+    if let Some((user, password)) = auth {
+        eprintln!("Log in as {} with password {}", user, password);
+    }
+    serve(listener, cluster)
+}
+
+/// Answer requests on `listener` for `cluster`, each connection on its own
+/// thread so slow answers don't hold up the others
+pub fn serve(listener: TcpListener, cluster: MockCluster) -> Result<()> {
+    let latency = cluster.options.latency;
+    let cluster = Arc::new(Mutex::new(cluster));
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                let cluster = Arc::clone(&cluster);
+                std::thread::spawn(move || {
+                    if let Err(e) = respond(stream, &cluster, latency) {
+                        eprintln!("Request failed: {}", e);
+                    }
+                });
+            }
+            Err(e) => eprintln!("Connection failed: {}", e),
+        }
+    }
+    Ok(())
+}
+
+/// Answer a single HTTP request
+fn respond(mut stream: TcpStream, cluster: &Mutex<MockCluster>, latency: Duration) -> Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut reader = BufReader::new(&stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut authorization = None;
+    let mut content_length = 0;
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        if let Some((name, value)) = header.split_once(':') {
+            let value = value.trim();
+            match name.to_ascii_lowercase().as_str() {
+                "authorization" => authorization = Some(value.to_string()),
+                "content-length" => content_length = value.parse().unwrap_or(0),
+                _ => {}
+            }
+        }
+        header.clear();
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+
+    let mut words = request_line.split_whitespace();
+    let method = words.next().unwrap_or("");
+    let path = words.next().unwrap_or("");
+    let path = path.split('?').next().unwrap_or(path);
+    std::thread::sleep(latency);
+    let (status, body) =
+        cluster
+            .lock()
+            .unwrap()
+            .handle(method, path, authorization.as_deref(), &body);
+    let body = body.to_string();
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        match status {
+            200 => "OK",
+            401 => "Unauthorized",
+            404 => "Not Found",
+            _ => "Internal Server Error",
+        },
+        body.len(),
+        body
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_topology() {
+        assert_eq!(
+            "2x10x3".parse::<Topology>().unwrap(),
+            Topology {
+                tiers: 2,
+                replicasets: 10,
+                instances: 3
+            }
+        );
+        assert!("2x10".parse::<Topology>().is_err());
+        assert!("2x0x3".parse::<Topology>().is_err());
+    }
+
+    #[test]
+    fn test_generated_cluster_counts() {
+        let options = MockOptions {
+            topology: Some("3x4x2".parse().unwrap()),
+            offline: 5,
+            ..MockOptions::default()
+        };
+        let mut cluster = MockCluster::new(options, "127.0.0.1:8080", 42);
+        assert_eq!(cluster.instance_count(), 24);
+        let (status, info) = cluster.handle("GET", "/api/v1/cluster", None, b"");
+        assert_eq!(status, 200);
+        assert_eq!(info["instancesCurrentStateOnline"], 19);
+        assert_eq!(info["instancesCurrentStateOffline"], 5);
+        assert_eq!(info["replicasetsCount"], 12);
+    }
+
+    #[test]
+    fn test_auth_and_injected_failures() {
+        let options = MockOptions {
+            auth: Some(("admin".to_string(), "secret".to_string())),
+            ..MockOptions::default()
+        };
+        let mut cluster = MockCluster::new(options, "127.0.0.1:8080", 42);
+        assert_eq!(cluster.handle("GET", "/api/v1/tiers", None, b"").0, 401);
+        let wrong = br#"{"username": "admin", "password": "nope"}"#;
+        assert_eq!(
+            cluster.handle("POST", "/api/v1/session", None, wrong).0,
+            401
+        );
+        let right = br#"{"username": "admin", "password": "secret"}"#;
+        let (status, tokens) = cluster.handle("POST", "/api/v1/session", None, right);
+        assert_eq!(status, 200);
+        let bearer = format!("Bearer {}", tokens["auth"].as_str().unwrap());
+        assert_eq!(
+            cluster.handle("GET", "/api/v1/tiers", Some(&bearer), b"").0,
+            200
+        );
+
+        cluster.options.error_rate = 1.0;
+        assert_eq!(cluster.handle("GET", "/api/v1/config", None, b"").0, 500);
+    }
+}
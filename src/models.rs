@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ClusterInfo {
     pub capacity_usage: f64,
@@ -16,7 +16,7 @@ pub struct ClusterInfo {
     pub plugins: Vec<String>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TierInfo {
     pub replicasets: Vec<ReplicasetInfo>,
@@ -33,7 +33,7 @@ pub struct TierInfo {
     pub capacity_usage: f64,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ReplicasetInfo {
     #[allow(dead_code)]
@@ -45,6 +45,9 @@ pub struct ReplicasetInfo {
     #[serde(default)]
     pub replicaset_state: ReplicasetState,
     pub instance_count: usize,
+    /// Number of vshard buckets stored on the replicaset (newer Picodata versions).
+    #[serde(default)]
+    pub bucket_count: Option<u64>,
     #[allow(dead_code)]
     pub uuid: String,
     pub instances: Vec<InstanceInfo>,
@@ -53,12 +56,12 @@ pub struct ReplicasetInfo {
     pub name: String,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct InstanceInfo {
     pub http_address: String,
     pub version: String,
-    pub failure_domain: HashMap<String, String>,
+    pub failure_domain: BTreeMap<String, String>,
     /// Whether this instance is the vshard leader of its replicaset.
     pub is_leader: bool,
     /// Whether this instance is a Raft voter (Picodata 26.2+).
@@ -74,9 +77,21 @@ pub struct InstanceInfo {
     pub name: String,
     pub binary_address: String,
     pub pg_address: String,
+    /// Seconds since the instance process started (newer Picodata versions).
+    #[serde(default)]
+    pub uptime_seconds: Option<u64>,
+    /// Process ID of the instance (newer Picodata versions).
+    #[serde(default)]
+    pub pid: Option<u32>,
+    /// Raft node ID (newer Picodata versions).
+    #[serde(default)]
+    pub raft_id: Option<u64>,
+    /// Replication lag behind the replicaset leader in seconds (newer Picodata versions).
+    #[serde(default)]
+    pub replication_lag: Option<f64>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum StateVariant {
     Online,
     Offline,
@@ -86,7 +101,7 @@ pub enum StateVariant {
 /// Replicaset state from _pico_replicaset system table.
 /// Note: This is different from the `state` field in ReplicasetInfo,
 /// which represents the leader instance's state (kept for backward compatibility).
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
 #[serde(rename_all = "kebab-case")]
 pub enum ReplicasetState {
     #[default]
@@ -113,7 +128,7 @@ impl std::fmt::Display for StateVariant {
     }
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MemoryInfo {
     pub usable: u64,
     pub used: u64,
@@ -122,7 +137,6 @@ pub struct MemoryInfo {
 #[derive(Debug, Clone, Deserialize)]
 pub struct TokenResponse {
     pub auth: String,
-    #[allow(dead_code)]
     pub refresh: String,
 }
 
@@ -222,3 +236,34 @@ pub struct ClusterHealthInfo {
     pub uuid: String,
     pub version: String,
 }
+
+/// A table (space) of the cluster schema
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableInfo {
+    pub name: String,
+    pub distribution: TableDistribution,
+    /// Storage engine: memtx or vinyl
+    pub engine: String,
+    /// Approximate row count across the cluster, when it could be measured
+    pub rows: Option<u64>,
+    /// Approximate data size in bytes across the cluster (one copy of
+    /// each row), when it could be measured
+    pub size: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TableDistribution {
+    /// A full copy on every instance
+    Global,
+    /// Split into buckets across the replicasets of one tier
+    Sharded { tier: String },
+}
+
+/// A cluster-wide configuration parameter (`ALTER SYSTEM`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigParameter {
+    pub name: String,
+    /// Tier the value applies to, empty for the whole cluster
+    pub scope: String,
+    pub value: String,
+}
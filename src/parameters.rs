@@ -0,0 +1,78 @@
+use crate::models::ConfigParameter;
+
+/// Defaults of the `ALTER SYSTEM` parameters, as documented for Picodata
+/// 25.x. Parameters missing here are shown without a default.
+const DEFAULTS: &[(&str, &str)] = &[
+    ("auth_login_attempt_max", "4"),
+    ("auth_password_enforce_digits", "true"),
+    ("auth_password_enforce_lowercase", "true"),
+    ("auth_password_enforce_specialchars", "false"),
+    ("auth_password_enforce_uppercase", "true"),
+    ("auth_password_length_min", "8"),
+    ("governor_auto_offline_timeout", "30"),
+    ("governor_common_rpc_timeout", "3"),
+    ("governor_plugin_rpc_timeout", "10"),
+    ("governor_raft_op_timeout", "3"),
+    ("iproto_net_msg_max", "768"),
+    ("memtx_checkpoint_count", "2"),
+    ("memtx_checkpoint_interval", "3600"),
+    ("pg_portal_max", "1024"),
+    ("pg_statement_max", "1024"),
+    ("raft_snapshot_chunk_size_max", "16777216"),
+    ("raft_snapshot_read_view_close_timeout", "86400"),
+    ("raft_wal_count_max", "64"),
+    ("raft_wal_size_max", "67108864"),
+    ("sql_motion_row_max", "5000"),
+    ("sql_storage_cache_count_max", "50"),
+    ("sql_vdbe_opcode_max", "45000"),
+];
+
+/// Documented default of the parameter `name`
+pub fn default_value(name: &str) -> Option<&'static str> {
+    DEFAULTS
+        .iter()
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| *value)
+}
+
+/// Whether `value` differs from the default of `name`. Numbers compare by
+/// value, so `30.0` is the default `30`. Unknown parameters never differ.
+pub fn is_changed(name: &str, value: &str) -> bool {
+    let Some(default) = default_value(name) else {
+        return false;
+    };
+    match (value.parse::<f64>(), default.parse::<f64>()) {
+        (Ok(value), Ok(default)) => value != default,
+        _ => value != default,
+    }
+}
+
+/// Parameters whose name, scope or value contains `search`, ignoring case
+pub fn matching<'a>(parameters: &'a [ConfigParameter], search: &str) -> Vec<&'a ConfigParameter> {
+    let search = search.to_lowercase();
+    parameters
+        .iter()
+        .filter(|param| {
+            [&param.name, &param.scope, &param.value]
+                .iter()
+                .any(|field| field.to_lowercase().contains(&search))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parameters_differ_from_defaults_by_value() {
+        assert!(!is_changed("memtx_checkpoint_interval", "3600"));
+        assert!(
+            !is_changed("governor_auto_offline_timeout", "30.0"),
+            "numbers compare by value"
+        );
+        assert!(is_changed("memtx_checkpoint_interval", "600"));
+        assert!(is_changed("auth_password_enforce_digits", "false"));
+        assert!(!is_changed("some_future_parameter", "1"));
+    }
+}
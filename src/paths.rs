@@ -0,0 +1,48 @@
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+/// Set by `--data-dir`: everything except the config lives under it
+static DATA_DIR_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+
+/// Keep saved sessions, logs, snapshots and cache under `dir` instead of
+/// the platform directories. Only the first call has an effect.
+pub fn set_data_dir(dir: PathBuf) {
+    let _ = DATA_DIR_OVERRIDE.set(dir);
+}
+
+/// Files picotui keeps between runs but users don't edit: saved sessions
+/// and the debug log. `$XDG_STATE_HOME/picotui` (`~/.local/state/picotui`)
+/// on Linux, the local data directory elsewhere.
+pub fn state_dir() -> Option<PathBuf> {
+    if let Some(dir) = DATA_DIR_OVERRIDE.get() {
+        return Some(dir.clone());
+    }
+    dirs::state_dir()
+        .or_else(dirs::data_local_dir)
+        .map(|p| p.join("picotui"))
+}
+
+/// Files the user creates on purpose, such as snapshots
+pub fn data_dir() -> Option<PathBuf> {
+    if let Some(dir) = DATA_DIR_OVERRIDE.get() {
+        return Some(dir.clone());
+    }
+    dirs::data_dir().map(|p| p.join("picotui"))
+}
+
+/// Files that can be fetched again and may be deleted at any time.
+/// `$XDG_CACHE_HOME/picotui` (`~/.cache/picotui`) on Linux.
+pub fn cache_dir() -> Option<PathBuf> {
+    if let Some(dir) = DATA_DIR_OVERRIDE.get() {
+        return Some(dir.join("cache"));
+    }
+    dirs::cache_dir().map(|p| p.join("picotui"))
+}
+
+/// Where `--debug` writes its log; the working directory if no state
+/// directory can be determined
+pub fn log_file() -> PathBuf {
+    state_dir()
+        .map(|dir| dir.join("picotui.log"))
+        .unwrap_or_else(|| PathBuf::from("picotui.log"))
+}
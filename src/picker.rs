@@ -0,0 +1,120 @@
+//! Startup cluster picker: when several clusters have saved sessions and
+//! no `--url` is given, the user chooses where to connect from a list
+//! instead of retyping the URL. Each entry gets a quick probe in the
+//! background so dead clusters stand out before connecting.
+
+use crate::client::Client;
+use crate::tokens::{self, TokenEntry};
+use std::sync::mpsc::{channel, Receiver};
+use std::thread;
+
+/// What a quick look at a cluster showed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Probe {
+    Probing,
+    /// Answers, and the saved session (if needed) is still accepted
+    Ready,
+    /// Answers, but a login is needed
+    AuthRequired,
+    Down(String),
+}
+
+/// Probe the cluster at `url` with the saved session token `auth`
+pub fn probe(url: &str, auth: Option<&str>) -> Probe {
+    let mut client = Client::new(url);
+    match client.get_config().result {
+        Err(e) => Probe::Down(e),
+        Ok(config) if !config.is_auth_enabled => Probe::Ready,
+        Ok(_) => {
+            let Some(auth) = auth else {
+                return Probe::AuthRequired;
+            };
+            client.set_token(auth);
+            match client.get_cluster_info().result {
+                Ok(_) => Probe::Ready,
+                Err(_) => Probe::AuthRequired,
+            }
+        }
+    }
+}
+
+pub struct PickerEntry {
+    pub url: String,
+    /// User of the saved session
+    pub user: Option<String>,
+    pub probe: Probe,
+}
+
+/// State of the picker screen
+pub struct ClusterPicker {
+    pub entries: Vec<PickerEntry>,
+    pub selected: usize,
+    results: Receiver<(usize, Probe)>,
+}
+
+impl ClusterPicker {
+    /// Picker over the saved sessions, or `None` unless there are at least
+    /// two HTTP clusters to choose from
+    pub fn from_saved_sessions() -> Option<Self> {
+        let sessions: Vec<_> = tokens::list_tokens()
+            .into_iter()
+            .filter(|(url, _)| url.starts_with("http://") || url.starts_with("https://"))
+            .collect();
+        (sessions.len() >= 2).then(|| Self::new(sessions))
+    }
+
+    /// Start probing every session's cluster
+    pub fn new(sessions: Vec<(String, TokenEntry)>) -> Self {
+        let (tx, rx) = channel();
+        let entries = sessions
+            .into_iter()
+            .enumerate()
+            .map(|(index, (url, entry))| {
+                let tx = tx.clone();
+                let probe_url = url.clone();
+                let auth = entry.auth.clone();
+                thread::spawn(move || {
+                    let _ = tx.send((index, probe(&probe_url, Some(&auth))));
+                });
+                PickerEntry {
+                    url,
+                    user: tokens::username_from_jwt(&entry.auth),
+                    probe: Probe::Probing,
+                }
+            })
+            .collect();
+        Self {
+            entries,
+            selected: 0,
+            results: rx,
+        }
+    }
+
+    /// Apply finished probes. True if any arrived.
+    pub fn poll(&mut self) -> bool {
+        let mut changed = false;
+        while let Ok((index, probe)) = self.results.try_recv() {
+            if let Some(entry) = self.entries.get_mut(index) {
+                entry.probe = probe;
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    pub fn select_next(&mut self) {
+        if self.selected + 1 < self.entries.len() {
+            self.selected += 1;
+        }
+    }
+
+    pub fn select_previous(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn selected_url(&self) -> Option<&str> {
+        self.entries
+            .get(self.selected)
+            .map(|entry| entry.url.as_str())
+    }
+}
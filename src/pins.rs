@@ -0,0 +1,69 @@
+use anyhow::{Context, Result};
+use std::collections::{BTreeSet, HashMap};
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+
+/// Pinned instance names for each cluster, keyed by normalized URL
+type PinsFile = HashMap<String, BTreeSet<String>>;
+
+/// Default location of the pins file
+pub fn pins_file_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|p| p.join("picotui/pins.json"))
+}
+
+fn read_pins_file(path: &Path) -> PinsFile {
+    File::open(path)
+        .ok()
+        .and_then(|file| serde_json::from_reader(file).ok())
+        .unwrap_or_default()
+}
+
+/// Load the instances pinned for the cluster at `url`
+pub fn load_pins(path: &Path, url: &str) -> BTreeSet<String> {
+    read_pins_file(path)
+        .remove(url.trim_end_matches('/'))
+        .unwrap_or_default()
+}
+
+/// Save the instances pinned for the cluster at `url`, keeping other clusters' pins
+pub fn save_pins(path: &Path, url: &str, pins: &BTreeSet<String>) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut all = read_pins_file(path);
+    let normalized_url = url.trim_end_matches('/').to_string();
+    if pins.is_empty() {
+        all.remove(&normalized_url);
+    } else {
+        all.insert(normalized_url, pins.clone());
+    }
+
+    let file = File::create(path).with_context(|| format!("Could not write {}", path.display()))?;
+    serde_json::to_writer_pretty(file, &all)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pins_are_kept_per_cluster() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("pins.json");
+        assert!(load_pins(&path, "http://a:8080").is_empty());
+
+        let a = BTreeSet::from(["i1".to_string(), "i3".to_string()]);
+        let b = BTreeSet::from(["s1-i2".to_string()]);
+        save_pins(&path, "http://a:8080/", &a).unwrap();
+        save_pins(&path, "http://b:8080", &b).unwrap();
+
+        assert_eq!(load_pins(&path, "http://a:8080"), a);
+        assert_eq!(load_pins(&path, "http://b:8080"), b);
+
+        save_pins(&path, "http://a:8080", &BTreeSet::new()).unwrap();
+        assert!(load_pins(&path, "http://a:8080").is_empty());
+        assert_eq!(load_pins(&path, "http://b:8080"), b);
+    }
+}
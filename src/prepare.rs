@@ -0,0 +1,103 @@
+use crate::api::{ApiMessage, ApiResponse};
+use crate::health::{self, ClusterHealth};
+use crate::models::TierInfo;
+use std::collections::{HashMap, HashSet};
+use std::sync::mpsc::{Receiver, Sender};
+
+/// Per-replicaset bucket counts of a tiers snapshot, by tier and then by
+/// replicaset, so lookups don't have to build an owned key. Only
+/// replicasets that report a count are included.
+pub type BucketCounts = HashMap<String, HashMap<String, u64>>;
+
+/// A tiers snapshot together with everything derived from it, so the UI
+/// thread only has to swap it in
+#[derive(Debug, Clone)]
+pub struct PreparedTiers {
+    pub tiers: Vec<TierInfo>,
+    pub health: ClusterHealth,
+    /// Tiers whose buckets are moving between replicasets
+    pub rebalancing: HashSet<String>,
+    /// Kept to compare the next snapshot against
+    pub bucket_counts: BucketCounts,
+}
+
+pub fn bucket_counts(tiers: &[TierInfo]) -> BucketCounts {
+    tiers
+        .iter()
+        .map(|t| {
+            let counts = t
+                .replicasets
+                .iter()
+                .filter_map(|rs| Some((rs.name.clone(), rs.bucket_count?)))
+                .collect::<HashMap<_, _>>();
+            (t.name.clone(), counts)
+        })
+        .filter(|(_, counts)| !counts.is_empty())
+        .collect()
+}
+
+/// Tiers where buckets are in flight: replicaset bucket counts changed since
+/// the previous snapshot, or they don't add up to the tier's total.
+/// Servers that don't report per-replicaset counts never show as rebalancing.
+pub fn rebalancing_tiers(previous: &BucketCounts, new: &[TierInfo]) -> HashSet<String> {
+    new.iter()
+        .filter(|tier| {
+            let counts: Vec<u64> = tier
+                .replicasets
+                .iter()
+                .filter_map(|rs| rs.bucket_count)
+                .collect();
+            if counts.is_empty() {
+                return false;
+            }
+            if counts.iter().sum::<u64>() != tier.bucket_count {
+                return true;
+            }
+
+            let Some(previous) = previous.get(&tier.name) else {
+                return false;
+            };
+            tier.replicasets.iter().any(|rs| {
+                previous
+                    .get(&rs.name)
+                    .is_some_and(|&count| Some(count) != rs.bucket_count)
+            })
+        })
+        .map(|tier| tier.name.clone())
+        .collect()
+}
+
+/// Derive everything the UI needs from a new snapshot
+pub fn prepare(previous: &BucketCounts, tiers: Vec<TierInfo>) -> PreparedTiers {
+    PreparedTiers {
+        health: health::assess(&tiers),
+        rebalancing: rebalancing_tiers(previous, &tiers),
+        bucket_counts: bucket_counts(&tiers),
+        tiers,
+    }
+}
+
+/// Spawn a thread between the API worker and the UI that turns parsed
+/// tiers into `PreparedTiers`. Other responses pass through unchanged.
+pub fn spawn_preparer(input: Receiver<ApiMessage>, output: Sender<ApiMessage>) {
+    std::thread::spawn(move || {
+        let mut previous = BucketCounts::new();
+        for message in input {
+            let response = match message.response {
+                ApiResponse::Tiers(Ok(tiers)) => {
+                    let prepared = prepare(&previous, tiers);
+                    previous = prepared.bucket_counts.clone();
+                    ApiResponse::PreparedTiers(Box::new(prepared))
+                }
+                other => other,
+            };
+            let message = ApiMessage {
+                response,
+                meta: message.meta,
+            };
+            if output.send(message).is_err() {
+                break;
+            }
+        }
+    });
+}
@@ -0,0 +1,91 @@
+//! Files only the current user may read, such as saved sessions.
+//!
+//! On Unix the directory is created `0700` and files `0600`. Windows has no
+//! mode bits: files go under the per-user profile (`%LOCALAPPDATA%`), whose
+//! ACLs already deny other non-administrator users, and new files inherit
+//! them.
+
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::path::Path;
+
+#[cfg(unix)]
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+
+/// Create `dir` and its parents, and make `dir` private to the user
+pub fn create_private_dir(dir: &Path) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    #[cfg(unix)]
+    fs::set_permissions(dir, fs::Permissions::from_mode(0o700))?;
+    Ok(())
+}
+
+/// Create or truncate `path` for writing, readable by the user only. The
+/// mode only applies to a new file, so one left over (say, from a crash)
+/// is made private too; that fails if another user owns it.
+pub fn create_private_file(path: &Path) -> io::Result<File> {
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    let file = options.open(path)?;
+    #[cfg(unix)]
+    file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    Ok(file)
+}
+
+/// Open `path` for appending, creating it readable by the user only
+pub fn append_private_file(path: &Path) -> io::Result<File> {
+    let mut options = OpenOptions::new();
+    options.append(true).create(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    options.open(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_private_file_is_writable() {
+        let dir = tempfile::tempdir().unwrap();
+        let sub = dir.path().join("a/b");
+        create_private_dir(&sub).unwrap();
+
+        let path = sub.join("secret.json");
+        create_private_file(&path)
+            .unwrap()
+            .write_all(b"old contents")
+            .unwrap();
+        create_private_file(&path)
+            .unwrap()
+            .write_all(b"new")
+            .unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+
+        #[cfg(unix)]
+        {
+            let mode = |p: &Path| fs::metadata(p).unwrap().permissions().mode() & 0o777;
+            assert_eq!(mode(&sub), 0o700);
+            assert_eq!(mode(&path), 0o600);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_leftover_file_is_made_private() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tokens.json.tmp");
+        fs::write(&path, "stale").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+
+        create_private_file(&path).unwrap();
+        assert_eq!(
+            fs::metadata(&path).unwrap().permissions().mode() & 0o777,
+            0o600
+        );
+        assert_eq!(fs::read_to_string(&path).unwrap(), "");
+    }
+}
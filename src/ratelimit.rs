@@ -0,0 +1,154 @@
+//! Cap on the rate of requests the API worker sends (`--max-rate`), so a
+//! short refresh interval, several open views and health probes can't
+//! flood the HTTP endpoint of a cluster that is already struggling.
+
+use anyhow::{anyhow, Result};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Requests per second unless configured otherwise
+pub const DEFAULT_MAX_RATE: f64 = 20.0;
+
+static MAX_RATE: AtomicU64 = AtomicU64::new(DEFAULT_MAX_RATE.to_bits());
+
+/// Set the rate API workers started from now on keep to, in requests per
+/// second; 0 lifts the limit
+pub fn set_max_rate(rate: f64) {
+    MAX_RATE.store(rate.to_bits(), Ordering::Relaxed);
+}
+
+pub fn max_rate() -> f64 {
+    f64::from_bits(MAX_RATE.load(Ordering::Relaxed))
+}
+
+/// Token bucket: `rate` requests per second on average, with bursts of up
+/// to a second's worth after a quiet spell
+#[derive(Debug, Clone)]
+pub struct TokenBucket {
+    rate: f64,
+    burst: f64,
+    /// Negative while requests are waiting for tokens yet to come
+    tokens: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    /// A full bucket; a `rate` of 0 lets every request through at once
+    pub fn new(rate: f64) -> Self {
+        let burst = rate.max(1.0);
+        Self {
+            rate,
+            burst,
+            tokens: burst,
+            updated: Instant::now(),
+        }
+    }
+
+    /// Take a token for a request made at `now`, and return how long the
+    /// request has to wait for it
+    pub fn reserve(&mut self, now: Instant) -> Duration {
+        if self.rate <= 0.0 {
+            return Duration::ZERO;
+        }
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.burst);
+        self.updated = self.updated.max(now);
+        self.tokens -= 1.0;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.rate)
+        }
+    }
+}
+
+/// A token bucket shared by the threads of one worker, so fan-out
+/// requests count against the same limit
+#[derive(Debug, Clone)]
+pub struct RateLimiter(Arc<Mutex<TokenBucket>>);
+
+impl RateLimiter {
+    pub fn new(rate: f64) -> Self {
+        Self(Arc::new(Mutex::new(TokenBucket::new(rate))))
+    }
+
+    /// Block until a request may go. Waiting doesn't hold up other threads
+    /// taking the tokens after this one.
+    pub fn acquire(&self) {
+        let wait = self
+            .0
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .reserve(Instant::now());
+        if !wait.is_zero() {
+            thread::sleep(wait);
+        }
+    }
+}
+
+/// `--max-rate`: requests per second, 0 for no limit
+pub fn parse_rate(s: &str) -> Result<f64> {
+    s.trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|rate| rate.is_finite() && *rate >= 0.0)
+        .ok_or_else(|| anyhow!("Invalid request rate '{}' (expected e.g. 20 or 0.5)", s))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_burst_then_steady_rate() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(10.0);
+        bucket.updated = start;
+        for _ in 0..10 {
+            assert_eq!(bucket.reserve(start), Duration::ZERO);
+        }
+        // Each request past the burst waits for its own token
+        assert_eq!(bucket.reserve(start), Duration::from_millis(100));
+        assert_eq!(bucket.reserve(start), Duration::from_millis(200));
+        // Tokens come back with time, but never more than a burst
+        let later = start + Duration::from_secs(60);
+        for _ in 0..10 {
+            assert_eq!(bucket.reserve(later), Duration::ZERO);
+        }
+        assert!(!bucket.reserve(later).is_zero());
+    }
+
+    #[test]
+    fn test_slow_rate_allows_one_request_at_a_time() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(0.5);
+        bucket.updated = start;
+        assert_eq!(bucket.reserve(start), Duration::ZERO);
+        assert_eq!(bucket.reserve(start), Duration::from_secs(2));
+        assert_eq!(
+            bucket.reserve(start + Duration::from_secs(4)),
+            Duration::ZERO
+        );
+    }
+
+    #[test]
+    fn test_zero_rate_is_unlimited() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(0.0);
+        for _ in 0..1000 {
+            assert_eq!(bucket.reserve(start), Duration::ZERO);
+        }
+    }
+
+    #[test]
+    fn test_parse_rate() {
+        assert_eq!(parse_rate("20").unwrap(), 20.0);
+        assert_eq!(parse_rate("0.5").unwrap(), 0.5);
+        assert_eq!(parse_rate("0").unwrap(), 0.0);
+        assert!(parse_rate("-1").is_err());
+        assert!(parse_rate("fast").is_err());
+        assert!(parse_rate("inf").is_err());
+    }
+}
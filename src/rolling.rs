@@ -0,0 +1,547 @@
+//! Rolling restart assistant (`--ops`): restarts the instances of one
+//! replicaset one at a time. Leadership is moved off an instance before it
+//! goes down, and the next instance only starts once the previous one is
+//! back Online. The commands doing the work come from the config
+//! (`rolling_restart`); without them the operator runs each step by hand
+//! and the assistant watches the cluster to move on.
+
+use crate::actions::{reap_in_background, shell_quote, spawn_shell, StderrTail};
+use crate::config::RestartCommands;
+use crate::leaders::host;
+use crate::models::{InstanceInfo, StateVariant, TierInfo};
+use std::process::Child;
+use std::time::{Duration, Instant};
+
+/// How long one step may take before the restart stops
+pub const STEP_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Refresh interval while a restart runs, so finished steps are noticed
+/// quickly
+pub const REFRESH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How long to wait after a command exits for the rest of its stderr,
+/// which a process it left behind may keep open
+const STDERR_GRACE: Duration = Duration::from_secs(1);
+
+/// What the current instance is waiting for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+    /// The vshard leadership to move to another instance
+    SwitchLeader,
+    /// The instance to go down (or to come up as a new process)
+    GoOffline,
+    /// The instance to be Online again
+    ComeBack,
+}
+
+impl Step {
+    pub fn label(self) -> &'static str {
+        match self {
+            Step::SwitchLeader => "switching leader away",
+            Step::GoOffline => "taking offline",
+            Step::ComeBack => "waiting for Online",
+        }
+    }
+
+    /// What to do by hand when no command is configured for the step
+    pub fn manual_hint(self) -> Option<&'static str> {
+        match self {
+            Step::SwitchLeader => Some("move the replicaset leader to another instance"),
+            Step::GoOffline => Some("restart the instance"),
+            Step::ComeBack => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    /// Shown for confirmation, nothing started yet
+    Planned,
+    Running,
+    Done,
+    Failed(String),
+    Aborted,
+}
+
+/// The instance process as seen when a step started, to notice a restart
+/// that was over between two refreshes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Process {
+    pid: Option<u32>,
+    uptime_seconds: Option<u64>,
+}
+
+impl Process {
+    fn of(instance: &InstanceInfo) -> Self {
+        Self {
+            pid: instance.pid,
+            uptime_seconds: instance.uptime_seconds,
+        }
+    }
+
+    /// Whether `instance` runs a newer process than this one
+    fn replaced_by(self, instance: &InstanceInfo) -> bool {
+        let new_pid = matches!((self.pid, instance.pid), (Some(old), Some(new)) if old != new);
+        let younger = matches!(
+            (self.uptime_seconds, instance.uptime_seconds),
+            (Some(old), Some(new)) if new < old
+        );
+        new_pid || younger
+    }
+}
+
+pub struct RollingRestart {
+    pub tier: String,
+    pub replicaset: String,
+    /// Restart order: followers first and the leader last, so leadership
+    /// only moves once
+    pub instances: Vec<String>,
+    /// Index into `instances` of the instance being restarted
+    pub current: usize,
+    pub step: Step,
+    pub outcome: Outcome,
+    /// Finished steps, oldest first
+    pub log: Vec<String>,
+    commands: RestartCommands,
+    step_started: Instant,
+    before: Process,
+    command: Option<StepCommand>,
+    /// Commands started since `take_started_commands`, for the audit log
+    started_commands: Vec<String>,
+}
+
+/// The command of the current step, running or just exited
+struct StepCommand {
+    child: Child,
+    stderr: StderrTail,
+    exited_at: Option<Instant>,
+}
+
+impl RollingRestart {
+    /// Plan a restart of `replicaset`. Every instance has to be Online:
+    /// restarting next to one that's already down could lose the quorum.
+    pub fn plan(
+        tiers: &[TierInfo],
+        replicaset: &str,
+        commands: RestartCommands,
+    ) -> Result<Self, String> {
+        let (tier, rs) = tiers
+            .iter()
+            .flat_map(|tier| tier.replicasets.iter().map(move |rs| (tier, rs)))
+            .find(|(_, rs)| rs.name == replicaset)
+            .ok_or_else(|| format!("Replicaset {} not found", replicaset))?;
+        let members: Vec<&InstanceInfo> = rs
+            .instances
+            .iter()
+            .filter(|inst| inst.current_state != StateVariant::Expelled)
+            .collect();
+        if let Some(down) = members
+            .iter()
+            .find(|inst| inst.current_state != StateVariant::Online)
+        {
+            return Err(format!(
+                "{} is not Online; bring it back before a rolling restart",
+                down.name
+            ));
+        }
+        let (leaders, followers): (Vec<&InstanceInfo>, Vec<&InstanceInfo>) =
+            members.into_iter().partition(|inst| inst.is_leader);
+        let instances: Vec<String> = followers
+            .into_iter()
+            .chain(leaders)
+            .map(|inst| inst.name.clone())
+            .collect();
+        if instances.is_empty() {
+            return Err(format!("Replicaset {} has no instances", replicaset));
+        }
+        Ok(Self {
+            tier: tier.name.clone(),
+            replicaset: rs.name.clone(),
+            instances,
+            current: 0,
+            step: Step::GoOffline,
+            outcome: Outcome::Planned,
+            log: Vec::new(),
+            commands,
+            step_started: Instant::now(),
+            before: Process::default(),
+            command: None,
+            started_commands: Vec::new(),
+        })
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.outcome == Outcome::Running
+    }
+
+    /// Command run for `step`, if one is configured
+    pub fn command_for(&self, step: Step) -> Option<&str> {
+        match step {
+            Step::SwitchLeader => self.commands.switch_leader.as_deref(),
+            Step::GoOffline => self.commands.restart.as_deref(),
+            Step::ComeBack => None,
+        }
+    }
+
+    /// Command line of the current step for the current instance
+    pub fn current_command(&self, tiers: &[TierInfo]) -> Option<String> {
+        let template = self.command_for(self.step)?;
+        let instance = find_instance(tiers, &self.instances[self.current])?;
+        Some(fill(template, &self.tier, &self.replicaset, instance))
+    }
+
+    /// The whole restart as a shell script: the commands that would run,
+    /// with the waits and manual steps as comments
+    pub fn script(&self, tiers: &[TierInfo]) -> Vec<String> {
+        let mut lines = vec![format!(
+            "# Rolling restart of {} (tier {})",
+            self.replicaset, self.tier
+        )];
+        for name in &self.instances {
+            let Some(instance) = find_instance(tiers, name) else {
+                continue;
+            };
+            let mut steps = vec![Step::GoOffline];
+            if instance.is_leader && self.instances.len() > 1 {
+                steps.insert(0, Step::SwitchLeader);
+            }
+            lines.push(String::new());
+            for step in steps {
+                lines.push(match self.command_for(step) {
+                    Some(template) => fill(template, &self.tier, &self.replicaset, instance),
+                    None => format!(
+                        "# by hand: {} ({})",
+                        step.manual_hint().unwrap_or_default(),
+                        name
+                    ),
+                });
+                lines.push(match step {
+                    Step::SwitchLeader => format!("# wait until {} is no longer the leader", name),
+                    _ => format!("# wait until {} goes down and is back Online", name),
+                });
+            }
+        }
+        lines
+    }
+
+    /// Command lines run since the last call, oldest first
+    pub fn take_started_commands(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.started_commands)
+    }
+
+    /// Time spent on the current step
+    pub fn step_elapsed(&self, now: Instant) -> Duration {
+        now.saturating_duration_since(self.step_started)
+    }
+
+    /// Start the planned restart with the first instance
+    pub fn begin(&mut self, tiers: &[TierInfo], now: Instant) {
+        if self.outcome != Outcome::Planned {
+            return;
+        }
+        self.outcome = Outcome::Running;
+        self.begin_instance(tiers, now);
+    }
+
+    /// Move on as far as the cluster has caught up. True if anything
+    /// changed.
+    pub fn advance(&mut self, tiers: &[TierInfo], now: Instant) -> bool {
+        if !self.is_running() {
+            return false;
+        }
+        let name = self.instances[self.current].clone();
+        if let Err(e) = self.check_command(now) {
+            self.fail(format!("{}: {}", name, e));
+            return true;
+        }
+        let Some(instance) = find_instance(tiers, &name) else {
+            self.fail(format!("{} left the cluster", name));
+            return true;
+        };
+        let finished = self.command.is_none()
+            && match self.step {
+                Step::SwitchLeader => !instance.is_leader,
+                Step::GoOffline => {
+                    instance.current_state != StateVariant::Online
+                        || self.before.replaced_by(instance)
+                }
+                Step::ComeBack => instance.current_state == StateVariant::Online,
+            };
+        if !finished {
+            if self.step_elapsed(now) > STEP_TIMEOUT {
+                self.fail(format!(
+                    "{}: {} timed out after {}s",
+                    name,
+                    self.step.label(),
+                    STEP_TIMEOUT.as_secs()
+                ));
+                return true;
+            }
+            return false;
+        }
+
+        match self.step {
+            Step::SwitchLeader => {
+                self.log.push(format!("{}: leadership moved", name));
+                let instance = instance.clone();
+                self.enter(Step::GoOffline, &instance, now);
+            }
+            Step::GoOffline => {
+                self.log.push(format!("{}: went down", name));
+                let instance = instance.clone();
+                self.enter(Step::ComeBack, &instance, now);
+            }
+            Step::ComeBack => {
+                self.log.push(format!("{}: back Online", name));
+                self.current += 1;
+                if self.current == self.instances.len() {
+                    self.current -= 1;
+                    self.outcome = Outcome::Done;
+                    self.log.push(format!("{} restarted", self.replicaset));
+                } else {
+                    self.begin_instance(tiers, now);
+                }
+            }
+        }
+        true
+    }
+
+    /// Stop before the next step. A command already running is left to
+    /// finish: killing a restart halfway is worse than letting it complete.
+    pub fn abort(&mut self) {
+        if !matches!(self.outcome, Outcome::Planned | Outcome::Running) {
+            return;
+        }
+        self.release_command();
+        self.outcome = Outcome::Aborted;
+        self.log.push("Aborted".to_string());
+    }
+
+    fn begin_instance(&mut self, tiers: &[TierInfo], now: Instant) {
+        let name = &self.instances[self.current];
+        let Some(instance) = find_instance(tiers, name).cloned() else {
+            self.fail(format!("{} left the cluster", name));
+            return;
+        };
+        // A lone instance has nobody to hand leadership to
+        let step = if instance.is_leader && self.instances.len() > 1 {
+            Step::SwitchLeader
+        } else {
+            Step::GoOffline
+        };
+        self.enter(step, &instance, now);
+    }
+
+    fn enter(&mut self, step: Step, instance: &InstanceInfo, now: Instant) {
+        self.step = step;
+        self.step_started = now;
+        self.before = Process::of(instance);
+        let Some(template) = self.command_for(step) else {
+            return;
+        };
+        let command = fill(template, &self.tier, &self.replicaset, instance);
+        match spawn_shell(&command) {
+            Ok(mut child) => {
+                self.command = Some(StepCommand {
+                    stderr: StderrTail::watch(&mut child),
+                    child,
+                    exited_at: None,
+                });
+                self.started_commands.push(command);
+            }
+            Err(e) => self.fail(format!("Could not run {}: {}", command, e)),
+        }
+    }
+
+    /// Reap the step's command once it exits, failing on a non-zero status
+    fn check_command(&mut self, now: Instant) -> Result<(), String> {
+        let Some(command) = self.command.as_mut() else {
+            return Ok(());
+        };
+        let status = match command.child.try_wait() {
+            Ok(None) => return Ok(()),
+            Ok(Some(status)) => status,
+            Err(e) => return Err(e.to_string()),
+        };
+        // Its last words may still be on their way
+        let exited_at = *command.exited_at.get_or_insert(now);
+        if !status.success()
+            && !command.stderr.is_closed()
+            && now.saturating_duration_since(exited_at) < STDERR_GRACE
+        {
+            return Ok(());
+        }
+        let last_line = command.stderr.last_line();
+        self.command = None;
+        if status.success() {
+            return Ok(());
+        }
+        match last_line {
+            Some(line) => Err(format!("command failed ({}): {}", status, line)),
+            None => Err(format!("command failed ({})", status)),
+        }
+    }
+
+    fn fail(&mut self, reason: String) {
+        self.release_command();
+        self.log.push(reason.clone());
+        self.outcome = Outcome::Failed(reason);
+    }
+
+    /// Stop tracking the running command, reaping it in the background
+    fn release_command(&mut self) {
+        if let Some(command) = self.command.take() {
+            reap_in_background(command.child);
+        }
+    }
+}
+
+fn find_instance<'a>(tiers: &'a [TierInfo], name: &str) -> Option<&'a InstanceInfo> {
+    tiers
+        .iter()
+        .flat_map(|t| t.replicasets.iter())
+        .flat_map(|rs| rs.instances.iter())
+        .find(|inst| inst.name == name)
+}
+
+/// Substitute `{tier}`, `{replicaset}`, `{instance}`, `{address}` and
+/// `{host}` in a command template, quoted for the shell
+pub fn fill(template: &str, tier: &str, replicaset: &str, instance: &InstanceInfo) -> String {
+    [
+        ("{tier}", tier),
+        ("{replicaset}", replicaset),
+        ("{instance}", &instance.name),
+        ("{address}", &instance.binary_address),
+        ("{host}", host(&instance.binary_address)),
+    ]
+    .iter()
+    .fold(template.to_string(), |command, (placeholder, value)| {
+        command.replace(placeholder, &shell_quote(value))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock;
+
+    fn instance_mut<'a>(tiers: &'a mut [TierInfo], name: &str) -> &'a mut InstanceInfo {
+        tiers
+            .iter_mut()
+            .flat_map(|t| t.replicasets.iter_mut())
+            .flat_map(|rs| rs.instances.iter_mut())
+            .find(|inst| inst.name == name)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_rolling_restart_walks_the_replicaset() {
+        let mut tiers: Vec<TierInfo> = serde_json::from_value(mock::tiers()).unwrap();
+        let now = Instant::now();
+
+        let refused = RollingRestart::plan(&tiers, "r2", RestartCommands::default());
+        assert!(refused.is_err(), "i3 is already down");
+
+        let mut restart = RollingRestart::plan(&tiers, "r1", RestartCommands::default()).unwrap();
+        assert_eq!(restart.instances, ["i2", "i1"], "leader last");
+        restart.begin(&tiers, now);
+        assert_eq!(restart.step, Step::GoOffline);
+        assert!(!restart.advance(&tiers, now), "nothing happened yet");
+
+        instance_mut(&mut tiers, "i2").current_state = StateVariant::Offline;
+        assert!(restart.advance(&tiers, now));
+        assert_eq!(restart.step, Step::ComeBack);
+        instance_mut(&mut tiers, "i2").current_state = StateVariant::Online;
+        assert!(restart.advance(&tiers, now));
+
+        // The leader hands over first
+        assert_eq!(restart.current, 1);
+        assert_eq!(restart.step, Step::SwitchLeader);
+        instance_mut(&mut tiers, "i1").pid = Some(100);
+        instance_mut(&mut tiers, "i1").is_leader = false;
+        instance_mut(&mut tiers, "i2").is_leader = true;
+        assert!(restart.advance(&tiers, now));
+        assert_eq!(restart.step, Step::GoOffline);
+
+        // Restarted between two refreshes: only the new process shows it
+        instance_mut(&mut tiers, "i1").pid = Some(101);
+        assert!(restart.advance(&tiers, now));
+        assert_eq!(restart.step, Step::ComeBack);
+        assert!(restart.advance(&tiers, now));
+        assert_eq!(restart.outcome, Outcome::Done);
+        assert!(!restart.advance(&tiers, now));
+    }
+
+    #[test]
+    fn test_rolling_restart_stops_on_timeout_and_abort() {
+        let tiers: Vec<TierInfo> = serde_json::from_value(mock::tiers()).unwrap();
+        let now = Instant::now();
+
+        let mut restart = RollingRestart::plan(&tiers, "r1", RestartCommands::default()).unwrap();
+        restart.begin(&tiers, now);
+        assert!(restart.advance(&tiers, now + STEP_TIMEOUT + Duration::from_secs(1)));
+        assert!(
+            matches!(restart.outcome, Outcome::Failed(ref reason) if reason.contains("timed out"))
+        );
+
+        let mut restart = RollingRestart::plan(&tiers, "r1", RestartCommands::default()).unwrap();
+        restart.begin(&tiers, now);
+        restart.abort();
+        assert_eq!(restart.outcome, Outcome::Aborted);
+        assert!(!restart.advance(&tiers, now));
+    }
+
+    // The commands are written for sh
+    #[test]
+    #[cfg(unix)]
+    fn test_rolling_restart_runs_configured_commands() {
+        let tiers: Vec<TierInfo> = serde_json::from_value(mock::tiers()).unwrap();
+        let commands = RestartCommands {
+            switch_leader: None,
+            restart: Some("echo {instance} on {host} is busy >&2; exit 3".to_string()),
+        };
+        let mut restart = RollingRestart::plan(&tiers, "r1", commands).unwrap();
+        assert_eq!(
+            restart.current_command(&tiers).as_deref(),
+            Some("echo 'i2' on '10.0.0.2' is busy >&2; exit 3")
+        );
+        restart.begin(&tiers, Instant::now());
+
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while restart.is_running() && Instant::now() < deadline {
+            restart.advance(&tiers, Instant::now());
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        match restart.outcome {
+            Outcome::Failed(ref reason) => {
+                assert!(reason.contains("i2 on 10.0.0.2 is busy"), "{}", reason)
+            }
+            ref other => panic!("expected a failure, got {:?}", other),
+        }
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_rolling_restart_command_with_lots_of_stderr() {
+        let tiers: Vec<TierInfo> = serde_json::from_value(mock::tiers()).unwrap();
+        // Far more than a pipe holds
+        let commands = RestartCommands {
+            switch_leader: None,
+            restart: Some(
+                "yes noise | head -n 200000 >&2; echo {instance} failed >&2; exit 3".to_string(),
+            ),
+        };
+        let mut restart = RollingRestart::plan(&tiers, "r1", commands).unwrap();
+        restart.begin(&tiers, Instant::now());
+
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while restart.is_running() && Instant::now() < deadline {
+            restart.advance(&tiers, Instant::now());
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        match restart.outcome {
+            Outcome::Failed(ref reason) => assert!(reason.ends_with("i2 failed"), "{}", reason),
+            ref other => panic!("expected a failure, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,79 @@
+//! How strictly API responses are parsed. Lenient by default: fields a
+//! newer Picodata adds are ignored and missing optional ones take their
+//! defaults. With `--strict`, a field the models don't know is an error
+//! naming where it was found, so API changes show up instead of being
+//! silently dropped.
+
+use serde::de::{DeserializeOwned, Error as _};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static STRICT: AtomicBool = AtomicBool::new(false);
+
+/// Reject unknown fields in every response parsed from now on
+pub fn set_strict(strict: bool) {
+    STRICT.store(strict, Ordering::Relaxed);
+}
+
+pub fn strict() -> bool {
+    STRICT.load(Ordering::Relaxed)
+}
+
+/// Parse a JSON response body according to the current mode
+pub fn from_slice<T: DeserializeOwned>(body: &[u8]) -> serde_json::Result<T> {
+    parse(body, strict())
+}
+
+/// Like `from_slice`, for text such as stream events
+pub fn from_str<T: DeserializeOwned>(text: &str) -> serde_json::Result<T> {
+    parse(text.as_bytes(), strict())
+}
+
+fn parse<T: DeserializeOwned>(body: &[u8], strict: bool) -> serde_json::Result<T> {
+    if !strict {
+        return serde_json::from_slice(body);
+    }
+    let mut deserializer = serde_json::Deserializer::from_slice(body);
+    let mut unknown = Vec::new();
+    let value =
+        serde_ignored::deserialize(&mut deserializer, |path| unknown.push(path.to_string()))?;
+    deserializer.end()?;
+    match unknown.as_slice() {
+        [] => Ok(value),
+        [path] => Err(serde_json::Error::custom(format!(
+            "unknown field `{}` (--strict)",
+            path
+        ))),
+        paths => Err(serde_json::Error::custom(format!(
+            "unknown fields `{}` (--strict)",
+            paths.join("`, `")
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Tier {
+        name: String,
+        #[serde(default)]
+        rf: u8,
+    }
+
+    #[test]
+    fn test_modes() {
+        let body = br#"[{"name": "default", "rf": 2, "zone": "a"}, {"name": "router"}]"#;
+
+        let tiers: Vec<Tier> = parse(body, false).unwrap();
+        assert_eq!(tiers[0].rf, 2);
+        assert_eq!(tiers[1].rf, 0);
+
+        let err = parse::<Vec<Tier>>(body, true).unwrap_err().to_string();
+        assert_eq!(err, "unknown field `0.zone` (--strict)");
+        // Missing optional fields are still fine
+        let tiers: Vec<Tier> = parse(br#"[{"name": "router"}]"#, true).unwrap();
+        assert_eq!(tiers[0].rf, 0);
+    }
+}
@@ -0,0 +1,76 @@
+use crate::models::InstanceInfo;
+use serde::Serialize;
+use std::path::PathBuf;
+
+/// An exported instance together with its place in the topology
+#[derive(Debug, Serialize)]
+struct ExportedInstance<'a> {
+    tier: &'a str,
+    replicaset: &'a str,
+    #[serde(flatten)]
+    instance: &'a InstanceInfo,
+}
+
+/// Serialize marked instances as a pretty-printed JSON array. Instance
+/// fields keep the names the HTTP API uses.
+pub fn instances_to_json(instances: &[(&str, &str, &InstanceInfo)]) -> serde_json::Result<String> {
+    let rows: Vec<_> = instances
+        .iter()
+        .map(|&(tier, replicaset, instance)| ExportedInstance {
+            tier,
+            replicaset,
+            instance,
+        })
+        .collect();
+    serde_json::to_string_pretty(&rows)
+}
+
+/// Write marked instances to `picotui-selection-<unix time>.json` in the current directory
+pub fn save(instances: &[(&str, &str, &InstanceInfo)]) -> std::io::Result<PathBuf> {
+    let path = PathBuf::from(format!(
+        "picotui-selection-{}.json",
+        crate::ui::format::unix_now()
+    ));
+    std::fs::write(&path, instances_to_json(instances)?)?;
+    Ok(path)
+}
+
+/// OSC 52 escape sequence that asks the terminal to put `text` on the
+/// system clipboard. Works over SSH, no clipboard daemon needed.
+pub fn osc52_copy(text: &str) -> String {
+    format!("\x1b]52;c;{}\x07", encode_base64(text.as_bytes()))
+}
+
+/// Standard padded base64 (RFC 4648 §4)
+fn encode_base64(input: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut out = String::with_capacity(input.len().div_ceil(3) * 4);
+    for chunk in input.chunks(3) {
+        let acc = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |acc, (i, &b)| acc | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(acc >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_osc52_encodes_base64() {
+        assert_eq!(osc52_copy("i1\ni2"), "\x1b]52;c;aTEKaTI=\x07");
+        assert_eq!(encode_base64(b""), "");
+        assert_eq!(encode_base64(b"f"), "Zg==");
+        assert_eq!(encode_base64(b"foo"), "Zm9v");
+    }
+}
@@ -0,0 +1,70 @@
+//! Datasets shared between the views, snapshots and exports instead of
+//! copied into each of them.
+
+use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
+
+/// A dataset behind an `Arc`, with a generation that moves on whenever the
+/// data changes, so whatever was derived from it can tell it is stale
+/// without comparing the data
+#[derive(Debug, Default)]
+pub struct Shared<T> {
+    data: Arc<T>,
+    generation: u64,
+}
+
+impl<T> Shared<T> {
+    pub fn new(data: T) -> Self {
+        Self {
+            data: Arc::new(data),
+            generation: 0,
+        }
+    }
+
+    /// Swap in new data. Handles to the old data keep it alive.
+    pub fn replace(&mut self, data: T) {
+        self.data = Arc::new(data);
+        self.generation += 1;
+    }
+
+    /// Another handle to the current data, without copying it
+    pub fn handle(&self) -> Arc<T> {
+        Arc::clone(&self.data)
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+}
+
+impl<T> Clone for Shared<T> {
+    fn clone(&self) -> Self {
+        Self {
+            data: Arc::clone(&self.data),
+            generation: self.generation,
+        }
+    }
+}
+
+impl<T> From<T> for Shared<T> {
+    fn from(data: T) -> Self {
+        Self::new(data)
+    }
+}
+
+impl<T> Deref for Shared<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.data
+    }
+}
+
+/// Changing the data in place copies it first if a handle to it is held
+/// elsewhere, and moves to the next generation
+impl<T: Clone> DerefMut for Shared<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.generation += 1;
+        Arc::make_mut(&mut self.data)
+    }
+}
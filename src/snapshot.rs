@@ -0,0 +1,282 @@
+use crate::models::{StateVariant, TierInfo};
+use crate::paths;
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Cluster state saved for later comparison
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub name: String,
+    /// Unix time the snapshot was taken
+    pub taken_at: u64,
+    /// Base URL of the cluster it was taken from. Missing in snapshots
+    /// saved by older versions.
+    #[serde(default)]
+    pub url: Option<String>,
+    /// Shared with the live tiers the snapshot was taken from
+    pub tiers: Arc<Vec<TierInfo>>,
+}
+
+/// Default directory for snapshot files
+pub fn snapshot_dir() -> Option<PathBuf> {
+    paths::data_dir().map(|p| p.join("snapshots"))
+}
+
+/// Snapshot names become file names, so keep them to a safe character set
+fn validate_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        && !name.starts_with('.');
+    if valid {
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "Invalid snapshot name \"{}\": use letters, digits, '-', '_' and '.'",
+            name
+        ))
+    }
+}
+
+/// Write a snapshot to `<dir>/<name>.json`, replacing one with the same name
+pub fn save(dir: &Path, snapshot: &Snapshot) -> Result<PathBuf> {
+    validate_name(&snapshot.name)?;
+    fs::create_dir_all(dir).with_context(|| format!("Could not create {}", dir.display()))?;
+    let path = dir.join(format!("{}.json", snapshot.name));
+    let file =
+        File::create(&path).with_context(|| format!("Could not write {}", path.display()))?;
+    serde_json::to_writer_pretty(file, snapshot)?;
+    Ok(path)
+}
+
+pub fn load(dir: &Path, name: &str) -> Result<Snapshot> {
+    validate_name(name)?;
+    let path = dir.join(format!("{}.json", name));
+    let file = File::open(&path).with_context(|| format!("No snapshot named \"{}\"", name))?;
+    serde_json::from_reader(file)
+        .with_context(|| format!("Invalid snapshot file {}", path.display()))
+}
+
+/// Names of the saved snapshots, sorted
+pub fn list(dir: &Path) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            if path.extension()? != "json" {
+                return None;
+            }
+            Some(path.file_stem()?.to_str()?.to_string())
+        })
+        .collect();
+    names.sort();
+    names
+}
+
+/// Names of the saved snapshots taken from the cluster at `url`, or from
+/// an unknown one, sorted
+pub fn list_for(dir: &Path, url: &str) -> Vec<String> {
+    list(dir)
+        .into_iter()
+        .filter(|name| source_url(dir, name).is_none_or(|source| source == url))
+        .collect()
+}
+
+/// Where a saved snapshot was taken, without keeping its tiers
+fn source_url(dir: &Path, name: &str) -> Option<String> {
+    #[derive(Deserialize)]
+    struct Source {
+        #[serde(default)]
+        url: Option<String>,
+    }
+    let file = File::open(dir.join(format!("{}.json", name))).ok()?;
+    let source: Source = serde_json::from_reader(BufReader::new(file)).ok()?;
+    source.url
+}
+
+/// Warning for comparing snapshots taken from different clusters
+pub fn source_mismatch(old: &Snapshot, new: &Snapshot) -> Option<String> {
+    match (&old.url, &new.url) {
+        (Some(old_url), Some(new_url)) if old_url != new_url => Some(format!(
+            "\"{}\" was taken from {} and \"{}\" from {}",
+            old.name, old_url, new.name, new_url
+        )),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StateChange {
+    pub instance: String,
+    pub from: StateVariant,
+    pub to: StateVariant,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryChange {
+    pub tier: String,
+    pub before: u64,
+    pub after: u64,
+}
+
+impl MemoryChange {
+    pub fn delta(&self) -> i64 {
+        self.after as i64 - self.before as i64
+    }
+}
+
+/// What changed between two tiers snapshots
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SnapshotDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub state_changes: Vec<StateChange>,
+    /// Used memory per tier present in both snapshots, only where it changed
+    pub memory: Vec<MemoryChange>,
+}
+
+impl SnapshotDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.state_changes.is_empty()
+            && self.memory.is_empty()
+    }
+}
+
+/// Compare an older snapshot with a newer one. Instances are matched by name.
+pub fn diff(old: &[TierInfo], new: &[TierInfo]) -> SnapshotDiff {
+    let states = |tiers: &[TierInfo]| -> BTreeMap<String, StateVariant> {
+        tiers
+            .iter()
+            .flat_map(|t| t.replicasets.iter())
+            .flat_map(|rs| rs.instances.iter())
+            .map(|inst| (inst.name.clone(), inst.current_state))
+            .collect()
+    };
+    let (before, after) = (states(old), states(new));
+
+    let names: BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+    let mut result = SnapshotDiff::default();
+    for name in names {
+        match (before.get(name), after.get(name)) {
+            (None, Some(_)) => result.added.push(name.clone()),
+            (Some(_), None) => result.removed.push(name.clone()),
+            (Some(&from), Some(&to)) if from != to => result.state_changes.push(StateChange {
+                instance: name.clone(),
+                from,
+                to,
+            }),
+            _ => {}
+        }
+    }
+
+    for tier in new {
+        if let Some(previous) = old.iter().find(|t| t.name == tier.name) {
+            if previous.memory.used != tier.memory.used {
+                result.memory.push(MemoryChange {
+                    tier: tier.name.clone(),
+                    before: previous.memory.used,
+                    after: tier.memory.used,
+                });
+            }
+        }
+    }
+
+    result
+}
+
+impl fmt::Display for SnapshotDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "No changes");
+        }
+        for name in &self.added {
+            writeln!(f, "+ {}", name)?;
+        }
+        for name in &self.removed {
+            writeln!(f, "- {}", name)?;
+        }
+        for change in &self.state_changes {
+            writeln!(f, "~ {}: {} → {}", change.instance, change.from, change.to)?;
+        }
+        for change in &self.memory {
+            writeln!(
+                f,
+                "  {} memory used: {} → {} ({:+} bytes)",
+                change.tier,
+                change.before,
+                change.after,
+                change.delta()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_save_list_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let snapshot = Snapshot {
+            name: "before-upgrade".to_string(),
+            taken_at: 1_700_000_000,
+            url: Some("http://a:8080".to_string()),
+            tiers: Arc::default(),
+        };
+        save(dir.path(), &snapshot).unwrap();
+        let other = Snapshot {
+            name: "other".to_string(),
+            url: Some("http://b:8080".to_string()),
+            ..snapshot.clone()
+        };
+        save(dir.path(), &other).unwrap();
+        // Saved before the URL was recorded
+        fs::write(
+            dir.path().join("old.json"),
+            r#"{"name": "old", "taken_at": 1, "tiers": []}"#,
+        )
+        .unwrap();
+
+        assert_eq!(list(dir.path()), vec!["before-upgrade", "old", "other"]);
+        assert_eq!(
+            list_for(dir.path(), "http://a:8080"),
+            vec!["before-upgrade", "old"]
+        );
+        assert!(source_mismatch(&snapshot, &other)
+            .unwrap()
+            .contains("http://b:8080"));
+        let old = load(dir.path(), "old").unwrap();
+        assert_eq!(old.url, None);
+        assert_eq!(source_mismatch(&old, &snapshot), None);
+        assert_eq!(
+            load(dir.path(), "before-upgrade").unwrap().taken_at,
+            1_700_000_000
+        );
+        assert!(load(dir.path(), "missing").is_err());
+    }
+
+    #[test]
+    fn test_names_must_be_safe_file_names() {
+        assert!(validate_name("v25.6-rc_1").is_ok());
+        for name in ["", "../x", "a/b", ".hidden", "with space"] {
+            assert!(
+                validate_name(name).is_err(),
+                "{:?} should be rejected",
+                name
+            );
+        }
+    }
+}
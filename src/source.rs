@@ -0,0 +1,83 @@
+//! Where the App gets cluster data from.
+//!
+//! The App only speaks `ApiRequest` and `ApiMessage`: it queues requests
+//! and polls for answers without blocking. `HttpSource` is the Picodata
+//! HTTP API served by the worker thread; a recorded replay, a demo
+//! generator or another transport only has to implement `DataSource`.
+
+use crate::api::{self, ApiMessage, ApiRequest};
+use crate::prepare;
+use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
+
+/// Starts a data source; called again to replace one whose worker stopped
+pub type SpawnSource = Box<dyn Fn() -> Box<dyn DataSource>>;
+
+/// Asynchronous source of cluster data
+pub trait DataSource {
+    /// Queue a request. The answer, if the request has one, comes later
+    /// from `try_recv`.
+    fn send(&self, request: ApiRequest);
+
+    /// Next answer if one is ready. `Disconnected` means the source has
+    /// stopped for good.
+    fn try_recv(&self) -> Result<ApiMessage, TryRecvError>;
+}
+
+impl<S: DataSource + ?Sized> DataSource for Box<S> {
+    fn send(&self, request: ApiRequest) {
+        (**self).send(request)
+    }
+
+    fn try_recv(&self) -> Result<ApiMessage, TryRecvError> {
+        (**self).try_recv()
+    }
+}
+
+/// The HTTP API worker thread, reached over channels
+pub struct HttpSource {
+    requests: Sender<ApiRequest>,
+    responses: Receiver<ApiMessage>,
+}
+
+impl HttpSource {
+    /// Start the API worker for `base_url`. Its tiers are prepared for the
+    /// UI on the way (see `prepare`).
+    pub fn spawn(base_url: &str, debug: bool) -> Self {
+        Self::spawn_via(base_url, base_url, debug)
+    }
+
+    /// Start the API worker for `base_url`, reached at `connect_url`
+    /// through a tunnel (see `tunnel`)
+    pub fn spawn_via(base_url: &str, connect_url: &str, debug: bool) -> Self {
+        let (request_tx, request_rx) = channel();
+        let (raw_response_tx, raw_response_rx) = channel();
+        let (response_tx, response_rx) = channel();
+        api::spawn_api_worker_via(
+            base_url.to_string(),
+            connect_url.to_string(),
+            request_rx,
+            raw_response_tx,
+            debug,
+        );
+        prepare::spawn_preparer(raw_response_rx, response_tx);
+        Self::new(request_tx, response_rx)
+    }
+
+    /// Talk to a worker over existing channels
+    pub fn new(requests: Sender<ApiRequest>, responses: Receiver<ApiMessage>) -> Self {
+        Self {
+            requests,
+            responses,
+        }
+    }
+}
+
+impl DataSource for HttpSource {
+    fn send(&self, request: ApiRequest) {
+        let _ = self.requests.send(request);
+    }
+
+    fn try_recv(&self) -> Result<ApiMessage, TryRecvError> {
+        self.responses.try_recv()
+    }
+}
@@ -0,0 +1,79 @@
+use crate::models::TierInfo;
+use std::collections::BTreeMap;
+
+/// Memory totals for a group of replicasets or instances
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryGroup {
+    pub key: String,
+    pub used: u64,
+    pub usable: u64,
+}
+
+impl MemoryGroup {
+    /// Used share of usable memory, in percent
+    pub fn usage(&self) -> f64 {
+        if self.usable == 0 {
+            0.0
+        } else {
+            self.used as f64 * 100.0 / self.usable as f64
+        }
+    }
+}
+
+/// Memory of each tier as reported by the server
+pub fn memory_by_tier(tiers: &[TierInfo]) -> Vec<MemoryGroup> {
+    tiers
+        .iter()
+        .map(|tier| MemoryGroup {
+            key: tier.name.clone(),
+            used: tier.memory.used,
+            usable: tier.memory.usable,
+        })
+        .collect()
+}
+
+/// Memory per failure domain `key:value` pair. Memory is only reported per
+/// replicaset, so each instance is credited an equal share of its
+/// replicaset's memory.
+pub fn memory_by_domain(tiers: &[TierInfo]) -> Vec<MemoryGroup> {
+    let mut groups: BTreeMap<String, (u64, u64)> = BTreeMap::new();
+    for rs in tiers.iter().flat_map(|t| t.replicasets.iter()) {
+        let count = rs.instances.len() as u64;
+        if count == 0 {
+            continue;
+        }
+        for inst in &rs.instances {
+            for (key, value) in &inst.failure_domain {
+                let entry = groups.entry(format!("{}:{}", key, value)).or_default();
+                entry.0 += rs.memory.used / count;
+                entry.1 += rs.memory.usable / count;
+            }
+        }
+    }
+    groups
+        .into_iter()
+        .map(|(key, (used, usable))| MemoryGroup { key, used, usable })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock;
+
+    #[test]
+    fn test_memory_by_failure_domain() {
+        let tiers: Vec<TierInfo> = serde_json::from_value(mock::tiers()).unwrap();
+        const GIB: u64 = 1 << 30;
+
+        let groups = memory_by_domain(&tiers);
+        let usable = |key: &str| groups.iter().find(|g| g.key == key).unwrap().usable;
+        // Two halves of r1 plus one half of the 2 GiB s1
+        assert_eq!(usable("datacenter:dc1"), 2 * GIB);
+        assert_eq!(usable("rack:r1"), GIB);
+
+        let by_tier = memory_by_tier(&tiers);
+        assert_eq!(by_tier[0].key, "default");
+        assert!((by_tier[0].usage() - 30.0).abs() < 0.1);
+    }
+}
@@ -0,0 +1,288 @@
+use crate::api::{log_debug, ApiMessage, ApiResponse, ResponseMeta};
+use crate::models::*;
+use crate::schema;
+use std::io::{BufRead, BufReader};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Default server-sent events endpoint probed at startup
+pub const DEFAULT_STREAM_PATH: &str = "/api/v1/events";
+
+/// A stream that sends nothing for this long, not even a keep-alive
+/// comment, is taken for a dead connection and polling resumes
+pub const STREAM_IDLE_TIMEOUT: Duration = Duration::from_secs(45);
+
+/// How often the stream thread checks whether it was asked to stop
+const STOP_CHECK_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Lifecycle notifications from the event stream thread
+#[derive(Debug)]
+pub enum StreamEvent {
+    /// The stream is connected and pushing updates
+    Connected,
+    /// The server has no streaming endpoint; stay on polling
+    Unavailable(String),
+    /// A live stream dropped; polling resumes until it reconnects
+    Disconnected(String),
+}
+
+/// A single server-sent event
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SseEvent {
+    pub event: String,
+    pub data: String,
+}
+
+/// Incremental `text/event-stream` parser (one line at a time)
+#[derive(Debug, Default)]
+pub struct SseParser {
+    event: String,
+    data: Vec<String>,
+}
+
+impl SseParser {
+    /// Feed one line (without the trailing newline). Returns an event once
+    /// a blank line terminates it.
+    pub fn feed(&mut self, line: &str) -> Option<SseEvent> {
+        if line.is_empty() {
+            if self.data.is_empty() {
+                self.event.clear();
+                return None;
+            }
+            let event = SseEvent {
+                event: if self.event.is_empty() {
+                    "message".to_string()
+                } else {
+                    std::mem::take(&mut self.event)
+                },
+                data: self.data.join("\n"),
+            };
+            self.data.clear();
+            return Some(event);
+        }
+
+        // Lines starting with ':' are comments (often used as keep-alives)
+        if line.starts_with(':') {
+            return None;
+        }
+
+        let (field, value) = line.split_once(':').unwrap_or((line, ""));
+        let value = value.strip_prefix(' ').unwrap_or(value);
+        match field {
+            "event" => self.event = value.to_string(),
+            "data" => self.data.push(value.to_string()),
+            _ => {}
+        }
+        None
+    }
+}
+
+/// Convert a stream event into the matching API response, if known
+fn event_to_response(event: &SseEvent) -> Option<ApiResponse> {
+    match event.event.as_str() {
+        "tiers" => Some(ApiResponse::Tiers(
+            schema::from_str::<Vec<TierInfo>>(&event.data)
+                .map_err(|e| format!("Failed to parse streamed tiers: {}", e)),
+        )),
+        "cluster" => Some(ApiResponse::ClusterInfo(
+            schema::from_str::<ClusterInfo>(&event.data)
+                .map_err(|e| format!("Failed to parse streamed cluster info: {}", e)),
+        )),
+        _ => None,
+    }
+}
+
+/// Spawn a thread holding a long-lived SSE connection. Updates are pushed
+/// through `response_tx` as regular responses; the thread exits when the
+/// stream ends or goes `idle_timeout` without a line, after reporting why.
+/// Setting `stop` ends it quietly, e.g. when the session it was opened
+/// with is replaced.
+pub fn spawn_event_stream(
+    url: String,
+    auth_token: Option<String>,
+    stop: Arc<AtomicBool>,
+    idle_timeout: Duration,
+    response_tx: Sender<ApiMessage>,
+    debug: bool,
+) {
+    thread::spawn(move || {
+        let send = |response: ApiResponse, latency: Duration, reachable: bool| {
+            response_tx
+                .send(ApiMessage {
+                    response,
+                    meta: ResponseMeta {
+                        latency,
+                        reachable,
+                        bytes: 0,
+                    },
+                })
+                .is_ok()
+        };
+
+        let client = ureq::Agent::config_builder()
+            .timeout_connect(Some(Duration::from_secs(5)))
+            .timeout_recv_response(Some(Duration::from_secs(10)))
+            .build()
+            .new_agent();
+
+        log_debug(debug, &format!("STREAM {}", url));
+        let mut req = client.get(&url).header("Accept", "text/event-stream");
+        if let Some(ref token) = auth_token {
+            req = req.header("Authorization", &format!("Bearer {}", token));
+        }
+
+        let started = Instant::now();
+        let resp = match req.call() {
+            Ok(resp) => resp,
+            Err(ureq::Error::StatusCode(status)) => {
+                log_debug(debug, &format!("  STREAM UNAVAILABLE: HTTP {}", status));
+                send(
+                    ApiResponse::Stream(StreamEvent::Unavailable(format!("HTTP {}", status))),
+                    started.elapsed(),
+                    true,
+                );
+                return;
+            }
+            Err(e) => {
+                log_debug(debug, &format!("  STREAM ERROR: {}", e));
+                send(
+                    ApiResponse::Stream(StreamEvent::Disconnected(e.to_string())),
+                    started.elapsed(),
+                    false,
+                );
+                return;
+            }
+        };
+        let latency = started.elapsed();
+
+        let is_event_stream = resp
+            .headers()
+            .get("Content-Type")
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.starts_with("text/event-stream"));
+        if !is_event_stream {
+            log_debug(debug, "  STREAM UNAVAILABLE: not an event stream");
+            send(
+                ApiResponse::Stream(StreamEvent::Unavailable(
+                    "endpoint is not an event stream".to_string(),
+                )),
+                latency,
+                true,
+            );
+            return;
+        }
+
+        if stop.load(Ordering::Relaxed) {
+            log_debug(debug, "  STREAM STOPPED");
+            return;
+        }
+        log_debug(debug, "  OK: stream connected");
+        if !send(ApiResponse::Stream(StreamEvent::Connected), latency, true) {
+            return;
+        }
+
+        // Lines are read on a thread of their own, so this one can give
+        // up on a connection that went quiet. The reader ends with the
+        // connection, or at its next line once nobody listens.
+        let (line_tx, line_rx) = channel();
+        thread::spawn(move || {
+            for line in BufReader::new(resp.into_body().into_reader()).lines() {
+                if line_tx.send(line).is_err() {
+                    return;
+                }
+            }
+        });
+
+        let mut parser = SseParser::default();
+        let mut last_line = Instant::now();
+        loop {
+            if stop.load(Ordering::Relaxed) {
+                log_debug(debug, "  STREAM STOPPED");
+                return;
+            }
+            let line = match line_rx.recv_timeout(STOP_CHECK_INTERVAL) {
+                Ok(Ok(line)) => line,
+                Err(RecvTimeoutError::Timeout) if last_line.elapsed() < idle_timeout => {
+                    continue;
+                }
+                Err(RecvTimeoutError::Timeout) => {
+                    log_debug(debug, "  STREAM IDLE");
+                    send(
+                        ApiResponse::Stream(StreamEvent::Disconnected(format!(
+                            "no events for {} s",
+                            idle_timeout.as_secs()
+                        ))),
+                        Duration::ZERO,
+                        false,
+                    );
+                    return;
+                }
+                Err(RecvTimeoutError::Disconnected) => break,
+                Ok(Err(e)) => {
+                    log_debug(debug, &format!("  STREAM ERROR: {}", e));
+                    send(
+                        ApiResponse::Stream(StreamEvent::Disconnected(e.to_string())),
+                        Duration::ZERO,
+                        false,
+                    );
+                    return;
+                }
+            };
+            last_line = Instant::now();
+            if let Some(event) = parser.feed(line.trim_end_matches('\r')) {
+                log_debug(debug, &format!("  STREAM EVENT: {}", event.event));
+                if let Some(response) = event_to_response(&event) {
+                    if !send(response, Duration::ZERO, true) {
+                        // App is gone
+                        return;
+                    }
+                }
+            }
+        }
+
+        log_debug(debug, "  STREAM CLOSED");
+        send(
+            ApiResponse::Stream(StreamEvent::Disconnected(
+                "stream closed by server".to_string(),
+            )),
+            Duration::ZERO,
+            false,
+        );
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sse_parser_events() {
+        let mut parser = SseParser::default();
+
+        assert_eq!(parser.feed(": keep-alive"), None);
+        assert_eq!(parser.feed(""), None);
+        assert_eq!(parser.feed("event: tiers"), None);
+        assert_eq!(parser.feed("data: [1,"), None);
+        assert_eq!(parser.feed("data: 2]"), None);
+        assert_eq!(
+            parser.feed(""),
+            Some(SseEvent {
+                event: "tiers".to_string(),
+                data: "[1,\n2]".to_string(),
+            })
+        );
+
+        // Event name does not leak into the next event
+        assert_eq!(parser.feed("data:x"), None);
+        assert_eq!(
+            parser.feed(""),
+            Some(SseEvent {
+                event: "message".to_string(),
+                data: "x".to_string(),
+            })
+        );
+    }
+}
@@ -0,0 +1,140 @@
+//! Deterministic app states and rendering entry points for tests, so every
+//! view can be snapshot-tested at any terminal size without a terminal,
+//! a cluster or the clock. The app is built from the `mock` fixtures and
+//! never receives a refresh, so nothing time-dependent is drawn.
+
+use crate::app::{App, InputMode, ViewMode};
+use crate::mock::{self, Topology};
+use crate::models::{ClusterInfo, TierInfo};
+use crate::source::HttpSource;
+use crate::ui;
+use crate::ui::screenshot::buffer_to_text;
+use ratatui::{backend::TestBackend, buffer::Buffer, Terminal};
+use std::sync::mpsc::channel;
+
+/// Terminal sizes the snapshot tests render every view at
+pub const SIZES: [(u16, u16); 3] = [(80, 24), (120, 30), (160, 40)];
+
+/// URL of the app under test; no tokens are ever saved for it
+const BASE_URL: &str = "http://test:8080";
+
+/// An `App` with cluster data loaded, in a chosen view and state
+#[derive(Debug, Clone)]
+pub struct AppBuilder {
+    cluster_info: Option<ClusterInfo>,
+    tiers: Vec<TierInfo>,
+    view: ViewMode,
+    filter: Option<String>,
+    expanded: bool,
+    selected: usize,
+}
+
+impl Default for AppBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AppBuilder {
+    /// The cluster the tests use: two tiers, three replicasets, six instances
+    pub fn new() -> Self {
+        Self {
+            cluster_info: Some(serde_json::from_value(mock::cluster_info()).unwrap()),
+            tiers: serde_json::from_value(mock::tiers()).unwrap(),
+            view: ViewMode::Tiers,
+            filter: None,
+            expanded: false,
+            selected: 0,
+        }
+    }
+
+    /// No data yet, as before the first refresh answers
+    pub fn empty() -> Self {
+        Self {
+            cluster_info: None,
+            tiers: Vec::new(),
+            ..Self::new()
+        }
+    }
+
+    /// A generated cluster of `topology` instead of the fixture
+    pub fn topology(mut self, topology: Topology) -> Self {
+        let tiers = mock::generate(topology, "127.0.0.1:8080");
+        self.tiers = serde_json::from_value(tiers).unwrap();
+        self
+    }
+
+    pub fn tiers(mut self, tiers: Vec<TierInfo>) -> Self {
+        self.tiers = tiers;
+        self
+    }
+
+    pub fn cluster_info(mut self, cluster_info: Option<ClusterInfo>) -> Self {
+        self.cluster_info = cluster_info;
+        self
+    }
+
+    pub fn view(mut self, view: ViewMode) -> Self {
+        self.view = view;
+        self
+    }
+
+    /// Filter the Instances view, as `--filter` does
+    pub fn filter(mut self, filter: impl Into<String>) -> Self {
+        self.filter = Some(filter.into());
+        self
+    }
+
+    /// Expand every tier and replicaset of the tree views
+    pub fn expanded(mut self, expanded: bool) -> Self {
+        self.expanded = expanded;
+        self
+    }
+
+    /// Row to select, clamped to the rows the view has
+    pub fn selected(mut self, row: usize) -> Self {
+        self.selected = row;
+        self
+    }
+
+    pub fn build(self) -> App {
+        // Nothing answers: the app shows exactly the data given here
+        let (req_tx, _req_rx) = channel();
+        let (_res_tx, res_rx) = channel();
+        let mut app = App::new(BASE_URL.to_string(), HttpSource::new(req_tx, res_rx));
+        app.input_mode = InputMode::Normal;
+        app.cluster_info = self.cluster_info;
+        app.set_tiers(self.tiers);
+
+        if self.expanded {
+            for (t, tier) in app.tiers.iter().enumerate() {
+                app.expanded_tiers.insert(t);
+                for r in 0..tier.replicasets.len() {
+                    app.expanded_replicasets.insert((t, r));
+                }
+            }
+            app.rebuild_tree();
+        }
+
+        app.switch_view(self.view);
+        if let Some(filter) = self.filter {
+            app.set_filter(filter);
+        }
+        for _ in 0..self.selected.min(app.get_item_count().saturating_sub(1)) {
+            app.select_next();
+        }
+        app
+    }
+}
+
+/// Draw `app` on a `width`x`height` terminal
+pub fn render(app: &mut App, width: u16, height: u16) -> Buffer {
+    let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+    terminal.draw(|frame| ui::draw(frame, app)).unwrap();
+    terminal.backend().buffer().clone()
+}
+
+/// Draw `app` and return the screen as text, for golden files
+pub fn render_text(app: &mut App, width: u16, height: u16) -> String {
+    buffer_to_text(&render(app, width, height))
+}
@@ -0,0 +1,143 @@
+use crate::api::{ApiResponse, ResponseMeta};
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// Recent latencies kept per endpoint for the percentile; min and average
+/// cover the whole session
+const PERCENTILE_WINDOW: usize = 1000;
+
+/// Request figures of one endpoint this session
+#[derive(Debug, Clone, Default)]
+pub struct EndpointTimings {
+    pub requests: usize,
+    pub errors: usize,
+    pub bytes: u64,
+    min: Option<Duration>,
+    total: Duration,
+    recent: VecDeque<Duration>,
+}
+
+impl EndpointTimings {
+    fn record(&mut self, meta: &ResponseMeta, ok: bool) {
+        self.requests += 1;
+        if !ok {
+            self.errors += 1;
+        }
+        self.bytes += meta.bytes;
+        self.min = Some(self.min.map_or(meta.latency, |min| min.min(meta.latency)));
+        self.total += meta.latency;
+        if self.recent.len() == PERCENTILE_WINDOW {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(meta.latency);
+    }
+
+    pub fn min(&self) -> Option<Duration> {
+        self.min
+    }
+
+    pub fn avg(&self) -> Option<Duration> {
+        (self.requests > 0).then(|| self.total / self.requests as u32)
+    }
+
+    /// 95th percentile of the recent latencies (nearest rank)
+    pub fn p95(&self) -> Option<Duration> {
+        let mut sorted: Vec<Duration> = self.recent.iter().copied().collect();
+        sorted.sort_unstable();
+        let rank = (sorted.len() * 95).div_ceil(100);
+        sorted.get(rank.checked_sub(1)?).copied()
+    }
+}
+
+/// Request figures per endpoint, in the order endpoints were first seen
+#[derive(Debug, Clone, Default)]
+pub struct SessionTimings {
+    endpoints: Vec<(&'static str, EndpointTimings)>,
+}
+
+impl SessionTimings {
+    /// Count a response from the worker. Pushed stream events aren't
+    /// requests and are skipped.
+    pub fn record(&mut self, response: &ApiResponse, meta: &ResponseMeta) {
+        let Some((endpoint, ok)) = classify(response) else {
+            return;
+        };
+        let index = match self.endpoints.iter().position(|(e, _)| *e == endpoint) {
+            Some(index) => index,
+            None => {
+                self.endpoints.push((endpoint, EndpointTimings::default()));
+                self.endpoints.len() - 1
+            }
+        };
+        self.endpoints[index].1.record(meta, ok);
+    }
+
+    pub fn endpoints(&self) -> &[(&'static str, EndpointTimings)] {
+        &self.endpoints
+    }
+}
+
+/// Endpoint a response came from and whether the request succeeded
+fn classify(response: &ApiResponse) -> Option<(&'static str, bool)> {
+    Some(match response {
+        ApiResponse::Config(result) => ("config", result.is_ok()),
+        ApiResponse::ServerCheck(result) => ("config", result.is_ok()),
+        ApiResponse::Login(result) => ("session", result.is_ok()),
+        ApiResponse::ClusterInfo(result) => ("cluster", result.is_ok()),
+        ApiResponse::Tiers(result) => ("tiers", result.is_ok()),
+        ApiResponse::PreparedTiers(_) => ("tiers", true),
+        ApiResponse::HealthStatus(result) => ("health", result.is_ok()),
+        ApiResponse::HealthProbes(probes) => ("health", probes.results.iter().all(Result::is_ok)),
+        ApiResponse::Tables(result) => ("tables", result.is_ok()),
+        ApiResponse::Parameters(result) => ("parameters", result.is_ok()),
+        ApiResponse::NotModified(endpoint) => (endpoint.label(), true),
+        ApiResponse::Stream(_) | ApiResponse::WorkerPanic(_) => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::Endpoint;
+
+    fn meta(millis: u64, bytes: u64) -> ResponseMeta {
+        ResponseMeta {
+            latency: Duration::from_millis(millis),
+            reachable: true,
+            bytes,
+        }
+    }
+
+    #[test]
+    fn test_timings_per_endpoint() {
+        let mut timings = SessionTimings::default();
+        for millis in 1..=20 {
+            timings.record(&ApiResponse::NotModified(Endpoint::Tiers), &meta(millis, 0));
+        }
+        timings.record(
+            &ApiResponse::ClusterInfo(Err("HTTP 500".to_string())),
+            &meta(7, 120),
+        );
+        timings.record(
+            &ApiResponse::ClusterInfo(Err("HTTP 500".to_string())),
+            &meta(3, 80),
+        );
+
+        let endpoints = timings.endpoints();
+        assert_eq!(endpoints.len(), 2);
+
+        let (name, tiers) = &endpoints[0];
+        assert_eq!(*name, "tiers");
+        assert_eq!(tiers.requests, 20);
+        assert_eq!(tiers.errors, 0);
+        assert_eq!(tiers.min(), Some(Duration::from_millis(1)));
+        assert_eq!(tiers.avg(), Some(Duration::from_micros(10_500)));
+        assert_eq!(tiers.p95(), Some(Duration::from_millis(19)));
+
+        let (name, cluster) = &endpoints[1];
+        assert_eq!(*name, "cluster");
+        assert_eq!(cluster.errors, 2);
+        assert_eq!(cluster.bytes, 200);
+        assert_eq!(cluster.avg(), Some(Duration::from_millis(5)));
+    }
+}
@@ -0,0 +1,64 @@
+//! Short-lived notifications in the top right corner for things that just
+//! happened (a refresh failing, a file written, an instance coming back),
+//! so they don't all compete for the one status bar message.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// How long a toast stays on screen
+pub const TOAST_DURATION: Duration = Duration::from_secs(4);
+
+/// Toasts shown at once; the oldest goes first
+const MAX_TOASTS: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToastKind {
+    Info,
+    Success,
+    Error,
+}
+
+#[derive(Debug, Clone)]
+pub struct Toast {
+    pub kind: ToastKind,
+    pub message: String,
+    shown_at: Instant,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Toasts {
+    /// Oldest first
+    items: VecDeque<Toast>,
+}
+
+impl Toasts {
+    /// Show a toast. The same message again only restarts its countdown.
+    pub fn push(&mut self, kind: ToastKind, message: impl Into<String>, now: Instant) {
+        let message = message.into();
+        self.items.retain(|toast| toast.message != message);
+        if self.items.len() == MAX_TOASTS {
+            self.items.pop_front();
+        }
+        self.items.push_back(Toast {
+            kind,
+            message,
+            shown_at: now,
+        });
+    }
+
+    /// Drop the toasts that have been shown long enough. True if any went.
+    pub fn expire(&mut self, now: Instant) -> bool {
+        let before = self.items.len();
+        self.items
+            .retain(|toast| now.saturating_duration_since(toast.shown_at) < TOAST_DURATION);
+        self.items.len() != before
+    }
+
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &Toast> {
+        self.items.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+}
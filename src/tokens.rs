@@ -1,126 +1,335 @@
+use crate::paths;
+use crate::private_files::{create_private_dir, create_private_file};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs::{self, File, OpenOptions};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 
-#[cfg(unix)]
-use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct TokenEntry {
     pub auth: String,
     pub refresh: String,
     pub saved_at: u64,
 }
 
+/// Saved sessions, keyed by normalized URL
+type TokensFile = HashMap<String, TokenEntry>;
+
+/// Saved sessions older than this are pruned unless configured otherwise
+pub const DEFAULT_MAX_AGE_DAYS: u64 = 30;
+
+static MAX_AGE_DAYS: AtomicU64 = AtomicU64::new(DEFAULT_MAX_AGE_DAYS);
+
+/// Set how many days a saved session is kept; 0 keeps them forever
+pub fn set_max_age_days(days: u64) {
+    MAX_AGE_DAYS.store(days, Ordering::Relaxed);
+}
+
 /// Get the path to the tokens file
-fn token_file_path() -> Option<PathBuf> {
-    dirs::config_dir().map(|p| p.join("picotui/tokens.json"))
+pub fn token_file_path() -> Option<PathBuf> {
+    let path = paths::state_dir()?.join("tokens.json");
+    migrate_legacy_tokens(&path);
+    Some(path)
+}
+
+/// Move a tokens file left in the config directory by older versions
+fn migrate_legacy_tokens(path: &Path) {
+    let Some(legacy) = dirs::config_dir().map(|p| p.join("picotui/tokens.json")) else {
+        return;
+    };
+    if path.exists() || !legacy.exists() {
+        return;
+    }
+    if let Some(parent) = path.parent() {
+        if create_private_dir(parent).is_err() {
+            return;
+        }
+    }
+    let _ = fs::rename(&legacy, path);
+}
+
+fn read_tokens_file(path: &Path) -> TokensFile {
+    File::open(path)
+        .ok()
+        .and_then(|file| serde_json::from_reader(file).ok())
+        .unwrap_or_default()
+}
+
+/// Write the tokens file, readable by the owner only. The new contents go
+/// to a temporary file that replaces the old one, so readers never see a
+/// partially written file.
+fn write_tokens_file(path: &Path, tokens: &TokensFile) -> anyhow::Result<()> {
+    let tmp = path.with_extension("json.tmp");
+    let file = create_private_file(&tmp)?;
+
+    serde_json::to_writer_pretty(&file, tokens)?;
+    file.sync_all()?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+/// Hold an exclusive advisory lock on the tokens file until dropped. A
+/// separate lock file is used because writes replace the tokens file itself.
+fn lock_tokens_file(path: &Path) -> anyhow::Result<File> {
+    let lock = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path.with_extension("json.lock"))?;
+    lock.lock()?;
+    Ok(lock)
+}
+
+/// Read-modify-write the tokens file under the lock, so picotui processes
+/// connected to different clusters don't drop each other's sessions
+fn update_tokens_file<T>(
+    path: &Path,
+    update: impl FnOnce(&mut TokensFile) -> T,
+) -> anyhow::Result<T> {
+    let _lock = lock_tokens_file(path)?;
+    let mut tokens = read_tokens_file(path);
+    let result = update(&mut tokens);
+    write_tokens_file(path, &tokens)?;
+    Ok(result)
+}
+
+/// Read the tokens file, pruning expired sessions on disk if there are any
+fn read_current_tokens(path: &Path) -> TokensFile {
+    let mut tokens = read_tokens_file(path);
+    let now = now();
+    if prune(&mut tokens, now) > 0 {
+        let _ = update_tokens_file(path, |tokens| prune(tokens, now));
+    }
+    tokens
+}
+
+/// Drop sessions saved more than the configured age ago. Returns how many
+/// were dropped.
+fn prune(tokens: &mut TokensFile, now: u64) -> usize {
+    let max_age = MAX_AGE_DAYS.load(Ordering::Relaxed) * 24 * 3600;
+    if max_age == 0 {
+        return 0;
+    }
+    let before = tokens.len();
+    tokens.retain(|_, entry| now.saturating_sub(entry.saved_at) <= max_age);
+    before - tokens.len()
+}
+
+fn now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
 }
 
 /// Save tokens for a given URL
 pub fn save_tokens(url: &str, auth: &str, refresh: &str) -> anyhow::Result<()> {
     let path =
-        token_file_path().ok_or_else(|| anyhow::anyhow!("Could not determine config directory"))?;
+        token_file_path().ok_or_else(|| anyhow::anyhow!("Could not determine state directory"))?;
+    save_tokens_in(&path, url, auth, refresh)
+}
 
+fn save_tokens_in(path: &Path, url: &str, auth: &str, refresh: &str) -> anyhow::Result<()> {
     // Create parent directory with restricted permissions
     if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent)?;
-        #[cfg(unix)]
-        fs::set_permissions(parent, fs::Permissions::from_mode(0o700))?;
+        create_private_dir(parent)?;
     }
 
-    // Load existing tokens or create new map
-    let mut tokens: HashMap<String, TokenEntry> = if path.exists() {
-        let file = File::open(&path)?;
-        serde_json::from_reader(file).unwrap_or_default()
-    } else {
-        HashMap::new()
-    };
-
     // Normalize URL (remove trailing slash)
     let normalized_url = url.trim_end_matches('/').to_string();
 
-    // Insert/update token entry
-    tokens.insert(
-        normalized_url,
-        TokenEntry {
-            auth: auth.to_string(),
-            refresh: refresh.to_string(),
-            saved_at: std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .map(|d| d.as_secs())
-                .unwrap_or(0),
-        },
-    );
-
-    // Write file with restricted permissions (owner read/write only)
-    #[cfg(unix)]
-    let file = OpenOptions::new()
-        .write(true)
-        .create(true)
-        .truncate(true)
-        .mode(0o600)
-        .open(&path)?;
-
-    #[cfg(not(unix))]
-    let file = OpenOptions::new()
-        .write(true)
-        .create(true)
-        .truncate(true)
-        .open(&path)?;
+    update_tokens_file(path, |tokens| {
+        let now = now();
+        prune(tokens, now);
 
-    serde_json::to_writer_pretty(file, &tokens)?;
-
-    Ok(())
+        // Insert/update token entry
+        tokens.insert(
+            normalized_url,
+            TokenEntry {
+                auth: auth.to_string(),
+                refresh: refresh.to_string(),
+                saved_at: now,
+            },
+        );
+    })
 }
 
 /// Load tokens for a given URL
 pub fn load_tokens(url: &str) -> Option<TokenEntry> {
-    let path = token_file_path()?;
-    let file = File::open(path).ok()?;
-    let tokens: HashMap<String, TokenEntry> = serde_json::from_reader(file).ok()?;
+    load_tokens_in(&token_file_path()?, url)
+}
+
+fn load_tokens_in(path: &Path, url: &str) -> Option<TokenEntry> {
+    let mut tokens = read_current_tokens(path);
 
     // Normalize URL (remove trailing slash)
     let normalized_url = url.trim_end_matches('/');
 
-    tokens.get(normalized_url).cloned()
+    tokens.remove(normalized_url)
 }
 
-/// Delete tokens for a given URL
-#[allow(dead_code)]
-pub fn delete_tokens(url: &str) -> anyhow::Result<()> {
-    let path =
-        token_file_path().ok_or_else(|| anyhow::anyhow!("Could not determine config directory"))?;
+/// All saved sessions that haven't expired, sorted by URL
+pub fn list_tokens() -> Vec<(String, TokenEntry)> {
+    token_file_path()
+        .map(|path| list_tokens_in(&path))
+        .unwrap_or_default()
+}
+
+fn list_tokens_in(path: &Path) -> Vec<(String, TokenEntry)> {
+    let mut list: Vec<_> = read_current_tokens(path).into_iter().collect();
+    list.sort_by(|a, b| a.0.cmp(&b.0));
+    list
+}
 
+/// Delete every saved session. Returns how many there were.
+pub fn clear_tokens() -> anyhow::Result<usize> {
+    let path =
+        token_file_path().ok_or_else(|| anyhow::anyhow!("Could not determine state directory"))?;
     if !path.exists() {
-        return Ok(());
+        return Ok(0);
+    }
+    let _lock = lock_tokens_file(&path)?;
+    let count = read_tokens_file(&path).len();
+    fs::remove_file(&path)?;
+    Ok(count)
+}
+
+/// Extract the username from a JWT's payload without verifying it.
+/// Looks at the `username`, `user` and `sub` claims, in that order.
+pub fn username_from_jwt(token: &str) -> Option<String> {
+    let payload = token.split('.').nth(1)?;
+    let bytes = decode_base64url(payload)?;
+    let claims: serde_json::Value = serde_json::from_slice(&bytes).ok()?;
+
+    ["username", "user", "sub"]
+        .iter()
+        .find_map(|key| claims.get(key).and_then(|v| v.as_str()))
+        .map(|s| s.to_string())
+}
+
+/// Decode unpadded base64url (RFC 4648 §5), as used in JWT segments
+fn decode_base64url(input: &str) -> Option<Vec<u8>> {
+    fn value(c: u8) -> Option<u32> {
+        match c {
+            b'A'..=b'Z' => Some((c - b'A') as u32),
+            b'a'..=b'z' => Some((c - b'a') as u32 + 26),
+            b'0'..=b'9' => Some((c - b'0') as u32 + 52),
+            b'-' | b'+' => Some(62),
+            b'_' | b'/' => Some(63),
+            _ => None,
+        }
     }
 
-    let file = File::open(&path)?;
-    let mut tokens: HashMap<String, TokenEntry> = serde_json::from_reader(file).unwrap_or_default();
+    let input = input.trim_end_matches('=').as_bytes();
+    let mut out = Vec::with_capacity(input.len() * 3 / 4);
+    for chunk in input.chunks(4) {
+        if chunk.len() == 1 {
+            return None;
+        }
+        let mut acc = 0u32;
+        for (i, &c) in chunk.iter().enumerate() {
+            acc |= value(c)? << (18 - 6 * i);
+        }
+        out.push((acc >> 16) as u8);
+        if chunk.len() > 2 {
+            out.push((acc >> 8) as u8);
+        }
+        if chunk.len() > 3 {
+            out.push(acc as u8);
+        }
+    }
+    Some(out)
+}
+
+/// Delete tokens for a given URL. Returns whether there were any.
+pub fn delete_tokens(url: &str) -> anyhow::Result<bool> {
+    let path =
+        token_file_path().ok_or_else(|| anyhow::anyhow!("Could not determine state directory"))?;
+    delete_tokens_in(&path, url)
+}
+
+fn delete_tokens_in(path: &Path, url: &str) -> anyhow::Result<bool> {
+    if !path.exists() {
+        return Ok(false);
+    }
 
     // Normalize URL (remove trailing slash)
     let normalized_url = url.trim_end_matches('/');
-    tokens.remove(normalized_url);
+    update_tokens_file(path, |tokens| {
+        prune(tokens, now());
+        tokens.remove(normalized_url).is_some()
+    })
+}
 
-    // Write back
-    #[cfg(unix)]
-    let file = OpenOptions::new()
-        .write(true)
-        .create(true)
-        .truncate(true)
-        .mode(0o600)
-        .open(&path)?;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    #[cfg(not(unix))]
-    let file = OpenOptions::new()
-        .write(true)
-        .create(true)
-        .truncate(true)
-        .open(&path)?;
+    #[test]
+    fn test_username_from_jwt() {
+        // {"alg":"HS256","typ":"JWT"} . {"sub":"admin","exp":1700000000} . signature
+        let token = "eyJhbGciOiJIUzI1NiIsInR5cCI6IkpXVCJ9.\
+                     eyJzdWIiOiJhZG1pbiIsImV4cCI6MTcwMDAwMDAwMH0.sig";
+        assert_eq!(username_from_jwt(token), Some("admin".to_string()));
+    }
 
-    serde_json::to_writer_pretty(file, &tokens)?;
+    #[test]
+    fn test_expired_sessions_are_pruned() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tokens.json");
+        save_tokens_in(&path, "http://a:8080/", "a", "ra").unwrap();
 
-    Ok(())
+        // An entry last saved long ago, as left by a cluster no longer used
+        let mut tokens = read_tokens_file(&path);
+        tokens.insert(
+            "http://old:8080".to_string(),
+            TokenEntry {
+                auth: "o".to_string(),
+                refresh: "ro".to_string(),
+                saved_at: now() - (DEFAULT_MAX_AGE_DAYS + 1) * 24 * 3600,
+            },
+        );
+        write_tokens_file(&path, &tokens).unwrap();
+
+        assert_eq!(load_tokens_in(&path, "http://old:8080"), None);
+        let urls: Vec<_> = list_tokens_in(&path)
+            .into_iter()
+            .map(|(url, _)| url)
+            .collect();
+        assert_eq!(urls, vec!["http://a:8080"]);
+        assert!(read_tokens_file(&path).len() == 1, "pruned on disk too");
+
+        assert!(delete_tokens_in(&path, "http://a:8080").unwrap());
+        assert!(!delete_tokens_in(&path, "http://a:8080").unwrap());
+    }
+
+    #[test]
+    fn test_concurrent_saves_keep_every_cluster() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tokens.json");
+
+        let writers: Vec<_> = (0..8)
+            .map(|writer| {
+                let path = path.clone();
+                std::thread::spawn(move || {
+                    for i in 0..10 {
+                        let url = format!("http://cluster-{}-{}:8080", writer, i);
+                        save_tokens_in(&path, &url, "a", "r").unwrap();
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+
+        assert_eq!(read_tokens_file(&path).len(), 80);
+    }
+
+    #[test]
+    fn test_username_from_opaque_token() {
+        assert_eq!(username_from_jwt("test-auth-token-12345"), None);
+        assert_eq!(username_from_jwt("a.!!!.c"), None);
+    }
 }
@@ -0,0 +1,182 @@
+//! Instances laid out by failure domain: two levels of it, such as
+//! datacenter and rack, picked from the keys the instances report.
+
+use crate::models::{InstanceInfo, StateVariant, TierInfo};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// An instance as drawn on the map
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Marker {
+    pub name: String,
+    pub replicaset: String,
+    pub state: StateVariant,
+    pub is_leader: bool,
+}
+
+/// Instances sharing a value of the inner level, such as a rack
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Group {
+    /// `None` for instances that don't report the key
+    pub value: Option<String>,
+    pub instances: Vec<Marker>,
+}
+
+/// Groups sharing a value of the outer level, such as a datacenter
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Area {
+    pub value: Option<String>,
+    pub groups: Vec<Group>,
+}
+
+impl Area {
+    pub fn instances(&self) -> impl Iterator<Item = &Marker> {
+        self.groups.iter().flat_map(|g| g.instances.iter())
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Topology {
+    /// Failure domain keys of the outer and inner levels; fewer when the
+    /// instances report fewer keys
+    pub levels: Vec<String>,
+    pub areas: Vec<Area>,
+}
+
+/// Failure domain keys from the coarsest down: keys more instances report
+/// come first, then keys with fewer distinct values (a datacenter holds
+/// racks, not the other way round), then by name
+pub fn levels(tiers: &[TierInfo]) -> Vec<String> {
+    let mut keys: BTreeMap<&str, (usize, BTreeSet<&str>)> = BTreeMap::new();
+    for inst in instances(tiers) {
+        for (key, value) in &inst.failure_domain {
+            let entry = keys.entry(key).or_default();
+            entry.0 += 1;
+            entry.1.insert(value);
+        }
+    }
+    let mut keys: Vec<(&str, usize, usize)> = keys
+        .into_iter()
+        .map(|(key, (count, values))| (key, count, values.len()))
+        .collect();
+    // Stable sort keeps equal keys in name order
+    keys.sort_by_key(|&(_, count, values)| (std::cmp::Reverse(count), values));
+    keys.into_iter()
+        .map(|(key, _, _)| key.to_string())
+        .collect()
+}
+
+/// Lay out the non-expelled instances by the two coarsest failure domain
+/// levels. Areas and groups are sorted by value, unset values last.
+pub fn build(tiers: &[TierInfo]) -> Topology {
+    let levels: Vec<String> = levels(tiers).into_iter().take(2).collect();
+    let value = |inst: &InstanceInfo, level: usize| {
+        levels
+            .get(level)
+            .and_then(|key| inst.failure_domain.get(key))
+            .cloned()
+    };
+
+    type Placed = BTreeMap<(bool, Option<String>), Vec<Marker>>;
+    let mut areas: BTreeMap<(bool, Option<String>), Placed> = BTreeMap::new();
+    for rs in tiers.iter().flat_map(|t| t.replicasets.iter()) {
+        for inst in &rs.instances {
+            if inst.current_state == StateVariant::Expelled {
+                continue;
+            }
+            let outer = value(inst, 0);
+            let inner = value(inst, 1);
+            areas
+                .entry((outer.is_none(), outer))
+                .or_default()
+                .entry((inner.is_none(), inner))
+                .or_default()
+                .push(Marker {
+                    name: inst.name.clone(),
+                    replicaset: rs.name.clone(),
+                    state: inst.current_state,
+                    is_leader: inst.is_leader,
+                });
+        }
+    }
+
+    Topology {
+        levels,
+        areas: areas
+            .into_iter()
+            .map(|((_, value), groups)| Area {
+                value,
+                groups: groups
+                    .into_iter()
+                    .map(|((_, value), instances)| Group { value, instances })
+                    .collect(),
+            })
+            .collect(),
+    }
+}
+
+fn instances(tiers: &[TierInfo]) -> impl Iterator<Item = &InstanceInfo> {
+    tiers
+        .iter()
+        .flat_map(|t| t.replicasets.iter())
+        .flat_map(|rs| rs.instances.iter())
+        .filter(|inst| inst.current_state != StateVariant::Expelled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock;
+
+    #[test]
+    fn test_topology_by_failure_domain() {
+        let mut tiers = mock::tiers();
+        tiers[1]["replicasets"][0]["instances"][1]["currentState"] = "Expelled".into();
+        let tiers: Vec<TierInfo> = serde_json::from_value(tiers).unwrap();
+
+        let map = build(&tiers);
+        assert_eq!(
+            map.levels,
+            ["datacenter", "rack"],
+            "every instance has a datacenter"
+        );
+        let layout: Vec<String> = map
+            .areas
+            .iter()
+            .flat_map(|area| {
+                area.groups.iter().flat_map(move |g| {
+                    g.instances.iter().map(move |m| {
+                        let dc = area.value.as_deref().unwrap_or("-");
+                        format!("{}/{}/{}", dc, g.value.as_deref().unwrap_or("-"), m.name)
+                    })
+                })
+            })
+            .collect();
+        assert_eq!(
+            layout,
+            [
+                "dc1/r1/i1",
+                "dc1/r2/i2",
+                "dc1/-/s1-i1",
+                "dc2/r1/i3",
+                "dc2/r2/i4"
+            ],
+            "expelled s1-i2 left out, s1-i1 without a rack last"
+        );
+        let i3 = &map.areas[1].groups[0].instances[0];
+        assert!(i3.is_leader);
+        assert_eq!(i3.state, StateVariant::Offline);
+        assert_eq!(i3.replicaset, "r2");
+
+        // Without failure domains everything lands in one box
+        let mut tiers = tiers;
+        tiers
+            .iter_mut()
+            .flat_map(|t| t.replicasets.iter_mut())
+            .flat_map(|rs| rs.instances.iter_mut())
+            .for_each(|inst| inst.failure_domain.clear());
+        let map = build(&tiers);
+        assert!(map.levels.is_empty());
+        assert_eq!(map.areas.len(), 1);
+        assert_eq!(map.areas[0].instances().count(), 5);
+    }
+}
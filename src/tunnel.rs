@@ -0,0 +1,400 @@
+//! Port forwards to clusters behind a firewall (`--ssh`) or inside
+//! Kubernetes (`--k8s-service`).
+//!
+//! The forward runs as a child process that lives as long as the `Tunnel`
+//! value. The API worker talks to the local end, while sessions, pins and
+//! bookmarks stay keyed by the cluster URL the user typed.
+
+use crate::api::log_debug;
+use anyhow::{anyhow, bail, Context, Result};
+use std::fmt;
+use std::io::{BufRead, BufReader, Read};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::process::{Child, Command, Stdio};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How long to wait for the forward to come up. Long enough to type a
+/// password or confirm a host key.
+const START_TIMEOUT: Duration = Duration::from_secs(60);
+
+const PROBE_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Wait between restarts of a forward, doubled while they keep failing
+const RESTART_DELAY: Duration = Duration::from_secs(1);
+const MAX_RESTART_DELAY: Duration = Duration::from_secs(30);
+
+/// Picodata's HTTP port when `--k8s-service` doesn't name one
+const DEFAULT_K8S_PORT: u16 = 8080;
+
+/// A running port forward, stopped when dropped
+pub struct Tunnel {
+    child: Arc<Mutex<Child>>,
+    stopped: Arc<AtomicBool>,
+    local_url: String,
+}
+
+impl Tunnel {
+    /// Forward a free local port to the host and port of `url` through
+    /// `ssh destination`. ssh may ask for a password or passphrase on the
+    /// terminal, so this must run before the UI takes it over.
+    pub fn ssh(destination: &str, url: &str, debug: bool) -> Result<Self> {
+        let target = Target::parse(url)?;
+        let local_port = free_port()?;
+        let forward = format!(
+            "127.0.0.1:{}:{}:{}",
+            local_port,
+            target.forward_host(),
+            target.port
+        );
+        let destination_arg = destination.to_string();
+        let command = move || {
+            let mut command = Command::new("ssh");
+            command
+                .arg("-N")
+                .args(["-o", "ExitOnForwardFailure=yes"])
+                .args(["-o", "ServerAliveInterval=15"])
+                .args(["-L", &forward])
+                .arg(&destination_arg);
+            command
+        };
+        // A restart could prompt for a password over the UI, so a dropped
+        // ssh connection shows up as an unreachable cluster instead
+        Self::start(command, local_port, false, debug)
+            .map(|tunnel| tunnel.with_url(target.local_url(local_port)))
+            .with_context(|| format!("Could not open an SSH tunnel through {}", destination))
+    }
+
+    /// Forward a free local port to a Kubernetes service with
+    /// `kubectl port-forward`. kubectl gives up when the pod behind the
+    /// service goes away, so it is restarted on the same port until the
+    /// tunnel is dropped.
+    pub fn kubectl(service: &K8sService, debug: bool) -> Result<Self> {
+        let local_port = free_port()?;
+        let service = service.clone();
+        let label = service.to_string();
+        let command = move || {
+            let mut command = Command::new("kubectl");
+            if let Some(context) = &service.context {
+                command.args(["--context", context]);
+            }
+            if let Some(namespace) = &service.namespace {
+                command.args(["--namespace", namespace]);
+            }
+            command
+                .arg("port-forward")
+                .args(["--address", "127.0.0.1"])
+                .arg(format!("svc/{}", service.name))
+                .arg(format!("{}:{}", local_port, service.port));
+            command
+        };
+        Self::start(command, local_port, true, debug)
+            .map(|tunnel| tunnel.with_url(format!("http://127.0.0.1:{}", local_port)))
+            .with_context(|| format!("Could not port-forward to {}", label))
+    }
+
+    /// URL of the cluster API through the tunnel
+    pub fn local_url(&self) -> &str {
+        &self.local_url
+    }
+
+    fn with_url(mut self, local_url: String) -> Self {
+        self.local_url = local_url;
+        self
+    }
+
+    /// Run `command` and wait until `local_port` accepts connections. With
+    /// `restart`, a watcher thread runs it again whenever it exits.
+    fn start(
+        command: impl Fn() -> Command + Send + 'static,
+        local_port: u16,
+        restart: bool,
+        debug: bool,
+    ) -> Result<Self> {
+        let child = Arc::new(Mutex::new(spawn(command(), local_port, debug)?));
+        let stopped = Arc::new(AtomicBool::new(false));
+        if restart {
+            let child = Arc::clone(&child);
+            let stopped = Arc::clone(&stopped);
+            thread::spawn(move || supervise(command, local_port, child, stopped, debug));
+        }
+        Ok(Self {
+            child,
+            stopped,
+            local_url: String::new(),
+        })
+    }
+}
+
+impl Drop for Tunnel {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        if let Ok(mut child) = self.child.lock() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+/// Restart the forward whenever it exits, until the tunnel is stopped
+fn supervise(
+    command: impl Fn() -> Command,
+    local_port: u16,
+    child: Arc<Mutex<Child>>,
+    stopped: Arc<AtomicBool>,
+    debug: bool,
+) {
+    let mut backoff = RESTART_DELAY;
+    while !stopped.load(Ordering::SeqCst) {
+        thread::sleep(backoff);
+        let exited = match child.lock() {
+            Ok(mut child) => matches!(child.try_wait(), Ok(Some(_))),
+            Err(_) => return,
+        };
+        if !exited || stopped.load(Ordering::SeqCst) {
+            backoff = RESTART_DELAY;
+            continue;
+        }
+        // Not under the lock: waiting for the port can take a while, and
+        // dropping the tunnel shouldn't wait for it
+        log_debug(debug, "TUNNEL: forward exited, restarting");
+        match spawn(command(), local_port, debug) {
+            Ok(mut restarted) => {
+                let Ok(mut child) = child.lock() else {
+                    let _ = restarted.kill();
+                    return;
+                };
+                if stopped.load(Ordering::SeqCst) {
+                    let _ = restarted.kill();
+                    let _ = restarted.wait();
+                    return;
+                }
+                *child = restarted;
+                backoff = RESTART_DELAY;
+            }
+            Err(e) => {
+                log_debug(debug, &format!("  ERROR: {:#}", e));
+                backoff = (backoff * 2).min(MAX_RESTART_DELAY);
+            }
+        }
+    }
+}
+
+/// Spawn `command` and wait until `local_port` accepts connections
+fn spawn(mut command: Command, local_port: u16, debug: bool) -> Result<Child> {
+    log_debug(debug, &format!("TUNNEL {:?}", command));
+    let mut child = command
+        .stdin(Stdio::inherit())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Could not run {:?}", command.get_program()))?;
+
+    let local = SocketAddr::from(([127, 0, 0, 1], local_port));
+    let deadline = Instant::now() + START_TIMEOUT;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            let mut stderr = String::new();
+            if let Some(mut pipe) = child.stderr.take() {
+                let _ = pipe.read_to_string(&mut stderr);
+            }
+            let reason = stderr.lines().last().unwrap_or_default().trim().to_string();
+            if reason.is_empty() {
+                bail!("exited with {}", status);
+            }
+            bail!("{}", reason);
+        }
+        if TcpStream::connect_timeout(&local, PROBE_INTERVAL).is_ok() {
+            break;
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            bail!("not ready after {}s", START_TIMEOUT.as_secs());
+        }
+        thread::sleep(PROBE_INTERVAL);
+    }
+
+    // Later complaints go to the debug log, not over the UI
+    if let Some(stderr) = child.stderr.take() {
+        thread::spawn(move || {
+            for line in BufReader::new(stderr).lines().map_while(|line| line.ok()) {
+                log_debug(debug, &format!("TUNNEL: {}", line));
+            }
+        });
+    }
+    log_debug(debug, &format!("  OK: listening on {}", local));
+    Ok(child)
+}
+
+/// A port nobody listens on right now
+fn free_port() -> Result<u16> {
+    let listener = TcpListener::bind(("127.0.0.1", 0))?;
+    Ok(listener.local_addr()?.port())
+}
+
+/// A Kubernetes service exposing the HTTP API (`--k8s-service
+/// [NAMESPACE/]NAME[:PORT]`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct K8sService {
+    /// kubectl context, the current one if not set
+    pub context: Option<String>,
+    /// The context's namespace if not set
+    pub namespace: Option<String>,
+    pub name: String,
+    pub port: u16,
+}
+
+impl FromStr for K8sService {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (namespace, rest) = match s.split_once('/') {
+            Some((namespace, rest)) => (Some(namespace.to_string()), rest),
+            None => (None, s),
+        };
+        let (name, port) = match rest.split_once(':') {
+            Some((name, port)) => (
+                name,
+                port.parse()
+                    .map_err(|_| anyhow!("Invalid port in service {}", s))?,
+            ),
+            None => (rest, DEFAULT_K8S_PORT),
+        };
+        if name.is_empty() || namespace.as_deref() == Some("") {
+            bail!("Expected a service as [NAMESPACE/]NAME[:PORT], got {}", s);
+        }
+        Ok(Self {
+            context: None,
+            namespace,
+            name: name.to_string(),
+            port,
+        })
+    }
+}
+
+/// `k8s://[CONTEXT/][NAMESPACE/]NAME:PORT`, which also keys saved sessions
+impl fmt::Display for K8sService {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "k8s://")?;
+        if let Some(context) = &self.context {
+            write!(f, "{}/", context)?;
+        }
+        if let Some(namespace) = &self.namespace {
+            write!(f, "{}/", namespace)?;
+        }
+        write!(f, "{}:{}", self.name, self.port)
+    }
+}
+
+/// The parts of a cluster URL a forward needs
+#[derive(Debug, PartialEq)]
+struct Target {
+    scheme: String,
+    host: String,
+    port: u16,
+    /// Everything after the host and port, e.g. a path prefix
+    rest: String,
+}
+
+impl Target {
+    fn parse(url: &str) -> Result<Self> {
+        let (scheme, after) = url
+            .split_once("://")
+            .ok_or_else(|| anyhow!("Expected a URL like http://host:port, got {}", url))?;
+        let default_port = match scheme {
+            "http" => 80,
+            "https" => 443,
+            _ => bail!("Unsupported URL scheme {}", scheme),
+        };
+        let (authority, rest) = match after.find('/') {
+            Some(i) => after.split_at(i),
+            None => (after, ""),
+        };
+        // [v6]:port, host:port or a bare host
+        let (host, port) = if let Some(v6) = authority.strip_prefix('[') {
+            let (host, port) = v6
+                .split_once(']')
+                .ok_or_else(|| anyhow!("Invalid host in {}", url))?;
+            (host, port.strip_prefix(':'))
+        } else {
+            match authority.rsplit_once(':') {
+                Some((host, port)) => (host, Some(port)),
+                None => (authority, None),
+            }
+        };
+        if host.is_empty() {
+            bail!("No host in {}", url);
+        }
+        let port = match port {
+            Some(port) => port
+                .parse()
+                .map_err(|_| anyhow!("Invalid port in {}", url))?,
+            None => default_port,
+        };
+        Ok(Self {
+            scheme: scheme.to_string(),
+            host: host.to_string(),
+            port,
+            rest: rest.to_string(),
+        })
+    }
+
+    /// Host as ssh expects it in a forward spec
+    fn forward_host(&self) -> String {
+        if self.host.contains(':') {
+            format!("[{}]", self.host)
+        } else {
+            self.host.clone()
+        }
+    }
+
+    fn local_url(&self, local_port: u16) -> String {
+        format!("{}://127.0.0.1:{}{}", self.scheme, local_port, self.rest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_target() {
+        let target = Target::parse("http://10.0.0.5:8081").unwrap();
+        assert_eq!((target.host.as_str(), target.port), ("10.0.0.5", 8081));
+        assert_eq!(target.local_url(40000), "http://127.0.0.1:40000");
+
+        let target = Target::parse("https://picodata.internal/ui").unwrap();
+        assert_eq!(target.port, 443);
+        assert_eq!(target.local_url(40000), "https://127.0.0.1:40000/ui");
+
+        let target = Target::parse("http://[fd00::1]:8080/").unwrap();
+        assert_eq!((target.host.as_str(), target.port), ("fd00::1", 8080));
+        assert_eq!(target.forward_host(), "[fd00::1]");
+
+        assert!(Target::parse("localhost:8080").is_err());
+        assert!(Target::parse("http://host:port").is_err());
+    }
+
+    #[test]
+    fn test_parse_k8s_service() {
+        let service: K8sService = "picodata".parse().unwrap();
+        assert_eq!(service.namespace, None);
+        assert_eq!(service.port, DEFAULT_K8S_PORT);
+
+        let mut service: K8sService = "db/picodata-http:8081".parse().unwrap();
+        assert_eq!(service.namespace.as_deref(), Some("db"));
+        assert_eq!(
+            (service.name.as_str(), service.port),
+            ("picodata-http", 8081)
+        );
+        service.context = Some("prod".to_string());
+        assert_eq!(service.to_string(), "k8s://prod/db/picodata-http:8081");
+
+        assert!("db/".parse::<K8sService>().is_err());
+        assert!("picodata:http".parse::<K8sService>().is_err());
+    }
+}
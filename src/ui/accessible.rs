@@ -0,0 +1,66 @@
+//! Screen-reader friendly rendering (`--accessible`): plain ASCII instead
+//! of box-drawing and block characters, and words next to signals that are
+//! otherwise carried by color alone.
+
+use ratatui::buffer::Buffer;
+
+/// Word for a memory usage level the gauge and figure colors convey, or
+/// `None` below the warning threshold. Same thresholds as the colors.
+pub fn usage_level(usage: f64) -> Option<&'static str> {
+    if usage < 70.0 {
+        None
+    } else if usage < 90.0 {
+        Some("high")
+    } else {
+        Some("critical")
+    }
+}
+
+/// ASCII stand-in for a box-drawing, block or arrow symbol
+fn plain_symbol(symbol: &str) -> Option<&'static str> {
+    let mut chars = symbol.chars();
+    let c = chars.next()?;
+    if chars.next().is_some() {
+        return None;
+    }
+    Some(match c {
+        '─' | '━' | '═' | '╌' | '┄' => "-",
+        '│' | '┃' | '║' | '╎' | '┆' => "|",
+        '\u{2500}'..='\u{257f}' => "+",
+        '█' | '▓' | '▒' | '▌' | '▐' => "#",
+        '░' => ".",
+        '▶' | '▸' | '►' => ">",
+        '▼' | '▾' => "v",
+        '◀' | '◂' => "<",
+        '▲' | '▴' => "^",
+        _ => return None,
+    })
+}
+
+/// Replace decorative symbols in a rendered frame with ASCII
+pub fn plain_buffer(buf: &mut Buffer) {
+    for cell in buf.content.iter_mut() {
+        if let Some(plain) = plain_symbol(cell.symbol()) {
+            cell.set_symbol(plain);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plain_symbol() {
+        assert_eq!(plain_symbol("┌"), Some("+"));
+        assert_eq!(plain_symbol("─"), Some("-"));
+        assert_eq!(plain_symbol("│"), Some("|"));
+        assert_eq!(plain_symbol("▼"), Some("v"));
+        assert_eq!(plain_symbol("█"), Some("#"));
+        assert_eq!(plain_symbol("a"), None);
+        assert_eq!(plain_symbol("★"), None);
+        assert_eq!(usage_level(50.0), None);
+        assert_eq!(usage_level(75.0), Some("high"));
+        assert_eq!(usage_level(95.0), Some("critical"));
+    }
+}
@@ -1,17 +1,34 @@
-use super::format_bytes;
+use super::accessible::usage_level;
+use super::format::NumberFormat;
+use crate::health::ClusterHealth;
+use crate::i18n::{tr, trf};
 use crate::models::ClusterInfo;
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Style},
+    style::{Color, Modifier, Style},
     text::{Line, Span},
     widgets::{Block, Borders, Gauge, Paragraph},
     Frame,
 };
 
-pub fn draw_cluster_header(frame: &mut Frame, info: &ClusterInfo, area: Rect) {
-    let block = Block::default()
+pub fn draw_cluster_header(
+    frame: &mut Frame,
+    info: &ClusterInfo,
+    health: Option<&ClusterHealth>,
+    number_format: &NumberFormat,
+    accessible: bool,
+    area: Rect,
+) {
+    let mut block = Block::default()
         .borders(Borders::ALL)
-        .title(" Cluster Info ");
+        .title(format!(" {} ", tr("Cluster Info")));
+
+    // Health badge with the biggest contributing problems
+    if let Some(health) = health {
+        block = block
+            .title(health_badge(health).right_aligned())
+            .title_bottom(health_problems(health));
+    }
 
     let inner = block.inner(area);
     frame.render_widget(block, area);
@@ -27,19 +44,19 @@ pub fn draw_cluster_header(frame: &mut Frame, info: &ClusterInfo, area: Rect) {
 
     // Row 1: Cluster name and version
     let name_line = Line::from(vec![
-        Span::styled("Cluster: ", Style::default().fg(Color::Gray)),
+        Span::styled(tr("Cluster: "), Style::default().fg(Color::Gray)),
         Span::styled(&info.cluster_name, Style::default().fg(Color::White)),
         Span::raw("  │  "),
-        Span::styled("Version: ", Style::default().fg(Color::Gray)),
+        Span::styled(tr("Version: "), Style::default().fg(Color::Gray)),
         Span::styled(&info.cluster_version, Style::default().fg(Color::Cyan)),
         Span::raw("  │  "),
-        Span::styled("Picodata: ", Style::default().fg(Color::Gray)),
+        Span::styled(tr("Picodata: "), Style::default().fg(Color::Gray)),
         Span::styled(
             &info.current_instance_version,
             Style::default().fg(Color::Cyan),
         ),
         Span::raw("  │  "),
-        Span::styled("Replicasets: ", Style::default().fg(Color::Gray)),
+        Span::styled(tr("Replicasets: "), Style::default().fg(Color::Gray)),
         Span::styled(
             info.replicasets_count.to_string(),
             Style::default().fg(Color::White),
@@ -61,7 +78,7 @@ pub fn draw_cluster_header(frame: &mut Frame, info: &ClusterInfo, area: Rect) {
     };
 
     let instances_line = Line::from(vec![
-        Span::styled("Instances: ", Style::default().fg(Color::Gray)),
+        Span::styled(tr("Instances: "), Style::default().fg(Color::Gray)),
         Span::styled(format!("{}", online), Style::default().fg(Color::Green)),
         Span::styled("/", Style::default().fg(Color::Gray)),
         Span::styled(format!("{}", total), Style::default().fg(status_color)),
@@ -75,10 +92,10 @@ pub fn draw_cluster_header(frame: &mut Frame, info: &ClusterInfo, area: Rect) {
             Span::raw("")
         },
         Span::raw("  │  "),
-        Span::styled("Plugins: ", Style::default().fg(Color::Gray)),
+        Span::styled(tr("Plugins: "), Style::default().fg(Color::Gray)),
         Span::styled(
             if info.plugins.is_empty() {
-                "none".to_string()
+                tr("none").to_string()
             } else {
                 info.plugins.join(", ")
             },
@@ -104,12 +121,17 @@ pub fn draw_cluster_header(frame: &mut Frame, info: &ClusterInfo, area: Rect) {
         Color::Red
     };
 
-    let label = format!(
-        "Memory: {} / {} ({:.1}%)",
-        format_bytes(used),
-        format_bytes(usable),
-        info.capacity_usage
+    let mut label = trf(
+        "Memory: {} / {} ({}%)",
+        &[
+            &number_format.bytes(used),
+            &number_format.bytes(usable),
+            &format!("{:.1}", info.capacity_usage),
+        ],
     );
+    if let Some(level) = usage_level(info.capacity_usage).filter(|_| accessible) {
+        label.push_str(&format!(" [{}]", tr(level)));
+    }
 
     let gauge = Gauge::default()
         .ratio(ratio.min(1.0))
@@ -118,3 +140,44 @@ pub fn draw_cluster_header(frame: &mut Frame, info: &ClusterInfo, area: Rect) {
 
     frame.render_widget(gauge, chunks[2]);
 }
+
+/// How many problems the header lists next to the score
+const TOP_PROBLEMS: usize = 3;
+
+fn health_badge(health: &ClusterHealth) -> Line<'static> {
+    let color = if health.score >= 90 {
+        Color::Green
+    } else if health.score >= 70 {
+        Color::Yellow
+    } else {
+        Color::Red
+    };
+    Line::from(vec![
+        Span::raw(" "),
+        Span::styled(
+            format!(" Health {} ", health.score),
+            Style::default()
+                .fg(Color::Black)
+                .bg(color)
+                .add_modifier(Modifier::BOLD),
+        ),
+        Span::raw(" "),
+    ])
+}
+
+fn health_problems(health: &ClusterHealth) -> Line<'static> {
+    if health.problems.is_empty() {
+        return Line::from("");
+    }
+    let problems = health
+        .problems
+        .iter()
+        .take(TOP_PROBLEMS)
+        .map(|p| p.description.as_str())
+        .collect::<Vec<_>>()
+        .join(" · ");
+    Line::from(Span::styled(
+        format!(" {} ", problems),
+        Style::default().fg(Color::Yellow),
+    ))
+}
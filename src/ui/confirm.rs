@@ -0,0 +1,105 @@
+use super::centered_rect;
+use crate::app::DryRun;
+use crate::confirm::{ConfirmDialog, Severity};
+use crate::i18n::tr;
+use ratatui::{
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+
+/// Draw the confirmation dialog above everything else
+pub fn draw_confirm<A>(frame: &mut Frame, dialog: &ConfirmDialog<A>, area: Rect) {
+    let popup_area = centered_rect(60, 40, area);
+
+    frame.render_widget(Clear, popup_area);
+
+    let color = severity_color(dialog.severity);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(color))
+        .title(Span::styled(
+            format!(" {}: {} ", tr(dialog.severity.label()), tr(&dialog.title)),
+            Style::default().fg(color).add_modifier(Modifier::BOLD),
+        ))
+        .style(Style::default().bg(Color::Black));
+
+    let inner = block.inner(popup_area);
+    frame.render_widget(block, popup_area);
+
+    let dim = Style::default().fg(Color::DarkGray);
+    let mut lines = vec![Line::from(tr(&dialog.message)), Line::from("")];
+    if dialog.needs_typing() {
+        lines.push(Line::from(vec![
+            Span::raw(format!("{} ", tr("Type"))),
+            Span::styled(
+                dialog.expected.clone(),
+                Style::default().fg(color).add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(format!(" {}", tr("to confirm:"))),
+        ]));
+        let input_style = if dialog.is_confirmed() {
+            Style::default().fg(Color::Green)
+        } else {
+            Style::default().fg(Color::White)
+        };
+        lines.push(Line::from(vec![
+            Span::styled("> ", dim),
+            Span::styled(format!("{}_", dialog.input), input_style),
+        ]));
+        lines.push(Line::from(""));
+    }
+    let hint = if dialog.is_confirmed() {
+        tr("Enter to confirm, Esc to cancel")
+    } else {
+        tr("Esc to cancel")
+    };
+    lines.push(Line::from(Span::styled(hint, dim)));
+
+    frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }), inner);
+}
+
+/// Draw the commands a dry run would have run
+pub fn draw_dry_run(frame: &mut Frame, dry_run: &DryRun, area: Rect) {
+    let popup_area = centered_rect(80, 70, area);
+
+    frame.render_widget(Clear, popup_area);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!(" {}: {} ", tr("Dry run"), dry_run.title))
+        .style(Style::default().bg(Color::Black));
+
+    let inner = block.inner(popup_area);
+    frame.render_widget(block, popup_area);
+
+    let dim = Style::default().fg(Color::DarkGray);
+    let mut lines: Vec<Line> = dry_run
+        .lines
+        .iter()
+        .map(|line| {
+            if line.starts_with('#') {
+                Line::from(Span::styled(line.clone(), dim))
+            } else {
+                Line::from(line.clone())
+            }
+        })
+        .collect();
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        tr("Nothing was run. y to copy the commands, Esc to close"),
+        dim,
+    )));
+
+    frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }), inner);
+}
+
+fn severity_color(severity: Severity) -> Color {
+    match severity {
+        Severity::Caution => Color::Yellow,
+        Severity::Danger => Color::Red,
+        Severity::Critical => Color::Magenta,
+    }
+}
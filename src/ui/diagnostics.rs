@@ -0,0 +1,71 @@
+use crate::app::App;
+use crate::i18n::{tr, trf};
+use ratatui::{
+    layout::Rect,
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+use std::time::Duration;
+
+const WIDTH: u16 = 46;
+pub(super) const HEIGHT: u16 = 8;
+
+/// Frame diagnostics in the top right corner, over whatever is on screen.
+/// Figures are from the frame before this one, which can't time itself.
+pub fn draw_diagnostics(frame: &mut Frame, app: &App, area: Rect) {
+    let width = WIDTH.min(area.width);
+    let height = HEIGHT.min(area.height);
+    let overlay = Rect::new(area.right() - width, area.y, width, height);
+
+    frame.render_widget(Clear, overlay);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Magenta))
+        .title(format!(" {} ", tr("Diagnostics")))
+        .style(Style::default().bg(Color::Black));
+    let inner = block.inner(overlay);
+    frame.render_widget(block, overlay);
+
+    let stats = &app.frame_stats;
+    let millis = |took: Option<Duration>| match took {
+        Some(took) => format!("{:.1}", took.as_secs_f64() * 1000.0),
+        None => "-".to_string(),
+    };
+    let label_style = Style::default().fg(Color::Cyan);
+    let row = |label: &str, value: String| {
+        Line::from(vec![
+            Span::styled(format!("{:<10} ", tr(label)), label_style),
+            Span::raw(value),
+        ])
+    };
+    let lines = vec![
+        row(
+            "Frame",
+            trf(
+                "{} ms, avg {}, max {}",
+                &[
+                    &millis(stats.last()),
+                    &millis(stats.avg()),
+                    &millis(stats.max()),
+                ],
+            ),
+        ),
+        row("Frames", stats.frames.to_string()),
+        row("Rows", trf("{} of {}", &[&stats.rows.0, &stats.rows.1])),
+        row(
+            "Responses",
+            trf(
+                "{} last frame, {} at most per tick",
+                &[&stats.responses, &stats.peak_responses],
+            ),
+        ),
+        row("In flight", stats.in_flight.to_string()),
+        Line::from(Span::styled(
+            tr("F12 to hide"),
+            Style::default().fg(Color::DarkGray),
+        )),
+    ];
+    frame.render_widget(Paragraph::new(lines), inner);
+}
@@ -0,0 +1,243 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Local, Utc};
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Unit system for memory sizes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ByteUnits {
+    /// Powers of 1024 (KiB, MiB, GiB)
+    #[default]
+    Binary,
+    /// Powers of 1000 (kB, MB, GB)
+    Si,
+}
+
+impl ByteUnits {
+    fn base(self) -> f64 {
+        match self {
+            ByteUnits::Binary => 1024.0,
+            ByteUnits::Si => 1000.0,
+        }
+    }
+
+    fn names(self) -> &'static [&'static str] {
+        match self {
+            ByteUnits::Binary => &["B", "KiB", "MiB", "GiB", "TiB", "PiB"],
+            ByteUnits::Si => &["B", "kB", "MB", "GB", "TB", "PB"],
+        }
+    }
+}
+
+impl FromStr for ByteUnits {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "binary" | "iec" => Ok(ByteUnits::Binary),
+            "si" | "decimal" => Ok(ByteUnits::Si),
+            _ => Err(anyhow!("Unknown units '{}' (expected binary or si)", s)),
+        }
+    }
+}
+
+/// Display preferences for memory sizes and counts.
+/// All numbers shown in the UI go through this, so the options apply everywhere.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NumberFormat {
+    pub units: ByteUnits,
+    /// Digits after the decimal point for scaled sizes
+    pub precision: usize,
+    /// Group integer digits with commas ("3,000")
+    pub thousands_separator: bool,
+}
+
+impl Default for NumberFormat {
+    fn default() -> Self {
+        Self {
+            units: ByteUnits::Binary,
+            precision: 1,
+            thousands_separator: false,
+        }
+    }
+}
+
+impl NumberFormat {
+    /// Format a size in bytes using the largest unit that keeps the value below the base
+    pub fn bytes(&self, bytes: u64) -> String {
+        if bytes == 0 {
+            return "0 B".to_string();
+        }
+
+        let base = self.units.base();
+        let names = self.units.names();
+        let mut size = bytes as f64;
+        let mut unit = 0;
+        while size >= base && unit < names.len() - 1 {
+            size /= base;
+            unit += 1;
+        }
+
+        let number = format!("{:.*}", self.precision, size);
+        format!("{} {}", self.group(&number), names[unit])
+    }
+
+    /// Format a plain count (buckets, instances)
+    pub fn count(&self, n: u64) -> String {
+        self.group(&n.to_string())
+    }
+
+    /// Insert thousands separators into the integer part of a formatted number
+    fn group(&self, number: &str) -> String {
+        if !self.thousands_separator {
+            return number.to_string();
+        }
+
+        let (int, frac) = match number.split_once('.') {
+            Some((int, frac)) => (int, Some(frac)),
+            None => (number, None),
+        };
+        let mut grouped = String::new();
+        for (i, ch) in int.chars().enumerate() {
+            if i > 0 && (int.len() - i) % 3 == 0 {
+                grouped.push(',');
+            }
+            grouped.push(ch);
+        }
+        if let Some(frac) = frac {
+            grouped.push('.');
+            grouped.push_str(frac);
+        }
+        grouped
+    }
+}
+
+/// How timestamps are shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TimeFormat {
+    /// "2m ago"
+    #[default]
+    Relative,
+    /// "2026-03-14 15:09:26" in the local timezone
+    Local,
+    /// "2026-03-14 12:09:26 UTC"
+    Utc,
+}
+
+impl FromStr for TimeFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "relative" => Ok(TimeFormat::Relative),
+            "local" => Ok(TimeFormat::Local),
+            "utc" => Ok(TimeFormat::Utc),
+            _ => Err(anyhow!(
+                "Unknown time format '{}' (expected relative, local or utc)",
+                s
+            )),
+        }
+    }
+}
+
+impl TimeFormat {
+    /// Format a Unix timestamp (seconds), relative to `now` when in relative mode
+    pub fn timestamp(self, secs: u64, now: u64) -> String {
+        match self {
+            TimeFormat::Relative => format_ago(now.saturating_sub(secs)),
+            TimeFormat::Local => match DateTime::from_timestamp(secs as i64, 0) {
+                Some(utc) => utc
+                    .with_timezone(&Local)
+                    .format("%Y-%m-%d %H:%M:%S")
+                    .to_string(),
+                None => "?".to_string(),
+            },
+            TimeFormat::Utc => match DateTime::<Utc>::from_timestamp(secs as i64, 0) {
+                Some(utc) => utc.format("%Y-%m-%d %H:%M:%S UTC").to_string(),
+                None => "?".to_string(),
+            },
+        }
+    }
+}
+
+/// Current Unix time in seconds
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Coarse age: the largest whole unit ("45s ago", "2m ago", "3h ago", "4d ago")
+fn format_ago(secs: u64) -> String {
+    if secs < 5 {
+        "just now".to_string()
+    } else if secs < 60 {
+        format!("{}s ago", secs)
+    } else if secs < 3600 {
+        format!("{}m ago", secs / 60)
+    } else if secs < 86400 {
+        format!("{}h ago", secs / 3600)
+    } else {
+        format!("{}d ago", secs / 86400)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_matches_binary_one_decimal() {
+        let fmt = NumberFormat::default();
+        assert_eq!(fmt.bytes(0), "0 B");
+        assert_eq!(fmt.bytes(512), "512.0 B");
+        assert_eq!(fmt.bytes(4294967296), "4.0 GiB");
+        assert_eq!(fmt.bytes(1288490188), "1.2 GiB");
+    }
+
+    #[test]
+    fn test_si_units_and_precision() {
+        let fmt = NumberFormat {
+            units: ByteUnits::Si,
+            precision: 2,
+            thousands_separator: false,
+        };
+        assert_eq!(fmt.bytes(4294967296), "4.29 GB");
+        assert_eq!(fmt.bytes(1500), "1.50 kB");
+    }
+
+    #[test]
+    fn test_thousands_separator() {
+        let fmt = NumberFormat {
+            thousands_separator: true,
+            ..NumberFormat::default()
+        };
+        assert_eq!(fmt.count(3000), "3,000");
+        assert_eq!(fmt.count(999), "999");
+        assert_eq!(fmt.count(1234567), "1,234,567");
+        assert_eq!(fmt.bytes(1023 * 1024 * 1024 + 512 * 1024), "1,023.5 MiB");
+    }
+
+    #[test]
+    fn test_relative_timestamps() {
+        let now = 1_700_000_000;
+        let fmt = TimeFormat::Relative;
+        assert_eq!(fmt.timestamp(now, now), "just now");
+        assert_eq!(fmt.timestamp(now - 42, now), "42s ago");
+        assert_eq!(fmt.timestamp(now - 150, now), "2m ago");
+        assert_eq!(fmt.timestamp(now - 3 * 3600, now), "3h ago");
+        assert_eq!(fmt.timestamp(now - 2 * 86400, now), "2d ago");
+        assert_eq!(fmt.timestamp(now + 10, now), "just now", "clock skew");
+    }
+
+    #[test]
+    fn test_absolute_utc_timestamp() {
+        assert_eq!(
+            TimeFormat::Utc.timestamp(1_700_000_000, 0),
+            "2023-11-14 22:13:20 UTC"
+        );
+        assert!("utc".parse::<TimeFormat>().is_ok());
+        assert!("yesterday".parse::<TimeFormat>().is_err());
+    }
+}
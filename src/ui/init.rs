@@ -0,0 +1,126 @@
+use super::{centered_rect, spinner_frame};
+use crate::app::App;
+use crate::i18n::{tr, trf};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+use std::time::Instant;
+
+/// Connecting screen shown until the server answers its config, with the
+/// target URL and what can be done while waiting or after a failure
+pub fn draw_init(frame: &mut Frame, app: &App, area: Rect) {
+    let bg = Block::default().style(Style::default().bg(Color::Black));
+    frame.render_widget(bg, area);
+    // A quick connection goes straight to the cluster without a flash
+    if !app.init_screen_due() {
+        return;
+    }
+
+    let popup_area = centered_rect(60, 40, area);
+    frame.render_widget(Clear, popup_area);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!(" picotui - {} ", tr("Picodata Cluster Monitor")))
+        .style(Style::default().bg(Color::Black).fg(Color::White));
+    let inner = block.inner(popup_area);
+    frame.render_widget(block, popup_area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(1)])
+        .margin(1)
+        .split(inner);
+
+    let cyan = Style::default().fg(Color::Cyan);
+    let gray = Style::default().fg(Color::DarkGray);
+    let mut lines = Vec::new();
+    if app.loading {
+        let mut spans = Vec::new();
+        if app.animate() {
+            spans.push(Span::styled(
+                format!("{} ", spinner_frame(app.refresh_started_at)),
+                cyan,
+            ));
+        }
+        spans.push(Span::raw(tr("Connecting to ").to_string()));
+        spans.push(Span::styled(app.base_url.clone(), cyan));
+        lines.push(Line::from(spans));
+    } else {
+        lines.push(Line::from(vec![
+            Span::styled(
+                tr("Could not connect to ").to_string(),
+                Style::default().fg(Color::Red),
+            ),
+            Span::styled(app.base_url.clone(), cyan),
+        ]));
+        if let Some(ref error) = app.last_error {
+            lines.push(Line::from(Span::styled(
+                error.clone(),
+                Style::default().fg(Color::Red),
+            )));
+        }
+        if let Some(at) = app.next_refresh {
+            let remaining = at.saturating_duration_since(Instant::now());
+            lines.push(Line::from(Span::styled(
+                trf(
+                    "Retrying in {}s",
+                    &[&(remaining.as_secs_f64().ceil() as u64)],
+                ),
+                gray,
+            )));
+        }
+    }
+
+    if let Some(ref url) = app.url_prompt {
+        lines.push(Line::from(""));
+        lines.push(Line::from(vec![
+            Span::styled(tr("URL: ").to_string(), cyan),
+            Span::raw(url.clone()),
+            Span::raw("█"),
+        ]));
+        if let Some(ref message) = app.status_message {
+            lines.push(Line::from(Span::styled(
+                message.clone(),
+                Style::default().fg(Color::Yellow),
+            )));
+        }
+    }
+
+    if !app.queued_keys.is_empty() {
+        lines.push(Line::from(""));
+        let queued = match app.queued_keys.len() {
+            1 => tr("1 key will be applied once connected").to_string(),
+            count => trf("{} keys will be applied once connected", &[&count]),
+        };
+        lines.push(Line::from(Span::styled(queued, gray)));
+    }
+    frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }), chunks[0]);
+
+    let key = Style::default().fg(Color::Yellow);
+    let mut hints = Vec::new();
+    if app.url_prompt.is_some() {
+        hints.extend([
+            Span::styled("Enter", key),
+            Span::raw(format!(" {}  ", tr("connect"))),
+            Span::styled("Esc", key),
+            Span::raw(format!(" {}", tr("cancel"))),
+        ]);
+    } else {
+        if !app.loading {
+            hints.push(Span::styled("r", key));
+            hints.push(Span::raw(format!(" {}  ", tr("retry"))));
+        }
+        if app.url_editable {
+            hints.push(Span::styled("e", key));
+            hints.push(Span::raw(format!(" {}  ", tr("edit URL"))));
+        }
+        hints.push(Span::styled("q", key));
+        hints.push(Span::raw(format!(" {}", tr("quit"))));
+    }
+    frame.render_widget(Paragraph::new(Line::from(hints)), chunks[1]);
+}
@@ -1,10 +1,11 @@
 use super::centered_rect;
-use crate::app::{App, LoginFocus};
+use crate::app::{App, LoginFocus, ServerCheck};
+use crate::i18n::{tr, trf};
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Clear, Paragraph},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
     Frame,
 };
 
@@ -19,7 +20,7 @@ pub fn draw_login(frame: &mut Frame, app: &App, area: Rect) {
 
     let block = Block::default()
         .borders(Borders::ALL)
-        .title(" Picodata Login ")
+        .title(format!(" {} ", tr("Picodata Login")))
         .style(Style::default().bg(Color::Black).fg(Color::White));
 
     let inner = block.inner(popup_area);
@@ -39,23 +40,35 @@ pub fn draw_login(frame: &mut Frame, app: &App, area: Rect) {
         .margin(1)
         .split(inner);
 
-    // Instructions
-    let instructions = Paragraph::new(Line::from(vec![
-        Span::raw("Enter your credentials to connect to "),
-        Span::styled("Picodata", Style::default().fg(Color::Cyan)),
-    ]));
+    // Instructions and which server we're talking to
+    let instructions = Paragraph::new(vec![
+        Line::from(vec![
+            Span::raw(tr("Enter your credentials to connect to ").to_string()),
+            Span::styled("Picodata", Style::default().fg(Color::Cyan)),
+        ]),
+        server_check_line(&app.server_check),
+    ]);
     frame.render_widget(instructions, chunks[0]);
 
     // Username field
-    let username_style = if app.login_focus == LoginFocus::Username {
+    let username_style = if app.login_username_error.is_some() {
+        Style::default().fg(Color::Red)
+    } else if app.login_focus == LoginFocus::Username {
         Style::default().fg(Color::Yellow)
     } else {
         Style::default().fg(Color::White)
     };
 
+    let mut username_title = vec![Span::raw(format!(" {} ", tr("Username")))];
+    if let Some(ref error) = app.login_username_error {
+        username_title.push(Span::styled(
+            format!("{} ", tr(error)),
+            Style::default().fg(Color::Red),
+        ));
+    }
     let username_block = Block::default()
         .borders(Borders::ALL)
-        .title(" Username ")
+        .title(Line::from(username_title))
         .border_style(username_style);
 
     let username_inner = username_block.inner(chunks[1]);
@@ -79,33 +92,52 @@ pub fn draw_login(frame: &mut Frame, app: &App, area: Rect) {
         Style::default().fg(Color::White)
     };
 
-    let password_title = if app.login_show_password {
-        " Password (visible) "
-    } else {
-        " Password "
-    };
+    let revealed = app.password_revealed();
+    let password_len = app.login_password.chars().count();
+    let mut password_title = vec![Span::raw(format!(
+        " {} ",
+        tr(if revealed {
+            "Password (visible)"
+        } else {
+            "Password"
+        })
+    ))];
+    // Length only, so a paste can be confirmed without revealing it
+    if password_len > 0 {
+        password_title.push(Span::styled(
+            format!("{} ", trf("{} chars", &[&password_len])),
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
+    let warnings = [
+        app.login_caps_lock.then_some("caps lock on"),
+        app.login_password_warning.as_deref(),
+    ];
+    for warning in warnings.into_iter().flatten() {
+        password_title.push(Span::styled(
+            format!("{} ", tr(warning)),
+            Style::default().fg(Color::Yellow),
+        ));
+    }
     let password_block = Block::default()
         .borders(Borders::ALL)
-        .title(password_title)
+        .title(Line::from(password_title))
         .border_style(password_style);
 
     let password_inner = password_block.inner(chunks[2]);
     frame.render_widget(password_block, chunks[2]);
 
-    let password_display = if app.login_show_password {
+    let password_display = if revealed {
         app.login_password.clone()
     } else {
-        "*".repeat(app.login_password.len())
+        "*".repeat(password_len)
     };
     let password_text = Paragraph::new(password_display);
     frame.render_widget(password_text, password_inner);
 
     // Show cursor in password field
     if app.login_focus == LoginFocus::Password {
-        frame.set_cursor_position((
-            password_inner.x + app.login_password.len() as u16,
-            password_inner.y,
-        ));
+        frame.set_cursor_position((password_inner.x + password_len as u16, password_inner.y));
     }
 
     // Remember me checkbox
@@ -120,35 +152,98 @@ pub fn draw_login(frame: &mut Frame, app: &App, area: Rect) {
     let checkbox_line = Line::from(vec![
         Span::styled(if checkbox_focused { "> " } else { "  " }, checkbox_style),
         Span::styled(checkbox_char, checkbox_style),
-        Span::styled(" Remember me", checkbox_style),
+        Span::styled(format!(" {}", tr("Remember me")), checkbox_style),
         Span::styled(
-            " (save login session)",
+            format!(" {}", tr("(save login session)")),
             Style::default().fg(Color::DarkGray),
         ),
     ]);
     frame.render_widget(Paragraph::new(checkbox_line), chunks[3]);
 
     // Error message
+    // Error message, with failed attempts and any enforced wait below it
     if let Some(ref error) = app.login_error {
-        let error_msg = Paragraph::new(Line::from(vec![Span::styled(
-            error.as_str(),
+        let mut lines = vec![Line::from(Span::styled(
+            tr(error).to_string(),
             Style::default().fg(Color::Red),
-        )]));
-        frame.render_widget(error_msg, chunks[4]);
+        ))];
+        if let Some(wait) = app.login_retry_in() {
+            lines.push(Line::from(Span::styled(
+                trf(
+                    "{} failed attempts, retry in {}s",
+                    &[&app.login_failures, &wait.as_secs_f64().ceil()],
+                ),
+                Style::default().fg(Color::Yellow),
+            )));
+        } else if app.login_failures > 0 {
+            lines.push(Line::from(Span::styled(
+                trf("Failed attempts: {}", &[&app.login_failures]),
+                Style::default().fg(Color::DarkGray),
+            )));
+        }
+        frame.render_widget(Paragraph::new(lines), chunks[4]);
     }
 
     // Submit hint
     let hint = Paragraph::new(Line::from(vec![
         Span::styled("Tab/↑↓", Style::default().fg(Color::Yellow)),
-        Span::raw(" navigate  "),
+        Span::raw(format!(" {}  ", tr("navigate"))),
         Span::styled("Space", Style::default().fg(Color::Yellow)),
-        Span::raw(" toggle  "),
-        Span::styled("^S", Style::default().fg(Color::Yellow)),
-        Span::raw(" show/hide  "),
+        Span::raw(format!(" {}  ", tr("toggle"))),
+        Span::styled("^S/F2", Style::default().fg(Color::Yellow)),
+        Span::raw(format!(" {}  ", tr("show/hide"))),
+        Span::styled("^R", Style::default().fg(Color::Yellow)),
+        Span::raw(format!(" {}  ", tr("hold to peek"))),
+        Span::styled("^T", Style::default().fg(Color::Yellow)),
+        Span::raw(format!(" {}  ", tr("test"))),
         Span::styled("Enter", Style::default().fg(Color::Yellow)),
-        Span::raw(" login  "),
+        Span::raw(format!(" {}  ", tr("login"))),
         Span::styled("Esc", Style::default().fg(Color::Yellow)),
-        Span::raw(" quit"),
-    ]));
+        Span::raw(format!(" {}", tr("quit"))),
+    ]))
+    // Narrow terminals get the hint on two lines
+    .wrap(Wrap { trim: true });
     frame.render_widget(hint, chunks[5]);
 }
+
+fn server_check_line(check: &ServerCheck) -> Line<'static> {
+    match check {
+        ServerCheck::NotChecked => Line::from(Span::styled(
+            tr("Press ^T to test the connection").to_string(),
+            Style::default().fg(Color::DarkGray),
+        )),
+        ServerCheck::Checking => Line::from(Span::styled(
+            tr("Checking server...").to_string(),
+            Style::default().fg(Color::DarkGray),
+        )),
+        ServerCheck::Reachable(identity) => {
+            let ok = Style::default().fg(Color::Green);
+            match (&identity.cluster_name, &identity.version) {
+                (Some(name), version) => {
+                    let mut spans = vec![
+                        Span::styled(tr("✓ Cluster ").to_string(), ok),
+                        Span::styled(name.clone(), Style::default().fg(Color::Cyan)),
+                    ];
+                    if let Some(version) = version {
+                        spans.push(Span::styled(
+                            format!(" (Picodata {})", version),
+                            Style::default().fg(Color::Gray),
+                        ));
+                    }
+                    Line::from(spans)
+                }
+                (None, _) => Line::from(vec![
+                    Span::styled(tr("✓ Server reachable").to_string(), ok),
+                    Span::styled(
+                        tr(" (cluster shown after login)").to_string(),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                ]),
+            }
+        }
+        ServerCheck::Unreachable(error) => Line::from(Span::styled(
+            trf("✗ Server unreachable: {}", &[error]),
+            Style::default().fg(Color::Red),
+        )),
+    }
+}
@@ -1,27 +1,24 @@
+mod accessible;
 mod cluster_header;
+mod confirm;
+mod diagnostics;
+pub mod format;
+mod init;
 mod login;
 mod nodes;
+mod picker;
+pub mod screenshot;
+pub mod theme;
+mod toasts;
 
-use crate::app::{App, InputMode};
+use crate::app::{App, ConnectionStatus, InputMode, RequestProgress};
+use crate::i18n::{tr, trf};
+pub use picker::draw_picker;
+use std::time::Instant;
 
-/// Format bytes in human-readable binary units (KiB, MiB, GiB, etc.)
-pub fn format_bytes(bytes: u64) -> String {
-    const UNITS: &[&str] = &["B", "KiB", "MiB", "GiB", "TiB"];
-    if bytes == 0 {
-        return "0 B".to_string();
-    }
-    let mut size = bytes as f64;
-    for unit in UNITS {
-        if size < 1024.0 {
-            return format!("{:.1} {}", size, unit);
-        }
-        size /= 1024.0;
-    }
-    format!("{:.1} PiB", size)
-}
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Style},
+    style::{Color, Modifier, Style},
     text::{Line, Span},
     widgets::{Block, Borders, Paragraph},
     Frame,
@@ -39,6 +36,9 @@ pub fn draw(frame: &mut Frame, app: &mut App) {
 
     // Draw based on input mode
     match app.input_mode {
+        InputMode::Initializing => {
+            init::draw_init(frame, app, frame.area());
+        }
         InputMode::Login => {
             login::draw_login(frame, app, frame.area());
         }
@@ -46,15 +46,49 @@ pub fn draw(frame: &mut Frame, app: &mut App) {
             draw_header(frame, app, chunks[0]);
             nodes::draw_nodes(frame, app, chunks[1]);
             draw_status_bar(frame, app, chunks[2]);
+            if let Some(ref dry_run) = app.dry_run {
+                confirm::draw_dry_run(frame, dry_run, frame.area());
+            }
+            if let Some(ref dialog) = app.confirm {
+                confirm::draw_confirm(frame, dialog, frame.area());
+            }
         }
     }
+
+    if app.show_diagnostics {
+        diagnostics::draw_diagnostics(frame, app, frame.area());
+    }
+
+    if !app.toasts.is_empty() {
+        // Below the header, and below the diagnostics when they are shown
+        let top = if app.show_diagnostics {
+            frame.area().y + diagnostics::HEIGHT
+        } else {
+            chunks[1].y
+        };
+        toasts::draw_toasts(frame, app, frame.area(), top);
+    }
+
+    if app.is_flashing() {
+        let area = frame.area();
+        frame
+            .buffer_mut()
+            .set_style(area, Style::default().add_modifier(Modifier::REVERSED));
+    }
+
+    app.theme.depth.apply(frame.buffer_mut());
+
+    if app.accessible {
+        accessible::plain_buffer(frame.buffer_mut());
+    }
 }
 
 fn draw_header(frame: &mut Frame, app: &App, area: Rect) {
     let mode_label = format!(" [{}] ", app.view_mode.label());
-    let block = Block::default()
+    let mut block = Block::default()
         .borders(Borders::ALL)
-        .title(" picotui - Picodata Cluster Monitor ")
+        .title(format!(" picotui - {} ", tr("Picodata Cluster Monitor")))
+        .title(connection_badge(app).right_aligned())
         .title_bottom(
             Line::from(vec![Span::styled(
                 mode_label,
@@ -62,23 +96,135 @@ fn draw_header(frame: &mut Frame, app: &App, area: Rect) {
             )])
             .right_aligned(),
         );
+
+    if let Some(updated) = app.last_updated {
+        block = block.title_bottom(Line::from(Span::styled(
+            format!(
+                " {} ",
+                trf(
+                    "Updated {}",
+                    &[&app.time_format.timestamp(updated, format::unix_now())]
+                )
+            ),
+            Style::default().fg(Color::Gray),
+        )));
+    }
+
+    let inner = block.inner(area);
     frame.render_widget(block, area);
+
+    // Version mismatch banner inside the header
+    if let Some(warning) = app.compatibility_warning() {
+        let banner = Paragraph::new(Line::from(vec![
+            Span::styled(" ⚠ ", Style::default().fg(Color::Yellow)),
+            Span::styled(warning, Style::default().fg(Color::Yellow)),
+        ]));
+        frame.render_widget(banner, inner);
+    }
+}
+
+/// Connection badge: status dot, last request latency and target URL
+fn connection_badge(app: &App) -> Line<'static> {
+    let color = match app.connection_status {
+        ConnectionStatus::Connecting => Color::Gray,
+        ConnectionStatus::Connected => Color::Green,
+        ConnectionStatus::Reconnecting => Color::Yellow,
+        ConnectionStatus::Down => Color::Red,
+    };
+
+    let mut spans = Vec::new();
+    if app.read_only {
+        spans.push(Span::styled(
+            format!(" {} ", tr("READ-ONLY")),
+            Style::default()
+                .fg(Color::Black)
+                .bg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        ));
+    }
+    if app.can_change_cluster() {
+        let (label, bg) = if app.ops_dry_run {
+            ("OPS DRY-RUN", Color::Blue)
+        } else {
+            ("OPS", Color::Red)
+        };
+        spans.push(Span::styled(
+            format!(" {} ", tr(label)),
+            Style::default()
+                .fg(Color::White)
+                .bg(bg)
+                .add_modifier(Modifier::BOLD),
+        ));
+    }
+    let alerts = app.alerts.active().count();
+    if alerts > 0 {
+        let label = if alerts == 1 {
+            tr("1 alert").to_string()
+        } else {
+            trf("{} alerts", &[&alerts.to_string()])
+        };
+        spans.push(Span::styled(
+            format!(" ⚠ {} ", label),
+            Style::default()
+                .fg(Color::White)
+                .bg(Color::Red)
+                .add_modifier(Modifier::BOLD),
+        ));
+    }
+    spans.extend([
+        Span::raw(" "),
+        Span::styled("●", Style::default().fg(color)),
+        Span::styled(
+            format!(" {}", tr(app.connection_status.label())),
+            Style::default().fg(color),
+        ),
+    ]);
+
+    if app.is_streaming() {
+        spans.push(Span::styled(" ⇄ live", Style::default().fg(Color::Cyan)));
+    }
+
+    // Changes with every request, which `--slow-link` doesn't redraw for
+    if let Some(latency) = app.last_latency.filter(|_| !app.slow_link) {
+        spans.push(Span::styled(
+            format!("  {} ms", latency.as_millis()),
+            Style::default().fg(Color::Gray),
+        ));
+    }
+
+    spans.push(Span::styled(
+        format!("  {} ", app.base_url),
+        Style::default().fg(Color::DarkGray),
+    ));
+
+    Line::from(spans)
 }
 
 fn draw_status_bar(frame: &mut Frame, app: &App, area: Rect) {
-    use crate::app::ViewMode;
+    let key = app.theme.key_style();
 
     // Show different status bar when filtering
     if app.filter_active {
         let spans = vec![
-            Span::styled(" Filter: ", Style::default().fg(Color::Cyan)),
+            Span::styled(
+                format!(" {}", tr("Filter: ")),
+                Style::default().fg(Color::Cyan),
+            ),
             Span::styled(&app.filter_text, Style::default().fg(Color::White)),
             Span::styled("█", Style::default().fg(Color::White)),
             Span::raw("  │  "),
-            Span::styled("Enter", Style::default().fg(Color::Yellow)),
-            Span::raw(" Apply  "),
-            Span::styled("Esc", Style::default().fg(Color::Yellow)),
-            Span::raw(" Clear  "),
+            Span::styled("Enter", key),
+            hint("Apply"),
+            Span::styled("Esc", key),
+            hint("Clear"),
+            Span::styled("↑↓", key),
+            hint("History"),
+            Span::styled("Tab", key),
+            hint(if app.fuzzy_filter {
+                "Fuzzy: on"
+            } else {
+                "Fuzzy: off"
+            }),
         ];
         let paragraph = Paragraph::new(Line::from(spans))
             .style(Style::default().bg(Color::DarkGray).fg(Color::White));
@@ -86,59 +232,309 @@ fn draw_status_bar(frame: &mut Frame, app: &App, area: Rect) {
         return;
     }
 
-    let mut spans = vec![
-        Span::styled(" ↑↓/jk", Style::default().fg(Color::Yellow)),
-        Span::raw(" Navigate  "),
-    ];
+    // Snapshot name prompt
+    if app.snapshot_prompt_active {
+        let spans = vec![
+            Span::styled(
+                format!(" {}", tr("Snapshot name: ")),
+                Style::default().fg(Color::Cyan),
+            ),
+            Span::styled(&app.snapshot_name, Style::default().fg(Color::White)),
+            Span::styled("█", Style::default().fg(Color::White)),
+            Span::raw("  │  "),
+            Span::styled("Enter", key),
+            hint("Save (empty for timestamp)"),
+            Span::styled("Esc", key),
+            hint("Cancel"),
+        ];
+        let paragraph = Paragraph::new(Line::from(spans))
+            .style(Style::default().bg(Color::DarkGray).fg(Color::White));
+        frame.render_widget(paragraph, area);
+        return;
+    }
+
+    // Search prompt
+    if app.search_active {
+        let spans = vec![
+            Span::styled(
+                format!(" {}", tr("Search: ")),
+                Style::default().fg(Color::Cyan),
+            ),
+            Span::styled(&app.search_text, Style::default().fg(Color::White)),
+            Span::styled("█", Style::default().fg(Color::White)),
+            Span::raw("  │  "),
+            Span::styled("Enter", key),
+            hint("Confirm"),
+            Span::styled("Esc", key),
+            hint("Cancel"),
+        ];
+        let paragraph = Paragraph::new(Line::from(spans))
+            .style(Style::default().bg(Color::DarkGray).fg(Color::White));
+        frame.render_widget(paragraph, area);
+        return;
+    }
+
+    let hints = key_hints(app, key);
+    let spans = match app.status_bar_format {
+        Some(ref template) => expand_status_template(template, |placeholder| {
+            Some(match placeholder {
+                "keys" => hints.clone(),
+                "cluster" => vec![Span::raw(
+                    app.cluster_info
+                        .as_ref()
+                        .map_or("-".to_string(), |info| info.cluster_name.clone()),
+                )],
+                "refresh" => vec![Span::raw(refresh_countdown(app))],
+                "user" => vec![Span::raw(
+                    app.current_user.clone().unwrap_or_else(|| "-".to_string()),
+                )],
+                "url" => vec![Span::raw(app.base_url.clone())],
+                "marked" => vec![Span::raw(app.marked.len().to_string())],
+                "status" => status_spans(app),
+                _ => return None,
+            })
+        }),
+        None => {
+            let mut spans = hints;
+            if app.auth_enabled {
+                spans.push(Span::raw("  │  "));
+                spans.push(Span::styled("🔒 ", Style::default().fg(Color::Green)));
+                spans.push(Span::styled(
+                    trf("user: {}", &[&app.current_user.as_deref().unwrap_or("?")]),
+                    Style::default().fg(Color::White),
+                ));
+            }
+
+            if !app.marked.is_empty() {
+                spans.push(Span::raw("  │  "));
+                spans.push(Span::styled(
+                    trf("{} marked", &[&app.marked.len()]),
+                    Style::default().fg(Color::Green),
+                ));
+            }
+
+            if let Some(register) = app.macros.recording() {
+                spans.push(Span::raw("  │  "));
+                spans.push(Span::styled(
+                    trf("recording @{}", &[&register]),
+                    Style::default().fg(Color::Red),
+                ));
+            }
+
+            let status = status_spans(app);
+            if !status.is_empty() {
+                spans.push(Span::raw("  │  "));
+                spans.extend(status);
+            }
+            spans
+        }
+    };
+
+    let paragraph = Paragraph::new(Line::from(spans))
+        .style(Style::default().bg(Color::DarkGray).fg(Color::White));
+
+    frame.render_widget(paragraph, area);
+}
+
+/// Key hint label, translated and padded to sit between keys
+fn hint(label: &str) -> Span<'static> {
+    Span::raw(format!(" {}  ", tr(label)))
+}
+
+/// Key hints for the current view
+fn key_hints(app: &App, key: Style) -> Vec<Span<'static>> {
+    use crate::app::ViewMode;
+
+    let mut spans = vec![Span::styled(" ↑↓/jk", key), hint("Navigate")];
 
     // Show expand/collapse only in Tiers mode
     if app.view_mode == ViewMode::Tiers {
-        spans.push(Span::styled("←→/hl", Style::default().fg(Color::Yellow)));
-        spans.push(Span::raw(" Collapse/Expand  "));
+        spans.push(Span::styled("←→/hl", key));
+        spans.push(hint("Collapse/Expand"));
     }
 
-    spans.push(Span::styled("Enter", Style::default().fg(Color::Yellow)));
-    spans.push(Span::raw(" Details  "));
-    spans.push(Span::styled("g", Style::default().fg(Color::Yellow)));
-    spans.push(Span::raw(" View  "));
+    spans.push(Span::styled("Enter", key));
+    spans.push(hint("Details"));
+    if app.view_mode != ViewMode::Replicasets {
+        spans.push(Span::styled("a", key));
+        spans.push(hint("Actions"));
+    }
+    if !app.marked.is_empty() {
+        spans.push(Span::styled("e", key));
+        spans.push(hint("Export"));
+        spans.push(Span::styled("y", key));
+        spans.push(hint("Copy names"));
+    }
+    spans.push(Span::styled("g", key));
+    spans.push(hint("View"));
 
     // Show sort and filter options in Instances view
     if app.view_mode == ViewMode::Instances {
-        spans.push(Span::styled("s", Style::default().fg(Color::Yellow)));
-        spans.push(Span::raw(" Sort  "));
-        spans.push(Span::styled("S", Style::default().fg(Color::Yellow)));
-        spans.push(Span::raw(" Order  "));
-        spans.push(Span::styled("/", Style::default().fg(Color::Yellow)));
-        spans.push(Span::raw(" Filter  "));
+        spans.push(Span::styled("s", key));
+        spans.push(hint("Sort"));
+        spans.push(Span::styled("S", key));
+        spans.push(hint("Order"));
+        spans.push(Span::styled("/", key));
+        spans.push(hint("Filter"));
+        if !app.saved_filters.is_empty() {
+            spans.push(Span::styled("F", key));
+            spans.push(hint("Saved"));
+        }
+        spans.push(Span::styled("c", key));
+        spans.push(hint("Columns"));
+        if app.scope.is_some() {
+            spans.push(Span::styled("Esc", key));
+            spans.push(hint("Whole cluster"));
+        } else {
+            spans.push(Span::styled("z/Z", key));
+            spans.push(hint("Siblings"));
+        }
+    }
+
+    if app.search_text.is_empty() {
+        spans.push(Span::styled("?", key));
+        spans.push(hint("Search"));
+    } else {
+        spans.push(Span::styled("n/N", key));
+        spans.push(hint("Next/Prev"));
     }
 
-    spans.push(Span::styled("r", Style::default().fg(Color::Yellow)));
-    spans.push(Span::raw(" Refresh  "));
+    spans.push(Span::styled("r", key));
+    spans.push(hint("Refresh"));
 
     // Show logout option if auth is enabled
     if app.auth_enabled {
-        spans.push(Span::styled("X", Style::default().fg(Color::Yellow)));
-        spans.push(Span::raw(" Logout  "));
+        spans.push(Span::styled("X", key));
+        spans.push(hint("Logout"));
     }
 
-    spans.push(Span::styled("q", Style::default().fg(Color::Yellow)));
-    spans.push(Span::raw(" Quit"));
+    spans.push(Span::styled("q", key));
+    spans.push(Span::raw(format!(" {}", tr("Quit"))));
 
-    if app.loading {
-        spans.push(Span::raw("  │  "));
-        spans.push(Span::styled("Loading...", Style::default().fg(Color::Cyan)));
-    } else if let Some(ref error) = app.last_error {
-        spans.push(Span::raw("  │  "));
+    spans
+}
+
+const SPINNER_FRAMES: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+const SPINNER_FRAME_MS: u128 = 100;
+
+/// Spinner frame for a request running since `started`
+fn spinner_frame(started: Option<Instant>) -> char {
+    let elapsed = started.map_or(0, |at| at.elapsed().as_millis());
+    SPINNER_FRAMES[(elapsed / SPINNER_FRAME_MS) as usize % SPINNER_FRAMES.len()]
+}
+
+/// Spinner followed by each refresh request and where it stands,
+/// e.g. "⠹ cluster ✓  tiers …"
+fn loading_spans(app: &App) -> Vec<Span<'static>> {
+    let cyan = Style::default().fg(Color::Cyan);
+    let mut spans = Vec::new();
+    if app.animate() {
         spans.push(Span::styled(
-            format!("Error: {}", error),
-            Style::default().fg(Color::Red),
+            format!("{} ", spinner_frame(app.refresh_started_at)),
+            cyan,
         ));
     }
+    if app.refresh_progress.is_empty() {
+        spans.push(Span::styled(tr("Loading...").to_string(), cyan));
+        return spans;
+    }
+    for (i, (endpoint, progress)) in app.refresh_progress.iter().enumerate() {
+        if i > 0 {
+            spans.push(Span::raw("  "));
+        }
+        let (mark, color) = match progress {
+            RequestProgress::Pending => ("…", Color::Cyan),
+            RequestProgress::Done => ("✓", Color::Green),
+            RequestProgress::Failed => ("✗", Color::Red),
+        };
+        spans.push(Span::styled(format!("{} ", endpoint.label()), cyan));
+        spans.push(Span::styled(mark, Style::default().fg(color)));
+    }
+    spans
+}
 
-    let paragraph = Paragraph::new(Line::from(spans))
-        .style(Style::default().bg(Color::DarkGray).fg(Color::White));
+/// Loading indicator, last error or status message, whichever applies
+fn status_spans(app: &App) -> Vec<Span<'static>> {
+    // Background refreshes on a slow link go unannounced once data is shown
+    if app.loading && !(app.slow_link && app.cluster_info.is_some()) {
+        loading_spans(app)
+    } else if let Some(ref error) = app.last_error {
+        let mut spans = vec![Span::styled(
+            trf("Error: {}", &[error]),
+            Style::default().fg(Color::Red),
+        )];
+        if let Some(at) = app.next_refresh.filter(|_| app.is_backing_off()) {
+            let remaining = at.saturating_duration_since(Instant::now());
+            spans.push(Span::styled(
+                trf(
+                    " (backing off, next retry in {}s)",
+                    &[&(remaining.as_secs_f64().ceil() as u64)],
+                ),
+                Style::default().fg(Color::Yellow),
+            ));
+        }
+        // Earlier errors may have scrolled by unread
+        if app.error_log.len() > 1 {
+            spans.push(Span::styled(
+                trf(" (! lists {} errors)", &[&app.error_log.len()]),
+                Style::default().fg(Color::DarkGray),
+            ));
+        }
+        spans
+    } else if let Some(ref message) = app.status_message {
+        vec![Span::styled(
+            message.clone(),
+            Style::default().fg(Color::Cyan),
+        )]
+    } else {
+        Vec::new()
+    }
+}
 
-    frame.render_widget(paragraph, area);
+/// Time until the next automatic refresh
+fn refresh_countdown(app: &App) -> String {
+    if app.is_streaming() {
+        return "live".to_string();
+    }
+    match app.next_refresh {
+        Some(at) => {
+            let remaining = at.saturating_duration_since(Instant::now());
+            format!("{}s", remaining.as_secs_f64().ceil() as u64)
+        }
+        None => "off".to_string(),
+    }
+}
+
+/// Expand `{name}` placeholders in a status bar template. Unknown
+/// placeholders and unmatched braces are shown as written.
+fn expand_status_template(
+    template: &str,
+    expand: impl Fn(&str) -> Option<Vec<Span<'static>>>,
+) -> Vec<Span<'static>> {
+    let mut spans = Vec::new();
+    let mut literal = String::new();
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        literal.push_str(&rest[..open]);
+        rest = &rest[open..];
+        let expanded = rest
+            .find('}')
+            .and_then(|close| Some((close, expand(&rest[1..close])?)));
+        match expanded {
+            Some((close, placeholder)) => {
+                spans.push(Span::raw(std::mem::take(&mut literal)));
+                spans.extend(placeholder);
+                rest = &rest[close + 1..];
+            }
+            None => {
+                literal.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    literal.push_str(rest);
+    spans.push(Span::raw(literal));
+    spans
 }
 
 pub fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
@@ -1,53 +1,137 @@
+use super::accessible::usage_level;
+use super::centered_rect;
 use super::cluster_header::draw_cluster_header;
-use super::{centered_rect, format_bytes};
-use crate::app::{App, TreeItem, ViewMode};
+use super::format;
+use super::theme::Theme;
+use crate::app::{App, GroupHealth, InstanceColumn, SortField, SortOrder, TreeItem, ViewMode};
+use crate::compat::Capability;
+use crate::filter::FilterExpr;
+use crate::fuzzy::fuzzy_match;
+use crate::heatmap::{self, Coloring};
+use crate::i18n::{tr, trf};
+use crate::leaders::{leader_distribution, LeaderCount};
 use crate::models::{
     HealthStatusLevel, InstanceInfo, ReplicasetInfo, ReplicasetState, StateVariant,
+    TableDistribution, TierInfo,
 };
+use crate::parameters;
+use crate::rolling::{Outcome, RollingRestart, Step};
+use crate::snapshot::SnapshotDiff;
+use crate::stats::{memory_by_domain, memory_by_tier, MemoryGroup};
+use crate::topology::{self, Marker};
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Wrap},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Wrap},
     Frame,
 };
+use std::ops::Range;
+use std::time::{Duration, Instant};
 
-/// Helper to create spans with filter match highlighting
+/// Split text into spans, highlighting every occurrence of the filter's
+/// positive terms (exclusions and operators are never highlighted)
 fn highlight_match(text: &str, filter: &str, base_style: Style) -> Vec<Span<'static>> {
-    if filter.is_empty() {
-        return vec![Span::styled(text.to_string(), base_style)];
+    let text_lower = text.to_lowercase();
+    let mut marked = vec![false; text.len()];
+    // Lowercasing can change byte lengths for some scripts; only map
+    // offsets back when it didn't
+    if text_lower.len() == text.len() {
+        for term in FilterExpr::parse(filter).positive_terms() {
+            for (start, _) in text_lower.match_indices(term) {
+                marked[start..start + term.len()].fill(true);
+            }
+        }
     }
+    highlight_spans(text, &marked, base_style)
+}
 
-    let filter_lower = filter.to_lowercase();
-    let text_lower = text.to_lowercase();
+/// Split text into spans, highlighting the characters a fuzzy match of each
+/// positive filter term landed on
+fn highlight_fuzzy(text: &str, filter: &str, base_style: Style) -> Vec<Span<'static>> {
+    let mut marked = vec![false; text.len()];
+    for term in FilterExpr::parse(filter).positive_terms() {
+        for start in fuzzy_match(term, text)
+            .map(|m| m.positions)
+            .unwrap_or_default()
+        {
+            let end = start + text[start..].chars().next().map_or(0, char::len_utf8);
+            marked[start..end].fill(true);
+        }
+    }
+    highlight_spans(text, &marked, base_style)
+}
 
+/// Group runs of marked bytes into highlighted spans
+fn highlight_spans(text: &str, marked: &[bool], base_style: Style) -> Vec<Span<'static>> {
+    let highlight = base_style.bg(Color::Yellow).fg(Color::Black);
     let mut spans = Vec::new();
-    let mut last_end = 0;
-
-    for (start, _) in text_lower.match_indices(&filter_lower) {
-        // Add text before match
-        if start > last_end {
-            spans.push(Span::styled(text[last_end..start].to_string(), base_style));
+    let mut run_start = 0;
+    for (idx, _) in text.char_indices().skip(1) {
+        if marked[idx] != marked[run_start] {
+            let style = if marked[run_start] {
+                highlight
+            } else {
+                base_style
+            };
+            spans.push(Span::styled(text[run_start..idx].to_string(), style));
+            run_start = idx;
         }
-        // Add highlighted match
-        let end = start + filter.len();
-        spans.push(Span::styled(
-            text[start..end].to_string(),
-            base_style.bg(Color::Yellow).fg(Color::Black),
-        ));
-        last_end = end;
     }
+    let style = if marked.get(run_start).copied().unwrap_or(false) {
+        highlight
+    } else {
+        base_style
+    };
+    spans.push(Span::styled(text[run_start..].to_string(), style));
+    spans
+}
 
-    // Add remaining text after last match
-    if last_end < text.len() {
-        spans.push(Span::styled(text[last_end..].to_string(), base_style));
+/// Scroll `state` so the selection stays visible and return the range of
+/// rows that fit in `height`. Only these rows get a `ListItem` built, which
+/// keeps rendering cheap for clusters with thousands of instances.
+fn visible_window(
+    state: &mut ListState,
+    selected: usize,
+    len: usize,
+    height: usize,
+) -> Range<usize> {
+    let height = height.max(1);
+    let mut offset = state.offset();
+    if selected < offset {
+        offset = selected;
+    } else if selected >= offset + height {
+        offset = selected + 1 - height;
     }
+    offset = offset.min(len.saturating_sub(height));
+    *state.offset_mut() = offset;
+    state.select(Some(selected));
 
-    if spans.is_empty() {
-        vec![Span::styled(text.to_string(), base_style)]
-    } else {
-        spans
+    offset..(offset + height).min(len)
+}
+
+/// Render the rows of a `visible_window`, highlighting the selected one.
+/// In accessible mode the selection is also marked with `>` and the
+/// terminal cursor is parked on it, where screen readers look for focus.
+fn render_window(
+    frame: &mut Frame,
+    area: Rect,
+    items: Vec<ListItem>,
+    window: &Range<usize>,
+    selected: usize,
+    highlight: Style,
+    accessible: bool,
+) {
+    let mut list = List::new(items).highlight_style(highlight);
+    let row = selected.checked_sub(window.start);
+    if accessible {
+        list = list.highlight_symbol("> ");
+        if let Some(row) = row.filter(|&row| row < area.height as usize) {
+            frame.set_cursor_position((area.x, area.y + row as u16));
+        }
     }
+    let mut state = ListState::default().with_selected(row);
+    frame.render_stateful_widget(list, area, &mut state);
 }
 
 pub fn draw_nodes(frame: &mut Frame, app: &mut App, area: Rect) {
@@ -61,12 +145,19 @@ pub fn draw_nodes(frame: &mut Frame, app: &mut App, area: Rect) {
 
     // Draw cluster header
     if let Some(ref info) = app.cluster_info {
-        draw_cluster_header(frame, info, chunks[0]);
+        draw_cluster_header(
+            frame,
+            info,
+            app.cluster_health.as_ref(),
+            &app.number_format,
+            app.accessible,
+            chunks[0],
+        );
     } else {
         let block = Block::default()
             .borders(Borders::ALL)
-            .title(" Cluster Info ");
-        let loading = Paragraph::new("Loading...").block(block);
+            .title(format!(" {} ", tr("Cluster Info")));
+        let loading = Paragraph::new(tr("Loading...")).block(block);
         frame.render_widget(loading, chunks[0]);
     }
 
@@ -79,15 +170,99 @@ pub fn draw_nodes(frame: &mut Frame, app: &mut App, area: Rect) {
 
     // Draw detail popup if active
     if app.show_detail {
-        if let Some(instance) = app.get_selected_instance() {
-            draw_instance_detail(frame, instance, frame.area());
+        if let Some(instance) = app.detail_instance() {
+            let updated = app.detail_recently_updated();
+            let raft_roles = app.supports(Capability::RaftRoles);
+            draw_instance_detail(
+                frame,
+                &app.theme,
+                instance,
+                updated,
+                raft_roles,
+                frame.area(),
+            );
         }
     }
 
+    // Draw instance actions menu if active
+    if app.show_action_menu {
+        draw_action_menu(frame, app, frame.area());
+    }
+
     // Draw health status popup if active
     if app.show_health {
         draw_health_status(frame, app, frame.area());
     }
+
+    // Draw tables popup if active
+    if app.show_tables {
+        draw_tables(frame, app, frame.area());
+    }
+
+    if app.show_parameters {
+        draw_parameters(frame, app, frame.area());
+    }
+
+    if app.show_alerts {
+        draw_alerts(frame, app, frame.area());
+    }
+
+    if let Some(ref restart) = app.rolling_restart {
+        draw_rolling_restart(frame, app, restart, frame.area());
+    }
+
+    // Draw saved filter picker if active
+    if app.show_filter_picker {
+        draw_filter_picker(frame, app, frame.area());
+    }
+
+    // Draw leader distribution popup if active
+    if app.show_leaders {
+        draw_leader_distribution(frame, app, frame.area());
+    }
+
+    // Draw memory statistics popup if active
+    if app.show_memory_stats {
+        draw_memory_stats(frame, app, frame.area());
+    }
+
+    if app.show_heatmap {
+        draw_heatmap(frame, app, frame.area());
+    }
+
+    if app.show_topology {
+        draw_topology(frame, app, frame.area());
+    }
+
+    // Draw request timings popup if active
+    if app.show_timings {
+        draw_timings(frame, app, frame.area());
+    }
+
+    if app.show_error_log {
+        draw_error_log(frame, app, frame.area());
+    }
+
+    // Draw column picker if active
+    if app.show_column_picker {
+        draw_column_picker(frame, app, frame.area());
+    }
+
+    if app.show_sort_picker {
+        draw_sort_picker(frame, app, frame.area());
+    }
+
+    if app.show_view_switcher {
+        draw_view_switcher(frame, app, frame.area());
+    }
+
+    // Draw snapshot picker or comparison result if active
+    if app.show_snapshot_picker {
+        draw_snapshot_picker(frame, app, frame.area());
+    }
+    if let Some((name, diff)) = &app.snapshot_diff {
+        draw_snapshot_diff(frame, app, name, diff, frame.area());
+    }
 }
 
 fn draw_tiers_view(frame: &mut Frame, app: &mut App, area: Rect) {
@@ -97,57 +272,71 @@ fn draw_tiers_view(frame: &mut Frame, app: &mut App, area: Rect) {
 fn draw_tree(frame: &mut Frame, app: &mut App, area: Rect) {
     let block = Block::default()
         .borders(Borders::ALL)
-        .title(" Tiers / Replicasets / Instances ");
+        .title(format!(" {} ", tr("Tiers / Replicasets / Instances")));
 
     let inner = block.inner(area);
     frame.render_widget(block, area);
 
     if app.tiers.is_empty() {
-        let msg = Paragraph::new("No tiers found. Press 'r' to refresh.");
+        let msg = Paragraph::new(tr("No tiers found. Press 'r' to refresh."));
         frame.render_widget(msg, inner);
         return;
     }
 
-    let items: Vec<ListItem> = app
-        .tree_items
-        .iter()
-        .enumerate()
-        .map(|(idx, item)| {
-            let is_selected = idx == app.selected_index;
-            let line = match item {
-                TreeItem::Tier(tier_idx) => format_tier_line(app, *tier_idx),
-                TreeItem::Replicaset(tier_idx, rs_idx) => {
-                    format_replicaset_line(app, *tier_idx, *rs_idx)
-                }
-                TreeItem::Instance(tier_idx, rs_idx, inst_idx) => {
-                    format_instance_line(app, *tier_idx, *rs_idx, *inst_idx)
-                }
-            };
-
-            let style = if is_selected {
-                Style::default()
-                    .bg(Color::DarkGray)
-                    .add_modifier(Modifier::BOLD)
-            } else {
-                Style::default()
-            };
+    let window = visible_window(
+        &mut app.list_state,
+        app.selected_index,
+        app.tree_items.len(),
+        inner.height as usize,
+    );
+    app.frame_stats.rows = (window.len(), app.tree_items.len());
+
+    // Render rows that aren't cached yet, then build the list from borrowed
+    // spans so unchanged rows cost no string allocations
+    let mut cache = std::mem::take(&mut app.tree_line_cache);
+    cache.validate(app.tree_line_inputs());
+    for &item in &app.tree_items[window.clone()] {
+        cache.get_or_insert_with(item, app.is_expanded(item), || match item {
+            TreeItem::Tier(tier_idx) => format_tier_line(app, tier_idx),
+            TreeItem::Replicaset(tier_idx, rs_idx) => format_replicaset_line(app, tier_idx, rs_idx),
+            TreeItem::Instance(tier_idx, rs_idx, inst_idx) => {
+                format_instance_line(app, tier_idx, rs_idx, inst_idx)
+            }
+        });
+    }
 
-            ListItem::new(line).style(style)
+    let items: Vec<ListItem> = app.tree_items[window.clone()]
+        .iter()
+        .filter_map(|&item| cache.get(item, app.is_expanded(item)))
+        .map(|line| {
+            let spans: Vec<Span> = line
+                .spans
+                .iter()
+                .map(|span| Span::styled(span.content.as_ref(), span.style))
+                .collect();
+            ListItem::new(Line::from(spans))
         })
         .collect();
 
-    let list = List::new(items).highlight_style(
-        Style::default()
-            .bg(Color::DarkGray)
-            .add_modifier(Modifier::BOLD),
+    render_window(
+        frame,
+        inner,
+        items,
+        &window,
+        app.selected_index,
+        app.theme.selection_style(),
+        app.accessible,
     );
-    frame.render_stateful_widget(list, inner, &mut app.list_state);
+    app.tree_line_cache = cache;
 }
 
+/// Width of the memory gauge in each Replicasets view row
+const RS_GAUGE_WIDTH: usize = 10;
+
 fn draw_replicasets_view(frame: &mut Frame, app: &mut App, area: Rect) {
     let block = Block::default()
         .borders(Borders::ALL)
-        .title(" Replicasets ");
+        .title(format!(" {} ", tr("Replicasets")));
 
     let inner = block.inner(area);
     frame.render_widget(block, area);
@@ -164,22 +353,76 @@ fn draw_replicasets_view(frame: &mut Frame, app: &mut App, area: Rect) {
         .collect();
 
     if replicasets.is_empty() {
-        let msg = Paragraph::new("No replicasets found. Press 'r' to refresh.");
+        let msg = Paragraph::new(tr("No replicasets found. Press 'r' to refresh."));
         frame.render_widget(msg, inner);
         return;
     }
 
+    // Column widths fit the widest value, so the rows line up as a table
+    let width = |values: &mut dyn Iterator<Item = usize>, header: &str| {
+        values.max().unwrap_or(0).max(header.chars().count())
+    };
+    let name_width = width(
+        &mut replicasets.iter().map(|(_, rs)| rs.name.chars().count()),
+        "Name",
+    );
+    let tier_width = width(
+        &mut replicasets.iter().map(|(tier, _)| tier.chars().count()),
+        "Tier",
+    );
+    let buckets: Vec<Option<String>> = replicasets
+        .iter()
+        .map(|(_, rs)| rs.bucket_count.map(|count| app.number_format.count(count)))
+        .collect();
+    let buckets_width = buckets
+        .iter()
+        .any(Option::is_some)
+        .then(|| width(&mut buckets.iter().flatten().map(String::len), "Buckets"));
+    const STATE_WIDTH: usize = 12; // "[Expelled] ✓"
+    const INST_WIDTH: usize = 4;
+
+    let mut header = format!(
+        "{:<name_width$}  {:<STATE_WIDTH$}  {:<tier_width$}  {:>INST_WIDTH$}  ",
+        "Name", "State", "Tier", "Inst"
+    );
+    if let Some(buckets_width) = buckets_width {
+        header.push_str(&format!("{:>buckets_width$}  ", "Buckets"));
+    }
+    header.push_str("Memory");
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(1), Constraint::Min(0)])
+        .split(inner);
+    frame.render_widget(
+        Paragraph::new(Span::styled(
+            header,
+            Style::default()
+                .fg(Color::Gray)
+                .add_modifier(Modifier::BOLD),
+        )),
+        chunks[0],
+    );
+    let inner = chunks[1];
+
+    let window = visible_window(
+        &mut app.list_state,
+        app.selected_index,
+        replicasets.len(),
+        inner.height as usize,
+    );
+    app.frame_stats.rows = (window.len(), replicasets.len());
+
     let items: Vec<ListItem> = replicasets
         .iter()
+        .zip(&buckets)
         .enumerate()
-        .map(|(idx, (tier_name, rs))| {
+        .skip(window.start)
+        .take(window.len())
+        .map(|(idx, ((tier_name, rs), bucket_count))| {
             let is_selected = idx == app.selected_index;
 
-            let state_style = match rs.state {
-                StateVariant::Online => Style::default().fg(Color::Green),
-                StateVariant::Offline => Style::default().fg(Color::Red),
-                StateVariant::Expelled => Style::default().fg(Color::DarkGray),
-            };
+            let state_style = app.theme.state_style(rs.state);
 
             // Replicaset state indicator (Picodata 26.2+)
             let rs_state_style = match rs.replicaset_state {
@@ -187,38 +430,67 @@ fn draw_replicasets_view(frame: &mut Frame, app: &mut App, area: Rect) {
                 ReplicasetState::NotReady => Style::default().fg(Color::Yellow),
             };
             let rs_state_marker = match rs.replicaset_state {
+                _ if !app.supports(Capability::ReplicasetState) => " ",
                 ReplicasetState::Ready => "✓",
                 ReplicasetState::NotReady => "?",
             };
+            let state = rs.state.to_string();
 
-            let mem_str = format!(
-                "{}/{}",
-                format_bytes(rs.memory.used),
-                format_bytes(rs.memory.usable)
+            let mut spans = highlight_match(
+                &rs.name,
+                &app.search_text,
+                Style::default().fg(Color::White),
             );
-
-            let line = Line::from(vec![
-                Span::styled(rs.name.clone(), Style::default().fg(Color::White)),
-                Span::raw(" ["),
-                Span::styled(rs.state.to_string(), state_style),
+            spans.extend([
+                Span::raw(" ".repeat(name_width - rs.name.chars().count() + 2)),
+                Span::raw("["),
+                Span::styled(state.clone(), state_style),
                 Span::raw("] "),
                 Span::styled(rs_state_marker.to_string(), rs_state_style),
-                Span::raw("  "),
-                Span::styled("Tier:", Style::default().fg(Color::Gray)),
+                Span::raw(" ".repeat(STATE_WIDTH.saturating_sub(state.len() + 4) + 2)),
                 Span::styled(
-                    format!(" {}  ", tier_name),
+                    format!("{:<tier_width$}  ", tier_name),
                     Style::default().fg(Color::Cyan),
                 ),
-                Span::styled("Inst:", Style::default().fg(Color::Gray)),
-                Span::raw(format!(" {}  ", rs.instance_count)),
-                Span::styled("Mem:", Style::default().fg(Color::Gray)),
-                Span::raw(format!(" {} ({:.1}%)", mem_str, rs.capacity_usage)),
+                Span::raw(format!("{:>INST_WIDTH$}  ", rs.instance_count)),
             ]);
+            if let Some(buckets_width) = buckets_width {
+                spans.push(Span::raw(format!(
+                    "{:>buckets_width$}  ",
+                    bucket_count.as_deref().unwrap_or("")
+                )));
+            }
+            spans.extend(usage_bar(rs.capacity_usage, RS_GAUGE_WIDTH));
+            spans.extend([Span::styled(
+                format!(" {:>5.1}%", rs.capacity_usage),
+                Style::default().fg(usage_color(rs.capacity_usage)),
+            )]);
+            spans.extend(usage_marker(app, rs.capacity_usage));
+            spans.extend([Span::styled(
+                format!(
+                    "  {}/{}",
+                    app.number_format.bytes(rs.memory.used),
+                    app.number_format.bytes(rs.memory.usable)
+                ),
+                Style::default().fg(Color::Gray),
+            )]);
+            let transitioning = rs
+                .instances
+                .iter()
+                .filter(|inst| inst.target_state != inst.current_state)
+                .count();
+            if transitioning > 0 {
+                spans.push(Span::styled(
+                    format!("  → {} transitioning", transitioning),
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::SLOW_BLINK),
+                ));
+            }
+            let line = Line::from(spans);
 
             let style = if is_selected {
-                Style::default()
-                    .bg(Color::DarkGray)
-                    .add_modifier(Modifier::BOLD)
+                app.theme.selection_style()
             } else {
                 Style::default()
             };
@@ -227,32 +499,73 @@ fn draw_replicasets_view(frame: &mut Frame, app: &mut App, area: Rect) {
         })
         .collect();
 
-    let list = List::new(items).highlight_style(
-        Style::default()
-            .bg(Color::DarkGray)
-            .add_modifier(Modifier::BOLD),
+    render_window(
+        frame,
+        inner,
+        items,
+        &window,
+        app.selected_index,
+        app.theme.selection_style(),
+        app.accessible,
     );
-    frame.render_stateful_widget(list, inner, &mut app.list_state);
+}
+
+/// Memory usage color, same thresholds as the cluster memory gauge
+fn usage_color(usage: f64) -> Color {
+    if usage < 70.0 {
+        Color::Green
+    } else if usage < 90.0 {
+        Color::Yellow
+    } else {
+        Color::Red
+    }
+}
+
+/// `[high]` or `[critical]` after a usage figure in accessible mode
+fn usage_marker(app: &App, usage: f64) -> Option<Span<'static>> {
+    let level = usage_level(usage).filter(|_| app.accessible)?;
+    Some(Span::raw(format!(" [{}]", tr(level))))
+}
+
+/// `width` cells of bar filled in proportion to a usage percentage
+fn usage_bar(usage: f64, width: usize) -> [Span<'static>; 2] {
+    let filled = ((usage / 100.0 * width as f64).round() as usize).min(width);
+    [
+        Span::styled("█".repeat(filled), Style::default().fg(usage_color(usage))),
+        Span::styled(
+            "░".repeat(width - filled),
+            Style::default().fg(Color::DarkGray),
+        ),
+    ]
 }
 
 fn draw_instances_view(frame: &mut Frame, app: &mut App, area: Rect) {
     // Build title with sort indicator
     let sort_indicator = format!(
-        " Sort: {} {} ",
+        " {}: {} {} ",
+        tr("Sort"),
         app.sort_field.label(),
         app.sort_order.arrow()
     );
 
     // Build filter indicator for title
+    let filter_label = tr(if app.fuzzy_filter { "Fuzzy" } else { "Filter" });
     let filter_indicator = if !app.filter_text.is_empty() {
-        format!(" Filter: \"{}\" ", app.filter_text)
+        format!(" {}: \"{}\" ", filter_label, app.filter_text)
     } else if app.filter_active {
-        " Filter: _ ".to_string()
+        format!(" {}: _ ", filter_label)
     } else {
         String::new()
     };
 
-    let mut title_spans = vec![Span::raw(" Instances ")];
+    let mut title_spans = vec![Span::raw(format!(" {} ", tr("Instances")))];
+    if let Some(ref scope) = app.scope {
+        title_spans.push(Span::styled(
+            format!(" {} ", scope.label()),
+            Style::default().fg(Color::Black).bg(Color::Cyan),
+        ));
+        title_spans.push(Span::raw(" "));
+    }
     if !filter_indicator.is_empty() {
         title_spans.push(Span::styled(
             filter_indicator,
@@ -271,108 +584,125 @@ fn draw_instances_view(frame: &mut Frame, app: &mut App, area: Rect) {
             .right_aligned(),
         );
 
-    let inner = block.inner(area);
+    let mut inner = block.inner(area);
     frame.render_widget(block, area);
 
+    // Breadcrumb line above the list keeps the hierarchy the flat list hides
+    let count = app.sorted_instance_count();
+    let mut breadcrumb_area = None;
+    if count > 0 && inner.height > 1 {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Min(0)])
+            .split(inner);
+        breadcrumb_area = Some(chunks[0]);
+        inner = chunks[1];
+    }
+
+    let window = visible_window(
+        &mut app.list_state,
+        app.selected_index,
+        count,
+        inner.height as usize,
+    );
+    app.frame_stats.rows = (window.len(), count);
+
     // Get sorted and filtered instances
     let instances = app.get_sorted_instances();
 
+    if let (Some(crumb), Some((tier_name, rs_name, inst))) =
+        (breadcrumb_area, instances.get(app.selected_index))
+    {
+        frame.render_widget(
+            Paragraph::new(breadcrumb_line(tier_name, rs_name, &inst.name)),
+            crumb,
+        );
+    }
+
     if instances.is_empty() {
-        let msg = if !app.filter_text.is_empty() {
-            format!(
+        let msg = if let Some(ref scope) = app.scope {
+            trf(
+                "No instances in {}. Press Esc to return.",
+                &[&scope.label()],
+            )
+        } else if !app.filter_text.is_empty() {
+            trf(
                 "No instances match filter \"{}\". Press Esc to clear.",
-                app.filter_text
+                &[&app.filter_text],
             )
         } else {
-            "No instances found. Press 'r' to refresh.".to_string()
+            tr("No instances found. Press 'r' to refresh.").to_string()
         };
         let paragraph = Paragraph::new(msg);
         frame.render_widget(paragraph, inner);
         return;
     }
 
-    let filter = &app.filter_text;
+    // Search matches take over highlighting while a search is set
+    let filter = if app.search_text.is_empty() {
+        &app.filter_text
+    } else {
+        &app.search_text
+    };
+    let fuzzy = app.fuzzy_filter && app.search_text.is_empty();
+    let highlight = |text: &str, style: Style| {
+        if fuzzy {
+            highlight_fuzzy(text, filter, style)
+        } else {
+            highlight_match(text, filter, style)
+        }
+    };
 
     let items: Vec<ListItem> = instances
         .iter()
         .enumerate()
-        .map(|(idx, (_tier_name, rs_name, inst))| {
+        .skip(window.start)
+        .take(window.len())
+        .map(|(idx, (tier_name, rs_name, inst))| {
             let is_selected = idx == app.selected_index;
 
-            let state_style = match inst.current_state {
-                StateVariant::Online => Style::default().fg(Color::Green),
-                StateVariant::Offline => Style::default().fg(Color::Red),
-                StateVariant::Expelled => Style::default().fg(Color::DarkGray),
-            };
-
             let leader_marker = if inst.is_leader { "★" } else { " " };
 
-            // Raft role indicator (only shown for Picodata 26.2+)
-            let raft_marker = if inst.is_raft_leader {
-                "⚡"
-            } else if inst.is_voter {
-                "V"
+            let raft_marker = raft_marker(app, inst);
+
+            let pin_marker = if app.is_pinned(&inst.name) {
+                "◆"
             } else {
                 " "
             };
-
-            let failure_domain_str = if inst.failure_domain.is_empty() {
-                String::new()
+            let mark_marker = if app.is_marked(&inst.name) {
+                "✓"
             } else {
-                inst.failure_domain
-                    .iter()
-                    .map(|(k, v)| format!("{}:{}", k, v))
-                    .collect::<Vec<_>>()
-                    .join(", ")
+                " "
             };
 
             // Build line with highlighted matches
             let mut spans = vec![
-                Span::styled(leader_marker, Style::default().fg(Color::Yellow)),
+                Span::styled(mark_marker, Style::default().fg(Color::Green)),
+                Span::styled(pin_marker, Style::default().fg(Color::Cyan)),
+                Span::styled(leader_marker, Style::default().fg(app.theme.leader)),
                 Span::styled(raft_marker, Style::default().fg(Color::Magenta)),
                 Span::raw(" "),
             ];
 
-            // Instance name (with highlighting)
-            spans.extend(highlight_match(
-                &inst.name,
-                filter,
-                Style::default().fg(Color::White),
-            ));
-
-            spans.push(Span::raw(" ["));
-            spans.push(Span::styled(inst.current_state.to_string(), state_style));
-            spans.push(Span::raw("]  "));
-            spans.push(Span::styled("RS:", Style::default().fg(Color::Gray)));
-            spans.push(Span::raw(" "));
-
-            // Replicaset name (with highlighting)
-            spans.extend(highlight_match(rs_name, filter, Style::default()));
-            spans.push(Span::raw("  "));
-
-            // Binary address (with highlighting)
-            spans.extend(highlight_match(
-                &inst.binary_address,
-                filter,
-                Style::default().fg(Color::Gray),
-            ));
-
-            // Failure domain (with highlighting)
-            if !failure_domain_str.is_empty() {
-                spans.push(Span::raw("  "));
-                spans.extend(highlight_match(
-                    &failure_domain_str,
-                    filter,
-                    Style::default().fg(Color::DarkGray),
-                ));
+            // Configured columns, skipping cells with nothing to show
+            let mut first = true;
+            for &column in &app.instance_columns {
+                let cell = instance_cell(&app.theme, column, tier_name, rs_name, inst, &highlight);
+                if cell.is_empty() {
+                    continue;
+                }
+                if !first {
+                    spans.push(Span::raw("  "));
+                }
+                first = false;
+                spans.extend(cell);
             }
 
             let line = Line::from(spans);
 
             let style = if is_selected {
-                Style::default()
-                    .bg(Color::DarkGray)
-                    .add_modifier(Modifier::BOLD)
+                app.theme.selection_style()
             } else {
                 Style::default()
             };
@@ -381,322 +711,1958 @@ fn draw_instances_view(frame: &mut Frame, app: &mut App, area: Rect) {
         })
         .collect();
 
-    let list = List::new(items).highlight_style(
-        Style::default()
-            .bg(Color::DarkGray)
-            .add_modifier(Modifier::BOLD),
+    render_window(
+        frame,
+        inner,
+        items,
+        &window,
+        app.selected_index,
+        app.theme.selection_style(),
+        app.accessible,
     );
-    frame.render_stateful_widget(list, inner, &mut app.list_state);
 }
 
-fn format_tier_line(app: &App, tier_idx: usize) -> Line<'static> {
-    let tier = &app.tiers[tier_idx];
-    let expanded = app.expanded_tiers.contains(&tier_idx);
-    let arrow = if expanded { "▼" } else { "▶" };
-
-    let mem_str = format!(
-        "{}/{}",
-        format_bytes(tier.memory.used),
-        format_bytes(tier.memory.usable)
-    );
-
+/// `tier ▸ replicaset ▸ instance` for the selected row of a flat list
+fn breadcrumb_line(tier: &str, replicaset: &str, instance: &str) -> Line<'static> {
+    let dim = Style::default().fg(Color::DarkGray);
     Line::from(vec![
-        Span::styled(arrow.to_string(), Style::default().fg(Color::Yellow)),
-        Span::raw(" "),
-        Span::styled(tier.name.clone(), Style::default().fg(Color::Cyan)),
-        Span::raw("  "),
-        Span::styled("RS:", Style::default().fg(Color::Gray)),
-        Span::raw(format!(" {}  ", tier.replicaset_count)),
-        Span::styled("Inst:", Style::default().fg(Color::Gray)),
-        Span::raw(format!(" {}  ", tier.instance_count)),
-        Span::styled("RF:", Style::default().fg(Color::Gray)),
-        Span::raw(format!(" {}  ", tier.rf)),
-        Span::styled("Buckets:", Style::default().fg(Color::Gray)),
-        Span::raw(format!(" {}  ", tier.bucket_count)),
-        Span::styled("Vote:", Style::default().fg(Color::Gray)),
-        Span::raw(if tier.can_vote {
-            " ✓  ".to_string()
-        } else {
-            " ✗  ".to_string()
-        }),
-        Span::styled("Mem:", Style::default().fg(Color::Gray)),
-        Span::raw(format!(" {} ({:.1}%)", mem_str, tier.capacity_usage)),
+        Span::styled(tier.to_string(), Style::default().fg(Color::Cyan)),
+        Span::styled(" ▸ ", dim),
+        Span::styled(replicaset.to_string(), Style::default().fg(Color::Blue)),
+        Span::styled(" ▸ ", dim),
+        Span::styled(
+            instance.to_string(),
+            Style::default()
+                .fg(Color::White)
+                .add_modifier(Modifier::BOLD),
+        ),
     ])
 }
 
-fn format_replicaset_line(app: &App, tier_idx: usize, rs_idx: usize) -> Line<'static> {
-    let tier = &app.tiers[tier_idx];
-    let rs = &tier.replicasets[rs_idx];
-    let expanded = app.expanded_replicasets.contains(&(tier_idx, rs_idx));
-    let arrow = if expanded { "▼" } else { "▶" };
+/// `[Online]`, or `[Online → Offline]` with a blinking arrow while the
+/// instance is still moving towards its target state
+fn instance_state_spans(theme: &Theme, inst: &InstanceInfo) -> Vec<Span<'static>> {
+    let mut spans = vec![
+        Span::raw("["),
+        Span::styled(
+            inst.current_state.to_string(),
+            theme.state_style(inst.current_state),
+        ),
+    ];
+    if inst.target_state != inst.current_state {
+        spans.push(Span::styled(
+            " → ",
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::SLOW_BLINK),
+        ));
+        spans.push(Span::styled(
+            inst.target_state.to_string(),
+            theme
+                .state_style(inst.target_state)
+                .add_modifier(Modifier::ITALIC),
+        ));
+    }
+    spans.push(Span::raw("]"));
+    spans
+}
 
-    let state_style = match rs.state {
-        StateVariant::Online => Style::default().fg(Color::Green),
-        StateVariant::Offline => Style::default().fg(Color::Red),
-        StateVariant::Expelled => Style::default().fg(Color::DarkGray),
-    };
+/// Spans for one Instances view cell; empty when the instance has no value
+/// for the column (e.g. fields older Picodata versions don't report)
+fn instance_cell(
+    theme: &Theme,
+    column: InstanceColumn,
+    tier_name: &str,
+    rs_name: &str,
+    inst: &InstanceInfo,
+    highlight: &dyn Fn(&str, Style) -> Vec<Span<'static>>,
+) -> Vec<Span<'static>> {
+    let label =
+        |text: &str| Span::styled(format!("{}: ", tr(text)), Style::default().fg(Color::Gray));
+    let gray = Style::default().fg(Color::Gray);
+
+    match column {
+        InstanceColumn::Name => highlight(&inst.name, theme.instance_name_style(inst.is_leader)),
+        InstanceColumn::State => instance_state_spans(theme, inst),
+        InstanceColumn::Tier => {
+            let mut spans = vec![label("Tier")];
+            spans.extend(highlight(tier_name, Style::default()));
+            spans
+        }
+        InstanceColumn::Replicaset => {
+            let mut spans = vec![label("RS")];
+            spans.extend(highlight(rs_name, Style::default()));
+            spans
+        }
+        InstanceColumn::Lag => match inst.replication_lag {
+            Some(lag) => vec![
+                label("Lag"),
+                Span::styled(format!("{:.2}s", lag), lag_style(lag)),
+            ],
+            None => Vec::new(),
+        },
+        InstanceColumn::Address => highlight(&inst.binary_address, gray),
+        InstanceColumn::PgAddress if !inst.pg_address.is_empty() => {
+            vec![Span::styled(format!("pg:{}", inst.pg_address), gray)]
+        }
+        InstanceColumn::HttpAddress if !inst.http_address.is_empty() => {
+            vec![Span::styled(format!("http:{}", inst.http_address), gray)]
+        }
+        InstanceColumn::FailureDomain if !inst.failure_domain.is_empty() => {
+            let domain = inst
+                .failure_domain
+                .iter()
+                .map(|(k, v)| format!("{}:{}", k, v))
+                .collect::<Vec<_>>()
+                .join(", ");
+            highlight(&domain, Style::default().fg(Color::DarkGray))
+        }
+        InstanceColumn::Version if !inst.version.is_empty() => {
+            vec![label("Ver"), Span::raw(inst.version.clone())]
+        }
+        InstanceColumn::Uptime => match inst.uptime_seconds {
+            Some(seconds) => vec![label("Up"), Span::raw(format_uptime(seconds))],
+            None => Vec::new(),
+        },
+        InstanceColumn::Pid => match inst.pid {
+            Some(pid) => vec![label("PID"), Span::raw(pid.to_string())],
+            None => Vec::new(),
+        },
+        InstanceColumn::RaftId => match inst.raft_id {
+            Some(id) => vec![label("Raft"), Span::raw(id.to_string())],
+            None => Vec::new(),
+        },
+        InstanceColumn::PgAddress
+        | InstanceColumn::HttpAddress
+        | InstanceColumn::FailureDomain
+        | InstanceColumn::Version => Vec::new(),
+    }
+}
 
-    // Replicaset state indicator (Picodata 26.2+)
-    let rs_state_style = match rs.replicaset_state {
-        ReplicasetState::Ready => Style::default().fg(Color::Green),
-        ReplicasetState::NotReady => Style::default().fg(Color::Yellow),
-    };
-    let rs_state_marker = match rs.replicaset_state {
-        ReplicasetState::Ready => "✓",
-        ReplicasetState::NotReady => "?",
-    };
+/// Popup listing every Instances view column, shown ones first
+fn draw_column_picker(frame: &mut Frame, app: &App, area: Rect) {
+    let popup_area = centered_rect(40, 60, area);
 
-    let mem_str = format!(
-        "{}/{}",
-        format_bytes(rs.memory.used),
-        format_bytes(rs.memory.usable)
-    );
+    frame.render_widget(Clear, popup_area);
 
-    Line::from(vec![
-        Span::raw("  ├─".to_string()),
-        Span::styled(arrow.to_string(), Style::default().fg(Color::Yellow)),
-        Span::raw(" "),
-        Span::styled(rs.name.clone(), Style::default().fg(Color::White)),
-        Span::raw(" ["),
-        Span::styled(rs.state.to_string(), state_style),
-        Span::raw("] "),
-        Span::styled(rs_state_marker.to_string(), rs_state_style),
-        Span::raw("  "),
-        Span::styled("Inst:", Style::default().fg(Color::Gray)),
-        Span::raw(format!(" {}  ", rs.instance_count)),
-        Span::styled("Mem:", Style::default().fg(Color::Gray)),
-        Span::raw(format!(" {} ({:.1}%)", mem_str, rs.capacity_usage)),
-    ])
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!(" {} ", tr("Columns")))
+        .style(Style::default().bg(Color::Black));
+
+    let inner = block.inner(popup_area);
+    frame.render_widget(block, popup_area);
+
+    let mut lines: Vec<Line> = app
+        .column_picker_rows()
+        .into_iter()
+        .enumerate()
+        .map(|(idx, (column, shown))| {
+            let (check, color) = if shown {
+                ("[x] ", Color::Green)
+            } else {
+                ("[ ] ", Color::DarkGray)
+            };
+            let line = Line::from(vec![
+                Span::styled(check, Style::default().fg(color)),
+                Span::styled(column.label(), Style::default().fg(Color::White)),
+            ]);
+            if idx == app.column_picker_index {
+                line.style(app.theme.selection_style())
+            } else {
+                line
+            }
+        })
+        .collect();
+
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![Span::styled(
+        tr("Space show/hide, J/K move, Esc to close"),
+        Style::default().fg(Color::DarkGray),
+    )]));
+
+    frame.render_widget(Paragraph::new(lines), inner);
 }
 
-fn format_instance_line(
-    app: &App,
-    tier_idx: usize,
-    rs_idx: usize,
-    inst_idx: usize,
-) -> Line<'static> {
-    let tier = &app.tiers[tier_idx];
-    let rs = &tier.replicasets[rs_idx];
-    let inst = &rs.instances[inst_idx];
+fn draw_sort_picker(frame: &mut Frame, app: &App, area: Rect) {
+    let popup_area = centered_rect(40, 40, area);
+
+    frame.render_widget(Clear, popup_area);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!(" {} ", tr("Sort by")))
+        .style(Style::default().bg(Color::Black));
+
+    let inner = block.inner(popup_area);
+    frame.render_widget(block, popup_area);
+
+    let key = app.theme.key_style();
+    let mut lines: Vec<Line> = SortField::ALL
+        .into_iter()
+        .enumerate()
+        .map(|(idx, field)| {
+            let (marker, color) = if field == app.sort_field {
+                (format!("{} ", app.sort_order.arrow()), Color::Green)
+            } else {
+                ("  ".to_string(), Color::White)
+            };
+            let line = Line::from(vec![
+                Span::styled(format!("{} ", field.key()), key),
+                Span::styled(marker, Style::default().fg(Color::Green)),
+                Span::styled(field.label(), Style::default().fg(color)),
+            ]);
+            if idx == app.sort_picker_index {
+                line.style(app.theme.selection_style())
+            } else {
+                line
+            }
+        })
+        .collect();
+
+    let order = match app.sort_order {
+        SortOrder::Asc => tr("ascending"),
+        SortOrder::Desc => tr("descending"),
+    };
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![
+        Span::styled(
+            format!("{}: ", tr("Order")),
+            Style::default().fg(Color::Cyan),
+        ),
+        Span::raw(format!("{} {}", app.sort_order.arrow(), order)),
+    ]));
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![Span::styled(
+        tr("Enter or key to sort, ←→ order, Esc to close"),
+        Style::default().fg(Color::DarkGray),
+    )]));
+
+    frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }), inner);
+}
+
+/// Every view with its row count, so views past the number keys can be found
+fn draw_view_switcher(frame: &mut Frame, app: &App, area: Rect) {
+    let popup_area = centered_rect(50, 40, area);
+
+    frame.render_widget(Clear, popup_area);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!(" {} ", tr("Views")))
+        .style(Style::default().bg(Color::Black));
+
+    let inner = block.inner(popup_area);
+    frame.render_widget(block, popup_area);
+
+    let key = app.theme.key_style();
+    let mut lines: Vec<Line> = app
+        .view_summaries()
+        .into_iter()
+        .enumerate()
+        .map(|(idx, view)| {
+            let current = view.mode == app.view_mode;
+            let count = match view.shown {
+                Some(shown) => trf("{} of {}", &[&shown, &view.count]),
+                None => view.count.to_string(),
+            };
+            let mut spans = vec![
+                Span::styled(format!("{} ", idx + 1), key),
+                Span::styled(
+                    if current { "● " } else { "  " },
+                    Style::default().fg(Color::Green),
+                ),
+                Span::styled(
+                    format!("{:<12}", tr(view.mode.label())),
+                    Style::default().fg(Color::White),
+                ),
+                Span::raw(format!("{:>9}", count)),
+            ];
+            if view.shown.is_some() {
+                spans.push(Span::styled(
+                    format!("  {}", tr("filtered")),
+                    Style::default().fg(Color::Yellow),
+                ));
+            }
+            if view.problems > 0 {
+                spans.push(Span::styled(
+                    format!("  ⚠ {}", trf("{} with problems", &[&view.problems])),
+                    Style::default().fg(Color::Red),
+                ));
+            }
+            let line = Line::from(spans);
+            if idx == app.view_switcher_index {
+                line.style(app.theme.selection_style())
+            } else {
+                line
+            }
+        })
+        .collect();
+
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![Span::styled(
+        tr("Number or Enter to switch, Esc to close"),
+        Style::default().fg(Color::DarkGray),
+    )]));
+
+    frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }), inner);
+}
+
+/// Popup listing what can be done with the selected instance
+fn draw_action_menu(frame: &mut Frame, app: &App, area: Rect) {
+    let popup_area = centered_rect(40, 40, area);
+
+    frame.render_widget(Clear, popup_area);
+
+    let title = match app.action_menu_instance() {
+        Some(instance) => format!(" {}: {} ", tr("Actions"), instance.name),
+        None => format!(" {} ", tr("Actions")),
+    };
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(title)
+        .style(Style::default().bg(Color::Black));
+
+    let inner = block.inner(popup_area);
+    frame.render_widget(block, popup_area);
+
+    let mut lines: Vec<Line> = app
+        .action_menu_rows()
+        .into_iter()
+        .enumerate()
+        .map(|(idx, action)| {
+            let line = Line::from(vec![
+                Span::styled(
+                    format!("{} ", action.key()),
+                    Style::default().fg(Color::Yellow),
+                ),
+                Span::styled(action.label(), Style::default().fg(Color::White)),
+            ]);
+            if idx == app.action_menu_index {
+                line.style(app.theme.selection_style())
+            } else {
+                line
+            }
+        })
+        .collect();
+    if lines.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "Instance left the cluster",
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![Span::styled(
+        tr("Enter or key to run, Esc to close"),
+        Style::default().fg(Color::DarkGray),
+    )]));
+
+    frame.render_widget(Paragraph::new(lines), inner);
+}
+
+fn format_tier_line(app: &App, tier_idx: usize) -> Line<'static> {
+    let tier = &app.tiers[tier_idx];
+    let expanded = app.expanded_tiers.contains(&tier_idx);
+    let arrow = if expanded { "▼" } else { "▶" };
+
+    let mem_str = format!(
+        "{}/{}",
+        app.number_format.bytes(tier.memory.used),
+        app.number_format.bytes(tier.memory.usable)
+    );
+
+    let mut spans = vec![
+        Span::styled(arrow.to_string(), Style::default().fg(Color::Yellow)),
+        Span::raw(" "),
+    ];
+    spans.extend(highlight_match(
+        &tier.name,
+        &app.search_text,
+        Style::default().fg(Color::Cyan),
+    ));
+    spans.extend([
+        Span::raw("  "),
+        Span::styled(tr("RS:"), Style::default().fg(Color::Gray)),
+        Span::raw(format!(" {}  ", tier.replicaset_count)),
+        Span::styled(tr("Inst:"), Style::default().fg(Color::Gray)),
+        Span::raw(" "),
+    ]);
+    spans.extend(tier_state_spans(&app.theme, tier));
+    spans.extend([
+        Span::raw("  "),
+        Span::styled(tr("RF:"), Style::default().fg(Color::Gray)),
+        Span::raw(format!(" {}  ", tier.rf)),
+        Span::styled(tr("Buckets:"), Style::default().fg(Color::Gray)),
+        Span::raw(format!(" {}  ", app.number_format.count(tier.bucket_count))),
+    ]);
+
+    if app.is_rebalancing(&tier.name) {
+        spans.push(Span::styled(
+            "⇄ rebalancing  ",
+            Style::default().fg(Color::Yellow),
+        ));
+    }
+
+    spans.extend([
+        Span::styled(tr("Vote:"), Style::default().fg(Color::Gray)),
+        Span::raw(if tier.can_vote {
+            " ✓  ".to_string()
+        } else {
+            " ✗  ".to_string()
+        }),
+        Span::styled(tr("Mem:"), Style::default().fg(Color::Gray)),
+        Span::raw(format!(" {} ({:.1}%)", mem_str, tier.capacity_usage)),
+    ]);
+    spans.extend(usage_marker(app, tier.capacity_usage));
+
+    Line::from(spans)
+}
+
+/// "4 online / 1 offline": instance counts per current state, so a
+/// collapsed tier still shows its problems. States with no instances are left out.
+fn tier_state_spans(theme: &Theme, tier: &TierInfo) -> Vec<Span<'static>> {
+    let instances = || tier.replicasets.iter().flat_map(|rs| rs.instances.iter());
+    let mut spans = Vec::new();
+    for state in [
+        StateVariant::Online,
+        StateVariant::Offline,
+        StateVariant::Expelled,
+    ] {
+        let count = instances()
+            .filter(|inst| inst.current_state == state)
+            .count();
+        if count == 0 {
+            continue;
+        }
+        if !spans.is_empty() {
+            spans.push(Span::styled(" / ", Style::default().fg(Color::DarkGray)));
+        }
+        spans.push(Span::styled(
+            format!("{} {}", count, state.to_string().to_lowercase()),
+            theme.state_style(state),
+        ));
+    }
+    if spans.is_empty() {
+        spans.push(Span::raw("0"));
+    }
+    spans
+}
+
+/// "Buckets: N" for replicasets when the server reports per-replicaset counts
+fn replicaset_bucket_spans(app: &App, rs: &ReplicasetInfo) -> Vec<Span<'static>> {
+    match rs.bucket_count {
+        Some(count) => vec![
+            Span::styled(tr("Buckets:"), Style::default().fg(Color::Gray)),
+            Span::raw(format!(" {}  ", app.number_format.count(count))),
+        ],
+        None => Vec::new(),
+    }
+}
+
+fn format_replicaset_line(app: &App, tier_idx: usize, rs_idx: usize) -> Line<'static> {
+    let tier = &app.tiers[tier_idx];
+    let rs = &tier.replicasets[rs_idx];
+    let expanded = app.expanded_replicasets.contains(&(tier_idx, rs_idx));
+    let arrow = if expanded { "▼" } else { "▶" };
+
+    let state_style = app.theme.state_style(rs.state);
+
+    // Replicaset state indicator (Picodata 26.2+)
+    let rs_state_style = match rs.replicaset_state {
+        ReplicasetState::Ready => Style::default().fg(Color::Green),
+        ReplicasetState::NotReady => Style::default().fg(Color::Yellow),
+    };
+    let rs_state_marker = match rs.replicaset_state {
+        _ if !app.supports(Capability::ReplicasetState) => " ",
+        ReplicasetState::Ready => "✓",
+        ReplicasetState::NotReady => "?",
+    };
+
+    let mem_str = format!(
+        "{}/{}",
+        app.number_format.bytes(rs.memory.used),
+        app.number_format.bytes(rs.memory.usable)
+    );
+
+    let mut spans = vec![
+        Span::raw("  ├─".to_string()),
+        Span::styled(arrow.to_string(), Style::default().fg(Color::Yellow)),
+        Span::raw(" "),
+    ];
+    spans.extend(highlight_match(
+        &rs.name,
+        &app.search_text,
+        Style::default().fg(Color::White),
+    ));
+    spans.extend([
+        Span::raw(" ["),
+        Span::styled(rs.state.to_string(), state_style),
+        Span::raw("] "),
+        Span::styled(rs_state_marker.to_string(), rs_state_style),
+        Span::raw("  "),
+        Span::styled(tr("Inst:"), Style::default().fg(Color::Gray)),
+        Span::raw(format!(" {}  ", rs.instance_count)),
+    ]);
+    spans.extend(replicaset_bucket_spans(app, rs));
+    spans.extend([
+        Span::styled(tr("Mem:"), Style::default().fg(Color::Gray)),
+        Span::raw(format!(" {} ({:.1}%)", mem_str, rs.capacity_usage)),
+    ]);
+    spans.extend(usage_marker(app, rs.capacity_usage));
+
+    Line::from(spans)
+}
+
+/// Raft role indicator: ⚡ = raft leader, V = voter. Older servers report
+/// neither, so nothing is shown for them rather than "not a voter".
+fn raft_marker(app: &App, inst: &InstanceInfo) -> &'static str {
+    if !app.supports(Capability::RaftRoles) {
+        " "
+    } else if inst.is_raft_leader {
+        "⚡"
+    } else if inst.is_voter {
+        "V"
+    } else {
+        " "
+    }
+}
+
+fn format_instance_line(
+    app: &App,
+    tier_idx: usize,
+    rs_idx: usize,
+    inst_idx: usize,
+) -> Line<'static> {
+    let tier = &app.tiers[tier_idx];
+    let rs = &tier.replicasets[rs_idx];
+    let inst = &rs.instances[inst_idx];
+
+    let is_last = inst_idx == rs.instances.len() - 1;
+    let prefix = if is_last {
+        "  │  └─".to_string()
+    } else {
+        "  │  ├─".to_string()
+    };
+
+    // Leader markers: ★ = vshard leader, ⚡ = raft leader, V = voter
+    let leader_marker = if inst.is_leader {
+        " ★".to_string()
+    } else {
+        "  ".to_string()
+    };
+
+    let raft_marker = raft_marker(app, inst);
+
+    let pg_span = if !inst.pg_address.is_empty() {
+        Span::styled(
+            format!("  pg:{}", inst.pg_address),
+            Style::default().fg(Color::Gray),
+        )
+    } else {
+        Span::raw("".to_string())
+    };
+
+    let mut spans = vec![
+        Span::raw(prefix),
+        Span::styled(leader_marker, Style::default().fg(app.theme.leader)),
+        Span::styled(raft_marker.to_string(), Style::default().fg(Color::Magenta)),
+        Span::raw(" "),
+    ];
+    spans.extend(highlight_match(
+        &inst.name,
+        &app.search_text,
+        app.theme.instance_name_style(inst.is_leader),
+    ));
+    if app.is_pinned(&inst.name) {
+        spans.push(Span::styled(" ◆", Style::default().fg(Color::Cyan)));
+    }
+    if app.is_marked(&inst.name) {
+        spans.push(Span::styled(" ✓", Style::default().fg(Color::Green)));
+    }
+    spans.push(Span::raw(" "));
+    spans.extend(instance_state_spans(&app.theme, inst));
+    spans.extend([
+        Span::raw("  "),
+        Span::styled(
+            inst.binary_address.clone(),
+            Style::default().fg(Color::Gray),
+        ),
+        pg_span,
+    ]);
+
+    Line::from(spans)
+}
+
+/// Color replication lag by severity
+fn lag_style(lag: f64) -> Style {
+    let color = if lag >= 10.0 {
+        Color::Red
+    } else if lag >= 1.0 {
+        Color::Yellow
+    } else {
+        Color::Green
+    };
+    Style::default().fg(color)
+}
+
+/// Label of a popup field, padded to line up the values after it. A
+/// translation longer than `width` still keeps a space before its value.
+fn field_label(label: &str, width: usize) -> Span<'static> {
+    let text = tr(label);
+    let width = width.max(text.chars().count() + 1);
+    Span::styled(
+        format!("{:<width$}", text),
+        Style::default().fg(Color::Gray),
+    )
+}
+
+fn draw_instance_detail(
+    frame: &mut Frame,
+    theme: &Theme,
+    instance: &InstanceInfo,
+    updated: bool,
+    raft_roles: bool,
+    area: Rect,
+) {
+    let popup_area = centered_rect(60, 60, area);
+
+    frame.render_widget(Clear, popup_area);
+
+    let mut block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!(" {}: {} ", tr("Instance"), instance.name))
+        .style(Style::default().bg(Color::Black));
+    // Flash after a refresh changed what the popup shows
+    if updated {
+        block = block.title(
+            Line::from(Span::styled(
+                format!(" {} ", tr("updated")),
+                Style::default()
+                    .fg(Color::DarkGray)
+                    .add_modifier(Modifier::ITALIC),
+            ))
+            .right_aligned(),
+        );
+    }
+
+    let inner = block.inner(popup_area);
+    frame.render_widget(block, popup_area);
+
+    let state_color = theme.state_color(instance.current_state);
+    let target_color = theme.state_color(instance.target_state);
+
+    let mut lines = vec![
+        Line::from(vec![
+            field_label("Name:", 15),
+            Span::styled(instance.name.clone(), Style::default().fg(Color::White)),
+        ]),
+        Line::from(vec![
+            field_label("Current State:", 15),
+            Span::styled(
+                instance.current_state.to_string(),
+                Style::default().fg(state_color),
+            ),
+        ]),
+        Line::from(vec![
+            field_label("Target State:", 15),
+            Span::styled(
+                instance.target_state.to_string(),
+                Style::default().fg(target_color),
+            ),
+        ]),
+        Line::from(vec![
+            field_label("Is Leader:", 15),
+            Span::styled(
+                if instance.is_leader {
+                    "Yes ★".to_string()
+                } else {
+                    "No".to_string()
+                },
+                Style::default().fg(if instance.is_leader {
+                    Color::Yellow
+                } else {
+                    Color::White
+                }),
+            ),
+        ]),
+    ];
+    if raft_roles {
+        lines.extend([
+            Line::from(vec![
+                field_label("Is Voter:", 15),
+                Span::styled(
+                    if instance.is_voter { "Yes" } else { "No" },
+                    Style::default().fg(if instance.is_voter {
+                        Color::Magenta
+                    } else {
+                        Color::White
+                    }),
+                ),
+            ]),
+            Line::from(vec![
+                field_label("Is Raft Leader:", 16),
+                Span::styled(
+                    if instance.is_raft_leader {
+                        "Yes ⚡".to_string()
+                    } else {
+                        " No".to_string()
+                    },
+                    Style::default().fg(if instance.is_raft_leader {
+                        Color::Magenta
+                    } else {
+                        Color::White
+                    }),
+                ),
+            ]),
+        ]);
+    }
+    lines.push(Line::from(vec![
+        field_label("Version:", 15),
+        Span::styled(instance.version.clone(), Style::default().fg(Color::Cyan)),
+    ]));
+
+    // Runtime fields reported by newer Picodata versions
+    if let Some(raft_id) = instance.raft_id {
+        lines.push(Line::from(vec![
+            field_label("Raft ID:", 15),
+            Span::styled(raft_id.to_string(), Style::default().fg(Color::White)),
+        ]));
+    }
+    if let Some(uptime) = instance.uptime_seconds {
+        lines.push(Line::from(vec![
+            field_label("Uptime:", 15),
+            Span::styled(format_uptime(uptime), Style::default().fg(Color::White)),
+        ]));
+    }
+    if let Some(pid) = instance.pid {
+        lines.push(Line::from(vec![
+            field_label("PID:", 15),
+            Span::styled(pid.to_string(), Style::default().fg(Color::White)),
+        ]));
+    }
+    if let Some(lag) = instance.replication_lag {
+        lines.push(Line::from(vec![
+            Span::styled("Repl. Lag:     ", Style::default().fg(Color::Gray)),
+            Span::styled(format!("{:.3}s", lag), lag_style(lag)),
+        ]));
+    }
+
+    lines.extend([
+        Line::from(""),
+        Line::from(vec![Span::styled(
+            "Addresses:".to_string(),
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        )]),
+        Line::from(vec![
+            Span::styled("  Binary:      ", Style::default().fg(Color::Gray)),
+            Span::styled(
+                instance.binary_address.clone(),
+                Style::default().fg(Color::White),
+            ),
+        ]),
+    ]);
+
+    if !instance.pg_address.is_empty() {
+        lines.push(Line::from(vec![
+            Span::styled("  PostgreSQL:  ", Style::default().fg(Color::Gray)),
+            Span::styled(
+                instance.pg_address.clone(),
+                Style::default().fg(Color::White),
+            ),
+        ]));
+    }
+
+    if !instance.http_address.is_empty() {
+        lines.push(Line::from(vec![
+            Span::styled("  HTTP:        ", Style::default().fg(Color::Gray)),
+            Span::styled(
+                instance.http_address.clone(),
+                Style::default().fg(Color::White),
+            ),
+        ]));
+    }
+
+    if !instance.failure_domain.is_empty() {
+        lines.push(Line::from(""));
+        lines.push(Line::from(vec![Span::styled(
+            "Failure Domain:".to_string(),
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        )]));
+        for (key, value) in &instance.failure_domain {
+            lines.push(Line::from(vec![
+                Span::styled(format!("  {}:", key), Style::default().fg(Color::Gray)),
+                Span::raw(" "),
+                Span::styled(value.clone(), Style::default().fg(Color::White)),
+            ]));
+        }
+    }
+
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![Span::styled(
+        tr("Press Esc or Enter to close").to_string(),
+        Style::default().fg(Color::DarkGray),
+    )]));
+
+    let paragraph = Paragraph::new(lines).wrap(Wrap { trim: false });
+    frame.render_widget(paragraph, inner);
+}
+
+/// Longest bar in the leader distribution popup
+const LEADER_BAR_WIDTH: usize = 20;
+
+fn draw_leader_distribution(frame: &mut Frame, app: &App, area: Rect) {
+    let popup_area = centered_rect(60, 70, area);
+
+    frame.render_widget(Clear, popup_area);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!(" {} ", tr("Leader Distribution")))
+        .style(Style::default().bg(Color::Black));
+
+    let inner = block.inner(popup_area);
+    frame.render_widget(block, popup_area);
+
+    let distribution = leader_distribution(&app.tiers);
+    let most = distribution
+        .by_host
+        .iter()
+        .chain(&distribution.by_domain)
+        .map(|c| c.leaders)
+        .max()
+        .unwrap_or(0);
+    let key_width = distribution
+        .by_host
+        .iter()
+        .chain(&distribution.by_domain)
+        .map(|c| c.key.chars().count())
+        .max()
+        .unwrap_or(0);
+
+    let section = |title: &str, counts: &[LeaderCount]| -> Vec<Line<'static>> {
+        let mut lines = vec![Line::from(Span::styled(
+            title.to_string(),
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+        ))];
+        if counts.is_empty() {
+            lines.push(Line::from(Span::styled(
+                "  none",
+                Style::default().fg(Color::DarkGray),
+            )));
+        }
+        for count in counts {
+            let bar = if most > 0 {
+                (count.leaders * LEADER_BAR_WIDTH).div_ceil(most)
+            } else {
+                0
+            };
+            // Everything led from a single group is a pile-up
+            let color = if count.leaders > 1 && count.leaders == most {
+                Color::Yellow
+            } else {
+                Color::Green
+            };
+            lines.push(Line::from(vec![
+                Span::styled(
+                    format!("  {:<width$}  ", count.key, width = key_width),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled("█".repeat(bar), Style::default().fg(color)),
+                Span::styled(
+                    format!(" {} of {} leading", count.leaders, count.instances),
+                    Style::default().fg(Color::Gray),
+                ),
+            ]));
+        }
+        lines
+    };
+
+    let mut lines = section(tr("By host"), &distribution.by_host);
+    lines.push(Line::from(""));
+    lines.extend(section(tr("By failure domain"), &distribution.by_domain));
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![Span::styled(
+        tr("Esc to close"),
+        Style::default().fg(Color::DarkGray),
+    )]));
+
+    frame.render_widget(Paragraph::new(lines), inner);
+}
+
+/// Width of the usage bars in the memory statistics popup
+const MEMORY_BAR_WIDTH: usize = 20;
+
+fn draw_memory_stats(frame: &mut Frame, app: &App, area: Rect) {
+    let popup_area = centered_rect(70, 70, area);
+
+    frame.render_widget(Clear, popup_area);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!(" {} ", tr("Memory Statistics")))
+        .style(Style::default().bg(Color::Black));
+
+    let inner = block.inner(popup_area);
+    frame.render_widget(block, popup_area);
+
+    let by_tier = memory_by_tier(&app.tiers);
+    let by_domain = memory_by_domain(&app.tiers);
+    let key_width = by_tier
+        .iter()
+        .chain(&by_domain)
+        .map(|g| g.key.chars().count())
+        .max()
+        .unwrap_or(0);
+
+    let section = |title: &str, groups: &[MemoryGroup]| -> Vec<Line<'static>> {
+        let mut lines = vec![Line::from(Span::styled(
+            title.to_string(),
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+        ))];
+        if groups.is_empty() {
+            lines.push(Line::from(Span::styled(
+                "  none",
+                Style::default().fg(Color::DarkGray),
+            )));
+        }
+        for group in groups {
+            let usage = group.usage();
+            let mut spans = vec![Span::styled(
+                format!("  {:<width$}  ", group.key, width = key_width),
+                Style::default().fg(Color::White),
+            )];
+            spans.extend(usage_bar(usage, MEMORY_BAR_WIDTH));
+            spans.push(Span::styled(
+                format!(
+                    " {} / {} ({:.1}%)",
+                    app.number_format.bytes(group.used),
+                    app.number_format.bytes(group.usable),
+                    usage
+                ),
+                Style::default().fg(Color::Gray),
+            ));
+            lines.push(Line::from(spans));
+        }
+        lines
+    };
+
+    let mut lines = section(tr("By tier"), &by_tier);
+    lines.push(Line::from(""));
+    lines.extend(section(tr("By failure domain"), &by_domain));
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![Span::styled(
+        tr("Failure domain figures split each replicaset's memory evenly among its instances. Esc to close"),
+        Style::default().fg(Color::DarkGray),
+    )]));
+
+    frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }), inner);
+}
+
+fn draw_heatmap(frame: &mut Frame, app: &App, area: Rect) {
+    let popup_area = centered_rect(90, 85, area);
+
+    frame.render_widget(Clear, popup_area);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!(
+            " {}: {} ",
+            tr("Heatmap"),
+            tr(app.heatmap_coloring.label())
+        ))
+        .style(Style::default().bg(Color::Black));
+
+    let inner = block.inner(popup_area);
+    frame.render_widget(block, popup_area);
+
+    let dim = Style::default().fg(Color::DarkGray);
+    let cells = heatmap::cells(&app.tiers);
+    let Some(&(t, r, i)) = cells.get(app.heatmap_cursor(&cells)) else {
+        frame.render_widget(Paragraph::new(tr("No instances")), inner);
+        return;
+    };
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(0),    // Cells
+            Constraint::Length(4), // Cursor instance, legend and keys
+        ])
+        .split(inner);
+
+    // A replicaset starts a new line rather than wrap, unless it's wider
+    // than the popup itself
+    let width = chunks[0].width as usize;
+    let mut lines: Vec<Line> = Vec::new();
+    let mut row: Vec<Span> = Vec::new();
+    let mut row_width = 0;
+    let mut cursor_line = 0;
+    for (tier_idx, tier) in app.tiers.iter().enumerate() {
+        if !row.is_empty() {
+            lines.push(Line::from(std::mem::take(&mut row)));
+            row_width = 0;
+        }
+        lines.push(Line::from(Span::styled(
+            tier.name.clone(),
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+        )));
+        for (rs_idx, rs) in tier.replicasets.iter().enumerate() {
+            if row_width > 0 && row_width + rs.instances.len() > width {
+                lines.push(Line::from(std::mem::take(&mut row)));
+                row_width = 0;
+            }
+            for (inst_idx, inst) in rs.instances.iter().enumerate() {
+                if row_width >= width {
+                    lines.push(Line::from(std::mem::take(&mut row)));
+                    row_width = 0;
+                }
+                let (symbol, color) = heat_cell(app, rs, inst);
+                if (tier_idx, rs_idx, inst_idx) == (t, r, i) {
+                    cursor_line = lines.len();
+                    row.push(Span::styled("◆", app.theme.selection_style().fg(color)));
+                } else {
+                    row.push(Span::styled(symbol, Style::default().fg(color)));
+                }
+                row_width += 1;
+            }
+            row.push(Span::raw(" "));
+            row_width += 1;
+        }
+    }
+    if !row.is_empty() {
+        lines.push(Line::from(row));
+    }
+    // Keep the cursor in sight
+    let height = chunks[0].height as usize;
+    let scroll = (cursor_line + 1).saturating_sub(height);
+    frame.render_widget(Paragraph::new(lines).scroll((scroll as u16, 0)), chunks[0]);
+
+    let tier = &app.tiers[t];
+    let rs = &tier.replicasets[r];
+    let inst = &rs.instances[i];
+    let mut cursor = vec![
+        Span::styled(
+            inst.name.clone(),
+            app.theme.instance_name_style(inst.is_leader),
+        ),
+        Span::raw(format!(
+            "  {} {} / {} {}  ",
+            tr("replicaset"),
+            rs.name,
+            tr("tier"),
+            tier.name
+        )),
+        Span::styled(
+            inst.current_state.to_string(),
+            app.theme.state_style(inst.current_state),
+        ),
+    ];
+    if inst.target_state != inst.current_state {
+        cursor.push(Span::styled(
+            format!(" → {}", inst.target_state),
+            app.theme.state_style(inst.target_state),
+        ));
+    }
+    cursor.push(Span::raw("  "));
+    cursor.push(Span::styled(
+        format!("{:.1}%", rs.capacity_usage),
+        Style::default().fg(usage_color(rs.capacity_usage)),
+    ));
+    cursor.extend(usage_marker(app, rs.capacity_usage));
+    if inst.is_leader {
+        cursor.push(Span::styled(
+            format!("  ★ {}", tr("leader")),
+            Style::default().fg(app.theme.leader),
+        ));
+    }
+
+    let legend: Vec<(&str, String, Color)> = match app.heatmap_coloring {
+        Coloring::Capacity => vec![
+            ("░", "< 70%".to_string(), Color::Green),
+            ("▒", "70-90%".to_string(), Color::Yellow),
+            ("█", "≥ 90%".to_string(), Color::Red),
+        ],
+        Coloring::State => vec![
+            ("█", "Online".to_string(), app.theme.online),
+            ("▒", tr("changing state").to_string(), Color::Yellow),
+            ("░", "Offline".to_string(), app.theme.offline),
+        ],
+    };
+    let legend = legend
+        .into_iter()
+        .chain([("·", "Expelled".to_string(), app.theme.expelled)])
+        .flat_map(|(symbol, label, color)| {
+            [
+                Span::styled(symbol, Style::default().fg(color)),
+                Span::raw(format!(" {}   ", label)),
+            ]
+        })
+        .collect::<Vec<_>>();
+
+    let footer = vec![
+        Line::from(""),
+        Line::from(cursor),
+        Line::from(legend),
+        Line::from(Span::styled(
+            tr("←→ instance, ↑↓ replicaset, c coloring, Enter to go to the instance, Esc to close"),
+            dim,
+        )),
+    ];
+    frame.render_widget(Paragraph::new(footer), chunks[1]);
+}
+
+/// Symbol and color of an instance's heatmap cell. The symbol follows the
+/// level too, so the map still reads without color.
+fn heat_cell(app: &App, rs: &ReplicasetInfo, inst: &InstanceInfo) -> (&'static str, Color) {
+    if inst.current_state == StateVariant::Expelled {
+        return ("·", app.theme.expelled);
+    }
+    match app.heatmap_coloring {
+        Coloring::Capacity => {
+            let usage = rs.capacity_usage;
+            let symbol = if usage < 70.0 {
+                "░"
+            } else if usage < 90.0 {
+                "▒"
+            } else {
+                "█"
+            };
+            (symbol, usage_color(usage))
+        }
+        Coloring::State if inst.current_state != inst.target_state => ("▒", Color::Yellow),
+        Coloring::State if inst.current_state == StateVariant::Online => ("█", app.theme.online),
+        Coloring::State => ("░", app.theme.offline),
+    }
+}
+
+/// Narrowest a failure domain box gets before the map wraps to more rows
+const TOPOLOGY_AREA_WIDTH: u16 = 28;
+
+fn draw_topology(frame: &mut Frame, app: &App, area: Rect) {
+    let popup_area = centered_rect(90, 85, area);
+
+    frame.render_widget(Clear, popup_area);
+
+    let topology = topology::build(&app.tiers);
+    let title = if topology.levels.is_empty() {
+        format!(" {} ", tr("Topology"))
+    } else {
+        format!(" {}: {} ", tr("Topology"), topology.levels.join(" → "))
+    };
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(title)
+        .style(Style::default().bg(Color::Black));
+
+    let inner = block.inner(popup_area);
+    frame.render_widget(block, popup_area);
+
+    let dim = Style::default().fg(Color::DarkGray);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(0),    // Failure domains
+            Constraint::Length(1), // Legend and keys
+        ])
+        .split(inner);
+    frame.render_widget(
+        Paragraph::new(Line::from(vec![
+            Span::raw("★ "),
+            Span::styled(tr("leader"), dim),
+            Span::raw("  ● "),
+            Span::styled(tr("instance"), dim),
+            Span::raw("  ☆ ○ "),
+            Span::styled("Offline", dim),
+            Span::styled(format!("   {}", tr("n to show names, Esc to close")), dim),
+        ])),
+        chunks[1],
+    );
+
+    if topology.areas.is_empty() {
+        frame.render_widget(Paragraph::new(tr("No instances")), chunks[0]);
+        return;
+    }
+
+    // As many columns of boxes as fit, then as many rows as needed
+    let count = topology.areas.len();
+    let columns = usize::from(chunks[0].width / TOPOLOGY_AREA_WIDTH).clamp(1, count);
+    let rows = count.div_ceil(columns);
+    let row_areas = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(vec![Constraint::Ratio(1, rows as u32); rows])
+        .split(chunks[0]);
+    let cells = row_areas.iter().flat_map(|&row| {
+        Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(vec![Constraint::Ratio(1, columns as u32); columns])
+            .split(row)
+            .to_vec()
+    });
+
+    let not_set = tr("not set");
+    for (domain, cell) in topology.areas.iter().zip(cells) {
+        let title = match (topology.levels.first(), &domain.value) {
+            (Some(key), Some(value)) => format!(" {}: {} ", key, value),
+            (Some(key), None) => format!(" {} {} ", key, not_set),
+            (None, _) => format!(" {} ", tr("No failure domains")),
+        };
+        let offline = domain
+            .instances()
+            .filter(|m| m.state != StateVariant::Online)
+            .count();
+        let total = domain.instances().count();
+        let color = if offline > 0 {
+            app.theme.offline
+        } else {
+            Color::DarkGray
+        };
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(color))
+            .title(Span::styled(title, Style::default().fg(Color::Cyan)))
+            .title_bottom(Line::from(Span::styled(
+                format!(
+                    " {} ",
+                    trf(
+                        "{}/{} online",
+                        &[&(total - offline).to_string(), &total.to_string()]
+                    )
+                ),
+                Style::default().fg(color),
+            )));
+        let inner = block.inner(cell);
+        frame.render_widget(block, cell);
+
+        let inner_key = topology.levels.get(1);
+        let label_width = domain
+            .groups
+            .iter()
+            .map(|g| g.value.as_deref().unwrap_or(not_set).chars().count())
+            .max()
+            .unwrap_or(0);
+        let mut lines = Vec::new();
+        for group in &domain.groups {
+            let mut spans = Vec::new();
+            if inner_key.is_some() {
+                spans.push(Span::styled(
+                    format!(
+                        "{:<width$} ",
+                        group.value.as_deref().unwrap_or(not_set),
+                        width = label_width
+                    ),
+                    Style::default().fg(Color::White),
+                ));
+            }
+            if app.topology_names {
+                lines.push(Line::from(spans));
+                for marker in &group.instances {
+                    lines.push(Line::from(vec![
+                        Span::raw("  "),
+                        topology_marker(app, marker),
+                        Span::styled(
+                            format!(" {}", marker.name),
+                            app.theme.instance_name_style(marker.is_leader),
+                        ),
+                        Span::styled(format!(" {}", marker.replicaset), dim),
+                    ]));
+                }
+            } else {
+                spans.extend(group.instances.iter().map(|m| topology_marker(app, m)));
+                lines.push(Line::from(spans));
+            }
+        }
+        frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }), inner);
+    }
+}
+
+/// `★` for a replicaset leader and `●` for the rest, hollow when not Online
+fn topology_marker(app: &App, marker: &Marker) -> Span<'static> {
+    let online = marker.state == StateVariant::Online;
+    let symbol = match (marker.is_leader, online) {
+        (true, true) => "★",
+        (true, false) => "☆",
+        (false, true) => "●",
+        (false, false) => "○",
+    };
+    Span::styled(symbol, app.theme.state_style(marker.state))
+}
+
+fn draw_timings(frame: &mut Frame, app: &App, area: Rect) {
+    let popup_area = centered_rect(70, 50, area);
+
+    frame.render_widget(Clear, popup_area);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!(" {} ", tr("Request Timings")))
+        .style(Style::default().bg(Color::Black));
+
+    let inner = block.inner(popup_area);
+    frame.render_widget(block, popup_area);
+
+    let millis = |latency: Option<Duration>| match latency {
+        Some(latency) => format!("{} ms", latency.as_millis()),
+        None => "-".to_string(),
+    };
+    let row = |cells: [String; 7]| {
+        format!(
+            "  {:<8} {:>8} {:>6} {:>8} {:>8} {:>8} {:>10}",
+            cells[0], cells[1], cells[2], cells[3], cells[4], cells[5], cells[6]
+        )
+    };
+
+    let mut lines = vec![Line::from(Span::styled(
+        row([
+            "Endpoint", "Requests", "Errors", "Min", "Avg", "p95", "Received",
+        ]
+        .map(|header| tr(header).to_string())),
+        Style::default()
+            .fg(Color::Cyan)
+            .add_modifier(Modifier::BOLD),
+    ))];
+    let endpoints = app.timings.endpoints();
+    if endpoints.is_empty() {
+        lines.push(Line::from(Span::styled(
+            format!("  {}", tr("No requests yet")),
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+    for (name, timings) in endpoints {
+        let color = if timings.errors > 0 {
+            Color::Yellow
+        } else {
+            Color::White
+        };
+        lines.push(Line::from(Span::styled(
+            row([
+                name.to_string(),
+                timings.requests.to_string(),
+                timings.errors.to_string(),
+                millis(timings.min()),
+                millis(timings.avg()),
+                millis(timings.p95()),
+                app.number_format.bytes(timings.bytes),
+            ]),
+            Style::default().fg(color),
+        )));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![Span::styled(
+        tr("Figures cover this session; 304 responses count as requests with no body. Esc to close"),
+        Style::default().fg(Color::DarkGray),
+    )]));
+
+    frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }), inner);
+}
+
+fn draw_error_log(frame: &mut Frame, app: &App, area: Rect) {
+    let popup_area = centered_rect(70, 60, area);
+
+    frame.render_widget(Clear, popup_area);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!(" {} ", tr("Error Log")))
+        .style(Style::default().bg(Color::Black));
+
+    let inner = block.inner(popup_area);
+    frame.render_widget(block, popup_area);
+
+    let dim = Style::default().fg(Color::DarkGray);
+    let now = format::unix_now();
+    let mut lines = Vec::new();
+    if app.error_log.is_empty() {
+        lines.push(Line::from(Span::styled(
+            tr("No errors this session"),
+            Style::default().fg(Color::Green),
+        )));
+    }
+    for error in app.error_log.iter() {
+        let mut spans = vec![
+            Span::styled(
+                format!("{:>10}  ", app.time_format.timestamp(error.last_at, now)),
+                dim,
+            ),
+            Span::styled(error.message.clone(), Style::default().fg(Color::Red)),
+        ];
+        if error.count > 1 {
+            spans.push(Span::styled(
+                trf(
+                    "  ×{} since {}",
+                    &[
+                        &error.count,
+                        &app.time_format.timestamp(error.first_at, now),
+                    ],
+                ),
+                Style::default().fg(Color::Yellow),
+            ));
+        }
+        lines.push(Line::from(spans));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        tr("Newest first. c to clear, Esc to close"),
+        dim,
+    )));
+
+    frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }), inner);
+}
+
+fn draw_snapshot_picker(frame: &mut Frame, app: &App, area: Rect) {
+    let popup_area = centered_rect(50, 50, area);
+
+    frame.render_widget(Clear, popup_area);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!(" {} ", tr("Compare Snapshot")))
+        .style(Style::default().bg(Color::Black));
+
+    let inner = block.inner(popup_area);
+    frame.render_widget(block, popup_area);
+
+    let mut lines: Vec<Line> = app
+        .snapshot_names
+        .iter()
+        .enumerate()
+        .map(|(idx, name)| {
+            let line = Line::from(Span::styled(
+                format!(" {}", name),
+                Style::default().fg(Color::White),
+            ));
+            if idx == app.snapshot_picker_index {
+                line.style(app.theme.selection_style())
+            } else {
+                line
+            }
+        })
+        .collect();
+
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![Span::styled(
+        tr("Enter to compare with the live cluster, Esc to close"),
+        Style::default().fg(Color::DarkGray),
+    )]));
+
+    frame.render_widget(Paragraph::new(lines), inner);
+}
+
+fn draw_snapshot_diff(frame: &mut Frame, app: &App, name: &str, diff: &SnapshotDiff, area: Rect) {
+    let popup_area = centered_rect(70, 70, area);
+
+    frame.render_widget(Clear, popup_area);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!(" {} ", trf("Changes since {}", &[&name])))
+        .style(Style::default().bg(Color::Black));
+
+    let inner = block.inner(popup_area);
+    frame.render_widget(block, popup_area);
+
+    let mut lines: Vec<Line> = Vec::new();
+    if diff.is_empty() {
+        lines.push(Line::from(Span::styled(
+            tr("No changes"),
+            Style::default().fg(Color::Green),
+        )));
+    }
+    for name in &diff.added {
+        lines.push(Line::from(Span::styled(
+            format!("+ {}", name),
+            Style::default().fg(Color::Green),
+        )));
+    }
+    for name in &diff.removed {
+        lines.push(Line::from(Span::styled(
+            format!("- {}", name),
+            Style::default().fg(Color::Red),
+        )));
+    }
+    for change in &diff.state_changes {
+        lines.push(Line::from(vec![
+            Span::styled(
+                format!("~ {}: ", change.instance),
+                Style::default().fg(Color::Yellow),
+            ),
+            Span::styled(change.from.to_string(), app.theme.state_style(change.from)),
+            Span::styled(" → ", Style::default().fg(Color::Gray)),
+            Span::styled(change.to.to_string(), app.theme.state_style(change.to)),
+        ]));
+    }
+    if !diff.memory.is_empty() {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            "Memory used",
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+        )));
+    }
+    for change in &diff.memory {
+        let delta = change.delta();
+        let (sign, color) = if delta < 0 {
+            ("-", Color::Green)
+        } else {
+            ("+", Color::Yellow)
+        };
+        lines.push(Line::from(vec![
+            Span::styled(
+                format!(
+                    "  {}: {} → {} ",
+                    change.tier,
+                    app.number_format.bytes(change.before),
+                    app.number_format.bytes(change.after)
+                ),
+                Style::default().fg(Color::White),
+            ),
+            Span::styled(
+                format!(
+                    "({}{})",
+                    sign,
+                    app.number_format.bytes(delta.unsigned_abs())
+                ),
+                Style::default().fg(color),
+            ),
+        ]));
+    }
+
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![Span::styled(
+        tr("Esc to close"),
+        Style::default().fg(Color::DarkGray),
+    )]));
+
+    frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }), inner);
+}
+
+fn draw_filter_picker(frame: &mut Frame, app: &App, area: Rect) {
+    let popup_area = centered_rect(50, 50, area);
+
+    frame.render_widget(Clear, popup_area);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!(" {} ", tr("Saved Filters")))
+        .style(Style::default().bg(Color::Black));
+
+    let inner = block.inner(popup_area);
+    frame.render_widget(block, popup_area);
+
+    let mut lines: Vec<Line> = app
+        .saved_filters
+        .iter()
+        .enumerate()
+        .map(|(idx, saved)| {
+            let key = if idx < 9 {
+                format!("{} ", idx + 1)
+            } else {
+                "  ".to_string()
+            };
+            let mut spans = vec![
+                Span::styled(key, Style::default().fg(Color::Yellow)),
+                Span::styled(saved.name.clone(), Style::default().fg(Color::White)),
+                Span::styled(
+                    format!("  \"{}\"", saved.filter),
+                    Style::default().fg(Color::Gray),
+                ),
+            ];
+            if saved.fuzzy {
+                spans.push(Span::styled(" (fuzzy)", Style::default().fg(Color::Cyan)));
+            }
+            let line = Line::from(spans);
+            if idx == app.filter_picker_index {
+                line.style(app.theme.selection_style())
+            } else {
+                line
+            }
+        })
+        .collect();
+
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![Span::styled(
+        tr("Enter or 1-9 to apply, Esc to close"),
+        Style::default().fg(Color::DarkGray),
+    )]));
+
+    frame.render_widget(Paragraph::new(lines), inner);
+}
 
-    let is_last = inst_idx == rs.instances.len() - 1;
-    let prefix = if is_last {
-        "  │  └─".to_string()
-    } else {
-        "  │  ├─".to_string()
-    };
+fn draw_tables(frame: &mut Frame, app: &App, area: Rect) {
+    let popup_area = centered_rect(80, 70, area);
 
-    let state_style = match inst.current_state {
-        StateVariant::Online => Style::default().fg(Color::Green),
-        StateVariant::Offline => Style::default().fg(Color::Red),
-        StateVariant::Expelled => Style::default().fg(Color::DarkGray),
-    };
+    frame.render_widget(Clear, popup_area);
 
-    // Leader markers: ★ = vshard leader, ⚡ = raft leader, V = voter
-    let leader_marker = if inst.is_leader {
-        " ★".to_string()
+    let title = if app.tables_loading {
+        format!(" {} ({}) ", tr("Tables"), tr("loading..."))
     } else {
-        "  ".to_string()
+        format!(" {} ", tr("Tables"))
     };
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(title)
+        .style(Style::default().bg(Color::Black));
 
-    // Raft role indicator (only shown for Picodata 26.2+)
-    let raft_marker = if inst.is_raft_leader {
-        "⚡"
-    } else if inst.is_voter {
-        "V"
-    } else {
-        " "
-    };
+    let inner = block.inner(popup_area);
+    frame.render_widget(block, popup_area);
 
-    let pg_span = if !inst.pg_address.is_empty() {
-        Span::styled(
-            format!("  pg:{}", inst.pg_address),
-            Style::default().fg(Color::Gray),
+    let row = |cells: [String; 6]| {
+        format!(
+            "  {:<28} {:<12} {:<12} {:<7} {:>12} {:>10}",
+            cells[0], cells[1], cells[2], cells[3], cells[4], cells[5]
         )
-    } else {
-        Span::raw("".to_string())
     };
+    let unknown = || "-".to_string();
 
-    Line::from(vec![
-        Span::raw(prefix),
-        Span::styled(leader_marker, Style::default().fg(Color::Yellow)),
-        Span::styled(raft_marker.to_string(), Style::default().fg(Color::Magenta)),
-        Span::raw(" "),
-        Span::styled(inst.name.clone(), Style::default().fg(Color::White)),
-        Span::raw(" ["),
-        Span::styled(inst.current_state.to_string(), state_style),
-        Span::raw("]  "),
-        Span::styled(
-            inst.binary_address.clone(),
-            Style::default().fg(Color::Gray),
-        ),
-        pg_span,
-    ])
+    let mut lines = Vec::new();
+    if let Some(ref error) = app.tables_error {
+        lines.push(Line::from(vec![
+            Span::styled("Error: ", Style::default().fg(Color::Red)),
+            Span::raw(tr(error).to_string()),
+        ]));
+    }
+    match app.tables {
+        Some(ref tables) => {
+            lines.push(Line::from(Span::styled(
+                row(["Table", "Distribution", "Tier", "Engine", "Rows", "Size"]
+                    .map(|header| tr(header).to_string())),
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            )));
+            // Header, blank line and hint stay visible
+            let visible = (inner.height as usize).saturating_sub(3);
+            for table in tables.iter().skip(app.tables_scroll).take(visible) {
+                let (distribution, tier) = match &table.distribution {
+                    TableDistribution::Global => (tr("global"), String::new()),
+                    TableDistribution::Sharded { tier } => (tr("sharded"), tier.clone()),
+                };
+                lines.push(Line::from(row([
+                    table.name.clone(),
+                    distribution.to_string(),
+                    tier,
+                    table.engine.clone(),
+                    table
+                        .rows
+                        .map_or_else(unknown, |rows| app.number_format.count(rows)),
+                    table
+                        .size
+                        .map_or_else(unknown, |size| app.number_format.bytes(size)),
+                ])));
+            }
+            lines.push(Line::from(""));
+            lines.push(Line::from(Span::styled(
+                tr("Sizes are estimated from the connected instance; - when it holds no share. j/k to scroll, r to reload, Esc to close"),
+                Style::default().fg(Color::DarkGray),
+            )));
+        }
+        None if app.tables_loading => lines.push(Line::from(tr("Loading tables..."))),
+        None => {}
+    }
+
+    frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }), inner);
 }
 
-fn draw_instance_detail(frame: &mut Frame, instance: &InstanceInfo, area: Rect) {
-    let popup_area = centered_rect(60, 60, area);
+fn draw_alerts(frame: &mut Frame, app: &App, area: Rect) {
+    let popup_area = centered_rect(70, 60, area);
 
     frame.render_widget(Clear, popup_area);
 
     let block = Block::default()
         .borders(Borders::ALL)
-        .title(format!(" Instance: {} ", instance.name))
+        .title(format!(" {} ", tr("Alerts")))
         .style(Style::default().bg(Color::Black));
 
     let inner = block.inner(popup_area);
     frame.render_widget(block, popup_area);
 
-    let state_color = match instance.current_state {
-        StateVariant::Online => Color::Green,
-        StateVariant::Offline => Color::Red,
-        StateVariant::Expelled => Color::DarkGray,
-    };
-
-    let target_color = match instance.target_state {
-        StateVariant::Online => Color::Green,
-        StateVariant::Offline => Color::Red,
-        StateVariant::Expelled => Color::DarkGray,
-    };
-
-    let mut lines = vec![
-        Line::from(vec![
-            Span::styled("Name:          ", Style::default().fg(Color::Gray)),
-            Span::styled(instance.name.clone(), Style::default().fg(Color::White)),
-        ]),
-        Line::from(vec![
-            Span::styled("Current State: ", Style::default().fg(Color::Gray)),
-            Span::styled(
-                instance.current_state.to_string(),
-                Style::default().fg(state_color),
-            ),
-        ]),
-        Line::from(vec![
-            Span::styled("Target State:  ", Style::default().fg(Color::Gray)),
-            Span::styled(
-                instance.target_state.to_string(),
-                Style::default().fg(target_color),
-            ),
-        ]),
-        Line::from(vec![
-            Span::styled("Is Leader:     ", Style::default().fg(Color::Gray)),
-            Span::styled(
-                if instance.is_leader {
-                    "Yes ★".to_string()
-                } else {
-                    "No".to_string()
-                },
-                Style::default().fg(if instance.is_leader {
-                    Color::Yellow
-                } else {
-                    Color::White
-                }),
-            ),
-        ]),
-        Line::from(vec![
-            Span::styled("Is Voter:      ", Style::default().fg(Color::Gray)),
+    let dim = Style::default().fg(Color::DarkGray);
+    let mut lines = Vec::new();
+    let now = format::unix_now();
+    for alert in app.alerts.active() {
+        lines.push(Line::from(vec![
+            Span::styled("⚠ ", Style::default().fg(Color::Red)),
             Span::styled(
-                if instance.is_voter { "Yes" } else { "No" },
-                Style::default().fg(if instance.is_voter {
-                    Color::Magenta
-                } else {
-                    Color::White
-                }),
+                alert.rule.clone(),
+                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
             ),
-        ]),
-        Line::from(vec![
-            Span::styled("Is Raft Leader:", Style::default().fg(Color::Gray)),
+            Span::raw(format!(
+                "  {} ({} {})  ",
+                alert.condition,
+                tr("now"),
+                format_value(alert.value)
+            )),
             Span::styled(
-                if instance.is_raft_leader {
-                    " Yes ⚡".to_string()
-                } else {
-                    " No".to_string()
-                },
-                Style::default().fg(if instance.is_raft_leader {
-                    Color::Magenta
-                } else {
-                    Color::White
-                }),
+                trf("since {}", &[&app.time_format.timestamp(alert.since, now)]),
+                dim,
             ),
-        ]),
-        Line::from(vec![
-            Span::styled("Version:       ", Style::default().fg(Color::Gray)),
-            Span::styled(instance.version.clone(), Style::default().fg(Color::Cyan)),
-        ]),
-        Line::from(""),
-        Line::from(vec![Span::styled(
-            "Addresses:".to_string(),
-            Style::default()
-                .fg(Color::Yellow)
-                .add_modifier(Modifier::BOLD),
-        )]),
-        Line::from(vec![
-            Span::styled("  Binary:      ", Style::default().fg(Color::Gray)),
-            Span::styled(
-                instance.binary_address.clone(),
-                Style::default().fg(Color::White),
+        ]));
+    }
+    let rules = app.alerts.rules().len();
+    if rules == 0 {
+        lines.push(Line::from(tr(
+            "No alert rules configured; add them under \"alerts\" in the config file",
+        )));
+    } else if lines.is_empty() {
+        lines.push(Line::from(Span::styled(
+            trf(
+                "All clear: {} rules checked on every refresh",
+                &[&rules.to_string()],
             ),
-        ]),
-    ];
+            Style::default().fg(Color::Green),
+        )));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(tr("Esc to close"), dim)));
 
-    if !instance.pg_address.is_empty() {
+    frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }), inner);
+}
+
+/// A measured value without a pointless fraction
+fn format_value(value: f64) -> String {
+    if value.fract() == 0.0 {
+        format!("{:.0}", value)
+    } else {
+        format!("{:.1}", value)
+    }
+}
+
+fn draw_parameters(frame: &mut Frame, app: &App, area: Rect) {
+    let popup_area = centered_rect(80, 70, area);
+
+    frame.render_widget(Clear, popup_area);
+
+    let title = if app.parameters_loading {
+        format!(" {} ({}) ", tr("Configuration"), tr("loading..."))
+    } else {
+        format!(" {} ", tr("Configuration"))
+    };
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(title)
+        .style(Style::default().bg(Color::Black));
+
+    let inner = block.inner(popup_area);
+    frame.render_widget(block, popup_area);
+
+    let row = |cells: [&str; 4]| {
+        format!(
+            "  {:<40} {:<12} {:<20} {}",
+            cells[0], cells[1], cells[2], cells[3]
+        )
+    };
+
+    let mut lines = Vec::new();
+    if let Some(ref error) = app.parameters_error {
         lines.push(Line::from(vec![
-            Span::styled("  PostgreSQL:  ", Style::default().fg(Color::Gray)),
-            Span::styled(
-                instance.pg_address.clone(),
-                Style::default().fg(Color::White),
-            ),
+            Span::styled("Error: ", Style::default().fg(Color::Red)),
+            Span::raw(tr(error).to_string()),
         ]));
     }
-
-    if !instance.http_address.is_empty() {
+    if app.parameters_search_active || !app.parameters_search.is_empty() {
+        let cursor = if app.parameters_search_active {
+            "_"
+        } else {
+            ""
+        };
         lines.push(Line::from(vec![
-            Span::styled("  HTTP:        ", Style::default().fg(Color::Gray)),
-            Span::styled(
-                instance.http_address.clone(),
-                Style::default().fg(Color::White),
-            ),
+            Span::styled("/", Style::default().fg(Color::Yellow)),
+            Span::raw(format!("{}{}", app.parameters_search, cursor)),
         ]));
     }
+    match app.parameters {
+        Some(_) => {
+            let params = app.visible_parameters();
+            lines.push(Line::from(Span::styled(
+                row(["Parameter", "Tier", "Value", "Default"].map(tr)),
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            )));
+            // Header, search, blank line and hint stay visible
+            let visible = (inner.height as usize).saturating_sub(4);
+            for param in params.iter().skip(app.parameters_scroll).take(visible) {
+                let default = parameters::default_value(&param.name).unwrap_or("");
+                let text = row([&param.name, &param.scope, &param.value, default]);
+                if parameters::is_changed(&param.name, &param.value) {
+                    lines.push(Line::from(Span::styled(
+                        text,
+                        Style::default()
+                            .fg(Color::Yellow)
+                            .add_modifier(Modifier::BOLD),
+                    )));
+                } else {
+                    lines.push(Line::from(text));
+                }
+            }
+            if params.is_empty() {
+                lines.push(Line::from(Span::styled(
+                    format!("  {}", tr("No matching parameters")),
+                    Style::default().fg(Color::DarkGray),
+                )));
+            }
+            lines.push(Line::from(""));
+            lines.push(Line::from(Span::styled(
+                tr("Highlighted values differ from the default. / to search, j/k to scroll, r to reload, Esc to close"),
+                Style::default().fg(Color::DarkGray),
+            )));
+        }
+        None if app.parameters_loading => lines.push(Line::from(tr("Loading parameters..."))),
+        None => {}
+    }
 
-    if !instance.failure_domain.is_empty() {
+    frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }), inner);
+}
+
+fn draw_rolling_restart(frame: &mut Frame, app: &App, restart: &RollingRestart, area: Rect) {
+    let popup_area = centered_rect(70, 70, area);
+
+    frame.render_widget(Clear, popup_area);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!(
+            " {}: {} ({}) ",
+            tr("Rolling restart"),
+            restart.replicaset,
+            restart.tier
+        ))
+        .style(Style::default().bg(Color::Black));
+
+    let inner = block.inner(popup_area);
+    frame.render_widget(block, popup_area);
+
+    let dim = Style::default().fg(Color::DarkGray);
+    let mut lines = Vec::new();
+
+    if restart.outcome == Outcome::Planned {
+        lines.push(Line::from(tr(
+            "Instances are restarted one at a time, followers first:",
+        )));
         lines.push(Line::from(""));
-        lines.push(Line::from(vec![Span::styled(
-            "Failure Domain:".to_string(),
-            Style::default()
-                .fg(Color::Yellow)
-                .add_modifier(Modifier::BOLD),
-        )]));
-        for (key, value) in &instance.failure_domain {
+        for name in &restart.instances {
+            lines.push(Line::from(format!("  · {}", name)));
+        }
+        lines.push(Line::from(""));
+        for (label, step) in [
+            ("Leader switch", Step::SwitchLeader),
+            ("Restart", Step::GoOffline),
+        ] {
+            let command = restart
+                .command_for(step)
+                .map_or_else(|| tr("by hand").to_string(), |c| format!("$ {}", c));
             lines.push(Line::from(vec![
-                Span::styled(format!("  {}:", key), Style::default().fg(Color::Gray)),
-                Span::raw(" "),
-                Span::styled(value.clone(), Style::default().fg(Color::White)),
+                Span::styled(format!("{}: ", tr(label)), Style::default().fg(Color::Cyan)),
+                Span::raw(command),
             ]));
         }
+    } else {
+        let running = restart.is_running();
+        for (index, name) in restart.instances.iter().enumerate() {
+            let finished = index < restart.current
+                || (index == restart.current && restart.outcome == Outcome::Done);
+            let line = if finished {
+                Line::from(Span::styled(
+                    format!("  ✓ {}", name),
+                    Style::default().fg(Color::Green),
+                ))
+            } else if index == restart.current && running {
+                Line::from(Span::styled(
+                    format!(
+                        "  ▶ {}  {} ({}s)",
+                        name,
+                        tr(restart.step.label()),
+                        restart.step_elapsed(Instant::now()).as_secs()
+                    ),
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                ))
+            } else {
+                Line::from(Span::styled(format!("  · {}", name), dim))
+            };
+            lines.push(line);
+            if index == restart.current && running {
+                match restart.current_command(&app.tiers) {
+                    Some(command) => lines.push(Line::from(Span::styled(
+                        format!("      $ {}", command),
+                        dim,
+                    ))),
+                    None => {
+                        if let Some(hint) = restart.step.manual_hint() {
+                            lines.push(Line::from(Span::styled(
+                                format!("      → {}", tr(hint)),
+                                Style::default().fg(Color::Cyan),
+                            )));
+                        }
+                    }
+                }
+            }
+        }
+        lines.push(Line::from(""));
+        match &restart.outcome {
+            Outcome::Done => lines.push(Line::from(Span::styled(
+                tr("All instances restarted"),
+                Style::default().fg(Color::Green),
+            ))),
+            Outcome::Failed(reason) => lines.push(Line::from(vec![
+                Span::styled(
+                    format!("{}: ", tr("Stopped")),
+                    Style::default().fg(Color::Red),
+                ),
+                Span::raw(reason.clone()),
+            ])),
+            Outcome::Aborted => lines.push(Line::from(Span::styled(
+                tr("Aborted; the remaining instances were not touched"),
+                Style::default().fg(Color::Yellow),
+            ))),
+            Outcome::Planned | Outcome::Running => {}
+        }
+        // The latest steps that fit, above the hint
+        let room = (inner.height as usize).saturating_sub(lines.len() + 2);
+        let skip = restart.log.len().saturating_sub(room);
+        for entry in restart.log.iter().skip(skip) {
+            lines.push(Line::from(Span::styled(format!("  {}", entry), dim)));
+        }
     }
 
+    let hint = match restart.outcome {
+        Outcome::Planned if app.ops_dry_run => tr("Enter for a dry run, Esc to cancel"),
+        Outcome::Planned => tr("Enter to start, Esc to cancel"),
+        Outcome::Running => tr("x to abort before the next step"),
+        _ => tr("Esc to close"),
+    };
     lines.push(Line::from(""));
-    lines.push(Line::from(vec![Span::styled(
-        "Press Esc or Enter to close".to_string(),
-        Style::default().fg(Color::DarkGray),
-    )]));
+    lines.push(Line::from(Span::styled(hint, dim)));
 
-    let paragraph = Paragraph::new(lines).wrap(Wrap { trim: false });
-    frame.render_widget(paragraph, inner);
+    frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }), inner);
 }
 
 fn draw_health_status(frame: &mut Frame, app: &App, area: Rect) {
+    if let Some(ref group) = app.group_health {
+        draw_group_health(frame, group, area);
+        return;
+    }
     let popup_area = centered_rect(70, 80, area);
 
     frame.render_widget(Clear, popup_area);
 
     let title = if app.health_loading {
-        " Health Status (loading...) "
+        format!(" {} ({}) ", tr("Health Status"), tr("loading..."))
     } else if app.health_error.is_some() {
-        " Health Status (error) "
+        format!(" {} ({}) ", tr("Health Status"), tr("error"))
     } else {
-        " Health Status "
+        format!(" {} ", tr("Health Status"))
     };
 
     let block = Block::default()
@@ -710,7 +2676,7 @@ fn draw_health_status(frame: &mut Frame, app: &App, area: Rect) {
     let mut lines = Vec::new();
 
     if app.health_loading {
-        lines.push(Line::from("Loading health status..."));
+        lines.push(Line::from(tr("Loading health status...")));
     } else if let Some(ref error) = app.health_error {
         lines.push(Line::from(vec![
             Span::styled("Error: ", Style::default().fg(Color::Red)),
@@ -718,14 +2684,10 @@ fn draw_health_status(frame: &mut Frame, app: &App, area: Rect) {
         ]));
     } else if let Some(ref status) = app.health_status {
         // Status indicator with color
-        let (status_symbol, status_color) = match status.status {
-            HealthStatusLevel::Healthy => ("●", Color::Green),
-            HealthStatusLevel::Degraded => ("●", Color::Yellow),
-            HealthStatusLevel::Unhealthy => ("●", Color::Red),
-        };
+        let (status_symbol, status_color) = health_level_symbol(status.status);
 
         lines.push(Line::from(vec![
-            Span::styled("Status:       ", Style::default().fg(Color::Gray)),
+            field_label("Status:", 14),
             Span::styled(
                 format!("{} {}", status_symbol, status.status),
                 Style::default().fg(status_color),
@@ -743,7 +2705,7 @@ fn draw_health_status(frame: &mut Frame, app: &App, area: Rect) {
         }
 
         lines.push(Line::from(vec![
-            Span::styled("Uptime:       ", Style::default().fg(Color::Gray)),
+            field_label("Uptime:", 14),
             Span::styled(format_uptime(status.uptime_seconds), Style::default()),
         ]));
 
@@ -754,27 +2716,27 @@ fn draw_health_status(frame: &mut Frame, app: &App, area: Rect) {
         )]));
 
         lines.push(Line::from(vec![
-            Span::styled("Name:         ", Style::default().fg(Color::Gray)),
+            field_label("Name:", 14),
             Span::styled(status.name.clone(), Style::default().fg(Color::White)),
         ]));
 
         lines.push(Line::from(vec![
-            Span::styled("Version:      ", Style::default().fg(Color::Gray)),
+            field_label("Version:", 14),
             Span::styled(status.version.clone(), Style::default().fg(Color::Cyan)),
         ]));
 
         lines.push(Line::from(vec![
-            Span::styled("Tier:         ", Style::default().fg(Color::Gray)),
+            field_label("Tier:", 14),
             Span::raw(status.tier.clone()),
         ]));
 
         lines.push(Line::from(vec![
-            Span::styled("Replicaset:   ", Style::default().fg(Color::Gray)),
+            field_label("Replicaset:", 14),
             Span::raw(status.replicaset.clone()),
         ]));
 
         lines.push(Line::from(vec![
-            Span::styled("State:        ", Style::default().fg(Color::Gray)),
+            field_label("State:", 14),
             Span::raw(format!(
                 "{} → {}",
                 status.current_state, status.target_state
@@ -798,12 +2760,12 @@ fn draw_health_status(frame: &mut Frame, app: &App, area: Rect) {
         )]));
 
         lines.push(Line::from(vec![
-            Span::styled("State:        ", Style::default().fg(Color::Gray)),
+            field_label("State:", 14),
             Span::styled(status.raft.state.clone(), Style::default().fg(Color::Cyan)),
         ]));
 
         lines.push(Line::from(vec![
-            Span::styled("Term:         ", Style::default().fg(Color::Gray)),
+            field_label("Term:", 14),
             Span::raw(status.raft.term.to_string()),
         ]));
 
@@ -818,12 +2780,12 @@ fn draw_health_status(frame: &mut Frame, app: &App, area: Rect) {
             )
         };
         lines.push(Line::from(vec![
-            Span::styled("Leader:       ", Style::default().fg(Color::Gray)),
+            field_label("Leader:", 14),
             Span::raw(leader_info),
         ]));
 
         lines.push(Line::from(vec![
-            Span::styled("Applied:      ", Style::default().fg(Color::Gray)),
+            field_label("Applied:", 14),
             Span::raw(format!(
                 "{} / Committed: {}",
                 status.raft.applied_index, status.raft.committed_index
@@ -831,7 +2793,7 @@ fn draw_health_status(frame: &mut Frame, app: &App, area: Rect) {
         ]));
 
         lines.push(Line::from(vec![
-            Span::styled("Persisted:    ", Style::default().fg(Color::Gray)),
+            field_label("Persisted:", 14),
             Span::raw(format!(
                 "{} / Compacted: {}",
                 status.raft.persisted_index, status.raft.compacted_index
@@ -840,7 +2802,7 @@ fn draw_health_status(frame: &mut Frame, app: &App, area: Rect) {
 
         if status.limbo_owner != 0 {
             lines.push(Line::from(vec![
-                Span::styled("Limbo Owner:  ", Style::default().fg(Color::Gray)),
+                field_label("Limbo Owner:", 14),
                 Span::styled(
                     format!("raft_id: {}", status.limbo_owner),
                     Style::default().fg(Color::Yellow),
@@ -855,7 +2817,7 @@ fn draw_health_status(frame: &mut Frame, app: &App, area: Rect) {
         )]));
 
         lines.push(Line::from(vec![
-            Span::styled("Active:       ", Style::default().fg(Color::Gray)),
+            field_label("Active:", 14),
             Span::raw(format!(
                 "{} / {}",
                 status.buckets.active, status.buckets.total
@@ -864,7 +2826,7 @@ fn draw_health_status(frame: &mut Frame, app: &App, area: Rect) {
 
         if status.buckets.sending > 0 || status.buckets.receiving > 0 {
             lines.push(Line::from(vec![
-                Span::styled("Resharding:   ", Style::default().fg(Color::Gray)),
+                field_label("Resharding:", 14),
                 Span::styled(
                     format!(
                         "sending: {}, receiving: {}",
@@ -877,7 +2839,7 @@ fn draw_health_status(frame: &mut Frame, app: &App, area: Rect) {
 
         if status.buckets.garbage > 0 {
             lines.push(Line::from(vec![
-                Span::styled("Garbage:      ", Style::default().fg(Color::Gray)),
+                field_label("Garbage:", 14),
                 Span::raw(status.buckets.garbage.to_string()),
             ]));
         }
@@ -889,14 +2851,14 @@ fn draw_health_status(frame: &mut Frame, app: &App, area: Rect) {
         )]));
 
         lines.push(Line::from(vec![
-            Span::styled("Version:      ", Style::default().fg(Color::Gray)),
+            field_label("Version:", 14),
             Span::raw(status.cluster.version.clone()),
         ]));
     }
 
     lines.push(Line::from(""));
     lines.push(Line::from(vec![Span::styled(
-        "Press Esc to close, r to refresh",
+        tr("Press Esc to close, r to refresh"),
         Style::default().fg(Color::DarkGray),
     )]));
 
@@ -904,6 +2866,83 @@ fn draw_health_status(frame: &mut Frame, app: &App, area: Rect) {
     frame.render_widget(paragraph, inner);
 }
 
+/// Health of every instance of a tier or replicaset, one line each, with
+/// the reasons of the ones not healthy
+fn draw_group_health(frame: &mut Frame, group: &GroupHealth, area: Rect) {
+    let popup_area = centered_rect(70, 80, area);
+    frame.render_widget(Clear, popup_area);
+
+    let title = match group.results {
+        None => format!(
+            " {}: {} ({}) ",
+            tr("Health Status"),
+            group.title,
+            tr("loading...")
+        ),
+        Some(_) => format!(" {}: {} ", tr("Health Status"), group.title),
+    };
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(title)
+        .style(Style::default().bg(Color::Black));
+    let inner = block.inner(popup_area);
+    frame.render_widget(block, popup_area);
+
+    let mut lines = Vec::new();
+    match group.results {
+        None => lines.push(Line::from(trf(
+            "Probing {} instances...",
+            &[&group.instances.len()],
+        ))),
+        Some(ref results) => {
+            let width = group
+                .instances
+                .iter()
+                .map(|(name, _)| name.chars().count())
+                .max()
+                .unwrap_or(0);
+            for ((name, _), result) in group.instances.iter().zip(results) {
+                match result {
+                    Ok(status) => {
+                        let (symbol, color) = health_level_symbol(status.status);
+                        lines.push(Line::from(vec![
+                            Span::styled(format!("{} ", symbol), Style::default().fg(color)),
+                            Span::raw(format!("{:width$}  ", name, width = width)),
+                            Span::styled(status.status.to_string(), Style::default().fg(color)),
+                        ]));
+                        for reason in &status.reasons {
+                            lines.push(Line::from(Span::styled(
+                                format!("{:width$}    - {}", "", reason, width = width),
+                                Style::default().fg(color),
+                            )));
+                        }
+                    }
+                    Err(e) => lines.push(Line::from(vec![
+                        Span::styled("✗ ", Style::default().fg(Color::Red)),
+                        Span::raw(format!("{:width$}  ", name, width = width)),
+                        Span::styled(e.clone(), Style::default().fg(Color::Red)),
+                    ])),
+                }
+            }
+        }
+    }
+
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![Span::styled(
+        tr("Press Esc to close, r to refresh"),
+        Style::default().fg(Color::DarkGray),
+    )]));
+    frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }), inner);
+}
+
+fn health_level_symbol(level: HealthStatusLevel) -> (&'static str, Color) {
+    match level {
+        HealthStatusLevel::Healthy => ("●", Color::Green),
+        HealthStatusLevel::Degraded => ("●", Color::Yellow),
+        HealthStatusLevel::Unhealthy => ("●", Color::Red),
+    }
+}
+
 /// Format seconds as human-readable uptime (e.g., "3d 14h 22m")
 fn format_uptime(seconds: u64) -> String {
     let days = seconds / 86400;
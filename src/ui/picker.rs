@@ -0,0 +1,90 @@
+use super::centered_rect;
+use crate::i18n::tr;
+use crate::picker::{ClusterPicker, Probe};
+use ratatui::{
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph},
+    Frame,
+};
+
+pub fn draw_picker(frame: &mut Frame, picker: &ClusterPicker) {
+    let area = frame.area();
+    let bg = Block::default().style(Style::default().bg(Color::Black));
+    frame.render_widget(bg, area);
+
+    let popup_area = centered_rect(70, 60, area);
+    frame.render_widget(Clear, popup_area);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!(" {} ", tr("Choose a cluster")))
+        .style(Style::default().bg(Color::Black).fg(Color::White));
+    let inner = block.inner(popup_area);
+    frame.render_widget(block, popup_area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(1)])
+        .margin(1)
+        .split(inner);
+
+    let url_width = picker
+        .entries
+        .iter()
+        .map(|entry| entry.url.chars().count())
+        .max()
+        .unwrap_or(0);
+    let user_width = picker
+        .entries
+        .iter()
+        .filter_map(|entry| entry.user.as_ref())
+        .map(|user| user.chars().count())
+        .max()
+        .unwrap_or(0);
+    let items: Vec<ListItem> = picker
+        .entries
+        .iter()
+        .map(|entry| {
+            let (status, color) = probe_label(&entry.probe);
+            ListItem::new(Line::from(vec![
+                Span::raw(format!("{:<width$}  ", entry.url, width = url_width)),
+                Span::styled(
+                    format!(
+                        "{:<width$}  ",
+                        entry.user.as_deref().unwrap_or(""),
+                        width = user_width
+                    ),
+                    Style::default().fg(Color::Cyan),
+                ),
+                Span::styled(status, Style::default().fg(color)),
+            ]))
+        })
+        .collect();
+    let list = List::new(items)
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .highlight_symbol("> ");
+    let mut state = ListState::default().with_selected(Some(picker.selected));
+    frame.render_stateful_widget(list, chunks[0], &mut state);
+
+    let key = Style::default().fg(Color::Yellow);
+    let hint = Paragraph::new(Line::from(vec![
+        Span::styled("↑↓", key),
+        Span::raw(format!(" {}  ", tr("navigate"))),
+        Span::styled("Enter", key),
+        Span::raw(format!(" {}  ", tr("connect"))),
+        Span::styled("Esc", key),
+        Span::raw(format!(" {}", tr("quit"))),
+    ]));
+    frame.render_widget(hint, chunks[1]);
+}
+
+fn probe_label(probe: &Probe) -> (String, Color) {
+    match probe {
+        Probe::Probing => (tr("checking...").to_string(), Color::DarkGray),
+        Probe::Ready => (format!("● {}", tr("ready")), Color::Green),
+        Probe::AuthRequired => (format!("● {}", tr("login required")), Color::Yellow),
+        Probe::Down(reason) => (format!("● {}: {}", tr("down"), reason), Color::Red),
+    }
+}
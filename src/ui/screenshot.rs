@@ -0,0 +1,139 @@
+use ratatui::{
+    buffer::Buffer,
+    style::{Color, Modifier, Style},
+    text::Span,
+};
+use std::path::PathBuf;
+
+/// Display width of a cell symbol; the cells hidden behind a wide
+/// symbol are skipped so exported lines keep their on-screen width.
+fn symbol_width(symbol: &str) -> usize {
+    Span::raw(symbol).width().max(1)
+}
+
+/// Serialize a rendered buffer as plain text, one line per row,
+/// with trailing whitespace trimmed
+pub fn buffer_to_text(buffer: &Buffer) -> String {
+    let mut result = String::new();
+    for y in 0..buffer.area.height {
+        let mut line = String::new();
+        let mut x = 0;
+        while x < buffer.area.width {
+            let symbol = buffer[(buffer.area.x + x, buffer.area.y + y)].symbol();
+            line.push_str(symbol);
+            x += symbol_width(symbol) as u16;
+        }
+        result.push_str(line.trim_end());
+        result.push('\n');
+    }
+    result
+}
+
+/// Serialize a rendered buffer with ANSI SGR escape sequences, so the
+/// output can be replayed with `cat` in a color terminal
+pub fn buffer_to_ansi(buffer: &Buffer) -> String {
+    let mut result = String::new();
+    for y in 0..buffer.area.height {
+        let mut current: Option<Style> = None;
+        let mut x = 0;
+        while x < buffer.area.width {
+            let cell = &buffer[(buffer.area.x + x, buffer.area.y + y)];
+            let style = cell.style();
+            if current != Some(style) {
+                result.push_str(&sgr(style));
+                current = Some(style);
+            }
+            result.push_str(cell.symbol());
+            x += symbol_width(cell.symbol()) as u16;
+        }
+        result.push_str("\x1b[0m\n");
+    }
+    result
+}
+
+/// Build a full SGR sequence (reset + attributes) for a style
+fn sgr(style: Style) -> String {
+    let mut codes = vec!["0".to_string()];
+
+    let modifiers = style.add_modifier;
+    for (modifier, code) in [
+        (Modifier::BOLD, "1"),
+        (Modifier::DIM, "2"),
+        (Modifier::ITALIC, "3"),
+        (Modifier::UNDERLINED, "4"),
+        (Modifier::REVERSED, "7"),
+        (Modifier::CROSSED_OUT, "9"),
+    ] {
+        if modifiers.contains(modifier) {
+            codes.push(code.to_string());
+        }
+    }
+
+    if let Some(fg) = style.fg {
+        codes.push(color_code(fg, false));
+    }
+    if let Some(bg) = style.bg {
+        codes.push(color_code(bg, true));
+    }
+
+    format!("\x1b[{}m", codes.join(";"))
+}
+
+fn color_code(color: Color, background: bool) -> String {
+    let base = if background { 40 } else { 30 };
+    let bright = if background { 100 } else { 90 };
+    match color {
+        Color::Reset => (base + 9).to_string(),
+        Color::Black => base.to_string(),
+        Color::Red => (base + 1).to_string(),
+        Color::Green => (base + 2).to_string(),
+        Color::Yellow => (base + 3).to_string(),
+        Color::Blue => (base + 4).to_string(),
+        Color::Magenta => (base + 5).to_string(),
+        Color::Cyan => (base + 6).to_string(),
+        Color::Gray => (base + 7).to_string(),
+        Color::DarkGray => bright.to_string(),
+        Color::LightRed => (bright + 1).to_string(),
+        Color::LightGreen => (bright + 2).to_string(),
+        Color::LightYellow => (bright + 3).to_string(),
+        Color::LightBlue => (bright + 4).to_string(),
+        Color::LightMagenta => (bright + 5).to_string(),
+        Color::LightCyan => (bright + 6).to_string(),
+        Color::White => (bright + 7).to_string(),
+        Color::Indexed(i) => format!("{};5;{}", base + 8, i),
+        Color::Rgb(r, g, b) => format!("{};2;{};{};{}", base + 8, r, g, b),
+    }
+}
+
+/// Output format for screen dumps
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScreenshotFormat {
+    Text,
+    Ansi,
+}
+
+impl ScreenshotFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ScreenshotFormat::Text => "txt",
+            ScreenshotFormat::Ansi => "ansi",
+        }
+    }
+}
+
+/// Write the buffer to `picotui-<unix time>.<ext>` in the current directory
+pub fn save(buffer: &Buffer, format: ScreenshotFormat) -> std::io::Result<PathBuf> {
+    let path = PathBuf::from(format!(
+        "picotui-{}.{}",
+        super::format::unix_now(),
+        format.extension()
+    ));
+
+    let contents = match format {
+        ScreenshotFormat::Text => buffer_to_text(buffer),
+        ScreenshotFormat::Ansi => buffer_to_ansi(buffer),
+    };
+    std::fs::write(&path, contents)?;
+
+    Ok(path)
+}
@@ -0,0 +1,357 @@
+use crate::models::StateVariant;
+use anyhow::{anyhow, Context, Result};
+use ratatui::buffer::Buffer;
+use ratatui::style::{Color, Modifier, Style};
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Colors the UI picks by role rather than hardcoding
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Theme {
+    pub name: String,
+    pub online: Color,
+    pub offline: Color,
+    pub expelled: Color,
+    /// Background of the selected row
+    pub selection: Color,
+    /// Key names in the status bar hints
+    pub key: Color,
+    /// Replicaset leaders: the ★ marker and the instance name
+    pub leader: Color,
+    /// Colors the terminal gets, whatever the skin asks for
+    pub depth: ColorDepth,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            name: "default".to_string(),
+            online: Color::Green,
+            offline: Color::Red,
+            expelled: Color::DarkGray,
+            selection: Color::DarkGray,
+            key: Color::Yellow,
+            leader: Color::Yellow,
+            depth: ColorDepth::Full,
+        }
+    }
+}
+
+impl Theme {
+    pub fn state_color(&self, state: StateVariant) -> Color {
+        match state {
+            StateVariant::Online => self.online,
+            StateVariant::Offline => self.offline,
+            StateVariant::Expelled => self.expelled,
+        }
+    }
+
+    pub fn state_style(&self, state: StateVariant) -> Style {
+        Style::default().fg(self.state_color(state))
+    }
+
+    pub fn selection_style(&self) -> Style {
+        // Without colors a background would disappear
+        if self.depth == ColorDepth::Monochrome {
+            return Style::default().add_modifier(Modifier::REVERSED | Modifier::BOLD);
+        }
+        Style::default()
+            .bg(self.selection)
+            .add_modifier(Modifier::BOLD)
+    }
+
+    pub fn key_style(&self) -> Style {
+        Style::default().fg(self.key)
+    }
+
+    /// Style of an instance name, accented for replicaset leaders
+    pub fn instance_name_style(&self, is_leader: bool) -> Style {
+        if is_leader {
+            Style::default().fg(self.leader)
+        } else {
+            Style::default().fg(Color::White)
+        }
+    }
+}
+
+/// `--color`: whether to color the UI, and with how many colors
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorMode {
+    /// Follow `NO_COLOR` and the terminal type
+    #[default]
+    Auto,
+    Always,
+    Never,
+    /// The 16 basic ANSI colors only
+    Ansi16,
+}
+
+impl FromStr for ColorMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "auto" => Ok(ColorMode::Auto),
+            "always" => Ok(ColorMode::Always),
+            "never" => Ok(ColorMode::Never),
+            "16" => Ok(ColorMode::Ansi16),
+            other => Err(anyhow!(
+                "Unknown color mode '{}' (expected auto, always, never or 16)",
+                other
+            )),
+        }
+    }
+}
+
+impl ColorMode {
+    /// Colors to draw with. `no_color` is whether `NO_COLOR` is set to
+    /// something, `term` the value of `TERM`; both only matter for `auto`.
+    pub fn depth(self, no_color: bool, term: Option<&str>) -> ColorDepth {
+        match self {
+            ColorMode::Always => ColorDepth::Full,
+            ColorMode::Never => ColorDepth::Monochrome,
+            ColorMode::Ansi16 => ColorDepth::Ansi16,
+            ColorMode::Auto if no_color => ColorDepth::Monochrome,
+            ColorMode::Auto => match term.unwrap_or_default() {
+                "dumb" => ColorDepth::Monochrome,
+                "linux" | "vt100" | "vt220" | "ansi" => ColorDepth::Ansi16,
+                term if term.ends_with("-16color") => ColorDepth::Ansi16,
+                _ => ColorDepth::Full,
+            },
+        }
+    }
+}
+
+/// Colors a frame may use. Everything is drawn as if all were available
+/// and brought down to what the terminal gets just before the frame goes
+/// out, so no widget has to care.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorDepth {
+    #[default]
+    Full,
+    Ansi16,
+    /// No colors, only bold, reverse and the other modifiers
+    Monochrome,
+}
+
+impl ColorDepth {
+    /// Bring every color of a rendered frame down to this depth
+    pub fn apply(self, buf: &mut Buffer) {
+        if self == ColorDepth::Full {
+            return;
+        }
+        for cell in buf.content.iter_mut() {
+            cell.fg = self.color(cell.fg);
+            cell.bg = self.color(cell.bg);
+            cell.underline_color = self.color(cell.underline_color);
+        }
+    }
+
+    pub fn color(self, color: Color) -> Color {
+        match self {
+            ColorDepth::Full => color,
+            ColorDepth::Monochrome => Color::Reset,
+            ColorDepth::Ansi16 => match color {
+                Color::Rgb(r, g, b) => nearest_ansi16((r, g, b)),
+                Color::Indexed(i) => match ANSI16.get(i as usize) {
+                    Some(&(color, _)) => color,
+                    None => nearest_ansi16(indexed_rgb(i)),
+                },
+                color => color,
+            },
+        }
+    }
+}
+
+/// The 16 basic colors in index order, with the xterm RGB values they
+/// usually get
+const ANSI16: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (205, 0, 0)),
+    (Color::Green, (0, 205, 0)),
+    (Color::Yellow, (205, 205, 0)),
+    (Color::Blue, (0, 0, 238)),
+    (Color::Magenta, (205, 0, 205)),
+    (Color::Cyan, (0, 205, 205)),
+    (Color::Gray, (229, 229, 229)),
+    (Color::DarkGray, (127, 127, 127)),
+    (Color::LightRed, (255, 0, 0)),
+    (Color::LightGreen, (0, 255, 0)),
+    (Color::LightYellow, (255, 255, 0)),
+    (Color::LightBlue, (92, 92, 255)),
+    (Color::LightMagenta, (255, 0, 255)),
+    (Color::LightCyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+/// RGB value of a 256-color palette entry past the basic 16: the 6x6x6
+/// color cube, then a ramp of grays
+fn indexed_rgb(index: u8) -> (u8, u8, u8) {
+    if index >= 232 {
+        let level = 8 + (index - 232) * 10;
+        return (level, level, level);
+    }
+    let level = |n: u8| if n == 0 { 0 } else { 55 + n * 40 };
+    let n = index - 16;
+    (level(n / 36), level(n / 6 % 6), level(n % 6))
+}
+
+fn nearest_ansi16((r, g, b): (u8, u8, u8)) -> Color {
+    let distance = |&(_, (r2, g2, b2)): &(Color, (u8, u8, u8))| {
+        [(r, r2), (g, g2), (b, b2)]
+            .iter()
+            .map(|&(a, b)| (i32::from(a) - i32::from(b)).pow(2))
+            .sum::<i32>()
+    };
+    ANSI16
+        .iter()
+        .min_by_key(|entry| distance(entry))
+        .map_or(Color::Reset, |&(color, _)| color)
+}
+
+/// Contents of a skin file. Every color is optional and falls back to the
+/// default theme; colors are names (`light_blue`), `#rrggbb` or 0-255 indexes.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct SkinFile {
+    name: Option<String>,
+    online: Option<String>,
+    offline: Option<String>,
+    expelled: Option<String>,
+    selection: Option<String>,
+    key: Option<String>,
+    leader: Option<String>,
+}
+
+/// Default skins directory
+pub fn skins_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|p| p.join("picotui/skins"))
+}
+
+/// Parse a skin file; `fallback_name` is used when it doesn't set a name
+pub fn parse_skin(text: &str, fallback_name: &str) -> Result<Theme> {
+    let skin: SkinFile = toml::from_str(text)?;
+    let base = Theme::default();
+    let color = |value: Option<String>, default: Color| -> Result<Color> {
+        match value {
+            Some(value) => {
+                Color::from_str(&value).map_err(|_| anyhow!("Unknown color \"{}\"", value))
+            }
+            None => Ok(default),
+        }
+    };
+    Ok(Theme {
+        name: skin.name.unwrap_or_else(|| fallback_name.to_string()),
+        online: color(skin.online, base.online)?,
+        offline: color(skin.offline, base.offline)?,
+        expelled: color(skin.expelled, base.expelled)?,
+        selection: color(skin.selection, base.selection)?,
+        key: color(skin.key, base.key)?,
+        leader: color(skin.leader, base.leader)?,
+        depth: base.depth,
+    })
+}
+
+/// The built-in theme followed by every `*.toml` skin in `dir`, in file
+/// name order. Skins that fail to load are reported instead of aborting.
+pub fn load_themes(dir: &Path) -> (Vec<Theme>, Vec<String>) {
+    let mut themes = vec![Theme::default()];
+    let mut errors = Vec::new();
+
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+                .collect()
+        })
+        .unwrap_or_default();
+    paths.sort();
+
+    for path in paths {
+        let stem = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or_default();
+        let loaded = fs::read_to_string(&path)
+            .with_context(|| format!("Could not read {}", path.display()))
+            .and_then(|text| parse_skin(&text, stem));
+        match loaded {
+            Ok(theme) => themes.push(theme),
+            Err(e) => errors.push(format!(
+                "{}: {}",
+                path.file_name().unwrap_or_default().to_string_lossy(),
+                e
+            )),
+        }
+    }
+
+    (themes, errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_skin_falls_back_to_defaults() {
+        let theme = parse_skin(
+            "online = \"#00ff00\"\nkey = \"light_blue\"\nleader = \"magenta\"\n",
+            "neon",
+        )
+        .unwrap();
+        assert_eq!(theme.name, "neon");
+        assert_eq!(theme.online, Color::Rgb(0, 255, 0));
+        assert_eq!(theme.key, Color::LightBlue);
+        assert_eq!(theme.leader, Color::Magenta);
+        assert_eq!(theme.offline, Theme::default().offline);
+
+        assert!(parse_skin("online = \"no-such-color\"", "x").is_err());
+        assert!(parse_skin("onlin = \"green\"", "x").is_err());
+    }
+
+    #[test]
+    fn test_color_depth() {
+        assert_eq!(
+            ColorMode::Auto.depth(true, Some("xterm-256color")),
+            ColorDepth::Monochrome
+        );
+        assert_eq!(ColorMode::Always.depth(true, None), ColorDepth::Full);
+        assert_eq!(
+            ColorMode::Auto.depth(false, Some("linux")),
+            ColorDepth::Ansi16
+        );
+        assert_eq!(
+            ColorMode::Auto.depth(false, Some("dumb")),
+            ColorDepth::Monochrome
+        );
+        assert_eq!(
+            ColorMode::Auto.depth(false, Some("xterm-256color")),
+            ColorDepth::Full
+        );
+
+        let ansi = ColorDepth::Ansi16;
+        assert_eq!(ansi.color(Color::Rgb(0, 250, 10)), Color::LightGreen);
+        assert_eq!(ansi.color(Color::Indexed(1)), Color::Red);
+        assert_eq!(ansi.color(Color::Indexed(196)), Color::LightRed);
+        assert_eq!(ansi.color(Color::Indexed(244)), Color::DarkGray);
+        assert_eq!(ansi.color(Color::Cyan), Color::Cyan);
+        assert_eq!(ColorDepth::Monochrome.color(Color::Cyan), Color::Reset);
+    }
+
+    #[test]
+    fn test_load_themes_skips_broken_skins() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("b.toml"), "name = \"Solar\"\n").unwrap();
+        fs::write(dir.path().join("a.toml"), "online = [").unwrap();
+        fs::write(dir.path().join("notes.txt"), "ignored").unwrap();
+
+        let (themes, errors) = load_themes(dir.path());
+        let names: Vec<_> = themes.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, vec!["default", "Solar"]);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("a.toml: "));
+    }
+}
@@ -0,0 +1,42 @@
+use crate::app::App;
+use crate::toasts::ToastKind;
+use ratatui::{
+    layout::Rect,
+    style::{Color, Style},
+    widgets::{Block, Borders, Clear, Padding, Paragraph},
+    Frame,
+};
+
+const MAX_WIDTH: u16 = 50;
+const HEIGHT: u16 = 3;
+
+/// Toasts stacked in the top right corner from `top` down, newest first,
+/// as many as fit
+pub fn draw_toasts(frame: &mut Frame, app: &App, area: Rect, top: u16) {
+    let mut y = top;
+    for toast in app.toasts.iter().rev() {
+        if y + HEIGHT > area.bottom() {
+            break;
+        }
+        let width = (toast.message.chars().count() as u16 + 4)
+            .min(MAX_WIDTH)
+            .min(area.width);
+        let rect = Rect::new(area.right() - width, y, width, HEIGHT);
+        let color = match toast.kind {
+            ToastKind::Info => Color::Cyan,
+            ToastKind::Success => Color::Green,
+            ToastKind::Error => Color::Red,
+        };
+
+        frame.render_widget(Clear, rect);
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(color))
+            .padding(Padding::horizontal(1))
+            .style(Style::default().bg(Color::Black));
+        let inner = block.inner(rect);
+        frame.render_widget(block, rect);
+        frame.render_widget(Paragraph::new(toast.message.as_str()), inner);
+        y += HEIGHT;
+    }
+}
@@ -0,0 +1,64 @@
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+
+/// Last username that logged in to each cluster, keyed by normalized URL.
+/// Passwords are never stored.
+type UsernamesFile = HashMap<String, String>;
+
+/// Default location of the usernames file
+pub fn usernames_file_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|p| p.join("picotui/usernames.json"))
+}
+
+fn read_usernames_file(path: &Path) -> UsernamesFile {
+    File::open(path)
+        .ok()
+        .and_then(|file| serde_json::from_reader(file).ok())
+        .unwrap_or_default()
+}
+
+/// Load the last username used for the cluster at `url`
+pub fn load_username(path: &Path, url: &str) -> Option<String> {
+    read_usernames_file(path).remove(url.trim_end_matches('/'))
+}
+
+/// Remember `username` for the cluster at `url`, keeping other clusters' entries
+pub fn save_username(path: &Path, url: &str, username: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut all = read_usernames_file(path);
+    all.insert(url.trim_end_matches('/').to_string(), username.to_string());
+
+    let file = File::create(path).with_context(|| format!("Could not write {}", path.display()))?;
+    serde_json::to_writer_pretty(file, &all)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_usernames_are_kept_per_cluster() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("usernames.json");
+        assert_eq!(load_username(&path, "http://a:8080"), None);
+
+        save_username(&path, "http://a:8080/", "admin").unwrap();
+        save_username(&path, "http://b:8080", "ops").unwrap();
+        save_username(&path, "http://a:8080", "alice").unwrap();
+
+        assert_eq!(
+            load_username(&path, "http://a:8080").as_deref(),
+            Some("alice")
+        );
+        assert_eq!(
+            load_username(&path, "http://b:8080/").as_deref(),
+            Some("ops")
+        );
+    }
+}
@@ -0,0 +1,267 @@
+use crate::api::{self, ApiMessage, ApiRequest, ApiResponse};
+use crate::models::*;
+use crate::tokens;
+use anyhow::{anyhow, Result};
+use std::str::FromStr;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::time::{Duration, Instant};
+
+/// Condition checked by `--wait-until` in headless mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WaitCondition {
+    /// Every instance is currently Online
+    AllOnline,
+    /// Every instance's current state matches its target state
+    Converged,
+    /// Every replicaset is Ready (Picodata 26.2+)
+    AllReady,
+}
+
+impl WaitCondition {
+    pub fn label(self) -> &'static str {
+        match self {
+            WaitCondition::AllOnline => "all-online",
+            WaitCondition::Converged => "converged",
+            WaitCondition::AllReady => "all-ready",
+        }
+    }
+
+    /// Check the condition against a tiers snapshot.
+    /// An empty cluster never satisfies a condition.
+    pub fn is_met(self, tiers: &[TierInfo]) -> bool {
+        let mut instances = tiers
+            .iter()
+            .flat_map(|t| t.replicasets.iter())
+            .flat_map(|r| r.instances.iter())
+            .peekable();
+        if instances.peek().is_none() {
+            return false;
+        }
+
+        match self {
+            WaitCondition::AllOnline => {
+                instances.all(|inst| inst.current_state == StateVariant::Online)
+            }
+            WaitCondition::Converged => {
+                instances.all(|inst| inst.current_state == inst.target_state)
+            }
+            WaitCondition::AllReady => tiers
+                .iter()
+                .flat_map(|t| t.replicasets.iter())
+                .all(|rs| rs.replicaset_state == ReplicasetState::Ready),
+        }
+    }
+}
+
+impl FromStr for WaitCondition {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "all-online" => Ok(WaitCondition::AllOnline),
+            "converged" => Ok(WaitCondition::Converged),
+            "all-ready" => Ok(WaitCondition::AllReady),
+            _ => Err(anyhow!(
+                "Unknown condition '{}' (expected all-online, converged or all-ready)",
+                s
+            )),
+        }
+    }
+}
+
+/// Outcome of a headless wait
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WaitOutcome {
+    Met,
+    TimedOut,
+}
+
+impl WaitOutcome {
+    /// Process exit code for the outcome
+    pub fn exit_code(self) -> i32 {
+        match self {
+            WaitOutcome::Met => 0,
+            WaitOutcome::TimedOut => 1,
+        }
+    }
+}
+
+/// Spawn an API worker for a headless mode, reaching `base_url` at
+/// `connect_url` (the local end of a tunnel, or `base_url` itself). There
+/// is no login screen, so when the cluster requires authentication the
+/// saved session is used. Returns `None` if the cluster doesn't answer
+/// within `timeout`.
+pub fn start_headless(
+    base_url: &str,
+    connect_url: &str,
+    timeout: Duration,
+    debug: bool,
+) -> Result<Option<(Sender<ApiRequest>, Receiver<ApiMessage>)>> {
+    let (request_tx, request_rx) = channel();
+    let (response_tx, response_rx) = channel();
+    api::spawn_api_worker_via(
+        base_url.to_string(),
+        connect_url.to_string(),
+        request_rx,
+        response_tx,
+        debug,
+    );
+
+    let _ = request_tx.send(ApiRequest::GetConfig);
+    match response_rx
+        .recv_timeout(timeout)
+        .map(|message| message.response)
+    {
+        Ok(ApiResponse::Config(Ok(config))) => {
+            if config.is_auth_enabled {
+                let entry = tokens::load_tokens(base_url).ok_or_else(|| {
+                    anyhow!(
+                        "Authentication is enabled but no saved session was found; \
+                         log in interactively with \"Remember me\" first"
+                    )
+                })?;
+                let _ = request_tx.send(ApiRequest::SetToken {
+                    auth: entry.auth,
+                    refresh: entry.refresh,
+                    remember: true,
+                });
+            }
+            Ok(Some((request_tx, response_rx)))
+        }
+        Ok(ApiResponse::Config(Err(e))) => Err(anyhow!(e)),
+        _ => Ok(None),
+    }
+}
+
+/// Poll the cluster until `condition` holds or `timeout` passes.
+/// Progress is printed to stderr so scripts can keep stdout clean.
+pub fn wait_until(
+    base_url: &str,
+    connect_url: &str,
+    condition: WaitCondition,
+    interval: Duration,
+    timeout: Duration,
+    debug: bool,
+) -> Result<WaitOutcome> {
+    let deadline = Instant::now() + timeout;
+    let Some((request_tx, response_rx)) = start_headless(base_url, connect_url, timeout, debug)?
+    else {
+        return Ok(WaitOutcome::TimedOut);
+    };
+    let recv = |rx: &Receiver<ApiMessage>| {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        rx.recv_timeout(remaining.max(Duration::from_millis(1)))
+            .map(|message| message.response)
+            .ok()
+    };
+
+    let outcome = loop {
+        let _ = request_tx.send(ApiRequest::GetTiers);
+        match recv(&response_rx) {
+            Some(ApiResponse::Tiers(Ok(tiers))) => {
+                if condition.is_met(&tiers) {
+                    eprintln!("Condition '{}' met", condition.label());
+                    break WaitOutcome::Met;
+                }
+                eprintln!("Waiting for '{}'...", condition.label());
+            }
+            Some(ApiResponse::Tiers(Err(e))) => {
+                if e.contains("401") {
+                    let _ = request_tx.send(ApiRequest::Shutdown);
+                    return Err(anyhow!("Saved session is no longer valid: {}", e));
+                }
+                eprintln!("Waiting for '{}': {}", condition.label(), e);
+            }
+            _ => {}
+        }
+
+        if Instant::now() + interval >= deadline {
+            eprintln!("Timed out waiting for '{}'", condition.label());
+            break WaitOutcome::TimedOut;
+        }
+        std::thread::sleep(interval);
+    };
+
+    let _ = request_tx.send(ApiRequest::Shutdown);
+    Ok(outcome)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn instance(current: &str, target: &str) -> serde_json::Value {
+        json!({
+            "name": "i1",
+            "httpAddress": "",
+            "version": "25.6.0",
+            "failureDomain": {},
+            "isLeader": false,
+            "currentState": current,
+            "targetState": target,
+            "binaryAddress": "",
+            "pgAddress": ""
+        })
+    }
+
+    fn tiers_with(instances: Vec<serde_json::Value>) -> Vec<TierInfo> {
+        let memory = json!({"usable": 0, "used": 0});
+        serde_json::from_value(json!([{
+            "name": "default",
+            "replicasetCount": 1,
+            "rf": 1,
+            "bucketCount": 0,
+            "instanceCount": instances.len(),
+            "can_vote": true,
+            "services": [],
+            "memory": memory,
+            "capacityUsage": 0.0,
+            "replicasets": [{
+                "name": "r1",
+                "version": "1",
+                "state": "Online",
+                "instanceCount": instances.len(),
+                "uuid": "uuid-r1",
+                "capacityUsage": 0.0,
+                "memory": memory,
+                "instances": instances
+            }]
+        }]))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_parse_conditions() {
+        assert_eq!(
+            "all-online".parse::<WaitCondition>().unwrap(),
+            WaitCondition::AllOnline
+        );
+        assert_eq!(
+            "converged".parse::<WaitCondition>().unwrap(),
+            WaitCondition::Converged
+        );
+        assert!("everything".parse::<WaitCondition>().is_err());
+    }
+
+    #[test]
+    fn test_all_online() {
+        let online = tiers_with(vec![instance("Online", "Online")]);
+        let offline = tiers_with(vec![
+            instance("Online", "Online"),
+            instance("Offline", "Online"),
+        ]);
+
+        assert!(WaitCondition::AllOnline.is_met(&online));
+        assert!(!WaitCondition::AllOnline.is_met(&offline));
+        assert!(!WaitCondition::AllOnline.is_met(&[]));
+    }
+
+    #[test]
+    fn test_converged_accepts_intentionally_offline() {
+        let tiers = tiers_with(vec![instance("Offline", "Offline")]);
+
+        assert!(WaitCondition::Converged.is_met(&tiers));
+        assert!(!WaitCondition::AllOnline.is_met(&tiers));
+    }
+}
@@ -0,0 +1,1953 @@
+//! App state tests using mock cluster data
+//!
+//! These tests exercise navigation and data refresh logic without rendering.
+
+mod common;
+
+use common::mock_tiers;
+use crossterm::event::{KeyCode, KeyModifiers};
+use picotui::actions::InstanceAction;
+use picotui::alerts::{AlertEngine, AlertRule};
+use picotui::api::{
+    ApiMessage, ApiRequest, ApiResponse, Endpoint, HealthProbes, ResponseMeta, WorkerPanic,
+};
+use picotui::app::{
+    App, Bell, InputMode, InstanceColumn, SortField, SortOrder, TreeItem, ViewMode,
+};
+use picotui::config::{RestartCommands, SavedFilter};
+use picotui::confirm::Severity;
+use picotui::heatmap;
+use picotui::models::{
+    ConfigParameter, InstanceInfo, StateVariant, TierInfo, TokenResponse, UiConfig,
+};
+use picotui::prepare;
+use picotui::rolling::{Outcome, RollingRestart};
+use picotui::source::{DataSource, HttpSource, SpawnSource};
+use picotui::toasts::{ToastKind, TOAST_DURATION};
+use ratatui::style::Color;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+use std::sync::mpsc::{channel, TryRecvError};
+use std::time::{Duration, Instant};
+
+/// Create a test app with mock tiers loaded
+fn test_app_with_tiers() -> App {
+    let (req_tx, _req_rx) = channel();
+    let (_res_tx, res_rx) = channel();
+    let mut app = App::new(
+        "http://test:8080".to_string(),
+        HttpSource::new(req_tx, res_rx),
+    );
+
+    app.set_tiers(serde_json::from_value(mock_tiers()).unwrap());
+    app.input_mode = InputMode::Normal;
+
+    app
+}
+
+/// Tree rows as (kind, tier, replicaset, instance) tuples for comparison
+fn tree_shape(app: &App) -> Vec<(u8, usize, usize, usize)> {
+    app.tree_items
+        .iter()
+        .map(|item| match *item {
+            TreeItem::Tier(t) => (0, t, 0, 0),
+            TreeItem::Replicaset(t, r) => (1, t, r, 0),
+            TreeItem::Instance(t, r, i) => (2, t, r, i),
+        })
+        .collect()
+}
+
+#[test]
+fn test_incremental_expand_matches_full_rebuild() {
+    let mut app = test_app_with_tiers();
+
+    // Expand "default", then its second replicaset, then "storage"
+    app.expand_selected();
+    app.selected_index = 2;
+    app.expand_selected();
+    app.select_last();
+    app.expand_selected();
+
+    let incremental = tree_shape(&app);
+    app.rebuild_tree();
+    assert_eq!(incremental, tree_shape(&app));
+
+    // Collapse "default" with a nested replicaset expanded
+    app.selected_index = 0;
+    app.collapse_selected();
+
+    let incremental = tree_shape(&app);
+    app.rebuild_tree();
+    assert_eq!(incremental, tree_shape(&app));
+    assert_eq!(app.tree_items.len(), 3, "default, storage, s1");
+}
+
+#[test]
+fn test_collapse_from_instance_selects_parent() {
+    let mut app = test_app_with_tiers();
+
+    app.expand_selected(); // default
+    app.selected_index = 1;
+    app.expand_selected(); // r1
+    app.selected_index = 3; // i2
+
+    app.collapse_selected();
+
+    assert_eq!(app.selected_index, 1);
+    assert!(matches!(app.tree_items[1], TreeItem::Replicaset(0, 0)));
+}
+
+#[test]
+fn test_refresh_preserves_selection_by_name() {
+    let mut app = test_app_with_tiers();
+
+    app.expand_selected(); // default
+    app.selected_index = 2;
+    app.expand_selected(); // r2
+    app.selected_index = 4; // i4
+    assert_eq!(app.get_selected_instance().unwrap().name, "i4");
+
+    // Server now returns tiers in a different order
+    let mut tiers: Vec<TierInfo> = serde_json::from_value(mock_tiers()).unwrap();
+    tiers.reverse();
+    app.set_tiers(tiers);
+
+    assert_eq!(
+        app.get_selected_instance().map(|i| i.name.as_str()),
+        Some("i4"),
+        "Selection should follow the instance, not the row index"
+    );
+    assert!(app.expanded_tiers.contains(&1), "default moved to index 1");
+    assert!(app.expanded_replicasets.contains(&(1, 1)));
+}
+
+#[test]
+fn test_refresh_falls_back_to_parent_when_node_disappears() {
+    let mut app = test_app_with_tiers();
+
+    app.expand_selected(); // default
+    app.selected_index = 1;
+    app.expand_selected(); // r1
+    app.selected_index = 3; // i2
+
+    let mut tiers: Vec<TierInfo> = serde_json::from_value(mock_tiers()).unwrap();
+    tiers[0].replicasets[0].instances.pop();
+    app.set_tiers(tiers);
+
+    assert!(matches!(
+        app.tree_items[app.selected_index],
+        TreeItem::Replicaset(0, 0)
+    ));
+}
+
+#[test]
+fn test_refresh_preserves_instances_view_selection() {
+    let mut app = test_app_with_tiers();
+    app.view_mode = ViewMode::Instances;
+    app.selected_index = 2; // i3
+    assert_eq!(app.get_selected_instance().unwrap().name, "i3");
+
+    let mut tiers: Vec<TierInfo> = serde_json::from_value(mock_tiers()).unwrap();
+    tiers[0].replicasets[0].instances.clear();
+    app.set_tiers(tiers);
+
+    assert_eq!(app.get_selected_instance().unwrap().name, "i3");
+}
+
+#[test]
+fn test_refresh_rebuilds_rows_only_when_the_shape_changes() {
+    let mut app = test_app_with_tiers();
+    app.expand_selected(); // default
+    app.selected_index = 1;
+    app.expand_selected(); // r1
+    app.selected_index = 3; // i2
+    let rows = tree_shape(&app);
+
+    let mut tiers: Vec<TierInfo> = serde_json::from_value(mock_tiers()).unwrap();
+    tiers[0].replicasets[0].instances[1].current_state = StateVariant::Offline;
+    app.set_tiers(tiers.clone());
+    assert_eq!(tree_shape(&app), rows);
+    assert_eq!(app.selected_index, 3);
+    assert_eq!(
+        app.get_selected_instance().unwrap().current_state,
+        StateVariant::Offline
+    );
+
+    let mut extra = tiers[0].replicasets[0].instances[1].clone();
+    extra.name = "i2b".to_string();
+    tiers[0].replicasets[0].instances.push(extra);
+    app.set_tiers(tiers);
+    let refreshed = tree_shape(&app);
+    assert_eq!(refreshed.len(), rows.len() + 1);
+    app.rebuild_tree();
+    assert_eq!(tree_shape(&app), refreshed);
+    assert_eq!(app.get_selected_instance().unwrap().name, "i2");
+}
+
+/// Mock tiers with per-replicaset bucket counts for "default" (3000 buckets)
+fn tiers_with_buckets(r1: u64, r2: u64) -> Vec<TierInfo> {
+    let mut tiers = mock_tiers();
+    tiers[0]["bucketCount"] = 3000.into();
+    tiers[0]["replicasets"][0]["bucketCount"] = r1.into();
+    tiers[0]["replicasets"][1]["bucketCount"] = r2.into();
+    serde_json::from_value(tiers).unwrap()
+}
+
+#[test]
+fn test_rebalancing_detected_from_bucket_movement() {
+    let mut app = test_app_with_tiers();
+    assert!(
+        !app.is_rebalancing("default"),
+        "No per-replicaset counts, no indicator"
+    );
+
+    app.set_tiers(tiers_with_buckets(1500, 1500));
+    assert!(!app.is_rebalancing("default"));
+
+    // Buckets moved from r1 to r2
+    app.set_tiers(tiers_with_buckets(1400, 1600));
+    assert!(app.is_rebalancing("default"));
+    assert!(!app.is_rebalancing("storage"));
+
+    // Buckets in transit are missing from the per-replicaset sum
+    app.set_tiers(tiers_with_buckets(1400, 1590));
+    assert!(app.is_rebalancing("default"));
+
+    // Settled
+    app.set_tiers(tiers_with_buckets(1400, 1600));
+    app.set_tiers(tiers_with_buckets(1400, 1600));
+    assert!(!app.is_rebalancing("default"));
+}
+
+#[test]
+fn test_detail_popup_follows_refreshes() {
+    let mut app = test_app_with_tiers();
+    app.view_mode = ViewMode::Instances;
+    app.selected_index = 1; // i2
+    app.expand_selected();
+    assert!(app.show_detail);
+    assert!(!app.detail_recently_updated());
+
+    // Same data: nothing to flash
+    app.set_tiers(serde_json::from_value(mock_tiers()).unwrap());
+    assert!(!app.detail_recently_updated());
+
+    let mut tiers = mock_tiers();
+    tiers[0]["replicasets"][0]["instances"][1]["currentState"] = "Offline".into();
+    app.set_tiers(serde_json::from_value(tiers.clone()).unwrap());
+    let shown = app.detail_instance().unwrap();
+    assert_eq!(shown.name, "i2");
+    assert_eq!(shown.current_state, StateVariant::Offline);
+    assert!(app.detail_recently_updated());
+
+    // The instance went away: close instead of showing a neighbour
+    tiers[0]["replicasets"][0]["instances"]
+        .as_array_mut()
+        .unwrap()
+        .remove(1);
+    app.set_tiers(serde_json::from_value(tiers).unwrap());
+    assert!(!app.show_detail);
+    assert_eq!(app.status_message.as_deref(), Some("i2 left the cluster"));
+}
+
+#[test]
+fn test_tiers_prepared_off_the_ui_thread() {
+    let (raw_tx, raw_rx) = channel();
+    let (prepared_tx, prepared_rx) = channel();
+    prepare::spawn_preparer(raw_rx, prepared_tx);
+    let meta = ResponseMeta {
+        latency: Duration::from_millis(1),
+        reachable: true,
+        bytes: 0,
+    };
+    let send = |response| raw_tx.send(ApiMessage { response, meta }).unwrap();
+    let recv = || {
+        prepared_rx
+            .recv_timeout(Duration::from_secs(5))
+            .unwrap()
+            .response
+    };
+
+    let (req_tx, _req_rx) = channel();
+    let (_res_tx, res_rx) = channel();
+    let mut app = App::new(
+        "http://test:8080".to_string(),
+        HttpSource::new(req_tx, res_rx),
+    );
+    app.input_mode = InputMode::Normal;
+
+    send(ApiResponse::Tiers(Ok(tiers_with_buckets(1500, 1500))));
+    send(ApiResponse::Tiers(Ok(tiers_with_buckets(1400, 1600))));
+    send(ApiResponse::Tiers(Err("HTTP 500".to_string())));
+
+    for _ in 0..2 {
+        match recv() {
+            ApiResponse::PreparedTiers(prepared) => app.set_prepared_tiers(*prepared),
+            other => panic!("expected prepared tiers, got {:?}", other),
+        }
+    }
+    assert!(
+        app.is_rebalancing("default"),
+        "compared with the previous snapshot"
+    );
+    assert_eq!(app.cluster_health.as_ref().unwrap().score, 90);
+    assert_eq!(app.tree_items.len(), 2);
+
+    assert!(
+        matches!(recv(), ApiResponse::Tiers(Err(_))),
+        "errors pass through"
+    );
+}
+
+#[test]
+fn test_search_reveals_match_under_collapsed_parents() {
+    let mut app = test_app_with_tiers();
+
+    app.start_search();
+    app.search_text = "i3".to_string();
+    app.update_search();
+    app.confirm_search();
+
+    assert_eq!(app.get_selected_instance().unwrap().name, "i3");
+    assert!(app.expanded_tiers.contains(&0));
+    assert!(app.expanded_replicasets.contains(&(0, 1)));
+    assert!(app.status_message.is_none());
+}
+
+#[test]
+fn test_search_next_and_prev_wrap_without_hiding_rows() {
+    let mut app = test_app_with_tiers();
+    app.view_mode = ViewMode::Instances;
+
+    app.start_search();
+    app.search_text = "s1-".to_string();
+    app.update_search();
+    app.confirm_search();
+    assert_eq!(app.get_selected_instance().unwrap().name, "s1-i1");
+
+    app.search_next(true);
+    assert_eq!(app.get_selected_instance().unwrap().name, "s1-i2");
+    app.search_next(true);
+    assert_eq!(app.get_selected_instance().unwrap().name, "s1-i1", "wraps");
+    app.search_next(false);
+    assert_eq!(app.get_selected_instance().unwrap().name, "s1-i2");
+
+    assert_eq!(app.get_item_count(), 6, "search does not filter");
+}
+
+#[test]
+fn test_search_cancel_restores_selection() {
+    let mut app = test_app_with_tiers();
+    app.view_mode = ViewMode::Replicasets;
+    app.selected_index = 1;
+
+    app.start_search();
+    app.search_text = "s1".to_string();
+    app.update_search();
+    assert_eq!(app.selected_index, 2);
+
+    app.cancel_search();
+    assert_eq!(app.selected_index, 1);
+    assert!(app.search_text.is_empty());
+}
+
+#[test]
+fn test_search_without_match_reports_it() {
+    let mut app = test_app_with_tiers();
+
+    app.start_search();
+    app.search_text = "nope".to_string();
+    app.update_search();
+    app.confirm_search();
+
+    assert_eq!(app.selected_index, 0);
+    assert!(app
+        .status_message
+        .as_deref()
+        .is_some_and(|m| m.contains("not found")));
+}
+
+#[test]
+fn test_fuzzy_filter_ranks_by_score() {
+    let mut app = test_app_with_tiers();
+    app.view_mode = ViewMode::Instances;
+
+    // "si2" is not a substring of anything
+    app.filter_text = "si2".to_string();
+    assert_eq!(app.get_item_count(), 0);
+
+    app.fuzzy_filter = true;
+    let names: Vec<&str> = app
+        .get_sorted_instances()
+        .iter()
+        .map(|(_, _, inst)| inst.name.as_str())
+        .collect();
+    assert_eq!(names.first(), Some(&"s1-i2"), "best match first");
+    assert!(!names.contains(&"i1"), "non-matching instances are hidden");
+}
+
+#[test]
+fn test_filter_expression_operators() {
+    let mut app = test_app_with_tiers();
+    app.view_mode = ViewMode::Instances;
+    let names = |app: &App| -> Vec<String> {
+        app.get_sorted_instances()
+            .iter()
+            .map(|(_, _, inst)| inst.name.clone())
+            .collect()
+    };
+
+    app.filter_text = "default !offline".to_string();
+    assert_eq!(names(&app), vec!["i1", "i2", "i4"]);
+
+    app.filter_text = "i1 | s1-i2".to_string();
+    assert_eq!(names(&app), vec!["i1", "s1-i1", "s1-i2"]);
+
+    app.filter_text = "r2 offline".to_string();
+    assert_eq!(names(&app), vec!["i3"]);
+}
+
+#[test]
+fn test_filter_edits_debounced_on_large_clusters() {
+    let mut app = test_app_with_tiers();
+    app.view_mode = ViewMode::Instances;
+    let names = |app: &App| -> Vec<String> {
+        app.get_sorted_instances()
+            .iter()
+            .map(|(_, _, inst)| inst.name.clone())
+            .collect()
+    };
+
+    // Small cluster: every keystroke applies immediately, and typing more
+    // narrows the previous result
+    app.filter_text.push('s');
+    app.filter_edited();
+    assert!(names(&app).contains(&"s1-i1".to_string()));
+    app.filter_text.push_str("1-i");
+    app.filter_edited();
+    assert_eq!(names(&app), vec!["s1-i1", "s1-i2"]);
+    app.filter_text.clear();
+
+    // Large cluster: the previous result stays until typing pauses
+    app.tiers[0].instance_count = 5000;
+    assert_eq!(app.get_item_count(), 6);
+    app.filter_text.push_str("i4");
+    app.filter_edited();
+    assert_eq!(app.get_item_count(), 6);
+    assert!(!app.settle_filter());
+
+    std::thread::sleep(Duration::from_millis(120));
+    assert!(app.settle_filter());
+    assert_eq!(names(&app), vec!["i4"]);
+}
+
+#[test]
+fn test_filter_history_navigation() {
+    let mut app = test_app_with_tiers();
+    app.view_mode = ViewMode::Instances;
+
+    for filter in ["i1", "storage", "i1"] {
+        app.filter_text = filter.to_string();
+        app.record_filter();
+    }
+    assert_eq!(app.filter_history, vec!["storage", "i1"], "deduplicated");
+
+    app.filter_text = "dra".to_string();
+    app.filter_history_prev();
+    assert_eq!(app.filter_text, "i1");
+    app.filter_history_prev();
+    assert_eq!(app.filter_text, "storage");
+    app.filter_history_prev();
+    assert_eq!(app.filter_text, "storage", "stops at the oldest");
+
+    app.filter_history_next();
+    assert_eq!(app.filter_text, "i1");
+    app.filter_history_next();
+    assert_eq!(app.filter_text, "dra", "returns to the draft");
+}
+
+#[test]
+fn test_apply_saved_filter() {
+    let mut app = test_app_with_tiers();
+    app.view_mode = ViewMode::Instances;
+    app.saved_filters = vec![SavedFilter {
+        name: "Storage".to_string(),
+        filter: "s1".to_string(),
+        fuzzy: false,
+    }];
+
+    app.open_filter_picker();
+    assert!(app.show_filter_picker);
+    app.apply_saved_filter(0);
+
+    assert!(!app.show_filter_picker);
+    assert_eq!(app.filter_text, "s1");
+    assert_eq!(app.get_item_count(), 2);
+    assert_eq!(app.filter_history, vec!["s1"]);
+}
+
+#[test]
+fn test_pinned_instances_sort_first() {
+    let mut app = test_app_with_tiers();
+    app.view_mode = ViewMode::Instances;
+    app.select_last();
+    assert_eq!(app.get_selected_instance().unwrap().name, "s1-i2");
+
+    app.toggle_pin_selected();
+    assert!(app.is_pinned("s1-i2"));
+    assert_eq!(app.selected_index, 0, "selection follows the pinned row");
+
+    app.pinned.insert("i3".to_string());
+    app.reset_selection();
+    let names: Vec<&str> = app
+        .get_sorted_instances()
+        .iter()
+        .map(|(_, _, inst)| inst.name.as_str())
+        .collect();
+    assert_eq!(names[..2], ["i3", "s1-i2"], "pins keep the sort order");
+
+    app.select_last();
+    app.select_previous();
+    assert_eq!(app.get_selected_instance().unwrap().name, "i4");
+}
+
+#[test]
+fn test_marking_instances_for_bulk_actions() {
+    let mut app = test_app_with_tiers();
+    app.view_mode = ViewMode::Instances;
+
+    // Space marks the row and moves on to the next one
+    app.toggle_mark_selected();
+    app.select_last();
+    app.toggle_mark_selected();
+    assert_eq!(
+        app.selected_index,
+        app.get_item_count() - 1,
+        "stays on last"
+    );
+    app.select_first();
+    app.select_next();
+    app.select_next();
+    app.toggle_mark_selected();
+
+    let names: Vec<&str> = app
+        .marked_instances()
+        .iter()
+        .map(|(_, _, inst)| inst.name.as_str())
+        .collect();
+    assert_eq!(names, vec!["i1", "i3", "s1-i2"]);
+
+    app.copy_marked_names();
+    assert_eq!(
+        app.pending_clipboard.take().as_deref(),
+        Some("i1\ni3\ns1-i2")
+    );
+
+    app.select_first();
+    app.toggle_mark_selected();
+    assert!(!app.is_marked("i1"), "Space again unmarks");
+
+    app.clear_marks();
+    app.copy_marked_names();
+    assert!(app.pending_clipboard.is_none());
+}
+
+#[test]
+fn test_view_switcher_counts_and_switches() {
+    let mut app = test_app_with_tiers();
+    app.view_mode = ViewMode::Instances;
+    app.set_filter("s1".to_string());
+
+    let summaries: Vec<_> = app
+        .view_summaries()
+        .into_iter()
+        .map(|v| (v.mode, v.count, v.shown, v.problems))
+        .collect();
+    assert_eq!(
+        summaries,
+        [
+            (ViewMode::Tiers, 2, None, 1),
+            (ViewMode::Replicasets, 3, None, 1),
+            (ViewMode::Instances, 6, Some(2), 1),
+        ]
+    );
+
+    app.open_view_switcher();
+    assert_eq!(app.view_switcher_index, 2, "cursor on the current view");
+    app.pick_view(ViewMode::Instances);
+    assert!(!app.show_view_switcher);
+    assert_eq!(
+        app.filter_text, "s1",
+        "staying in the view keeps the filter"
+    );
+
+    app.pick_view(ViewMode::Replicasets);
+    assert_eq!(app.view_mode, ViewMode::Replicasets);
+    assert!(app.filter_text.is_empty());
+}
+
+#[test]
+fn test_sort_picker_picks_field_and_order() {
+    let mut app = test_app_with_tiers();
+    app.view_mode = ViewMode::Instances;
+    app.sort_field = SortField::FailureDomain;
+    app.open_sort_picker();
+    assert!(app.show_sort_picker);
+    assert_eq!(app.sort_picker_index, 1, "cursor on the current field");
+
+    // The order flips with the picker still open
+    app.toggle_sort_order();
+    assert_eq!(app.sort_order, SortOrder::Desc);
+    assert!(app.show_sort_picker);
+
+    app.pick_sort_field(SortField::Name);
+    assert!(!app.show_sort_picker);
+    assert_eq!(app.sort_field, SortField::Name);
+    let names: Vec<_> = app
+        .get_sorted_instances()
+        .iter()
+        .map(|(_, _, inst)| inst.name.clone())
+        .collect();
+    assert_eq!(names, ["s1-i2", "s1-i1", "i4", "i3", "i2", "i1"]);
+}
+
+#[test]
+fn test_column_picker_toggles_and_reorders() {
+    let mut app = test_app_with_tiers();
+    app.instance_columns = vec![InstanceColumn::Name, InstanceColumn::State];
+    app.open_column_picker();
+
+    let rows = app.column_picker_rows();
+    assert_eq!(rows.len(), InstanceColumn::ALL.len());
+    assert_eq!(rows[0], (InstanceColumn::Name, true));
+    assert_eq!(rows[2], (InstanceColumn::Tier, false));
+
+    // Name can't be hidden
+    app.toggle_picker_column();
+    assert_eq!(app.instance_columns[0], InstanceColumn::Name);
+
+    // Show Tier: it's appended and the cursor follows it
+    app.column_picker_index = 2;
+    app.toggle_picker_column();
+    assert_eq!(
+        app.instance_columns,
+        vec![
+            InstanceColumn::Name,
+            InstanceColumn::State,
+            InstanceColumn::Tier
+        ]
+    );
+    assert_eq!(app.column_picker_index, 2);
+
+    app.move_picker_column(true);
+    assert_eq!(
+        app.instance_columns,
+        vec![
+            InstanceColumn::Name,
+            InstanceColumn::Tier,
+            InstanceColumn::State
+        ]
+    );
+    assert_eq!(app.column_picker_index, 1);
+
+    // Hide State
+    app.column_picker_index = 2;
+    app.toggle_picker_column();
+    assert_eq!(
+        app.instance_columns,
+        vec![InstanceColumn::Name, InstanceColumn::Tier]
+    );
+}
+
+#[test]
+fn test_cluster_health_score() {
+    let app = test_app_with_tiers();
+    let health = app.cluster_health.as_ref().unwrap();
+    // i3 is offline (40 * 1/6) and not at its target state (20 * 1/6)
+    assert_eq!(health.score, 90);
+    assert_eq!(
+        health
+            .problems
+            .iter()
+            .map(|p| p.description.as_str())
+            .collect::<Vec<_>>(),
+        vec!["1 instance offline", "1 instance not at target state"]
+    );
+
+    let mut tiers = mock_tiers();
+    tiers[1]["replicasets"][0]["capacityUsage"] = 95.0.into();
+    tiers[0]["replicasets"][0]["instances"][0]["version"] = "25.5.0".into();
+    let mut app = test_app_with_tiers();
+    app.set_tiers(serde_json::from_value(tiers).unwrap());
+    let health = app.cluster_health.as_ref().unwrap();
+    assert_eq!(health.score, 67);
+    assert_eq!(health.problems[0].description, "2 versions running");
+    assert_eq!(
+        health.problems[1].description,
+        "1 replicaset over 85% capacity"
+    );
+}
+
+#[test]
+fn test_snapshot_compare_with_live_cluster() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut app = test_app_with_tiers();
+    app.snapshot_dir = Some(dir.path().to_path_buf());
+
+    app.start_snapshot_prompt();
+    app.snapshot_name = "before".to_string();
+    app.save_snapshot();
+    assert!(!app.snapshot_prompt_active);
+
+    // i4 leaves, i3 comes back online, the storage tier fills up
+    let mut tiers: Vec<TierInfo> = serde_json::from_value(mock_tiers()).unwrap();
+    tiers[0].replicasets[1].instances.remove(1);
+    tiers[0].replicasets[1].instances[0].current_state = StateVariant::Online;
+    tiers[1].memory.used += 1024;
+    app.set_tiers(tiers);
+
+    app.open_snapshot_picker();
+    assert!(app.show_snapshot_picker);
+    assert_eq!(app.snapshot_names, vec!["before"]);
+    app.compare_snapshot(0);
+    assert!(!app.show_snapshot_picker);
+
+    let (name, diff) = app.snapshot_diff.as_ref().unwrap();
+    assert_eq!(name, "before");
+    assert!(diff.added.is_empty());
+    assert_eq!(diff.removed, vec!["i4"]);
+    assert_eq!(diff.state_changes.len(), 1);
+    assert_eq!(diff.state_changes[0].instance, "i3");
+    assert_eq!(diff.state_changes[0].to, StateVariant::Online);
+    assert_eq!(diff.memory.len(), 1);
+    assert_eq!(diff.memory[0].tier, "storage");
+    assert_eq!(diff.memory[0].delta(), 1024);
+}
+
+#[test]
+fn test_snapshot_diff_follows_live_tiers() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut app = test_app_with_tiers();
+    app.snapshot_dir = Some(dir.path().to_path_buf());
+    app.start_snapshot_prompt();
+    app.snapshot_name = "before".to_string();
+    app.save_snapshot();
+
+    let mut tiers = app.tiers.to_vec();
+    tiers[0].replicasets[1].instances.remove(1);
+    app.set_tiers(tiers);
+    app.open_snapshot_picker();
+    app.compare_snapshot(0);
+    assert_eq!(app.snapshot_diff.as_ref().unwrap().1.removed, vec!["i4"]);
+
+    // i4 is back by the next refresh
+    app.set_tiers(serde_json::from_value(mock_tiers()).unwrap());
+    assert!(app.snapshot_diff.as_ref().unwrap().1.removed.is_empty());
+}
+
+#[test]
+fn test_tiers_shared_until_changed() {
+    let mut app = test_app_with_tiers();
+    let handle = app.tiers.handle();
+    let generation = app.tiers.generation();
+    assert!(std::ptr::eq(&*handle, &*app.tiers));
+
+    app.tiers[0].name = "renamed".to_string();
+    assert_eq!(handle[0].name, "default");
+    assert_eq!(app.tiers[0].name, "renamed");
+    assert!(app.tiers.generation() > generation);
+
+    let generation = app.tiers.generation();
+    app.set_tiers(serde_json::from_value(mock_tiers()).unwrap());
+    assert!(app.tiers.generation() > generation);
+}
+
+#[test]
+fn test_cycle_theme_reloads_skins() {
+    let dir = tempfile::tempdir().unwrap();
+    let skin = dir.path().join("night.toml");
+    std::fs::write(&skin, "online = \"cyan\"\n").unwrap();
+    let mut app = test_app_with_tiers();
+    app.skins_dir = Some(dir.path().to_path_buf());
+
+    app.cycle_theme();
+    assert_eq!(app.theme.name, "night");
+    assert_eq!(app.theme.online, Color::Cyan);
+
+    // Edits are picked up the next time the theme is switched
+    std::fs::write(&skin, "online = \"blue\"\n").unwrap();
+    app.cycle_theme();
+    assert_eq!(app.theme.name, "default");
+    assert!(app.select_theme("night"));
+    assert_eq!(app.theme.online, Color::Blue);
+    assert!(!app.select_theme("missing"));
+}
+
+#[test]
+fn test_action_menu_runs_on_its_instance() {
+    let mut app = test_app_with_tiers();
+    app.view_mode = ViewMode::Instances;
+    app.open_action_menu();
+    assert!(app.show_action_menu);
+    let name = app.action_menu_instance().unwrap().name.clone();
+    let binary_address = app.action_menu_instance().unwrap().binary_address.clone();
+    assert!(app.action_menu_rows().contains(&InstanceAction::OpenWebUi));
+
+    // Runs on the instance it was opened for, even if the selection moves
+    app.select_last();
+    app.run_action(InstanceAction::CopyAddress);
+    assert!(!app.show_action_menu);
+    assert_eq!(app.pending_clipboard.take(), Some(binary_address));
+
+    app.select_first();
+    app.open_action_menu();
+    app.run_action(InstanceAction::OpenWebUi);
+    assert_eq!(
+        app.pending_open_url.take().as_deref(),
+        Some("http://10.0.0.1:8080/")
+    );
+
+    app.open_action_menu();
+    app.run_action(InstanceAction::TogglePin);
+    assert!(app.is_pinned(&name));
+
+    app.open_action_menu();
+    app.run_action(InstanceAction::Details);
+    assert!(app.show_detail);
+    assert_eq!(app.detail_instance().unwrap().name, name);
+}
+
+/// Source that answers every GetTiers with the mock tiers, without a worker
+#[derive(Clone, Default)]
+struct ScriptedSource {
+    requests: Rc<RefCell<Vec<String>>>,
+    answers: Rc<RefCell<VecDeque<ApiMessage>>>,
+}
+
+impl DataSource for ScriptedSource {
+    fn send(&self, request: ApiRequest) {
+        if matches!(request, ApiRequest::GetTiers) {
+            self.answers.borrow_mut().push_back(ApiMessage {
+                response: ApiResponse::Tiers(Ok(serde_json::from_value(mock_tiers()).unwrap())),
+                meta: ResponseMeta {
+                    latency: Duration::ZERO,
+                    reachable: true,
+                    bytes: 0,
+                },
+            });
+        }
+        self.requests.borrow_mut().push(format!("{:?}", request));
+    }
+
+    fn try_recv(&self) -> Result<ApiMessage, TryRecvError> {
+        self.answers
+            .borrow_mut()
+            .pop_front()
+            .ok_or(TryRecvError::Empty)
+    }
+}
+
+#[test]
+fn test_app_runs_on_any_data_source() {
+    let source = ScriptedSource::default();
+    let mut app = App::new("demo://".to_string(), source.clone());
+    app.input_mode = InputMode::Normal;
+
+    app.request_refresh();
+    app.process_responses();
+
+    assert_eq!(*source.requests.borrow(), ["GetClusterInfo", "GetTiers"]);
+    assert_eq!(app.tiers.len(), 2);
+    assert!(!app.tree_items.is_empty());
+}
+
+#[test]
+fn test_tiers_refresh_skips_cluster_info() {
+    let source = ScriptedSource::default();
+    let mut app = App::new("demo://".to_string(), source.clone());
+    app.input_mode = InputMode::Normal;
+
+    app.request_tiers_refresh();
+    assert!(app.loading);
+    app.process_responses();
+
+    assert_eq!(*source.requests.borrow(), ["GetTiers"]);
+    assert!(!app.loading, "done without waiting for the cluster");
+    assert_eq!(app.tiers.len(), 2);
+}
+
+#[test]
+fn test_frame_stats_count_requests_and_responses() {
+    let source = ScriptedSource::default();
+    let mut app = App::new("demo://".to_string(), source.clone());
+    app.input_mode = InputMode::Normal;
+
+    // Only GetTiers is answered by the script
+    app.request_refresh();
+    assert_eq!(app.frame_stats.in_flight, 2);
+    app.process_responses();
+    assert_eq!(app.frame_stats.in_flight, 1);
+    app.process_responses();
+
+    app.frame_stats.record_frame(Duration::from_millis(4));
+    app.frame_stats.record_frame(Duration::from_millis(2));
+    assert_eq!(app.frame_stats.frames, 2);
+    assert_eq!(app.frame_stats.responses, 0, "none since the first frame");
+    assert_eq!(app.frame_stats.peak_responses, 1);
+    assert_eq!(app.frame_stats.last(), Some(Duration::from_millis(2)));
+    assert_eq!(app.frame_stats.avg(), Some(Duration::from_millis(3)));
+    assert_eq!(app.frame_stats.max(), Some(Duration::from_millis(4)));
+}
+
+#[test]
+fn test_slow_link_skips_redraw_for_unchanged_data() {
+    let (req_tx, _req_rx) = channel();
+    let (res_tx, res_rx) = channel();
+    let mut app = App::new(
+        "http://test:8080".to_string(),
+        HttpSource::new(req_tx, res_rx),
+    );
+    app.input_mode = InputMode::Normal;
+    let unchanged = || ApiMessage {
+        response: ApiResponse::NotModified(Endpoint::Tiers),
+        meta: ResponseMeta {
+            latency: Duration::from_millis(5),
+            reachable: true,
+            bytes: 0,
+        },
+    };
+
+    app.dirty = false;
+    res_tx.send(unchanged()).unwrap();
+    app.process_responses();
+    assert!(app.dirty, "redrawn by default, e.g. for the latency");
+
+    app.slow_link = true;
+    app.dirty = false;
+    res_tx.send(unchanged()).unwrap();
+    app.process_responses();
+    assert!(!app.dirty);
+}
+
+#[test]
+fn test_selection_changes_are_announced() {
+    let mut app = test_app_with_tiers();
+    let (tx, rx) = channel();
+    app.announce_tx = Some(tx);
+    app.view_mode = ViewMode::Instances;
+    app.reset_selection();
+
+    app.announce_selection();
+    let first = rx.try_recv().unwrap();
+    assert!(first.starts_with("instance "), "{}", first);
+    assert!(first.contains("Online"), "{}", first);
+
+    // Only changes are announced
+    app.announce_selection();
+    assert!(rx.try_recv().is_err());
+
+    app.select_next();
+    app.announce_selection();
+    let second = rx.try_recv().unwrap();
+    assert_ne!(first, second);
+    assert!(second.contains("replicaset "), "{}", second);
+}
+
+#[test]
+fn test_user_actions_are_audited() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("audit.log");
+    let mut app = test_app_with_tiers();
+    app.audit_log_path = Some(path.clone());
+    app.current_user = Some("admin".to_string());
+    app.view_mode = ViewMode::Instances;
+
+    app.open_action_menu();
+    app.run_action(InstanceAction::CopyAddress);
+    app.current_user = None;
+    app.open_action_menu();
+    app.run_action(InstanceAction::ToggleMark);
+
+    let entries: Vec<serde_json::Value> = std::fs::read_to_string(&path)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0]["action"], "action");
+    assert_eq!(entries[0]["detail"], "Copy address on i1");
+    assert_eq!(entries[0]["user"], "admin");
+    assert_eq!(entries[0]["url"], "http://test:8080");
+    assert_eq!(entries[1]["detail"], "Mark / unmark on i1");
+    assert!(entries[1].get("user").is_none());
+}
+
+#[test]
+fn test_scope_to_selected_siblings() {
+    let mut app = test_app_with_tiers();
+    app.view_mode = ViewMode::Instances;
+    app.select_next(); // i2 in default/r1
+
+    app.scope_to_selected(false);
+    let names = |app: &App| -> Vec<String> {
+        app.get_sorted_instances()
+            .iter()
+            .map(|(_, _, inst)| inst.name.clone())
+            .collect()
+    };
+    assert_eq!(names(&app), vec!["i1", "i2"]);
+    assert_eq!(app.get_selected_instance().unwrap().name, "i2");
+    assert_eq!(app.scope.as_ref().unwrap().label(), "replicaset r1");
+
+    // The filter still applies inside the scope
+    app.filter_text = "i2".to_string();
+    assert_eq!(names(&app), vec!["i2"]);
+    app.filter_text.clear();
+
+    app.scope_to_selected(true);
+    assert_eq!(names(&app), vec!["i1", "i2", "i3", "i4"]);
+
+    app.select_last();
+    app.clear_scope();
+    assert!(app.scope.is_none());
+    assert_eq!(app.get_item_count(), 6);
+    assert_eq!(app.get_selected_instance().unwrap().name, "i4");
+}
+
+#[test]
+fn test_jump_to_leader() {
+    let mut app = test_app_with_tiers();
+    app.view_mode = ViewMode::Instances;
+    app.select_next(); // i2, follower in r1
+
+    app.jump_to_leader();
+    assert_eq!(app.get_selected_instance().unwrap().name, "i1");
+
+    // From the leader, on to the Raft leader when one is reported
+    app.jump_to_leader();
+    assert_eq!(app.get_selected_instance().unwrap().name, "i1");
+    assert_eq!(
+        app.status_message.as_deref(),
+        Some("No Raft leader reported")
+    );
+    app.tiers[1].replicasets[0].instances[0].is_raft_leader = true;
+    app.jump_to_leader();
+    assert_eq!(app.get_selected_instance().unwrap().name, "s1-i1");
+
+    // A replicaset row in the Tiers view reveals its collapsed leader
+    app.view_mode = ViewMode::Tiers;
+    app.select_first();
+    app.expand_selected();
+    app.select_next();
+    assert!(matches!(
+        app.tree_items[app.selected_index],
+        TreeItem::Replicaset(0, 0)
+    ));
+    app.jump_to_leader();
+    assert_eq!(app.get_selected_instance().unwrap().name, "i1");
+}
+
+#[test]
+fn test_expand_problems_reveals_unhealthy_instances() {
+    let mut app = test_app_with_tiers();
+    assert_eq!(app.tree_items.len(), 2, "starts collapsed");
+
+    app.toggle_expand_problems();
+    // default tier and its r2 with the offline i3; r1 and storage stay closed
+    assert_eq!(
+        tree_shape(&app),
+        vec![
+            (0, 0, 0, 0),
+            (1, 0, 0, 0),
+            (1, 0, 1, 0),
+            (2, 0, 1, 0),
+            (2, 0, 1, 1),
+            (0, 1, 0, 0)
+        ]
+    );
+
+    // A refresh where storage gets a transitioning instance expands it too
+    let mut tiers: Vec<TierInfo> = serde_json::from_value(mock_tiers()).unwrap();
+    tiers[1].replicasets[0].instances[1].target_state = StateVariant::Offline;
+    app.set_tiers(tiers);
+    assert!(app.expanded_replicasets.contains(&(1, 0)));
+
+    app.toggle_expand_problems();
+    assert!(!app.expand_problems);
+    assert!(
+        app.expanded_replicasets.contains(&(0, 1)),
+        "off doesn't collapse"
+    );
+}
+
+#[test]
+fn test_bookmarks_jump_back_to_tree_positions() {
+    let mut app = test_app_with_tiers();
+    let dir = tempfile::tempdir().unwrap();
+    app.bookmarks_path = Some(dir.path().join("bookmarks.json"));
+
+    // Bookmark s1-i2 from the Instances view and r1 from the Replicasets view
+    app.view_mode = ViewMode::Instances;
+    app.select_last();
+    app.set_bookmark('a');
+    app.view_mode = ViewMode::Replicasets;
+    app.select_first();
+    app.set_bookmark('b');
+
+    app.jump_to_bookmark('a');
+    assert_eq!(app.view_mode, ViewMode::Tiers);
+    assert_eq!(app.get_selected_instance().unwrap().name, "s1-i2");
+    app.jump_to_bookmark('b');
+    assert_eq!(
+        app.tree_items[app.selected_index],
+        TreeItem::Replicaset(0, 0)
+    );
+
+    app.jump_to_bookmark('c');
+    assert_eq!(app.status_message.as_deref(), Some("No bookmark c"));
+
+    let saved = picotui::bookmarks::load_bookmarks(
+        app.bookmarks_path.as_ref().unwrap(),
+        "http://test:8080",
+    );
+    assert_eq!(saved, app.bookmarks);
+}
+
+#[test]
+fn test_parameters_response_and_search() {
+    let (req_tx, req_rx) = channel();
+    let (res_tx, res_rx) = channel();
+    let mut app = App::new(
+        "http://test:8080".to_string(),
+        HttpSource::new(req_tx, res_rx),
+    );
+    app.input_mode = InputMode::Normal;
+
+    app.request_parameters();
+    assert!(matches!(req_rx.try_recv(), Ok(ApiRequest::GetParameters)));
+    assert!(app.show_parameters && app.parameters_loading);
+
+    let param = |name: &str, scope: &str| ConfigParameter {
+        name: name.to_string(),
+        scope: scope.to_string(),
+        value: "1".to_string(),
+    };
+    res_tx
+        .send(ApiMessage {
+            response: ApiResponse::Parameters(Ok(vec![
+                param("memtx_checkpoint_count", ""),
+                param("memtx_checkpoint_interval", ""),
+                param("sql_vdbe_opcode_max", "storage"),
+            ])),
+            meta: ResponseMeta {
+                latency: Duration::from_millis(1),
+                reachable: true,
+                bytes: 0,
+            },
+        })
+        .unwrap();
+    app.process_responses();
+    assert!(!app.parameters_loading);
+    assert_eq!(app.visible_parameters().len(), 3);
+
+    app.parameters_scroll = 2;
+    app.set_parameters_search("checkpoint".to_string());
+    assert_eq!(app.parameters_scroll, 0, "a new search starts from the top");
+    let names: Vec<&str> = app
+        .visible_parameters()
+        .iter()
+        .map(|p| p.name.as_str())
+        .collect();
+    assert_eq!(
+        names,
+        ["memtx_checkpoint_count", "memtx_checkpoint_interval"]
+    );
+
+    app.set_parameters_search("storage".to_string());
+    assert_eq!(app.visible_parameters().len(), 1, "the tier matches too");
+}
+
+#[test]
+fn test_rolling_restart_needs_ops() {
+    let mut app = test_app_with_tiers();
+    app.view_mode = ViewMode::Instances;
+    app.open_action_menu();
+    assert!(!app
+        .action_menu_rows()
+        .contains(&InstanceAction::RollingRestart));
+    app.run_action(InstanceAction::RollingRestart);
+    assert!(app.rolling_restart.is_none());
+
+    app.ops = true;
+    app.open_action_menu();
+    assert!(app
+        .action_menu_rows()
+        .contains(&InstanceAction::RollingRestart));
+    app.read_only = true;
+    assert!(
+        !app.action_menu_rows()
+            .contains(&InstanceAction::RollingRestart),
+        "--read-only wins"
+    );
+
+    app.read_only = false;
+    app.run_action(InstanceAction::RollingRestart);
+    let restart = app.rolling_restart.as_ref().unwrap();
+    assert_eq!(restart.replicaset, "r1");
+    assert_eq!(restart.outcome, Outcome::Planned);
+}
+
+#[test]
+fn test_rolling_restart_starts_once_confirmed() {
+    let mut app = test_app_with_tiers();
+    app.view_mode = ViewMode::Instances;
+    app.ops = true;
+    app.open_action_menu();
+    app.run_action(InstanceAction::RollingRestart);
+
+    app.ask_to_start_rolling_restart();
+    let dialog = app.confirm.as_mut().unwrap();
+    assert_eq!(dialog.severity, Severity::Danger);
+    dialog.push_str("r2");
+    app.confirm_operation();
+    assert!(app.confirm.is_some(), "wrong name");
+    assert_eq!(
+        app.rolling_restart.as_ref().unwrap().outcome,
+        Outcome::Planned
+    );
+
+    let dialog = app.confirm.as_mut().unwrap();
+    dialog.pop();
+    dialog.pop();
+    app.paste("r1");
+    app.confirm_operation();
+    assert!(app.confirm.is_none());
+    assert!(app.rolling_restart.as_ref().unwrap().is_running());
+}
+
+fn instance_mut<'a>(tiers: &'a mut [TierInfo], name: &str) -> &'a mut InstanceInfo {
+    tiers
+        .iter_mut()
+        .flat_map(|t| t.replicasets.iter_mut())
+        .flat_map(|rs| rs.instances.iter_mut())
+        .find(|inst| inst.name == name)
+        .unwrap()
+}
+
+#[test]
+#[cfg(unix)]
+fn test_rolling_restart_commands_are_audited() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("audit.log");
+    let mut app = test_app_with_tiers();
+    app.audit_log_path = Some(path.clone());
+    app.view_mode = ViewMode::Instances;
+    app.ops = true;
+    app.restart_commands = RestartCommands {
+        switch_leader: None,
+        restart: Some("true {instance}".to_string()),
+    };
+    app.open_action_menu();
+    app.run_action(InstanceAction::RollingRestart);
+    app.ask_to_start_rolling_restart();
+    app.paste("r1");
+    app.confirm_operation();
+
+    let details: Vec<String> = std::fs::read_to_string(&path)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+        .map(|entry| entry["detail"].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(
+        details,
+        [
+            "Rolling restart of the replicaset on i1",
+            "start r1",
+            "run true 'i2'"
+        ]
+    );
+}
+
+// Placeholders are quoted for sh
+#[test]
+#[cfg(unix)]
+fn test_ops_dry_run_shows_commands_instead_of_running() {
+    let mut app = test_app_with_tiers();
+    app.ops = true;
+    app.ops_dry_run = true;
+    app.restart_commands = RestartCommands {
+        switch_leader: None,
+        restart: Some("ssh {host} systemctl restart picodata@{instance}".to_string()),
+    };
+    app.rolling_restart =
+        Some(RollingRestart::plan(&app.tiers, "r1", app.restart_commands.clone()).unwrap());
+
+    app.ask_to_start_rolling_restart();
+    assert!(app.confirm.is_none(), "nothing to confirm");
+    assert_eq!(
+        app.rolling_restart.as_ref().unwrap().outcome,
+        Outcome::Planned
+    );
+    let dry_run = app.dry_run.as_ref().unwrap();
+    let commands: Vec<&str> = dry_run
+        .lines
+        .iter()
+        .map(String::as_str)
+        .filter(|line| !line.is_empty() && !line.starts_with("# wait"))
+        .collect();
+    assert_eq!(
+        commands,
+        [
+            "# Rolling restart of r1 (tier default)",
+            "ssh '10.0.0.2' systemctl restart picodata@'i2'",
+            "# by hand: move the replicaset leader to another instance (i1)",
+            "ssh '10.0.0.1' systemctl restart picodata@'i1'",
+        ]
+    );
+
+    app.copy_dry_run();
+    assert!(app
+        .pending_clipboard
+        .take()
+        .unwrap()
+        .starts_with("# Rolling restart of r1"));
+}
+
+fn alert_rule(json: serde_json::Value) -> AlertRule {
+    serde_json::from_value(json).unwrap()
+}
+
+#[test]
+fn test_bell_on_new_offline_instance() {
+    let mut app = test_app_with_tiers();
+    app.bell = Some(Bell::Ring);
+    let mut tiers: Vec<TierInfo> = serde_json::from_value(mock_tiers()).unwrap();
+
+    app.set_tiers(tiers.clone());
+    assert!(!app.pending_bell, "i3 was already offline");
+
+    instance_mut(&mut tiers, "i4").current_state = StateVariant::Offline;
+    app.set_tiers(tiers.clone());
+    assert!(app.pending_bell);
+    assert!(!app.is_flashing());
+
+    app.pending_bell = false;
+    app.bell = Some(Bell::Flash);
+    instance_mut(&mut tiers, "i4").current_state = StateVariant::Online;
+    app.set_tiers(tiers.clone());
+    assert!(!app.is_flashing(), "coming back is no problem");
+    instance_mut(&mut tiers, "i4").current_state = StateVariant::Offline;
+    app.set_tiers(tiers.clone());
+    assert!(app.is_flashing());
+    assert!(!app.pending_bell);
+
+    // No flashing for screen readers, the bell rings instead
+    app.accessible = true;
+    instance_mut(&mut tiers, "i4").current_state = StateVariant::Online;
+    app.set_tiers(tiers.clone());
+    instance_mut(&mut tiers, "i4").current_state = StateVariant::Offline;
+    app.set_tiers(tiers);
+    assert!(app.pending_bell);
+}
+
+#[test]
+fn test_bell_on_new_alert() {
+    let mut app = test_app_with_tiers();
+    app.bell = Some(Bell::Ring);
+    app.alerts = AlertEngine::new(vec![alert_rule(
+        serde_json::json!({"name": "Skew", "metric": "versions", "threshold": 1}),
+    )]);
+    let mut tiers: Vec<TierInfo> = serde_json::from_value(mock_tiers()).unwrap();
+
+    instance_mut(&mut tiers, "i1").version = "25.7.0".to_string();
+    app.set_tiers(tiers);
+    assert!(app.pending_bell);
+}
+
+#[test]
+fn test_error_log_keeps_replaced_errors() {
+    let (req_tx, _req_rx) = channel();
+    let (res_tx, res_rx) = channel();
+    let mut app = App::new(
+        "http://test:8080".to_string(),
+        HttpSource::new(req_tx, res_rx),
+    );
+    app.input_mode = InputMode::Normal;
+    let send = |response| {
+        res_tx
+            .send(ApiMessage {
+                response,
+                meta: ResponseMeta {
+                    latency: Duration::from_millis(5),
+                    reachable: true,
+                    bytes: 0,
+                },
+            })
+            .unwrap()
+    };
+
+    send(ApiResponse::ClusterInfo(Err("refused".to_string())));
+    send(ApiResponse::Tiers(Err("HTTP 502".to_string())));
+    send(ApiResponse::ClusterInfo(Err("refused".to_string())));
+    app.process_responses();
+    assert_eq!(app.last_error.as_deref(), Some("Cluster: refused"));
+
+    let logged: Vec<_> = app
+        .error_log
+        .iter()
+        .map(|e| (e.message.as_str(), e.count))
+        .collect();
+    assert_eq!(
+        logged,
+        [
+            ("Cluster: refused", 1),
+            ("Tiers: HTTP 502", 1),
+            ("Cluster: refused", 1),
+        ],
+        "newest first, with the tiers error the status bar never showed"
+    );
+
+    send(ApiResponse::ClusterInfo(Err("refused".to_string())));
+    app.process_responses();
+    assert_eq!(app.error_log.len(), 3, "repeats are counted");
+    assert_eq!(app.error_log.iter().next().unwrap().count, 2);
+}
+
+#[test]
+fn test_toasts_for_state_changes() {
+    let mut app = test_app_with_tiers();
+    let mut tiers: Vec<TierInfo> = serde_json::from_value(mock_tiers()).unwrap();
+    app.set_tiers(tiers.clone());
+    assert!(app.toasts.is_empty(), "nothing changed");
+
+    instance_mut(&mut tiers, "i3").current_state = StateVariant::Online;
+    instance_mut(&mut tiers, "i2").current_state = StateVariant::Offline;
+    instance_mut(&mut tiers, "i4").current_state = StateVariant::Offline;
+    app.set_tiers(tiers);
+    let toasts: Vec<_> = app
+        .toasts
+        .iter()
+        .map(|toast| (toast.kind, toast.message.as_str()))
+        .collect();
+    assert_eq!(
+        toasts,
+        [
+            (ToastKind::Success, "i3 back Online"),
+            (ToastKind::Error, "i2, i4 went Offline"),
+        ]
+    );
+
+    // Gone for good by the time they expire
+    assert!(!app.settle_toasts());
+    assert!(app.toasts.expire(Instant::now() + TOAST_DURATION));
+    assert!(app.toasts.is_empty());
+}
+
+#[test]
+fn test_toast_for_new_errors() {
+    let (req_tx, _req_rx) = channel();
+    let (res_tx, res_rx) = channel();
+    let mut app = App::new(
+        "http://test:8080".to_string(),
+        HttpSource::new(req_tx, res_rx),
+    );
+    app.input_mode = InputMode::Normal;
+    let failed = |error: &str| ApiMessage {
+        response: ApiResponse::Tiers(Err(error.to_string())),
+        meta: ResponseMeta {
+            latency: Duration::from_millis(5),
+            reachable: true,
+            bytes: 0,
+        },
+    };
+
+    res_tx.send(failed("HTTP 500")).unwrap();
+    res_tx.send(failed("HTTP 502")).unwrap();
+    app.process_responses();
+    let messages: Vec<_> = app.toasts.iter().map(|t| t.message.as_str()).collect();
+    assert_eq!(
+        messages,
+        ["Tiers: HTTP 500"],
+        "the error the status bar shows"
+    );
+    assert!(app.toasts.iter().all(|t| t.kind == ToastKind::Error));
+
+    app.toasts.expire(Instant::now() + TOAST_DURATION);
+    res_tx.send(failed("HTTP 500")).unwrap();
+    app.process_responses();
+    assert!(app.toasts.is_empty(), "still the same failure");
+
+    // Once the server answers again, the next failure is news
+    res_tx
+        .send(ApiMessage {
+            response: ApiResponse::NotModified(Endpoint::ClusterInfo),
+            meta: ResponseMeta {
+                latency: Duration::from_millis(5),
+                reachable: true,
+                bytes: 0,
+            },
+        })
+        .unwrap();
+    res_tx.send(failed("HTTP 500")).unwrap();
+    app.process_responses();
+    assert_eq!(app.toasts.iter().count(), 1);
+}
+
+#[test]
+fn test_heatmap_navigation() {
+    let mut app = test_app_with_tiers();
+    app.open_heatmap();
+    let cursor = |app: &App| {
+        let cells = heatmap::cells(&app.tiers);
+        let (t, r, i) = cells[app.heatmap_cursor(&cells)];
+        app.tiers[t].replicasets[r].instances[i].name.clone()
+    };
+    assert_eq!(cursor(&app), "i1");
+
+    app.move_heatmap_cursor(1);
+    assert_eq!(cursor(&app), "i2");
+    app.move_heatmap_replicaset(true);
+    assert_eq!(cursor(&app), "i3");
+    app.move_heatmap_replicaset(true);
+    assert_eq!(cursor(&app), "s1-i1", "on to the next tier");
+    app.move_heatmap_replicaset(true);
+    assert_eq!(cursor(&app), "s1-i1", "stays at the end");
+    app.move_heatmap_cursor(-2);
+    assert_eq!(cursor(&app), "i3");
+    app.move_heatmap_cursor(1);
+    app.move_heatmap_replicaset(false);
+    assert_eq!(
+        cursor(&app),
+        "i3",
+        "back to the start of the replicaset first"
+    );
+    app.move_heatmap_replicaset(false);
+    assert_eq!(cursor(&app), "i1");
+    app.move_heatmap_cursor(-1);
+    assert_eq!(cursor(&app), "i1", "stays at the start");
+
+    app.move_heatmap_cursor(3);
+    app.view_mode = ViewMode::Instances;
+    app.jump_to_heatmap_cell();
+    assert!(!app.show_heatmap);
+    assert_eq!(app.view_mode, ViewMode::Tiers);
+    assert_eq!(app.get_selected_instance().unwrap().name, "i4");
+
+    // Opened on the selected instance, and follows it through refreshes
+    app.open_heatmap();
+    assert_eq!(cursor(&app), "i4");
+    let mut tiers: Vec<TierInfo> = serde_json::from_value(mock_tiers()).unwrap();
+    tiers[0].replicasets.swap(0, 1);
+    app.set_tiers(tiers);
+    assert_eq!(cursor(&app), "i4");
+}
+
+#[test]
+fn test_unchanged_tiers_still_count_for_alerts() {
+    let (req_tx, _req_rx) = channel();
+    let (res_tx, res_rx) = channel();
+    let mut app = App::new(
+        "http://test:8080".to_string(),
+        HttpSource::new(req_tx, res_rx),
+    );
+    app.input_mode = InputMode::Normal;
+    app.alerts = AlertEngine::new(vec![alert_rule(
+        serde_json::json!({"name": "Down", "metric": "offline", "threshold": 0, "for": 2}),
+    )]);
+    let meta = ResponseMeta {
+        latency: Duration::from_millis(5),
+        reachable: true,
+        bytes: 0,
+    };
+
+    res_tx
+        .send(ApiMessage {
+            response: ApiResponse::Tiers(Ok(serde_json::from_value(mock_tiers()).unwrap())),
+            meta,
+        })
+        .unwrap();
+    app.process_responses();
+    assert_eq!(app.alerts.active().count(), 0);
+
+    res_tx
+        .send(ApiMessage {
+            response: ApiResponse::NotModified(Endpoint::Tiers),
+            meta,
+        })
+        .unwrap();
+    app.process_responses();
+    assert_eq!(
+        app.alerts.active().count(),
+        1,
+        "i3 has been down for two refreshes"
+    );
+}
+
+#[test]
+fn test_initializing_until_config_answers() {
+    let (req_tx, req_rx) = channel();
+    let (res_tx, res_rx) = channel();
+    let mut app = App::new(
+        "http://test:8080".to_string(),
+        HttpSource::new(req_tx, res_rx),
+    );
+    let answer = |response, reachable| ApiMessage {
+        response,
+        meta: ResponseMeta {
+            latency: Duration::from_millis(5),
+            reachable,
+            bytes: 0,
+        },
+    };
+
+    app.start_init();
+    assert_eq!(app.input_mode, InputMode::Initializing);
+    assert!(matches!(req_rx.try_recv(), Ok(ApiRequest::GetConfig)));
+
+    // A failed attempt stays on the connecting screen to be retried
+    app.queue_key((KeyCode::Char('3'), KeyModifiers::NONE));
+    res_tx
+        .send(answer(
+            ApiResponse::Config(Err("connection refused".to_string())),
+            false,
+        ))
+        .unwrap();
+    app.process_responses();
+    assert_eq!(app.input_mode, InputMode::Initializing);
+    assert!(!app.loading);
+    assert!(app.init_screen_due());
+    assert!(app
+        .last_error
+        .as_ref()
+        .unwrap()
+        .contains("connection refused"));
+
+    app.start_init();
+    res_tx
+        .send(answer(
+            ApiResponse::Config(Ok(UiConfig {
+                is_auth_enabled: false,
+            })),
+            true,
+        ))
+        .unwrap();
+    app.process_responses();
+    assert_eq!(app.input_mode, InputMode::Normal);
+    assert_eq!(app.queued_keys, [(KeyCode::Char('3'), KeyModifiers::NONE)]);
+    let requests: Vec<_> = req_rx.try_iter().collect();
+    assert!(matches!(
+        requests[..],
+        [
+            ApiRequest::GetConfig,
+            ApiRequest::GetClusterInfo,
+            ApiRequest::GetTiers
+        ]
+    ));
+}
+
+#[test]
+fn test_keys_queued_for_the_cluster_are_dropped_for_login() {
+    let (req_tx, _req_rx) = channel();
+    let (res_tx, res_rx) = channel();
+    let mut app = App::new(
+        "http://test:8080".to_string(),
+        HttpSource::new(req_tx, res_rx),
+    );
+    app.start_init();
+    app.queue_key((KeyCode::Char('j'), KeyModifiers::NONE));
+    res_tx
+        .send(ApiMessage {
+            response: ApiResponse::Config(Ok(UiConfig {
+                is_auth_enabled: true,
+            })),
+            meta: ResponseMeta {
+                latency: Duration::from_millis(5),
+                reachable: true,
+                bytes: 0,
+            },
+        })
+        .unwrap();
+    app.process_responses();
+    assert_eq!(app.input_mode, InputMode::Login);
+    assert!(app.queued_keys.is_empty());
+}
+
+#[test]
+fn test_edit_url_while_initializing_connects_elsewhere() {
+    let (old_tx, old_rx) = channel();
+    let (_res_tx, res_rx) = channel();
+    let mut app = App::new(
+        "http://test:8080".to_string(),
+        HttpSource::new(old_tx, res_rx),
+    );
+    app.start_init();
+    let _ = old_rx.try_recv();
+
+    // Only plain HTTP connections can be edited
+    app.start_url_edit();
+    assert!(app.url_prompt.is_none());
+    app.url_editable = true;
+    app.start_url_edit();
+    assert_eq!(app.url_prompt.as_deref(), Some("http://test:8080"));
+
+    app.url_prompt = Some("test:9090".to_string());
+    app.submit_url_edit();
+    assert!(app.pending_connect.is_none(), "no scheme");
+    assert!(app.status_message.is_some());
+
+    app.url_prompt = Some("http://other:9090/".to_string());
+    app.submit_url_edit();
+    assert!(app.url_prompt.is_none());
+    assert_eq!(app.pending_connect.as_deref(), Some("http://other:9090"));
+
+    let (new_tx, new_rx) = channel();
+    let (_res_tx, res_rx) = channel();
+    let url = app.pending_connect.take().unwrap();
+    app.queue_key((KeyCode::Char('j'), KeyModifiers::NONE));
+    app.connect(url, spawn_sources(vec![HttpSource::new(new_tx, res_rx)]));
+    assert_eq!(app.base_url, "http://other:9090");
+    assert_eq!(app.input_mode, InputMode::Initializing);
+    assert!(app.queued_keys.is_empty());
+    assert!(matches!(old_rx.try_recv(), Ok(ApiRequest::Shutdown)));
+    assert!(matches!(new_rx.try_recv(), Ok(ApiRequest::GetConfig)));
+}
+
+/// Starts `sources` in turn, as a worker that stops is replaced
+fn spawn_sources(sources: Vec<HttpSource>) -> SpawnSource {
+    let sources = RefCell::new(VecDeque::from(sources));
+    Box::new(move || Box::new(sources.borrow_mut().pop_front().expect("no sources left")))
+}
+
+fn login_ok() -> ApiMessage {
+    ApiMessage {
+        response: ApiResponse::Login(Ok(TokenResponse {
+            auth: "auth-token".to_string(),
+            refresh: "refresh-token".to_string(),
+        })),
+        meta: ResponseMeta {
+            latency: Duration::from_millis(5),
+            reachable: true,
+            bytes: 0,
+        },
+    }
+}
+
+#[test]
+fn test_stopped_worker_is_restarted_with_the_session() {
+    let (req_tx, _req_rx) = channel();
+    let (res_tx, res_rx) = channel();
+    let mut app = App::new(
+        "http://test:8080".to_string(),
+        HttpSource::new(req_tx, res_rx),
+    );
+    let (new_tx, new_rx) = channel();
+    let (_new_res_tx, new_res_rx) = channel();
+    app.respawn_source = Some(spawn_sources(vec![HttpSource::new(new_tx, new_res_rx)]));
+    app.input_mode = InputMode::Login;
+    app.login_username = "admin".to_string();
+    app.login_remember_me = false;
+    res_tx.send(login_ok()).unwrap();
+    app.process_responses();
+    assert_eq!(app.input_mode, InputMode::Normal);
+
+    // The worker goes away: a new one gets the tokens and refreshes
+    drop(res_tx);
+    app.loading = false;
+    app.process_responses();
+    assert!(!app.worker_stopped);
+    let requests: Vec<_> = new_rx.try_iter().collect();
+    match &requests[..] {
+        [ApiRequest::SetToken {
+            auth,
+            refresh,
+            remember,
+        }, ApiRequest::GetClusterInfo, ApiRequest::GetTiers, ..] => {
+            assert_eq!(auth, "auth-token");
+            assert_eq!(refresh, "refresh-token");
+            assert!(!remember, "not saved without Remember me");
+        }
+        other => panic!("unexpected requests {:?}", other),
+    }
+    assert!(app
+        .toasts
+        .iter()
+        .any(|toast| toast.message == "API worker restarted"));
+}
+
+#[test]
+fn test_worker_stopping_again_waits_for_reconnect() {
+    let (req_tx, _req_rx) = channel();
+    let (res_tx, res_rx) = channel();
+    let mut app = App::new(
+        "http://test:8080".to_string(),
+        HttpSource::new(req_tx, res_rx),
+    );
+    app.input_mode = InputMode::Normal;
+    let (first_tx, _first_rx) = channel();
+    let (first_res_tx, first_res_rx) = channel();
+    let (second_tx, second_rx) = channel();
+    let (_second_res_tx, second_res_rx) = channel();
+    app.respawn_source = Some(spawn_sources(vec![
+        HttpSource::new(first_tx, first_res_rx),
+        HttpSource::new(second_tx, second_res_rx),
+    ]));
+
+    drop(res_tx);
+    app.process_responses();
+    assert!(!app.worker_stopped);
+
+    // The replacement dies right away: left for the user to restart
+    drop(first_res_tx);
+    app.process_responses();
+    assert!(app.worker_stopped);
+    assert_eq!(
+        app.last_error.as_deref(),
+        Some("API worker disconnected, R to restart it")
+    );
+
+    assert!(app.reconnect());
+    assert!(!app.worker_stopped);
+    assert!(second_rx
+        .try_iter()
+        .any(|request| matches!(request, ApiRequest::GetTiers)));
+}
+
+#[test]
+fn test_stopped_worker_without_a_way_to_restart() {
+    let (req_tx, _req_rx) = channel();
+    let (res_tx, res_rx) = channel();
+    let mut app = App::new(
+        "http://test:8080".to_string(),
+        HttpSource::new(req_tx, res_rx),
+    );
+    drop(res_tx);
+    app.process_responses();
+    assert!(app.worker_stopped);
+    assert_eq!(app.last_error.as_deref(), Some("API worker disconnected"));
+    assert!(!app.reconnect());
+}
+
+#[test]
+fn test_worker_panic_fails_the_request_it_was_handling() {
+    let (req_tx, _req_rx) = channel();
+    let (res_tx, res_rx) = channel();
+    let mut app = App::new(
+        "http://test:8080".to_string(),
+        HttpSource::new(req_tx, res_rx),
+    );
+    app.input_mode = InputMode::Normal;
+    app.request_tiers_refresh();
+    assert_eq!(app.frame_stats.in_flight, 1);
+
+    // What the worker sends when GetTiers panics
+    let panic = WorkerPanic {
+        request: "GetTiers",
+        message: "index out of bounds".to_string(),
+    };
+    let meta = ResponseMeta {
+        latency: Duration::ZERO,
+        reachable: true,
+        bytes: 0,
+    };
+    for response in [
+        ApiResponse::WorkerPanic(panic.clone()),
+        ApiResponse::Tiers(Err(panic.to_string())),
+    ] {
+        res_tx.send(ApiMessage { response, meta }).unwrap();
+    }
+    app.process_responses();
+
+    assert!(!app.loading);
+    assert_eq!(app.frame_stats.in_flight, 0);
+    assert_eq!(
+        app.last_error.as_deref(),
+        Some("API worker crashed handling GetTiers (index out of bounds) and was restarted")
+    );
+    assert!(app
+        .toasts
+        .iter()
+        .any(|toast| toast.kind == ToastKind::Error && toast.message.contains("crashed")));
+    assert_eq!(app.error_log.len(), 2);
+}
+
+#[test]
+fn test_group_health_probes_a_tier_and_is_cancelled_by_a_view_change() {
+    let (req_tx, req_rx) = channel();
+    let (res_tx, res_rx) = channel();
+    let mut app = App::new(
+        "http://test:8080".to_string(),
+        HttpSource::new(req_tx, res_rx),
+    );
+    app.set_tiers(serde_json::from_value(mock_tiers()).unwrap());
+    app.input_mode = InputMode::Normal;
+    let tier = app.tiers[0].clone();
+
+    // The first row is a tier: every instance of it is probed
+    assert!(app.request_group_health());
+    assert!(app.show_health && app.health_loading);
+    let expected: Vec<String> = tier
+        .replicasets
+        .iter()
+        .flat_map(|rs| &rs.instances)
+        .map(|inst| inst.http_address.clone())
+        .collect();
+    let id = match req_rx.try_recv() {
+        Ok(ApiRequest::ProbeHealth { id, addresses }) => {
+            assert_eq!(addresses, expected);
+            id
+        }
+        other => panic!("unexpected request {:?}", other),
+    };
+    let group = app.group_health.as_ref().unwrap();
+    assert_eq!(group.title, format!("tier {}", tier.name));
+    assert!(group.results.is_none());
+
+    let answer = |id, cancelled| ApiMessage {
+        response: ApiResponse::HealthProbes(HealthProbes {
+            id,
+            results: vec![Err("refused".to_string()); expected.len()],
+            cancelled,
+        }),
+        meta: ResponseMeta {
+            latency: Duration::from_millis(5),
+            reachable: false,
+            bytes: 0,
+        },
+    };
+    // An answer to an older probe changes nothing
+    res_tx.send(answer(id - 1, false)).unwrap();
+    app.process_responses();
+    assert!(app.health_loading);
+    res_tx.send(answer(id, false)).unwrap();
+    app.process_responses();
+    assert!(!app.health_loading);
+    assert_eq!(
+        app.group_health
+            .as_ref()
+            .unwrap()
+            .results
+            .as_ref()
+            .unwrap()
+            .len(),
+        expected.len()
+    );
+
+    // Probing again and leaving the view stops what's left
+    app.refresh_group_health();
+    assert!(matches!(
+        req_rx.try_recv(),
+        Ok(ApiRequest::ProbeHealth { id: next, .. }) if next == id + 1
+    ));
+    app.switch_view(ViewMode::Instances);
+    assert!(matches!(req_rx.try_recv(), Ok(ApiRequest::CancelFanOut)));
+    assert!(app.group_health.is_none());
+    assert!(!app.health_loading);
+    res_tx.send(answer(id + 1, true)).unwrap();
+    app.process_responses();
+    assert!(app.group_health.is_none());
+
+    // An instance row is probed on its own, as before
+    assert!(!app.request_group_health());
+}
@@ -5,24 +5,51 @@
 mod common;
 
 use common::{buffer_contains, mock_cluster_info, mock_tiers};
-use picotui::app::{App, InputMode, SortField, SortOrder, ViewMode};
-use picotui::models::{ClusterInfo, TierInfo};
+use crossterm::event::{KeyCode, KeyModifiers};
+use picotui::alerts::AlertEngine;
+use picotui::api::{ApiResponse, ResponseMeta, ServerIdentity};
+use picotui::app::{
+    App, ConnectionStatus, GroupHealth, InputMode, InstanceColumn, LoginFocus, ServerCheck,
+    SortField, SortOrder, ViewMode,
+};
+use picotui::compat::PicodataVersion;
+use picotui::config::{RestartCommands, SavedFilter};
+use picotui::models::{
+    ClusterInfo, ConfigParameter, StateVariant, TableDistribution, TableInfo, TierInfo,
+};
+use picotui::picker::{ClusterPicker, Probe};
+use picotui::rolling::RollingRestart;
+use picotui::snapshot;
+use picotui::source::HttpSource;
+use picotui::toasts::ToastKind;
+use picotui::tokens::TokenEntry;
 use picotui::ui;
-use ratatui::{backend::TestBackend, Terminal};
+use picotui::ui::format::{unix_now, ByteUnits, NumberFormat, TimeFormat};
+use picotui::ui::screenshot::{buffer_to_ansi, buffer_to_text};
+use picotui::ui::theme::ColorDepth;
+use ratatui::{
+    backend::TestBackend,
+    style::{Color, Modifier},
+    Terminal,
+};
 use std::sync::mpsc::channel;
+use std::time::Duration;
 
 /// Create a test app with mock data loaded
 fn test_app_with_data() -> App {
     let (req_tx, _req_rx) = channel();
     let (_res_tx, res_rx) = channel();
-    let mut app = App::new("http://test:8080".to_string(), req_tx, res_rx);
+    let mut app = App::new(
+        "http://test:8080".to_string(),
+        HttpSource::new(req_tx, res_rx),
+    );
 
     // Load mock data
     let cluster_info: ClusterInfo = serde_json::from_value(mock_cluster_info()).unwrap();
     let tiers: Vec<TierInfo> = serde_json::from_value(mock_tiers()).unwrap();
 
     app.cluster_info = Some(cluster_info);
-    app.tiers = tiers;
+    app.tiers = tiers.into();
     app.rebuild_tree();
     app.input_mode = InputMode::Normal;
 
@@ -75,6 +102,18 @@ fn test_tiers_view_renders_tiers() {
     );
 }
 
+#[test]
+fn test_tier_rows_break_down_instance_states() {
+    let mut terminal = test_terminal(120, 30);
+    let mut app = test_app_with_data();
+
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+
+    let buffer = terminal.backend().buffer();
+    assert!(buffer_contains(buffer, "Inst: 3 online / 1 offline"));
+    assert!(buffer_contains(buffer, "Inst: 2 online  "));
+}
+
 #[test]
 fn test_tiers_view_shows_collapsed_arrows() {
     let mut terminal = test_terminal(100, 30);
@@ -151,6 +190,26 @@ fn test_replicasets_view_renders() {
     assert!(buffer_contains(buffer, "s1"), "Should show replicaset s1");
 }
 
+#[test]
+fn test_replicasets_view_table_with_gauges() {
+    let mut terminal = test_terminal(100, 30);
+    let mut app = test_app_with_data();
+
+    app.view_mode = ViewMode::Replicasets;
+
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+
+    let buffer = terminal.backend().buffer();
+    assert!(buffer_contains(
+        buffer,
+        "Name  State         Tier     Inst  Memory"
+    ));
+    assert!(buffer_contains(
+        buffer,
+        "r1    [Online] ✓    default     2  ███░░░░░░░  30.0%"
+    ));
+}
+
 #[test]
 fn test_instances_view_renders() {
     let mut terminal = test_terminal(100, 30);
@@ -217,6 +276,65 @@ fn test_instances_view_sort_descending() {
     assert!(buffer_contains(buffer, "↓"), "Should show descending arrow");
 }
 
+#[test]
+fn test_view_switcher_overlay() {
+    let mut terminal = test_terminal(120, 30);
+    let mut app = test_app_with_data();
+    app.open_view_switcher();
+
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+
+    let buffer = terminal.backend().buffer();
+    assert!(buffer_contains(buffer, " Views "));
+    assert!(buffer_contains(
+        buffer,
+        "1 ● Tiers               2  ⚠ 1 with problems"
+    ));
+    assert!(buffer_contains(buffer, "3   Instances           6"));
+}
+
+#[test]
+fn test_sort_picker_popup() {
+    let mut terminal = test_terminal(100, 30);
+    let mut app = test_app_with_data();
+
+    app.view_mode = ViewMode::Instances;
+    app.sort_field = SortField::FailureDomain;
+    app.sort_order = SortOrder::Desc;
+    app.open_sort_picker();
+
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+
+    let buffer = terminal.backend().buffer();
+    assert!(buffer_contains(buffer, " Sort by "));
+    assert!(buffer_contains(buffer, "n   Name"));
+    assert!(
+        buffer_contains(buffer, "d ↓ Domain"),
+        "current field marked"
+    );
+    assert!(buffer_contains(buffer, "Order: ↓ descending"));
+}
+
+#[test]
+fn test_instances_view_breadcrumb_follows_selection() {
+    let mut terminal = test_terminal(100, 30);
+    let mut app = test_app_with_data();
+
+    app.view_mode = ViewMode::Instances;
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+    assert!(buffer_contains(
+        terminal.backend().buffer(),
+        "default ▸ r1 ▸ i1"
+    ));
+
+    app.select_last();
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+    assert!(buffer_contains(
+        terminal.backend().buffer(),
+        "storage ▸ s1 ▸ s1-i2"
+    ));
+}
+
 #[test]
 fn test_instances_view_filter_shows_indicator() {
     let mut terminal = test_terminal(100, 30);
@@ -265,7 +383,10 @@ fn test_login_screen_renders() {
     let mut terminal = test_terminal(80, 24);
     let (req_tx, _req_rx) = channel();
     let (_res_tx, res_rx) = channel();
-    let mut app = App::new("http://test:8080".to_string(), req_tx, res_rx);
+    let mut app = App::new(
+        "http://test:8080".to_string(),
+        HttpSource::new(req_tx, res_rx),
+    );
 
     app.input_mode = InputMode::Login;
     app.auth_enabled = true;
@@ -290,6 +411,124 @@ fn test_login_screen_renders() {
     );
 }
 
+#[test]
+fn test_connecting_screen_after_grace_period() {
+    let mut terminal = test_terminal(100, 24);
+    let (req_tx, _req_rx) = channel();
+    let (_res_tx, res_rx) = channel();
+    let mut app = App::new(
+        "http://test:8080".to_string(),
+        HttpSource::new(req_tx, res_rx),
+    );
+    app.url_editable = true;
+    app.start_init();
+
+    // Nothing but the background while a quick answer may still come
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+    assert!(!buffer_contains(terminal.backend().buffer(), "Connecting"));
+
+    app.refresh_started_at = app.refresh_started_at.map(|at| at - Duration::from_secs(1));
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+    let buffer = terminal.backend().buffer();
+    assert!(buffer_contains(buffer, "Connecting to http://test:8080"));
+    assert!(buffer_contains(buffer, "e edit URL"));
+    assert!(!buffer_contains(buffer, "r retry"), "still trying");
+    assert!(!buffer_contains(buffer, "Tiers"), "no empty cluster view");
+
+    // A failure shows the error and how to go on
+    app.loading = false;
+    app.last_error = Some("Failed to connect: connection refused".to_string());
+    app.queued_keys
+        .push((KeyCode::Char('3'), KeyModifiers::NONE));
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+    let buffer = terminal.backend().buffer();
+    assert!(buffer_contains(
+        buffer,
+        "Could not connect to http://test:8080"
+    ));
+    assert!(buffer_contains(buffer, "connection refused"));
+    assert!(buffer_contains(buffer, "r retry"));
+    assert!(buffer_contains(
+        buffer,
+        "1 key will be applied once connected"
+    ));
+
+    app.start_url_edit();
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+    let buffer = terminal.backend().buffer();
+    assert!(buffer_contains(buffer, "URL: http://test:8080█"));
+    assert!(buffer_contains(buffer, "Enter connect"));
+}
+
+#[test]
+fn test_login_validation_and_server_identity() {
+    let mut terminal = test_terminal(100, 30);
+    let (req_tx, req_rx) = channel();
+    let (_res_tx, res_rx) = channel();
+    let mut app = App::new(
+        "http://test:8080".to_string(),
+        HttpSource::new(req_tx, res_rx),
+    );
+    app.input_mode = InputMode::Login;
+    app.auth_enabled = true;
+    app.login_focus = LoginFocus::Password;
+
+    // An empty username is caught before anything is sent
+    app.submit_login();
+    assert!(req_rx.try_recv().is_err());
+    assert_eq!(app.login_focus, LoginFocus::Username);
+
+    app.server_check = ServerCheck::Reachable(ServerIdentity {
+        cluster_name: Some("prod-eu".to_string()),
+        version: Some("25.3.1".to_string()),
+    });
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+    let buffer = terminal.backend().buffer();
+    assert!(buffer_contains(buffer, "Username required"));
+    assert!(buffer_contains(
+        buffer,
+        "✓ Cluster prod-eu (Picodata 25.3.1)"
+    ));
+
+    app.server_check = ServerCheck::Unreachable("connection refused".to_string());
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+    assert!(buffer_contains(
+        terminal.backend().buffer(),
+        "Server unreachable: connection refused"
+    ));
+}
+
+#[test]
+fn test_pasted_password_stays_masked() {
+    let mut terminal = test_terminal(100, 30);
+    let (req_tx, _req_rx) = channel();
+    let (_res_tx, res_rx) = channel();
+    let mut app = App::new(
+        "http://test:8080".to_string(),
+        HttpSource::new(req_tx, res_rx),
+    );
+    app.input_mode = InputMode::Login;
+    app.login_focus = LoginFocus::Password;
+
+    // A trailing newline from the clipboard must not end up in the password
+    app.paste("hunter2\n");
+    assert_eq!(app.login_password, "hunter2");
+
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+    let buffer = terminal.backend().buffer();
+    assert!(buffer_contains(buffer, "Password 7 chars"));
+    assert!(buffer_contains(buffer, "*******"));
+    assert!(!buffer_contains(buffer, "hunter2"));
+
+    // Revealed only while the reveal key keeps repeating
+    app.hold_password_reveal();
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+    assert!(buffer_contains(terminal.backend().buffer(), "hunter2"));
+    std::thread::sleep(Duration::from_millis(700));
+    assert!(app.settle_password_reveal());
+    assert!(!app.password_revealed());
+}
+
 #[test]
 fn test_view_mode_indicator_in_header() {
     let mut terminal = test_terminal(100, 30);
@@ -334,6 +573,44 @@ fn test_status_bar_shows_keybindings() {
     assert!(buffer_contains(buffer, "Quit"), "Should show Quit hint");
 }
 
+#[test]
+fn test_status_bar_template() {
+    let mut terminal = test_terminal(120, 30);
+    let mut app = test_app_with_data();
+    app.status_bar_format =
+        Some(" {cluster} @ {url} | next refresh {refresh} | {unknown}".to_string());
+
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+
+    let buffer = terminal.backend().buffer();
+    assert!(buffer_contains(
+        buffer,
+        "test-cluster @ http://test:8080 | next refresh off | {unknown}"
+    ));
+    assert!(
+        !buffer_contains(buffer, "Navigate"),
+        "Key hints only appear through {{keys}}"
+    );
+}
+
+#[test]
+fn test_status_bar_shows_logged_in_user() {
+    let mut terminal = test_terminal(160, 30);
+    let mut app = test_app_with_data();
+
+    app.auth_enabled = true;
+    app.current_user = Some("admin".to_string());
+
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+
+    let buffer = terminal.backend().buffer();
+
+    assert!(
+        buffer_contains(buffer, "user: admin"),
+        "Should show logged-in username"
+    );
+}
+
 #[test]
 fn test_instances_view_status_bar_shows_filter_key() {
     let mut terminal = test_terminal(120, 30);
@@ -372,6 +649,181 @@ fn test_offline_instance_shown_differently() {
     );
 }
 
+#[test]
+fn test_header_shows_connection_badge() {
+    let mut terminal = test_terminal(120, 30);
+    let mut app = test_app_with_data();
+
+    app.connection_status = ConnectionStatus::Connected;
+    app.last_latency = Some(Duration::from_millis(42));
+
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+
+    let buffer = terminal.backend().buffer();
+
+    assert!(
+        buffer_contains(buffer, "connected"),
+        "Should show connection status"
+    );
+    assert!(buffer_contains(buffer, "42 ms"), "Should show latency");
+    assert!(
+        buffer_contains(buffer, "http://test:8080"),
+        "Should show target URL"
+    );
+}
+
+#[test]
+fn test_status_bar_shows_macro_recording() {
+    let mut terminal = test_terminal(160, 30);
+    let mut app = test_app_with_data();
+    app.macros.start_recording('a');
+
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+    assert!(buffer_contains(terminal.backend().buffer(), "recording @a"));
+
+    app.macros.stop_recording();
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+    assert!(!buffer_contains(terminal.backend().buffer(), "recording"));
+}
+
+#[test]
+fn test_header_marks_read_only_session() {
+    let mut terminal = test_terminal(120, 30);
+    let mut app = test_app_with_data();
+
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+    assert!(!buffer_contains(terminal.backend().buffer(), "READ-ONLY"));
+
+    app.read_only = true;
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+    assert!(buffer_contains(terminal.backend().buffer(), "READ-ONLY"));
+}
+
+#[test]
+fn test_accessible_mode_draws_plain_ascii() {
+    let mut terminal = test_terminal(120, 30);
+    let mut app = test_app_with_data();
+    app.accessible = true;
+
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+    let buffer = terminal.backend().buffer();
+    let text: String = buffer.content().iter().map(|cell| cell.symbol()).collect();
+
+    assert!(
+        !text.chars().any(|c| ('\u{2500}'..='\u{259f}').contains(&c)),
+        "No box-drawing or block characters"
+    );
+    assert!(buffer_contains(buffer, "+-"), "Borders drawn in ASCII");
+    assert!(buffer_contains(buffer, "> "), "Selection marked with text");
+}
+
+#[test]
+fn test_color_depth_applies_to_the_frame() {
+    let mut terminal = test_terminal(120, 30);
+    let mut app = test_app_with_data();
+    app.theme.depth = ColorDepth::Monochrome;
+
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+    let buffer = terminal.backend().buffer();
+    assert!(buffer
+        .content()
+        .iter()
+        .all(|cell| cell.fg == Color::Reset && cell.bg == Color::Reset));
+    assert!(
+        buffer
+            .content()
+            .iter()
+            .any(|cell| cell.modifier.contains(Modifier::REVERSED)),
+        "Selection stands out without colors"
+    );
+
+    app.theme.depth = ColorDepth::Ansi16;
+    app.theme.online = Color::Rgb(10, 240, 10);
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+    let buffer = terminal.backend().buffer();
+    assert!(buffer
+        .content()
+        .iter()
+        .all(|cell| !matches!(cell.fg, Color::Rgb(..) | Color::Indexed(_))));
+    assert!(buffer
+        .content()
+        .iter()
+        .any(|cell| cell.fg == Color::LightGreen));
+}
+
+#[test]
+fn test_header_warns_about_newer_server() {
+    let mut terminal = test_terminal(120, 30);
+    let mut app = test_app_with_data();
+
+    app.server_version = PicodataVersion::parse("27.1.0");
+
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+
+    let buffer = terminal.backend().buffer();
+
+    assert!(
+        buffer_contains(buffer, "Picodata 27.1.0 is newer"),
+        "Should show a compatibility warning"
+    );
+}
+
+#[test]
+fn test_instance_runtime_fields_displayed() {
+    let mut terminal = test_terminal(120, 40);
+    let mut tiers = mock_tiers();
+    let instance = &mut tiers[0]["replicasets"][0]["instances"][0];
+    instance["uptimeSeconds"] = 90061.into();
+    instance["pid"] = 4242.into();
+    instance["raftId"] = 7.into();
+    instance["replicationLag"] = 0.25.into();
+
+    let mut app = test_app_with_data();
+    app.set_tiers(serde_json::from_value(tiers).unwrap());
+    app.view_mode = ViewMode::Instances;
+    app.selected_index = 0;
+
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+    assert!(
+        buffer_contains(terminal.backend().buffer(), "Lag: 0.25s"),
+        "Should show replication lag column"
+    );
+
+    app.show_detail = true;
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+
+    let buffer = terminal.backend().buffer();
+    assert!(buffer_contains(buffer, "1d 1h 1m"), "Should show uptime");
+    assert!(buffer_contains(buffer, "4242"), "Should show PID");
+    assert!(buffer_contains(buffer, "Raft ID:"), "Should show raft id");
+    assert!(buffer_contains(buffer, "0.250s"), "Should show lag");
+}
+
+#[test]
+fn test_replicaset_buckets_and_rebalancing_displayed() {
+    let mut terminal = test_terminal(140, 30);
+    let mut app = test_app_with_data();
+
+    let mut tiers = mock_tiers();
+    tiers[0]["bucketCount"] = 3000.into();
+    tiers[0]["replicasets"][0]["bucketCount"] = 1200.into();
+    tiers[0]["replicasets"][1]["bucketCount"] = 1700.into();
+    app.set_tiers(serde_json::from_value(tiers).unwrap());
+    app.expand_selected();
+
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+
+    let buffer = terminal.backend().buffer();
+    assert!(
+        buffer_contains(buffer, "Buckets: 1200"),
+        "Should show per-replicaset bucket count"
+    );
+    assert!(
+        buffer_contains(buffer, "rebalancing"),
+        "Should flag buckets in transit"
+    );
+}
+
 #[test]
 fn test_memory_usage_displayed() {
     let mut terminal = test_terminal(100, 30);
@@ -384,3 +836,638 @@ fn test_memory_usage_displayed() {
     // Memory bar should be visible in cluster header
     assert!(buffer_contains(buffer, "GiB"), "Should show memory in GiB");
 }
+
+#[test]
+fn test_number_format_preferences_applied() {
+    let mut terminal = test_terminal(100, 30);
+    let mut app = test_app_with_data();
+
+    app.number_format = NumberFormat {
+        units: ByteUnits::Si,
+        precision: 2,
+        thousands_separator: true,
+    };
+
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+
+    let buffer = terminal.backend().buffer();
+
+    assert!(
+        buffer_contains(buffer, "1.29 GB / 4.29 GB"),
+        "Cluster memory should use SI units with 2 decimals"
+    );
+    assert!(
+        buffer_contains(buffer, "Buckets: 3,000"),
+        "Bucket counts should be grouped"
+    );
+    assert!(!buffer_contains(buffer, "GiB"));
+}
+
+#[test]
+fn test_header_shows_last_update_time() {
+    let mut terminal = test_terminal(120, 30);
+    let mut app = test_app_with_data();
+
+    app.last_updated = Some(unix_now() - 150);
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+    assert!(buffer_contains(
+        terminal.backend().buffer(),
+        "Updated 2m ago"
+    ));
+
+    app.time_format = TimeFormat::Utc;
+    app.last_updated = Some(1_700_000_000);
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+    assert!(buffer_contains(
+        terminal.backend().buffer(),
+        "Updated 2023-11-14 22:13:20 UTC"
+    ));
+}
+
+#[test]
+fn test_saved_filter_picker_renders() {
+    let mut terminal = test_terminal(120, 30);
+    let mut app = test_app_with_data();
+    app.view_mode = ViewMode::Instances;
+    app.saved_filters = vec![SavedFilter {
+        name: "DC2 offline".to_string(),
+        filter: "dc2".to_string(),
+        fuzzy: true,
+    }];
+    app.open_filter_picker();
+
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+
+    let buffer = terminal.backend().buffer();
+    assert!(buffer_contains(buffer, "Saved Filters"));
+    assert!(buffer_contains(buffer, "1 DC2 offline"));
+    assert!(buffer_contains(buffer, "(fuzzy)"));
+}
+
+#[test]
+fn test_screen_dump_text_and_ansi() {
+    let mut terminal = test_terminal(100, 30);
+    let mut app = test_app_with_data();
+
+    app.expanded_tiers.insert(0);
+    app.expanded_replicasets.insert((0, 0));
+    app.rebuild_tree();
+
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+
+    let buffer = terminal.backend().buffer();
+
+    let text = buffer_to_text(buffer);
+    assert_eq!(text.lines().count(), 30, "One line per row");
+    assert!(text.contains("test-cluster"), "Should contain screen text");
+    assert!(
+        text.lines().all(|line| !line.ends_with(' ')),
+        "Trailing whitespace should be trimmed"
+    );
+    assert!(!text.contains('\x1b'), "Plain text has no escapes");
+
+    let ansi = buffer_to_ansi(buffer);
+    assert!(ansi.contains("\x1b["), "ANSI dump should contain SGR codes");
+    assert!(
+        ansi.contains("test-cluster"),
+        "ANSI dump should contain text"
+    );
+}
+
+#[test]
+fn test_instances_view_scrolls_large_list() {
+    let mut terminal = test_terminal(100, 30);
+    let mut app = test_app_with_data();
+
+    // Blow the first replicaset up to 2000 instances
+    let template = app.tiers[0].replicasets[0].instances[0].clone();
+    app.tiers[0].replicasets[0].instances = (0..2000)
+        .map(|i| {
+            let mut inst = template.clone();
+            inst.name = format!("big-{:04}", i);
+            inst
+        })
+        .collect();
+    app.rebuild_tree();
+    app.view_mode = ViewMode::Instances;
+
+    app.select_last();
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+
+    let buffer = terminal.backend().buffer();
+    // Sorted by name, s1-i2 comes last
+    assert!(
+        buffer_contains(buffer, "s1-i2"),
+        "Last row should be visible"
+    );
+    assert!(
+        !buffer_contains(buffer, "big-0000"),
+        "First rows should be scrolled away"
+    );
+
+    // Filtering narrows navigation to matching rows
+    app.filter_text = "big-19".to_string();
+    assert_eq!(app.get_item_count(), 100);
+    app.select_first();
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+    assert!(buffer_contains(terminal.backend().buffer(), "big-1900"));
+}
+
+#[test]
+fn test_instance_columns_follow_configuration() {
+    let mut terminal = test_terminal(120, 40);
+    let mut tiers = mock_tiers();
+    tiers[0]["replicasets"][0]["instances"][0]["uptimeSeconds"] = 90061.into();
+
+    let mut app = test_app_with_data();
+    app.set_tiers(serde_json::from_value(tiers).unwrap());
+    app.view_mode = ViewMode::Instances;
+    app.instance_columns = vec![
+        InstanceColumn::Name,
+        InstanceColumn::Version,
+        InstanceColumn::Uptime,
+    ];
+
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+    let buffer = terminal.backend().buffer();
+    assert!(
+        buffer_contains(buffer, "Up: 1d 1h 1m"),
+        "Should show uptime"
+    );
+    assert!(buffer_contains(buffer, "Ver: "), "Should show version");
+    assert!(!buffer_contains(buffer, "RS: "), "Replicaset column hidden");
+    assert!(!buffer_contains(buffer, "[Online]"), "State column hidden");
+
+    app.open_column_picker();
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+    let buffer = terminal.backend().buffer();
+    assert!(buffer_contains(buffer, " Columns "));
+    assert!(buffer_contains(buffer, "[x] Version"));
+    assert!(buffer_contains(buffer, "[ ] Replicaset"));
+}
+
+#[test]
+fn test_cluster_health_badge_displayed() {
+    let mut terminal = test_terminal(120, 40);
+    let mut app = test_app_with_data();
+    app.set_tiers(serde_json::from_value(mock_tiers()).unwrap());
+
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+
+    let buffer = terminal.backend().buffer();
+    assert!(
+        buffer_contains(buffer, "Health 90"),
+        "Should show the score"
+    );
+    assert!(
+        buffer_contains(
+            buffer,
+            "1 instance offline · 1 instance not at target state"
+        ),
+        "Should list the top problems"
+    );
+}
+
+#[test]
+fn test_leader_distribution_popup() {
+    let mut terminal = test_terminal(120, 40);
+    let mut app = test_app_with_data();
+    app.show_leaders = true;
+
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+
+    let buffer = terminal.backend().buffer();
+    assert!(buffer_contains(buffer, "Leader Distribution"));
+    assert!(buffer_contains(buffer, "By failure domain"));
+    assert!(buffer_contains(buffer, "2 of 3 leading"));
+}
+
+#[test]
+fn test_state_transition_shown_in_rows() {
+    let mut terminal = test_terminal(120, 40);
+    let mut app = test_app_with_data();
+
+    // i3 is Offline with target Online in the mock data
+    app.view_mode = ViewMode::Instances;
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+    assert!(buffer_contains(
+        terminal.backend().buffer(),
+        "[Offline → Online]"
+    ));
+    assert!(!buffer_contains(terminal.backend().buffer(), "[Online →"));
+
+    app.view_mode = ViewMode::Replicasets;
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+    assert!(buffer_contains(
+        terminal.backend().buffer(),
+        "→ 1 transitioning"
+    ));
+}
+
+#[test]
+fn test_memory_stats_popup() {
+    let mut terminal = test_terminal(140, 40);
+    let mut app = test_app_with_data();
+    app.show_memory_stats = true;
+
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+
+    let buffer = terminal.backend().buffer();
+    assert!(buffer_contains(buffer, "Memory Statistics"));
+    assert!(buffer_contains(buffer, "By failure domain"));
+    assert!(buffer_contains(buffer, "datacenter:dc1"));
+    assert!(buffer_contains(buffer, "614.4 MiB / 2.0 GiB (30.0%)"));
+}
+
+#[test]
+fn test_timings_popup() {
+    let mut terminal = test_terminal(140, 40);
+    let mut app = test_app_with_data();
+    let meta = ResponseMeta {
+        latency: Duration::from_millis(42),
+        reachable: true,
+        bytes: 2048,
+    };
+    app.timings
+        .record(&ApiResponse::Tiers(Ok(Vec::new())), &meta);
+    app.timings.record(
+        &ApiResponse::ClusterInfo(Err("HTTP 500".to_string())),
+        &meta,
+    );
+    app.show_timings = true;
+
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+
+    let buffer = terminal.backend().buffer();
+    assert!(buffer_contains(buffer, "Request Timings"));
+    assert!(buffer_contains(
+        buffer,
+        "tiers           1      0    42 ms    42 ms    42 ms    2.0 KiB"
+    ));
+    assert!(buffer_contains(buffer, "cluster         1      1"));
+}
+
+#[test]
+fn test_tables_popup() {
+    let mut terminal = test_terminal(140, 40);
+    let mut app = test_app_with_data();
+    app.tables = Some(vec![
+        TableInfo {
+            name: "orders".to_string(),
+            distribution: TableDistribution::Sharded {
+                tier: "default".to_string(),
+            },
+            engine: "memtx".to_string(),
+            rows: Some(1200),
+            size: Some(3 * 1024 * 1024),
+        },
+        TableInfo {
+            name: "archive".to_string(),
+            distribution: TableDistribution::Global,
+            engine: "vinyl".to_string(),
+            rows: None,
+            size: None,
+        },
+    ]);
+    app.show_tables = true;
+
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+
+    let buffer = terminal.backend().buffer();
+    assert!(buffer_contains(buffer, "Tables"));
+    assert!(buffer_contains(buffer, "orders"));
+    assert!(buffer_contains(buffer, "sharded      default      memtx"));
+    assert!(buffer_contains(buffer, "3.0 MiB"));
+    assert!(buffer_contains(buffer, "archive"));
+}
+
+#[test]
+fn test_parameters_popup_filters_by_search() {
+    let mut terminal = test_terminal(140, 40);
+    let mut app = test_app_with_data();
+    let param = |name: &str, scope: &str, value: &str| ConfigParameter {
+        name: name.to_string(),
+        scope: scope.to_string(),
+        value: value.to_string(),
+    };
+    app.parameters = Some(vec![
+        param("memtx_checkpoint_interval", "", "600"),
+        param("pg_portal_max", "", "1024"),
+        param("sql_motion_row_max", "storage", "5000"),
+    ]);
+    app.show_parameters = true;
+    app.set_parameters_search("CHECKPOINT".to_string());
+
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+
+    let buffer = terminal.backend().buffer();
+    assert!(buffer_contains(buffer, "Configuration"));
+    assert!(buffer_contains(buffer, "/CHECKPOINT"));
+    assert!(buffer_contains(buffer, "memtx_checkpoint_interval"));
+    assert!(buffer_contains(buffer, "600"));
+    assert!(buffer_contains(buffer, "3600"), "default is shown");
+    assert!(!buffer_contains(buffer, "pg_portal_max"));
+}
+
+#[test]
+fn test_rolling_restart_plan_popup() {
+    let mut terminal = test_terminal(140, 40);
+    let mut app = test_app_with_data();
+    let commands = RestartCommands {
+        switch_leader: None,
+        restart: Some("ssh {host} systemctl restart picodata@{instance}".to_string()),
+    };
+    app.rolling_restart = Some(RollingRestart::plan(&app.tiers, "r1", commands).unwrap());
+
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+
+    let buffer = terminal.backend().buffer();
+    assert!(buffer_contains(buffer, "Rolling restart: r1 (default)"));
+    assert!(buffer_contains(buffer, "Leader switch: by hand"));
+    assert!(buffer_contains(
+        buffer,
+        "Restart: $ ssh {host} systemctl restart picodata@{instance}"
+    ));
+    assert!(buffer_contains(buffer, "Enter to start, Esc to cancel"));
+}
+
+#[test]
+fn test_confirm_dialog_asks_for_the_name() {
+    let mut terminal = test_terminal(140, 40);
+    let mut app = test_app_with_data();
+    app.rolling_restart =
+        Some(RollingRestart::plan(&app.tiers, "r1", RestartCommands::default()).unwrap());
+    app.ask_to_start_rolling_restart();
+    app.paste("r");
+
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+
+    let buffer = terminal.backend().buffer();
+    assert!(buffer_contains(buffer, "Danger: Rolling restart"));
+    assert!(buffer_contains(buffer, "Type r1 to confirm:"));
+    assert!(buffer_contains(buffer, "> r_"));
+    assert!(buffer_contains(buffer, "Esc to cancel"));
+    assert!(!buffer_contains(buffer, "Enter to confirm"));
+}
+
+#[test]
+fn test_alerts_badge_and_popup() {
+    let mut terminal = test_terminal(140, 40);
+    let mut app = test_app_with_data();
+    app.alerts = AlertEngine::new(vec![serde_json::from_value(serde_json::json!({
+        "name": "Instances down",
+        "metric": "offline",
+        "threshold": 0
+    }))
+    .unwrap()]);
+    let tiers = app.tiers.to_vec();
+    app.set_tiers(tiers);
+    app.show_alerts = true;
+
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+
+    let buffer = terminal.backend().buffer();
+    assert!(buffer_contains(buffer, "⚠ 1 alert "));
+    assert!(buffer_contains(
+        buffer,
+        "Instances down  offline > 0 (now 1)"
+    ));
+}
+
+#[test]
+fn test_heatmap_popup() {
+    let mut terminal = test_terminal(140, 40);
+    let mut app = test_app_with_data();
+    app.open_heatmap();
+    app.move_heatmap_replicaset(true);
+
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+
+    let buffer = terminal.backend().buffer();
+    assert!(buffer_contains(buffer, " Heatmap: capacity "));
+    assert!(
+        buffer_contains(buffer, "░░ ◆░ "),
+        "r1, then r2 with the cursor on i3"
+    );
+    assert!(buffer_contains(
+        buffer,
+        "i3  replicaset r2 / tier default  Offline → Online  30.0%  ★ leader"
+    ));
+
+    app.heatmap_coloring = app.heatmap_coloring.toggle();
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+    let buffer = terminal.backend().buffer();
+    assert!(buffer_contains(buffer, " Heatmap: state "));
+    assert!(buffer_contains(buffer, "██ ◆█ "));
+}
+
+#[test]
+fn test_topology_popup() {
+    let mut terminal = test_terminal(140, 40);
+    let mut app = test_app_with_data();
+    app.show_topology = true;
+
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+
+    let buffer = terminal.backend().buffer();
+    assert!(buffer_contains(buffer, " Topology: datacenter → rack "));
+    assert!(buffer_contains(buffer, " datacenter: dc1 "));
+    assert!(buffer_contains(buffer, "r1      ★"));
+    assert!(buffer_contains(buffer, "not set ★"), "s1-i1 has no rack");
+    assert!(
+        buffer_contains(buffer, "r1      ☆"),
+        "i3 is an offline leader"
+    );
+    assert!(buffer_contains(buffer, " 2/3 online "));
+
+    app.topology_names = true;
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+    assert!(buffer_contains(terminal.backend().buffer(), "☆ i3 r2"));
+}
+
+#[test]
+fn test_diagnostics_overlay() {
+    let mut terminal = test_terminal(140, 40);
+    let mut app = test_app_with_data();
+    app.show_diagnostics = true;
+    app.frame_stats.record_frame(Duration::from_micros(1500));
+
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+
+    let buffer = terminal.backend().buffer();
+    assert!(buffer_contains(buffer, " Diagnostics "));
+    assert!(buffer_contains(
+        buffer,
+        "Frame      1.5 ms, avg 1.5, max 1.5"
+    ));
+    assert!(buffer_contains(buffer, "Frames     1"));
+    let rows = format!(
+        "Rows       {} of {}",
+        app.tree_items.len(),
+        app.tree_items.len()
+    );
+    assert!(buffer_contains(buffer, &rows));
+}
+
+#[test]
+fn test_error_log_popup() {
+    let mut terminal = test_terminal(160, 40);
+    let mut app = test_app_with_data();
+    let now = unix_now();
+    app.error_log.record("Cluster: refused", now);
+    app.error_log.record("Cluster: refused", now);
+    app.error_log.record("Tiers: HTTP 502", now);
+    app.last_error = Some("Cluster: refused".to_string());
+
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+    assert!(buffer_contains(
+        terminal.backend().buffer(),
+        "Error: Cluster: refused (! lists 2 errors)"
+    ));
+
+    app.show_error_log = true;
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+
+    let buffer = terminal.backend().buffer();
+    assert!(buffer_contains(buffer, " Error Log "));
+    assert!(buffer_contains(buffer, "just now  Tiers: HTTP 502"));
+    assert!(buffer_contains(
+        buffer,
+        "Cluster: refused  ×2 since just now"
+    ));
+}
+
+#[test]
+fn test_toasts_drawn_top_right() {
+    let mut terminal = test_terminal(140, 40);
+    let mut app = test_app_with_data();
+    app.toast(ToastKind::Error, "i4 went Offline");
+    app.toast(ToastKind::Success, "Saved snapshot \"before\"");
+
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+
+    let buffer = terminal.backend().buffer();
+    let row = |y: u16| {
+        (0..140)
+            .map(|x| buffer[(x, y)].symbol())
+            .collect::<String>()
+    };
+    // Newest first, right below the header
+    assert!(row(4).ends_with("│ Saved snapshot \"before\" │"));
+    assert_eq!(buffer[(139, 4)].fg, Color::Green);
+    assert!(row(7).ends_with("│ i4 went Offline │"));
+    assert_eq!(buffer[(139, 7)].fg, Color::Red);
+}
+
+#[test]
+fn test_snapshot_diff_popup() {
+    let mut terminal = test_terminal(140, 40);
+    let mut app = test_app_with_data();
+    let mut old = app.tiers.to_vec();
+    old[0].replicasets[1].instances[0].current_state = StateVariant::Online;
+    old[1].replicasets[0].instances.pop();
+    app.snapshot_diff = Some(("nightly".to_string(), snapshot::diff(&old, &app.tiers)));
+
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+
+    let buffer = terminal.backend().buffer();
+    assert!(buffer_contains(buffer, "Changes since nightly"));
+    assert!(buffer_contains(buffer, "+ s1-i2"));
+    assert!(buffer_contains(buffer, "~ i3: Online → Offline"));
+}
+
+#[test]
+fn test_tree_lines_cached_until_inputs_change() {
+    let mut terminal = test_terminal(120, 30);
+    let mut app = test_app_with_data();
+    app.expanded_tiers.insert(0);
+    app.expanded_replicasets.insert((0, 0));
+    app.rebuild_tree();
+
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+    let rows = app.tree_items.len();
+    assert_eq!(app.tree_line_cache.len(), rows);
+
+    // Moving the selection reuses every line
+    app.select_next();
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+    assert_eq!(app.tree_line_cache.len(), rows);
+
+    // New data is rendered, not served from the cache
+    app.tiers[0].replicasets[0].instances[1].name = "renamed".to_string();
+    app.rebuild_tree();
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+    let buffer = terminal.backend().buffer();
+    assert!(buffer_contains(buffer, "renamed"));
+    assert!(!buffer_contains(buffer, "i2 ["));
+}
+
+#[test]
+fn test_cluster_picker_shows_probe_results() {
+    let session = |url: &str| {
+        (
+            url.to_string(),
+            TokenEntry {
+                auth: String::new(),
+                refresh: String::new(),
+                saved_at: unix_now(),
+            },
+        )
+    };
+    let mut picker = ClusterPicker::new(vec![
+        session("http://127.0.0.1:1"),
+        session("http://127.0.0.1:2"),
+        session("http://127.0.0.1:3"),
+    ]);
+    picker.entries[0].probe = Probe::Ready;
+    picker.entries[1].probe = Probe::AuthRequired;
+    picker.entries[2].probe = Probe::Down("connection refused".to_string());
+    picker.select_next();
+
+    let mut terminal = test_terminal(120, 30);
+    terminal.draw(|f| ui::draw_picker(f, &picker)).unwrap();
+    let buffer = terminal.backend().buffer();
+
+    assert!(buffer_contains(buffer, "Choose a cluster"));
+    assert!(buffer_contains(buffer, "● ready"));
+    assert!(buffer_contains(buffer, "> http://127.0.0.1:2"));
+    assert!(buffer_contains(buffer, "● login required"));
+    assert!(buffer_contains(buffer, "● down: connection refused"));
+    assert_eq!(picker.selected_url(), Some("http://127.0.0.1:2"));
+}
+
+#[test]
+fn test_group_health_popup_lists_every_instance() {
+    let mut terminal = test_terminal(120, 40);
+    let mut app = test_app_with_data();
+    let instances = vec![
+        ("default_1_1".to_string(), "127.0.0.1:8081".to_string()),
+        ("default_1_2".to_string(), "127.0.0.1:8082".to_string()),
+    ];
+    app.show_health = true;
+    app.health_loading = true;
+    app.group_health = Some(GroupHealth {
+        id: 1,
+        title: "replicaset default_1".to_string(),
+        instances,
+        results: None,
+    });
+
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+    let buffer = terminal.backend().buffer();
+    assert!(buffer_contains(
+        buffer,
+        "Health Status: replicaset default_1 (loading...)"
+    ));
+    assert!(buffer_contains(buffer, "Probing 2 instances..."));
+
+    app.health_loading = false;
+    app.group_health.as_mut().unwrap().results = Some(vec![
+        Err("connection refused".to_string()),
+        Err("Cancelled".to_string()),
+    ]);
+    terminal.draw(|f| ui::draw(f, &mut app)).unwrap();
+    let buffer = terminal.backend().buffer();
+    assert!(buffer_contains(buffer, "✗ default_1_1  connection refused"));
+    assert!(buffer_contains(buffer, "✗ default_1_2  Cancelled"));
+}
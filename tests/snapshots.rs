@@ -0,0 +1,86 @@
+//! Golden-file snapshots of each view at several terminal sizes, built on
+//! `picotui::testing`. After an intended UI change, review and accept the
+//! new snapshots with `cargo insta review` (or `INSTA_UPDATE=always`).
+
+use picotui::app::ViewMode;
+use picotui::mock::Topology;
+use picotui::testing::{render, render_text, AppBuilder, SIZES};
+use proptest::prelude::*;
+
+fn view_name(view: ViewMode) -> &'static str {
+    match view {
+        ViewMode::Tiers => "tiers",
+        ViewMode::Replicasets => "replicasets",
+        ViewMode::Instances => "instances",
+    }
+}
+
+#[test]
+fn snapshot_views_at_each_size() {
+    for view in ViewMode::ALL {
+        for (width, height) in SIZES {
+            let mut app = AppBuilder::new().view(view).expanded(true).build();
+            insta::assert_snapshot!(
+                format!("{}_{}x{}", view_name(view), width, height),
+                render_text(&mut app, width, height)
+            );
+        }
+    }
+}
+
+#[test]
+fn snapshot_collapsed_tiers() {
+    let mut app = AppBuilder::new().selected(1).build();
+    insta::assert_snapshot!(render_text(&mut app, 100, 20));
+}
+
+#[test]
+fn snapshot_filtered_instances() {
+    let mut app = AppBuilder::new()
+        .view(ViewMode::Instances)
+        .filter("storage")
+        .build();
+    insta::assert_snapshot!(render_text(&mut app, 100, 20));
+}
+
+#[test]
+fn snapshot_before_first_refresh() {
+    let mut app = AppBuilder::empty().build();
+    insta::assert_snapshot!(render_text(&mut app, 80, 24));
+}
+
+#[test]
+fn test_builder_is_deterministic() {
+    let draw = || render(&mut AppBuilder::new().expanded(true).build(), 120, 30);
+    assert_eq!(draw(), draw());
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    /// No view panics or draws outside the screen, however small or large
+    /// the terminal and the cluster
+    #[test]
+    fn prop_any_view_renders_at_any_size(
+        view in prop::sample::select(ViewMode::ALL.to_vec()),
+        width in 1u16..240,
+        height in 1u16..80,
+        tiers in 1usize..4,
+        replicasets in 1usize..6,
+        instances in 1usize..4,
+        expanded in any::<bool>(),
+        selected in 0usize..64,
+    ) {
+        let topology = Topology { tiers, replicasets, instances };
+        let mut app = AppBuilder::new()
+            .topology(topology)
+            .view(view)
+            .expanded(expanded)
+            .selected(selected)
+            .build();
+        let buffer = render(&mut app, width, height);
+        prop_assert_eq!(buffer.area.width, width);
+        prop_assert_eq!(buffer.area.height, height);
+        prop_assert!(app.selected_index < app.get_item_count().max(1));
+    }
+}